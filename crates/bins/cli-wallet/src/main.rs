@@ -86,6 +86,19 @@ enum NodeCommands {
     )]
     #[clap(name = "ls")]
     List {},
+    /// Check the node proof of liabilities
+    #[command(
+        about = "Check the node proof of liabilities",
+        long_about = "Check that the node proof of liabilities report includes the tokens issued to this wallet, and those it spent."
+    )]
+    CheckLiabilities {
+        /// Id of the node to check
+        #[arg(long, short)]
+        node_id: u32,
+        /// Id of the report to check against, the latest one if unspecified
+        #[arg(long)]
+        report_id: Option<u64>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -322,6 +335,38 @@ async fn main() -> Result<()> {
                 println!("{} {}", id, url);
             }
         }
+        Commands::Node(NodeCommands::CheckLiabilities { node_id, report_id }) => {
+            let mut node_client = connect_to_node(&mut db_conn, node_id).await?;
+            let check = wallet::liabilities::check_liabilities(
+                pool,
+                &mut node_client.client,
+                node_id,
+                report_id,
+            )
+            .await?;
+
+            println!(
+                "Liabilities report {} of node {} ({}):",
+                check.report_id, node_id, node_client.url
+            );
+            println!(
+                "  issued: {} included, {} missing",
+                check.issued_included.len(),
+                check.issued_missing.len()
+            );
+            println!(
+                "  burned: {} included, {} missing",
+                check.burned_included.len(),
+                check.burned_missing.len()
+            );
+            if !check.issued_missing.is_empty() || !check.burned_missing.is_empty() {
+                println!(
+                    "{}",
+                    "Missing tokens were either issued or spent after the report, or left out by the node."
+                        .yellow()
+                );
+            }
+        }
         Commands::Balance { node_id } => match node_id {
             Some(node_id) => {
                 let balances = wallet::db::balance::get_for_node(&db_conn, node_id)?;
@@ -407,6 +452,12 @@ async fn main() -> Result<()> {
                 &mut node_client.client,
                 STARKNET_STR.to_string(),
                 mint_quote_response.quote.clone(),
+                |amount_paid| {
+                    println!(
+                        "Partially paid, received {} out of {} {}",
+                        amount_paid, amount, unit
+                    )
+                },
             )
             .await?
             {
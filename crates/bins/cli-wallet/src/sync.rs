@@ -1,5 +1,7 @@
 use anyhow::{Result, anyhow};
 use cashu_client::GrpcClient;
+use nuts::Amount;
+use nuts::nut04::MintQuoteState;
 use nuts::nut05::MeltQuoteState;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
@@ -10,6 +12,8 @@ use wallet::melt::format_melt_transfers_id_into_term_message;
 use crate::SEED_PHRASE_MANAGER;
 
 const STARKNET_STR: &str = "starknet";
+/// Proofs of keysets expiring within this many seconds are swapped into the active keysets
+const KEYSET_EXPIRY_MARGIN: u64 = 7 * 24 * 60 * 60;
 
 pub async fn sync_all_pending_operations(pool: Pool<SqliteConnectionManager>) -> Result<()> {
     let db_conn = pool.get()?;
@@ -25,7 +29,7 @@ pub async fn sync_all_pending_operations(pool: Pool<SqliteConnectionManager>) ->
         println!("Syncing node {} ({}) mint quotes", node_id, node_url);
 
         let mut node_client = connect_to_node(pool.clone(), node_id).await?;
-        wallet::sync::mint_quotes(
+        let state_updates = wallet::sync::mint_quotes(
             SEED_PHRASE_MANAGER,
             pool.clone(),
             &mut node_client.client,
@@ -34,6 +38,14 @@ pub async fn sync_all_pending_operations(pool: Pool<SqliteConnectionManager>) ->
             true,
         )
         .await?;
+        for quote in state_updates.unchanged {
+            if quote.state == MintQuoteState::Unpaid && quote.amount_paid != Amount::ZERO {
+                println!(
+                    "Mint quote {} partially paid, received {} out of {} {}",
+                    quote.id, quote.amount_paid, quote.amount, quote.unit
+                );
+            }
+        }
     }
     for (node_id, pending_quotes) in pending_melt_quotes {
         let node_url = wallet::db::node::get_url_by_id(&db_conn, node_id)?
@@ -46,7 +58,7 @@ pub async fn sync_all_pending_operations(pool: Pool<SqliteConnectionManager>) ->
 
     // Sync pending WADs using the lib wallet function i
     println!("Syncing pending WADs");
-    let wad_results = wallet::sync::pending_wads(pool, None).await?;
+    let wad_results = wallet::sync::pending_wads(pool.clone(), None).await?;
 
     for result in wad_results {
         match result.result {
@@ -57,6 +69,39 @@ pub async fn sync_all_pending_operations(pool: Pool<SqliteConnectionManager>) ->
         }
     }
 
+    let nodes = wallet::db::node::fetch_all(&db_conn)?;
+    for (node_id, node_url) in nodes {
+        println!("Syncing node {} ({}) expiring keysets", node_id, node_url);
+
+        // An unreachable node must not prevent saving the proofs of the others
+        let mut node_client = match connect_to_node(pool.clone(), node_id).await {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Failed to connect to node {}: {}", node_id, e);
+                continue;
+            }
+        };
+        match wallet::node::swap_expiring_proofs(
+            SEED_PHRASE_MANAGER,
+            pool.clone(),
+            &mut node_client.client,
+            node_id,
+            KEYSET_EXPIRY_MARGIN,
+        )
+        .await
+        {
+            Ok(swapped) => {
+                for (unit, amount) in swapped {
+                    println!("Swapped {} {} out of expiring keysets", amount, unit);
+                }
+            }
+            Err(e) => eprintln!(
+                "Failed to swap the proofs of node {} expiring keysets: {}",
+                node_id, e
+            ),
+        }
+    }
+
     println!("Sync completed for all nodes");
     Ok(())
 }
@@ -92,6 +137,13 @@ async fn sync_melt_quote(
             display_paid_melt_quote(quote_id, tx_ids);
             true
         }
+        Some((MeltQuoteState::Failed, _)) => {
+            println!(
+                "Melt quote {} failed, the inputs returned by the node are spendable again",
+                quote_id
+            );
+            true
+        }
         None => {
             println!("Melt quote {} has expired", quote_id);
             true
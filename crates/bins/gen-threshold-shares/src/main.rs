@@ -0,0 +1,50 @@
+//! Dealer of a threshold signer group
+//!
+//! Writes one `share-<index>.json` file per share signer, to be moved to its host and loaded
+//! with `THRESHOLD_SHARE_PATH`, and prints a fresh `KEYSET_REGISTRY_MAC_KEY` for the coordinator.
+//! The output directory should be wiped once the files are distributed.
+
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use anyhow::Context;
+use bitcoin::hex::DisplayHex;
+use clap::{Parser, ValueHint};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    /// Number of share signers needed to sign
+    #[arg(long)]
+    threshold: u32,
+    /// Number of share signers
+    #[arg(long)]
+    total: u32,
+    /// Directory the share files are written to
+    #[arg(long, value_hint(ValueHint::DirPath))]
+    out_dir: PathBuf,
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let cli = Cli::parse();
+
+    let share_files = signer::shares::deal(cli.threshold, cli.total)?;
+    std::fs::create_dir_all(&cli.out_dir)?;
+    for share_file in share_files {
+        let path = cli.out_dir.join(format!("share-{}.json", share_file.index));
+        // Refuse to overwrite the shares of another group
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(&path)
+            .with_context(|| format!("failed to create `{}`", path.display()))?;
+        file.write_all(&serde_json::to_vec_pretty(&share_file)?)?;
+        file.sync_all()?;
+    }
+
+    let mac_key: [u8; 32] = rand::random();
+    println!("KEYSET_REGISTRY_MAC_KEY={}", mac_key.to_lower_hex_string());
+
+    Ok(())
+}
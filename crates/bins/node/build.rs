@@ -11,6 +11,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 "../../../proto/node.proto",
                 "../../../proto/bdhke.proto",
                 "../../../proto/keyset_rotation.proto",
+                "../../../proto/admin.proto",
             ],
             &["../../../proto"],
         )?;
@@ -0,0 +1,86 @@
+//! Administrative service for the node operator
+//!
+//! Served on its own port, `ADMIN_GRPC_PORT`, and only to callers presenting the `ADMIN_TOKEN`
+//! as an `authorization: Bearer <token>` metadata.
+//!
+//! Its mutations are stored in db, so that they apply to every node instance sharing it.
+
+use bitcoin_hashes::sha256;
+use nuts::Amount;
+use tonic::{Request, Status, service::Interceptor};
+use uuid::Uuid;
+
+mod service;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error(transparent)]
+    Db(#[from] db_node::Error),
+    #[error("failed to serialize: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("the quote kind must be specified")]
+    UnspecifiedQuoteKind,
+    #[error("unknown method `{0}`")]
+    InvalidMethod(String),
+    #[error("unknown unit `{0}`")]
+    InvalidUnit(String),
+    #[error("unknown quote state `{0}`")]
+    InvalidQuoteState(String),
+    #[error("invalid quote id: {0}")]
+    InvalidQuoteId(#[from] uuid::Error),
+    #[error("min amount {0} is above max amount {1}")]
+    MinAboveMax(Amount, Amount),
+    #[error("the melt fee policy cannot compute the fee of quotes in `{0}`")]
+    MeltFeePolicyUnsupportedUnit(starknet_types::Unit),
+    #[error("no unpaid and unexpired quote `{0}`")]
+    QuoteNotExpirable(Uuid),
+}
+
+impl From<Error> for Status {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Sqlx(_) | Error::Db(_) | Error::Json(_) => Status::internal(value.to_string()),
+            Error::UnspecifiedQuoteKind
+            | Error::InvalidMethod(_)
+            | Error::InvalidUnit(_)
+            | Error::InvalidQuoteState(_)
+            | Error::InvalidQuoteId(_)
+            | Error::MinAboveMax(_, _) => Status::invalid_argument(value.to_string()),
+            Error::MeltFeePolicyUnsupportedUnit(_) | Error::QuoteNotExpirable(_) => {
+                Status::failed_precondition(value.to_string())
+            }
+        }
+    }
+}
+
+/// Rejects the calls not bearing the admin token
+#[derive(Debug, Clone)]
+pub struct AdminAuth {
+    // Only the hash is kept, so that comparing it doesn't leak the token through timing
+    token_hash: sha256::Hash,
+}
+
+impl AdminAuth {
+    pub fn new(token: &str) -> Self {
+        Self {
+            token_hash: sha256::Hash::hash(token.as_bytes()),
+        }
+    }
+}
+
+impl Interceptor for AdminAuth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+
+        match token {
+            Some(token) if sha256::Hash::hash(token.as_bytes()) == self.token_hash => Ok(request),
+            _ => Err(Status::unauthenticated("invalid or missing admin token")),
+        }
+    }
+}
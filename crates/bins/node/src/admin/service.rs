@@ -0,0 +1,355 @@
+use std::str::FromStr;
+
+use node::{
+    Admin,
+    admin::{
+        ExpireQuoteRequest, ExpireQuoteResponse, GetLiabilitiesRequest, GetLiabilitiesResponse,
+        GetSpentProofCountsRequest, GetSpentProofCountsResponse, ListPendingMeltsRequest,
+        ListPendingMeltsResponse, ListQuotesRequest, ListQuotesResponse, MethodSettings, Quote,
+        QuoteKind, UnitLiabilities, UnitProofCounts, UpdateNodeInfoRequest, UpdateNodeInfoResponse,
+        UpdateNutsSettingsRequest, UpdateNutsSettingsResponse,
+    },
+};
+use nuts::{
+    Amount,
+    nut04::{MintMethodSettings, MintQuoteState},
+    nut05::{MeltMethodSettings, MeltQuoteState},
+    nut06::{ContactInfo, NutsSettings},
+};
+use starknet_types::Unit;
+use tonic::{Request, Response, Status};
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use super::Error;
+use crate::{app_state::MeltFeePolicy, grpc_service::GrpcState, methods::Method};
+
+const DEFAULT_LIST_LIMIT: u32 = 100;
+const MAX_LIST_LIMIT: u32 = 1000;
+
+fn quote_kind(kind: i32) -> Result<QuoteKind, Error> {
+    match QuoteKind::try_from(kind) {
+        Ok(QuoteKind::QkMint) => Ok(QuoteKind::QkMint),
+        Ok(QuoteKind::QkMelt) => Ok(QuoteKind::QkMelt),
+        Ok(QuoteKind::QkUnspecified) | Err(_) => Err(Error::UnspecifiedQuoteKind),
+    }
+}
+
+/// Replace the settings of a method-unit pair, or remove them if it gets disabled
+fn apply_method_settings(
+    nuts_settings: &mut NutsSettings<Method, Unit, serde_json::Value>,
+    melt_fee_policy: MeltFeePolicy,
+    update: MethodSettings,
+) -> Result<(), Error> {
+    let kind = quote_kind(update.kind)?;
+    let method =
+        Method::from_str(&update.method).map_err(|_| Error::InvalidMethod(update.method))?;
+    let unit = Unit::from_str(&update.unit).map_err(|_| Error::InvalidUnit(update.unit))?;
+    let min_amount = update.min_amount.map(Amount::from);
+    let max_amount = update.max_amount.map(Amount::from);
+    if let (Some(min), Some(max)) = (min_amount, max_amount) {
+        if min > max {
+            return Err(Error::MinAboveMax(min, max));
+        }
+    }
+
+    match kind {
+        QuoteKind::QkMint => {
+            let methods = &mut nuts_settings.nut04.methods;
+            let options = methods
+                .iter()
+                .find(|s| s.method == method && s.unit == unit)
+                .and_then(|s| s.options.clone());
+            methods.retain(|s| s.method != method || s.unit != unit);
+            if update.enabled {
+                methods.push(MintMethodSettings {
+                    method,
+                    unit,
+                    min_amount,
+                    max_amount,
+                    options,
+                });
+            }
+        }
+        QuoteKind::QkMelt => {
+            if update.enabled && !melt_fee_policy.supports_unit(unit) {
+                return Err(Error::MeltFeePolicyUnsupportedUnit(unit));
+            }
+            let methods = &mut nuts_settings.nut05.methods;
+            methods.retain(|s| s.method != method || s.unit != unit);
+            if update.enabled {
+                methods.push(MeltMethodSettings {
+                    method,
+                    unit,
+                    min_amount,
+                    max_amount,
+                });
+            }
+        }
+        QuoteKind::QkUnspecified => unreachable!("rejected by quote_kind"),
+    }
+
+    Ok(())
+}
+
+/// `Some("")` clears the field
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn non_empty_vec<T>(values: Vec<T>) -> Option<Vec<T>> {
+    (!values.is_empty()).then_some(values)
+}
+
+impl GrpcState {
+    async fn inner_list_quotes(
+        &self,
+        kind: QuoteKind,
+        state: Option<String>,
+        unit: Option<String>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Quote>, Error> {
+        let mut conn = self.pg_pool.acquire().await?;
+
+        let quotes = match kind {
+            QuoteKind::QkMint => {
+                let state = state
+                    .map(|s| MintQuoteState::from_str(&s).map_err(|_| Error::InvalidQuoteState(s)))
+                    .transpose()?;
+                db_node::mint_quote::list(&mut conn, state, unit.as_deref(), limit, offset)
+                    .await?
+                    .into_iter()
+                    .map(|q| Quote {
+                        quote: q.id.to_string(),
+                        unit: q.unit,
+                        amount: q.amount.into(),
+                        fee: 0,
+                        state: q.state.to_string(),
+                        expiry: q.expiry,
+                        request: q.request,
+                        amount_paid: q.amount_paid.into(),
+                        excess: q
+                            .excess
+                            .map(|e| format!("{:#x}", primitive_types::U256::from_big_endian(&e))),
+                    })
+                    .collect()
+            }
+            QuoteKind::QkMelt => {
+                let state = state
+                    .map(|s| MeltQuoteState::from_str(&s).map_err(|_| Error::InvalidQuoteState(s)))
+                    .transpose()?;
+                db_node::melt_quote::list(&mut conn, state, unit.as_deref(), limit, offset)
+                    .await?
+                    .into_iter()
+                    .map(|(id, unit, amount, fee, state, expiry, request)| Quote {
+                        quote: id.to_string(),
+                        unit,
+                        amount: amount.into(),
+                        fee: fee.into(),
+                        state: state.to_string(),
+                        expiry,
+                        request,
+                        amount_paid: 0,
+                        excess: None,
+                    })
+                    .collect()
+            }
+            QuoteKind::QkUnspecified => return Err(Error::UnspecifiedQuoteKind),
+        };
+
+        Ok(quotes)
+    }
+}
+
+#[tonic::async_trait]
+impl Admin for GrpcState {
+    #[instrument(skip(self))]
+    async fn update_nuts_settings(
+        &self,
+        request: Request<UpdateNutsSettingsRequest>,
+    ) -> Result<Response<UpdateNutsSettingsResponse>, Status> {
+        let request = request.into_inner();
+
+        // Held until stored, so that concurrent updates don't overwrite each other
+        let mut write_lock = self.nuts.write().await;
+        let mut nuts_settings = write_lock.clone();
+        if let Some(disabled) = request.mint_disabled {
+            nuts_settings.nut04.disabled = disabled;
+        }
+        if let Some(disabled) = request.melt_disabled {
+            nuts_settings.nut05.disabled = disabled;
+        }
+        for update in request.methods {
+            apply_method_settings(&mut nuts_settings, self.melt_fee_policy, update)?;
+        }
+
+        let settings = serde_json::to_string(&nuts_settings).map_err(Error::Json)?;
+        let mut conn = self.pg_pool.acquire().await.map_err(Error::Sqlx)?;
+        db_node::nuts_settings::upsert(&mut conn, &settings)
+            .await
+            .map_err(Error::Sqlx)?;
+        *write_lock = nuts_settings;
+
+        info!(name: "admin-update-nuts-settings", name = "admin-update-nuts-settings", settings);
+
+        Ok(Response::new(UpdateNutsSettingsResponse { settings }))
+    }
+
+    #[instrument(skip(self))]
+    async fn update_node_info(
+        &self,
+        request: Request<UpdateNodeInfoRequest>,
+    ) -> Result<Response<UpdateNodeInfoResponse>, Status> {
+        let request = request.into_inner();
+
+        {
+            let mut write_lock = self.node_info.write().await;
+            let mut node_info = write_lock.clone();
+            if let Some(name) = request.name {
+                node_info.name = non_empty(name);
+            }
+            if let Some(description) = request.description {
+                node_info.description = non_empty(description);
+            }
+            if let Some(description_long) = request.description_long {
+                node_info.description_long = non_empty(description_long);
+            }
+            if let Some(contact) = request.contact {
+                node_info.contact = non_empty_vec(
+                    contact
+                        .contact_infos
+                        .into_iter()
+                        .map(|c| ContactInfo::new(c.method, c.info))
+                        .collect(),
+                );
+            }
+            if let Some(icon_url) = request.icon_url {
+                node_info.icon_url = non_empty(icon_url);
+            }
+            if let Some(urls) = request.urls {
+                node_info.urls = non_empty_vec(urls.urls);
+            }
+            if let Some(motd) = request.motd {
+                node_info.motd = non_empty(motd);
+            }
+
+            let stored = serde_json::to_string(&node_info).map_err(Error::Json)?;
+            let mut conn = self.pg_pool.acquire().await.map_err(Error::Sqlx)?;
+            db_node::node_info::upsert(&mut conn, &stored)
+                .await
+                .map_err(Error::Sqlx)?;
+            *write_lock = node_info;
+
+            info!(name: "admin-update-node-info", name = "admin-update-node-info", info = stored);
+        }
+
+        let node_info = self.inner_node_info().await?;
+        let info = serde_json::to_string(&node_info).map_err(Error::Json)?;
+
+        Ok(Response::new(UpdateNodeInfoResponse { info }))
+    }
+
+    #[instrument(skip(self))]
+    async fn expire_quote(
+        &self,
+        request: Request<ExpireQuoteRequest>,
+    ) -> Result<Response<ExpireQuoteResponse>, Status> {
+        let request = request.into_inner();
+        let kind = quote_kind(request.kind)?;
+        let quote_id = Uuid::from_str(&request.quote).map_err(Error::InvalidQuoteId)?;
+
+        let mut conn = self.pg_pool.acquire().await.map_err(Error::Sqlx)?;
+        let expired = match kind {
+            QuoteKind::QkMint => db_node::mint_quote::expire(&mut conn, quote_id).await,
+            QuoteKind::QkMelt => db_node::melt_quote::expire(&mut conn, quote_id).await,
+            QuoteKind::QkUnspecified => unreachable!("rejected by quote_kind"),
+        }
+        .map_err(Error::Sqlx)?;
+        if !expired {
+            return Err(Error::QuoteNotExpirable(quote_id).into());
+        }
+
+        info!(name: "admin-expire-quote", name = "admin-expire-quote", kind = kind.as_str_name(), %quote_id);
+
+        Ok(Response::new(ExpireQuoteResponse {}))
+    }
+
+    #[instrument(skip(self))]
+    async fn list_quotes(
+        &self,
+        request: Request<ListQuotesRequest>,
+    ) -> Result<Response<ListQuotesResponse>, Status> {
+        let request = request.into_inner();
+        let kind = quote_kind(request.kind)?;
+        let limit = request
+            .limit
+            .unwrap_or(DEFAULT_LIST_LIMIT)
+            .min(MAX_LIST_LIMIT);
+
+        let quotes = self
+            .inner_list_quotes(kind, request.state, request.unit, limit, request.offset)
+            .await?;
+
+        Ok(Response::new(ListQuotesResponse { quotes }))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_liabilities(
+        &self,
+        _request: Request<GetLiabilitiesRequest>,
+    ) -> Result<Response<GetLiabilitiesResponse>, Status> {
+        let mut conn = self.pg_pool.acquire().await.map_err(Error::Sqlx)?;
+        let liabilities = db_node::liabilities::get_by_unit(&mut conn)
+            .await
+            .map_err(Error::Db)?
+            .into_iter()
+            .map(|(unit, liabilities)| UnitLiabilities {
+                unit,
+                issued: liabilities.issued.into(),
+                redeemed: liabilities.redeemed.into(),
+                outstanding: liabilities.outstanding().into(),
+            })
+            .collect();
+
+        Ok(Response::new(GetLiabilitiesResponse { liabilities }))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_spent_proof_counts(
+        &self,
+        _request: Request<GetSpentProofCountsRequest>,
+    ) -> Result<Response<GetSpentProofCountsResponse>, Status> {
+        let mut conn = self.pg_pool.acquire().await.map_err(Error::Sqlx)?;
+        let counts = db_node::proof::count_by_unit(&mut conn)
+            .await
+            .map_err(Error::Sqlx)?
+            .into_iter()
+            .map(|(unit, spent, pending)| UnitProofCounts {
+                unit,
+                spent,
+                pending,
+            })
+            .collect();
+
+        Ok(Response::new(GetSpentProofCountsResponse { counts }))
+    }
+
+    #[instrument(skip(self))]
+    async fn list_pending_melts(
+        &self,
+        _request: Request<ListPendingMeltsRequest>,
+    ) -> Result<Response<ListPendingMeltsResponse>, Status> {
+        let quotes = self
+            .inner_list_quotes(
+                QuoteKind::QkMelt,
+                Some(MeltQuoteState::Pending.to_string()),
+                None,
+                u32::MAX,
+                0,
+            )
+            .await?;
+
+        Ok(Response::new(ListPendingMeltsResponse { quotes }))
+    }
+}
@@ -1,13 +1,18 @@
-use std::sync::{Arc, atomic::AtomicU64};
+use std::{
+    str::FromStr,
+    sync::{Arc, atomic::AtomicU64},
+};
 
-use nuts::{QuoteTTLConfig, nut06::NutsSettings};
-use starknet_types::Unit;
+use nuts::traits::Unit as _;
+use nuts::{Amount, QuoteTTLConfig, nut06::NutsSettings};
+use starknet_types::{Asset, Unit};
 use tokio::sync::RwLock;
 use tonic::transport::Channel;
 
-use crate::methods::Method;
+use crate::{methods::Method, routes::OperatorNodeInfo};
 
 pub type NutsSettingsState = Arc<RwLock<NutsSettings<Method, Unit, serde_json::Value>>>;
+pub type NodeInfoState = Arc<RwLock<OperatorNodeInfo>>;
 pub type SignerClient = signer::SignerClient<tower_otel::trace::Grpc<Channel>>;
 
 /// Quote Time To Live config
@@ -40,3 +45,99 @@ impl From<QuoteTTLConfig> for QuoteTTLConfigState {
         }
     }
 }
+
+/// Melt fee policy
+///
+/// Specifies how the fee charged on top of each melt quote amount is computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeltFeePolicy {
+    /// The same amount of unit for every quote
+    Fixed(Amount),
+    /// A share of the quote amount, in basis points (100 = 1%), rounded up
+    Percentage(u64),
+    /// The on-chain fee the node expects to pay for the transfer
+    ///
+    /// The gas is paid in STRK, which we cannot convert into other assets,
+    /// so it can only be used when STRK is the only unit melted.
+    EstimatedGas,
+}
+
+impl MeltFeePolicy {
+    /// Returns true if the fee of the quotes in `unit` can be computed
+    pub fn supports_unit(&self, unit: Unit) -> bool {
+        match self {
+            Self::Fixed(_) | Self::Percentage(_) => true,
+            Self::EstimatedGas => unit.is_asset_supported(Asset::Strk),
+        }
+    }
+}
+
+impl Default for MeltFeePolicy {
+    fn default() -> Self {
+        Self::Fixed(Amount::ZERO)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "invalid melt fee policy `{0}`, expected `fixed:<amount>`, `percentage:<basis points>` or `estimated-gas`"
+)]
+pub struct MeltFeePolicyFromStrError(String);
+
+impl FromStr for MeltFeePolicy {
+    type Err = MeltFeePolicyFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || MeltFeePolicyFromStrError(s.to_string());
+
+        match s.split_once(':') {
+            Some(("fixed", v)) => Ok(Self::Fixed(Amount::from(
+                v.parse::<u64>().map_err(|_| err())?,
+            ))),
+            Some(("percentage", v)) => Ok(Self::Percentage(v.parse().map_err(|_| err())?)),
+            None if s == "estimated-gas" => Ok(Self::EstimatedGas),
+            _ => Err(err()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_melt_fee_policy() {
+        assert_eq!(
+            MeltFeePolicy::from_str("fixed:3").unwrap(),
+            MeltFeePolicy::Fixed(Amount::from(3u64))
+        );
+        assert_eq!(
+            MeltFeePolicy::from_str("percentage:50").unwrap(),
+            MeltFeePolicy::Percentage(50)
+        );
+        assert_eq!(
+            MeltFeePolicy::from_str("estimated-gas").unwrap(),
+            MeltFeePolicy::EstimatedGas
+        );
+        assert!(MeltFeePolicy::from_str("estimated-gas:1").is_err());
+        assert!(MeltFeePolicy::from_str("fixed:-1").is_err());
+    }
+
+    #[test]
+    fn estimated_gas_only_supports_strk() {
+        for unit in [
+            Unit::MilliStrk,
+            Unit::Gwei,
+            Unit::Satoshi,
+            Unit::CentiUsdT,
+            Unit::CentiUsdC,
+        ] {
+            assert_eq!(
+                MeltFeePolicy::EstimatedGas.supports_unit(unit),
+                unit == Unit::MilliStrk
+            );
+            assert!(MeltFeePolicy::Fixed(Amount::ONE).supports_unit(unit));
+            assert!(MeltFeePolicy::Percentage(1).supports_unit(unit));
+        }
+    }
+}
@@ -1,9 +1,14 @@
 //! Instrumentation for the amounts at the different steps of the deposit and withdrawal processes
 //!
 //! The values are represented as open-telemetry gauges, and read from db at a fixed time interval.
+//! So are the counts of the quotes expired and archived by the quote sweeper, and of the late
+//! payments it flagged.
+//! The reserve ratio of the latest reserves attestation is also reported, along with whether it
+//! dropped below the minimum one, to be alerted on.
 use std::time::Duration;
 
 use opentelemetry::{KeyValue, metrics::Gauge};
+use primitive_types::U256;
 use sqlx::{PgPool, Pool, Postgres};
 use starknet_types::Unit;
 use tracing::error;
@@ -12,11 +17,18 @@ pub struct DbMetricsObserver {
     pool: Pool<Postgres>,
     units: Vec<Unit>,
     gauge: Gauge<u64>,
+    /// In basis points (10000 = 100%)
+    min_reserve_ratio: u64,
 }
 
 impl DbMetricsObserver {
-    pub fn new(pool: PgPool, units: Vec<Unit>, gauge: Gauge<u64>) -> Self {
-        Self { pool, units, gauge }
+    pub fn new(pool: PgPool, units: Vec<Unit>, gauge: Gauge<u64>, min_reserve_ratio: u64) -> Self {
+        Self {
+            pool,
+            units,
+            gauge,
+            min_reserve_ratio,
+        }
     }
 
     async fn poll_metrics(&mut self) -> Result<(), anyhow::Error> {
@@ -66,6 +78,102 @@ impl DbMetricsObserver {
                     KeyValue::new("unit", unit.clone()),
                 ],
             );
+            self.gauge.record(
+                metrics.failed_withdrawals.into(),
+                &[
+                    KeyValue::new("metric", "withdrawals.failed"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+            self.gauge.record(
+                metrics.collected_withdrawal_fees.into(),
+                &[
+                    KeyValue::new("metric", "withdrawals.fees.collected"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+            self.gauge.record(
+                metrics.spent_withdrawal_fees.into(),
+                &[
+                    KeyValue::new("metric", "withdrawals.fees.spent"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+        }
+
+        let quote_counts =
+            db_node::gauge::get_quote_counts_by_units(&mut conn, &self.units).await?;
+        for (unit, counts) in quote_counts {
+            self.gauge.record(
+                counts.expired_deposits,
+                &[
+                    KeyValue::new("metric", "deposits.expired"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+            self.gauge.record(
+                counts.archived_deposits,
+                &[
+                    KeyValue::new("metric", "deposits.archived"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+            self.gauge.record(
+                counts.late_deposit_payments,
+                &[
+                    KeyValue::new("metric", "deposits.late_payments"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+            self.gauge.record(
+                counts.expired_withdrawals,
+                &[
+                    KeyValue::new("metric", "withdrawals.expired"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+            self.gauge.record(
+                counts.archived_withdrawals,
+                &[
+                    KeyValue::new("metric", "withdrawals.archived"),
+                    KeyValue::new("unit", unit.clone()),
+                ],
+            );
+        }
+
+        if let Some(attestation) = db_node::reserves_attestation::get_latest(&mut conn).await? {
+            for asset in attestation.assets {
+                let liabilities = U256::from_big_endian(&asset.liabilities);
+                // Nothing to back
+                if liabilities.is_zero() {
+                    continue;
+                }
+                let reserves = U256::from_big_endian(&asset.reserves);
+                let ratio = reserves
+                    .checked_mul(U256::from(10_000))
+                    .map(|r| r / liabilities)
+                    .and_then(|r| u64::try_from(r).ok())
+                    .unwrap_or(u64::MAX);
+                let below_threshold = ratio < self.min_reserve_ratio;
+
+                self.gauge.record(
+                    ratio,
+                    &[
+                        KeyValue::new("metric", "reserves.ratio"),
+                        KeyValue::new("asset", asset.asset.clone()),
+                    ],
+                );
+                self.gauge.record(
+                    below_threshold.into(),
+                    &[
+                        KeyValue::new("metric", "reserves.below_threshold"),
+                        KeyValue::new("asset", asset.asset.clone()),
+                    ],
+                );
+                if below_threshold {
+                    error!(name: "reserves-ratio-below-threshold", asset = asset.asset, ratio, threshold = self.min_reserve_ratio, attestation_id = attestation.id);
+                }
+            }
         }
 
         Ok(())
@@ -1,37 +1,48 @@
 use crate::{
     keyset_cache::CachedKeysetInfo,
+    liabilities_report::LeafInclusionProof,
     liquidity_sources::LiquiditySources,
-    response_cache::{CachedResponse, InMemResponseCache, ResponseCache},
+    notifications::Notifier,
+    response_cache::{CachedResponse, ResponseCache},
 };
+use futures::{Stream, StreamExt};
 use node::{
     AcknowledgeRequest, AcknowledgeResponse, CheckStateRequest, CheckStateResponse, GetKeysRequest,
-    GetKeysResponse, GetKeysetsRequest, GetKeysetsResponse, GetNodeInfoRequest, Keyset,
-    MeltQuoteRequest, MeltQuoteResponse, MeltQuoteStateRequest, MeltRequest, MeltResponse,
-    MintQuoteRequest, MintQuoteResponse, MintRequest, MintResponse, Node, NodeInfoResponse,
-    ProofCheckState, QuoteStateRequest, RestoreRequest, RestoreResponse, SwapRequest, SwapResponse,
+    GetKeysResponse, GetKeysetsRequest, GetKeysetsResponse, GetLiabilitiesInclusionProofsRequest,
+    GetLiabilitiesInclusionProofsResponse, GetLiabilitiesReportRequest, GetNodeInfoRequest,
+    GetReservesAttestationRequest, Keyset, KeysetLiabilities, LiabilitiesInclusionProof,
+    LiabilitiesReport, MeltQuoteRequest, MeltQuoteResponse, MeltQuoteStateRequest, MeltRequest,
+    MeltResponse, MintQuoteRequest, MintQuoteResponse, MintRequest, MintResponse, Node,
+    NodeInfoResponse, ProofCheckState, QuoteStateRequest, ReservesAttestation, RestoreRequest,
+    RestoreResponse, SubscribeRequest, SubscribeResponse, SubscriptionKind, SwapRequest,
+    SwapResponse, subscribe_response,
 };
 use nuts::{
     Amount, QuoteTTLConfig,
-    nut00::{BlindedMessage, Proof, secret::Secret},
+    nut00::{BlindSignature, BlindedMessage, Proof, Witness, secret::Secret},
     nut01::{self, PublicKey},
     nut02::{self, KeysetId},
-    nut06::{ContactInfo, NodeInfo, NodeVersion, NutsSettings},
+    nut06::NutsSettings,
+    nut12::BlindSignatureDleq,
+    nut17::{Kind, NotificationPayload},
     nut19::{CacheResponseKey, Route, hash_melt_request, hash_mint_request, hash_swap_request},
 };
-use signer::GetRootPubKeyRequest;
 use sqlx::PgPool;
 use starknet_types::Unit;
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashSet, pin::Pin, str::FromStr, sync::Arc, time::Duration};
 use thiserror::Error;
 use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
-use tracing::instrument;
+use tracing::{instrument, warn};
 use uuid::Uuid;
 
 use crate::{
-    app_state::{NutsSettingsState, QuoteTTLConfigState, SignerClient},
+    app_state::{
+        MeltFeePolicy, NodeInfoState, NutsSettingsState, QuoteTTLConfigState, SignerClient,
+    },
     keyset_cache::KeysetCache,
     methods::Method,
+    routes::limits,
 };
 
 #[derive(Debug, Clone)]
@@ -40,9 +51,122 @@ pub struct GrpcState {
     pub signer: SignerClient,
     pub keyset_cache: KeysetCache,
     pub nuts: NutsSettingsState,
+    pub node_info: NodeInfoState,
     pub quote_ttl: Arc<QuoteTTLConfigState>,
+    pub melt_fee_policy: MeltFeePolicy,
     pub liquidity_sources: LiquiditySources<Unit>,
-    pub response_cache: Arc<InMemResponseCache<(Route, u64), CachedResponse>>,
+    pub response_cache: Arc<dyn ResponseCache<CacheResponseKey, CachedResponse>>,
+    pub notifier: Notifier,
+    /// How long the proofs of a retired keyset stay valid, `None` for forever
+    pub keyset_expiry_delay: Option<Duration>,
+    /// Fee of the keysets created from now on, the existing ones keep theirs
+    pub input_fee_ppk: u64,
+}
+
+pub fn blind_signature_to_proto(blind_signature: &BlindSignature) -> node::BlindSignature {
+    node::BlindSignature {
+        amount: blind_signature.amount.into(),
+        keyset_id: blind_signature.keyset_id.to_bytes().to_vec(),
+        blind_signature: blind_signature.c.to_bytes().to_vec(),
+        dleq: blind_signature
+            .dleq
+            .as_ref()
+            .map(blind_signature_dleq_to_proto),
+    }
+}
+
+pub fn mint_quote_response_to_proto(
+    response: nuts::nut04::MintQuoteResponse<Uuid>,
+) -> MintQuoteResponse {
+    MintQuoteResponse {
+        quote: response.quote.to_string(),
+        request: response.request,
+        state: node::MintQuoteState::from(response.state).into(),
+        expiry: response.expiry,
+        amount_paid: response.amount_paid.into(),
+    }
+}
+
+pub fn melt_quote_response_to_proto(
+    response: nuts::nut05::MeltQuoteResponse<Uuid, Unit>,
+) -> MeltQuoteResponse {
+    MeltQuoteResponse {
+        quote: response.quote.to_string(),
+        unit: response.unit.to_string(),
+        amount: response.amount.into(),
+        state: node::MeltQuoteState::from(response.state).into(),
+        expiry: response.expiry,
+        transfer_ids: response.transfer_ids.unwrap_or_default(),
+    }
+}
+
+fn merkle_sum_node_to_proto(hash: &[u8], amount: Amount) -> node::MerkleSumNode {
+    node::MerkleSumNode {
+        hash: hash.to_vec(),
+        amount: amount.into(),
+    }
+}
+
+fn liabilities_report_to_proto(report: db_node::liabilities_report::Report) -> LiabilitiesReport {
+    LiabilitiesReport {
+        id: report.id as u64,
+        created_at: report.created_at,
+        keysets: report
+            .keysets
+            .into_iter()
+            .map(|k| KeysetLiabilities {
+                keyset_id: k.keyset_id.to_bytes().to_vec(),
+                issued_root: Some(merkle_sum_node_to_proto(&k.issued_root, k.issued)),
+                issued_count: k.issued_count,
+                burned_root: Some(merkle_sum_node_to_proto(&k.burned_root, k.burned)),
+                burned_count: k.burned_count,
+            })
+            .collect(),
+    }
+}
+
+fn inclusion_proof_to_proto(leaf_proof: LeafInclusionProof) -> LiabilitiesInclusionProof {
+    LiabilitiesInclusionProof {
+        point: leaf_proof.leaf.point.to_bytes().to_vec(),
+        keyset_id: leaf_proof.leaf.keyset_id.to_bytes().to_vec(),
+        amount: leaf_proof.leaf.amount.into(),
+        leaf_index: leaf_proof.proof.leaf_index,
+        leaf_count: leaf_proof.proof.leaf_count,
+        siblings: leaf_proof
+            .proof
+            .siblings
+            .iter()
+            .map(|n| merkle_sum_node_to_proto(n.hash.as_byte_array(), n.amount))
+            .collect(),
+    }
+}
+
+fn reserves_attestation_to_proto(
+    attestation: db_node::reserves_attestation::Attestation,
+) -> ReservesAttestation {
+    ReservesAttestation {
+        id: attestation.id as u64,
+        created_at: attestation.created_at,
+        reserves_address: attestation.reserves_address,
+        assets: attestation
+            .assets
+            .into_iter()
+            .map(|a| node::AssetReserves {
+                asset: a.asset,
+                liabilities: a.liabilities.to_vec(),
+                reserves: a.reserves.to_vec(),
+            })
+            .collect(),
+        hash: attestation.hash.to_vec(),
+        signature: attestation.signature,
+    }
+}
+
+fn blind_signature_dleq_to_proto(dleq: &BlindSignatureDleq) -> node::BlindSignatureDleq {
+    node::BlindSignatureDleq {
+        e: dleq.e.to_secret_bytes().to_vec(),
+        s: dleq.s.to_secret_bytes().to_vec(),
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -55,91 +179,278 @@ pub enum InitKeysetError {
     Nut02(#[from] nut02::Error),
     #[error(transparent)]
     Sqlx(#[from] sqlx::Error),
+    #[error(transparent)]
+    Db(#[from] db_node::Error),
+    #[error("signer derived keyset {1} instead of {0}, is it using another root key?")]
+    KeysetIdMismatch(KeysetId, KeysetId),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadNutsSettingsError {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error("invalid nuts settings in db: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadNodeInfoError {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error("invalid node info in db: {0}")]
+    Json(#[from] serde_json::Error),
 }
 
 impl GrpcState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         pg_pool: PgPool,
         signer_client: SignerClient,
         nuts_settings: NutsSettings<Method, Unit, serde_json::Value>,
         quote_ttl: QuoteTTLConfig,
+        melt_fee_policy: MeltFeePolicy,
         liquidity_sources: LiquiditySources<Unit>,
+        response_cache: Arc<dyn ResponseCache<CacheResponseKey, CachedResponse>>,
+        keyset_expiry_delay: Option<Duration>,
+        input_fee_ppk: u64,
     ) -> Self {
         Self {
             pg_pool,
             keyset_cache: Default::default(),
             nuts: Arc::new(RwLock::new(nuts_settings)),
+            node_info: Default::default(),
             quote_ttl: Arc::new(quote_ttl.into()),
+            melt_fee_policy,
             signer: signer_client,
             liquidity_sources,
-            response_cache: Arc::new(InMemResponseCache::new(None)),
+            response_cache,
+            notifier: Notifier::default(),
+            keyset_expiry_delay,
+            input_fee_ppk,
         }
     }
 
+    /// Declare to the signer the active keyset of each unit, creating it if there is none yet
+    ///
+    /// Keysets may have been rotated since, by this instance or another one sharing the db,
+    /// so the active ones are declared again with their own derivation path and max order.
     pub async fn init_first_keysets(
         &self,
         units: impl Iterator<Item = Unit>,
         index: u32,
         max_order: u32,
     ) -> Result<(), InitKeysetError> {
+        let input_fee_ppk = self.input_fee_ppk;
+        let mut conn = self.pg_pool.acquire().await?;
+
+        let active_keysets = db_node::keyset::get_active_keysets::<Unit>(&mut conn).await?;
         let mut insert_keysets_query_builder = db_node::InsertKeysetsQueryBuilder::new();
+        let mut any_new_keyset = false;
 
         for unit in units {
+            match active_keysets.iter().find(|(_, info)| info.unit() == unit) {
+                Some((keyset_id, info)) => {
+                    let declared_keyset_id = self
+                        .declare_keyset(
+                            unit,
+                            info.derivation_path_index(),
+                            info.max_order().into(),
+                            info.input_fee_ppk(),
+                        )
+                        .await?;
+                    if declared_keyset_id != *keyset_id {
+                        return Err(InitKeysetError::KeysetIdMismatch(
+                            *keyset_id,
+                            declared_keyset_id,
+                        ));
+                    }
+                }
+                None => {
+                    let keyset_id = self
+                        .declare_keyset(unit, index, max_order, input_fee_ppk)
+                        .await?;
+                    insert_keysets_query_builder.add_row(
+                        keyset_id,
+                        unit,
+                        max_order,
+                        index,
+                        input_fee_ppk,
+                    );
+                    any_new_keyset = true;
+                }
+            }
+        }
+
+        if any_new_keyset {
+            insert_keysets_query_builder.execute(&mut conn).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Declare an active keyset to the signer and cache it
+    async fn declare_keyset(
+        &self,
+        unit: Unit,
+        index: u32,
+        max_order: u32,
+        input_fee_ppk: u64,
+    ) -> Result<KeysetId, InitKeysetError> {
+        let response = self
+            .signer
+            .clone()
+            .declare_keyset(signer::DeclareKeysetRequest {
+                unit: unit.to_string(),
+                index,
+                max_order,
+            })
+            .await?
+            .into_inner();
+        let keyset_id = KeysetId::from_bytes(&response.keyset_id)?;
+
+        self.keyset_cache
+            .insert_info(
+                keyset_id,
+                CachedKeysetInfo::new(true, unit, max_order, input_fee_ppk),
+            )
+            .await;
+
+        let keys = response
+            .keys
+            .into_iter()
+            .map(|k| -> Result<(Amount, PublicKey), InitKeysetError> {
+                Ok((
+                    Amount::from(k.amount),
+                    PublicKey::from_str(&k.pubkey).map_err(InitKeysetError::Nut01)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.keyset_cache
+            .insert_keys(keyset_id, keys.into_iter())
+            .await;
+
+        Ok(keyset_id)
+    }
+
+    /// Declare to the signer the keysets of the db it doesn't know about
+    ///
+    /// The signer rebuilds the keysets it keeps a record of by itself on restart,
+    /// but this record may be missing some of them, e.g. if it was restored from a backup.
+    pub async fn reconcile_keysets_with_signer(&self) -> Result<(), InitKeysetError> {
+        let signer_keysets = self
+            .signer
+            .clone()
+            .list_keysets(signer::ListKeysetsRequest {})
+            .await?
+            .into_inner()
+            .keysets
+            .into_iter()
+            .map(|k| KeysetId::from_bytes(&k.keyset_id))
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        let mut conn = self.pg_pool.acquire().await?;
+        let db_keysets = db_node::keyset::get_keysets(&mut conn)
+            .await?
+            .map(|(id, ..)| KeysetId::from_bytes(&id))
+            .collect::<Result<HashSet<_>, _>>()?;
+
+        for keyset_id in db_keysets.difference(&signer_keysets) {
+            let keyset = db_node::keyset::get_keyset::<Unit>(&mut conn, keyset_id).await?;
             let response = self
                 .signer
                 .clone()
                 .declare_keyset(signer::DeclareKeysetRequest {
-                    unit: unit.to_string(),
-                    index,
-                    max_order,
+                    unit: keyset.unit().to_string(),
+                    index: keyset.derivation_path_index(),
+                    max_order: keyset.max_order().into(),
                 })
                 .await?;
-            let response = response.into_inner();
-            let keyset_id = KeysetId::from_bytes(&response.keyset_id)?;
-
-            insert_keysets_query_builder.add_row(keyset_id, unit, max_order, index);
-
-            self.keyset_cache
-                .insert_info(keyset_id, CachedKeysetInfo::new(true, unit, max_order))
-                .await;
-
-            let keys = response
-                .keys
-                .into_iter()
-                .map(|k| -> Result<(Amount, PublicKey), InitKeysetError> {
-                    Ok((
-                        Amount::from(k.amount),
-                        PublicKey::from_str(&k.pubkey).map_err(InitKeysetError::Nut01)?,
-                    ))
-                })
-                .collect::<Result<Vec<_>, _>>()?;
+            let declared_keyset_id = KeysetId::from_bytes(&response.into_inner().keyset_id)?;
+            if declared_keyset_id != *keyset_id {
+                return Err(InitKeysetError::KeysetIdMismatch(
+                    *keyset_id,
+                    declared_keyset_id,
+                ));
+            }
+        }
 
-            self.keyset_cache
-                .insert_keys(keyset_id, keys.into_iter())
-                .await;
+        for keyset_id in signer_keysets.difference(&db_keysets) {
+            warn!(name: "unknown-signer-keyset", name = "unknown-signer-keyset", keyset_id = %keyset_id);
         }
 
+        Ok(())
+    }
+
+    /// Replace the nuts settings with the ones stored in db, if any
+    ///
+    /// NUT-19 settings describe this instance's response cache, so they are kept as is.
+    pub async fn load_nuts_settings(&self) -> Result<(), LoadNutsSettingsError> {
         let mut conn = self.pg_pool.acquire().await?;
-        insert_keysets_query_builder.execute(&mut conn).await?;
+        let Some(stored) = db_node::nuts_settings::get(&mut conn).await? else {
+            return Ok(());
+        };
+        let mut nuts_settings: NutsSettings<Method, Unit, serde_json::Value> =
+            serde_json::from_str(&stored)?;
+
+        let mut write_lock = self.nuts.write().await;
+        nuts_settings.nut19 = write_lock.nut19.clone();
+        *write_lock = nuts_settings;
 
         Ok(())
     }
 
-    pub fn get_cached_response(&self, cache_key: &CacheResponseKey) -> Option<CachedResponse> {
-        if let Some(cached_response) = self.response_cache.get(cache_key) {
-            return Some(cached_response);
-        }
+    /// Replace the node info with the one stored in db, if any
+    pub async fn load_node_info(&self) -> Result<(), LoadNodeInfoError> {
+        let mut conn = self.pg_pool.acquire().await?;
+        let Some(stored) = db_node::node_info::get(&mut conn).await? else {
+            return Ok(());
+        };
+        let node_info = serde_json::from_str(&stored)?;
+
+        *self.node_info.write().await = node_info;
 
-        None
+        Ok(())
     }
 
-    pub fn cache_response(
+    pub async fn get_cached_response(
+        &self,
+        cache_key: &CacheResponseKey,
+    ) -> Result<Option<CachedResponse>, Status> {
+        self.response_cache
+            .get(cache_key)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+
+    pub async fn cache_response(
         &self,
         cache_key: (Route, u64),
         response: CachedResponse,
     ) -> Result<(), Status> {
         self.response_cache
             .insert(cache_key, response)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Forget a response once the client acknowledged it stored it
+    pub async fn remove_cached_response(&self, cache_key: CacheResponseKey) -> Result<(), Status> {
+        self.response_cache
+            .remove(&cache_key)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        // The entry may also be held by the in-memory cache of another instance
+        let mut conn = self
+            .pg_pool
+            .acquire()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        db_node::notify::response_cache_entry(&mut conn, &cache_key.0.to_string(), cache_key.1)
+            .await
             .map_err(|e| Status::internal(e.to_string()))?;
 
         Ok(())
@@ -160,6 +471,8 @@ enum ParseGrpcError {
     Uuid(uuid::Error),
     #[error(transparent)]
     Secret(nuts::nut00::secret::Error),
+    #[error("invalid witness: {0}")]
+    Witness(serde_json::Error),
 }
 
 impl From<ParseGrpcError> for Status {
@@ -184,10 +497,12 @@ impl Node for GrpcState {
         let keysets = db_node::keyset::get_keysets(&mut conn)
             .await
             .map_err(|e| Status::internal(e.to_string()))?
-            .map(|(id, unit, active)| Keyset {
+            .map(|(id, unit, active, input_fee_ppk, final_expiry)| Keyset {
                 id: id.to_vec(),
                 unit,
                 active,
+                input_fee_ppk,
+                final_expiry,
             })
             .collect();
 
@@ -225,23 +540,8 @@ impl Node for GrpcState {
     ) -> Result<Response<SwapResponse>, Status> {
         let swap_request = swap_request.into_inner();
 
-        if swap_request.inputs.len() > 64 {
-            return Err(Status::invalid_argument(
-                "Too many inputs: maximum allowed is 64",
-            ));
-        }
-        if swap_request.outputs.len() > 64 {
-            return Err(Status::invalid_argument(
-                "Too many outputs: maximum allowed is 64",
-            ));
-        }
-
-        if swap_request.inputs.is_empty() {
-            return Err(Status::invalid_argument("Inputs cannot be empty"));
-        }
-        if swap_request.outputs.is_empty() {
-            return Err(Status::invalid_argument("Outputs cannot be empty"));
-        }
+        limits::check_inputs_count(swap_request.inputs.len())?;
+        limits::check_outputs_count(swap_request.outputs.len())?;
 
         let inputs = swap_request
             .inputs
@@ -254,6 +554,12 @@ impl Node for GrpcState {
                     secret: Secret::new(p.secret).map_err(ParseGrpcError::Secret)?,
                     c: PublicKey::from_slice(&p.unblind_signature)
                         .map_err(ParseGrpcError::PublicKey)?,
+                    witness: p
+                        .witness
+                        .map(|w| Witness::from_str(&w))
+                        .transpose()
+                        .map_err(ParseGrpcError::Witness)?,
+                    dleq: None,
                 })
             })
             .collect::<Result<Vec<_>, _>>()?;
@@ -278,25 +584,21 @@ impl Node for GrpcState {
 
         let cache_key = (Route::Swap, hash_swap_request(&nut_swap_request));
         // Try to get from cache first
-        if let Some(CachedResponse::Swap(swap_response)) = self.get_cached_response(&cache_key) {
+        if let Some(CachedResponse::Swap(swap_response)) =
+            self.get_cached_response(&cache_key).await?
+        {
             return Ok(Response::new(swap_response));
         }
 
         let promises = self.inner_swap(&inputs, &outputs).await?;
 
         let swap_response = SwapResponse {
-            signatures: promises
-                .iter()
-                .map(|p| node::BlindSignature {
-                    amount: p.amount.into(),
-                    keyset_id: p.keyset_id.to_bytes().to_vec(),
-                    blind_signature: p.c.to_bytes().to_vec(),
-                })
-                .collect(),
+            signatures: promises.iter().map(blind_signature_to_proto).collect(),
         };
 
         // Store in cache
-        self.cache_response(cache_key, CachedResponse::Swap(swap_response.clone()))?;
+        self.cache_response(cache_key, CachedResponse::Swap(swap_response.clone()))
+            .await?;
 
         Ok(Response::new(swap_response))
     }
@@ -320,6 +622,7 @@ impl Node for GrpcState {
             request: response.request.clone(),
             state: node::MintQuoteState::from(response.state).into(),
             expiry: response.expiry,
+            amount_paid: response.amount_paid.into(),
         };
 
         Ok(Response::new(mint_quote_response))
@@ -332,15 +635,7 @@ impl Node for GrpcState {
     ) -> Result<Response<MintResponse>, Status> {
         let mint_request = mint_request.into_inner();
 
-        if mint_request.outputs.is_empty() {
-            return Err(Status::invalid_argument("Outputs cannot be empty"));
-        }
-
-        if mint_request.outputs.len() > 64 {
-            return Err(Status::invalid_argument(
-                "Too many outputs: maximum allowed is 64",
-            ));
-        }
+        limits::check_outputs_count(mint_request.outputs.len())?;
 
         let outputs = mint_request
             .outputs
@@ -363,7 +658,9 @@ impl Node for GrpcState {
 
         let cache_key = (Route::Mint, hash_mint_request(&nut_mint_request));
         // Try to get from cache first
-        if let Some(CachedResponse::Mint(mint_response)) = self.get_cached_response(&cache_key) {
+        if let Some(CachedResponse::Mint(mint_response)) =
+            self.get_cached_response(&cache_key).await?
+        {
             return Ok(Response::new(mint_response));
         }
 
@@ -374,11 +671,7 @@ impl Node for GrpcState {
         let promises = self.inner_mint(method, quote_id, &outputs).await?;
         let signatures = promises
             .iter()
-            .map(|p| node::BlindSignature {
-                amount: p.amount.into(),
-                keyset_id: p.keyset_id.to_bytes().to_vec(),
-                blind_signature: p.c.to_bytes().to_vec(),
-            })
+            .map(blind_signature_to_proto)
             .collect::<Vec<_>>();
 
         let mint_response = MintResponse {
@@ -386,7 +679,8 @@ impl Node for GrpcState {
         };
 
         // Store in cache
-        self.cache_response(cache_key, CachedResponse::Mint(mint_response.clone()))?;
+        self.cache_response(cache_key, CachedResponse::Mint(mint_response.clone()))
+            .await?;
 
         Ok(Response::new(mint_response))
     }
@@ -421,15 +715,7 @@ impl Node for GrpcState {
     ) -> Result<Response<MeltResponse>, Status> {
         let melt_request = melt_request.into_inner();
 
-        if melt_request.inputs.len() > 64 {
-            return Err(Status::invalid_argument(
-                "Too many inputs: maximum allowed is 64",
-            ));
-        }
-
-        if melt_request.inputs.is_empty() {
-            return Err(Status::invalid_argument("Inputs cannot be empty"));
-        }
+        limits::check_inputs_count(melt_request.inputs.len())?;
 
         let inputs = melt_request
             .clone()
@@ -443,6 +729,12 @@ impl Node for GrpcState {
                     secret: Secret::new(p.secret).map_err(ParseGrpcError::Secret)?,
                     c: PublicKey::from_slice(&p.unblind_signature)
                         .map_err(ParseGrpcError::PublicKey)?,
+                    witness: p
+                        .witness
+                        .map(|w| Witness::from_str(&w))
+                        .transpose()
+                        .map_err(ParseGrpcError::Witness)?,
+                    dleq: None,
                 })
             })
             .collect::<Result<Vec<_>, _>>()?;
@@ -454,7 +746,9 @@ impl Node for GrpcState {
         let cache_key = (Route::Melt, hash_melt_request(&nut_melt_request));
 
         // Try to get from cache first
-        if let Some(CachedResponse::Melt(melt_response)) = self.get_cached_response(&cache_key) {
+        if let Some(CachedResponse::Melt(melt_response)) =
+            self.get_cached_response(&cache_key).await?
+        {
             return Ok(Response::new(melt_response));
         }
 
@@ -469,7 +763,8 @@ impl Node for GrpcState {
         };
 
         // Store in cache
-        self.cache_response(cache_key, CachedResponse::Melt(melt_response.clone()))?;
+        self.cache_response(cache_key, CachedResponse::Melt(melt_response.clone()))
+            .await?;
 
         Ok(Response::new(melt_response))
     }
@@ -486,12 +781,7 @@ impl Node for GrpcState {
             Uuid::from_str(&mint_quote_state_request.quote).map_err(ParseGrpcError::Uuid)?;
 
         match self.inner_mint_quote_state(method, quote_id).await? {
-            Some(response) => Ok(Response::new(MintQuoteResponse {
-                quote: response.quote.to_string(),
-                request: response.request,
-                state: node::MintQuoteState::from(response.state).into(),
-                expiry: response.expiry,
-            })),
+            Some(response) => Ok(Response::new(mint_quote_response_to_proto(response))),
             None => Err(Status::not_found(format!(
                 "no mint quote with id {}",
                 quote_id
@@ -512,14 +802,7 @@ impl Node for GrpcState {
 
         let response = self.inner_melt_quote_state(method, quote_id).await?;
 
-        Ok(Response::new(MeltQuoteResponse {
-            quote: response.quote.to_string(),
-            unit: response.unit.to_string(),
-            amount: response.amount.into(),
-            state: node::MeltQuoteState::from(response.state).into(),
-            expiry: response.expiry,
-            transfer_ids: response.transfer_ids.unwrap_or_default(),
-        }))
+        Ok(Response::new(melt_quote_response_to_proto(response)))
     }
 
     #[instrument(skip(self))]
@@ -527,38 +810,7 @@ impl Node for GrpcState {
         &self,
         _node_info_request: Request<GetNodeInfoRequest>,
     ) -> Result<Response<NodeInfoResponse>, Status> {
-        let nuts_config = {
-            let nuts_read_lock = self.nuts.read().await;
-            nuts_read_lock.clone()
-        };
-        let pub_key = self
-            .signer
-            .clone()
-            .get_root_pub_key(Request::new(GetRootPubKeyRequest {}))
-            .await?
-            .into_inner()
-            .root_pubkey;
-        let node_info = NodeInfo {
-            name: Some("Paynet Test Node".to_string()),
-            pubkey: Some(
-                PublicKey::from_str(&pub_key).map_err(|e| Status::internal(e.to_string()))?,
-            ),
-            version: Some(NodeVersion {
-                name: "some_name".to_string(),
-                version: "0.0.0".to_string(),
-            }),
-            description: Some("A test node".to_string()),
-            description_long: Some("This is a longer description of the test node.".to_string()),
-            contact: Some(vec![ContactInfo {
-                method: "some_method".to_string(),
-                info: "some_info".to_string(),
-            }]),
-            nuts: nuts_config,
-            icon_url: Some("http://example.com/icon.png".to_string()),
-            urls: Some(vec!["http://example.com".to_string()]),
-            motd: Some("Welcome to the node!".to_string()),
-            time: Some(std::time::UNIX_EPOCH.elapsed().unwrap().as_secs()),
-        };
+        let node_info = self.inner_node_info().await?;
 
         let node_info_str =
             serde_json::to_string(&node_info).map_err(|e| Status::internal(e.to_string()))?;
@@ -579,13 +831,7 @@ impl Node for GrpcState {
             Route::from_str(&path_str).map_err(|_| Status::invalid_argument("Invalid path"))?;
         let request_hash = ack_request.get_ref().request_hash;
 
-        let cache_key = (path, request_hash);
-
-        // check if the request is already in the cache, if so, remove it
-        let exist = self.response_cache.get(&cache_key);
-        if exist.is_some() {
-            self.response_cache.remove(&cache_key);
-        }
+        self.remove_cached_response((path, request_hash)).await?;
 
         Ok(Response::new(AcknowledgeResponse {}))
     }
@@ -620,11 +866,7 @@ impl Node for GrpcState {
     ) -> Result<Response<RestoreResponse>, Status> {
         let restore_signatures_request = restore_signatures_request.into_inner();
 
-        if restore_signatures_request.outputs.len() > 100 {
-            return Err(Status::invalid_argument(
-                "Too many outputs: maximum allowed is 100",
-            ));
-        }
+        limits::check_restore_outputs_count(restore_signatures_request.outputs.len())?;
 
         let blind_messages = restore_signatures_request
             .outputs
@@ -659,6 +901,7 @@ impl Node for GrpcState {
                         amount,
                         keyset_id,
                         blind_signature: res.blind_signature.to_bytes().to_vec(),
+                        dleq: res.dleq.as_ref().map(blind_signature_dleq_to_proto),
                     },
                 )
             })
@@ -671,4 +914,155 @@ impl Node for GrpcState {
 
         Ok(Response::new(restore_response))
     }
+
+    #[instrument(skip(self))]
+    async fn get_liabilities_report(
+        &self,
+        request: Request<GetLiabilitiesReportRequest>,
+    ) -> Result<Response<LiabilitiesReport>, Status> {
+        let mut conn = self
+            .pg_pool
+            .acquire()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let report_id = match request.into_inner().report_id {
+            Some(report_id) => i64::try_from(report_id)
+                .map_err(|_| Status::not_found(format!("no liabilities report {}", report_id)))?,
+            None => db_node::liabilities_report::get_latest_id(&mut conn)
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?
+                .ok_or_else(|| Status::not_found("no liabilities report yet"))?,
+        };
+        let report = db_node::liabilities_report::get(&mut conn, report_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found(format!("no liabilities report {}", report_id)))?;
+
+        Ok(Response::new(liabilities_report_to_proto(report)))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_liabilities_inclusion_proofs(
+        &self,
+        request: Request<GetLiabilitiesInclusionProofsRequest>,
+    ) -> Result<Response<GetLiabilitiesInclusionProofsResponse>, Status> {
+        let request = request.into_inner();
+
+        if request.blinded_messages.len() + request.ys.len() > 100 {
+            return Err(Status::invalid_argument(
+                "Too many points: maximum allowed is 100",
+            ));
+        }
+
+        let parse_points = |points: &[Vec<u8>]| {
+            points
+                .iter()
+                .map(|p| PublicKey::from_slice(p).map_err(ParseGrpcError::PublicKey))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let blinded_messages = parse_points(&request.blinded_messages)?;
+        let ys = parse_points(&request.ys)?;
+
+        let report_id = i64::try_from(request.report_id).map_err(|_| {
+            Status::not_found(format!("no liabilities report {}", request.report_id))
+        })?;
+        let mut conn = self
+            .pg_pool
+            .acquire()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        if db_node::liabilities_report::get(&mut conn, report_id)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .is_none()
+        {
+            return Err(Status::not_found(format!(
+                "no liabilities report {}",
+                report_id
+            )));
+        }
+
+        let (issued, burned) = crate::liabilities_report::get_inclusion_proofs(
+            &mut conn,
+            report_id,
+            &blinded_messages,
+            &ys,
+        )
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(Response::new(GetLiabilitiesInclusionProofsResponse {
+            issued: issued.into_iter().map(inclusion_proof_to_proto).collect(),
+            burned: burned.into_iter().map(inclusion_proof_to_proto).collect(),
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_reserves_attestation(
+        &self,
+        _request: Request<GetReservesAttestationRequest>,
+    ) -> Result<Response<ReservesAttestation>, Status> {
+        let mut conn = self
+            .pg_pool
+            .acquire()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let attestation = db_node::reserves_attestation::get_latest(&mut conn)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("no reserves attestation yet"))?;
+
+        Ok(Response::new(reserves_attestation_to_proto(attestation)))
+    }
+
+    type SubscribeStream =
+        Pin<Box<dyn Stream<Item = Result<SubscribeResponse, Status>> + Send + 'static>>;
+
+    #[instrument(skip(self))]
+    async fn subscribe(
+        &self,
+        subscribe_request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let subscribe_request = subscribe_request.into_inner();
+
+        let kind = match SubscriptionKind::try_from(subscribe_request.kind)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?
+        {
+            SubscriptionKind::SkUnspecified => {
+                return Err(Status::invalid_argument("unspecified subscription kind"));
+            }
+            SubscriptionKind::SkMintQuote => Kind::MintQuote(
+                Method::from_str(&subscribe_request.method).map_err(ParseGrpcError::Method)?,
+            ),
+            SubscriptionKind::SkMeltQuote => Kind::MeltQuote(
+                Method::from_str(&subscribe_request.method).map_err(ParseGrpcError::Method)?,
+            ),
+            SubscriptionKind::SkProofState => Kind::ProofState,
+        };
+
+        let stream = self.inner_subscribe(kind, subscribe_request.filters)?;
+
+        Ok(Response::new(Box::pin(stream.map(|payload| {
+            let payload = match payload? {
+                NotificationPayload::MintQuote(response) => {
+                    subscribe_response::Payload::MintQuote(mint_quote_response_to_proto(response))
+                }
+                NotificationPayload::MeltQuote(response) => {
+                    subscribe_response::Payload::MeltQuote(melt_quote_response_to_proto(response))
+                }
+                NotificationPayload::ProofState(proof_state) => {
+                    subscribe_response::Payload::ProofState(ProofCheckState {
+                        y: proof_state.y.to_bytes().to_vec(),
+                        state: proof_state.state.into(),
+                    })
+                }
+            };
+
+            Ok(SubscribeResponse {
+                payload: Some(payload),
+            })
+        }))))
+    }
 }
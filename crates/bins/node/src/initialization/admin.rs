@@ -0,0 +1,51 @@
+use std::net::SocketAddr;
+
+use futures::TryFutureExt;
+use node::AdminServer;
+use tonic::service::LayerExt;
+use tower::ServiceBuilder;
+use tracing::instrument;
+
+use crate::{admin::AdminAuth, grpc_service::GrpcState};
+
+use super::{
+    Error,
+    env_variables::{AdminGrpcConfig, EnvVariables},
+    grpc::build_server,
+};
+
+/// Serve the admin service on its own port, only to the callers bearing the admin token
+#[instrument(skip(grpc_state, env_vars))]
+pub async fn launch_admin_server_task(
+    grpc_state: GrpcState,
+    admin_config: &AdminGrpcConfig,
+    #[cfg_attr(not(feature = "tls"), allow(unused_variables))] env_vars: &EnvVariables,
+) -> Result<(SocketAddr, impl Future<Output = Result<(), crate::Error>>), Error> {
+    let address = format!("[::0]:{}", admin_config.port)
+        .parse()
+        .map_err(Error::InvalidAdminGrpcAddress)?;
+
+    let optl_layer = tower_otel::trace::GrpcLayer::server(tracing::Level::INFO);
+    let admin_service =
+        ServiceBuilder::new()
+            .layer(optl_layer)
+            .named_layer(AdminServer::with_interceptor(
+                grpc_state,
+                AdminAuth::new(&admin_config.token),
+            ));
+
+    let tonic_server = build_server(
+        #[cfg(feature = "tls")]
+        env_vars,
+    )
+    .map_err(Error::BuildServer)?;
+    let meter = opentelemetry::global::meter(env!("CARGO_PKG_NAME"));
+    let mut tonic_server = tonic_server.layer(tower_otel::metrics::HttpLayer::server(&meter));
+
+    let admin_future = tonic_server
+        .add_service(admin_service)
+        .serve(address)
+        .map_err(crate::Error::Tonic);
+
+    Ok((address, admin_future))
+}
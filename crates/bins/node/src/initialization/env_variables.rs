@@ -1,7 +1,14 @@
 use std::env::VarError;
 
+use crate::{
+    app_state::MeltFeePolicy, keyset_rotation::RotationPolicies, response_cache::ResponseCacheKind,
+};
+
 use super::Error;
 
+/// Reserves should fully back the ecash in circulation
+const DEFAULT_MIN_RESERVE_RATIO: u64 = 10_000;
+
 pub fn read_env_variables() -> Result<EnvVariables, Error> {
     // Only if we are in debug mode, we allow loading env variable from a .env file
     #[cfg(debug_assertions)]
@@ -21,22 +28,126 @@ pub fn read_env_variables() -> Result<EnvVariables, Error> {
         Err(VarError::NotPresent) => None,
         Err(e) => return Err(Error::Env("QUOTE_TTL", e)),
     };
+    let input_fee_ppk = match std::env::var("INPUT_FEE_PPK") {
+        Ok(v) => Some(v.parse().map_err(Error::ParseInt)?),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("INPUT_FEE_PPK", e)),
+    };
+    let melt_fee_policy = match std::env::var("MELT_FEE_POLICY") {
+        Ok(v) => v.parse()?,
+        Err(VarError::NotPresent) => MeltFeePolicy::default(),
+        Err(e) => return Err(Error::Env("MELT_FEE_POLICY", e)),
+    };
+    let response_cache = match std::env::var("RESPONSE_CACHE") {
+        Ok(v) => v.parse()?,
+        Err(VarError::NotPresent) => ResponseCacheKind::default(),
+        Err(e) => return Err(Error::Env("RESPONSE_CACHE", e)),
+    };
+    let response_cache_ttl = match std::env::var("RESPONSE_CACHE_TTL") {
+        Ok(v) => Some(v.parse().map_err(Error::ParseInt)?),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("RESPONSE_CACHE_TTL", e)),
+    };
+    let keyset_rotation_policies = match std::env::var("KEYSET_ROTATION_POLICIES") {
+        Ok(v) => v.parse()?,
+        Err(VarError::NotPresent) => RotationPolicies::default(),
+        Err(e) => return Err(Error::Env("KEYSET_ROTATION_POLICIES", e)),
+    };
+    let keyset_expiry_delay = match std::env::var("KEYSET_EXPIRY_DELAY") {
+        Ok(v) => Some(v.parse().map_err(Error::ParseInt)?),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("KEYSET_EXPIRY_DELAY", e)),
+    };
+    let liabilities_report_interval = match std::env::var("LIABILITIES_REPORT_INTERVAL") {
+        Ok(v) => Some(v.parse().map_err(Error::ParseInt)?),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("LIABILITIES_REPORT_INTERVAL", e)),
+    };
+    let reserves_attestation_interval = match std::env::var("RESERVES_ATTESTATION_INTERVAL") {
+        Ok(v) => Some(v.parse().map_err(Error::ParseInt)?),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("RESERVES_ATTESTATION_INTERVAL", e)),
+    };
+    let quote_sweep_interval = match std::env::var("QUOTE_SWEEP_INTERVAL") {
+        Ok(v) => Some(v.parse().map_err(Error::ParseInt)?),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("QUOTE_SWEEP_INTERVAL", e)),
+    };
+    let quote_archive_delay = match std::env::var("QUOTE_ARCHIVE_DELAY") {
+        Ok(v) => Some(v.parse().map_err(Error::ParseInt)?),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("QUOTE_ARCHIVE_DELAY", e)),
+    };
+    let min_reserve_ratio = match std::env::var("MIN_RESERVE_RATIO") {
+        Ok(v) => v.parse().map_err(Error::ParseInt)?,
+        Err(VarError::NotPresent) => DEFAULT_MIN_RESERVE_RATIO,
+        Err(e) => return Err(Error::Env("MIN_RESERVE_RATIO", e)),
+    };
+
+    let admin_grpc = match std::env::var("ADMIN_GRPC_PORT") {
+        Ok(port) => {
+            let token = std::env::var("ADMIN_TOKEN").map_err(|e| Error::Env("ADMIN_TOKEN", e))?;
+            if token.is_empty() {
+                return Err(Error::EmptyAdminToken);
+            }
+            Some(AdminGrpcConfig {
+                port: port.parse().map_err(Error::ParseInt)?,
+                token,
+            })
+        }
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("ADMIN_GRPC_PORT", e)),
+    };
+
+    #[cfg(feature = "rest")]
+    let rest_port = std::env::var("REST_PORT")
+        .map_err(|e| Error::Env("REST_PORT", e))?
+        .parse()
+        .map_err(Error::ParseInt)?;
 
     #[cfg(feature = "tls")]
     let tls_cert_path =
         std::env::var("TLS_CERT_PATH").map_err(|e| Error::Env("TLS_CERT_PATH", e))?;
     #[cfg(feature = "tls")]
     let tls_key_path = std::env::var("TLS_KEY_PATH").map_err(|e| Error::Env("TLS_KEY_PATH", e))?;
+    #[cfg(feature = "tls")]
+    let signer_tls = match std::env::var("SIGNER_TLS_CA_PATH") {
+        Ok(ca_path) => Some(SignerTlsPaths {
+            ca_path,
+            cert_path: std::env::var("SIGNER_TLS_CERT_PATH")
+                .map_err(|e| Error::Env("SIGNER_TLS_CERT_PATH", e))?,
+            key_path: std::env::var("SIGNER_TLS_KEY_PATH")
+                .map_err(|e| Error::Env("SIGNER_TLS_KEY_PATH", e))?,
+        }),
+        Err(VarError::NotPresent) => None,
+        Err(e) => return Err(Error::Env("SIGNER_TLS_CA_PATH", e)),
+    };
 
     Ok(EnvVariables {
         pg_url,
         signer_url,
         grpc_port,
         quote_ttl,
+        input_fee_ppk,
+        melt_fee_policy,
+        response_cache,
+        response_cache_ttl,
+        keyset_rotation_policies,
+        keyset_expiry_delay,
+        liabilities_report_interval,
+        reserves_attestation_interval,
+        min_reserve_ratio,
+        quote_sweep_interval,
+        quote_archive_delay,
+        admin_grpc,
+        #[cfg(feature = "rest")]
+        rest_port,
         #[cfg(feature = "tls")]
         tls_cert_path,
         #[cfg(feature = "tls")]
         tls_key_path,
+        #[cfg(feature = "tls")]
+        signer_tls,
     })
 }
 
@@ -46,8 +157,62 @@ pub struct EnvVariables {
     pub signer_url: String,
     pub grpc_port: u16,
     pub quote_ttl: Option<u64>,
+    /// Fee of the keysets created at startup or by a rotation, the existing ones keep theirs
+    pub input_fee_ppk: Option<u64>,
+    pub melt_fee_policy: MeltFeePolicy,
+    pub response_cache: ResponseCacheKind,
+    /// Seconds, `None` for responses cached until acknowledged
+    pub response_cache_ttl: Option<u64>,
+    pub keyset_rotation_policies: RotationPolicies,
+    /// Seconds the proofs of a retired keyset stay valid, `None` for forever
+    pub keyset_expiry_delay: Option<u64>,
+    /// Seconds between two proof of liabilities reports, `None` for the default
+    pub liabilities_report_interval: Option<u64>,
+    /// Seconds between two reserves attestations, `None` for no attestation
+    pub reserves_attestation_interval: Option<u64>,
+    /// In basis points (10000 = 100%), the reserve ratio under which an alert is raised
+    pub min_reserve_ratio: u64,
+    /// Seconds between two sweeps of the expired quotes, `None` for the default
+    pub quote_sweep_interval: Option<u64>,
+    /// Seconds a quote stays expired before being archived, `None` for the default
+    pub quote_archive_delay: Option<u64>,
+    /// Set to serve the admin service
+    pub admin_grpc: Option<AdminGrpcConfig>,
+    #[cfg(feature = "rest")]
+    pub rest_port: u16,
     #[cfg(feature = "tls")]
     pub tls_cert_path: String,
     #[cfg(feature = "tls")]
     pub tls_key_path: String,
+    /// Set to authenticate the signer and be authenticated by it
+    #[cfg(feature = "tls")]
+    pub signer_tls: Option<SignerTlsPaths>,
+}
+
+/// Where and to whom the admin service is served
+pub struct AdminGrpcConfig {
+    pub port: u16,
+    /// Callers must present it as `authorization: Bearer <token>`
+    pub token: String,
+}
+
+// The env variables get logged, the token must not
+impl std::fmt::Debug for AdminGrpcConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminGrpcConfig")
+            .field("port", &self.port)
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Mutual TLS with the signer
+#[cfg(feature = "tls")]
+#[derive(Debug)]
+pub struct SignerTlsPaths {
+    /// CA the signer certificate is verified against
+    pub ca_path: String,
+    /// Certificate the node presents to the signer
+    pub cert_path: String,
+    pub key_path: String,
 }
@@ -1,6 +1,6 @@
 #[cfg(feature = "keyset-rotation")]
 use node::KeysetRotationServiceServer;
-use std::{collections::HashSet, net::SocketAddr};
+use std::{collections::HashSet, net::SocketAddr, sync::Arc, time::Duration};
 use tonic::transport::Server;
 use tower::ServiceBuilder;
 use tower_otel::trace;
@@ -14,18 +14,26 @@ use sqlx::Postgres;
 use starknet_types::Unit;
 use tonic::{service::LayerExt, transport::Channel};
 
-use crate::{grpc_service::GrpcState, liquidity_sources::LiquiditySources};
+use crate::{
+    grpc_service::GrpcState,
+    liquidity_sources::LiquiditySources,
+    response_cache::{self, InMemResponseCache, PgResponseCache, ResponseCacheKind},
+};
 
 use super::{Error, env_variables::EnvVariables};
 
-#[instrument]
-pub async fn launch_tonic_server_task(
+/// How often the expired cached responses are removed
+const RESPONSE_CACHE_EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Build the state shared by all the node's services and declare its first keysets
+pub async fn init_grpc_state(
     pg_pool: sqlx::Pool<Postgres>,
     signer_client: SignerClient<trace::Grpc<Channel>>,
     liquidity_sources: LiquiditySources<Unit>,
-    env_vars: EnvVariables,
-) -> Result<(SocketAddr, impl Future<Output = Result<(), crate::Error>>), super::Error> {
-    let nuts_settings = super::nuts_settings::nuts_settings();
+    env_vars: &EnvVariables,
+) -> Result<GrpcState, super::Error> {
+    let mut nuts_settings = super::nuts_settings::nuts_settings();
+    nuts_settings.nut19.ttl = env_vars.response_cache_ttl;
     let supported_units: HashSet<_> = nuts_settings
         .nut04
         .methods
@@ -34,6 +42,21 @@ pub async fn launch_tonic_server_task(
         .chain(nuts_settings.nut05.methods.iter().map(|m| m.unit))
         .collect();
 
+    let response_cache_ttl = env_vars.response_cache_ttl.map(Duration::from_secs);
+    let response_cache: Arc<dyn response_cache::ResponseCache<_, _>> = match env_vars.response_cache
+    {
+        ResponseCacheKind::InMemory => Arc::new(InMemResponseCache::new(response_cache_ttl)),
+        ResponseCacheKind::Postgres => {
+            Arc::new(PgResponseCache::new(pg_pool.clone(), response_cache_ttl))
+        }
+    };
+    if response_cache_ttl.is_some() {
+        let _handle = response_cache::launch_eviction_task(
+            response_cache.clone(),
+            RESPONSE_CACHE_EVICTION_INTERVAL,
+        );
+    }
+
     let ttl = env_vars.quote_ttl.unwrap_or(3600);
     let grpc_state = GrpcState::new(
         pg_pool,
@@ -43,18 +66,48 @@ pub async fn launch_tonic_server_task(
             mint_ttl: ttl,
             melt_ttl: ttl,
         },
+        env_vars.melt_fee_policy,
         liquidity_sources,
+        response_cache,
+        env_vars.keyset_expiry_delay.map(Duration::from_secs),
+        env_vars.input_fee_ppk.unwrap_or(0),
     );
-    let address = format!("[::0]:{}", env_vars.grpc_port)
-        .parse()
-        .map_err(Error::InvalidGrpcAddress)?;
 
-    // TODO: take into account past keyset rotations
+    // Settings and info updated at runtime take precedence over the built-in ones
+    grpc_state.load_nuts_settings().await?;
+    grpc_state.load_node_info().await?;
+
+    if let Some(method) = grpc_state
+        .nuts
+        .read()
+        .await
+        .nut05
+        .methods
+        .iter()
+        .find(|m| !env_vars.melt_fee_policy.supports_unit(m.unit))
+    {
+        return Err(Error::MeltFeePolicyUnsupportedUnit(method.unit));
+    }
+
+    grpc_state.reconcile_keysets_with_signer().await?;
+
     // init node shared
     grpc_state
         .init_first_keysets(supported_units.into_iter(), 0, 32)
         .await?;
 
+    Ok(grpc_state)
+}
+
+#[instrument(skip(grpc_state))]
+pub async fn launch_tonic_server_task(
+    grpc_state: GrpcState,
+    env_vars: &EnvVariables,
+) -> Result<(SocketAddr, impl Future<Output = Result<(), crate::Error>>), super::Error> {
+    let address = format!("[::0]:{}", env_vars.grpc_port)
+        .parse()
+        .map_err(Error::InvalidGrpcAddress)?;
+
     // init health reporter service
     let health_service = {
         let (health_reporter, health_service) = tonic_health::server::health_reporter();
@@ -88,7 +141,7 @@ pub async fn launch_tonic_server_task(
     let tonic_future = {
         let tonic_server = build_server(
             #[cfg(feature = "tls")]
-            &env_vars,
+            env_vars,
         )
         .map_err(super::Error::BuildServer)?;
         let mut tonic_server = tonic_server.layer(tower_otel::metrics::HttpLayer::server(&meter));
@@ -6,9 +6,15 @@ pub use db::connect_to_db_and_run_migrations;
 mod signer_client;
 pub use signer_client::connect_to_signer;
 mod grpc;
-pub use grpc::launch_tonic_server_task;
+pub use grpc::{init_grpc_state, launch_tonic_server_task};
+mod admin;
+pub use admin::launch_admin_server_task;
+#[cfg(feature = "rest")]
+mod rest;
+#[cfg(feature = "rest")]
+pub use rest::launch_rest_server_task;
 
-use crate::grpc_service::InitKeysetError;
+use crate::grpc_service::{InitKeysetError, LoadNodeInfoError, LoadNutsSettingsError};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -20,14 +26,41 @@ pub enum Error {
     Env(&'static str, #[source] std::env::VarError),
     #[error(transparent)]
     ParseInt(#[from] std::num::ParseIntError),
+    #[error("`ADMIN_TOKEN` must not be empty")]
+    EmptyAdminToken,
+    #[error(transparent)]
+    MeltFeePolicy(#[from] crate::app_state::MeltFeePolicyFromStrError),
+    #[error(
+        "the melt fee policy cannot compute the fee of quotes in `{0}`, disable its melt method or use another policy"
+    )]
+    MeltFeePolicyUnsupportedUnit(starknet_types::Unit),
+    #[error(transparent)]
+    RotationPolicies(#[from] crate::keyset_rotation::RotationPoliciesFromStrError),
+    #[error(transparent)]
+    ResponseCacheKind(#[from] crate::response_cache::ResponseCacheKindFromStrError),
     #[error("Failed parse the Grpc address")]
     InvalidGrpcAddress(#[from] std::net::AddrParseError),
     #[error("failed to connect to signer")]
     SignerConnection(tonic::transport::Error),
+    #[cfg(feature = "tls")]
+    #[error("failed to read `{0}`: {1}")]
+    ReadFile(String, #[source] std::io::Error),
     #[error("failed to bind gRPC server to port: {0}")]
     Bind(#[from] std::io::Error),
+    #[error("Failed parse the admin gRPC address: {0}")]
+    InvalidAdminGrpcAddress(#[source] std::net::AddrParseError),
+    #[cfg(feature = "rest")]
+    #[error("Failed parse the REST address: {0}")]
+    InvalidRestAddress(#[source] std::net::AddrParseError),
+    #[cfg(feature = "rest")]
+    #[error("failed to bind REST server to port: {0}")]
+    BindRest(#[source] std::io::Error),
     #[error("failed to init first keysets: {0}")]
     InitKeysets(#[from] InitKeysetError),
+    #[error("failed to load nuts settings: {0}")]
+    LoadNutsSettings(#[from] LoadNutsSettingsError),
+    #[error("failed to load node info: {0}")]
+    LoadNodeInfo(#[from] LoadNodeInfoError),
     #[error("invalid signer uri: {0}")]
     Uri(#[from] http::uri::InvalidUri),
     #[error("failed to build tonic server: {0}")]
@@ -1,11 +1,17 @@
-use nuts::{Amount, nut04::MintMethodSettings, nut05::MeltMethodSettings, nut06::NutsSettings};
+use nuts::{
+    Amount,
+    nut04::MintMethodSettings,
+    nut05::MeltMethodSettings,
+    nut06::NutsSettings,
+    nut17::{self, Kind},
+};
 use starknet_types::Unit;
 
 use crate::methods::Method;
 
 // TODO: make it a compile time const
 pub(super) fn nuts_settings() -> NutsSettings<Method, Unit, serde_json::Value> {
-    NutsSettings {
+    let mut nuts_settings = NutsSettings {
         nut04: nuts::nut04::Settings {
             methods: vec![
                 MintMethodSettings {
@@ -82,6 +88,30 @@ pub(super) fn nuts_settings() -> NutsSettings<Method, Unit, serde_json::Value> {
             disabled: false,
         },
         nut09: nuts::nut06::SupportedSettings { supported: true },
+        nut10: nuts::nut06::SupportedSettings { supported: true },
+        nut11: nuts::nut06::SupportedSettings { supported: true },
+        nut12: nuts::nut06::SupportedSettings { supported: true },
+        nut14: nuts::nut06::SupportedSettings { supported: true },
+        nut17: Default::default(),
         nut19: nuts::nut19::Settings { ttl: None },
-    }
+    };
+
+    nuts_settings.nut17 = nut17::Settings {
+        supported: nuts_settings
+            .nut04
+            .methods
+            .iter()
+            .map(|m| nut17::SupportedMethod {
+                method: m.method,
+                unit: m.unit,
+                commands: vec![
+                    Kind::MintQuote(m.method).to_string(),
+                    Kind::MeltQuote(m.method).to_string(),
+                    Kind::<Method>::ProofState.to_string(),
+                ],
+            })
+            .collect(),
+    };
+
+    nuts_settings
 }
@@ -0,0 +1,28 @@
+use std::net::SocketAddr;
+
+use crate::grpc_service::GrpcState;
+
+use super::{Error, env_variables::EnvVariables};
+
+pub async fn launch_rest_server_task(
+    grpc_state: GrpcState,
+    env_vars: &EnvVariables,
+) -> Result<(SocketAddr, impl Future<Output = Result<(), crate::Error>>), Error> {
+    let address: SocketAddr = format!("[::0]:{}", env_vars.rest_port)
+        .parse()
+        .map_err(Error::InvalidRestAddress)?;
+
+    let meter = opentelemetry::global::meter(env!("CARGO_PKG_NAME"));
+    let app = crate::rest::router(grpc_state)
+        .layer(tower_otel::trace::HttpLayer::server(tracing::Level::INFO))
+        .layer(tower_otel::metrics::HttpLayer::server(&meter));
+
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(Error::BindRest)?;
+
+    tracing::info!("🚀 Starting REST server...");
+    let rest_future = async move { axum::serve(listener, app).await.map_err(crate::Error::Rest) };
+
+    Ok((address, rest_future))
+}
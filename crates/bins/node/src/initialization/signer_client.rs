@@ -4,16 +4,35 @@ use tracing::Level;
 
 use crate::app_state::SignerClient;
 
-use super::Error;
+use super::{Error, env_variables::EnvVariables};
 
-pub async fn connect_to_signer(signer_url: String) -> Result<SignerClient, Error> {
-    let channel = Channel::builder(signer_url.parse()?)
-        .connect()
-        .await
-        .map_err(Error::SignerConnection)?;
+pub async fn connect_to_signer(env_vars: &EnvVariables) -> Result<SignerClient, Error> {
+    let endpoint = Channel::builder(env_vars.signer_url.parse()?);
+    #[cfg(feature = "tls")]
+    let endpoint = match &env_vars.signer_tls {
+        Some(paths) => endpoint
+            .tls_config(client_tls_config(paths)?)
+            .map_err(Error::SignerConnection)?,
+        None => endpoint,
+    };
+
+    let channel = endpoint.connect().await.map_err(Error::SignerConnection)?;
     let channel = tower::ServiceBuilder::new()
         .layer(trace::GrpcLayer::client(Level::INFO))
         .service(channel);
 
     Ok(signer::SignerClient::new(channel))
 }
+
+#[cfg(feature = "tls")]
+fn client_tls_config(
+    paths: &super::env_variables::SignerTlsPaths,
+) -> Result<tonic::transport::ClientTlsConfig, Error> {
+    use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+    let read = |path: &String| std::fs::read(path).map_err(|e| Error::ReadFile(path.clone(), e));
+    let ca = Certificate::from_pem(read(&paths.ca_path)?);
+    let identity = Identity::from_pem(read(&paths.cert_path)?, read(&paths.key_path)?);
+
+    Ok(ClientTlsConfig::new().ca_certificate(ca).identity(identity))
+}
@@ -31,14 +31,18 @@ pub struct CachedKeysetInfo {
     active: bool,
     unit: Unit,
     max_order: u32,
+    input_fee_ppk: u64,
+    final_expiry: Option<u64>,
 }
 
 impl CachedKeysetInfo {
-    pub fn new(active: bool, unit: Unit, max_order: u32) -> Self {
+    pub fn new(active: bool, unit: Unit, max_order: u32, input_fee_ppk: u64) -> Self {
         Self {
             active,
             unit,
             max_order,
+            input_fee_ppk,
+            final_expiry: None,
         }
     }
 
@@ -53,6 +57,16 @@ impl CachedKeysetInfo {
     pub fn max_order(&self) -> u32 {
         self.max_order
     }
+
+    pub fn input_fee_ppk(&self) -> u64 {
+        self.input_fee_ppk
+    }
+
+    /// True once the keyset proofs are not accepted as inputs anymore
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.final_expiry
+            .is_some_and(|final_expiry| final_expiry <= now)
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -76,13 +90,30 @@ impl KeysetCache {
         write_lock.insert(keyset_id, keys.into_iter().collect());
     }
 
-    #[cfg(feature = "keyset-rotation")]
-    pub async fn disable_keys(&self, keyset_ids: &[KeysetId]) {
+    /// Forget the infos of these keysets, they will be read again from the db when needed
+    pub async fn invalidate_infos(&self, keyset_ids: &[KeysetId]) {
+        let mut write_lock = self.infos.write().await;
+
+        for keyset_id in keyset_ids {
+            write_lock.remove(keyset_id);
+        }
+    }
+
+    /// Forget the infos of all the keysets
+    ///
+    /// Their keys are derived from immutable data and can be kept.
+    pub async fn invalidate_all_infos(&self) {
+        self.infos.write().await.clear();
+    }
+
+    /// Mark these keysets as retired, see [`crate::keyset_rotation`]
+    pub async fn retire_keys(&self, keyset_ids: &[KeysetId], final_expiry: Option<u64>) {
         let mut write_lock = self.infos.write().await;
 
         for keyset_id in keyset_ids {
             if let Some(info) = write_lock.get_mut(keyset_id) {
                 info.active = false;
+                info.final_expiry = final_expiry;
             }
         }
     }
@@ -158,6 +189,8 @@ impl KeysetCache {
             active: db_content.active(),
             unit: db_content.unit(),
             max_order: db_content.max_order().into(),
+            input_fee_ppk: db_content.input_fee_ppk(),
+            final_expiry: db_content.final_expiry(),
         };
 
         {
@@ -0,0 +1,259 @@
+//! Keyset rotation
+//!
+//! Rotating a unit declares to the signer the keyset at the next derivation index, which
+//! replaces the active one for signing outputs. The retired keyset goes through two states:
+//! - inactive: its proofs are still accepted as inputs, so that users can swap them into the
+//!   new keyset
+//! - expired: once its `final_expiry` is reached, set from `KEYSET_EXPIRY_DELAY`, its proofs
+//!   are rejected. Without a delay, retired keysets never expire.
+//!
+//! Rotations are triggered either manually through the `KeysetRotationService`
+//! (`keyset-rotation` feature), or by the per unit [`RotationPolicy`]s checked periodically by
+//! [`launch_rotation_task`].
+
+use std::{str::FromStr, time::Duration};
+
+use db_node::keyset::KeysetInfo;
+use nuts::{
+    Amount,
+    nut01::{self, PublicKey},
+    nut02::{self, KeysetId},
+};
+use sqlx::PgConnection;
+use starknet_types::Unit;
+use tracing::{error, info};
+
+use crate::{grpc_service::GrpcState, keyset_cache::CachedKeysetInfo, utils::unix_time};
+
+#[cfg(feature = "keyset-rotation")]
+mod service;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error(transparent)]
+    Db(#[from] db_node::Error),
+    #[error("failed to declare the new keyset to the signer: {0}")]
+    Signer(#[from] tonic::Status),
+    #[error(transparent)]
+    Nut01(#[from] nut01::Error),
+    #[error(transparent)]
+    Nut02(#[from] nut02::Error),
+    #[error("another keyset rotation is in progress")]
+    Locked,
+}
+
+/// When the active keyset of a unit gets rotated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationPolicy {
+    /// Once the keyset has been active for this long
+    Age(Duration),
+    /// Once the keyset has signed outputs for this amount of unit
+    Volume(Amount),
+}
+
+impl RotationPolicy {
+    async fn is_due(
+        &self,
+        conn: &mut PgConnection,
+        keyset_id: KeysetId,
+        keyset_info: &KeysetInfo<Unit>,
+    ) -> Result<bool, Error> {
+        let is_due = match self {
+            RotationPolicy::Age(max_age) => {
+                unix_time().saturating_sub(keyset_info.activated_at()) >= max_age.as_secs()
+            }
+            RotationPolicy::Volume(max_volume) => {
+                db_node::blind_signature::sum_amount_signed_with_keyset(conn, keyset_id).await?
+                    >= *max_volume
+            }
+        };
+
+        Ok(is_due)
+    }
+}
+
+/// The rotation policies of each unit, a unit being rotated as soon as any of its policies is due
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RotationPolicies(Vec<(Unit, RotationPolicy)>);
+
+impl RotationPolicies {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    async fn is_due(
+        &self,
+        conn: &mut PgConnection,
+        keyset_id: KeysetId,
+        keyset_info: &KeysetInfo<Unit>,
+    ) -> Result<bool, Error> {
+        for (_, policy) in self.0.iter().filter(|(u, _)| *u == keyset_info.unit()) {
+            if policy.is_due(conn, keyset_id, keyset_info).await? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "invalid keyset rotation policies `{0}`, expected a comma separated list of `<unit>=age:<seconds>` or `<unit>=volume:<amount>`"
+)]
+pub struct RotationPoliciesFromStrError(String);
+
+impl FromStr for RotationPolicies {
+    type Err = RotationPoliciesFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || RotationPoliciesFromStrError(s.to_string());
+
+        s.split(',')
+            .map(|policy| {
+                let (unit, policy) = policy.trim().split_once('=').ok_or_else(err)?;
+                let unit = Unit::from_str(unit).map_err(|_| err())?;
+                let policy = match policy.split_once(':') {
+                    Some(("age", v)) => {
+                        RotationPolicy::Age(Duration::from_secs(v.parse().map_err(|_| err())?))
+                    }
+                    Some(("volume", v)) => {
+                        RotationPolicy::Volume(Amount::from(v.parse::<u64>().map_err(|_| err())?))
+                    }
+                    _ => return Err(err()),
+                };
+
+                Ok((unit, policy))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Replace the active keysets
+///
+/// Only those whose rotation is due according to `policies` are rotated, or all of them if `None`.
+/// Returns the ids of the retired keysets.
+///
+/// A single rotation runs at a time among the node instances sharing the database,
+/// [`Error::Locked`] is returned if another one is in progress.
+pub async fn rotate_keysets(
+    grpc_state: &GrpcState,
+    policies: Option<&RotationPolicies>,
+) -> Result<Vec<KeysetId>, Error> {
+    let mut tx = db_node::begin_db_tx(&grpc_state.pg_pool).await?;
+    if !db_node::keyset::try_lock_rotation(&mut tx).await? {
+        return Err(Error::Locked);
+    }
+
+    let mut keysets_to_rotate = Vec::new();
+    for (keyset_id, keyset_info) in db_node::keyset::get_active_keysets::<Unit>(&mut tx).await? {
+        let is_due = match policies {
+            Some(policies) => policies.is_due(&mut tx, keyset_id, &keyset_info).await?,
+            None => true,
+        };
+        if is_due {
+            keysets_to_rotate.push((keyset_id, keyset_info));
+        }
+    }
+    if keysets_to_rotate.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut insert_keysets_query_builder = db_node::InsertKeysetsQueryBuilder::new();
+    let mut new_keysets = Vec::with_capacity(keysets_to_rotate.len());
+    let mut retired_keyset_ids = Vec::with_capacity(keysets_to_rotate.len());
+
+    // TODO: add concurency
+    for (keyset_id, keyset_info) in keysets_to_rotate {
+        let unit = keyset_info.unit();
+        let index = keyset_info.derivation_path_index() + 1;
+        let max_order = u32::from(keyset_info.max_order());
+        // Rotating is how a new INPUT_FEE_PPK gets applied to the existing units
+        let input_fee_ppk = grpc_state.input_fee_ppk;
+
+        let response = grpc_state
+            .signer
+            .clone()
+            .declare_keyset(signer::DeclareKeysetRequest {
+                unit: unit.to_string(),
+                index,
+                max_order,
+            })
+            .await?
+            .into_inner();
+
+        let new_keyset_id = KeysetId::from_bytes(&response.keyset_id)?;
+        let keys = response
+            .keys
+            .into_iter()
+            .map(|k| -> Result<(Amount, PublicKey), Error> {
+                Ok((Amount::from(k.amount), PublicKey::from_str(&k.pubkey)?))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        insert_keysets_query_builder.add_row(new_keyset_id, unit, max_order, index, input_fee_ppk);
+        new_keysets.push((
+            new_keyset_id,
+            CachedKeysetInfo::new(true, unit, max_order, input_fee_ppk),
+            keys,
+        ));
+        retired_keyset_ids.push(keyset_id);
+    }
+
+    let final_expiry = grpc_state
+        .keyset_expiry_delay
+        .map(|delay| unix_time() + delay.as_secs());
+
+    insert_keysets_query_builder.execute(&mut tx).await?;
+    db_node::keyset::deactivate_keysets(
+        &mut tx,
+        &retired_keyset_ids
+            .iter()
+            .map(KeysetId::as_i64)
+            .collect::<Vec<_>>(),
+        final_expiry,
+    )
+    .await?;
+    tx.commit().await?;
+
+    for (keyset_id, info, keys) in new_keysets {
+        grpc_state.keyset_cache.insert_info(keyset_id, info).await;
+        grpc_state.keyset_cache.insert_keys(keyset_id, keys).await;
+    }
+    grpc_state
+        .keyset_cache
+        .retire_keys(&retired_keyset_ids, final_expiry)
+        .await;
+
+    Ok(retired_keyset_ids)
+}
+
+/// Periodically rotate the keysets whose rotation is due according to `policies`
+pub fn launch_rotation_task(
+    grpc_state: GrpcState,
+    policies: RotationPolicies,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            match rotate_keysets(&grpc_state, Some(&policies)).await {
+                Ok(retired_keyset_ids) if retired_keyset_ids.is_empty() => {}
+                Ok(retired_keyset_ids) => {
+                    let retired: Vec<String> =
+                        retired_keyset_ids.iter().map(ToString::to_string).collect();
+                    info!(name: "keyset-rotation", name = "keyset-rotation", retired = ?retired)
+                }
+                // Another instance is taking care of it
+                Err(Error::Locked) => {}
+                Err(err) => {
+                    error!(name: "keyset-rotation-error", name = "keyset-rotation-error", error = %err)
+                }
+            }
+        }
+    })
+}
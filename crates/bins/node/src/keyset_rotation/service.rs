@@ -0,0 +1,23 @@
+use node::{KeysetRotationService, RotateKeysetsRequest, RotateKeysetsResponse};
+use tonic::{Request, Response, Status};
+
+use super::Error;
+use crate::grpc_service::GrpcState;
+
+#[tonic::async_trait]
+impl KeysetRotationService for GrpcState {
+    async fn rotate_keysets(
+        &self,
+        _request: Request<RotateKeysetsRequest>,
+    ) -> Result<Response<RotateKeysetsResponse>, Status> {
+        super::rotate_keysets(self, None)
+            .await
+            .map_err(|e| match e {
+                Error::Locked => Status::aborted(e.to_string()),
+                Error::Signer(status) => status,
+                _ => Status::internal(e.to_string()),
+            })?;
+
+        Ok(Response::new(RotateKeysetsResponse {}))
+    }
+}
@@ -0,0 +1,169 @@
+//! Proof of liabilities reports
+//!
+//! Each report commits, for every keyset, to the blinded messages signed and the proofs spent
+//! so far, as the roots of two Merkle sum trees whose amounts are the totals issued and burned.
+//! They are created periodically by [`launch_report_task`], and served by the `Node` service
+//! along with the inclusion proofs users can check their own issuance and spends against.
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    time::Duration,
+};
+
+use db_node::liabilities_report::{KeysetCommitments, ReportedLeaf};
+use nuts::{Amount, nut01::PublicKey, nut02::KeysetId};
+use proof_of_liabilities::{InclusionProof, MerkleSumTree, Node};
+use sqlx::{PgConnection, PgPool};
+use tracing::{error, info};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error(transparent)]
+    Db(#[from] db_node::Error),
+    #[error(transparent)]
+    Tree(#[from] proof_of_liabilities::Error),
+    #[error("leaf {0} is missing from the tree of keyset {1}")]
+    MissingLeaf(PublicKey, KeysetId),
+    #[error("another report is being created")]
+    Locked,
+}
+
+/// A reported leaf and the proof of its inclusion
+#[derive(Debug, Clone)]
+pub struct LeafInclusionProof {
+    pub leaf: ReportedLeaf,
+    pub proof: InclusionProof,
+}
+
+fn build_tree(leaves: &[(PublicKey, Amount)]) -> Result<MerkleSumTree, Error> {
+    let leaves = leaves
+        .iter()
+        .map(|(point, amount)| Node::leaf(point, *amount))
+        .collect();
+
+    Ok(MerkleSumTree::new(leaves)?)
+}
+
+/// Create a report covering everything issued and burned since the previous one
+///
+/// Returns `None` if there was nothing new to cover.
+/// A single report is created at a time among the node instances sharing the database,
+/// [`Error::Locked`] is returned if another one is in progress.
+pub async fn create_report(pg_pool: &PgPool) -> Result<Option<i64>, Error> {
+    // Not serializable, so that tagging the rows doesn't abort the concurrent swaps and melts.
+    // Rows committed meanwhile are left for the next report.
+    let mut tx = pg_pool.begin().await?;
+    if !db_node::liabilities_report::try_lock(&mut tx).await? {
+        return Err(Error::Locked);
+    }
+
+    if !db_node::liabilities_report::has_unreported_rows(&mut tx).await? {
+        return Ok(None);
+    }
+    let report_id = db_node::liabilities_report::create(&mut tx).await?;
+
+    for keyset_id in db_node::liabilities_report::get_reported_keysets(&mut tx, report_id).await? {
+        let issued = build_tree(
+            &db_node::liabilities_report::get_issued_leaves(&mut tx, report_id, keyset_id).await?,
+        )?;
+        let burned = build_tree(
+            &db_node::liabilities_report::get_burned_leaves(&mut tx, report_id, keyset_id).await?,
+        )?;
+
+        db_node::liabilities_report::insert_keyset_commitments(
+            &mut tx,
+            report_id,
+            &KeysetCommitments {
+                keyset_id,
+                issued: issued.root().amount,
+                issued_count: issued.leaf_count(),
+                issued_root: issued.root().hash.to_byte_array(),
+                burned: burned.root().amount,
+                burned_count: burned.leaf_count(),
+                burned_root: burned.root().hash.to_byte_array(),
+            },
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(Some(report_id))
+}
+
+/// Prove the inclusion of the `blinded_messages` and `ys` covered by the report
+///
+/// Returns the proofs of the issued ones, then those of the burned ones.
+pub async fn get_inclusion_proofs(
+    conn: &mut PgConnection,
+    report_id: i64,
+    blinded_messages: &[PublicKey],
+    ys: &[PublicKey],
+) -> Result<(Vec<LeafInclusionProof>, Vec<LeafInclusionProof>), Error> {
+    let issued =
+        db_node::liabilities_report::find_issued(conn, report_id, blinded_messages).await?;
+    let mut issued_trees = HashMap::new();
+    let mut issued_proofs = Vec::with_capacity(issued.len());
+    for leaf in issued {
+        if let Entry::Vacant(entry) = issued_trees.entry(leaf.keyset_id) {
+            let leaves =
+                db_node::liabilities_report::get_issued_leaves(conn, report_id, leaf.keyset_id)
+                    .await?;
+            let tree = build_tree(&leaves)?;
+            entry.insert((leaves, tree));
+        }
+        issued_proofs.push(prove(&issued_trees[&leaf.keyset_id], leaf)?);
+    }
+
+    let burned = db_node::liabilities_report::find_burned(conn, report_id, ys).await?;
+    let mut burned_trees = HashMap::new();
+    let mut burned_proofs = Vec::with_capacity(burned.len());
+    for leaf in burned {
+        if let Entry::Vacant(entry) = burned_trees.entry(leaf.keyset_id) {
+            let leaves =
+                db_node::liabilities_report::get_burned_leaves(conn, report_id, leaf.keyset_id)
+                    .await?;
+            let tree = build_tree(&leaves)?;
+            entry.insert((leaves, tree));
+        }
+        burned_proofs.push(prove(&burned_trees[&leaf.keyset_id], leaf)?);
+    }
+
+    Ok((issued_proofs, burned_proofs))
+}
+
+fn prove(
+    (leaves, tree): &(Vec<(PublicKey, Amount)>, MerkleSumTree),
+    leaf: ReportedLeaf,
+) -> Result<LeafInclusionProof, Error> {
+    // The leaves are ordered by the db the same way bytes are
+    let proof = leaves
+        .binary_search_by(|(point, _)| point.to_bytes().cmp(&leaf.point.to_bytes()))
+        .ok()
+        .and_then(|index| tree.prove(index))
+        .ok_or(Error::MissingLeaf(leaf.point, leaf.keyset_id))?;
+
+    Ok(LeafInclusionProof { leaf, proof })
+}
+
+/// Periodically create a report, if there is something new to cover
+pub fn launch_report_task(pg_pool: PgPool, interval: Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            match create_report(&pg_pool).await {
+                Ok(None) => {}
+                Ok(Some(report_id)) => {
+                    info!(name: "liabilities-report", name = "liabilities-report", report_id)
+                }
+                // Another instance is taking care of it
+                Err(Error::Locked) => {}
+                Err(err) => {
+                    error!(name: "liabilities-report-error", name = "liabilities-report-error", error = %err)
+                }
+            }
+        }
+    })
+}
@@ -1,5 +1,6 @@
 use nuts::{nut04, nut05};
-pub use proto::bdhke::{BlindSignature, BlindedMessage, Proof};
+pub use proto::admin::admin_server::{Admin, AdminServer};
+pub use proto::bdhke::{BlindSignature, BlindSignatureDleq, BlindedMessage, Proof};
 #[cfg(feature = "keyset-rotation")]
 pub use proto::keyset_rotation::keyset_rotation_service_server::{
     KeysetRotationService, KeysetRotationServiceServer,
@@ -9,7 +10,14 @@ pub use proto::keyset_rotation::*;
 pub use proto::node::node_server::{Node, NodeServer};
 pub use proto::node::*;
 
+pub mod admin {
+    pub use super::proto::admin::*;
+}
+
 mod proto {
+    pub mod admin {
+        tonic::include_proto!("admin");
+    }
     pub mod bdhke {
         tonic::include_proto!("bdhke");
     }
@@ -35,6 +43,7 @@ impl TryFrom<MeltQuoteState> for nut05::MeltQuoteState {
             MeltQuoteState::MlqsUnpaid => Ok(nut05::MeltQuoteState::Unpaid),
             MeltQuoteState::MlqsPending => Ok(nut05::MeltQuoteState::Pending),
             MeltQuoteState::MlqsPaid => Ok(nut05::MeltQuoteState::Paid),
+            MeltQuoteState::MlqsFailed => Ok(nut05::MeltQuoteState::Failed),
         }
     }
 }
@@ -45,6 +54,7 @@ impl From<nut05::MeltQuoteState> for MeltQuoteState {
             nut05::MeltQuoteState::Unpaid => MeltQuoteState::MlqsUnpaid,
             nut05::MeltQuoteState::Pending => MeltQuoteState::MlqsPending,
             nut05::MeltQuoteState::Paid => MeltQuoteState::MlqsPaid,
+            nut05::MeltQuoteState::Failed => MeltQuoteState::MlqsFailed,
         }
     }
 }
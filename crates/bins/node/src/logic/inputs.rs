@@ -3,7 +3,14 @@ use thiserror::Error;
 use tonic::{Code, Status};
 use tonic_types::{ErrorDetails, FieldViolation, StatusExt};
 
-use nuts::{Amount, nut01::PublicKey, nut02::KeysetId};
+use nuts::{
+    Amount,
+    nut00::Proof,
+    nut01::PublicKey,
+    nut02::KeysetId,
+    nut10::{self, Kind},
+    nut11, nut14,
+};
 use signer::VerifyProofsRequest;
 use sqlx::PgConnection;
 
@@ -30,8 +37,14 @@ pub enum Error {
     KeysetCache(#[from] keyset_cache::Error),
     #[error(transparent)]
     Signer(tonic::Status),
+    #[error("keyset {0} has expired, its proofs are not accepted anymore")]
+    ExpiredKeyset(KeysetId),
     #[error("amount {1} exceeds max order {2} of keyset {0}")]
     AmountExceedsMaxOrder(KeysetId, Amount, u64),
+    #[error("spending conditions not met: {0}")]
+    SpendingConditions(#[from] nut11::Error),
+    #[error("htlc spending conditions not met: {0}")]
+    HtlcSpendingConditions(#[from] nut14::Error),
     #[error("proof issues found")]
     ProofIssues {
         invalid_crypto_indices: Vec<u32>,
@@ -47,7 +60,10 @@ impl From<Error> for Status {
             | Error::UnexpectedUnit
             | Error::TotalAmountTooBig
             | Error::TotalFeeTooBig
-            | Error::AmountExceedsMaxOrder(_, _, _) => Status::invalid_argument(value.to_string()),
+            | Error::ExpiredKeyset(_)
+            | Error::AmountExceedsMaxOrder(_, _, _)
+            | Error::SpendingConditions(_)
+            | Error::HtlcSpendingConditions(_) => Status::invalid_argument(value.to_string()),
             Error::Db(sqlx::Error::RowNotFound) => Status::not_found(value.to_string()),
             Error::Db(_) | Error::KeysetCache(_) => Status::internal(value.to_string()),
             Error::Signer(status) => status,
@@ -79,6 +95,23 @@ impl From<Error> for Status {
     }
 }
 
+/// Enforce the NUT-10 spending conditions the proof secret may carry
+///
+/// Proofs whose secret is a plain random value are not locked and always pass.
+pub fn verify_spending_conditions(proof: &Proof) -> Result<(), Error> {
+    let secret = match nut10::Secret::try_from(&proof.secret) {
+        Ok(secret) => secret,
+        Err(_) => return Ok(()),
+    };
+
+    match secret.kind {
+        Kind::P2PK => proof.verify_p2pk()?,
+        Kind::HTLC => proof.verify_htlc()?,
+    }
+
+    Ok(())
+}
+
 // signer fields is `proofs` while node uses `inputs`
 // whe have to substitute one for another
 fn rename_signer_error_details_field_name(status: tonic::Status) -> tonic::Status {
@@ -3,4 +3,5 @@ pub use outputs::{Error as OutputsError, check_outputs_allow_multiple_units, pro
 mod inputs;
 pub use inputs::{
     Error as InputsError, run_verification_queries as run_inputs_verification_queries,
+    verify_spending_conditions,
 };
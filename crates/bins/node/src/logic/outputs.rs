@@ -1,17 +1,19 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, iter};
 
 use db_node::InsertBlindSignaturesQueryBuilder;
 use num_traits::CheckedAdd;
 use nuts::{
     Amount,
     nut00::{BlindSignature, BlindedMessage},
-    nut01::PublicKey,
+    nut01::{PublicKey, SecretKey},
     nut02::KeysetId,
+    nut12::BlindSignatureDleq,
 };
 use signer::SignBlindedMessagesRequest;
 use sqlx::PgConnection;
 use starknet_types::Unit;
 use thiserror::Error;
+use tonic::{Code, Status};
 use tonic_types::{ErrorDetails, StatusExt};
 
 use crate::{
@@ -41,6 +43,35 @@ pub enum Error {
     KeysetCache(#[from] keyset_cache::Error),
 }
 
+impl From<Error> for Status {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::DuplicateOutput
+            | Error::AlreadySigned
+            | Error::AmountExceedsMaxOrder(_, _, _) => Status::with_error_details(
+                Code::InvalidArgument,
+                "invalid outputs",
+                ErrorDetails::with_bad_request_violation("outputs", value.to_string()),
+            ),
+            Error::MultipleUnits | Error::TotalAmountTooBig => {
+                Status::invalid_argument(value.to_string())
+            }
+            Error::InactiveKeyset(keyset_id) => Status::with_error_details(
+                Code::FailedPrecondition,
+                "inactive keyset",
+                ErrorDetails::with_precondition_failure_violation(
+                    "keyset.state",
+                    format!("keyset/{}", keyset_id),
+                    "Keyset must be active to perform this operation",
+                ),
+            ),
+            Error::Db(sqlx::Error::RowNotFound) => Status::not_found(value.to_string()),
+            Error::Db(_) | Error::KeysetCache(_) => Status::internal(value.to_string()),
+            Error::Signer(status) => status,
+        }
+    }
+}
+
 // signer fields is `messages` while node uses `outputs`
 // whe have to substitute one for another
 fn rename_signer_error_details_field_name(status: tonic::Status) -> tonic::Status {
@@ -122,7 +153,7 @@ pub async fn process_outputs<'a>(
 ) -> Result<(Vec<BlindSignature>, InsertBlindSignaturesQueryBuilder<'a>), Error> {
     let mut query_builder = InsertBlindSignaturesQueryBuilder::new();
 
-    let blind_signatures = signer
+    let signer_response = signer
         .sign_blinded_messages(SignBlindedMessagesRequest {
             messages: outputs
                 .iter()
@@ -135,17 +166,28 @@ pub async fn process_outputs<'a>(
         })
         .await
         .map_err(|s| Error::Signer(rename_signer_error_details_field_name(s)))?
-        .into_inner()
-        .signatures;
+        .into_inner();
 
     let blind_signatures = outputs
         .iter()
-        .zip(blind_signatures)
-        .map(|(bm, bs)| {
+        .zip(signer_response.signatures)
+        // A signer that predates NUT-12 won't return any dleq
+        .zip(
+            signer_response
+                .dleqs
+                .into_iter()
+                .map(Some)
+                .chain(iter::repeat(None)),
+        )
+        .map(|((bm, bs), dleq)| {
             let blind_signature = BlindSignature {
                 amount: bm.amount,
                 keyset_id: bm.keyset_id,
                 c: PublicKey::from_slice(&bs).expect("the signer should return valid pubkey"),
+                dleq: dleq.map(|dleq| BlindSignatureDleq {
+                    e: SecretKey::from_slice(&dleq.e).expect("the signer should return valid dleq"),
+                    s: SecretKey::from_slice(&dleq.s).expect("the signer should return valid dleq"),
+                }),
             };
 
             query_builder.add_row(bm.blinded_secret, &blind_signature);
@@ -7,26 +7,39 @@ use std::time::Duration;
 use errors::Error;
 use gauge::DbMetricsObserver;
 use initialization::{
-    connect_to_db_and_run_migrations, connect_to_signer, launch_tonic_server_task,
-    read_env_variables,
+    connect_to_db_and_run_migrations, connect_to_signer, init_grpc_state, launch_admin_server_task,
+    launch_tonic_server_task, read_env_variables,
 };
 use tracing::{info, trace};
 
+mod admin;
 mod app_state;
 mod errors;
 mod gauge;
 mod grpc_service;
 mod initialization;
 mod keyset_cache;
-#[cfg(feature = "keyset-rotation")]
 mod keyset_rotation;
+mod liabilities_report;
 mod liquidity_sources;
 mod logic;
 mod methods;
+mod notifications;
+mod quote_sweeper;
+mod reserves_attestation;
 mod response_cache;
+#[cfg(feature = "rest")]
+mod rest;
 mod routes;
 mod utils;
 
+/// How often the keyset rotation policies are checked
+const KEYSET_ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the proof of liabilities reports are created, unless `LIABILITIES_REPORT_INTERVAL` is set
+const DEFAULT_LIABILITIES_REPORT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_QUOTE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_QUOTE_ARCHIVE_DELAY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     const PKG_NAME: &str = env!("CARGO_PKG_NAME");
@@ -51,6 +64,7 @@ async fn main() -> Result<(), anyhow::Error> {
         pg_pool.clone(),
         vec![starknet_types::Unit::MilliStrk],
         gauge,
+        env_variables.min_reserve_ratio,
     );
     let _handle = tokio::spawn(gauge::run_metrics_polling(
         observer,
@@ -58,27 +72,103 @@ async fn main() -> Result<(), anyhow::Error> {
     ));
 
     // Connect to the signer service
-    let signer_client = connect_to_signer(env_variables.signer_url.clone()).await?;
+    let signer_client = connect_to_signer(&env_variables).await?;
     info!("Connected to signer server.");
 
     let liquidity_sources = liquidity_sources::LiquiditySources::init(pg_pool.clone()).await?;
 
-    // Launch tonic server task
-    let (address, grpc_future) = launch_tonic_server_task(
+    let grpc_state = init_grpc_state(
         pg_pool.clone(),
         signer_client,
         liquidity_sources,
-        env_variables,
+        &env_variables,
     )
     .await?;
 
+    // Launch the task rotating the keysets according to their policies
+    if !env_variables.keyset_rotation_policies.is_empty() {
+        let _handle = keyset_rotation::launch_rotation_task(
+            grpc_state.clone(),
+            env_variables.keyset_rotation_policies.clone(),
+            KEYSET_ROTATION_CHECK_INTERVAL,
+        );
+    }
+
+    // Launch the task creating the proof of liabilities reports
+    let _handle = liabilities_report::launch_report_task(
+        pg_pool.clone(),
+        env_variables
+            .liabilities_report_interval
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_LIABILITIES_REPORT_INTERVAL),
+    );
+
+    // Launch the task marking the expired quotes and archiving the old ones
+    let _handle = quote_sweeper::launch_sweeper_task(
+        pg_pool.clone(),
+        env_variables
+            .quote_sweep_interval
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_QUOTE_SWEEP_INTERVAL),
+        env_variables
+            .quote_archive_delay
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_QUOTE_ARCHIVE_DELAY),
+    );
+
+    // Launch the task attesting the reserves, if enabled
+    if let Some(interval) = env_variables.reserves_attestation_interval {
+        let _handle = reserves_attestation::launch_attestation_task(
+            pg_pool.clone(),
+            grpc_state.liquidity_sources.clone(),
+            Duration::from_secs(interval),
+        );
+    }
+
+    // Launch the task feeding the subscriptions and keeping the caches coherent between instances
+    let _handle = notifications::launch_notifications_task(grpc_state.clone());
+
+    // Launch tonic server task
+    let (address, grpc_future) =
+        launch_tonic_server_task(grpc_state.clone(), &env_variables).await?;
+
     trace!(name: "grpc-listen", port = address.port());
 
+    // Launch the admin server task, if enabled
+    let admin_future = match &env_variables.admin_grpc {
+        Some(admin_config) => {
+            let (admin_address, admin_future) =
+                launch_admin_server_task(grpc_state.clone(), admin_config, &env_variables).await?;
+            trace!(name: "admin-grpc-listen", port = admin_address.port());
+            futures::future::Either::Left(admin_future)
+        }
+        None => futures::future::Either::Right(std::future::pending::<Result<(), Error>>()),
+    };
+
+    // Launch REST server task
+    #[cfg(feature = "rest")]
+    let rest_future = {
+        let (rest_address, rest_future) =
+            initialization::launch_rest_server_task(grpc_state, &env_variables).await?;
+        trace!(name: "rest-listen", port = rest_address.port());
+        rest_future
+    };
+    #[cfg(not(feature = "rest"))]
+    let rest_future = std::future::pending::<Result<(), Error>>();
+
     tokio::select! {
         grpc_res = grpc_future => match grpc_res {
             Ok(()) => eprintln!("gRPC task should never return"),
             Err(err) => eprintln!("gRPC task failed: {}", err),
         },
+        rest_res = rest_future => match rest_res {
+            Ok(()) => eprintln!("REST task should never return"),
+            Err(err) => eprintln!("REST task failed: {}", err),
+        },
+        admin_res = admin_future => match admin_res {
+            Ok(()) => eprintln!("admin gRPC task should never return"),
+            Err(err) => eprintln!("admin gRPC task failed: {}", err),
+        },
         sig = tokio::signal::ctrl_c() => match sig {
             Ok(()) => info!("gRPC task terminated"),
             Err(err) => eprintln!("unable to listen for shutdown signal: {}", err)
@@ -0,0 +1,279 @@
+//! Shared state change notifications
+//!
+//! Every write of a quote or proof state through `db_node` also emits a Postgres notification.
+//! A single task listens to them and broadcasts the updated ids to the active subscriptions,
+//! whichever process (node, indexer, ...) did the update.
+//!
+//! The same task keeps the in-process caches coherent with the other node instances sharing
+//! the database, invalidating them on keyset activity, nuts settings, node info and cached
+//! response changes.
+
+use std::str::FromStr;
+
+use nuts::{
+    nut01::PublicKey,
+    nut02::KeysetId,
+    nut19::{CacheResponseKey, Route},
+};
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::grpc_service::GrpcState;
+
+/// Number of notifications a slow subscriber can lag behind before missing some
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notification {
+    MintQuote(Uuid),
+    MeltQuote(Uuid),
+    ProofState(PublicKey),
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ParseNotificationError {
+    #[error("unknown channel `{0}`")]
+    UnknownChannel(String),
+    #[error(transparent)]
+    Uuid(#[from] uuid::Error),
+    #[error(transparent)]
+    PublicKey(#[from] nuts::nut01::Error),
+    #[error(transparent)]
+    KeysetId(#[from] nuts::nut02::Error),
+    #[error(transparent)]
+    Route(#[from] nuts::nut19::PathFromStrError),
+    #[error(transparent)]
+    Int(#[from] std::num::ParseIntError),
+    #[error("invalid response cache key `{0}`")]
+    ResponseCacheKey(String),
+}
+
+/// A change, possibly made by another instance, that outdates some in-process state
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Invalidation {
+    KeysetActivity(KeysetId),
+    NutsSettings,
+    NodeInfo,
+    ResponseCacheEntry(CacheResponseKey),
+}
+
+impl Invalidation {
+    fn parse(channel: &str, payload: &str) -> Result<Option<Self>, ParseNotificationError> {
+        let invalidation = match channel {
+            db_node::notify::KEYSET_ACTIVITY_CHANNEL => {
+                Invalidation::KeysetActivity(KeysetId::try_from(payload.parse::<i64>()?)?)
+            }
+            db_node::notify::NUTS_SETTINGS_CHANNEL => Invalidation::NutsSettings,
+            db_node::notify::NODE_INFO_CHANNEL => Invalidation::NodeInfo,
+            db_node::notify::RESPONSE_CACHE_CHANNEL => {
+                let (route, request_hash) = payload
+                    .split_once(':')
+                    .ok_or_else(|| ParseNotificationError::ResponseCacheKey(payload.to_string()))?;
+                Invalidation::ResponseCacheEntry((Route::from_str(route)?, request_hash.parse()?))
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(invalidation))
+    }
+
+    async fn apply(self, grpc_state: &GrpcState) {
+        match self {
+            Invalidation::KeysetActivity(keyset_id) => {
+                grpc_state.keyset_cache.invalidate_infos(&[keyset_id]).await
+            }
+            Invalidation::NutsSettings => reload_nuts_settings(grpc_state).await,
+            Invalidation::NodeInfo => reload_node_info(grpc_state).await,
+            Invalidation::ResponseCacheEntry(key) => {
+                grpc_state.response_cache.invalidate(&key).await
+            }
+        }
+    }
+}
+
+async fn reload_nuts_settings(grpc_state: &GrpcState) {
+    if let Err(err) = grpc_state.load_nuts_settings().await {
+        error!(name: "reload-nuts-settings-error", name = "reload-nuts-settings-error", error = %err);
+    }
+}
+
+async fn reload_node_info(grpc_state: &GrpcState) {
+    if let Err(err) = grpc_state.load_node_info().await {
+        error!(name: "reload-node-info-error", name = "reload-node-info-error", error = %err);
+    }
+}
+
+impl Notification {
+    fn parse(channel: &str, payload: &str) -> Result<Self, ParseNotificationError> {
+        let notification = match channel {
+            db_node::notify::MINT_QUOTE_STATE_CHANNEL => {
+                Notification::MintQuote(Uuid::from_str(payload)?)
+            }
+            db_node::notify::MELT_QUOTE_STATE_CHANNEL => {
+                Notification::MeltQuote(Uuid::from_str(payload)?)
+            }
+            db_node::notify::PROOF_STATE_CHANNEL => {
+                Notification::ProofState(PublicKey::from_hex(payload)?)
+            }
+            _ => return Err(ParseNotificationError::UnknownChannel(channel.to_string())),
+        };
+
+        Ok(notification)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    sender: broadcast::Sender<Notification>,
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        Self { sender }
+    }
+}
+
+impl Notifier {
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.sender.subscribe()
+    }
+}
+
+/// Forward the database notifications to the [`Notifier`] subscribers and to the caches
+///
+/// Only returns on error.
+/// The listener reconnects by itself if the connection to the database is lost.
+async fn listen_to_db_notifications(grpc_state: GrpcState) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(&grpc_state.pg_pool).await?;
+    listener.listen_all(db_node::notify::ALL_CHANNELS).await?;
+
+    loop {
+        let Some(notification) = listener.try_recv().await? else {
+            // The notifications sent while reconnecting are lost, don't trust the caches anymore
+            warn!(name: "db-notifications-connection-lost", name = "db-notifications-connection-lost");
+            grpc_state.keyset_cache.invalidate_all_infos().await;
+            reload_nuts_settings(&grpc_state).await;
+            reload_node_info(&grpc_state).await;
+            continue;
+        };
+        let (channel, payload) = (notification.channel(), notification.payload());
+
+        let parsed = match Invalidation::parse(channel, payload) {
+            Ok(Some(invalidation)) => {
+                invalidation.apply(&grpc_state).await;
+                Ok(())
+            }
+            // Err only means there is currently no subscriber
+            Ok(None) => Notification::parse(channel, payload)
+                .map(|notification| _ = grpc_state.notifier.sender.send(notification)),
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = parsed {
+            warn!(
+                name: "invalid-db-notification",
+                name = "invalid-db-notification",
+                channel = channel,
+                error = %err
+            );
+        }
+    }
+}
+
+/// Spawn [`listen_to_db_notifications`], logging its error if it ever returns
+pub fn launch_notifications_task(grpc_state: GrpcState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(err) = listen_to_db_notifications(grpc_state).await {
+            error!(name: "notifications-task-error", name = "notifications-task-error", error = ?err);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_invalidations() {
+        let keyset_id = KeysetId::from_str("00456a94ab4e1c46").unwrap();
+        assert_eq!(
+            Invalidation::parse(
+                db_node::notify::KEYSET_ACTIVITY_CHANNEL,
+                &keyset_id.as_i64().to_string()
+            )
+            .unwrap(),
+            Some(Invalidation::KeysetActivity(keyset_id))
+        );
+        assert_eq!(
+            Invalidation::parse(db_node::notify::NUTS_SETTINGS_CHANNEL, "").unwrap(),
+            Some(Invalidation::NutsSettings)
+        );
+        assert_eq!(
+            Invalidation::parse(db_node::notify::NODE_INFO_CHANNEL, "").unwrap(),
+            Some(Invalidation::NodeInfo)
+        );
+        assert_eq!(
+            Invalidation::parse(db_node::notify::RESPONSE_CACHE_CHANNEL, "mint:123").unwrap(),
+            Some(Invalidation::ResponseCacheEntry((Route::Mint, 123)))
+        );
+        // Left to the subscriptions
+        assert_eq!(
+            Invalidation::parse(
+                db_node::notify::MINT_QUOTE_STATE_CHANNEL,
+                &Uuid::new_v4().to_string()
+            )
+            .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_invalid_invalidations() {
+        for (channel, payload) in [
+            (db_node::notify::KEYSET_ACTIVITY_CHANNEL, "not-a-number"),
+            (db_node::notify::RESPONSE_CACHE_CHANNEL, "mint"),
+            (db_node::notify::RESPONSE_CACHE_CHANNEL, "unknown:123"),
+            (db_node::notify::RESPONSE_CACHE_CHANNEL, "swap:-1"),
+        ] {
+            assert!(
+                Invalidation::parse(channel, payload).is_err(),
+                "{channel}: {payload}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_notifications() {
+        let quote_id = Uuid::new_v4();
+        assert_eq!(
+            Notification::parse(
+                db_node::notify::MINT_QUOTE_STATE_CHANNEL,
+                &quote_id.to_string()
+            )
+            .unwrap(),
+            Notification::MintQuote(quote_id)
+        );
+        assert_eq!(
+            Notification::parse(
+                db_node::notify::MELT_QUOTE_STATE_CHANNEL,
+                &quote_id.to_string()
+            )
+            .unwrap(),
+            Notification::MeltQuote(quote_id)
+        );
+        let y = PublicKey::from_hex(
+            "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2",
+        )
+        .unwrap();
+        assert_eq!(
+            Notification::parse(db_node::notify::PROOF_STATE_CHANNEL, &y.to_hex()).unwrap(),
+            Notification::ProofState(y)
+        );
+        assert!(Notification::parse(db_node::notify::PROOF_STATE_CHANNEL, "00").is_err());
+        assert!(Notification::parse("unknown", "").is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Garbage collection of the expired quotes
+//!
+//! Quotes that expired unpaid are marked expired, then moved to the archive tables once they
+//! have been expired for long enough. Quotes that received any payment are never archived.
+//! On-chain payments received after the expiry of their quote, archived or not, are flagged,
+//! so that operators can refund the payer, or credit them manually.
+//! Their counts are reported by the [`crate::gauge`] metrics.
+
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+
+use crate::utils::unix_time;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error(transparent)]
+    Db(#[from] db_node::Error),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SweepOutcome {
+    pub expired_mint_quotes: u64,
+    pub expired_melt_quotes: u64,
+    pub archived_mint_quotes: u64,
+    pub archived_melt_quotes: u64,
+    pub late_payments: u64,
+}
+
+/// Mark the expired quotes, flag the late payments, and archive the quotes expired for longer
+/// than `archive_delay`
+pub async fn sweep(pg_pool: &PgPool, archive_delay: Duration) -> Result<SweepOutcome, Error> {
+    let mut tx = pg_pool.begin().await?;
+
+    let expired_mint_quotes = db_node::mint_quote::mark_expired(&mut tx).await?;
+    let expired_melt_quotes = db_node::melt_quote::mark_expired(&mut tx).await?;
+
+    let late_payments = db_node::mint_payment_event::flag_late_payments(&mut tx).await?;
+    for payment in &late_payments {
+        warn!(
+            name: "late-mint-payment",
+            name = "late-mint-payment",
+            quote_id = %payment.quote_id,
+            tx_hash = payment.tx_hash,
+            event_index = payment.event_index,
+            payer = payment.payer,
+            asset = payment.asset,
+            amount_low = payment.amount_low,
+            amount_high = payment.amount_high,
+        );
+    }
+
+    let expired_before = unix_time().saturating_sub(archive_delay.as_secs());
+    let archived_mint_quotes =
+        db_node::mint_quote::archive_expired(&mut tx, expired_before).await?;
+    let archived_melt_quotes =
+        db_node::melt_quote::archive_expired(&mut tx, expired_before).await?;
+
+    tx.commit().await?;
+
+    Ok(SweepOutcome {
+        expired_mint_quotes,
+        expired_melt_quotes,
+        archived_mint_quotes,
+        archived_melt_quotes,
+        late_payments: late_payments.len() as u64,
+    })
+}
+
+/// Periodically sweep the expired quotes
+pub fn launch_sweeper_task(
+    pg_pool: PgPool,
+    interval: Duration,
+    archive_delay: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            match sweep(&pg_pool, archive_delay).await {
+                Ok(outcome) if outcome == SweepOutcome::default() => {}
+                Ok(outcome) => {
+                    info!(
+                        name: "quote-sweep",
+                        name = "quote-sweep",
+                        expired_mint_quotes = outcome.expired_mint_quotes,
+                        expired_melt_quotes = outcome.expired_melt_quotes,
+                        archived_mint_quotes = outcome.archived_mint_quotes,
+                        archived_melt_quotes = outcome.archived_melt_quotes,
+                        late_payments = outcome.late_payments,
+                    )
+                }
+                Err(err) => {
+                    error!(name: "quote-sweep-error", name = "quote-sweep-error", error = %err)
+                }
+            }
+        }
+    })
+}
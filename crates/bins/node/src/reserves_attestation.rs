@@ -0,0 +1,130 @@
+//! Proof of reserves attestations
+//!
+//! Each attestation states, for every asset the node issued ecash for, the ecash in circulation
+//! and the balance of the account holding the deposits, both in the asset blockchain-native
+//! representation. It is signed with the key controlling that account, so that users can check
+//! the node holds the reserves it claims.
+//! They are created periodically by [`launch_attestation_task`], served by the `Node` service,
+//! and the reserve ratio they state is monitored by the [`crate::gauge`] metrics.
+
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
+
+use liquidity_source::{AssetReserves, LiquiditySource, ReservesInterface, attestation_hash};
+use primitive_types::U256;
+use sqlx::PgPool;
+use starknet_types::{Asset, Unit, UnitFromStrError};
+use tracing::{error, info};
+
+use crate::{liquidity_sources::LiquiditySources, methods::Method, utils::unix_time};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error(transparent)]
+    Db(#[from] db_node::Error),
+    #[error(transparent)]
+    Unit(#[from] UnitFromStrError),
+    #[error("the liabilities of asset {0} overflow")]
+    Overflow(Asset),
+    #[error("failed to get the reserves: {0}")]
+    Reserves(#[source] anyhow::Error),
+}
+
+/// Attest the current reserves and liabilities of the node
+///
+/// Returns the attestation id.
+pub async fn create_attestation(
+    pg_pool: &PgPool,
+    reserves: &impl ReservesInterface<Unit = Unit>,
+) -> Result<i64, Error> {
+    let liabilities = {
+        let mut conn = pg_pool.acquire().await?;
+        db_node::liabilities::get_by_unit(&mut conn).await?
+    };
+
+    // Several units may represent the same asset, ordered by name so that the hash is reproducible
+    let mut liabilities_by_asset: BTreeMap<String, (Asset, U256)> = BTreeMap::new();
+    for (unit, liabilities) in liabilities {
+        let unit = Unit::from_str(&unit)?;
+        let asset = unit.asset();
+        let amount = unit.convert_amount_into_u256(liabilities.outstanding());
+
+        let (_, total) = liabilities_by_asset
+            .entry(asset.to_string())
+            .or_insert((asset, U256::zero()));
+        *total = total.checked_add(amount).ok_or(Error::Overflow(asset))?;
+    }
+
+    let mut assets = Vec::with_capacity(liabilities_by_asset.len());
+    for (asset, liabilities) in liabilities_by_asset.into_values() {
+        let balance = reserves
+            .get_balance(asset)
+            .await
+            .map_err(|e| Error::Reserves(e.into()))?;
+        assets.push(AssetReserves {
+            asset: asset.to_string(),
+            liabilities,
+            reserves: balance,
+        });
+    }
+
+    let created_at = unix_time();
+    let reserves_address = reserves.reserves_address();
+    let hash = attestation_hash(created_at, &reserves_address, &assets);
+    let signature = reserves
+        .sign_attestation(hash)
+        .map_err(|e| Error::Reserves(e.into()))?;
+
+    let assets: Vec<_> = assets
+        .into_iter()
+        .map(|a| db_node::reserves_attestation::AssetAmounts {
+            asset: a.asset,
+            liabilities: a.liabilities.to_big_endian(),
+            reserves: a.reserves.to_big_endian(),
+        })
+        .collect();
+    let mut tx = pg_pool.begin().await?;
+    let attestation_id = db_node::reserves_attestation::insert(
+        &mut tx,
+        created_at,
+        &reserves_address,
+        &assets,
+        &hash,
+        &signature,
+    )
+    .await?;
+    tx.commit().await?;
+
+    Ok(attestation_id)
+}
+
+/// Periodically attest the reserves backing the ecash issued
+pub fn launch_attestation_task(
+    pg_pool: PgPool,
+    liquidity_sources: LiquiditySources<Unit>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(reserves) = liquidity_sources
+            .get_liquidity_source(Method::Starknet)
+            .map(|liquidity_source| liquidity_source.reserves())
+        else {
+            error!(name: "reserves-attestation-error", name = "reserves-attestation-error", error = "no starknet liquidity source");
+            return;
+        };
+
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            match create_attestation(&pg_pool, &reserves).await {
+                Ok(attestation_id) => {
+                    info!(name: "reserves-attestation", name = "reserves-attestation", attestation_id)
+                }
+                Err(err) => {
+                    error!(name: "reserves-attestation-error", name = "reserves-attestation-error", error = %err)
+                }
+            }
+        }
+    })
+}
@@ -1,28 +1,73 @@
 use std::{
     fmt::Debug,
+    str::FromStr,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use crate::errors;
 use dashmap::DashMap;
 use node::{MeltResponse, MintResponse, SwapResponse};
+use nuts::nut19::{CacheResponseKey, Route};
+use prost::Message;
+use sqlx::PgPool;
+use tracing::{error, info};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error("failed to decode cached response: {0}")]
+    Decode(#[from] prost::DecodeError),
+}
 
 /// A trait that defines a cache for storing and retrieving responses.
-pub trait ResponseCache<K, V> {
-    /// Retrieves a value from the cache using the specified key.
-    fn get(&self, key: &K) -> Option<V>;
+#[async_trait::async_trait]
+pub trait ResponseCache<K, V>: Debug + Send + Sync {
+    /// Retrieves a value from the cache using the specified key, unless it has expired.
+    async fn get(&self, key: &K) -> Result<Option<V>, Error>;
 
     /// Inserts a key-value pair into the cache.
-    fn insert(&self, key: K, value: V) -> Result<(), errors::Error>;
+    async fn insert(&self, key: K, value: V) -> Result<(), Error>;
 
     /// Removes a key-value pair from the cache.
-    fn remove(&self, key: &K) -> bool;
+    async fn remove(&self, key: &K) -> Result<bool, Error>;
+
+    /// Removes all the expired entries, returning how many were removed.
+    async fn evict_expired(&self) -> Result<u64, Error>;
+
+    /// Drops the copy of an entry this process may hold, after another node instance removed it.
+    ///
+    /// Nothing to do for caches shared between instances.
+    async fn invalidate(&self, _key: &K) {}
+}
+
+/// Where the responses are cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseCacheKind {
+    /// Lost when the node shuts down
+    InMemory,
+    /// Shared by every node using the same database and kept across restarts
+    #[default]
+    Postgres,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid response cache `{0}`, expected `in-memory` or `postgres`")]
+pub struct ResponseCacheKindFromStrError(String);
 
-    // TODO: persistent after shutting down
+impl FromStr for ResponseCacheKind {
+    type Err = ResponseCacheKindFromStrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "in-memory" => Ok(Self::InMemory),
+            "postgres" => Ok(Self::Postgres),
+            _ => Err(ResponseCacheKindFromStrError(s.to_string())),
+        }
+    }
 }
 
 /// An in-memory implementation of the `ResponseCache` trait with optional TTL support.
-#[allow(dead_code)]
 #[derive(Debug)]
 pub struct InMemResponseCache<K, V>
 where
@@ -45,29 +90,147 @@ where
             ttl,
         }
     }
+
+    fn is_expired(&self, created_at: &Instant) -> bool {
+        self.ttl.is_some_and(|ttl| created_at.elapsed() >= ttl)
+    }
 }
 
+#[async_trait::async_trait]
 impl<K, V> ResponseCache<K, V> for InMemResponseCache<K, V>
 where
-    K: Eq + std::hash::Hash + Debug,
-    V: Clone,
+    K: Eq + std::hash::Hash + Debug + Send + Sync,
+    V: Clone + Debug + Send + Sync,
 {
-    fn get(&self, key: &K) -> Option<V> {
-        let entry = self.store.get(key)?;
-        let (value, _created_at) = &*entry;
-        Some(value.clone())
+    async fn get(&self, key: &K) -> Result<Option<V>, Error> {
+        let Some(entry) = self.store.get(key) else {
+            return Ok(None);
+        };
+        let (value, created_at) = &*entry;
+        if self.is_expired(created_at) {
+            return Ok(None);
+        }
+
+        Ok(Some(value.clone()))
     }
 
-    fn insert(&self, key: K, value: V) -> Result<(), errors::Error> {
+    async fn insert(&self, key: K, value: V) -> Result<(), Error> {
         self.store.insert(key, (value, Instant::now()));
         Ok(())
     }
 
-    fn remove(&self, key: &K) -> bool {
-        self.store.remove(key).is_some()
+    async fn remove(&self, key: &K) -> Result<bool, Error> {
+        Ok(self.store.remove(key).is_some())
+    }
+
+    async fn invalidate(&self, key: &K) {
+        self.store.remove(key);
+    }
+
+    async fn evict_expired(&self) -> Result<u64, Error> {
+        let len_before = self.store.len();
+        self.store
+            .retain(|_, (_, created_at)| !self.is_expired(created_at));
+
+        Ok((len_before.saturating_sub(self.store.len())) as u64)
+    }
+}
+
+/// A `ResponseCache` stored in the node database, surviving restarts.
+///
+/// Responses are stored as their protobuf encoding, the route of the key telling which message it is.
+#[derive(Debug)]
+pub struct PgResponseCache {
+    pg_pool: PgPool,
+    ttl: Option<Duration>,
+}
+
+impl PgResponseCache {
+    pub fn new(pg_pool: PgPool, ttl: Option<Duration>) -> Self {
+        Self { pg_pool, ttl }
+    }
+}
+
+#[async_trait::async_trait]
+impl ResponseCache<CacheResponseKey, CachedResponse> for PgResponseCache {
+    async fn get(&self, key: &CacheResponseKey) -> Result<Option<CachedResponse>, Error> {
+        let (route, request_hash) = key;
+        let mut conn = self.pg_pool.acquire().await?;
+        let Some(bytes) =
+            db_node::response_cache::get(&mut conn, &route.to_string(), *request_hash).await?
+        else {
+            return Ok(None);
+        };
+
+        let response = match route {
+            Route::Mint => CachedResponse::Mint(MintResponse::decode(bytes.as_slice())?),
+            Route::Swap => CachedResponse::Swap(SwapResponse::decode(bytes.as_slice())?),
+            Route::Melt => CachedResponse::Melt(MeltResponse::decode(bytes.as_slice())?),
+        };
+
+        Ok(Some(response))
+    }
+
+    async fn insert(&self, key: CacheResponseKey, value: CachedResponse) -> Result<(), Error> {
+        let (route, request_hash) = key;
+        let bytes = match value {
+            CachedResponse::Mint(response) => response.encode_to_vec(),
+            CachedResponse::Swap(response) => response.encode_to_vec(),
+            CachedResponse::Melt(response) => response.encode_to_vec(),
+        };
+
+        let mut conn = self.pg_pool.acquire().await?;
+        db_node::response_cache::insert(
+            &mut conn,
+            &route.to_string(),
+            request_hash,
+            &bytes,
+            self.ttl,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheResponseKey) -> Result<bool, Error> {
+        let (route, request_hash) = key;
+        let mut conn = self.pg_pool.acquire().await?;
+        let removed =
+            db_node::response_cache::remove(&mut conn, &route.to_string(), *request_hash).await?;
+
+        Ok(removed)
+    }
+
+    async fn evict_expired(&self) -> Result<u64, Error> {
+        let mut conn = self.pg_pool.acquire().await?;
+        let removed = db_node::response_cache::delete_expired(&mut conn).await?;
+
+        Ok(removed)
     }
 }
 
+/// Periodically remove the expired entries of `cache`
+pub fn launch_eviction_task(
+    cache: Arc<dyn ResponseCache<CacheResponseKey, CachedResponse>>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        loop {
+            interval.tick().await;
+            match cache.evict_expired().await {
+                Ok(0) => {}
+                Ok(n) => {
+                    info!(name: "response-cache-eviction", name = "response-cache-eviction", evicted = n)
+                }
+                Err(err) => {
+                    error!(name: "response-cache-eviction-error", name = "response-cache-eviction-error", error = %err)
+                }
+            }
+        }
+    })
+}
+
 /// An enum representing the different types of responses that can be cached.
 #[derive(Debug, Clone)]
 pub enum CachedResponse {
@@ -78,3 +241,62 @@ pub enum CachedResponse {
     /// A response from a melt operation.
     Melt(MeltResponse),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap_response() -> CachedResponse {
+        CachedResponse::Swap(SwapResponse { signatures: vec![] })
+    }
+
+    #[tokio::test]
+    async fn in_memory_entries_expire() {
+        let cache = InMemResponseCache::new(Some(Duration::from_millis(50)));
+        let key = (Route::Swap, 1);
+        cache.insert(key.clone(), swap_response()).await.unwrap();
+        assert!(cache.get(&key).await.unwrap().is_some());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(cache.get(&key).await.unwrap().is_none());
+        cache
+            .insert((Route::Swap, 2), swap_response())
+            .await
+            .unwrap();
+        assert_eq!(cache.evict_expired().await.unwrap(), 1);
+        assert!(cache.get(&(Route::Swap, 2)).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn in_memory_entries_without_ttl_never_expire() {
+        let cache = InMemResponseCache::new(None);
+        let key = (Route::Mint, 1);
+        cache
+            .insert(
+                key.clone(),
+                CachedResponse::Mint(MintResponse { signatures: vec![] }),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(cache.evict_expired().await.unwrap(), 0);
+        assert!(cache.get(&key).await.unwrap().is_some());
+        assert!(cache.remove(&key).await.unwrap());
+        assert!(!cache.remove(&key).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn eviction_task_removes_expired_entries() {
+        let cache = Arc::new(InMemResponseCache::new(Some(Duration::from_millis(10))));
+        cache
+            .insert((Route::Swap, 1), swap_response())
+            .await
+            .unwrap();
+
+        let handle = launch_eviction_task(cache.clone(), Duration::from_millis(20));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        handle.abort();
+
+        assert!(cache.store.is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use nuts::nut00::{CashuError, InvalidField, error_code};
+use tonic::{Code, Status};
+use tonic_types::StatusExt;
+
+/// Error returned by the REST handlers
+///
+/// The route logic is shared with the gRPC service and already reports its failures as
+/// [`Status`], so we only have to translate them into an HTTP status code and a NUT-00 error body.
+#[derive(Debug)]
+pub struct Error(Status);
+
+impl From<Status> for Error {
+    fn from(value: Status) -> Self {
+        Self(value)
+    }
+}
+
+impl Error {
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self(Status::invalid_argument(message))
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self(Status::internal(message))
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status_code = match self.0.code() {
+            Code::Ok => StatusCode::OK,
+            Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+                StatusCode::BAD_REQUEST
+            }
+            Code::NotFound => StatusCode::NOT_FOUND,
+            Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+            Code::PermissionDenied => StatusCode::FORBIDDEN,
+            Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+            Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+            Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            Code::Cancelled | Code::Unknown | Code::Internal | Code::DataLoss => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+
+        (status_code, Json(to_cashu_error(&self.0))).into_response()
+    }
+}
+
+/// Translate the error details attached by the route logic into NUT error codes
+///
+/// The rejected proofs and outputs are also listed in the `fields` of the error body,
+/// as `proofs[<index>]` or `outputs[<index>]`, so that our clients can tell which to discard.
+/// Any other error has no NUT code.
+fn to_cashu_error(status: &Status) -> CashuError {
+    let error_details = status.get_error_details();
+
+    if let Some(bad_request) = error_details.bad_request() {
+        let fields: Vec<InvalidField> = bad_request
+            .field_violations
+            .iter()
+            .map(|v| InvalidField {
+                field: v.field.clone(),
+                description: v.description.clone(),
+            })
+            .collect();
+        let codes: Vec<u16> = fields.iter().map(field_error_code).collect();
+        // A spent proof is the one error a wallet cannot retry
+        let code = if codes.contains(&error_code::PROOF_ALREADY_SPENT) {
+            error_code::PROOF_ALREADY_SPENT
+        } else {
+            codes
+                .into_iter()
+                .find(|code| *code != error_code::UNKNOWN)
+                .unwrap_or(error_code::UNKNOWN)
+        };
+
+        return CashuError::new(code, status.message().to_string()).with_fields(fields);
+    }
+
+    if let Some(precondition_failure) = error_details.precondition_failure() {
+        if precondition_failure
+            .violations
+            .iter()
+            .any(|v| v.r#type == "keyset.state")
+        {
+            return CashuError::new(error_code::KEYSET_INACTIVE, status.message().to_string());
+        }
+    }
+
+    // The NUT codes and the gRPC ones overlap, the kind of failure is told by the HTTP status
+    CashuError::new(error_code::UNKNOWN, status.message().to_string())
+}
+
+fn field_error_code(field: &InvalidField) -> u16 {
+    if field.field.starts_with("proofs") || field.field.starts_with("inputs") {
+        if field.description.contains("already spent") {
+            error_code::PROOF_ALREADY_SPENT
+        } else {
+            error_code::PROOF_VERIFICATION_FAILED
+        }
+    } else if field.field.starts_with("outputs") {
+        if field.description.contains("already signed") {
+            error_code::OUTPUT_ALREADY_SIGNED
+        } else if field.description.contains("duplicated") {
+            error_code::DUPLICATE_OUTPUTS
+        } else if field.description.contains("max order") {
+            error_code::AMOUNT_OUTSIDE_LIMIT
+        } else if field.field.ends_with("keyset_id") {
+            error_code::KEYSET_UNKNOWN
+        } else {
+            error_code::UNKNOWN
+        }
+    } else {
+        error_code::UNKNOWN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic_types::{ErrorDetails, FieldViolation, StatusExt};
+
+    use super::*;
+
+    fn bad_request(violations: &[(&str, &str)]) -> Status {
+        Status::with_error_details(
+            Code::InvalidArgument,
+            "invalid request",
+            ErrorDetails::with_bad_request(
+                violations
+                    .iter()
+                    .map(|(field, description)| FieldViolation::new(*field, *description))
+                    .collect::<Vec<_>>(),
+            ),
+        )
+    }
+
+    #[test]
+    fn proof_errors() {
+        let error = to_cashu_error(&bad_request(&[
+            ("proofs[0]", "proof failed cryptographic verification"),
+            ("proofs[2]", "proof already spent"),
+        ]));
+        assert_eq!(error.code(), error_code::PROOF_ALREADY_SPENT);
+        assert_eq!(error.fields().len(), 2);
+        assert_eq!(error.fields()[1].field, "proofs[2]");
+
+        let error = to_cashu_error(&bad_request(&[(
+            "inputs[1]",
+            "proof failed cryptographic verification",
+        )]));
+        assert_eq!(error.code(), error_code::PROOF_VERIFICATION_FAILED);
+    }
+
+    #[test]
+    fn output_errors() {
+        for (violation, code) in [
+            (
+                ("outputs", "blind message is already signed"),
+                error_code::OUTPUT_ALREADY_SIGNED,
+            ),
+            (
+                ("outputs", "outputs contains a duplicated element"),
+                error_code::DUPLICATE_OUTPUTS,
+            ),
+            (
+                (
+                    "outputs[0].amount",
+                    "the provided amount 64 is greater than the max order: 32",
+                ),
+                error_code::AMOUNT_OUTSIDE_LIMIT,
+            ),
+            (
+                (
+                    "outputs[3].keyset_id",
+                    "the specified keyset id '00' does not exist",
+                ),
+                error_code::KEYSET_UNKNOWN,
+            ),
+            (
+                (
+                    "outputs[1].amount",
+                    "the provided amount 3 is not a power of two",
+                ),
+                error_code::UNKNOWN,
+            ),
+        ] {
+            assert_eq!(to_cashu_error(&bad_request(&[violation])).code(), code);
+        }
+    }
+
+    #[test]
+    fn other_errors_have_no_nut_code() {
+        let error = to_cashu_error(&Status::not_found("no mint quote"));
+        assert_eq!(error.code(), error_code::UNKNOWN);
+        assert!(error.fields().is_empty());
+    }
+}
@@ -0,0 +1,396 @@
+use std::str::FromStr;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use nuts::{
+    nut00::BlindSignature,
+    nut01::{PublicKey, SecretKey},
+    nut02::{KeySet, KeySetInfo, KeysResponse, KeysetId, KeysetResponse},
+    nut03::{SwapRequest, SwapResponse},
+    nut04::{MintQuoteRequest, MintQuoteResponse, MintRequest, MintResponse},
+    nut05::{MeltQuoteRequest, MeltQuoteResponse, MeltRequest, MeltResponse},
+    nut06::NodeInfo,
+    nut07::{CheckStateRequest, CheckStateResponse},
+    nut09::{RestoreRequest, RestoreResponse},
+    nut12::BlindSignatureDleq,
+    nut19::{AcknowledgeRequest, Route, hash_melt_request, hash_mint_request, hash_swap_request},
+};
+use sqlx::pool::PoolConnection;
+use starknet_types::Unit;
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::{
+    grpc_service::{GrpcState, blind_signature_to_proto},
+    methods::Method,
+    response_cache::CachedResponse,
+    routes::limits,
+};
+
+use super::Error;
+
+async fn acquire_conn(state: &GrpcState) -> Result<PoolConnection<sqlx::Postgres>, Error> {
+    state
+        .pg_pool
+        .acquire()
+        .await
+        .map_err(|e| Error::internal(e.to_string()))
+}
+
+fn parse_method(method: &str) -> Result<Method, Error> {
+    Method::from_str(method).map_err(|e| Error::invalid_argument(e.to_string()))
+}
+
+fn parse_quote_id(quote_id: &str) -> Result<Uuid, Error> {
+    Uuid::from_str(quote_id).map_err(|e| Error::invalid_argument(e.to_string()))
+}
+
+fn keyset_keys_from_proto(keyset: node::KeysetKeys) -> Result<KeySet<Unit>, Error> {
+    Ok(KeySet {
+        id: KeysetId::from_bytes(&keyset.id).map_err(|e| Error::internal(e.to_string()))?,
+        unit: Unit::from_str(&keyset.unit).map_err(|e| Error::internal(e.to_string()))?,
+        keys: keyset
+            .keys
+            .into_iter()
+            .map(|k| -> Result<_, Error> {
+                Ok((
+                    k.amount.into(),
+                    PublicKey::from_str(&k.pubkey).map_err(|e| Error::internal(e.to_string()))?,
+                ))
+            })
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Rebuild the signatures of a response cached by the gRPC service
+fn blind_signatures_from_proto(
+    signatures: Vec<node::BlindSignature>,
+) -> Result<Vec<BlindSignature>, Error> {
+    signatures
+        .into_iter()
+        .map(|s| {
+            Ok(BlindSignature {
+                amount: s.amount.into(),
+                keyset_id: KeysetId::from_bytes(&s.keyset_id)
+                    .map_err(|e| Error::internal(e.to_string()))?,
+                c: PublicKey::from_slice(&s.blind_signature)
+                    .map_err(|e| Error::internal(e.to_string()))?,
+                dleq: s
+                    .dleq
+                    .map(|dleq| -> Result<_, Error> {
+                        Ok(BlindSignatureDleq {
+                            e: SecretKey::from_slice(&dleq.e)
+                                .map_err(|e| Error::internal(e.to_string()))?,
+                            s: SecretKey::from_slice(&dleq.s)
+                                .map_err(|e| Error::internal(e.to_string()))?,
+                        })
+                    })
+                    .transpose()?,
+            })
+        })
+        .collect()
+}
+
+pub async fn keys(State(state): State<GrpcState>) -> Result<Json<KeysResponse<Unit>>, Error> {
+    let mut conn = acquire_conn(&state).await?;
+    let keysets = state.inner_keys_no_keyset_id(&mut conn).await?;
+
+    Ok(Json(KeysResponse {
+        keysets: keysets
+            .into_iter()
+            .map(keyset_keys_from_proto)
+            .collect::<Result<_, _>>()?,
+    }))
+}
+
+pub async fn keys_for_keyset_id(
+    State(state): State<GrpcState>,
+    Path(keyset_id): Path<String>,
+) -> Result<Json<KeysResponse<Unit>>, Error> {
+    let keyset_id =
+        KeysetId::from_str(&keyset_id).map_err(|e| Error::invalid_argument(e.to_string()))?;
+
+    let mut conn = acquire_conn(&state).await?;
+    let keysets = state
+        .inner_keys_for_keyset_id(&mut conn, keyset_id.to_bytes().to_vec())
+        .await?;
+
+    Ok(Json(KeysResponse {
+        keysets: keysets
+            .into_iter()
+            .map(keyset_keys_from_proto)
+            .collect::<Result<_, _>>()?,
+    }))
+}
+
+pub async fn keysets(State(state): State<GrpcState>) -> Result<Json<KeysetResponse<Unit>>, Error> {
+    let mut conn = acquire_conn(&state).await?;
+
+    let keysets = db_node::keyset::get_keysets(&mut conn)
+        .await
+        .map_err(|e| Error::internal(e.to_string()))?
+        .map(
+            |(id, unit, active, input_fee_ppk, final_expiry)| -> Result<_, Error> {
+                Ok(KeySetInfo {
+                    id: KeysetId::from_bytes(&id).map_err(|e| Error::internal(e.to_string()))?,
+                    unit: Unit::from_str(&unit).map_err(|e| Error::internal(e.to_string()))?,
+                    active,
+                    input_fee_ppk,
+                    final_expiry,
+                })
+            },
+        )
+        .collect::<Result<_, _>>()?;
+
+    Ok(Json(KeysetResponse { keysets }))
+}
+
+pub async fn swap(
+    State(state): State<GrpcState>,
+    Json(swap_request): Json<SwapRequest>,
+) -> Result<Json<SwapResponse>, Error> {
+    limits::check_inputs_count(swap_request.inputs.len())?;
+    limits::check_outputs_count(swap_request.outputs.len())?;
+
+    let cache_key = (Route::Swap, hash_swap_request(&swap_request));
+    // Try to get from cache first
+    if let Some(CachedResponse::Swap(swap_response)) = state.get_cached_response(&cache_key).await?
+    {
+        return Ok(Json(SwapResponse {
+            signatures: blind_signatures_from_proto(swap_response.signatures)?,
+        }));
+    }
+
+    let signatures = state
+        .inner_swap(&swap_request.inputs, &swap_request.outputs)
+        .await
+        .map_err(Status::from)?;
+
+    // Store in cache
+    state
+        .cache_response(
+            cache_key,
+            CachedResponse::Swap(node::SwapResponse {
+                signatures: signatures.iter().map(blind_signature_to_proto).collect(),
+            }),
+        )
+        .await?;
+
+    Ok(Json(SwapResponse { signatures }))
+}
+
+pub async fn mint_quote(
+    State(state): State<GrpcState>,
+    Path(method): Path<String>,
+    Json(mint_quote_request): Json<MintQuoteRequest<Unit>>,
+) -> Result<Json<MintQuoteResponse<Uuid>>, Error> {
+    let method = parse_method(&method)?;
+
+    let response = state
+        .inner_mint_quote(method, mint_quote_request.amount, mint_quote_request.unit)
+        .await
+        .map_err(Status::from)?;
+
+    Ok(Json(response))
+}
+
+pub async fn mint_quote_state(
+    State(state): State<GrpcState>,
+    Path((method, quote_id)): Path<(String, String)>,
+) -> Result<Json<MintQuoteResponse<Uuid>>, Error> {
+    let method = parse_method(&method)?;
+    let quote_id = parse_quote_id(&quote_id)?;
+
+    match state
+        .inner_mint_quote_state(method, quote_id)
+        .await
+        .map_err(Status::from)?
+    {
+        Some(response) => Ok(Json(response)),
+        None => Err(Status::not_found(format!("no mint quote with id {}", quote_id)).into()),
+    }
+}
+
+pub async fn mint(
+    State(state): State<GrpcState>,
+    Path(method): Path<String>,
+    Json(mint_request): Json<MintRequest<String>>,
+) -> Result<Json<MintResponse>, Error> {
+    limits::check_outputs_count(mint_request.outputs.len())?;
+
+    let cache_key = (Route::Mint, hash_mint_request(&mint_request));
+    // Try to get from cache first
+    if let Some(CachedResponse::Mint(mint_response)) = state.get_cached_response(&cache_key).await?
+    {
+        return Ok(Json(MintResponse {
+            signatures: blind_signatures_from_proto(mint_response.signatures)?,
+        }));
+    }
+
+    let method = parse_method(&method)?;
+    let quote_id = parse_quote_id(&mint_request.quote)?;
+
+    let signatures = state
+        .inner_mint(method, quote_id, &mint_request.outputs)
+        .await
+        .map_err(Status::from)?;
+
+    // Store in cache
+    state
+        .cache_response(
+            cache_key,
+            CachedResponse::Mint(node::MintResponse {
+                signatures: signatures.iter().map(blind_signature_to_proto).collect(),
+            }),
+        )
+        .await?;
+
+    Ok(Json(MintResponse { signatures }))
+}
+
+pub async fn melt_quote(
+    State(state): State<GrpcState>,
+    Path(method): Path<String>,
+    Json(melt_quote_request): Json<MeltQuoteRequest<Unit>>,
+) -> Result<Json<MeltQuoteResponse<Uuid, Unit>>, Error> {
+    let method = parse_method(&method)?;
+
+    let response = state
+        .inner_melt_quote(method, melt_quote_request.unit, melt_quote_request.request)
+        .await
+        .map_err(Status::from)?;
+
+    Ok(Json(response))
+}
+
+pub async fn melt_quote_state(
+    State(state): State<GrpcState>,
+    Path((method, quote_id)): Path<(String, String)>,
+) -> Result<Json<MeltQuoteResponse<Uuid, Unit>>, Error> {
+    let method = parse_method(&method)?;
+    let quote_id = parse_quote_id(&quote_id)?;
+
+    let response = state
+        .inner_melt_quote_state(method, quote_id)
+        .await
+        .map_err(Status::from)?;
+
+    Ok(Json(response))
+}
+
+pub async fn melt(
+    State(state): State<GrpcState>,
+    Path(method): Path<String>,
+    Json(melt_request): Json<MeltRequest<String>>,
+) -> Result<Json<MeltResponse>, Error> {
+    limits::check_inputs_count(melt_request.inputs.len())?;
+
+    let cache_key = (Route::Melt, hash_melt_request(&melt_request));
+    // Try to get from cache first
+    if let Some(CachedResponse::Melt(melt_response)) = state.get_cached_response(&cache_key).await?
+    {
+        let state = node::MeltQuoteState::try_from(melt_response.state)
+            .map_err(|e| Error::internal(e.to_string()))?
+            .try_into()
+            .map_err(|e: node::UnspecifiedEnum| Error::internal(e.to_string()))?;
+
+        return Ok(Json(MeltResponse {
+            state,
+            transfer_ids: Some(melt_response.transfer_ids),
+        }));
+    }
+
+    let method = parse_method(&method)?;
+    let quote_id = parse_quote_id(&melt_request.quote)?;
+
+    let response = state
+        .inner_melt(method, quote_id, &melt_request.inputs)
+        .await
+        .map_err(Status::from)?;
+
+    // Store in cache
+    state
+        .cache_response(
+            cache_key,
+            CachedResponse::Melt(node::MeltResponse {
+                state: node::MeltQuoteState::from(response.state).into(),
+                transfer_ids: response.transfer_ids.clone().unwrap_or_default(),
+            }),
+        )
+        .await?;
+
+    Ok(Json(response))
+}
+
+/// Acknowledge the client stored the response to a mint, melt or swap request
+pub async fn acknowledge(
+    State(state): State<GrpcState>,
+    Json(ack_request): Json<AcknowledgeRequest>,
+) -> Result<Json<serde_json::Value>, Error> {
+    let path =
+        Route::from_str(&ack_request.path).map_err(|_| Error::invalid_argument("Invalid path"))?;
+
+    state
+        .remove_cached_response((path, ack_request.request_hash))
+        .await?;
+
+    Ok(Json(serde_json::json!({})))
+}
+
+pub async fn check_state(
+    State(state): State<GrpcState>,
+    Json(check_state_request): Json<CheckStateRequest>,
+) -> Result<Json<CheckStateResponse>, Error> {
+    let response = state
+        .inner_check_state(check_state_request.ys)
+        .await
+        .map_err(Status::from)?;
+
+    Ok(Json(response))
+}
+
+pub async fn restore(
+    State(state): State<GrpcState>,
+    Json(restore_request): Json<RestoreRequest>,
+) -> Result<Json<RestoreResponse>, Error> {
+    limits::check_restore_outputs_count(restore_request.outputs.len())?;
+
+    let restored = state
+        .inner_restore(restore_request.outputs)
+        .await
+        .map_err(Status::from)?;
+
+    let (outputs, signatures) = restored
+        .into_iter()
+        .map(|res| {
+            (
+                nuts::nut00::BlindedMessage {
+                    amount: res.amount,
+                    keyset_id: res.keyset_id,
+                    blinded_secret: res.blinded_secret,
+                },
+                BlindSignature {
+                    amount: res.amount,
+                    keyset_id: res.keyset_id,
+                    c: res.blind_signature,
+                    dleq: res.dleq,
+                },
+            )
+        })
+        .unzip();
+
+    Ok(Json(RestoreResponse {
+        outputs,
+        signatures,
+    }))
+}
+
+pub async fn node_info(
+    State(state): State<GrpcState>,
+) -> Result<Json<NodeInfo<Method, Unit, serde_json::Value>>, Error> {
+    let node_info = state.inner_node_info().await?;
+
+    Ok(Json(node_info))
+}
@@ -0,0 +1,44 @@
+//! HTTP/JSON API
+//!
+//! Exposes the node routes using the Cashu NUTs wire format, so that standard wallets
+//! and simple scripts can reach the node without a gRPC client.
+//! All the business logic lives in [`crate::routes`] and is shared with the gRPC service.
+
+mod errors;
+mod handlers;
+mod ws;
+
+use axum::{
+    Router,
+    routing::{get, post},
+};
+
+use crate::grpc_service::GrpcState;
+
+pub use errors::Error;
+
+pub fn router(state: GrpcState) -> Router {
+    Router::new()
+        .route("/v1/keys", get(handlers::keys))
+        .route("/v1/keys/{keyset_id}", get(handlers::keys_for_keyset_id))
+        .route("/v1/keysets", get(handlers::keysets))
+        .route("/v1/swap", post(handlers::swap))
+        .route("/v1/mint/quote/{method}", post(handlers::mint_quote))
+        .route(
+            "/v1/mint/quote/{method}/{quote_id}",
+            get(handlers::mint_quote_state),
+        )
+        .route("/v1/mint/{method}", post(handlers::mint))
+        .route("/v1/melt/quote/{method}", post(handlers::melt_quote))
+        .route(
+            "/v1/melt/quote/{method}/{quote_id}",
+            get(handlers::melt_quote_state),
+        )
+        .route("/v1/melt/{method}", post(handlers::melt))
+        .route("/v1/acknowledge", post(handlers::acknowledge))
+        .route("/v1/checkstate", post(handlers::check_state))
+        .route("/v1/restore", post(handlers::restore))
+        .route("/v1/info", get(handlers::node_info))
+        .route("/v1/ws", get(ws::ws))
+        .with_state(state)
+}
@@ -0,0 +1,158 @@
+//! NUT-17 JSON-RPC over WebSocket
+//!
+//! Each subscription of a connection runs in its own task, forwarding the
+//! [`GrpcState::inner_subscribe`] stream to a single writer task.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{
+        State, WebSocketUpgrade,
+        ws::{Message, WebSocket},
+    },
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use nuts::nut17::{
+    Params, WsErrorResponse, WsMethodRequest, WsNotification, WsRequest, WsResponse, error_code,
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::warn;
+
+use crate::{grpc_service::GrpcState, methods::Method};
+
+/// Maximum number of simultaneous subscriptions on a single connection
+const MAX_SUBSCRIPTIONS: usize = 32;
+
+pub async fn ws(State(state): State<GrpcState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(state, socket))
+}
+
+async fn handle_socket(state: GrpcState, socket: WebSocket) {
+    let (mut sink, mut stream) = socket.split();
+    let (sender, mut receiver) = mpsc::channel::<String>(64);
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            if sink.send(Message::Text(message.into())).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut subscriptions: HashMap<String, JoinHandle<()>> = HashMap::new();
+    while let Some(Ok(message)) = stream.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        if let Err(response) = handle_request(&state, &text, &sender, &mut subscriptions).await {
+            let response = serde_json::to_string(&response).expect("serializable");
+            if sender.send(response).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    for subscription in subscriptions.into_values() {
+        subscription.abort();
+    }
+    writer.abort();
+}
+
+/// Handle a request, answering it on success
+async fn handle_request(
+    state: &GrpcState,
+    text: &str,
+    sender: &mpsc::Sender<String>,
+    subscriptions: &mut HashMap<String, JoinHandle<()>>,
+) -> Result<(), WsErrorResponse> {
+    let request: WsRequest<Method> = serde_json::from_str(text).map_err(|e| {
+        // Still try to give back the id of the request
+        let id = serde_json::from_str::<serde_json::Value>(text)
+            .ok()
+            .and_then(|v| v.get("id").and_then(serde_json::Value::as_u64));
+        WsErrorResponse::new(id, error_code::PARSE_ERROR, e.to_string())
+    })?;
+    let id = request.id;
+    let error = |code, message: String| WsErrorResponse::new(Some(id), code, message);
+
+    subscriptions.retain(|_, subscription| !subscription.is_finished());
+
+    let sub_id = match request.method {
+        WsMethodRequest::Subscribe(Params {
+            kind,
+            sub_id,
+            filters,
+        }) => {
+            if subscriptions.contains_key(&sub_id) {
+                return Err(error(
+                    error_code::INVALID_PARAMS,
+                    format!("subscription `{}` already exists", sub_id),
+                ));
+            }
+            if subscriptions.len() >= MAX_SUBSCRIPTIONS {
+                return Err(error(
+                    error_code::INVALID_REQUEST,
+                    format!("too many subscriptions: maximum allowed is {MAX_SUBSCRIPTIONS}"),
+                ));
+            }
+
+            let mut updates = Box::pin(
+                state
+                    .inner_subscribe(kind, filters)
+                    .map_err(|e| error(error_code::INVALID_PARAMS, e.to_string()))?,
+            );
+
+            // Answer before the subscription task starts pushing notifications
+            send(sender, &WsResponse::ok(id, sub_id.clone())).await;
+
+            let subscription_sender = sender.clone();
+            let notification_sub_id = sub_id.clone();
+            let handle = tokio::spawn(async move {
+                while let Some(update) = updates.next().await {
+                    let payload = match update {
+                        Ok(payload) => payload,
+                        Err(err) => {
+                            warn!(name: "ws-subscription-error", name = "ws-subscription-error", error = %err);
+                            break;
+                        }
+                    };
+
+                    let notification = WsNotification::new(notification_sub_id.clone(), payload);
+                    if !send(&subscription_sender, &notification).await {
+                        break;
+                    }
+                }
+            });
+            subscriptions.insert(sub_id, handle);
+
+            return Ok(());
+        }
+        WsMethodRequest::Unsubscribe(params) => match subscriptions.remove(&params.sub_id) {
+            Some(subscription) => {
+                subscription.abort();
+                params.sub_id
+            }
+            None => {
+                return Err(error(
+                    error_code::INVALID_PARAMS,
+                    format!("unknown subscription `{}`", params.sub_id),
+                ));
+            }
+        },
+    };
+
+    send(sender, &WsResponse::ok(id, sub_id)).await;
+
+    Ok(())
+}
+
+/// Returns false if the connection has been closed
+async fn send<T: serde::Serialize>(sender: &mpsc::Sender<String>, message: &T) -> bool {
+    let message = serde_json::to_string(message).expect("serializable");
+
+    sender.send(message).await.is_ok()
+}
@@ -28,6 +28,7 @@ impl GrpcState {
             id: keyset_id.to_bytes().to_vec(),
             unit: keyset_info.unit().to_string(),
             active: keyset_info.active(),
+            input_fee_ppk: keyset_info.input_fee_ppk(),
             keys: keys
                 .into_iter()
                 .map(|(a, pk)| Key {
@@ -59,6 +60,7 @@ impl GrpcState {
                 id: keyset_id.to_bytes().to_vec(),
                 unit: keyset_info.unit(),
                 active: keyset_info.active(),
+                input_fee_ppk: keyset_info.input_fee_ppk(),
                 keys: keys
                     .into_iter()
                     .map(|(a, pk)| Key {
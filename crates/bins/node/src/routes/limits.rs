@@ -0,0 +1,47 @@
+//! Bounds on the size of the requests, enforced the same way by the gRPC and the REST APIs
+
+use tonic::Status;
+
+pub const MAX_INPUTS: usize = 64;
+pub const MAX_OUTPUTS: usize = 64;
+/// Restore requests carry outputs that were never signed, wallets send them in bigger batches
+pub const MAX_RESTORE_OUTPUTS: usize = 100;
+
+pub fn check_inputs_count(count: usize) -> Result<(), Status> {
+    if count > MAX_INPUTS {
+        return Err(Status::invalid_argument(format!(
+            "Too many inputs: maximum allowed is {}",
+            MAX_INPUTS
+        )));
+    }
+    if count == 0 {
+        return Err(Status::invalid_argument("Inputs cannot be empty"));
+    }
+
+    Ok(())
+}
+
+pub fn check_outputs_count(count: usize) -> Result<(), Status> {
+    if count > MAX_OUTPUTS {
+        return Err(Status::invalid_argument(format!(
+            "Too many outputs: maximum allowed is {}",
+            MAX_OUTPUTS
+        )));
+    }
+    if count == 0 {
+        return Err(Status::invalid_argument("Outputs cannot be empty"));
+    }
+
+    Ok(())
+}
+
+pub fn check_restore_outputs_count(count: usize) -> Result<(), Status> {
+    if count > MAX_RESTORE_OUTPUTS {
+        return Err(Status::invalid_argument(format!(
+            "Too many outputs: maximum allowed is {}",
+            MAX_RESTORE_OUTPUTS
+        )));
+    }
+
+    Ok(())
+}
@@ -32,11 +32,13 @@ pub enum Error {
     QuoteAlreadyProcessed(Uuid),
     #[error("the sum of all the inputs' amount must fit in a u64")]
     TotalAmountTooBig,
+    #[error("the quote amount and its fee must fit in a u64")]
+    FeeOverflow,
     #[error(transparent)]
     Inputs(#[from] InputsError),
-    #[error("total input amount {0} is lower than the minimum required {1}")]
+    #[error("melt amount {0} is lower than the minimum required {1}")]
     AmountTooLow(Amount, Amount),
-    #[error("total input amount {0} is higher than the maximum allowed {1}")]
+    #[error("melt amount {0} is higher than the maximum allowed {1}")]
     AmountTooHigh(Amount, Amount),
     #[error(transparent)]
     InvalidPaymentRequest(serde_json::Error),
@@ -56,6 +58,7 @@ impl From<Error> for Status {
             | Error::AmountTooLow(_, _)
             | Error::AmountTooHigh(_, _)
             | Error::TotalAmountTooBig
+            | Error::FeeOverflow
             | Error::MethodNotSupported(_)
             | Error::InvalidPaymentRequest(_) => Status::invalid_argument(value.to_string()),
             Error::Inputs(error) => error.into(),
@@ -3,27 +3,33 @@ use starknet_types::Unit;
 use std::collections::HashSet;
 
 use db_node::InsertSpentProofsQueryBuilder;
-use nuts::{Amount, nut00::Proof};
+use nuts::{Amount, nut00::Proof, nut02::calculate_input_fee};
 use sqlx::PgConnection;
 
 use crate::{
     app_state::SignerClient,
     keyset_cache::KeysetCache,
-    logic::{InputsError, run_inputs_verification_queries},
+    logic::{InputsError, run_inputs_verification_queries, verify_spending_conditions},
+    utils::unix_time,
 };
 
+/// Verify the melt inputs
+///
+/// Returns their total amount along with the fee owed for spending them
 pub async fn process_melt_inputs<'a>(
     conn: &mut PgConnection,
     signer: SignerClient,
     keyset_cache: KeysetCache,
     inputs: &'a [Proof],
     expected_unit: Unit,
-) -> Result<(Amount, InsertSpentProofsQueryBuilder<'a>), InputsError> {
+) -> Result<(Amount, Amount, InsertSpentProofsQueryBuilder<'a>), InputsError> {
     let mut secrets = HashSet::new();
     let mut query_builder = InsertSpentProofsQueryBuilder::new();
     let mut total_amount = Amount::ZERO;
+    let mut total_fee_ppk: u64 = 0;
 
     let mut verify_proofs_request = Vec::with_capacity(inputs.len());
+    let now = unix_time();
 
     for proof in inputs {
         let y = proof.y().map_err(|_| InputsError::HashOnCurve)?;
@@ -32,10 +38,15 @@ pub async fn process_melt_inputs<'a>(
             Err(InputsError::DuplicateInput)?;
         }
 
+        verify_spending_conditions(proof)?;
+
         let keyset_info = keyset_cache
             .get_keyset_info(conn, proof.keyset_id)
             .await
             .map_err(InputsError::KeysetCache)?;
+        if keyset_info.is_expired(now) {
+            return Err(InputsError::ExpiredKeyset(proof.keyset_id));
+        }
 
         // Validate amount doesn't exceed max_order
         let max_order = keyset_info.max_order();
@@ -58,6 +69,9 @@ pub async fn process_melt_inputs<'a>(
         total_amount = total_amount
             .checked_add(&proof.amount)
             .ok_or(InputsError::TotalAmountTooBig)?;
+        total_fee_ppk = total_fee_ppk
+            .checked_add(keyset_info.input_fee_ppk())
+            .ok_or(InputsError::TotalFeeTooBig)?;
 
         // Append to insert query
         query_builder.add_row(&y, proof);
@@ -68,10 +82,15 @@ pub async fn process_melt_inputs<'a>(
             keyset_id: proof.keyset_id.to_bytes().to_vec(),
             secret: proof.secret.to_string(),
             unblind_signature: proof.c.to_bytes().to_vec(),
+            witness: None,
         });
     }
 
     run_inputs_verification_queries(conn, secrets, signer, verify_proofs_request).await?;
 
-    Ok((total_amount, query_builder))
+    Ok((
+        total_amount,
+        calculate_input_fee(total_fee_ppk),
+        query_builder,
+    ))
 }
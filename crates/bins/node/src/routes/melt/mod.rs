@@ -3,13 +3,15 @@ mod inputs;
 
 use inputs::process_melt_inputs;
 use liquidity_source::{LiquiditySource, WithdrawInterface};
+use num_traits::CheckedAdd;
 use nuts::Amount;
 use nuts::nut00::Proof;
-use nuts::nut05::{MeltQuoteState, MeltResponse};
+use nuts::nut05::MeltResponse;
 use starknet_types::Unit;
 use tracing::{Level, event};
 use uuid::Uuid;
 
+use crate::app_state::MeltFeePolicy;
 use crate::utils::unix_time;
 use crate::{grpc_service::GrpcState, methods::Method};
 
@@ -49,16 +51,36 @@ impl GrpcState {
             .deserialize_payment_request(&melt_payment_request)
             .map_err(|e| Error::LiquiditySource(e.into()))?;
 
-        // No fee for now
-        let fee = Amount::ZERO;
-        let total_amount = withdrawer
-            .compute_total_amount_expected(payment_request, unit, fee)
-            .map_err(|e| Error::LiquiditySource(e.into()))?;
-
         let expiry = unix_time() + self.quote_ttl.melt_ttl();
         let quote_id = Uuid::new_v4();
         let invoice_id = liquidity_source.compute_invoice_id(quote_id, expiry);
 
+        let amount = withdrawer
+            .compute_amount_expected(&payment_request, unit)
+            .map_err(|e| Error::LiquiditySource(e.into()))?;
+        if let Some(min_amount) = settings.min_amount {
+            if min_amount > amount {
+                Err(Error::AmountTooLow(amount, min_amount))?;
+            }
+        }
+        if let Some(max_amount) = settings.max_amount {
+            if max_amount < amount {
+                Err(Error::AmountTooHigh(amount, max_amount))?;
+            }
+        }
+        let fee = match self.melt_fee_policy {
+            MeltFeePolicy::Fixed(fee) => fee,
+            MeltFeePolicy::Percentage(basis_points) => u64::from(amount)
+                .checked_mul(basis_points)
+                .map(|v| Amount::from(v.div_ceil(10_000)))
+                .ok_or(Error::FeeOverflow)?,
+            MeltFeePolicy::EstimatedGas => withdrawer
+                .estimate_fee(quote_id, &payment_request, expiry, unit)
+                .await
+                .map_err(|e| Error::LiquiditySource(e.into()))?,
+        };
+        let total_amount = amount.checked_add(&fee).ok_or(Error::FeeOverflow)?;
+
         // Store the quote in database
         let mut conn = self.pg_pool.acquire().await?;
         db_node::melt_quote::insert_new(
@@ -97,7 +119,6 @@ impl GrpcState {
             .await
             .map_err(Error::TxBegin)?;
         // Get the existing quote from database
-        // TODO: keep a record of our fees somewhere
         let (unit, required_amount, _fee, state, expiry, _quote_hash, payment_request) =
             db_node::melt_quote::get_data::<Unit>(&mut tx, quote_id).await?;
 
@@ -112,7 +133,7 @@ impl GrpcState {
         }
 
         // Process and validate inputs
-        let (total_amount, insert_spent_proof_query) = process_melt_inputs(
+        let (total_amount, input_fee, mut insert_spent_proof_query) = process_melt_inputs(
             &mut tx,
             self.signer.clone(),
             self.keyset_cache.clone(),
@@ -121,17 +142,20 @@ impl GrpcState {
         )
         .await?;
 
-        // Verify the input amount matches the quote amount
+        // Verify the input amount matches the quote amount plus the inputs fee
+        let required_amount = required_amount
+            .checked_add(&input_fee)
+            .ok_or(Error::TotalAmountTooBig)?;
         if total_amount != required_amount {
             return Err(Error::InvalidAmount(total_amount, required_amount));
         }
 
-        // Mark inputs as spent
+        // Mark inputs as spent, they are returned if the payment fails
+        insert_spent_proof_query.set_melt_quote_id(quote_id);
         insert_spent_proof_query.execute(&mut tx).await?;
-        db_node::melt_quote::set_state(&mut tx, quote_id, MeltQuoteState::Pending).await?;
-        tx.commit().await?;
 
-        // Process the actual payment
+        // Register the payment along with the spending of the inputs, so that a crash can't
+        // leave one without the other
         let state = {
             // Get withdrawer and deserialize payment request
             let mut withdrawer = self
@@ -146,13 +170,14 @@ impl GrpcState {
                 .map_err(|e| Error::LiquiditySource(e.into()))?;
 
             withdrawer
-                .proceed_to_payment(quote_id, payment_request, expiry)
+                .proceed_to_payment(&mut tx, quote_id, payment_request, expiry)
                 .await
                 .map_err(|e| Error::LiquiditySource(e.into()))?
         };
 
-        // Update quote state and transfer ID
-        db_node::melt_quote::set_state(&mut conn, quote_id, state).await?;
+        // Update quote state
+        db_node::melt_quote::set_state(&mut tx, quote_id, state).await?;
+        tx.commit().await?;
 
         let meter = opentelemetry::global::meter("business");
         let n_melt_counter = meter.u64_counter("melt.operation.count").build();
@@ -15,7 +15,12 @@ pub enum Error {
 
 impl From<Error> for Status {
     fn from(value: Error) -> Self {
-        Status::invalid_argument(value.to_string())
+        match value {
+            Error::Db(db_node::Error::Sqlx(sqlx::Error::RowNotFound)) => {
+                Status::not_found("melt quote not found")
+            }
+            _ => Status::invalid_argument(value.to_string()),
+        }
     }
 }
 
@@ -47,21 +47,7 @@ impl From<Error> for Status {
                 Status::internal(error.to_string())
             }
             Error::Db(error) => Status::internal(error.to_string()),
-            Error::Outputs(error) => match error {
-                OutputsError::DuplicateOutput
-                | OutputsError::InactiveKeyset(_)
-                | OutputsError::MultipleUnits
-                | OutputsError::TotalAmountTooBig
-                | OutputsError::AlreadySigned
-                | OutputsError::AmountExceedsMaxOrder(_, _, _) => {
-                    Status::invalid_argument(error.to_string())
-                }
-                OutputsError::Db(sqlx::Error::RowNotFound) => Status::not_found(error.to_string()),
-                OutputsError::Db(_) | OutputsError::KeysetCache(_) => {
-                    Status::internal(error.to_string())
-                }
-                OutputsError::Signer(status) => status,
-            },
+            Error::Outputs(error) => error.into(),
             Error::InvalidQuoteStateAtThisPoint(_)
             | Error::OutputsAmount { .. }
             | Error::QuoteExpired => Status::deadline_exceeded(value.to_string()),
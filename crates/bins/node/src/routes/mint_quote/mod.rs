@@ -150,21 +150,24 @@ async fn create_new_mint_quote(
     .await
     .map_err(Error::Db)?;
 
-    let state = {
+    let (state, amount_paid) = {
         // If running with no backend, we immediatly set the state to paid
         #[cfg(feature = "mock")]
         {
             use futures::TryFutureExt;
 
             let new_state = MintQuoteState::Paid;
+            db_node::mint_quote::set_amount_paid(conn, quote_id, amount, None)
+                .map_err(Error::Sqlx)
+                .await?;
             db_node::mint_quote::set_state(conn, quote_id, new_state)
                 .map_err(Error::Sqlx)
                 .await?;
-            new_state
+            (new_state, amount)
         }
 
         #[cfg(all(not(feature = "mock"), feature = "starknet"))]
-        MintQuoteState::Unpaid
+        (MintQuoteState::Unpaid, Amount::ZERO)
     };
 
     Ok(MintQuoteResponse {
@@ -172,5 +175,6 @@ async fn create_new_mint_quote(
         request,
         state,
         expiry,
+        amount_paid,
     })
 }
@@ -1,9 +1,13 @@
 mod check_state;
 mod keys;
+pub mod limits;
 mod melt;
 mod melt_quote_state;
 mod mint;
 mod mint_quote;
 mod mint_quote_state;
+mod node_info;
+pub use node_info::OperatorNodeInfo;
 mod restore;
+mod subscribe;
 mod swap;
@@ -0,0 +1,82 @@
+use std::str::FromStr;
+
+use nuts::{
+    nut01::PublicKey,
+    nut06::{ContactInfo, NodeInfo, NodeVersion},
+};
+use serde::{Deserialize, Serialize};
+use signer::GetRootPubKeyRequest;
+use starknet_types::Unit;
+use tonic::{Request, Status};
+
+use crate::{grpc_service::GrpcState, methods::Method};
+
+/// The node info fields chosen by the operator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorNodeInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub description_long: Option<String>,
+    pub contact: Option<Vec<ContactInfo>>,
+    pub icon_url: Option<String>,
+    pub urls: Option<Vec<String>>,
+    pub motd: Option<String>,
+}
+
+impl Default for OperatorNodeInfo {
+    fn default() -> Self {
+        Self {
+            name: Some("Paynet Test Node".to_string()),
+            description: Some("A test node".to_string()),
+            description_long: Some("This is a longer description of the test node.".to_string()),
+            contact: Some(vec![ContactInfo {
+                method: "some_method".to_string(),
+                info: "some_info".to_string(),
+            }]),
+            icon_url: Some("http://example.com/icon.png".to_string()),
+            urls: Some(vec!["http://example.com".to_string()]),
+            motd: Some("Welcome to the node!".to_string()),
+        }
+    }
+}
+
+impl GrpcState {
+    pub async fn inner_node_info(
+        &self,
+    ) -> Result<NodeInfo<Method, Unit, serde_json::Value>, Status> {
+        let nuts_config = {
+            let nuts_read_lock = self.nuts.read().await;
+            nuts_read_lock.clone()
+        };
+        let operator_info = {
+            let node_info_read_lock = self.node_info.read().await;
+            node_info_read_lock.clone()
+        };
+        let pub_key = self
+            .signer
+            .clone()
+            .get_root_pub_key(Request::new(GetRootPubKeyRequest {}))
+            .await?
+            .into_inner()
+            .root_pubkey;
+
+        Ok(NodeInfo {
+            name: operator_info.name,
+            pubkey: Some(
+                PublicKey::from_str(&pub_key).map_err(|e| Status::internal(e.to_string()))?,
+            ),
+            version: Some(NodeVersion {
+                name: "some_name".to_string(),
+                version: "0.0.0".to_string(),
+            }),
+            description: operator_info.description,
+            description_long: operator_info.description_long,
+            contact: operator_info.contact,
+            nuts: nuts_config,
+            icon_url: operator_info.icon_url,
+            urls: operator_info.urls,
+            motd: operator_info.motd,
+            time: Some(std::time::UNIX_EPOCH.elapsed().unwrap().as_secs()),
+        })
+    }
+}
@@ -0,0 +1,223 @@
+use std::str::FromStr;
+
+use futures::Stream;
+use nuts::{
+    nut01::PublicKey,
+    nut07::ProofCheckState,
+    nut17::{Kind, NotificationPayload},
+};
+use sqlx::PgPool;
+use starknet_types::Unit;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::Status;
+use uuid::Uuid;
+
+use crate::{grpc_service::GrpcState, methods::Method, notifications::Notification};
+
+pub const MAX_FILTERS: usize = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("too many filters: maximum allowed is {MAX_FILTERS}")]
+    TooManyFilters,
+    #[error("invalid quote id `{0}`: {1}")]
+    QuoteId(String, #[source] uuid::Error),
+    #[error("invalid Y `{0}`: {1}")]
+    Y(String, #[source] nuts::nut01::Error),
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+    #[error(transparent)]
+    Db(#[from] db_node::Error),
+}
+
+impl From<Error> for Status {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::TooManyFilters | Error::QuoteId(_, _) | Error::Y(_, _) => {
+                Status::invalid_argument(value.to_string())
+            }
+            Error::Sqlx(_) | Error::Db(_) => Status::internal(value.to_string()),
+        }
+    }
+}
+
+pub type SubscriptionPayload = NotificationPayload<Uuid, Unit>;
+
+#[derive(Debug, Clone)]
+enum Filters {
+    MintQuote(Vec<Uuid>),
+    MeltQuote(Vec<Uuid>),
+    ProofState(Vec<PublicKey>),
+}
+
+impl Filters {
+    fn parse(kind: Kind<Method>, filters: Vec<String>) -> Result<Self, Error> {
+        if filters.len() > MAX_FILTERS {
+            return Err(Error::TooManyFilters);
+        }
+
+        let parse_uuids = |filters: Vec<String>| {
+            filters
+                .into_iter()
+                .map(|f| Uuid::from_str(&f).map_err(|e| Error::QuoteId(f, e)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        // There is a single method for now, so it doesn't need to be checked against the quote's one
+        let filters = match kind {
+            Kind::MintQuote(Method::Starknet) => Filters::MintQuote(parse_uuids(filters)?),
+            Kind::MeltQuote(Method::Starknet) => Filters::MeltQuote(parse_uuids(filters)?),
+            Kind::ProofState => Filters::ProofState(
+                filters
+                    .into_iter()
+                    .map(|f| PublicKey::from_hex(&f).map_err(|e| Error::Y(f, e)))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+
+        Ok(filters)
+    }
+
+    fn matches(&self, notification: &Notification) -> bool {
+        match (self, notification) {
+            (Filters::MintQuote(ids), Notification::MintQuote(id))
+            | (Filters::MeltQuote(ids), Notification::MeltQuote(id)) => ids.contains(id),
+            (Filters::ProofState(ys), Notification::ProofState(y)) => ys.contains(y),
+            _ => false,
+        }
+    }
+
+    fn notifications(&self) -> Vec<Notification> {
+        match self {
+            Filters::MintQuote(ids) => ids.iter().copied().map(Notification::MintQuote).collect(),
+            Filters::MeltQuote(ids) => ids.iter().copied().map(Notification::MeltQuote).collect(),
+            Filters::ProofState(ys) => ys.iter().copied().map(Notification::ProofState).collect(),
+        }
+    }
+}
+
+/// Read the current state of the quote or proof a notification is about
+///
+/// Returns `None` for unknown quotes
+async fn fetch_payload(
+    pg_pool: &PgPool,
+    notification: Notification,
+) -> Result<Option<SubscriptionPayload>, Error> {
+    let mut conn = pg_pool.acquire().await?;
+
+    let payload = match notification {
+        Notification::MintQuote(quote_id) => {
+            db_node::mint_quote::build_response_from_db(&mut conn, quote_id)
+                .await?
+                .map(NotificationPayload::MintQuote)
+        }
+        Notification::MeltQuote(quote_id) => {
+            match db_node::melt_quote::build_response_from_db(&mut conn, quote_id).await {
+                Ok(response) => Some(NotificationPayload::MeltQuote(response)),
+                Err(db_node::Error::Sqlx(sqlx::Error::RowNotFound)) => None,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Notification::ProofState(y) => {
+            let state = db_node::proof::get_proofs_by_ids(&mut conn, &[y])
+                .await?
+                .pop()
+                .ok_or(sqlx::Error::RowNotFound)?;
+
+            Some(NotificationPayload::ProofState(ProofCheckState {
+                y,
+                state,
+            }))
+        }
+    };
+
+    Ok(payload)
+}
+
+impl GrpcState {
+    /// Stream the state of the quotes or proofs matching `filters`
+    ///
+    /// Starts with their current state, then yields a new item each time one of them is updated.
+    /// The stream never ends by itself, it's up to the client to drop it.
+    pub fn inner_subscribe(
+        &self,
+        kind: Kind<Method>,
+        filters: Vec<String>,
+    ) -> Result<impl Stream<Item = Result<SubscriptionPayload, Error>> + Send + 'static, Error>
+    {
+        let filters = Filters::parse(kind, filters)?;
+        let pg_pool = self.pg_pool.clone();
+        // Subscribe before reading the initial states so that no update can be missed
+        let mut receiver = self.notifier.subscribe();
+
+        Ok(async_stream::try_stream! {
+            for notification in filters.notifications() {
+                if let Some(payload) = fetch_payload(&pg_pool, notification).await? {
+                    yield payload;
+                }
+            }
+
+            loop {
+                match receiver.recv().await {
+                    Ok(notification) => {
+                        if filters.matches(&notification) {
+                            if let Some(payload) = fetch_payload(&pg_pool, notification).await? {
+                                yield payload;
+                            }
+                        }
+                    }
+                    // Some updates were dropped, send the current state of everything again
+                    Err(RecvError::Lagged(_)) => {
+                        for notification in filters.notifications() {
+                            if let Some(payload) = fetch_payload(&pg_pool, notification).await? {
+                                yield payload;
+                            }
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nuts::nut01::SecretKey;
+
+    use super::*;
+
+    #[test]
+    fn parse_filters() {
+        let quote_id = Uuid::new_v4();
+        let filters = Filters::parse(
+            Kind::MintQuote(Method::Starknet),
+            vec![quote_id.to_string()],
+        )
+        .unwrap();
+        assert!(filters.matches(&Notification::MintQuote(quote_id)));
+        assert!(!filters.matches(&Notification::MeltQuote(quote_id)));
+        assert!(!filters.matches(&Notification::MintQuote(Uuid::new_v4())));
+
+        let y = SecretKey::generate().public_key();
+        let filters = Filters::parse(Kind::ProofState, vec![y.to_hex()]).unwrap();
+        assert!(filters.matches(&Notification::ProofState(y)));
+        assert_eq!(filters.notifications().len(), 1);
+    }
+
+    #[test]
+    fn parse_invalid_filters() {
+        assert!(matches!(
+            Filters::parse(Kind::MeltQuote(Method::Starknet), vec!["quote".to_string()]),
+            Err(Error::QuoteId(_, _))
+        ));
+        assert!(matches!(
+            Filters::parse(Kind::ProofState, vec![Uuid::new_v4().to_string()]),
+            Err(Error::Y(_, _))
+        ));
+        assert!(matches!(
+            Filters::parse(Kind::ProofState, vec![String::new(); MAX_FILTERS + 1]),
+            Err(Error::TooManyFilters)
+        ));
+    }
+}
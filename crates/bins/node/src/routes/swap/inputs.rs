@@ -2,28 +2,39 @@ use num_traits::CheckedAdd;
 use std::collections::HashSet;
 
 use db_node::InsertSpentProofsQueryBuilder;
-use nuts::{Amount, nut00::Proof};
+use nuts::{Amount, nut00::Proof, nut02::calculate_input_fee};
 use sqlx::PgConnection;
 use starknet_types::Unit;
 
 use crate::{
     app_state::SignerClient,
     keyset_cache::KeysetCache,
-    logic::{InputsError, run_inputs_verification_queries},
+    logic::{InputsError, run_inputs_verification_queries, verify_spending_conditions},
+    utils::unix_time,
 };
 
+/// Verify the swap inputs
+///
+/// Returns, for each unit, the fee owed for spending those inputs and their total amount
 pub async fn process_swap_inputs<'a>(
     conn: &mut PgConnection,
     signer: SignerClient,
     keyset_cache: KeysetCache,
     inputs: &'a [Proof],
-) -> Result<(Vec<(Unit, Amount)>, InsertSpentProofsQueryBuilder<'a>), InputsError> {
+) -> Result<
+    (
+        Vec<(Unit, Amount, Amount)>,
+        InsertSpentProofsQueryBuilder<'a>,
+    ),
+    InputsError,
+> {
     // Input process
     let mut secrets = HashSet::new();
-    let mut amounts_per_unit: Vec<(Unit, Amount)> = Vec::new();
+    let mut fee_ppk_and_amounts_per_unit: Vec<(Unit, u64, Amount)> = Vec::new();
     let mut query_builder = InsertSpentProofsQueryBuilder::new();
 
     let mut verify_proofs_request = Vec::with_capacity(inputs.len());
+    let now = unix_time();
 
     for proof in inputs {
         let y = proof.y().map_err(|_| InputsError::HashOnCurve)?;
@@ -32,7 +43,12 @@ pub async fn process_swap_inputs<'a>(
             Err(InputsError::DuplicateInput)?;
         }
 
+        verify_spending_conditions(proof)?;
+
         let keyset_info = keyset_cache.get_keyset_info(conn, proof.keyset_id).await?;
+        if keyset_info.is_expired(now) {
+            return Err(InputsError::ExpiredKeyset(proof.keyset_id));
+        }
 
         let keyset_unit = keyset_info.unit();
 
@@ -48,13 +64,20 @@ pub async fn process_swap_inputs<'a>(
             ));
         }
 
-        match amounts_per_unit.iter_mut().find(|(u, _)| *u == keyset_unit) {
-            Some((_, a)) => {
+        let input_fee_ppk = keyset_info.input_fee_ppk();
+        match fee_ppk_and_amounts_per_unit
+            .iter_mut()
+            .find(|(u, _, _)| *u == keyset_unit)
+        {
+            Some((_, f, a)) => {
+                *f = f
+                    .checked_add(input_fee_ppk)
+                    .ok_or(InputsError::TotalFeeTooBig)?;
                 *a = a
                     .checked_add(&proof.amount)
                     .ok_or(InputsError::TotalAmountTooBig)?;
             }
-            None => amounts_per_unit.push((keyset_unit, proof.amount)),
+            None => fee_ppk_and_amounts_per_unit.push((keyset_unit, input_fee_ppk, proof.amount)),
         }
 
         // Append to insert query
@@ -66,10 +89,16 @@ pub async fn process_swap_inputs<'a>(
             amount: proof.amount.into(),
             secret: proof.secret.to_string(),
             unblind_signature: proof.c.to_bytes().to_vec(),
+            witness: None,
         });
     }
 
     run_inputs_verification_queries(conn, secrets, signer, verify_proofs_request).await?;
 
-    Ok((amounts_per_unit, query_builder))
+    let fees_and_amounts_per_unit = fee_ppk_and_amounts_per_unit
+        .into_iter()
+        .map(|(u, f, a)| (u, calculate_input_fee(f), a))
+        .collect();
+
+    Ok((fees_and_amounts_per_unit, query_builder))
 }
@@ -1,6 +1,7 @@
 mod inputs;
 
 use inputs::process_swap_inputs;
+use num_traits::CheckedAdd;
 use nuts::{
     Amount,
     nut00::{BlindSignature, BlindedMessage, Proof},
@@ -8,7 +9,6 @@ use nuts::{
 use starknet_types::Unit;
 use thiserror::Error;
 use tonic::Status;
-use tonic_types::{ErrorDetails, StatusExt};
 use tracing::{Level, event};
 
 use crate::{
@@ -33,8 +33,8 @@ pub enum Error {
     // Swap specific errors
     #[error("All input units should be present as output")]
     UnbalancedUnits,
-    #[error("For unit {0}, Inputs: `{1}`, Outputs: `{2}`")]
-    TransactionUnbalanced(Unit, Amount, Amount),
+    #[error("For unit {0}, Inputs: `{1}`, Outputs: `{2}`, Fee: `{3}`")]
+    TransactionUnbalanced(Unit, Amount, Amount, Amount),
     #[error("the sum off all the outputs' amount and the fee must fit in a u64")]
     TotalOutputAndFeeTooBig,
 }
@@ -45,32 +45,10 @@ impl From<Error> for Status {
             Error::TxBegin(error) | Error::TxCommit(error) | Error::Sqlx(error) => {
                 Status::internal(error.to_string())
             }
-            Error::Outputs(error) => match error {
-                OutputsError::DuplicateOutput
-                | OutputsError::MultipleUnits
-                | OutputsError::TotalAmountTooBig
-                | OutputsError::AlreadySigned
-                | OutputsError::AmountExceedsMaxOrder(_, _, _) => {
-                    Status::invalid_argument(error.to_string())
-                }
-                OutputsError::Db(sqlx::Error::RowNotFound) => Status::not_found(error.to_string()),
-                OutputsError::Db(_) | OutputsError::KeysetCache(_) => {
-                    Status::internal(error.to_string())
-                }
-                OutputsError::Signer(status) => status,
-                OutputsError::InactiveKeyset(keyset_id) => Status::with_error_details(
-                    tonic::Code::FailedPrecondition,
-                    "inactive keyset",
-                    ErrorDetails::with_precondition_failure_violation(
-                        "keyset.state",
-                        format!("keyset/{}", keyset_id),
-                        "Keyset must be active to perform this operation",
-                    ),
-                ),
-            },
+            Error::Outputs(error) => error.into(),
             Error::Inputs(error) => error.into(),
             Error::UnbalancedUnits
-            | Error::TransactionUnbalanced(_, _, _)
+            | Error::TransactionUnbalanced(_, _, _, _)
             | Error::TotalOutputAndFeeTooBig => Status::invalid_argument(value.to_string()),
         }
     }
@@ -102,16 +80,20 @@ impl GrpcState {
 
         // Amount matching
         for (unit, output_amount) in outputs_amounts.iter() {
-            let &(_, input_amount) = input_fees_and_amount
+            let &(_, fee, input_amount) = input_fees_and_amount
                 .iter()
-                .find(|(u, _)| u == unit)
+                .find(|(u, _, _)| u == unit)
                 .ok_or(Error::UnbalancedUnits)?;
 
-            if input_amount != *output_amount {
+            let output_amount_and_fee = output_amount
+                .checked_add(&fee)
+                .ok_or(Error::TotalOutputAndFeeTooBig)?;
+            if input_amount != output_amount_and_fee {
                 Err(Error::TransactionUnbalanced(
                     *unit,
                     input_amount,
                     *output_amount,
+                    fee,
                 ))?;
             }
         }
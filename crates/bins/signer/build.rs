@@ -3,7 +3,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build_client(true)
         .build_server(true)
         .compile_protos(
-            &["../../../proto/signer.proto", "../../../proto/bdhke.proto"],
+            &[
+                "../../../proto/signer.proto",
+                "../../../proto/bdhke.proto",
+                "../../../proto/threshold.proto",
+            ],
             &["../../../proto"],
         )?;
     Ok(())
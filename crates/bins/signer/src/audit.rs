@@ -0,0 +1,264 @@
+//! Audit log of the calls creating keys or signatures
+//!
+//! One JSON object per line, appended to `AUDIT_LOG_PATH`. The same records are also emitted as
+//! `audit` tracing events, so they reach the logs pipeline even when no file is configured.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bitcoin::hex::DisplayHex;
+use serde::Serialize;
+use tonic::Status;
+
+use crate::auth::Caller;
+
+const AUDIT_LOG_PATH_ENV_VAR: &str = "AUDIT_LOG_PATH";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to open audit log `{0}`: {1}")]
+    Open(PathBuf, #[source] std::io::Error),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum AuditEvent {
+    DeclareKeyset {
+        unit: String,
+        index: u32,
+        max_order: u32,
+    },
+    SignBlindedMessages {
+        /// Sum of the requested amounts, by hex encoded keyset id
+        amounts: BTreeMap<String, u64>,
+        messages: usize,
+    },
+    /// First round of a threshold signing session, on a share signer
+    ThresholdCommit {
+        /// Sum of the requested amounts, by `unit/index` of the keyset
+        amounts: BTreeMap<String, u64>,
+        messages: usize,
+    },
+    /// Partial evaluations of proof secrets, on a share signer
+    ///
+    /// Combined, they are the signatures of these secrets, so they are recorded as such.
+    ThresholdEvaluate {
+        /// Sum of the proof amounts, by `unit/index` of the keyset
+        amounts: BTreeMap<String, u64>,
+        secrets: usize,
+    },
+}
+
+impl AuditEvent {
+    pub fn sign_blinded_messages(messages: &[signer::BlindedMessage]) -> Self {
+        let mut amounts = BTreeMap::new();
+        for message in messages {
+            let amount: &mut u64 = amounts
+                .entry(message.keyset_id.to_lower_hex_string())
+                .or_default();
+            *amount = amount.saturating_add(message.amount);
+        }
+
+        Self::SignBlindedMessages {
+            amounts,
+            messages: messages.len(),
+        }
+    }
+
+    pub fn threshold_commit(messages: &[signer::threshold::PathPoint]) -> Self {
+        Self::ThresholdCommit {
+            amounts: amounts_by_keyset(messages.iter().filter_map(|m| m.path.as_ref())),
+            messages: messages.len(),
+        }
+    }
+
+    pub fn threshold_evaluate(secrets: &[signer::threshold::PathSecret]) -> Self {
+        Self::ThresholdEvaluate {
+            amounts: amounts_by_keyset(secrets.iter().filter_map(|s| s.path.as_ref())),
+            secrets: secrets.len(),
+        }
+    }
+}
+
+fn amounts_by_keyset<'a>(
+    paths: impl Iterator<Item = &'a signer::threshold::KeyPath>,
+) -> BTreeMap<String, u64> {
+    let mut amounts = BTreeMap::new();
+    for path in paths {
+        let amount: &mut u64 = amounts
+            .entry(format!("{}/{}", path.unit, path.index))
+            .or_default();
+        *amount = amount.saturating_add(path.amount);
+    }
+
+    amounts
+}
+
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    timestamp: u64,
+    caller: &'a Caller,
+    #[serde(flatten)]
+    event: &'a AuditEvent,
+    /// `None` on success
+    error: Option<&'a str>,
+}
+
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    file: Option<Mutex<File>>,
+}
+
+impl AuditLog {
+    /// Open the file at `AUDIT_LOG_PATH` in append mode, if set
+    pub fn from_env() -> Result<Self, Error> {
+        let Ok(path) = std::env::var(AUDIT_LOG_PATH_ENV_VAR) else {
+            return Ok(Self::default());
+        };
+
+        Self::open(PathBuf::from(path))
+    }
+
+    /// Open the file at `path` in append mode
+    pub fn open(path: PathBuf) -> Result<Self, Error> {
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| Error::Open(path, e))?;
+
+        Ok(Self {
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    /// Record a call and its outcome
+    ///
+    /// Fails if the record could not be written, in which case the call result must not be
+    /// returned: nothing is signed without leaving a trace.
+    pub fn record(
+        &self,
+        caller: &Caller,
+        event: &AuditEvent,
+        outcome: Result<(), &Status>,
+    ) -> Result<(), Status> {
+        let record = AuditRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            caller,
+            event,
+            error: outcome.err().map(Status::message),
+        };
+        let line = serde_json::to_string(&record).expect("serializable");
+
+        tracing::info!(name: "audit", target: "audit", name = "audit", record = %line);
+
+        if let Some(file) = &self.file {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            writeln!(file, "{line}").map_err(|e| {
+                tracing::error!(name: "audit-log-write-error", name = "audit-log-write-error", error = %e);
+                Status::internal("failed to write the audit log")
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(path: &PathBuf) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn one_record_per_call() {
+        let path = std::env::temp_dir().join(format!("signer-audit-{}.log", rand::random::<u64>()));
+        let audit_log = AuditLog::open(path.clone()).unwrap();
+        let caller = Caller {
+            addr: Some("127.0.0.1:4242".parse().unwrap()),
+            fingerprint: None,
+        };
+        let messages = [
+            signer::BlindedMessage {
+                amount: 4,
+                keyset_id: vec![0, 1],
+                blinded_secret: vec![],
+            },
+            signer::BlindedMessage {
+                amount: 8,
+                keyset_id: vec![0, 1],
+                blinded_secret: vec![],
+            },
+        ];
+
+        audit_log
+            .record(
+                &caller,
+                &AuditEvent::sign_blinded_messages(&messages),
+                Ok(()),
+            )
+            .unwrap();
+        audit_log
+            .record(
+                &caller,
+                &AuditEvent::DeclareKeyset {
+                    unit: "strk".to_string(),
+                    index: 1,
+                    max_order: 32,
+                },
+                Err(&Status::invalid_argument("unknown unit")),
+            )
+            .unwrap();
+
+        // Appends to the existing file
+        let audit_log = AuditLog::open(path.clone()).unwrap();
+        audit_log
+            .record(
+                &caller,
+                &AuditEvent::sign_blinded_messages(&messages[..1]),
+                Ok(()),
+            )
+            .unwrap();
+
+        let records = records(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["method"], "sign_blinded_messages");
+        assert_eq!(records[0]["amounts"]["0001"], 12);
+        assert_eq!(records[0]["messages"], 2);
+        assert_eq!(records[0]["caller"]["addr"], "127.0.0.1:4242");
+        assert!(records[0]["error"].is_null());
+        assert_eq!(records[1]["method"], "declare_keyset");
+        assert_eq!(records[1]["error"], "unknown unit");
+        assert_eq!(records[2]["messages"], 1);
+    }
+
+    #[test]
+    fn without_file_only_traces() {
+        let caller = Caller {
+            addr: None,
+            fingerprint: None,
+        };
+
+        assert!(
+            AuditLog::default()
+                .record(&caller, &AuditEvent::sign_blinded_messages(&[]), Ok(()))
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Caller identification and authorization
+//!
+//! When the signer requires client certificates (see [`crate::build_server`]), callers are
+//! identified by the SHA-256 fingerprint of their certificate, and only the ones listed in
+//! `TLS_ALLOWED_CLIENTS` are served.
+
+use std::{collections::HashSet, fmt, net::SocketAddr, str::FromStr, sync::Arc};
+
+#[cfg(feature = "tls")]
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::sha256;
+use serde::Serialize;
+use tonic::{Request, Status};
+
+const ALLOWED_CLIENTS_ENV_VAR: &str = "TLS_ALLOWED_CLIENTS";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid client certificate fingerprint `{0}`, expected 32 hex encoded bytes")]
+    InvalidFingerprint(String),
+    #[cfg(not(feature = "tls"))]
+    #[error("`{ALLOWED_CLIENTS_ENV_VAR}` requires the signer to be built with the `tls` feature")]
+    TlsNotEnabled,
+    #[cfg(feature = "tls")]
+    #[error(
+        "`{ALLOWED_CLIENTS_ENV_VAR}` requires `{}` to be set",
+        crate::build_server::CLIENT_CA_PATH_ENV_VAR
+    )]
+    ClientCertificatesNotRequired,
+}
+
+/// SHA-256 fingerprint of a DER encoded client certificate
+///
+/// Parsed from hex, with or without the colons `openssl x509 -fingerprint -sha256` outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientFingerprint(sha256::Hash);
+
+impl ClientFingerprint {
+    #[cfg(feature = "tls")]
+    pub fn from_der(certificate: &[u8]) -> Self {
+        Self(sha256::Hash::hash(certificate))
+    }
+}
+
+impl fmt::Display for ClientFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for ClientFingerprint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex: String = s.trim().chars().filter(|c| *c != ':').collect();
+
+        sha256::Hash::from_str(&hex.to_lowercase())
+            .map(Self)
+            .map_err(|_| Error::InvalidFingerprint(s.to_string()))
+    }
+}
+
+impl Serialize for ClientFingerprint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Who is making a call
+#[derive(Debug, Clone, Serialize)]
+pub struct Caller {
+    pub addr: Option<SocketAddr>,
+    /// `None` if the caller didn't present a certificate
+    pub fingerprint: Option<ClientFingerprint>,
+}
+
+impl Caller {
+    fn identify<T>(request: &Request<T>) -> Self {
+        #[cfg(feature = "tls")]
+        let fingerprint = request
+            .peer_certs()
+            .and_then(|certs| certs.first().map(|cert| ClientFingerprint::from_der(cert)));
+        #[cfg(not(feature = "tls"))]
+        let fingerprint = None;
+
+        Self {
+            addr: request.remote_addr(),
+            fingerprint,
+        }
+    }
+
+    /// The caller of a request that went through [`ClientAllowList::authorize`]
+    pub fn of<T>(request: &Request<T>) -> Self {
+        request
+            .extensions()
+            .get::<Caller>()
+            .cloned()
+            .unwrap_or_else(|| Self::identify(request))
+    }
+}
+
+/// The client certificates allowed to call the signer
+///
+/// Everyone is allowed if empty.
+#[derive(Debug, Clone, Default)]
+pub struct ClientAllowList(Arc<HashSet<ClientFingerprint>>);
+
+impl ClientAllowList {
+    /// Read the comma separated fingerprints of `TLS_ALLOWED_CLIENTS`
+    pub fn from_env() -> Result<Self, Error> {
+        let Ok(allowed_clients) = std::env::var(ALLOWED_CLIENTS_ENV_VAR) else {
+            return Ok(Self::default());
+        };
+
+        #[cfg(not(feature = "tls"))]
+        {
+            let _ = allowed_clients;
+            Err(Error::TlsNotEnabled)
+        }
+        #[cfg(feature = "tls")]
+        {
+            if std::env::var(crate::build_server::CLIENT_CA_PATH_ENV_VAR).is_err() {
+                return Err(Error::ClientCertificatesNotRequired);
+            }
+
+            allowed_clients
+                .split(',')
+                .filter(|s| !s.trim().is_empty())
+                .map(ClientFingerprint::from_str)
+                .collect()
+        }
+    }
+
+    /// Interceptor rejecting the callers that are not allowed
+    ///
+    /// The [`Caller`] is stored in the request extensions, for the handlers to audit.
+    pub fn authorize(&self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let caller = Caller::identify(&request);
+        self.check(&caller)?;
+        request.extensions_mut().insert(caller);
+
+        Ok(request)
+    }
+
+    fn check(&self, caller: &Caller) -> Result<(), Status> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        match caller.fingerprint {
+            Some(fingerprint) if self.0.contains(&fingerprint) => Ok(()),
+            _ => {
+                tracing::warn!(
+                    name: "unauthorized-caller",
+                    name = "unauthorized-caller",
+                    addr = ?caller.addr,
+                    fingerprint = ?caller.fingerprint.map(|f| f.to_string()),
+                );
+                Err(Status::permission_denied(
+                    "client certificate is not allowed",
+                ))
+            }
+        }
+    }
+}
+
+impl FromIterator<ClientFingerprint> for ClientAllowList {
+    fn from_iter<I: IntoIterator<Item = ClientFingerprint>>(iter: I) -> Self {
+        Self(Arc::new(iter.into_iter().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    const FINGERPRINT: &str = "AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89:AB:CD:EF:01:23:45:67:89";
+
+    fn caller(fingerprint: Option<&str>) -> Caller {
+        Caller {
+            addr: Some("127.0.0.1:4242".parse().unwrap()),
+            fingerprint: fingerprint.map(|f| ClientFingerprint::from_str(f).unwrap()),
+        }
+    }
+
+    #[test]
+    fn parse_fingerprints() {
+        let with_colons = ClientFingerprint::from_str(FINGERPRINT).unwrap();
+        let without_colons = ClientFingerprint::from_str(&FINGERPRINT.replace(':', "")).unwrap();
+        assert_eq!(with_colons, without_colons);
+        assert_eq!(
+            with_colons.to_string(),
+            FINGERPRINT.replace(':', "").to_lowercase()
+        );
+
+        assert!(ClientFingerprint::from_str("AB:CD").is_err());
+        assert!(ClientFingerprint::from_str(&"zz".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn reject_unlisted_clients() {
+        let allow_list: ClientAllowList = [ClientFingerprint::from_str(FINGERPRINT).unwrap()]
+            .into_iter()
+            .collect();
+
+        assert!(allow_list.check(&caller(Some(FINGERPRINT))).is_ok());
+        let unlisted = "00".repeat(32);
+        assert_eq!(
+            allow_list
+                .check(&caller(Some(&unlisted)))
+                .unwrap_err()
+                .code(),
+            Code::PermissionDenied
+        );
+        assert_eq!(
+            allow_list.check(&caller(None)).unwrap_err().code(),
+            Code::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn empty_allow_list_serves_everyone() {
+        let allow_list = ClientAllowList::default();
+
+        assert!(allow_list.check(&caller(None)).is_ok());
+        assert!(allow_list.check(&caller(Some(FINGERPRINT))).is_ok());
+    }
+
+    // Without the `tls` feature, or a client certificate, requests have no fingerprint
+    #[test]
+    fn authorize_stores_the_caller() {
+        let request = ClientAllowList::default()
+            .authorize(Request::new(()))
+            .unwrap();
+        assert!(request.extensions().get::<Caller>().is_some());
+
+        let allow_list: ClientAllowList = [ClientFingerprint::from_str(FINGERPRINT).unwrap()]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            allow_list.authorize(Request::new(())).unwrap_err().code(),
+            Code::PermissionDenied
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use tonic::transport::Server;
 
+/// CA verifying the client certificates, which are required if set
+#[cfg(feature = "tls")]
+pub const CLIENT_CA_PATH_ENV_VAR: &str = "TLS_CLIENT_CA_PATH";
+
 #[cfg(not(feature = "tls"))]
 pub fn build_server() -> Result<Server, anyhow::Error> {
     tracing::info!("🚀 Starting gRPC server...");
@@ -60,7 +64,19 @@ pub fn build_server() -> Result<Server, anyhow::Error> {
     };
 
     let identity = tonic::transport::Identity::from_pem(cert, key);
-    let tls_config = tonic::transport::ServerTlsConfig::new().identity(identity);
+    let mut tls_config = tonic::transport::ServerTlsConfig::new().identity(identity);
+
+    if let Ok(client_ca_path) = std::env::var(CLIENT_CA_PATH_ENV_VAR) {
+        let client_ca = std::fs::read(&client_ca_path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to load client CA certificate {}: {}",
+                client_ca_path,
+                e
+            )
+        })?;
+        tls_config = tls_config.client_ca_root(tonic::transport::Certificate::from_pem(client_ca));
+        tracing::info!("🪪 Client certificates required, CA: {}", client_ca_path);
+    }
 
     tracing::info!("🔒 Starting gRPC server with TLS...");
     tracing::info!("📜 Certificate: {}", cert_path);
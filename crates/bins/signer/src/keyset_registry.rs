@@ -0,0 +1,140 @@
+//! Persistent record of the declared keysets
+//!
+//! The keys of a keyset are derived from the root key and the `(unit, index, max_order)` it was
+//! declared with. Storing those parameters is enough to rebuild every keyset when the signer
+//! restarts, without waiting for the node to declare them again.
+//!
+//! The registry file is authenticated with an HMAC keyed by a key derived from the root key,
+//! so that it cannot be edited to make the signer hold keysets that were never declared.
+
+use std::path::PathBuf;
+
+use bitcoin::hashes::{Hash, HashEngine, hmac, sha256};
+use nuts::nut02::KeysetId;
+use serde::{Deserialize, Serialize};
+use starknet_types::Unit;
+use tokio::sync::Mutex;
+
+const REGISTRY_PATH_ENV_VAR: &str = "KEYSET_REGISTRY_PATH";
+const DEFAULT_REGISTRY_PATH: &str = "keyset_registry.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("failed to read keyset registry `{0}`: {1}")]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("failed to write keyset registry `{0}`: {1}")]
+    Write(PathBuf, #[source] std::io::Error),
+    #[error("invalid keyset registry: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(
+        "keyset registry authentication failed, it was modified or created with another root key"
+    )]
+    InvalidMac,
+    #[error("keyset {0} is registered with another id than the one derived from the root key")]
+    KeysetIdMismatch(KeysetId),
+}
+
+/// Parameters a keyset was declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeclaredKeyset {
+    pub id: KeysetId,
+    pub unit: Unit,
+    pub index: u32,
+    pub max_order: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryFile {
+    keysets: Vec<DeclaredKeyset>,
+    mac: String,
+}
+
+#[derive(Debug)]
+pub struct KeysetRegistry {
+    path: PathBuf,
+    mac_key: [u8; 32],
+    keysets: Mutex<Vec<DeclaredKeyset>>,
+}
+
+impl KeysetRegistry {
+    /// Load the registry at `KEYSET_REGISTRY_PATH`, or start an empty one if the file doesn't exist
+    pub fn load_from_env(mac_key: [u8; 32]) -> Result<Self, Error> {
+        let path = PathBuf::from(
+            std::env::var(REGISTRY_PATH_ENV_VAR)
+                .unwrap_or_else(|_| DEFAULT_REGISTRY_PATH.to_string()),
+        );
+
+        Self::load(path, mac_key)
+    }
+
+    /// Load the registry at `path`, or start an empty one if the file doesn't exist
+    pub fn load(path: PathBuf, mac_key: [u8; 32]) -> Result<Self, Error> {
+        let keysets = match std::fs::read(&path) {
+            Ok(content) => {
+                let file: RegistryFile = serde_json::from_slice(&content)?;
+                if compute_mac(&mac_key, &file.keysets)? != file.mac {
+                    return Err(Error::InvalidMac);
+                }
+                file.keysets
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::Read(path, e)),
+        };
+
+        Ok(Self {
+            path,
+            mac_key,
+            keysets: Mutex::new(keysets),
+        })
+    }
+
+    pub async fn keysets(&self) -> Vec<DeclaredKeyset> {
+        self.keysets.lock().await.clone()
+    }
+
+    /// Record a newly declared keyset, doing nothing if it is already known
+    ///
+    /// Returns once the registry is persisted.
+    pub async fn register(&self, keyset: DeclaredKeyset) -> Result<(), Error> {
+        let mut keysets = self.keysets.lock().await;
+        if keysets.iter().any(|k| k.id == keyset.id) {
+            return Ok(());
+        }
+
+        keysets.push(keyset);
+        if let Err(e) = self.persist(&keysets) {
+            keysets.pop();
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Atomically replace the registry file
+    fn persist(&self, keysets: &[DeclaredKeyset]) -> Result<(), Error> {
+        let file = RegistryFile {
+            keysets: keysets.to_vec(),
+            mac: compute_mac(&self.mac_key, keysets)?,
+        };
+        let content = serde_json::to_vec_pretty(&file)?;
+
+        let tmp_path = self.path.with_extension("tmp");
+        let write = || -> Result<(), std::io::Error> {
+            {
+                let mut tmp_file = std::fs::File::create(&tmp_path)?;
+                std::io::Write::write_all(&mut tmp_file, &content)?;
+                tmp_file.sync_all()?;
+            }
+            std::fs::rename(&tmp_path, &self.path)
+        };
+
+        write().map_err(|e| Error::Write(self.path.clone(), e))
+    }
+}
+
+fn compute_mac(mac_key: &[u8; 32], keysets: &[DeclaredKeyset]) -> Result<String, Error> {
+    let mut engine = hmac::HmacEngine::<sha256::Hash>::new(mac_key);
+    engine.input(&serde_json::to_vec(keysets)?);
+
+    Ok(hmac::Hmac::<sha256::Hash>::from_engine(engine).to_string())
+}
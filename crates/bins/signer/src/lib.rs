@@ -1,11 +1,21 @@
 mod server_errors;
 pub use server_errors::Error;
+pub mod shares;
 
-pub use proto::bdhke::{BlindSignature, BlindedMessage, Proof};
+pub use proto::bdhke::{BlindSignature, BlindSignatureDleq, BlindedMessage, Proof};
 pub use proto::signer::signer_client::SignerClient;
 pub use proto::signer::signer_server::{Signer, SignerServer};
 pub use proto::signer::*;
 
+/// Protocol between the threshold coordinator and the share signers
+pub mod threshold {
+    pub use super::proto::threshold::threshold_share_client::ThresholdShareClient;
+    pub use super::proto::threshold::threshold_share_server::{
+        ThresholdShare, ThresholdShareServer,
+    };
+    pub use super::proto::threshold::*;
+}
+
 mod proto {
     pub mod bdhke {
         tonic::include_proto!("bdhke");
@@ -13,4 +23,7 @@ mod proto {
     pub mod signer {
         tonic::include_proto!("signer");
     }
+    pub mod threshold {
+        tonic::include_proto!("threshold");
+    }
 }
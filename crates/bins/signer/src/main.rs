@@ -1,15 +1,19 @@
-use bitcoin::bip32::Xpriv;
+use audit::{AuditEvent, AuditLog};
+use auth::{Caller, ClientAllowList};
+use keyset_registry::{DeclaredKeyset, KeysetRegistry};
 use nuts::{
     Amount,
     dhke::{sign_message, verify_message},
     nut01::{PublicKey, SetKeyPairs},
     nut02::{KeysetId, MintKeySet},
+    nut12::calculate_dleq,
 };
 use server_errors::{Error, VerifyProofError, VerifyProofsErrors};
 use signer::{
-    DeclareKeysetRequest, DeclareKeysetResponse, GetRootPubKeyRequest, GetRootPubKeyResponse, Key,
+    BlindSignatureDleq, BlindedMessage, DeclareKeysetRequest, DeclareKeysetResponse,
+    GetRootPubKeyRequest, GetRootPubKeyResponse, Key, ListKeysetsRequest, ListKeysetsResponse,
     SignBlindedMessagesRequest, SignBlindedMessagesResponse, SignerServer, VerifyProofsRequest,
-    VerifyProofsResponse,
+    VerifyProofsResponse, threshold::ThresholdShareServer,
 };
 use state::{SharedKeySetCache, SharedRootKey};
 use std::{collections::HashMap, net::SocketAddr, str::FromStr, sync::Arc};
@@ -18,30 +22,32 @@ use tonic::{Request, Response, Status, service::LayerExt};
 use tower::ServiceBuilder;
 use tracing::{instrument, trace};
 
+mod audit;
+mod auth;
 mod build_server;
+mod keyset_registry;
+mod root_key;
 mod server_errors;
 mod state;
+mod threshold;
 
 use build_server::build_server;
 
-const ROOT_KEY_ENV_VAR: &str = "ROOT_KEY";
 const GRPC_PORT_ENV_VAR: &str = "GRPC_PORT";
 
 #[derive(Debug)]
 pub struct SignerState {
     root_key: SharedRootKey,
     keyset_cache: SharedKeySetCache,
+    keyset_registry: Arc<KeysetRegistry>,
+    audit_log: AuditLog,
 }
 
-#[tonic::async_trait]
-impl signer::Signer for SignerState {
-    #[instrument(skip(self))]
-    async fn declare_keyset(
+impl SignerState {
+    async fn declare_keyset_inner(
         &self,
-        declare_keyset_request: Request<DeclareKeysetRequest>,
-    ) -> Result<Response<DeclareKeysetResponse>, Status> {
-        let declare_keyset_request = declare_keyset_request.get_ref();
-
+        declare_keyset_request: &DeclareKeysetRequest,
+    ) -> Result<DeclareKeysetResponse, Status> {
         // By setting this limit, we make sure that the bigest key has amount 2^63
         // which is exactly i64::MAX. So we can convert any proof amount to i64 safely,
         // this is really usefull for interacting with external dependecies,
@@ -53,16 +59,30 @@ impl signer::Signer for SignerState {
         let unit = starknet_types::Unit::from_str(&declare_keyset_request.unit)
             .map_err(|_| Error::UnknownUnit(&declare_keyset_request.unit))?;
 
+        let max_order = declare_keyset_request
+            .max_order
+            .try_into()
+            .map_err(|_| Error::MaxOrderTooBig(declare_keyset_request.max_order))?;
+
         let keyset = {
             let keyset = create_new_starknet_keyset(
                 self.root_key.clone(),
                 unit,
                 declare_keyset_request.index,
-                declare_keyset_request
-                    .max_order
-                    .try_into()
-                    .map_err(|_| Error::MaxOrderTooBig(declare_keyset_request.max_order))?,
-            );
+                max_order,
+            )
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+            // Persist it first, so that the signer never signs with a keyset it would forget on restart
+            self.keyset_registry
+                .register(DeclaredKeyset {
+                    id: keyset.id,
+                    unit,
+                    index: declare_keyset_request.index,
+                    max_order,
+                })
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
 
             self.keyset_cache
                 .insert(keyset.id, keyset.keys.clone())
@@ -71,7 +91,7 @@ impl signer::Signer for SignerState {
             keyset
         };
 
-        Ok(Response::new(DeclareKeysetResponse {
+        Ok(DeclareKeysetResponse {
             keyset_id: keyset.id.to_bytes().to_vec(),
             keys: keyset
                 .keys
@@ -81,17 +101,15 @@ impl signer::Signer for SignerState {
                     pubkey: keypair.public_key.to_string(),
                 })
                 .collect(),
-        }))
+        })
     }
 
-    #[instrument(skip(self))]
-    async fn sign_blinded_messages(
+    async fn sign_blinded_messages_inner(
         &self,
-        sign_blinded_messages_request: Request<SignBlindedMessagesRequest>,
-    ) -> Result<Response<SignBlindedMessagesResponse>, Status> {
-        let blinded_messages = sign_blinded_messages_request.into_inner().messages;
-
+        blinded_messages: Vec<BlindedMessage>,
+    ) -> Result<SignBlindedMessagesResponse, Status> {
         let mut signatures = Vec::with_capacity(blinded_messages.len());
+        let mut dleqs = Vec::with_capacity(blinded_messages.len());
 
         let keyset_cache_read_lock = self.keyset_cache.0.read().await;
 
@@ -131,10 +149,58 @@ impl signer::Signer for SignerState {
             let c = sign_message(&key_pair.secret_key, &blind_secret)
                 .map_err(|e| Error::CouldNotSignMessage(idx, blind_secret, e))?;
 
+            let dleq = calculate_dleq(c, &blind_secret, &key_pair.secret_key)
+                .map_err(|e| Error::CouldNotComputeDleq(idx, blind_secret, e))?;
+
             signatures.push(c.to_bytes().to_vec());
+            dleqs.push(BlindSignatureDleq {
+                e: dleq.e.to_secret_bytes().to_vec(),
+                s: dleq.s.to_secret_bytes().to_vec(),
+            });
         }
 
-        Ok(Response::new(SignBlindedMessagesResponse { signatures }))
+        Ok(SignBlindedMessagesResponse { signatures, dleqs })
+    }
+}
+
+#[tonic::async_trait]
+impl signer::Signer for SignerState {
+    #[instrument(skip(self))]
+    async fn declare_keyset(
+        &self,
+        declare_keyset_request: Request<DeclareKeysetRequest>,
+    ) -> Result<Response<DeclareKeysetResponse>, Status> {
+        let caller = Caller::of(&declare_keyset_request);
+        let declare_keyset_request = declare_keyset_request.into_inner();
+
+        let result = self.declare_keyset_inner(&declare_keyset_request).await;
+        self.audit_log.record(
+            &caller,
+            &AuditEvent::DeclareKeyset {
+                unit: declare_keyset_request.unit,
+                index: declare_keyset_request.index,
+                max_order: declare_keyset_request.max_order,
+            },
+            result.as_ref().map(|_| ()),
+        )?;
+
+        result.map(Response::new)
+    }
+
+    #[instrument(skip(self))]
+    async fn sign_blinded_messages(
+        &self,
+        sign_blinded_messages_request: Request<SignBlindedMessagesRequest>,
+    ) -> Result<Response<SignBlindedMessagesResponse>, Status> {
+        let caller = Caller::of(&sign_blinded_messages_request);
+        let blinded_messages = sign_blinded_messages_request.into_inner().messages;
+        let audit_event = AuditEvent::sign_blinded_messages(&blinded_messages);
+
+        let result = self.sign_blinded_messages_inner(blinded_messages).await;
+        self.audit_log
+            .record(&caller, &audit_event, result.as_ref().map(|_| ()))?;
+
+        result.map(Response::new)
     }
 
     #[instrument(skip(self))]
@@ -188,6 +254,27 @@ impl signer::Signer for SignerState {
             root_pubkey: pub_key.to_string(),
         }))
     }
+
+    #[instrument(skip(self))]
+    async fn list_keysets(
+        &self,
+        _list_keysets_request: Request<ListKeysetsRequest>,
+    ) -> Result<Response<ListKeysetsResponse>, Status> {
+        let keysets = self
+            .keyset_registry
+            .keysets()
+            .await
+            .into_iter()
+            .map(|keyset| signer::DeclaredKeyset {
+                keyset_id: keyset.id.to_bytes().to_vec(),
+                unit: keyset.unit.to_string(),
+                index: keyset.index,
+                max_order: keyset.max_order.into(),
+            })
+            .collect();
+
+        Ok(Response::new(ListKeysetsResponse { keysets }))
+    }
 }
 
 struct ValidatedProof {
@@ -257,33 +344,54 @@ async fn main() -> Result<(), anyhow::Error> {
             std::env::var(GRPC_PORT_ENV_VAR).expect("env var `GRPC_PORT` should be set");
         format!("[::0]:{}", socket_port_env_var).parse()?
     };
-    let root_private_key = {
-        let root_key_env_var: String =
-            std::env::var(ROOT_KEY_ENV_VAR).expect("env var `ROOT_KEY` should be set");
-        Xpriv::from_str(&root_key_env_var)
-            .expect("content of `ROOT_KEY` env var should be a valid private key")
-    };
-
-    let signer_logic = SignerState {
-        root_key: SharedRootKey(Arc::new(root_private_key)),
-        keyset_cache: SharedKeySetCache(Arc::new(RwLock::new(HashMap::new()))),
-    };
+    let client_allow_list = ClientAllowList::from_env()?;
+    let audit_log = AuditLog::from_env()?;
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let otel_layer = tower_otel::trace::GrpcLayer::server(tracing::Level::INFO);
+    let authorize = move |request| client_allow_list.authorize(request);
 
-    let signer_server_service = ServiceBuilder::new()
-        .layer(tower_otel::trace::GrpcLayer::server(tracing::Level::INFO))
-        .named_layer(SignerServer::new(signer_logic));
+    let mode = threshold::Mode::from_env()?;
+    tracing::info!(name: "signer-mode", name = "signer-mode", mode = ?mode);
 
-    let (health_reporter, health_service) = tonic_health::server::health_reporter();
-    health_reporter
-        .set_serving::<SignerServer<SignerState>>()
-        .await;
+    let mut server = build_server()?;
+    let router = match mode {
+        threshold::Mode::Standalone => {
+            let signer_logic = init_standalone(audit_log).await?;
+            health_reporter
+                .set_serving::<SignerServer<SignerState>>()
+                .await;
+            server.add_service(
+                ServiceBuilder::new()
+                    .layer(otel_layer)
+                    .named_layer(SignerServer::with_interceptor(signer_logic, authorize)),
+            )
+        }
+        threshold::Mode::Coordinator => {
+            let coordinator = threshold::Coordinator::from_env(audit_log).await?;
+            health_reporter
+                .set_serving::<SignerServer<threshold::Coordinator>>()
+                .await;
+            server.add_service(
+                ServiceBuilder::new()
+                    .layer(otel_layer)
+                    .named_layer(SignerServer::with_interceptor(coordinator, authorize)),
+            )
+        }
+        threshold::Mode::Share => {
+            let share_signer = threshold::ShareSigner::from_env(audit_log)?;
+            health_reporter
+                .set_serving::<ThresholdShareServer<threshold::ShareSigner>>()
+                .await;
+            server.add_service(ServiceBuilder::new().layer(otel_layer).named_layer(
+                ThresholdShareServer::with_interceptor(share_signer, authorize),
+            ))
+        }
+    };
 
     trace!(name: "grpc-listen", port = socket_addr.port());
 
-    let mut server = build_server()?;
     tracing::info!("🚀 Binding to: http://{}", socket_addr);
-    server
-        .add_service(signer_server_service)
+    router
         .add_service(health_service)
         .serve(socket_addr)
         .await?;
@@ -291,11 +399,129 @@ async fn main() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+async fn init_standalone(audit_log: AuditLog) -> Result<SignerState, anyhow::Error> {
+    let root_key = SharedRootKey(Arc::from(root_key::provider_from_env()?));
+    let keyset_registry = KeysetRegistry::load_from_env(root_key.registry_mac_key()?)?;
+    let keyset_cache = SharedKeySetCache(Arc::new(RwLock::new(HashMap::new())));
+    rebuild_keysets(&root_key, &keyset_registry, &keyset_cache).await?;
+
+    Ok(SignerState {
+        root_key,
+        keyset_cache,
+        keyset_registry: Arc::new(keyset_registry),
+        audit_log,
+    })
+}
+
 fn create_new_starknet_keyset(
     root_key: SharedRootKey,
     unit: starknet_types::Unit,
     index: u32,
     max_order: u8,
-) -> MintKeySet<starknet_types::Unit> {
+) -> Result<MintKeySet<starknet_types::Unit>, root_key::Error> {
     root_key.generate_keyset(unit, index, max_order)
 }
+
+/// Derive again all the keysets declared before the last restart
+async fn rebuild_keysets(
+    root_key: &SharedRootKey,
+    keyset_registry: &KeysetRegistry,
+    keyset_cache: &SharedKeySetCache,
+) -> Result<(), anyhow::Error> {
+    let declared_keysets = keyset_registry.keysets().await;
+
+    for declared in &declared_keysets {
+        let keyset = create_new_starknet_keyset(
+            root_key.clone(),
+            declared.unit,
+            declared.index,
+            declared.max_order,
+        )?;
+        if keyset.id != declared.id {
+            return Err(keyset_registry::Error::KeysetIdMismatch(declared.id).into());
+        }
+
+        keyset_cache.insert(keyset.id, keyset.keys).await;
+    }
+
+    tracing::info!(name: "keysets-rebuilt", name = "keysets-rebuilt", count = declared_keysets.len());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use nuts::dhke::blind_message;
+    use root_key::InMemoryRootKey;
+    use signer::Signer;
+
+    use super::*;
+
+    const ROOT_KEY: &str = "tprv8ZgxMBicQKsPeb6rodrmEXb1zRucvxYJgTKDhqQkZtbz8eY4Pf2EgbsT2swBXnnbDPQChQeFrFqHN72yFxzKfFAVsHdPeRWq2xqyUT2c4wH";
+
+    // - declare a keyset
+    // - sign a blinded message, then fail to sign one with an invalid amount
+    // - check each call left its own record
+    #[tokio::test]
+    async fn every_signing_call_is_audited() {
+        let dir = std::env::temp_dir().join(format!("signer-{}", rand::random::<u64>()));
+        std::fs::create_dir(&dir).unwrap();
+        let root_key = SharedRootKey(Arc::new(InMemoryRootKey::from_str(ROOT_KEY).unwrap()));
+        let keyset_registry = KeysetRegistry::load(
+            dir.join("registry.json"),
+            root_key.registry_mac_key().unwrap(),
+        )
+        .unwrap();
+        let state = SignerState {
+            root_key,
+            keyset_cache: SharedKeySetCache(Arc::new(RwLock::new(HashMap::new()))),
+            keyset_registry: Arc::new(keyset_registry),
+            audit_log: AuditLog::open(dir.join("audit.log")).unwrap(),
+        };
+
+        let keyset_id = state
+            .declare_keyset(Request::new(DeclareKeysetRequest {
+                unit: starknet_types::Unit::MilliStrk.to_string(),
+                index: 1,
+                max_order: 32,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .keyset_id;
+        let (blinded_secret, _r) = blind_message(b"secret", None).unwrap();
+        let message = |amount| BlindedMessage {
+            amount,
+            keyset_id: keyset_id.clone(),
+            blinded_secret: blinded_secret.to_bytes().to_vec(),
+        };
+        state
+            .sign_blinded_messages(Request::new(SignBlindedMessagesRequest {
+                messages: vec![message(4)],
+            }))
+            .await
+            .unwrap();
+        state
+            .sign_blinded_messages(Request::new(SignBlindedMessagesRequest {
+                messages: vec![message(4), message(3)],
+            }))
+            .await
+            .unwrap_err();
+
+        let records: Vec<serde_json::Value> = std::fs::read_to_string(dir.join("audit.log"))
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["method"], "declare_keyset");
+        assert!(records[0]["error"].is_null());
+        assert_eq!(records[1]["method"], "sign_blinded_messages");
+        assert_eq!(records[1]["messages"], 1);
+        assert!(records[1]["error"].is_null());
+        assert_eq!(records[2]["method"], "sign_blinded_messages");
+        assert_eq!(records[2]["messages"], 2);
+        assert!(records[2]["error"].is_string());
+    }
+}
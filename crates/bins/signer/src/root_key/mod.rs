@@ -0,0 +1,203 @@
+//! Access to the signer BIP32 root key
+//!
+//! Every key the signer uses is derived from the root key, so it is only ever needed when
+//! declaring a keyset. Providers keep it to themselves and hand out derived keys instead.
+
+use std::{fmt::Debug, path::PathBuf, str::FromStr};
+
+use age::secrecy::SecretString;
+use bitcoin::{
+    bip32::{DerivationPath, Xpriv},
+    key::Secp256k1,
+    secp256k1::{self, All},
+};
+
+#[cfg(feature = "pkcs11")]
+mod pkcs11;
+#[cfg(feature = "pkcs11")]
+pub use pkcs11::Pkcs11RootKey;
+
+const PROVIDER_ENV_VAR: &str = "ROOT_KEY_PROVIDER";
+const ROOT_KEY_ENV_VAR: &str = "ROOT_KEY";
+const KEYSTORE_PATH_ENV_VAR: &str = "ROOT_KEY_KEYSTORE_PATH";
+const KEYSTORE_PASSPHRASE_ENV_VAR: &str = "ROOT_KEY_KEYSTORE_PASSPHRASE";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("env var `{0}` should be set: {1}")]
+    Env(&'static str, #[source] std::env::VarError),
+    #[error("unknown root key provider `{0}`, expected `env`, `keystore` or `pkcs11`")]
+    UnknownProvider(String),
+    #[cfg(not(feature = "pkcs11"))]
+    #[error("root key provider `{0}` requires the signer to be built with the `{0}` feature")]
+    ProviderNotEnabled(&'static str),
+    #[error("invalid root key: {0}")]
+    InvalidRootKey(#[source] bitcoin::bip32::Error),
+    #[error("root key should be valid utf-8")]
+    NotUtf8,
+    #[error("failed to read `{0}`: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+    #[error("failed to decrypt the keystore: {0}")]
+    Keystore(#[from] age::DecryptError),
+    #[error("failed to derive key: {0}")]
+    Derivation(#[source] bitcoin::bip32::Error),
+    #[cfg(feature = "pkcs11")]
+    #[error(transparent)]
+    Pkcs11(#[from] pkcs11::Error),
+}
+
+/// Holder of the signer root key
+pub trait RootKeyProvider: Debug + Send + Sync {
+    /// Public key of the root key
+    fn root_pubkey(&self) -> secp256k1::PublicKey;
+
+    /// Derive the extended private key at `path`
+    fn derive_priv(&self, secp_ctx: &Secp256k1<All>, path: &DerivationPath)
+    -> Result<Xpriv, Error>;
+}
+
+/// A root key kept in the process memory, erased on drop
+pub struct InMemoryRootKey(Xpriv);
+
+impl Debug for InMemoryRootKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("InMemoryRootKey").field(&"..").finish()
+    }
+}
+
+impl Drop for InMemoryRootKey {
+    fn drop(&mut self) {
+        self.0.private_key.non_secure_erase();
+        self.0.chain_code = [0; 32].into();
+    }
+}
+
+impl FromStr for InMemoryRootKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(
+            Xpriv::from_str(s.trim()).map_err(Error::InvalidRootKey)?,
+        ))
+    }
+}
+
+impl InMemoryRootKey {
+    /// Decrypt an age keystore, as created by `age --passphrase`, holding the base58 encoded root key
+    pub fn from_keystore(ciphertext: &[u8], passphrase: SecretString) -> Result<Self, Error> {
+        let identity = age::scrypt::Identity::new(passphrase);
+        let mut plaintext = age::decrypt(&identity, ciphertext)?;
+
+        let root_key = std::str::from_utf8(&plaintext)
+            .map_err(|_| Error::NotUtf8)
+            .and_then(Self::from_str);
+        plaintext.fill(0);
+
+        root_key
+    }
+}
+
+impl RootKeyProvider for InMemoryRootKey {
+    fn root_pubkey(&self) -> secp256k1::PublicKey {
+        self.0.private_key.public_key(&Secp256k1::new())
+    }
+
+    fn derive_priv(
+        &self,
+        secp_ctx: &Secp256k1<All>,
+        path: &DerivationPath,
+    ) -> Result<Xpriv, Error> {
+        self.0
+            .derive_priv(secp_ctx, path)
+            .map_err(Error::Derivation)
+    }
+}
+
+/// Build the provider selected by the `ROOT_KEY_PROVIDER` env var
+///
+/// - `env` (default): plaintext root key in `ROOT_KEY`, for development only
+/// - `keystore`: age keystore at `ROOT_KEY_KEYSTORE_PATH`, unlocked with `ROOT_KEY_KEYSTORE_PASSPHRASE`
+/// - `pkcs11`: root key wrapped by a key held in a PKCS#11 token, see [`Pkcs11RootKey`]
+pub fn provider_from_env() -> Result<Box<dyn RootKeyProvider>, Error> {
+    let provider = match std::env::var(PROVIDER_ENV_VAR) {
+        Ok(provider) => provider,
+        Err(std::env::VarError::NotPresent) => "env".to_string(),
+        Err(e) => return Err(Error::Env(PROVIDER_ENV_VAR, e)),
+    };
+
+    match provider.as_str() {
+        "env" => {
+            let root_key = InMemoryRootKey::from_str(&env_var(ROOT_KEY_ENV_VAR)?)?;
+
+            Ok(Box::new(root_key))
+        }
+        "keystore" => {
+            let path = PathBuf::from(env_var(KEYSTORE_PATH_ENV_VAR)?);
+            let passphrase = SecretString::from(env_var(KEYSTORE_PASSPHRASE_ENV_VAR)?);
+            let ciphertext = std::fs::read(&path).map_err(|e| Error::Io(path, e))?;
+
+            Ok(Box::new(InMemoryRootKey::from_keystore(
+                &ciphertext,
+                passphrase,
+            )?))
+        }
+        #[cfg(feature = "pkcs11")]
+        "pkcs11" => Ok(Box::new(Pkcs11RootKey::from_env()?)),
+        #[cfg(not(feature = "pkcs11"))]
+        "pkcs11" => Err(Error::ProviderNotEnabled("pkcs11")),
+        _ => Err(Error::UnknownProvider(provider)),
+    }
+}
+
+pub(crate) fn env_var(name: &'static str) -> Result<String, Error> {
+    std::env::var(name).map_err(|e| Error::Env(name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT_KEY: &str = "tprv8ZgxMBicQKsPeb6rodrmEXb1zRucvxYJgTKDhqQkZtbz8eY4Pf2EgbsT2swBXnnbDPQChQeFrFqHN72yFxzKfFAVsHdPeRWq2xqyUT2c4wH";
+
+    /// Encrypt like `age --passphrase` does, with a cheap work factor
+    fn keystore(plaintext: &str, passphrase: &str) -> Vec<u8> {
+        let mut recipient = age::scrypt::Recipient::new(SecretString::from(passphrase));
+        recipient.set_work_factor(10);
+
+        age::encrypt(&recipient, plaintext.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn keystore_round_trip() {
+        let secp_ctx = Secp256k1::new();
+        let path = DerivationPath::from_str("m/0'/0'/1'").unwrap();
+        let expected = InMemoryRootKey::from_str(ROOT_KEY).unwrap();
+
+        // The trailing new line of `echo $ROOT_KEY | age --passphrase`
+        let ciphertext = keystore(&format!("{ROOT_KEY}\n"), "correct horse");
+        let root_key =
+            InMemoryRootKey::from_keystore(&ciphertext, SecretString::from("correct horse"))
+                .unwrap();
+
+        assert_eq!(root_key.root_pubkey(), expected.root_pubkey());
+        assert_eq!(
+            root_key.derive_priv(&secp_ctx, &path).unwrap(),
+            expected.derive_priv(&secp_ctx, &path).unwrap()
+        );
+    }
+
+    #[test]
+    fn keystore_errors() {
+        let ciphertext = keystore(ROOT_KEY, "correct horse");
+        assert!(matches!(
+            InMemoryRootKey::from_keystore(&ciphertext, SecretString::from("wrong horse")),
+            Err(Error::Keystore(_))
+        ));
+
+        let ciphertext = keystore("not a root key", "correct horse");
+        assert!(matches!(
+            InMemoryRootKey::from_keystore(&ciphertext, SecretString::from("correct horse")),
+            Err(Error::InvalidRootKey(_))
+        ));
+    }
+}
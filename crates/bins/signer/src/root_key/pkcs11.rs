@@ -0,0 +1,331 @@
+//! Root key wrapped by a key held in a PKCS#11 token (HSM, SoftHSM, ...)
+//!
+//! The token can't derive BIP32 keys, nor compute the blind signatures, so it holds an AES key
+//! instead. What it protects is the root key at rest: the wrapped root key file is useless
+//! without the token and its PIN.
+//!
+//! It does not keep the root key out of the signer memory:
+//! - every derivation (startup, each keyset declaration, the registry MAC key) has the token
+//!   AES-GCM decrypt the root key into the process memory. It is erased once the derivation
+//!   returns, but an attacker able to read the signer memory at that time gets it.
+//! - the derived keyset private keys stay in the process memory, as with the other providers,
+//!   for as long as the signer runs.
+//!
+//! The wrapped root key file is `iv (12 bytes) || AES-GCM ciphertext and 16 bytes tag`
+//! of the base58 encoded root key.
+
+use std::{path::PathBuf, str::FromStr, sync::Mutex};
+
+use bitcoin::{
+    bip32::{DerivationPath, Xpriv},
+    key::Secp256k1,
+    secp256k1::{self, All},
+};
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    mechanism::{Mechanism, aead::GcmParams},
+    object::{Attribute, ObjectClass},
+    session::{Session, UserType},
+    slot::Slot,
+    types::AuthPin,
+};
+
+use super::{InMemoryRootKey, RootKeyProvider, env_var};
+
+const MODULE_PATH_ENV_VAR: &str = "PKCS11_MODULE_PATH";
+const TOKEN_LABEL_ENV_VAR: &str = "PKCS11_TOKEN_LABEL";
+const PIN_ENV_VAR: &str = "PKCS11_PIN";
+const KEY_LABEL_ENV_VAR: &str = "PKCS11_KEY_LABEL";
+const WRAPPED_ROOT_KEY_PATH_ENV_VAR: &str = "PKCS11_WRAPPED_ROOT_KEY_PATH";
+
+const IV_LEN: usize = 12;
+const TAG_BITS: u64 = 128;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("pkcs11 error: {0}")]
+    Cryptoki(#[from] cryptoki::error::Error),
+    #[error("no token labeled `{0}`")]
+    TokenNotFound(String),
+    #[error("no secret key labeled `{0}` in the token")]
+    KeyNotFound(String),
+    #[error("wrapped root key is too short")]
+    WrappedRootKeyTooShort,
+}
+
+/// Root key unwrapped by the token on each derivation, see the [module docs](self)
+pub struct Pkcs11RootKey {
+    token: Token,
+    root_pubkey: secp256k1::PublicKey,
+}
+
+impl std::fmt::Debug for Pkcs11RootKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pkcs11RootKey")
+            .field("slot", &self.token.slot)
+            .field("key_label", &self.token.key_label)
+            .field("root_pubkey", &self.root_pubkey)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Token {
+    pkcs11: Pkcs11,
+    slot: Slot,
+    pin: AuthPin,
+    key_label: String,
+    wrapped_root_key: Vec<u8>,
+    /// Only one unwrapping at a time
+    lock: Mutex<()>,
+}
+
+impl Pkcs11RootKey {
+    pub fn new(
+        module_path: PathBuf,
+        token_label: &str,
+        pin: AuthPin,
+        key_label: String,
+        wrapped_root_key: Vec<u8>,
+    ) -> Result<Self, super::Error> {
+        let pkcs11 = Pkcs11::new(module_path).map_err(Error::from)?;
+        pkcs11
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(Error::from)?;
+
+        let mut slot = None;
+        for s in pkcs11.get_slots_with_token().map_err(Error::from)? {
+            if pkcs11.get_token_info(s).map_err(Error::from)?.label() == token_label {
+                slot = Some(s);
+                break;
+            }
+        }
+        let slot = slot.ok_or_else(|| Error::TokenNotFound(token_label.to_string()))?;
+
+        let token = Token {
+            pkcs11,
+            slot,
+            pin,
+            key_label,
+            wrapped_root_key,
+            lock: Mutex::new(()),
+        };
+        // Also checks the whole setup at startup
+        let root_pubkey = token.unwrap_root_key()?.root_pubkey();
+
+        Ok(Self { token, root_pubkey })
+    }
+
+    /// Configure it from the `PKCS11_*` env vars
+    pub fn from_env() -> Result<Self, super::Error> {
+        let path = PathBuf::from(env_var(WRAPPED_ROOT_KEY_PATH_ENV_VAR)?);
+        let wrapped_root_key = std::fs::read(&path).map_err(|e| super::Error::Io(path, e))?;
+
+        Self::new(
+            PathBuf::from(env_var(MODULE_PATH_ENV_VAR)?),
+            &env_var(TOKEN_LABEL_ENV_VAR)?,
+            AuthPin::new(env_var(PIN_ENV_VAR)?),
+            env_var(KEY_LABEL_ENV_VAR)?,
+            wrapped_root_key,
+        )
+    }
+}
+
+impl Token {
+    fn open_session(&self) -> Result<Session, Error> {
+        let session = self.pkcs11.open_ro_session(self.slot)?;
+        session.login(UserType::User, Some(&self.pin))?;
+
+        Ok(session)
+    }
+
+    fn unwrap_root_key(&self) -> Result<InMemoryRootKey, super::Error> {
+        if self.wrapped_root_key.len() <= IV_LEN {
+            return Err(Error::WrappedRootKeyTooShort.into());
+        }
+        let (iv, ciphertext) = self.wrapped_root_key.split_at(IV_LEN);
+        let mut iv = iv.to_vec();
+
+        let mut plaintext = {
+            let _lock = self.lock.lock().expect("lock not to be poisoned");
+            let session = self.open_session()?;
+            let key = session
+                .find_objects(&[
+                    Attribute::Class(ObjectClass::SECRET_KEY),
+                    Attribute::Label(self.key_label.as_bytes().to_vec()),
+                ])
+                .map_err(Error::from)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::KeyNotFound(self.key_label.clone()))?;
+
+            let mechanism = Mechanism::AesGcm(
+                GcmParams::new(&mut iv, &[], TAG_BITS.into()).map_err(Error::from)?,
+            );
+            session
+                .decrypt(&mechanism, key, ciphertext)
+                .map_err(Error::from)?
+        };
+
+        let root_key = std::str::from_utf8(&plaintext)
+            .map_err(|_| super::Error::NotUtf8)
+            .and_then(InMemoryRootKey::from_str);
+        plaintext.fill(0);
+
+        root_key
+    }
+}
+
+impl RootKeyProvider for Pkcs11RootKey {
+    fn root_pubkey(&self) -> secp256k1::PublicKey {
+        self.root_pubkey
+    }
+
+    fn derive_priv(
+        &self,
+        secp_ctx: &Secp256k1<All>,
+        path: &DerivationPath,
+    ) -> Result<Xpriv, super::Error> {
+        // Dropped, and erased, as soon as the derivation is done
+        let root_key = self.token.unwrap_root_key()?;
+
+        root_key.derive_priv(secp_ctx, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::root_key::Error as RootKeyError;
+
+    const ROOT_KEY: &str = "tprv8ZgxMBicQKsPeb6rodrmEXb1zRucvxYJgTKDhqQkZtbz8eY4Pf2EgbsT2swBXnnbDPQChQeFrFqHN72yFxzKfFAVsHdPeRWq2xqyUT2c4wH";
+
+    /// The token configured by the `PKCS11_MODULE_PATH`, `PKCS11_TOKEN_LABEL` and `PKCS11_PIN` env vars
+    fn token_config() -> (PathBuf, String, String) {
+        (
+            PathBuf::from(env_var(MODULE_PATH_ENV_VAR).unwrap()),
+            env_var(TOKEN_LABEL_ENV_VAR).unwrap(),
+            env_var(PIN_ENV_VAR).unwrap(),
+        )
+    }
+
+    fn new_root_key(
+        key_label: &str,
+        wrapped_root_key: Vec<u8>,
+    ) -> Result<Pkcs11RootKey, RootKeyError> {
+        let (module_path, token_label, pin) = token_config();
+
+        Pkcs11RootKey::new(
+            module_path,
+            &token_label,
+            AuthPin::new(pin),
+            key_label.to_string(),
+            wrapped_root_key,
+        )
+    }
+
+    /// Run `f` in a read-write session of the token
+    ///
+    /// The module is finalized on return, so that a `Pkcs11RootKey` can initialize it again.
+    fn with_rw_session<T>(f: impl FnOnce(&Session) -> T) -> T {
+        let (module_path, token_label, pin) = token_config();
+        let pkcs11 = Pkcs11::new(module_path).unwrap();
+        pkcs11.initialize(CInitializeArgs::OsThreads).unwrap();
+        let slot = pkcs11
+            .get_slots_with_token()
+            .unwrap()
+            .into_iter()
+            .find(|s| pkcs11.get_token_info(*s).unwrap().label() == token_label)
+            .unwrap();
+        let session = pkcs11.open_rw_session(slot).unwrap();
+        session
+            .login(UserType::User, Some(&AuthPin::new(pin)))
+            .unwrap();
+
+        f(&session)
+    }
+
+    // Needs a token, e.g. SoftHSM:
+    // softhsm2-util --init-token --free --label signer-test --so-pin 1234 --pin 1234
+    // PKCS11_MODULE_PATH=/usr/lib/softhsm/libsofthsm2.so PKCS11_TOKEN_LABEL=signer-test PKCS11_PIN=1234 \
+    //   cargo test -p signer --features pkcs11 -- --include-ignored
+    //
+    // - generate an AES key in the token and wrap the root key with it
+    // - check the unwrapped root key derives the same keys as the plaintext one
+    // - check the setup errors
+    #[test]
+    #[ignore = "needs a PKCS#11 token"]
+    fn softhsm_round_trip() {
+        let key_label = format!("signer-test-{}", rand::random::<u64>());
+        let wrapped_root_key = with_rw_session(|session| {
+            let key = session
+                .generate_key(
+                    &Mechanism::AesKeyGen,
+                    &[
+                        Attribute::Token(true),
+                        Attribute::Private(true),
+                        Attribute::Sensitive(true),
+                        Attribute::Encrypt(true),
+                        Attribute::Decrypt(true),
+                        Attribute::ValueLen(32u64.into()),
+                        Attribute::Label(key_label.as_bytes().to_vec()),
+                    ],
+                )
+                .unwrap();
+            let mut iv = [7; IV_LEN];
+            let ciphertext = session
+                .encrypt(
+                    &Mechanism::AesGcm(GcmParams::new(&mut iv, &[], TAG_BITS.into()).unwrap()),
+                    key,
+                    ROOT_KEY.as_bytes(),
+                )
+                .unwrap();
+
+            [iv.as_slice(), &ciphertext].concat()
+        });
+
+        let secp_ctx = Secp256k1::new();
+        let path = DerivationPath::from_str("m/0'/0'/1'").unwrap();
+        let expected = InMemoryRootKey::from_str(ROOT_KEY).unwrap();
+        let derived = new_root_key(&key_label, wrapped_root_key.clone()).map(|root_key| {
+            (
+                root_key.root_pubkey(),
+                root_key.derive_priv(&secp_ctx, &path).unwrap(),
+            )
+        });
+        let mut tampered = wrapped_root_key.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        let tampered = new_root_key(&key_label, tampered).map(|_| ());
+        let unknown_key = new_root_key("unknown", wrapped_root_key).map(|_| ());
+        let too_short = new_root_key(&key_label, vec![0; IV_LEN]).map(|_| ());
+
+        // Remove the key before checking, not to leave it in the token
+        with_rw_session(|session| {
+            for key in session
+                .find_objects(&[Attribute::Label(key_label.as_bytes().to_vec())])
+                .unwrap()
+            {
+                session.destroy_object(key).unwrap();
+            }
+        });
+
+        assert_eq!(
+            derived.unwrap(),
+            (
+                expected.root_pubkey(),
+                expected.derive_priv(&secp_ctx, &path).unwrap()
+            )
+        );
+        assert!(matches!(
+            tampered,
+            Err(RootKeyError::Pkcs11(Error::Cryptoki(_)))
+        ));
+        assert!(matches!(
+            unknown_key,
+            Err(RootKeyError::Pkcs11(Error::KeyNotFound(_)))
+        ));
+        assert!(matches!(
+            too_short,
+            Err(RootKeyError::Pkcs11(Error::WrappedRootKeyTooShort))
+        ));
+    }
+}
@@ -2,6 +2,7 @@ use nuts::{
     Amount, dhke,
     nut01::{self, PublicKey},
     nut02::{self, KeysetId},
+    nut12,
 };
 use starknet_types::Unit;
 use tonic::{Code, Status};
@@ -14,6 +15,7 @@ pub enum Error<'a> {
     UnknownUnit(&'a str),
     MaxOrderTooBig(u32),
     CouldNotSignMessage(usize, PublicKey, dhke::Error),
+    CouldNotComputeDleq(usize, PublicKey, nut12::Error),
     CouldNotVerifyProof(usize, PublicKey, String, dhke::Error),
     BadKeysetId(usize, &'a [u8], nut02::Error),
     KeysetNotFound(usize, KeysetId),
@@ -53,6 +55,16 @@ impl<'a> From<Error<'a>> for Status {
                     ),
                 )]),
             ),
+            Error::CouldNotComputeDleq(idx, message, error) => Status::with_error_details(
+                Code::InvalidArgument,
+                "failed to compute dleq proof",
+                ErrorDetails::with_bad_request(vec![FieldViolation::new(
+                    format!("messages[{}].blinded_secret", idx),
+                    format!(
+                        "given message {message} the dleq proof could not be computed: {error}"
+                    ),
+                )]),
+            ),
             Error::CouldNotVerifyProof(idx, proof, secret, error) => Status::with_error_details(
                 Code::InvalidArgument,
                 "failed to verify proof",
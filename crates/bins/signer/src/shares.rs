@@ -0,0 +1,318 @@
+//! Threshold sharing of the signer keys
+//!
+//! In threshold mode no process ever holds a keyset private key. Each of the `total` share
+//! signers holds a Shamir share of every key, any `threshold` of them being needed to sign.
+//!
+//! The keys must stay independent from each other, as any public relation between two keys of a
+//! keyset would let users turn a signature on one amount into a signature on another one. They
+//! are built with pseudo-random secret sharing (Cramer, Damgård and Ishai, 2005): a dealer draws
+//! one seed per subset of `total - threshold + 1` signers and hands it to the members of the
+//! subset. The key at some path is the sum, over all the subsets, of a PRF of their seed. Any
+//! `threshold` signers know every seed between them, while any smaller coalition misses at least
+//! one. Each signer turns the PRF outputs it knows into its own Shamir share locally, without
+//! talking to the others.
+//!
+//! The dealer is only needed once, to generate the share files.
+
+use std::collections::BTreeSet;
+
+use bitcoin::hashes::{Hash, HashEngine, hmac, sha256};
+use nuts::{
+    SECP256K1,
+    nut01::{PublicKey, SecretKey},
+};
+use serde::{Deserialize, Serialize};
+
+/// Above it the number of seeds held by each signer grows too fast
+pub const MAX_TOTAL: u32 = 10;
+
+/// Order of the secp256k1 group minus two, the exponent computing modular inverses
+const ORDER_MINUS_TWO: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x3f,
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(
+        "invalid threshold parameters: {threshold}-of-{total}, expected 2 <= threshold <= total <= {MAX_TOTAL}"
+    )]
+    InvalidParameters { threshold: u32, total: u32 },
+    #[error("invalid share file: {0}")]
+    InvalidShareFile(&'static str),
+    #[error("invalid seed: {0}")]
+    InvalidSeed(#[from] bitcoin::hex::HexToArrayError),
+    #[error("share indexes should be distinct and between 1 and {MAX_TOTAL}")]
+    InvalidIndexes,
+    #[error(transparent)]
+    Secp256k1(#[from] bitcoin::secp256k1::Error),
+}
+
+/// What a key is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPath {
+    /// Identifies the signer group, see `GetRootPubKey`
+    Root,
+    /// Signs the given amount of the keyset declared with `(unit, index)`
+    Amount { unit: u32, index: u32, amount: u64 },
+}
+
+impl KeyPath {
+    fn to_bytes(self) -> Vec<u8> {
+        match self {
+            KeyPath::Root => b"root".to_vec(),
+            KeyPath::Amount {
+                unit,
+                index,
+                amount,
+            } => [
+                b"amount".as_slice(),
+                &unit.to_be_bytes(),
+                &index.to_be_bytes(),
+                &amount.to_be_bytes(),
+            ]
+            .concat(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsetSeed {
+    /// Indexes of the signers knowing the seed, sorted
+    pub subset: Vec<u32>,
+    /// 32 hex encoded bytes
+    pub seed: String,
+}
+
+/// Everything a share signer needs to know, as written by the dealer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareFile {
+    /// Between 1 and `total`
+    pub index: u32,
+    pub threshold: u32,
+    pub total: u32,
+    pub seeds: Vec<SubsetSeed>,
+}
+
+/// Generate the share files of a new `threshold`-of-`total` signer group
+pub fn deal(threshold: u32, total: u32) -> Result<Vec<ShareFile>, Error> {
+    check_parameters(threshold, total)?;
+
+    let seeds: Vec<(Vec<u32>, [u8; 32])> = subsets(total, total - threshold + 1)
+        .into_iter()
+        .map(|subset| (subset, rand::random()))
+        .collect();
+
+    let share_files = (1..=total)
+        .map(|index| ShareFile {
+            index,
+            threshold,
+            total,
+            seeds: seeds
+                .iter()
+                .filter(|(subset, _)| subset.contains(&index))
+                .map(|(subset, seed)| SubsetSeed {
+                    subset: subset.clone(),
+                    seed: bitcoin::hex::DisplayHex::to_lower_hex_string(seed.as_slice()),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(share_files)
+}
+
+impl ShareFile {
+    /// Check that the file holds exactly the seeds of the subsets its signer belongs to
+    pub fn validate(&self) -> Result<(), Error> {
+        check_parameters(self.threshold, self.total)?;
+        if self.index == 0 || self.index > self.total {
+            return Err(Error::InvalidShareFile("index out of range"));
+        }
+
+        let expected: BTreeSet<Vec<u32>> = subsets(self.total, self.total - self.threshold + 1)
+            .into_iter()
+            .filter(|subset| subset.contains(&self.index))
+            .collect();
+        let held: BTreeSet<Vec<u32>> = self.seeds.iter().map(|s| s.subset.clone()).collect();
+        if held.len() != self.seeds.len() || held != expected {
+            return Err(Error::InvalidShareFile(
+                "seeds don't match the subsets of the signer",
+            ));
+        }
+        for seed in &self.seeds {
+            parse_seed(&seed.seed)?;
+        }
+
+        Ok(())
+    }
+
+    /// This signer's Shamir share of the key at `path`
+    pub fn secret_share(&self, path: KeyPath) -> Result<SecretKey, Error> {
+        let path = path.to_bytes();
+        let mut share: Option<SecretKey> = None;
+
+        for subset_seed in &self.seeds {
+            let value = prf(&parse_seed(&subset_seed.seed)?, &path);
+            // f_A(x) = prod_{j not in A} (j - x) / j is 1 in 0 and 0 at the other signers,
+            // which makes the sum of the f_A(i) * PRF(s_A) lie on a single polynomial
+            let mut weight = scalar_from_i64(1)?;
+            for j in (1..=self.total).filter(|j| !subset_seed.subset.contains(j)) {
+                let factor = mul(
+                    &scalar_from_i64(i64::from(j) - i64::from(self.index))?,
+                    &inverse(&scalar_from_i64(i64::from(j))?)?,
+                )?;
+                weight = mul(&weight, &factor)?;
+            }
+
+            let term = mul(&value, &weight)?;
+            share = Some(match share {
+                Some(share) => add(&share, &term)?,
+                None => term,
+            });
+        }
+
+        share.ok_or(Error::InvalidShareFile("no seed"))
+    }
+}
+
+/// Lagrange coefficients interpolating the polynomial at 0 from its values at `indexes`
+pub fn lagrange_coefficients(indexes: &[u32]) -> Result<Vec<SecretKey>, Error> {
+    let distinct: BTreeSet<_> = indexes.iter().collect();
+    if distinct.len() != indexes.len() || indexes.iter().any(|i| *i == 0 || *i > MAX_TOTAL) {
+        return Err(Error::InvalidIndexes);
+    }
+
+    indexes
+        .iter()
+        .map(|&i| {
+            // prod_{m != i} m / (m - i)
+            let mut coefficient = scalar_from_i64(1)?;
+            for &m in indexes.iter().filter(|&&m| m != i) {
+                let factor = mul(
+                    &scalar_from_i64(i64::from(m))?,
+                    &inverse(&scalar_from_i64(i64::from(m) - i64::from(i))?)?,
+                )?;
+                coefficient = mul(&coefficient, &factor)?;
+            }
+            Ok(coefficient)
+        })
+        .collect()
+}
+
+/// `sum(coefficient_i * point_i)`
+pub fn combine_points(
+    coefficients: &[SecretKey],
+    points: &[PublicKey],
+) -> Result<PublicKey, Error> {
+    let terms = coefficients
+        .iter()
+        .zip(points)
+        .map(|(c, p)| p.mul_tweak(&SECP256K1, &c.as_scalar()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let terms: Vec<_> = terms.iter().collect();
+
+    Ok(bitcoin::secp256k1::PublicKey::combine_keys(&terms)?.into())
+}
+
+/// `sum(coefficient_i * scalar_i)`
+pub fn combine_scalars(
+    coefficients: &[SecretKey],
+    scalars: &[SecretKey],
+) -> Result<SecretKey, Error> {
+    let mut sum: Option<SecretKey> = None;
+    for (c, s) in coefficients.iter().zip(scalars) {
+        let term = mul(c, s)?;
+        sum = Some(match sum {
+            Some(sum) => add(&sum, &term)?,
+            None => term,
+        });
+    }
+
+    sum.ok_or(Error::InvalidIndexes)
+}
+
+fn check_parameters(threshold: u32, total: u32) -> Result<(), Error> {
+    if threshold < 2 || threshold > total || total > MAX_TOTAL {
+        return Err(Error::InvalidParameters { threshold, total });
+    }
+
+    Ok(())
+}
+
+/// All the sorted subsets of `1..=n` of size `k`
+fn subsets(n: u32, k: u32) -> Vec<Vec<u32>> {
+    fn extend(from: u32, n: u32, k: u32, current: &mut Vec<u32>, out: &mut Vec<Vec<u32>>) {
+        if current.len() == k as usize {
+            out.push(current.clone());
+            return;
+        }
+        for i in from..=n {
+            current.push(i);
+            extend(i + 1, n, k, current, out);
+            current.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    extend(1, n, k, &mut Vec::new(), &mut out);
+    out
+}
+
+fn parse_seed(seed: &str) -> Result<[u8; 32], Error> {
+    Ok(<[u8; 32] as bitcoin::hex::FromHex>::from_hex(seed)?)
+}
+
+/// HMAC-SHA256 of `path` keyed with `seed`, as a valid scalar
+fn prf(seed: &[u8; 32], path: &[u8]) -> SecretKey {
+    // Retrying with a counter only happens with negligible probability
+    for counter in 0u32.. {
+        let mut engine = hmac::HmacEngine::<sha256::Hash>::new(seed);
+        engine.input(b"paynet-threshold-key");
+        engine.input(path);
+        engine.input(&counter.to_be_bytes());
+        let output = hmac::Hmac::<sha256::Hash>::from_engine(engine);
+
+        if let Ok(key) = SecretKey::from_slice(output.as_byte_array()) {
+            return key;
+        }
+    }
+
+    unreachable!("a valid scalar is found long before the counter overflows")
+}
+
+fn scalar_from_i64(value: i64) -> Result<SecretKey, Error> {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.unsigned_abs().to_be_bytes());
+    let scalar = bitcoin::secp256k1::SecretKey::from_slice(&bytes)?;
+
+    Ok(if value < 0 { scalar.negate() } else { scalar }.into())
+}
+
+fn mul(a: &SecretKey, b: &SecretKey) -> Result<SecretKey, Error> {
+    Ok(a.mul_tweak(&b.as_scalar())?.into())
+}
+
+fn add(a: &SecretKey, b: &SecretKey) -> Result<SecretKey, Error> {
+    Ok(a.add_tweak(&b.as_scalar())?.into())
+}
+
+/// `a^(n - 2)`, the inverse of `a` modulo the group order `n`
+fn inverse(a: &SecretKey) -> Result<SecretKey, Error> {
+    let mut result: Option<SecretKey> = None;
+    for byte in ORDER_MINUS_TWO {
+        for bit in (0..8).rev() {
+            if let Some(r) = &result {
+                result = Some(mul(r, r)?);
+            }
+            if (byte >> bit) & 1 == 1 {
+                result = Some(match &result {
+                    Some(r) => mul(r, a)?,
+                    None => a.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(result.expect("exponent is not zero"))
+}
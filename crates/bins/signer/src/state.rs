@@ -1,7 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use bitcoin::{
-    bip32::{ChildNumber, DerivationPath, Xpriv},
+    bip32::{ChildNumber, DerivationPath},
     key::Secp256k1,
 };
 use nuts::{
@@ -11,11 +11,18 @@ use nuts::{
 };
 use tokio::sync::RwLock;
 
+use crate::root_key::{self, RootKeyProvider};
+
 #[derive(Debug, Clone)]
-pub struct SharedRootKey(pub Arc<Xpriv>);
+pub struct SharedRootKey(pub Arc<dyn RootKeyProvider>);
 
 impl SharedRootKey {
-    pub fn generate_keyset<U: Unit>(&self, unit: U, index: u32, max_order: u8) -> MintKeySet<U> {
+    pub fn generate_keyset<U: Unit>(
+        &self,
+        unit: U,
+        index: u32,
+        max_order: u8,
+    ) -> Result<MintKeySet<U>, root_key::Error> {
         let unit_idx = unit.into();
         let secp_ctx = Secp256k1::new();
 
@@ -25,18 +32,25 @@ impl SharedRootKey {
             ChildNumber::from_hardened_idx(index).expect("should be a valid index"),
         ]);
 
-        let xpriv = self
-            .0
-            .derive_priv(&secp_ctx, &derivation_path)
-            .expect("RNG busted");
+        let xpriv = self.0.derive_priv(&secp_ctx, &derivation_path)?;
+
+        Ok(MintKeySet::generate(&secp_ctx, xpriv, unit, max_order))
+    }
+
+    /// Key authenticating the keyset registry
+    ///
+    /// Derived on its own branch, apart from the keysets ones.
+    pub fn registry_mac_key(&self) -> Result<[u8; 32], root_key::Error> {
+        let derivation_path = DerivationPath::from(vec![
+            ChildNumber::from_hardened_idx(1).expect("1 is a valid index"),
+        ]);
+        let xpriv = self.0.derive_priv(&Secp256k1::new(), &derivation_path)?;
 
-        MintKeySet::generate(&secp_ctx, xpriv, unit, max_order)
+        Ok(xpriv.private_key.secret_bytes())
     }
 
     pub fn get_pubkey(&self) -> bitcoin::secp256k1::PublicKey {
-        let secp256k1 = Secp256k1::new();
-        let private_key = &self.0.private_key;
-        private_key.public_key(&secp256k1)
+        self.0.root_pubkey()
     }
 }
 
@@ -0,0 +1,1000 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::Arc,
+};
+
+use futures::future::join_all;
+use nuts::{
+    Amount, SECP256K1,
+    dhke::{hash_e, hash_to_curve},
+    nut01::{PublicKey, SecretKey},
+    nut02::KeysetId,
+    nut12::verify_dleq,
+};
+use signer::{
+    BlindSignatureDleq, BlindedMessage, DeclareKeysetRequest, DeclareKeysetResponse,
+    GetRootPubKeyRequest, GetRootPubKeyResponse, Key, ListKeysetsRequest, ListKeysetsResponse,
+    SignBlindedMessagesRequest, SignBlindedMessagesResponse, VerifyProofsRequest,
+    VerifyProofsResponse,
+    shares::{combine_points, combine_scalars, lagrange_coefficients},
+    threshold::{
+        CommitRequest, EvaluateRequest, GetPublicSharesRequest, GetShareInfoRequest, KeyPath,
+        PathPoint, PathSecret, RespondRequest, ThresholdShareClient,
+    },
+};
+use starknet_types::Unit;
+use tokio::sync::{OnceCell, RwLock};
+use tonic::{Request, Response, Status, transport::Channel};
+use tracing::instrument;
+
+use super::{Error, env_var, parse_point};
+use crate::{
+    audit::{AuditEvent, AuditLog},
+    auth::Caller,
+    keyset_registry::{self, DeclaredKeyset, KeysetRegistry},
+    server_errors::{self, VerifyProofError, VerifyProofsErrors},
+};
+
+const SHARE_URLS_ENV_VAR: &str = "THRESHOLD_SHARE_URLS";
+const REGISTRY_MAC_KEY_ENV_VAR: &str = "KEYSET_REGISTRY_MAC_KEY";
+#[cfg(feature = "tls")]
+const SHARE_TLS_CA_PATH_ENV_VAR: &str = "THRESHOLD_TLS_CA_PATH";
+#[cfg(feature = "tls")]
+const SHARE_TLS_CERT_PATH_ENV_VAR: &str = "THRESHOLD_TLS_CERT_PATH";
+#[cfg(feature = "tls")]
+const SHARE_TLS_KEY_PATH_ENV_VAR: &str = "THRESHOLD_TLS_KEY_PATH";
+
+/// Parameters every share signer must agree on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Group {
+    threshold: u32,
+    total: u32,
+}
+
+#[derive(Debug)]
+struct Share {
+    url: String,
+    client: ThresholdShareClient<Channel>,
+    /// Learned on the first successful call, shares being allowed to be down at startup
+    index: OnceCell<u32>,
+}
+
+impl Share {
+    async fn index(&self, group: Group) -> Result<u32, Status> {
+        self.index
+            .get_or_try_init(|| async {
+                let info = self
+                    .client
+                    .clone()
+                    .get_share_info(GetShareInfoRequest {})
+                    .await?
+                    .into_inner();
+                if info.threshold != group.threshold || info.total != group.total {
+                    return Err(Status::failed_precondition(format!(
+                        "share signer {} is part of a {}-of-{} group",
+                        self.url, info.threshold, info.total
+                    )));
+                }
+
+                Ok(info.index)
+            })
+            .await
+            .copied()
+    }
+}
+
+/// A key nobody holds, and the public keys of its shares
+#[derive(Debug, Clone)]
+struct ThresholdKey {
+    public_key: PublicKey,
+    /// By share index, for the share signers that were available when the keyset was declared
+    public_shares: BTreeMap<u32, PublicKey>,
+}
+
+#[derive(Debug)]
+struct ThresholdKeyset {
+    unit: Unit,
+    index: u32,
+    keys: BTreeMap<Amount, ThresholdKey>,
+}
+
+impl ThresholdKeyset {
+    fn max_amount(&self) -> Amount {
+        self.keys
+            .last_key_value()
+            .map(|(&amount, _)| amount)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+struct ShareGroup {
+    shares: Vec<Share>,
+    group: Group,
+}
+
+/// Serves the `Signer` service by combining the work of `threshold` share signers
+#[derive(Debug)]
+pub struct Coordinator {
+    shares: ShareGroup,
+    root_pubkey: PublicKey,
+    keysets: RwLock<HashMap<KeysetId, Arc<ThresholdKeyset>>>,
+    keyset_registry: KeysetRegistry,
+    audit_log: AuditLog,
+}
+
+impl Coordinator {
+    /// Connect to the share signers and rebuild the keysets of the registry
+    pub async fn from_env(audit_log: AuditLog) -> Result<Self, Error> {
+        let shares = ShareGroup::connect_from_env().await?;
+        let root_pubkey = shares
+            .threshold_keys(&[root_path()])
+            .await
+            .map_err(|e| Error::RootKey(Box::new(e)))?
+            .remove(0)
+            .public_key;
+
+        let mac_key =
+            <[u8; 32] as bitcoin::hex::FromHex>::from_hex(&env_var(REGISTRY_MAC_KEY_ENV_VAR)?)
+                .map_err(|_| Error::InvalidMacKey)?;
+
+        let coordinator = Self {
+            shares,
+            root_pubkey,
+            keysets: RwLock::new(HashMap::new()),
+            keyset_registry: KeysetRegistry::load_from_env(mac_key)?,
+            audit_log,
+        };
+        coordinator.rebuild_keysets().await?;
+
+        tracing::info!(
+            name: "coordinator-ready",
+            name = "coordinator-ready",
+            threshold = coordinator.shares.group.threshold,
+            total = coordinator.shares.group.total,
+            configured_shares = coordinator.shares.shares.len(),
+        );
+
+        Ok(coordinator)
+    }
+
+    /// Derive again all the keysets declared before the last restart
+    async fn rebuild_keysets(&self) -> Result<(), Error> {
+        let declared_keysets = self.keyset_registry.keysets().await;
+
+        for declared in &declared_keysets {
+            let keys = self
+                .shares
+                .threshold_keys(&keyset_paths(
+                    declared.unit,
+                    declared.index,
+                    declared.max_order,
+                ))
+                .await
+                .map_err(|e| Error::Rebuild(declared.id, Box::new(e)))?;
+            let (id, keyset) = build_keyset(declared.unit, declared.index, keys);
+            if id != declared.id {
+                return Err(keyset_registry::Error::KeysetIdMismatch(declared.id).into());
+            }
+
+            self.keysets.write().await.insert(id, Arc::new(keyset));
+        }
+
+        tracing::info!(name: "keysets-rebuilt", name = "keysets-rebuilt", count = declared_keysets.len());
+
+        Ok(())
+    }
+}
+
+impl ShareGroup {
+    /// Connect to the comma separated share signers of `THRESHOLD_SHARE_URLS`
+    ///
+    /// At least one of them must be reachable to learn the group parameters.
+    async fn connect_from_env() -> Result<Self, Error> {
+        let urls: Vec<String> = env_var(SHARE_URLS_ENV_VAR)?
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(String::from)
+            .collect();
+        #[cfg(feature = "tls")]
+        let tls_config = share_tls_config()?;
+
+        let mut shares = Vec::with_capacity(urls.len());
+        for url in urls {
+            let endpoint =
+                Channel::from_shared(url.clone()).map_err(|e| Error::Url(url.clone(), e))?;
+            #[cfg(feature = "tls")]
+            let endpoint = match &tls_config {
+                Some(tls_config) => endpoint.tls_config(tls_config.clone())?,
+                None => endpoint,
+            };
+            shares.push(Share {
+                url,
+                client: ThresholdShareClient::new(endpoint.connect_lazy()),
+                index: OnceCell::new(),
+            });
+        }
+
+        let group = discover_group(&shares).await?;
+        if shares.len() < group.threshold as usize {
+            return Err(Error::NotEnoughShares(shares.len(), group.threshold));
+        }
+
+        Ok(Self { shares, group })
+    }
+
+    /// Call every share signer concurrently, keeping the successful responses sorted by index
+    async fn call_shares<R, T, F, Fut>(&self, request: &R, call: F) -> Vec<(u32, T)>
+    where
+        R: Clone,
+        F: Fn(ThresholdShareClient<Channel>, R) -> Fut,
+        Fut: Future<Output = Result<Response<T>, Status>>,
+    {
+        let calls = self.shares.iter().map(|share| {
+            let call = &call;
+            async move {
+                let index = share.index(self.group).await?;
+                let response = call(share.client.clone(), request.clone()).await?;
+                Ok::<_, Status>((index, response.into_inner()))
+            }
+        });
+
+        let mut responses: Vec<_> = join_all(calls)
+            .await
+            .into_iter()
+            .zip(&self.shares)
+            .filter_map(|(result, share)| {
+                result
+                    .inspect_err(|e| {
+                        tracing::warn!(
+                            name: "share-call-failed",
+                            name = "share-call-failed",
+                            url = share.url,
+                            error = %e,
+                        )
+                    })
+                    .ok()
+            })
+            .collect();
+        responses.sort_by_key(|(index, _)| *index);
+
+        responses
+    }
+
+    fn share_client(&self, index: u32) -> Option<ThresholdShareClient<Channel>> {
+        self.shares
+            .iter()
+            .find(|share| share.index.get() == Some(&index))
+            .map(|share| share.client.clone())
+    }
+
+    fn check_available(&self, available: usize) -> Result<(), Status> {
+        if available < self.group.threshold as usize {
+            return Err(Status::unavailable(format!(
+                "{available} share signers available, {} needed",
+                self.group.threshold
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Interpolate the public keys at `paths` from the public shares
+    ///
+    /// The shares beyond the first `threshold` ones are checked against them.
+    async fn threshold_keys(&self, paths: &[KeyPath]) -> Result<Vec<ThresholdKey>, Status> {
+        let responses: Vec<(u32, Vec<PublicKey>)> = self
+            .call_shares(
+                &GetPublicSharesRequest {
+                    paths: paths.to_vec(),
+                },
+                |mut client, request| async move { client.get_public_shares(request).await },
+            )
+            .await
+            .into_iter()
+            .filter_map(|(index, response)| {
+                let public_shares = response
+                    .public_shares
+                    .iter()
+                    .map(|bytes| parse_point(bytes, "public share"))
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()
+                    .filter(|public_shares| public_shares.len() == paths.len());
+                if public_shares.is_none() {
+                    tracing::warn!(name: "invalid-share-response", name = "invalid-share-response", index);
+                }
+
+                public_shares.map(|public_shares| (index, public_shares))
+            })
+            .collect();
+        self.check_available(responses.len())?;
+
+        let threshold = self.group.threshold as usize;
+        let (base, extra) = responses.split_at(threshold);
+        let base_indexes: Vec<u32> = base.iter().map(|(index, _)| *index).collect();
+        let base_coefficients = coefficients(&base_indexes)?;
+        // Swapping the last share of the base for each extra one must give the same key
+        let checks = extra
+            .iter()
+            .map(|(index, _)| {
+                let mut indexes = base_indexes[..threshold - 1].to_vec();
+                indexes.push(*index);
+                coefficients(&indexes)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        (0..paths.len())
+            .map(|path_idx| {
+                let base_points: Vec<PublicKey> =
+                    base.iter().map(|(_, points)| points[path_idx]).collect();
+                let public_key = combine(&base_coefficients, &base_points)?;
+
+                for ((index, points), check_coefficients) in extra.iter().zip(&checks) {
+                    let mut check_points = base_points[..threshold - 1].to_vec();
+                    check_points.push(points[path_idx]);
+                    if combine(check_coefficients, &check_points)? != public_key {
+                        return Err(Status::internal(format!(
+                            "public share of share signer {index} is inconsistent with the others"
+                        )));
+                    }
+                }
+
+                Ok(ThresholdKey {
+                    public_key,
+                    public_shares: responses
+                        .iter()
+                        .map(|(index, points)| (*index, points[path_idx]))
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Coordinator {
+    async fn declare_keyset_inner(
+        &self,
+        declare_keyset_request: &DeclareKeysetRequest,
+    ) -> Result<DeclareKeysetResponse, Status> {
+        // Same limit as the standalone signer, the biggest key has amount 2^63
+        if declare_keyset_request.max_order > 64 {
+            return Err(server_errors::Error::MaxOrderTooBig(
+                declare_keyset_request.max_order,
+            ))?;
+        }
+        let unit = Unit::from_str(&declare_keyset_request.unit)
+            .map_err(|_| server_errors::Error::UnknownUnit(&declare_keyset_request.unit))?;
+        let max_order = declare_keyset_request
+            .max_order
+            .try_into()
+            .map_err(|_| server_errors::Error::MaxOrderTooBig(declare_keyset_request.max_order))?;
+
+        let keys = self
+            .shares
+            .threshold_keys(&keyset_paths(unit, declare_keyset_request.index, max_order))
+            .await?;
+        let (id, keyset) = build_keyset(unit, declare_keyset_request.index, keys);
+
+        // Persist it first, so that the coordinator never signs with a keyset it would forget on restart
+        self.keyset_registry
+            .register(DeclaredKeyset {
+                id,
+                unit,
+                index: declare_keyset_request.index,
+                max_order,
+            })
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let keys = keyset
+            .keys
+            .iter()
+            .map(|(&amount, key)| Key {
+                amount: amount.into(),
+                pubkey: key.public_key.to_string(),
+            })
+            .collect();
+        self.keysets.write().await.insert(id, Arc::new(keyset));
+
+        Ok(DeclareKeysetResponse {
+            keyset_id: id.to_bytes().to_vec(),
+            keys,
+        })
+    }
+
+    async fn sign_blinded_messages_inner(
+        &self,
+        blinded_messages: Vec<BlindedMessage>,
+    ) -> Result<SignBlindedMessagesResponse, Status> {
+        let messages = {
+            let keysets = self.keysets.read().await;
+
+            blinded_messages
+                .iter()
+                .enumerate()
+                .map(|(idx, blinded_message)| validate_message(idx, blinded_message, &keysets))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        if messages.is_empty() {
+            return Ok(SignBlindedMessagesResponse::default());
+        }
+
+        // First round
+        let commit_request = CommitRequest {
+            messages: messages
+                .iter()
+                .map(|message| PathPoint {
+                    path: Some(message.path.clone()),
+                    point: message.blinded_secret.to_bytes().to_vec(),
+                })
+                .collect(),
+        };
+        let commitments: Vec<(u32, ShareCommitments)> = self
+            .shares
+            .call_shares(&commit_request, |mut client, request| async move {
+                client.commit(request).await
+            })
+            .await
+            .into_iter()
+            .filter(|(index, _)| {
+                messages
+                    .iter()
+                    .all(|message| message.key.public_shares.contains_key(index))
+            })
+            .filter_map(|(index, response)| {
+                let commitments = ShareCommitments::parse(response, messages.len());
+                if commitments.is_none() {
+                    tracing::warn!(name: "invalid-share-response", name = "invalid-share-response", index);
+                }
+
+                commitments.map(|commitments| (index, commitments))
+            })
+            .take(self.shares.group.threshold as usize)
+            .collect();
+        self.shares.check_available(commitments.len())?;
+
+        let indexes: Vec<u32> = commitments.iter().map(|(index, _)| *index).collect();
+        let coefficients = coefficients(&indexes)?;
+
+        let mut signatures = Vec::with_capacity(messages.len());
+        let mut challenges = Vec::with_capacity(messages.len());
+        for (msg_idx, message) in messages.iter().enumerate() {
+            let combine_column = |column: fn(&ShareCommitments) -> &Vec<PublicKey>| {
+                let points: Vec<PublicKey> = commitments
+                    .iter()
+                    .map(|(_, c)| column(c)[msg_idx])
+                    .collect();
+                combine(&coefficients, &points)
+            };
+            let signature = combine_column(|c| &c.partial_signatures)?;
+            let r1 = combine_column(|c| &c.r1s)?;
+            let r2 = combine_column(|c| &c.r2s)?;
+
+            let challenge =
+                SecretKey::from_slice(&hash_e([r1, r2, message.key.public_key, signature]))
+                    .map_err(|e| Status::internal(e.to_string()))?;
+            signatures.push(signature);
+            challenges.push(challenge);
+        }
+
+        // Second round, with the same shares: their nonces are bound to their session
+        let challenge_bytes: Vec<Vec<u8>> = challenges
+            .iter()
+            .map(|e| e.to_secret_bytes().to_vec())
+            .collect();
+        let responses = join_all(commitments.iter().map(|(index, commitments)| {
+            let client = self.shares.share_client(*index);
+            let request = RespondRequest {
+                session_id: commitments.session_id.clone(),
+                challenges: challenge_bytes.clone(),
+            };
+            async move {
+                let mut client = client.ok_or_else(|| Status::internal("unknown share signer"))?;
+                Ok::<_, Status>(client.respond(request).await?.into_inner().responses)
+            }
+        }))
+        .await;
+
+        let mut share_responses: Vec<Vec<SecretKey>> = Vec::with_capacity(responses.len());
+        for ((index, commitments), responses) in commitments.iter().zip(responses) {
+            let responses = responses
+                .map_err(|e| {
+                    Status::unavailable(format!(
+                        "share signer {index} failed to answer the challenges: {}",
+                        e.message()
+                    ))
+                })?
+                .iter()
+                .map(|bytes| SecretKey::from_slice(bytes))
+                .collect::<Result<Vec<_>, _>>()
+                .ok()
+                .filter(|responses| responses.len() == messages.len())
+                .ok_or_else(|| invalid_share(*index))?;
+
+            for (msg_idx, message) in messages.iter().enumerate() {
+                let valid = response_is_valid(
+                    &responses[msg_idx],
+                    &challenges[msg_idx],
+                    &message.blinded_secret,
+                    &message.key.public_shares[index],
+                    commitments,
+                    msg_idx,
+                );
+                if !valid {
+                    return Err(invalid_share(*index));
+                }
+            }
+
+            share_responses.push(responses);
+        }
+
+        let mut dleqs = Vec::with_capacity(messages.len());
+        for (msg_idx, message) in messages.iter().enumerate() {
+            let responses: Vec<SecretKey> = share_responses
+                .iter()
+                .map(|responses| responses[msg_idx].clone())
+                .collect();
+            let s = combine_scalars(&coefficients, &responses)
+                .map_err(|e| Status::internal(e.to_string()))?;
+
+            verify_dleq(
+                message.blinded_secret,
+                signatures[msg_idx],
+                &challenges[msg_idx],
+                &s,
+                message.key.public_key,
+            )
+            .map_err(|e| Status::internal(format!("combined dleq proof is invalid: {e}")))?;
+
+            dleqs.push(BlindSignatureDleq {
+                e: challenges[msg_idx].to_secret_bytes().to_vec(),
+                s: s.to_secret_bytes().to_vec(),
+            });
+        }
+
+        Ok(SignBlindedMessagesResponse {
+            signatures: signatures
+                .iter()
+                .map(|signature| signature.to_bytes().to_vec())
+                .collect(),
+            dleqs,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl signer::Signer for Coordinator {
+    #[instrument(skip(self))]
+    async fn declare_keyset(
+        &self,
+        declare_keyset_request: Request<DeclareKeysetRequest>,
+    ) -> Result<Response<DeclareKeysetResponse>, Status> {
+        let caller = Caller::of(&declare_keyset_request);
+        let declare_keyset_request = declare_keyset_request.into_inner();
+
+        let result = self.declare_keyset_inner(&declare_keyset_request).await;
+        self.audit_log.record(
+            &caller,
+            &AuditEvent::DeclareKeyset {
+                unit: declare_keyset_request.unit,
+                index: declare_keyset_request.index,
+                max_order: declare_keyset_request.max_order,
+            },
+            result.as_ref().map(|_| ()),
+        )?;
+
+        result.map(Response::new)
+    }
+
+    #[instrument(skip(self))]
+    async fn sign_blinded_messages(
+        &self,
+        sign_blinded_messages_request: Request<SignBlindedMessagesRequest>,
+    ) -> Result<Response<SignBlindedMessagesResponse>, Status> {
+        let caller = Caller::of(&sign_blinded_messages_request);
+        let blinded_messages = sign_blinded_messages_request.into_inner().messages;
+        let audit_event = AuditEvent::sign_blinded_messages(&blinded_messages);
+
+        let result = self.sign_blinded_messages_inner(blinded_messages).await;
+        self.audit_log
+            .record(&caller, &audit_event, result.as_ref().map(|_| ()))?;
+
+        result.map(Response::new)
+    }
+
+    #[instrument(skip(self))]
+    async fn verify_proofs(
+        &self,
+        verify_proofs_request: Request<VerifyProofsRequest>,
+    ) -> Result<Response<VerifyProofsResponse>, Status> {
+        let proofs = verify_proofs_request.into_inner().proofs;
+        let mut validation_errors = Vec::new();
+        let mut invalid_proof_indices = Vec::new();
+        let mut to_evaluate = Vec::new();
+
+        {
+            let keysets = self.keysets.read().await;
+            for (idx, proof) in proofs.iter().enumerate() {
+                match validate_proof(proof, &keysets) {
+                    Ok(validated) => match hash_to_curve(proof.secret.as_bytes()) {
+                        Ok(y) => to_evaluate.push((idx as u32, validated, y)),
+                        Err(error) => {
+                            tracing::error!(name: "verify-message", error = %error);
+                            invalid_proof_indices.push(idx as u32)
+                        }
+                    },
+                    Err(validation_error) => validation_errors.push((idx, validation_error)),
+                }
+            }
+        }
+        if !validation_errors.is_empty() {
+            return Err(VerifyProofsErrors(validation_errors).into());
+        }
+
+        if !to_evaluate.is_empty() {
+            let evaluate_request = EvaluateRequest {
+                secrets: to_evaluate
+                    .iter()
+                    .map(|(idx, validated, _)| PathSecret {
+                        path: Some(validated.path.clone()),
+                        secret: proofs[*idx as usize].secret.as_bytes().to_vec(),
+                    })
+                    .collect(),
+            };
+            let evaluations: Vec<(u32, Vec<PublicKey>)> = self
+                .shares
+                .call_shares(&evaluate_request, |mut client, request| async move {
+                    client.evaluate(request).await
+                })
+                .await
+                .into_iter()
+                .filter_map(|(index, response)| {
+                    let partials = response
+                        .evaluations
+                        .iter()
+                        .zip(&to_evaluate)
+                        .map(|(evaluation, (_, validated, y))| {
+                            evaluation_is_valid(evaluation, index, &validated.key, y)
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .filter(|partials| partials.len() == to_evaluate.len());
+                    if partials.is_none() {
+                        tracing::warn!(name: "invalid-share-response", name = "invalid-share-response", index);
+                    }
+
+                    partials.map(|partials| (index, partials))
+                })
+                .take(self.shares.group.threshold as usize)
+                .collect();
+            self.shares.check_available(evaluations.len())?;
+
+            let indexes: Vec<u32> = evaluations.iter().map(|(index, _)| *index).collect();
+            let coefficients = coefficients(&indexes)?;
+            for (eval_idx, (proof_idx, validated, _)) in to_evaluate.iter().enumerate() {
+                let partials: Vec<PublicKey> = evaluations
+                    .iter()
+                    .map(|(_, partials)| partials[eval_idx])
+                    .collect();
+                if combine(&coefficients, &partials)? != validated.signature {
+                    invalid_proof_indices.push(*proof_idx);
+                }
+            }
+            invalid_proof_indices.sort_unstable();
+        }
+
+        Ok(Response::new(VerifyProofsResponse {
+            invalid_proof_indices,
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_root_pub_key(
+        &self,
+        _get_root_pub_key_request: Request<GetRootPubKeyRequest>,
+    ) -> Result<Response<GetRootPubKeyResponse>, Status> {
+        Ok(Response::new(GetRootPubKeyResponse {
+            root_pubkey: self.root_pubkey.to_string(),
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn list_keysets(
+        &self,
+        _list_keysets_request: Request<ListKeysetsRequest>,
+    ) -> Result<Response<ListKeysetsResponse>, Status> {
+        let keysets = self
+            .keyset_registry
+            .keysets()
+            .await
+            .into_iter()
+            .map(|keyset| signer::DeclaredKeyset {
+                keyset_id: keyset.id.to_bytes().to_vec(),
+                unit: keyset.unit.to_string(),
+                index: keyset.index,
+                max_order: keyset.max_order.into(),
+            })
+            .collect();
+
+        Ok(Response::new(ListKeysetsResponse { keysets }))
+    }
+}
+
+/// First round answer of a share signer, one entry per message
+#[derive(Debug)]
+struct ShareCommitments {
+    session_id: Vec<u8>,
+    partial_signatures: Vec<PublicKey>,
+    r1s: Vec<PublicKey>,
+    r2s: Vec<PublicKey>,
+}
+
+impl ShareCommitments {
+    fn parse(response: signer::threshold::CommitResponse, messages: usize) -> Option<Self> {
+        if response.commitments.len() != messages {
+            return None;
+        }
+
+        let mut commitments = Self {
+            session_id: response.session_id,
+            partial_signatures: Vec::with_capacity(messages),
+            r1s: Vec::with_capacity(messages),
+            r2s: Vec::with_capacity(messages),
+        };
+        for commitment in response.commitments {
+            commitments
+                .partial_signatures
+                .push(PublicKey::from_slice(&commitment.partial_signature).ok()?);
+            commitments
+                .r1s
+                .push(PublicKey::from_slice(&commitment.r1).ok()?);
+            commitments
+                .r2s
+                .push(PublicKey::from_slice(&commitment.r2).ok()?);
+        }
+
+        Some(commitments)
+    }
+}
+
+/// A blinded message that passed the same checks as in the standalone signer
+struct ValidatedMessage {
+    path: KeyPath,
+    key: ThresholdKey,
+    blinded_secret: PublicKey,
+}
+
+fn validate_message<'a>(
+    idx: usize,
+    blinded_message: &'a BlindedMessage,
+    keysets: &HashMap<KeysetId, Arc<ThresholdKeyset>>,
+) -> Result<ValidatedMessage, server_errors::Error<'a>> {
+    let amount = Amount::from(blinded_message.amount);
+    if !blinded_message.amount.is_power_of_two() {
+        return Err(server_errors::Error::AmountNotPowerOfTwo(idx, amount));
+    }
+    let keyset_id = KeysetId::from_bytes(&blinded_message.keyset_id)
+        .map_err(|e| server_errors::Error::BadKeysetId(idx, &blinded_message.keyset_id, e))?;
+
+    let keyset = keysets
+        .get(&keyset_id)
+        .ok_or(server_errors::Error::KeysetNotFound(idx, keyset_id))?;
+    let max_amount = keyset.max_amount();
+    if amount > max_amount {
+        return Err(server_errors::Error::AmountGreaterThanMax(
+            idx, amount, max_amount,
+        ));
+    }
+    let key = keyset
+        .keys
+        .get(&amount)
+        .ok_or(server_errors::Error::AmountNotFound(idx, keyset_id, amount))?;
+
+    let blinded_secret = PublicKey::from_slice(&blinded_message.blinded_secret)
+        .map_err(|e| server_errors::Error::BadSecret(idx, e))?;
+
+    Ok(ValidatedMessage {
+        path: amount_path(keyset.unit, keyset.index, blinded_message.amount),
+        key: key.clone(),
+        blinded_secret,
+    })
+}
+
+struct ValidatedProof {
+    path: KeyPath,
+    key: ThresholdKey,
+    signature: PublicKey,
+}
+
+fn validate_proof(
+    proof: &signer::Proof,
+    keysets: &HashMap<KeysetId, Arc<ThresholdKeyset>>,
+) -> Result<ValidatedProof, VerifyProofError> {
+    let keyset_id = KeysetId::from_bytes(&proof.keyset_id)
+        .map_err(|e| VerifyProofError::BadKeysetId(proof.keyset_id.clone(), e))?;
+
+    let amount = Amount::from(proof.amount);
+    if !proof.amount.is_power_of_two() {
+        return Err(VerifyProofError::AmountNotPowerOfTwo(amount));
+    }
+
+    let keyset = keysets
+        .get(&keyset_id)
+        .ok_or(VerifyProofError::KeysetNotFound(keyset_id))?;
+
+    let key = keyset
+        .keys
+        .get(&amount)
+        .ok_or(VerifyProofError::AmountNotFound(keyset_id, amount))?;
+
+    let max_amount = keyset.max_amount();
+    if amount > max_amount {
+        return Err(VerifyProofError::AmountGreaterThanMax(amount, max_amount));
+    }
+
+    let signature = PublicKey::from_slice(&proof.unblind_signature)
+        .map_err(VerifyProofError::InvalidSignature)?;
+
+    Ok(ValidatedProof {
+        path: amount_path(keyset.unit, keyset.index, proof.amount),
+        key: key.clone(),
+        signature,
+    })
+}
+
+/// Check the DLEQ proof a share signer attached to its partial evaluation of `k_i*Y`
+fn evaluation_is_valid(
+    evaluation: &signer::threshold::Evaluation,
+    index: u32,
+    key: &ThresholdKey,
+    y: &PublicKey,
+) -> Option<PublicKey> {
+    let public_share = key.public_shares.get(&index)?;
+    let partial = PublicKey::from_slice(&evaluation.partial).ok()?;
+    let dleq = evaluation.dleq.as_ref()?;
+    let e = SecretKey::from_slice(&dleq.e).ok()?;
+    let s = SecretKey::from_slice(&dleq.s).ok()?;
+
+    verify_dleq(*y, partial, &e, &s, *public_share)
+        .ok()
+        .map(|_| partial)
+}
+
+/// `s_i*G == R1_i + e*K_i` and `s_i*B_ == R2_i + e*C_i`
+fn response_is_valid(
+    response: &SecretKey,
+    challenge: &SecretKey,
+    blinded_secret: &PublicKey,
+    public_share: &PublicKey,
+    commitments: &ShareCommitments,
+    msg_idx: usize,
+) -> bool {
+    let e = challenge.as_scalar();
+    let check = || -> Result<bool, bitcoin::secp256k1::Error> {
+        let lhs1 = response.public_key();
+        let rhs1 = commitments.r1s[msg_idx].combine(&public_share.mul_tweak(&SECP256K1, &e)?)?;
+        let lhs2 = blinded_secret.mul_tweak(&SECP256K1, &response.as_scalar())?;
+        let rhs2 = commitments.r2s[msg_idx]
+            .combine(&commitments.partial_signatures[msg_idx].mul_tweak(&SECP256K1, &e)?)?;
+
+        Ok(*lhs1 == rhs1 && lhs2 == rhs2)
+    };
+
+    check().unwrap_or(false)
+}
+
+fn invalid_share(index: u32) -> Status {
+    tracing::warn!(name: "invalid-share-response", name = "invalid-share-response", index);
+    Status::internal(format!("share signer {index} returned an invalid response"))
+}
+
+/// Ask the share signers for the group parameters, until one answers
+async fn discover_group(shares: &[Share]) -> Result<Group, Error> {
+    let infos = join_all(shares.iter().map(|share| async {
+        share
+            .client
+            .clone()
+            .get_share_info(GetShareInfoRequest {})
+            .await
+            .map(Response::into_inner)
+    }))
+    .await;
+
+    let mut group = None;
+    let mut last_error = None;
+    for (share, info) in shares.iter().zip(infos) {
+        match info {
+            Ok(info) => {
+                let info_group = Group {
+                    threshold: info.threshold,
+                    total: info.total,
+                };
+                if *group.get_or_insert(info_group) != info_group {
+                    return Err(Error::InconsistentGroup);
+                }
+                let _ = share.index.set(info.index);
+            }
+            Err(e) => {
+                tracing::warn!(name: "share-unreachable", name = "share-unreachable", url = share.url, error = %e);
+                last_error = Some(Error::ShareUnreachable(share.url.clone(), Box::new(e)));
+            }
+        }
+    }
+
+    let mut indexes: Vec<u32> = shares
+        .iter()
+        .filter_map(|s| s.index.get().copied())
+        .collect();
+    indexes.sort_unstable();
+    if indexes.windows(2).any(|w| w[0] == w[1]) {
+        return Err(Error::InconsistentGroup);
+    }
+
+    match (group, last_error) {
+        (Some(group), _) => Ok(group),
+        (None, Some(e)) => Err(e),
+        (None, None) => Err(Error::NotEnoughShares(0, 2)),
+    }
+}
+
+fn root_path() -> KeyPath {
+    KeyPath {
+        unit: String::new(),
+        index: 0,
+        amount: 0,
+    }
+}
+
+fn amount_path(unit: Unit, index: u32, amount: u64) -> KeyPath {
+    KeyPath {
+        unit: unit.to_string(),
+        index,
+        amount,
+    }
+}
+
+/// The paths of the keys of amounts `2^0` to `2^(max_order - 1)`, like `MintKeySet::generate`
+fn keyset_paths(unit: Unit, index: u32, max_order: u8) -> Vec<KeyPath> {
+    (0..max_order)
+        .map(|order| amount_path(unit, index, 1 << order))
+        .collect()
+}
+
+fn build_keyset(unit: Unit, index: u32, keys: Vec<ThresholdKey>) -> (KeysetId, ThresholdKeyset) {
+    let keys: BTreeMap<Amount, ThresholdKey> = keys
+        .into_iter()
+        .enumerate()
+        .map(|(order, key)| (Amount::from(1u64 << order), key))
+        .collect();
+    let id = KeysetId::from_iter(keys.values().map(|key| key.public_key));
+
+    (id, ThresholdKeyset { unit, index, keys })
+}
+
+fn coefficients(indexes: &[u32]) -> Result<Vec<SecretKey>, Status> {
+    lagrange_coefficients(indexes).map_err(|e| Status::internal(e.to_string()))
+}
+
+fn combine(coefficients: &[SecretKey], points: &[PublicKey]) -> Result<PublicKey, Status> {
+    combine_points(coefficients, points).map_err(|e| Status::internal(e.to_string()))
+}
+
+#[cfg(feature = "tls")]
+fn share_tls_config() -> Result<Option<tonic::transport::ClientTlsConfig>, Error> {
+    use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+    let Ok(ca_path) = std::env::var(SHARE_TLS_CA_PATH_ENV_VAR) else {
+        return Ok(None);
+    };
+    let cert_path = env_var(SHARE_TLS_CERT_PATH_ENV_VAR)?;
+    let key_path = env_var(SHARE_TLS_KEY_PATH_ENV_VAR)?;
+
+    let read = |path: &String| std::fs::read(path).map_err(|e| Error::Io(path.clone(), e));
+    let ca = Certificate::from_pem(read(&ca_path)?);
+    let identity = Identity::from_pem(read(&cert_path)?, read(&key_path)?);
+
+    Ok(Some(
+        ClientTlsConfig::new().ca_certificate(ca).identity(identity),
+    ))
+}
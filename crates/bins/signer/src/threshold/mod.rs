@@ -0,0 +1,124 @@
+//! Threshold signing
+//!
+//! Selected with `SIGNER_MODE`:
+//! - `share`: holds a share of every key, see [`signer::shares`], and serves the
+//!   [`signer::threshold::ThresholdShare`] service. Only the coordinator should be allowed to
+//!   call it, using [`crate::auth`].
+//! - `coordinator`: holds no secret. Serves the regular `Signer` service to the node,
+//!   combining the partial signatures of `threshold` share signers into standard BDHKE
+//!   signatures, along with their NUT-12 DLEQ proofs.
+//!
+//! Compromising a single host is not enough to get the keys anymore. It still lets the
+//! attacker ask for signatures through the coordinator, which is why the share signers
+//! audit every signing session they take part in.
+
+use std::str::FromStr;
+
+use nuts::nut01::PublicKey;
+use signer::shares::KeyPath;
+use tonic::Status;
+
+mod coordinator;
+mod share;
+
+pub use coordinator::Coordinator;
+pub use share::ShareSigner;
+
+const MODE_ENV_VAR: &str = "SIGNER_MODE";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("env var `{0}` should be set: {1}")]
+    Env(&'static str, #[source] std::env::VarError),
+    #[error("unknown signer mode `{0}`, expected `standalone`, `share` or `coordinator`")]
+    UnknownMode(String),
+    #[error("failed to read `{0}`: {1}")]
+    Io(String, #[source] std::io::Error),
+    #[error("invalid share file: {0}")]
+    ShareFile(#[from] serde_json::Error),
+    #[error(transparent)]
+    Shares(#[from] signer::shares::Error),
+    #[error("invalid share signer url `{0}`: {1}")]
+    Url(String, #[source] tonic::codegen::http::uri::InvalidUri),
+    #[error("failed to reach share signer `{0}`: {1}")]
+    ShareUnreachable(String, #[source] Box<Status>),
+    #[error("share signers disagree on the group parameters")]
+    InconsistentGroup,
+    #[error("failed to compute the root public key: {0}")]
+    RootKey(#[source] Box<Status>),
+    #[error("{0} share signers configured, at least {1} are needed")]
+    NotEnoughShares(usize, u32),
+    #[error("invalid `KEYSET_REGISTRY_MAC_KEY`, expected 32 hex encoded bytes")]
+    InvalidMacKey,
+    #[error(transparent)]
+    KeysetRegistry(#[from] crate::keyset_registry::Error),
+    #[error("failed to rebuild keyset {0}: {1}")]
+    Rebuild(nuts::nut02::KeysetId, #[source] Box<Status>),
+    #[cfg(feature = "tls")]
+    #[error(transparent)]
+    Transport(#[from] tonic::transport::Error),
+}
+
+/// How this signer process takes part in signing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Holds the root key and signs by itself
+    #[default]
+    Standalone,
+    Share,
+    Coordinator,
+}
+
+impl FromStr for Mode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standalone" => Ok(Self::Standalone),
+            "share" => Ok(Self::Share),
+            "coordinator" => Ok(Self::Coordinator),
+            _ => Err(Error::UnknownMode(s.to_string())),
+        }
+    }
+}
+
+impl Mode {
+    pub fn from_env() -> Result<Self, Error> {
+        match std::env::var(MODE_ENV_VAR) {
+            Ok(mode) => mode.parse(),
+            Err(std::env::VarError::NotPresent) => Ok(Self::default()),
+            Err(e) => Err(Error::Env(MODE_ENV_VAR, e)),
+        }
+    }
+}
+
+fn env_var(name: &'static str) -> Result<String, Error> {
+    std::env::var(name).map_err(|e| Error::Env(name, e))
+}
+
+fn parse_key_path(path: Option<&signer::threshold::KeyPath>) -> Result<KeyPath, Status> {
+    let path = path.ok_or_else(|| Status::invalid_argument("missing key path"))?;
+    if path.unit.is_empty() {
+        return Ok(KeyPath::Root);
+    }
+
+    let unit = starknet_types::Unit::from_str(&path.unit)
+        .map_err(|_| Status::invalid_argument(format!("unknown unit `{}`", path.unit)))?;
+    if !path.amount.is_power_of_two() {
+        return Err(Status::invalid_argument(format!(
+            "amount {} is not a power of two",
+            path.amount
+        )));
+    }
+
+    Ok(KeyPath::Amount {
+        unit: unit.into(),
+        index: path.index,
+        amount: path.amount,
+    })
+}
+
+fn parse_point(bytes: &[u8], field: &str) -> Result<PublicKey, Status> {
+    PublicKey::from_slice(bytes)
+        .map_err(|e| Status::invalid_argument(format!("invalid {field}: {e}")))
+}
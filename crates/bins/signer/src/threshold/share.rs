@@ -0,0 +1,346 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use nuts::{
+    SECP256K1,
+    dhke::{hash_to_curve, sign_message},
+    nut01::{PublicKey, SecretKey},
+    nut12::calculate_dleq,
+};
+use signer::{
+    BlindSignatureDleq,
+    shares::{KeyPath, ShareFile},
+    threshold::{
+        CommitRequest, CommitResponse, Commitment, EvaluateRequest, EvaluateResponse, Evaluation,
+        GetPublicSharesRequest, GetPublicSharesResponse, GetShareInfoRequest, GetShareInfoResponse,
+        PathPoint, PathSecret, RespondRequest, RespondResponse, ThresholdShare,
+    },
+};
+use tokio::sync::Mutex;
+use tonic::{Request, Response, Status};
+use tracing::instrument;
+
+use super::{Error, env_var, parse_key_path, parse_point};
+use crate::{
+    audit::{AuditEvent, AuditLog},
+    auth::Caller,
+};
+
+const SHARE_PATH_ENV_VAR: &str = "THRESHOLD_SHARE_PATH";
+
+/// How long the coordinator has to answer the challenges of a session
+const SESSION_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct Session {
+    created_at: Instant,
+    /// The nonce and key share of each message
+    secrets: Vec<(SecretKey, SecretKey)>,
+}
+
+/// Signer holding one share of every key
+#[derive(Debug)]
+pub struct ShareSigner {
+    share_file: ShareFile,
+    sessions: Mutex<HashMap<[u8; 16], Session>>,
+    audit_log: AuditLog,
+}
+
+impl ShareSigner {
+    /// Load the share file at `THRESHOLD_SHARE_PATH`
+    pub fn from_env(audit_log: AuditLog) -> Result<Self, Error> {
+        let path = PathBuf::from(env_var(SHARE_PATH_ENV_VAR)?);
+        let content =
+            std::fs::read(&path).map_err(|e| Error::Io(path.to_string_lossy().into_owned(), e))?;
+        let share_file: ShareFile = serde_json::from_slice(&content)?;
+        share_file.validate()?;
+
+        tracing::info!(
+            name: "share-loaded",
+            name = "share-loaded",
+            index = share_file.index,
+            threshold = share_file.threshold,
+            total = share_file.total,
+        );
+
+        Ok(Self {
+            share_file,
+            sessions: Mutex::new(HashMap::new()),
+            audit_log,
+        })
+    }
+
+    fn key_share(&self, path: KeyPath) -> Result<SecretKey, Status> {
+        self.share_file
+            .secret_share(path)
+            .map_err(|e| Status::internal(e.to_string()))
+    }
+
+    fn commit_inner(&self, messages: &[PathPoint]) -> Result<(Vec<Commitment>, Session), Status> {
+        let mut commitments = Vec::with_capacity(messages.len());
+        let mut secrets = Vec::with_capacity(messages.len());
+
+        for message in messages {
+            let key_share = self.key_share(parse_key_path(message.path.as_ref())?)?;
+            let blinded_message = parse_point(&message.point, "blinded message")?;
+
+            let partial_signature = sign_message(&key_share, &blinded_message)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            let nonce = SecretKey::generate();
+            let r2: PublicKey = blinded_message
+                .mul_tweak(&SECP256K1, &nonce.as_scalar())
+                .map_err(|e| Status::invalid_argument(e.to_string()))?
+                .into();
+
+            commitments.push(Commitment {
+                partial_signature: partial_signature.to_bytes().to_vec(),
+                r1: nonce.public_key().to_bytes().to_vec(),
+                r2: r2.to_bytes().to_vec(),
+            });
+            secrets.push((nonce, key_share));
+        }
+
+        Ok((
+            commitments,
+            Session {
+                created_at: Instant::now(),
+                secrets,
+            },
+        ))
+    }
+
+    /// Evaluate `k_i*Y` for the `Y = hash_to_curve(secret)` of each proof
+    ///
+    /// Hashing the secrets here, rather than accepting points, keeps the share signer from
+    /// signing blinded messages outside of an audited [`ThresholdShare::commit`].
+    fn evaluate_inner(&self, secrets: &[PathSecret]) -> Result<Vec<Evaluation>, Status> {
+        secrets
+            .iter()
+            .map(|secret| {
+                let key_share = self.key_share(parse_key_path(secret.path.as_ref())?)?;
+                let y = hash_to_curve(&secret.secret)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+                let partial = sign_message(&key_share, &y)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))?;
+                let dleq = calculate_dleq(partial, &y, &key_share)
+                    .map_err(|e| Status::internal(e.to_string()))?;
+
+                Ok(Evaluation {
+                    partial: partial.to_bytes().to_vec(),
+                    dleq: Some(BlindSignatureDleq {
+                        e: dleq.e.to_secret_bytes().to_vec(),
+                        s: dleq.s.to_secret_bytes().to_vec(),
+                    }),
+                })
+            })
+            .collect()
+    }
+}
+
+#[tonic::async_trait]
+impl ThresholdShare for ShareSigner {
+    #[instrument(skip(self))]
+    async fn get_share_info(
+        &self,
+        _request: Request<GetShareInfoRequest>,
+    ) -> Result<Response<GetShareInfoResponse>, Status> {
+        Ok(Response::new(GetShareInfoResponse {
+            index: self.share_file.index,
+            threshold: self.share_file.threshold,
+            total: self.share_file.total,
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_public_shares(
+        &self,
+        request: Request<GetPublicSharesRequest>,
+    ) -> Result<Response<GetPublicSharesResponse>, Status> {
+        let public_shares = request
+            .into_inner()
+            .paths
+            .iter()
+            .map(|path| {
+                let key_share = self.key_share(parse_key_path(Some(path))?)?;
+                Ok(key_share.public_key().to_bytes().to_vec())
+            })
+            .collect::<Result<_, Status>>()?;
+
+        Ok(Response::new(GetPublicSharesResponse { public_shares }))
+    }
+
+    #[instrument(skip(self))]
+    async fn commit(
+        &self,
+        request: Request<CommitRequest>,
+    ) -> Result<Response<CommitResponse>, Status> {
+        let caller = Caller::of(&request);
+        let messages = request.into_inner().messages;
+        let audit_event = AuditEvent::threshold_commit(&messages);
+
+        let result = self.commit_inner(&messages);
+        self.audit_log
+            .record(&caller, &audit_event, result.as_ref().map(|_| ()))?;
+        let (commitments, session) = result?;
+
+        let session_id: [u8; 16] = rand::random();
+        let mut sessions = self.sessions.lock().await;
+        sessions.retain(|_, session| session.created_at.elapsed() < SESSION_TTL);
+        sessions.insert(session_id, session);
+
+        Ok(Response::new(CommitResponse {
+            session_id: session_id.to_vec(),
+            commitments,
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn respond(
+        &self,
+        request: Request<RespondRequest>,
+    ) -> Result<Response<RespondResponse>, Status> {
+        let request = request.into_inner();
+        let session_id: [u8; 16] = request
+            .session_id
+            .as_slice()
+            .try_into()
+            .map_err(|_| Status::invalid_argument("invalid session id"))?;
+
+        // Removed whatever the outcome: answering two different challenges with the same
+        // nonce would reveal the key share
+        let session = self
+            .sessions
+            .lock()
+            .await
+            .remove(&session_id)
+            .filter(|session| session.created_at.elapsed() < SESSION_TTL)
+            .ok_or_else(|| Status::not_found("unknown or expired session"))?;
+        if request.challenges.len() != session.secrets.len() {
+            return Err(Status::invalid_argument(format!(
+                "expected {} challenges, got {}",
+                session.secrets.len(),
+                request.challenges.len()
+            )));
+        }
+
+        let responses = request
+            .challenges
+            .iter()
+            .zip(&session.secrets)
+            .map(|(challenge, (nonce, key_share))| {
+                let challenge = SecretKey::from_slice(challenge)
+                    .map_err(|e| Status::invalid_argument(format!("invalid challenge: {e}")))?;
+                // s = r + e*k
+                let response: SecretKey = challenge
+                    .mul_tweak(&key_share.as_scalar())
+                    .and_then(|ek| ek.add_tweak(&nonce.as_scalar()))
+                    .map_err(|e| Status::internal(e.to_string()))?
+                    .into();
+
+                Ok(response.to_secret_bytes().to_vec())
+            })
+            .collect::<Result<_, Status>>()?;
+
+        Ok(Response::new(RespondResponse { responses }))
+    }
+
+    #[instrument(skip(self))]
+    async fn evaluate(
+        &self,
+        request: Request<EvaluateRequest>,
+    ) -> Result<Response<EvaluateResponse>, Status> {
+        let caller = Caller::of(&request);
+        let secrets = request.into_inner().secrets;
+        let audit_event = AuditEvent::threshold_evaluate(&secrets);
+
+        let result = self.evaluate_inner(&secrets);
+        self.audit_log
+            .record(&caller, &audit_event, result.as_ref().map(|_| ()))?;
+
+        result.map(|evaluations| Response::new(EvaluateResponse { evaluations }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use signer::{shares::deal, threshold::KeyPath as PathMessage};
+
+    use super::*;
+
+    fn path(unit: &str) -> Option<PathMessage> {
+        Some(PathMessage {
+            unit: unit.to_string(),
+            index: 1,
+            amount: 8,
+        })
+    }
+
+    // - evaluate a proof secret and check the partial is `k_i*hash_to_curve(secret)`
+    // - fail to evaluate one of an unknown unit
+    // - check both calls were audited
+    #[tokio::test]
+    async fn evaluations_hash_the_secrets_and_are_audited() {
+        let audit_path =
+            std::env::temp_dir().join(format!("signer-share-audit-{}.log", rand::random::<u64>()));
+        let share_file = deal(2, 3).unwrap().remove(0);
+        let key_share = share_file
+            .secret_share(
+                parse_key_path(path(starknet_types::Unit::MilliStrk.as_str()).as_ref()).unwrap(),
+            )
+            .unwrap();
+        let share_signer = ShareSigner {
+            share_file,
+            sessions: Mutex::new(HashMap::new()),
+            audit_log: AuditLog::open(audit_path.clone()).unwrap(),
+        };
+
+        let secret = b"407915bc212be61a77e3e6d2aeb4c727980bda51cd06a6afc29e2861768a7837";
+        let evaluations = share_signer
+            .evaluate(Request::new(EvaluateRequest {
+                secrets: vec![PathSecret {
+                    path: path(starknet_types::Unit::MilliStrk.as_str()),
+                    secret: secret.to_vec(),
+                }],
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .evaluations;
+        let y = hash_to_curve(secret).unwrap();
+        assert_eq!(
+            evaluations[0].partial,
+            sign_message(&key_share, &y).unwrap().to_bytes().to_vec()
+        );
+
+        share_signer
+            .evaluate(Request::new(EvaluateRequest {
+                secrets: vec![PathSecret {
+                    path: path("unknown"),
+                    secret: secret.to_vec(),
+                }],
+            }))
+            .await
+            .unwrap_err();
+
+        let records: Vec<serde_json::Value> = std::fs::read_to_string(&audit_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_file(&audit_path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["method"], "threshold_evaluate");
+        assert_eq!(
+            records[0]["amounts"][format!("{}/1", starknet_types::Unit::MilliStrk)],
+            8
+        );
+        assert_eq!(records[0]["secrets"], 1);
+        assert!(records[0]["error"].is_null());
+        assert_eq!(records[1]["method"], "threshold_evaluate");
+        assert!(records[1]["error"].is_string());
+    }
+}
@@ -1,17 +1,20 @@
 use std::str::FromStr;
 
+use futures::StreamExt;
+
 use node_client::{
     AcknowledgeRequest, GetKeysRequest, NodeClient, QuoteStateRequest, RestoreRequest,
 };
 use nuts::{
     Amount,
     nut00::{BlindSignature, BlindedMessage},
-    nut01::{self, PublicKey},
+    nut01::{self, PublicKey, SecretKey},
     nut02::{self, KeysetId},
     nut03::{SwapRequest, SwapResponse},
     nut04::{self, MintQuoteResponse, MintQuoteState, MintRequest},
     nut05::{MeltQuoteState, MeltRequest, MeltResponse},
     nut07::{CheckStateResponse, ProofCheckState},
+    nut12::BlindSignatureDleq,
 };
 use tonic::transport::Channel;
 use tonic_types::StatusExt;
@@ -19,8 +22,9 @@ use tonic_types::StatusExt;
 use crate::{
     CashuClient, CashuClientError, ClientKey, ClientKeysResponse, ClientKeyset, ClientKeysetKeys,
     ClientKeysetsResponse, ClientMeltQuoteRequest, ClientMeltQuoteResponse, ClientMintQuoteRequest,
-    ClientRestoreResponse, Error, NodeInfoResponse,
-    proof_errors_handler::{ProofError, ProofErrorKind, extract_proof_index},
+    ClientNotification, ClientNotificationStream, ClientRestoreResponse, ClientSubscribeRequest,
+    ClientSubscriptionKind, Error, NodeInfoResponse,
+    proof_errors_handler::proof_errors_from_violations,
 };
 
 #[derive(Debug, Error)]
@@ -39,6 +43,20 @@ pub enum Error {
     InvalidFormat,
     #[error("invalid index: {0}")]
     ParseInt(#[from] std::num::ParseIntError),
+    #[error("invalid dleq: {0}")]
+    Dleq(nut01::Error),
+}
+
+fn parse_blind_signature_dleq(
+    dleq: Option<node_client::BlindSignatureDleq>,
+) -> Result<Option<BlindSignatureDleq>, Error> {
+    dleq.map(|dleq| {
+        Ok(BlindSignatureDleq {
+            e: SecretKey::from_slice(&dleq.e).map_err(Error::Dleq)?,
+            s: SecretKey::from_slice(&dleq.s).map_err(Error::Dleq)?,
+        })
+    })
+    .transpose()
 }
 
 impl From<Error> for CashuClientError {
@@ -46,31 +64,12 @@ impl From<Error> for CashuClientError {
         match value {
             Error::Grpc(status) => {
                 if let Some(bad_request) = status.get_error_details().bad_request() {
-                    let mut spent = Vec::new();
-                    let mut invalid = Vec::new();
-                    for violation in &bad_request.field_violations {
-                        let idx = extract_proof_index(&violation.field).unwrap_or(0);
-                        if violation.description.contains("already spent") {
-                            spent.push(idx);
-                        } else if violation
-                            .description
-                            .contains("failed cryptographic verification")
-                        {
-                            invalid.push(idx);
-                        }
-                    }
-                    let errs = vec![
-                        ProofError {
-                            indexes: spent,
-                            kind: ProofErrorKind::AlreadySpent,
-                        },
-                        ProofError {
-                            indexes: invalid,
-                            kind: ProofErrorKind::FailCryptoVerify,
-                        },
-                    ];
-
-                    return CashuClientError::Proof(errs);
+                    return CashuClientError::Proof(proof_errors_from_violations(
+                        bad_request
+                            .field_violations
+                            .iter()
+                            .map(|v| (v.field.as_str(), v.description.as_str())),
+                    ));
                 } else if status.message() == "inactive keyset"
                     && status.code() == tonic::Code::FailedPrecondition
                 {
@@ -92,6 +91,51 @@ impl From<Error> for CashuClientError {
     }
 }
 
+fn mint_quote_response_from_proto(
+    resp: node_client::MintQuoteResponse,
+    method: &str,
+) -> Result<MintQuoteResponse<String>, Error> {
+    Ok(MintQuoteResponse {
+        quote: resp.quote,
+        request: resp.request,
+        state: MintQuoteState::try_from(
+            node_client::MintQuoteState::try_from(resp.state).map_err(|_e| {
+                Error::InvalidState {
+                    method: method.to_string(),
+                }
+            })?,
+        )
+        .map_err(|_e| Error::InvalidState {
+            method: method.to_string(),
+        })?,
+        expiry: resp.expiry,
+        amount_paid: resp.amount_paid.into(),
+    })
+}
+
+fn melt_quote_response_from_proto(
+    resp: node_client::MeltQuoteResponse,
+    method: &str,
+) -> Result<ClientMeltQuoteResponse, Error> {
+    Ok(ClientMeltQuoteResponse {
+        quote: resp.quote,
+        amount: resp.amount.into(),
+        unit: resp.unit,
+        expiry: resp.expiry,
+        state: MeltQuoteState::try_from(
+            node_client::MeltQuoteState::try_from(resp.state).map_err(|_e| {
+                Error::InvalidState {
+                    method: method.to_string(),
+                }
+            })?,
+        )
+        .map_err(|_e| Error::InvalidState {
+            method: method.to_string(),
+        })?,
+        transfer_ids: Some(resp.transfer_ids),
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct GrpcClient {
     pub node: NodeClient<Channel>,
@@ -117,6 +161,8 @@ impl CashuClient for GrpcClient {
                     id: k.id,
                     unit: k.unit,
                     active: k.active,
+                    input_fee_ppk: k.input_fee_ppk,
+                    final_expiry: k.final_expiry,
                 })
                 .collect(),
         })
@@ -144,6 +190,7 @@ impl CashuClient for GrpcClient {
                         id: k.id,
                         unit: k.unit,
                         active: k.active,
+                        input_fee_ppk: k.input_fee_ppk,
                         keys: k
                             .keys
                             .into_iter()
@@ -192,6 +239,7 @@ impl CashuClient for GrpcClient {
                 method: "Mint_quote".to_string(),
             })?,
             expiry: resp.expiry,
+            amount_paid: resp.amount_paid.into(),
         };
         Ok(mint_quote_response)
     }
@@ -228,6 +276,7 @@ impl CashuClient for GrpcClient {
                     amount: s.amount.into(),
                     keyset_id: KeysetId::from_bytes(&s.keyset_id).map_err(Error::KeysetId)?,
                     c: PublicKey::from_slice(&s.blind_signature).map_err(Error::PublicKey)?,
+                    dleq: parse_blind_signature_dleq(s.dleq)?,
                 })
             })
             .collect::<Result<Vec<_>, _>>()?;
@@ -247,22 +296,7 @@ impl CashuClient for GrpcClient {
             .map_err(|e| CashuClientError::from(Error::Grpc(e)))?
             .into_inner();
 
-        let mint_quote_response = MintQuoteResponse {
-            quote: resp.quote,
-            request: resp.request,
-            state: MintQuoteState::try_from(
-                node_client::MintQuoteState::try_from(resp.state).map_err(|_e| {
-                    Error::InvalidState {
-                        method: "mint_quote_state".to_string(),
-                    }
-                })?,
-            )
-            .map_err(|_e| Error::InvalidState {
-                method: "mint_quote_state".to_string(),
-            })?,
-            expiry: resp.expiry,
-        };
-        Ok(mint_quote_response)
+        Ok(mint_quote_response_from_proto(resp, "mint_quote_state")?)
     }
 
     async fn swap(&mut self, req: SwapRequest) -> Result<SwapResponse, CashuClientError> {
@@ -275,6 +309,7 @@ impl CashuClient for GrpcClient {
                     keyset_id: p.keyset_id.to_bytes().to_vec(),
                     secret: p.secret.to_string(),
                     unblind_signature: p.c.to_bytes().to_vec(),
+                    witness: p.witness.map(|w| w.to_string()),
                 })
                 .collect(),
             outputs: req
@@ -307,6 +342,7 @@ impl CashuClient for GrpcClient {
                                 .map_err(Error::KeysetId)?,
                             c: PublicKey::from_slice(&s.blind_signature)
                                 .map_err(Error::PublicKey)?,
+                            dleq: parse_blind_signature_dleq(s.dleq)?,
                         })
                     },
                 )
@@ -370,6 +406,7 @@ impl CashuClient for GrpcClient {
                     keyset_id: p.keyset_id.to_bytes().to_vec(),
                     secret: p.secret.to_string(),
                     unblind_signature: p.c.to_bytes().to_vec(),
+                    witness: p.witness.map(|w| w.to_string()),
                 })
                 .collect(),
         };
@@ -409,24 +446,7 @@ impl CashuClient for GrpcClient {
             .map_err(|e| CashuClientError::from(Error::Grpc(e)))?
             .into_inner();
 
-        let melt_quote_response = crate::ClientMeltQuoteResponse {
-            quote: resp.quote,
-            amount: resp.amount.into(),
-            unit: resp.unit,
-            expiry: resp.expiry,
-            state: MeltQuoteState::try_from(
-                node_client::MeltQuoteState::try_from(resp.state).map_err(|_e| {
-                    Error::InvalidState {
-                        method: "melt_quote_state".to_string(),
-                    }
-                })?,
-            )
-            .map_err(|_e| Error::InvalidState {
-                method: "melt_quote_state".to_string(),
-            })?,
-            transfer_ids: Some(resp.transfer_ids),
-        };
-        Ok(melt_quote_response)
+        Ok(melt_quote_response_from_proto(resp, "melt_quote_state")?)
     }
 
     async fn info(&mut self) -> Result<NodeInfoResponse, CashuClientError> {
@@ -521,9 +541,64 @@ impl CashuClient for GrpcClient {
                         amount: s.amount.into(),
                         keyset_id: KeysetId::from_bytes(&s.keyset_id).map_err(Error::KeysetId)?,
                         c: PublicKey::from_slice(&s.blind_signature).map_err(Error::PublicKey)?,
+                        dleq: parse_blind_signature_dleq(s.dleq)?,
                     })
                 })
                 .collect::<Result<Vec<_>, Error>>()?,
         })
     }
+
+    async fn subscribe(
+        &mut self,
+        req: ClientSubscribeRequest,
+    ) -> Result<ClientNotificationStream, CashuClientError> {
+        let kind = match req.kind {
+            ClientSubscriptionKind::MintQuote => node_client::SubscriptionKind::SkMintQuote,
+            ClientSubscriptionKind::MeltQuote => node_client::SubscriptionKind::SkMeltQuote,
+            ClientSubscriptionKind::ProofState => node_client::SubscriptionKind::SkProofState,
+        };
+
+        let stream = self
+            .node
+            .subscribe(node_client::SubscribeRequest {
+                kind: kind.into(),
+                method: req.method,
+                filters: req.filters,
+            })
+            .await
+            .map_err(|e| CashuClientError::from(Error::Grpc(e)))?
+            .into_inner();
+
+        Ok(Box::pin(stream.map(|resp| {
+            let payload = resp
+                .map_err(Error::Grpc)?
+                .payload
+                .ok_or(Error::InvalidState {
+                    method: "subscribe".to_string(),
+                })?;
+
+            let notification = match payload {
+                node_client::subscribe_response::Payload::MintQuote(resp) => {
+                    ClientNotification::MintQuote(mint_quote_response_from_proto(
+                        resp,
+                        "subscribe",
+                    )?)
+                }
+                node_client::subscribe_response::Payload::MeltQuote(resp) => {
+                    ClientNotification::MeltQuote(melt_quote_response_from_proto(
+                        resp,
+                        "subscribe",
+                    )?)
+                }
+                node_client::subscribe_response::Payload::ProofState(state) => {
+                    ClientNotification::ProofState(ProofCheckState {
+                        y: PublicKey::from_slice(&state.y).map_err(Error::PublicKey)?,
+                        state: state.state.into(),
+                    })
+                }
+            };
+
+            Ok(notification)
+        })))
+    }
 }
@@ -0,0 +1,617 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use futures::{SinkExt, StreamExt};
+use nuts::{
+    Amount,
+    nut00::{BlindedMessage, CashuError, InvalidField, error_code},
+    nut01::{self, PublicKey},
+    nut02::{self, KeysetId},
+    nut03::{SwapRequest, SwapResponse},
+    nut04::{MintQuoteResponse, MintRequest, MintResponse},
+    nut05::{MeltQuoteState, MeltRequest, MeltResponse},
+    nut07::{CheckStateResponse, ProofCheckState},
+    nut09::{RestoreRequest, RestoreResponse},
+    nut17::{
+        JSON_RPC_VERSION, Kind, Params, WsErrorResponse, WsMethodRequest, WsNotification,
+        WsRequest, WsResponse,
+    },
+    nut19::AcknowledgeRequest,
+};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use crate::{
+    CashuClient, CashuClientError, ClientKey, ClientKeysResponse, ClientKeyset, ClientKeysetKeys,
+    ClientKeysetsResponse, ClientMeltQuoteRequest, ClientMeltQuoteResponse, ClientMintQuoteRequest,
+    ClientNotification, ClientNotificationStream, ClientRestoreResponse, ClientSubscribeRequest,
+    ClientSubscriptionKind, Error, NodeInfoResponse,
+    proof_errors_handler::proof_errors_from_violations,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("node answered {status} with error {code}: {detail}")]
+    Node {
+        status: StatusCode,
+        code: u16,
+        detail: String,
+        fields: Vec<InvalidField>,
+    },
+    #[error("node answered {status}: {body}")]
+    UnexpectedResponse { status: StatusCode, body: String },
+    #[error("url `{0}` cannot be used as a base url")]
+    InvalidBaseUrl(Url),
+    #[error(transparent)]
+    KeysetId(nut02::Error),
+    #[error(transparent)]
+    PublicKey(nut01::Error),
+    #[error("invalid amount: {0}")]
+    Amount(#[from] std::num::ParseIntError),
+    #[error("websocket error: {0}")]
+    WebSocket(Box<tungstenite::Error>),
+    #[error("invalid websocket message: {0}")]
+    InvalidMessage(#[from] serde_json::Error),
+    #[error("subscription rejected with error {code}: {message}")]
+    SubscriptionRejected { code: i32, message: String },
+    #[error("websocket closed by the node")]
+    WebSocketClosed,
+}
+
+impl From<Error> for CashuClientError {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Node {
+                code, ref fields, ..
+            } if code == error_code::PROOF_ALREADY_SPENT
+                || code == error_code::PROOF_VERIFICATION_FAILED =>
+            {
+                CashuClientError::Proof(proof_errors_from_violations(
+                    fields
+                        .iter()
+                        .map(|f| (f.field.as_str(), f.description.as_str())),
+                ))
+            }
+            Error::Node { code, .. } if code == error_code::KEYSET_INACTIVE => {
+                CashuClientError::InactiveKeyset
+            }
+            e => CashuClientError::Other(Box::new(e)),
+        }
+    }
+}
+
+/// Map a 404 on a quote route to [`CashuClientError::QuoteNotFound`]
+fn quote_not_found(error: Error) -> CashuClientError {
+    match error {
+        Error::Node {
+            status: StatusCode::NOT_FOUND,
+            ..
+        } => CashuClientError::QuoteNotFound,
+        e => e.into(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysetsResponse {
+    keysets: Vec<Keyset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Keyset {
+    id: String,
+    unit: String,
+    active: bool,
+    #[serde(default)]
+    input_fee_ppk: u64,
+    #[serde(default)]
+    final_expiry: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysResponse {
+    keysets: Vec<KeysetKeys>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysetKeys {
+    id: String,
+    unit: String,
+    // Not returned by every node, in which case we read it from the keysets route
+    active: Option<bool>,
+    input_fee_ppk: Option<u64>,
+    keys: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MintQuoteRequest {
+    amount: u64,
+    unit: String,
+    description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct MeltQuoteRequest {
+    request: String,
+    unit: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeltQuoteResponse {
+    quote: String,
+    amount: Amount,
+    unit: String,
+    state: MeltQuoteState,
+    expiry: u64,
+    #[serde(default)]
+    transfer_ids: Option<Vec<String>>,
+}
+
+impl From<MeltQuoteResponse> for ClientMeltQuoteResponse {
+    fn from(value: MeltQuoteResponse) -> Self {
+        Self {
+            quote: value.quote,
+            amount: value.amount,
+            unit: value.unit,
+            state: value.state,
+            expiry: value.expiry,
+            transfer_ids: value.transfer_ids,
+        }
+    }
+}
+
+/// A [`CashuClient`] using the Cashu HTTP/JSON API
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl HttpClient {
+    pub fn new(base_url: Url) -> Result<Self, Error> {
+        if base_url.cannot_be_a_base() {
+            return Err(Error::InvalidBaseUrl(base_url));
+        }
+
+        Ok(Self {
+            base_url,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// The NUT-17 websocket endpoint, `ws(s)://` instead of `http(s)://`
+    fn ws_url(&self) -> Url {
+        let mut url = self.url(&["ws"]);
+        let scheme = match url.scheme() {
+            "https" => "wss",
+            _ => "ws",
+        };
+        url.set_scheme(scheme).expect("http and ws are compatible");
+
+        url
+    }
+
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("checked at construction")
+            .pop_if_empty()
+            .push("v1")
+            .extend(segments);
+
+        url
+    }
+
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T, Error> {
+        let response = self.client.get(self.url(segments)).send().await?;
+
+        Self::read_json(response).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        body: &B,
+    ) -> Result<T, Error> {
+        let response = self
+            .client
+            .post(self.url(segments))
+            .json(body)
+            .send()
+            .await?;
+
+        Self::read_json(response).await
+    }
+
+    async fn read_json<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, Error> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+
+        let body = response.text().await?;
+        match serde_json::from_str::<CashuError>(&body) {
+            Ok(error) => Err(Error::Node {
+                status,
+                code: error.code(),
+                detail: error.detail().clone(),
+                fields: error.fields().to_vec(),
+            }),
+            Err(_) => Err(Error::UnexpectedResponse { status, body }),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CashuClient for HttpClient {
+    type InnerError = Error;
+
+    async fn keysets(&mut self) -> Result<ClientKeysetsResponse, CashuClientError> {
+        let resp: KeysetsResponse = self.get(&["keysets"]).await?;
+
+        Ok(ClientKeysetsResponse {
+            keysets: resp
+                .keysets
+                .into_iter()
+                .map(|k| -> Result<ClientKeyset, Error> {
+                    Ok(ClientKeyset {
+                        id: KeysetId::from_str(&k.id)
+                            .map_err(Error::KeysetId)?
+                            .to_bytes()
+                            .to_vec(),
+                        unit: k.unit,
+                        active: k.active,
+                        input_fee_ppk: k.input_fee_ppk,
+                        final_expiry: k.final_expiry,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+
+    async fn keys(
+        &mut self,
+        keyset_id: Option<KeysetId>,
+    ) -> Result<ClientKeysResponse, CashuClientError> {
+        let resp: KeysResponse = match keyset_id {
+            Some(keyset_id) => self.get(&["keys", &keyset_id.to_string()]).await?,
+            None => self.get(&["keys"]).await?,
+        };
+
+        let keysets_info = if resp
+            .keysets
+            .iter()
+            .any(|k| k.active.is_none() || k.input_fee_ppk.is_none())
+        {
+            self.keysets().await?.keysets
+        } else {
+            Vec::new()
+        };
+
+        let keysets = resp
+            .keysets
+            .into_iter()
+            .map(|k| -> Result<ClientKeysetKeys, Error> {
+                let id = KeysetId::from_str(&k.id)
+                    .map_err(Error::KeysetId)?
+                    .to_bytes()
+                    .to_vec();
+                let info = keysets_info.iter().find(|i| i.id == id);
+
+                Ok(ClientKeysetKeys {
+                    unit: k.unit,
+                    active: k.active.or(info.map(|i| i.active)).unwrap_or(true),
+                    input_fee_ppk: k
+                        .input_fee_ppk
+                        .or(info.map(|i| i.input_fee_ppk))
+                        .unwrap_or(0),
+                    keys: k
+                        .keys
+                        .into_iter()
+                        .map(|(amount, pubkey)| -> Result<ClientKey, Error> {
+                            Ok(ClientKey {
+                                amount: Amount::from(amount.parse::<u64>()?),
+                                publickey: PublicKey::from_str(&pubkey)
+                                    .map_err(Error::PublicKey)?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                    id,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ClientKeysResponse { keysets })
+    }
+
+    async fn mint_quote(
+        &mut self,
+        req: ClientMintQuoteRequest,
+    ) -> Result<MintQuoteResponse<String>, CashuClientError> {
+        let body = MintQuoteRequest {
+            amount: req.amount,
+            unit: req.unit,
+            description: req.description,
+        };
+
+        Ok(self.post(&["mint", "quote", &req.method], &body).await?)
+    }
+
+    async fn mint(
+        &mut self,
+        req: MintRequest<String>,
+        method: String,
+    ) -> Result<MintResponse, CashuClientError> {
+        Ok(self.post(&["mint", &method], &req).await?)
+    }
+
+    async fn mint_quote_state(
+        &mut self,
+        method: String,
+        quote: String,
+    ) -> Result<MintQuoteResponse<String>, CashuClientError> {
+        self.get(&["mint", "quote", &method, &quote])
+            .await
+            .map_err(quote_not_found)
+    }
+
+    async fn swap(&mut self, req: SwapRequest) -> Result<SwapResponse, CashuClientError> {
+        Ok(self.post(&["swap"], &req).await?)
+    }
+
+    async fn melt_quote(
+        &mut self,
+        req: ClientMeltQuoteRequest,
+    ) -> Result<ClientMeltQuoteResponse, CashuClientError> {
+        let body = MeltQuoteRequest {
+            request: req.request,
+            unit: req.unit,
+        };
+        let resp: MeltQuoteResponse = self.post(&["melt", "quote", &req.method], &body).await?;
+
+        Ok(resp.into())
+    }
+
+    async fn melt(
+        &mut self,
+        method: String,
+        req: MeltRequest<String>,
+    ) -> Result<MeltResponse, CashuClientError> {
+        Ok(self.post(&["melt", &method], &req).await?)
+    }
+
+    async fn melt_quote_state(
+        &mut self,
+        method: String,
+        quote: String,
+    ) -> Result<ClientMeltQuoteResponse, CashuClientError> {
+        let resp: MeltQuoteResponse = self
+            .get(&["melt", "quote", &method, &quote])
+            .await
+            .map_err(quote_not_found)?;
+
+        Ok(resp.into())
+    }
+
+    async fn info(&mut self) -> Result<NodeInfoResponse, CashuClientError> {
+        let info: serde_json::Value = self.get(&["info"]).await?;
+
+        Ok(NodeInfoResponse {
+            info: info.to_string(),
+        })
+    }
+
+    async fn check_state(
+        &mut self,
+        req: crate::CheckStateRequest,
+    ) -> Result<CheckStateResponse, CashuClientError> {
+        let body = nuts::nut07::CheckStateRequest {
+            ys: req
+                .ys
+                .iter()
+                .map(|y| PublicKey::from_slice(y))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::PublicKey)?,
+        };
+
+        Ok(self.post(&["checkstate"], &body).await?)
+    }
+
+    async fn acknowledge(
+        &mut self,
+        path: String,
+        request_hash: u64,
+    ) -> Result<(), CashuClientError> {
+        let _: serde_json::Value = self
+            .post(&["acknowledge"], &AcknowledgeRequest { path, request_hash })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn restore(
+        &mut self,
+        outputs: Vec<BlindedMessage>,
+    ) -> Result<ClientRestoreResponse, CashuClientError> {
+        let resp: RestoreResponse = self.post(&["restore"], &RestoreRequest { outputs }).await?;
+
+        Ok(ClientRestoreResponse {
+            outputs: resp.outputs,
+            signatures: resp.signatures,
+        })
+    }
+
+    async fn subscribe(
+        &mut self,
+        req: ClientSubscribeRequest,
+    ) -> Result<ClientNotificationStream, CashuClientError> {
+        const REQUEST_ID: u64 = 0;
+        const SUB_ID: &str = "0";
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.ws_url().as_str())
+            .await
+            .map_err(|e| Error::WebSocket(Box::new(e)))?;
+
+        let kind = match req.kind {
+            ClientSubscriptionKind::MintQuote => Kind::MintQuote(req.method),
+            ClientSubscriptionKind::MeltQuote => Kind::MeltQuote(req.method),
+            ClientSubscriptionKind::ProofState => Kind::ProofState,
+        };
+        let request = WsRequest {
+            jsonrpc: JSON_RPC_VERSION.to_string(),
+            method: WsMethodRequest::Subscribe(Params {
+                kind,
+                sub_id: SUB_ID.to_string(),
+                filters: req.filters,
+            }),
+            id: REQUEST_ID,
+        };
+        socket
+            .send(Message::text(
+                serde_json::to_string(&request).map_err(Error::InvalidMessage)?,
+            ))
+            .await
+            .map_err(|e| Error::WebSocket(Box::new(e)))?;
+
+        // The first message is the answer to our request
+        let text = next_text(&mut socket)
+            .await?
+            .ok_or(Error::WebSocketClosed)?;
+        if serde_json::from_str::<WsResponse>(&text).is_err() {
+            let error: WsErrorResponse =
+                serde_json::from_str(&text).map_err(Error::InvalidMessage)?;
+            return Err(Error::SubscriptionRejected {
+                code: error.error.code,
+                message: error.error.message,
+            }
+            .into());
+        }
+
+        let kind = req.kind;
+        let stream = futures::stream::unfold(Some(socket), move |socket| async move {
+            let mut socket = socket?;
+            let text = match next_text(&mut socket).await {
+                Ok(Some(text)) => text,
+                Ok(None) => return None,
+                Err(e) => return Some((Err(e.into()), None)),
+            };
+
+            let notification = parse_notification(kind, &text).map_err(CashuClientError::from);
+            Some((notification, Some(socket)))
+        });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Next text message of the socket, `None` once it is closed
+async fn next_text<S>(socket: &mut S) -> Result<Option<String>, Error>
+where
+    S: futures::Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+{
+    while let Some(message) = socket.next().await {
+        match message.map_err(|e| Error::WebSocket(Box::new(e)))? {
+            Message::Text(text) => return Ok(Some(text.to_string())),
+            Message::Close(_) => return Ok(None),
+            _ => continue,
+        }
+    }
+
+    Ok(None)
+}
+
+fn parse_notification(
+    kind: ClientSubscriptionKind,
+    text: &str,
+) -> Result<ClientNotification, Error> {
+    let notification = match kind {
+        ClientSubscriptionKind::MintQuote => {
+            let notification: WsNotification<MintQuoteResponse<String>> =
+                serde_json::from_str(text)?;
+            ClientNotification::MintQuote(notification.params.payload)
+        }
+        ClientSubscriptionKind::MeltQuote => {
+            let notification: WsNotification<MeltQuoteResponse> = serde_json::from_str(text)?;
+            ClientNotification::MeltQuote(notification.params.payload.into())
+        }
+        ClientSubscriptionKind::ProofState => {
+            let notification: WsNotification<ProofCheckState> = serde_json::from_str(text)?;
+            ClientNotification::ProofState(notification.params.payload)
+        }
+    };
+
+    Ok(notification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_errors_handler::ProofErrorKind;
+
+    #[test]
+    fn test_node_error_to_client_error() {
+        let err = Error::Node {
+            status: StatusCode::BAD_REQUEST,
+            code: error_code::PROOF_ALREADY_SPENT,
+            detail: "proof verification failed".to_string(),
+            fields: vec![
+                InvalidField {
+                    field: "proofs[1]".to_string(),
+                    description: "proof already spent".to_string(),
+                },
+                InvalidField {
+                    field: "proofs[3]".to_string(),
+                    description: "proof failed cryptographic verification".to_string(),
+                },
+            ],
+        };
+        match CashuClientError::from(err) {
+            CashuClientError::Proof(errors) => {
+                assert!(matches!(errors[0].kind, ProofErrorKind::AlreadySpent));
+                assert_eq!(errors[0].indexes, vec![1]);
+                assert!(matches!(errors[1].kind, ProofErrorKind::FailCryptoVerify));
+                assert_eq!(errors[1].indexes, vec![3]);
+            }
+            e => panic!("unexpected error: {e}"),
+        }
+
+        let err = Error::Node {
+            status: StatusCode::BAD_REQUEST,
+            code: error_code::KEYSET_INACTIVE,
+            detail: "keyset is inactive".to_string(),
+            fields: Vec::new(),
+        };
+        assert!(matches!(
+            CashuClientError::from(err),
+            CashuClientError::InactiveKeyset
+        ));
+
+        let err = Error::Node {
+            status: StatusCode::NOT_FOUND,
+            code: error_code::UNKNOWN,
+            detail: "quote not found".to_string(),
+            fields: Vec::new(),
+        };
+        assert!(matches!(
+            quote_not_found(err),
+            CashuClientError::QuoteNotFound
+        ));
+    }
+
+    #[test]
+    fn test_url() {
+        for base in [
+            "http://localhost:3338",
+            "http://localhost:3338/",
+            "http://localhost:3338/cashu/",
+        ] {
+            let client = HttpClient::new(Url::parse(base).unwrap()).unwrap();
+            let url = client.url(&["mint", "quote", "starknet"]);
+            assert!(url.as_str().ends_with("/v1/mint/quote/starknet"), "{url}");
+            assert!(!url.as_str().contains("//v1"), "{url}");
+        }
+
+        assert!(HttpClient::new(Url::parse("mailto:node@example.com").unwrap()).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use futures::stream::BoxStream;
 use nuts::{
     Amount,
     nut00::{BlindSignature, BlindedMessage},
@@ -6,15 +7,19 @@ use nuts::{
     nut03::{SwapRequest, SwapResponse},
     nut04::{MintQuoteResponse, MintRequest, MintResponse},
     nut05::{MeltQuoteState, MeltRequest, MeltResponse},
-    nut07::CheckStateResponse,
+    nut07::{CheckStateResponse, ProofCheckState},
 };
-use proof_errors_handler::ProofError;
+pub use proof_errors_handler::{ProofError, ProofErrorKind};
 use thiserror::Error;
 
 mod grpc_client;
+#[cfg(feature = "http")]
+mod http_client;
 mod proof_errors_handler;
 
 pub use grpc_client::GrpcClient;
+#[cfg(feature = "http")]
+pub use http_client::HttpClient;
 
 #[derive(Debug, Clone)]
 pub struct ClientMintQuoteRequest {
@@ -56,6 +61,9 @@ pub struct ClientKeyset {
     pub id: Vec<u8>,
     pub unit: String,
     pub active: bool,
+    pub input_fee_ppk: u64,
+    /// Unix timestamp past which the node stops accepting the proofs of this keyset
+    pub final_expiry: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -80,6 +88,7 @@ pub struct ClientKeysetKeys {
     pub unit: String,
     pub active: bool,
     pub keys: Vec<ClientKey>,
+    pub input_fee_ppk: u64,
 }
 
 pub struct ClientKeysResponse {
@@ -92,6 +101,33 @@ pub struct ClientRestoreResponse {
     pub signatures: Vec<BlindSignature>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientSubscriptionKind {
+    MintQuote,
+    MeltQuote,
+    ProofState,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClientSubscribeRequest {
+    pub kind: ClientSubscriptionKind,
+    /// Ignored for [`ClientSubscriptionKind::ProofState`]
+    pub method: String,
+    /// Quote ids, or hex encoded `Y`s for [`ClientSubscriptionKind::ProofState`]
+    pub filters: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum ClientNotification {
+    MintQuote(MintQuoteResponse<String>),
+    MeltQuote(ClientMeltQuoteResponse),
+    ProofState(ProofCheckState),
+}
+
+/// Current state of each subscribed item, followed by their updates
+pub type ClientNotificationStream =
+    BoxStream<'static, Result<ClientNotification, CashuClientError>>;
+
 #[derive(Debug, thiserror::Error)]
 pub enum CashuClientError {
     #[error("invalid proofs: {0:?}")]
@@ -156,4 +192,8 @@ pub trait CashuClient: Send + Sync + Clone {
         &mut self,
         outputs: Vec<BlindedMessage>,
     ) -> Result<ClientRestoreResponse, CashuClientError>;
+    async fn subscribe(
+        &mut self,
+        req: ClientSubscribeRequest,
+    ) -> Result<ClientNotificationStream, CashuClientError>;
 }
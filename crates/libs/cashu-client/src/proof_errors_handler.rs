@@ -12,6 +12,37 @@ pub struct ProofError {
     pub kind: ProofErrorKind,
 }
 
+/// Sort the proofs rejected by a node according to the reason it gave
+///
+/// Each violation is a `(field, description)` pair, where `field` looks like `proofs[<index>]`.
+/// The first returned [`ProofError`] holds the already spent proofs,
+/// the second one those that failed cryptographic verification.
+pub fn proof_errors_from_violations<'a>(
+    violations: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<ProofError> {
+    let mut spent = Vec::new();
+    let mut invalid = Vec::new();
+    for (field, description) in violations {
+        let idx = extract_proof_index(field).unwrap_or(0);
+        if description.contains("already spent") {
+            spent.push(idx);
+        } else if description.contains("failed cryptographic verification") {
+            invalid.push(idx);
+        }
+    }
+
+    vec![
+        ProofError {
+            indexes: spent,
+            kind: ProofErrorKind::AlreadySpent,
+        },
+        ProofError {
+            indexes: invalid,
+            kind: ProofErrorKind::FailCryptoVerify,
+        },
+    ]
+}
+
 pub fn extract_proof_index(field: &str) -> Result<u32, Error> {
     if let Some(start) = field.find('[') {
         if let Some(end) = field.find(']') {
@@ -25,7 +56,7 @@ pub fn extract_proof_index(field: &str) -> Result<u32, Error> {
 
 #[cfg(test)]
 mod tests {
-    use super::extract_proof_index;
+    use super::{ProofErrorKind, extract_proof_index, proof_errors_from_violations};
 
     #[test]
     fn test_extract_proof_index_valid_input() {
@@ -78,4 +109,22 @@ mod tests {
         assert!(extract_proof_index("proofs[4294967296]").is_err());
         assert!(extract_proof_index("proofs[-1]").is_err());
     }
+
+    #[test]
+    fn test_proof_errors_from_violations() {
+        let violations = [
+            ("proofs[2]", "proof already spent"),
+            ("proofs[0]", "proof failed cryptographic verification"),
+            ("proofs[5]", "proof already spent"),
+            ("outputs[1]", "unrelated"),
+        ];
+
+        let errors = proof_errors_from_violations(violations);
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0].kind, ProofErrorKind::AlreadySpent));
+        assert_eq!(errors[0].indexes, vec![2, 5]);
+        assert!(matches!(errors[1].kind, ProofErrorKind::FailCryptoVerify));
+        assert_eq!(errors[1].indexes, vec![0]);
+    }
 }
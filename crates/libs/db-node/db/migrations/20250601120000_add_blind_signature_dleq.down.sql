@@ -0,0 +1,2 @@
+ALTER TABLE blind_signature DROP COLUMN dleq_s;
+ALTER TABLE blind_signature DROP COLUMN dleq_e;
@@ -0,0 +1,3 @@
+-- NUT-12 DLEQ proof, NULL for signatures emitted before it was supported
+ALTER TABLE blind_signature ADD COLUMN dleq_e BYTEA CHECK (length(dleq_e) = 32);
+ALTER TABLE blind_signature ADD COLUMN dleq_s BYTEA CHECK (length(dleq_s) = 32);
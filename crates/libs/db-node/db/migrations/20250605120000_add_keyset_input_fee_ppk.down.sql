@@ -0,0 +1 @@
+ALTER TABLE keyset DROP COLUMN input_fee_ppk;
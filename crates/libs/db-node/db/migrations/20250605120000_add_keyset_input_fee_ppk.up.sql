@@ -0,0 +1,2 @@
+-- NUT-02 input fee, in parts per thousand of a unit, charged for each proof of this keyset spent
+ALTER TABLE keyset ADD COLUMN input_fee_ppk INT8 NOT NULL DEFAULT 0 CHECK (input_fee_ppk >= 0);
@@ -0,0 +1,10 @@
+-- On-chain fee actually paid by the node for each melt transfer,
+-- expressed in the node unit best matching the asset it was paid in
+CREATE TABLE IF NOT EXISTS melt_transfer_fee (
+    tx_hash TEXT PRIMARY KEY,
+    unit TEXT NOT NULL,
+    amount INT8 NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS melt_transfer_fee_unit ON melt_transfer_fee(unit);
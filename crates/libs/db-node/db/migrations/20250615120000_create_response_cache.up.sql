@@ -0,0 +1,11 @@
+-- NUT-19 cached responses, kept until acknowledged by the wallet or expired
+CREATE TABLE IF NOT EXISTS response_cache (
+    route TEXT NOT NULL,
+    request_hash INT8 NOT NULL,
+    response BYTEA NOT NULL,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (route, request_hash)
+);
+
+CREATE INDEX IF NOT EXISTS response_cache_expires_at ON response_cache(expires_at);
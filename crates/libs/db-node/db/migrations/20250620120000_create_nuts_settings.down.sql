@@ -0,0 +1 @@
+DROP TABLE IF EXISTS nuts_settings;
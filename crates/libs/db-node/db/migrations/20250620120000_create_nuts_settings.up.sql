@@ -0,0 +1,7 @@
+-- Nuts settings updated at runtime, shared by every node instance
+-- Single row table: when empty, nodes use their built-in settings
+CREATE TABLE IF NOT EXISTS nuts_settings (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    settings JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
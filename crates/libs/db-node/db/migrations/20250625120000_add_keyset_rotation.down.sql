@@ -0,0 +1,2 @@
+ALTER TABLE keyset DROP COLUMN final_expiry;
+ALTER TABLE keyset DROP COLUMN activated_at;
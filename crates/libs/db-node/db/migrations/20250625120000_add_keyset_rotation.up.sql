@@ -0,0 +1,5 @@
+-- When the keyset started signing outputs, age based rotation policies are computed from it
+ALTER TABLE keyset ADD COLUMN activated_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+-- Set when the keyset is retired, its proofs are rejected as inputs past this date.
+-- NULL for a keyset whose proofs stay valid forever
+ALTER TABLE keyset ADD COLUMN final_expiry TIMESTAMPTZ;
@@ -0,0 +1,7 @@
+-- Node info fields set by the operator at runtime, shared by every node instance
+-- Single row table: when empty, nodes use their built-in info
+CREATE TABLE IF NOT EXISTS node_info (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    info JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
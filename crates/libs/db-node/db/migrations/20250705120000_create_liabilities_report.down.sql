@@ -0,0 +1,6 @@
+DROP INDEX IF EXISTS proof_liabilities_report_index;
+DROP INDEX IF EXISTS blind_signature_liabilities_report_index;
+ALTER TABLE proof DROP COLUMN liabilities_report_id;
+ALTER TABLE blind_signature DROP COLUMN liabilities_report_id;
+DROP TABLE IF EXISTS liabilities_report_keyset;
+DROP TABLE IF EXISTS liabilities_report;
@@ -0,0 +1,27 @@
+-- Proof of liabilities reports, each covering everything issued and burned until it was made
+CREATE TABLE IF NOT EXISTS liabilities_report (
+    id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Per keyset commitments of a report, the roots of Merkle sum trees over
+-- the blinded messages signed and the proofs spent
+CREATE TABLE IF NOT EXISTS liabilities_report_keyset (
+    report_id BIGINT NOT NULL REFERENCES liabilities_report(id),
+    keyset_id BIGINT NOT NULL REFERENCES keyset(id),
+    issued INT8 NOT NULL,
+    issued_count INT8 NOT NULL,
+    issued_root BYTEA CHECK (length(issued_root) = 32) NOT NULL,
+    burned INT8 NOT NULL,
+    burned_count INT8 NOT NULL,
+    burned_root BYTEA CHECK (length(burned_root) = 32) NOT NULL,
+    PRIMARY KEY (report_id, keyset_id)
+);
+
+-- The first report covering the row, NULL until one does.
+-- It never changes afterward, so that the trees of past reports can be rebuilt
+ALTER TABLE blind_signature ADD COLUMN liabilities_report_id BIGINT REFERENCES liabilities_report(id);
+ALTER TABLE proof ADD COLUMN liabilities_report_id BIGINT REFERENCES liabilities_report(id);
+
+CREATE INDEX IF NOT EXISTS blind_signature_liabilities_report_index ON blind_signature(keyset_id, liabilities_report_id);
+CREATE INDEX IF NOT EXISTS proof_liabilities_report_index ON proof(keyset_id, liabilities_report_id);
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS reserves_attestation_asset;
+DROP TABLE IF EXISTS reserves_attestation;
@@ -0,0 +1,19 @@
+-- Attestations, signed with the key controlling the reserves account, of the node reserves
+-- and the liabilities they back
+CREATE TABLE IF NOT EXISTS reserves_attestation (
+    id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL,
+    reserves_address TEXT NOT NULL,
+    hash BYTEA CHECK (length(hash) = 32) NOT NULL,
+    signature BYTEA NOT NULL
+);
+
+-- Per asset amounts of an attestation, as 32 bytes big-endian integers
+-- in the asset blockchain-native representation
+CREATE TABLE IF NOT EXISTS reserves_attestation_asset (
+    attestation_id BIGINT NOT NULL REFERENCES reserves_attestation(id),
+    asset TEXT NOT NULL,
+    liabilities BYTEA CHECK (length(liabilities) = 32) NOT NULL,
+    reserves BYTEA CHECK (length(reserves) = 32) NOT NULL,
+    PRIMARY KEY (attestation_id, asset)
+);
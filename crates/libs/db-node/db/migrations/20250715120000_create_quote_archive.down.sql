@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS late_mint_payment;
+DROP TABLE IF EXISTS melt_quote_archive;
+DROP TABLE IF EXISTS mint_quote_archive;
+
+ALTER TABLE melt_quote DROP COLUMN expired_at;
+ALTER TABLE mint_quote DROP COLUMN expired_at;
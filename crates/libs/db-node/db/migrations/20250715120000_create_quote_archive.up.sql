@@ -0,0 +1,49 @@
+-- Set by the sweeper on the quotes that expired unpaid
+ALTER TABLE mint_quote ADD COLUMN expired_at TIMESTAMPTZ;
+ALTER TABLE melt_quote ADD COLUMN expired_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS mint_quote_expired_at ON mint_quote(expired_at);
+CREATE INDEX IF NOT EXISTS melt_quote_expired_at ON melt_quote(expired_at);
+
+CREATE TABLE IF NOT EXISTS mint_quote_archive (
+    id UUID PRIMARY KEY,
+    invoice_id BYTEA CHECK (length(invoice_id) = 32) NOT NULL,
+    unit TEXT NOT NULL,
+    amount INT8 NOT NULL,
+    request TEXT NOT NULL,
+    expiry TIMESTAMPTZ NOT NULL,
+    state mint_quote_state NOT NULL,
+    expired_at TIMESTAMPTZ NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS mint_quote_archive_invoice_id ON mint_quote_archive(invoice_id);
+CREATE INDEX IF NOT EXISTS mint_quote_archive_unit ON mint_quote_archive(unit);
+
+CREATE TABLE IF NOT EXISTS melt_quote_archive (
+    id UUID PRIMARY KEY,
+    invoice_id BYTEA CHECK (length(invoice_id) = 32) NOT NULL,
+    unit TEXT NOT NULL,
+    amount INT8 NOT NULL,
+    fee INT8 NOT NULL,
+    request TEXT NOT NULL,
+    expiry TIMESTAMPTZ NOT NULL,
+    state melt_quote_state NOT NULL,
+    expired_at TIMESTAMPTZ NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS melt_quote_archive_invoice_id ON melt_quote_archive(invoice_id);
+CREATE INDEX IF NOT EXISTS melt_quote_archive_unit ON melt_quote_archive(unit);
+
+-- Payments received on-chain after the expiry of their quote
+CREATE TABLE IF NOT EXISTS late_mint_payment (
+    tx_hash TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    quote_id UUID NOT NULL REFERENCES mint_quote(id),
+    detected_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (tx_hash, event_index),
+    FOREIGN KEY (tx_hash, event_index) REFERENCES mint_payment_event(tx_hash, event_index)
+);
+
+CREATE INDEX IF NOT EXISTS late_mint_payment_quote_id ON late_mint_payment(quote_id);
@@ -0,0 +1,2 @@
+ALTER TABLE mint_quote DROP COLUMN excess;
+ALTER TABLE mint_quote DROP COLUMN amount_paid;
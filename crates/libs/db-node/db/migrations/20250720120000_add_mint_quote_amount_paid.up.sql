@@ -0,0 +1,7 @@
+-- Sum of the payments received, in the quote unit, rounded down
+ALTER TABLE mint_quote ADD COLUMN amount_paid INT8 NOT NULL DEFAULT 0;
+-- Received on top of the quoted amount, as a 32 bytes big-endian integer
+-- in the asset blockchain-native representation
+ALTER TABLE mint_quote ADD COLUMN excess BYTEA CHECK (length(excess) = 32);
+
+UPDATE mint_quote SET amount_paid = amount WHERE state <> 'UNPAID';
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS withdraw_order;
+DROP TYPE IF EXISTS withdraw_order_state;
@@ -0,0 +1,16 @@
+CREATE TYPE withdraw_order_state AS ENUM ('PENDING', 'SENT', 'SUCCEEDED', 'FAILED');
+
+-- Outbox of the on-chain payments of the melt quotes, consumed by the liquidity source
+CREATE TABLE IF NOT EXISTS withdraw_order (
+    invoice_id BYTEA PRIMARY KEY REFERENCES melt_quote(invoice_id),
+    quote_id UUID NOT NULL,
+    payload TEXT NOT NULL,
+    state withdraw_order_state NOT NULL,
+    -- Set once sent
+    tx_hash TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS withdraw_order_state ON withdraw_order(state);
+CREATE INDEX IF NOT EXISTS withdraw_order_tx_hash ON withdraw_order(tx_hash);
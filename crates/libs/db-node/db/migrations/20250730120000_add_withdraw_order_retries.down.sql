@@ -0,0 +1,4 @@
+ALTER TABLE withdraw_order DROP COLUMN next_attempt_at;
+ALTER TABLE withdraw_order DROP COLUMN attempts;
+ALTER TABLE proof DROP COLUMN melt_quote_id;
+-- Postgres cannot remove a value from an enum, 'FAILED' is left in melt_quote_state
@@ -0,0 +1,10 @@
+-- Set when the on-chain payment failed for good
+ALTER TYPE melt_quote_state ADD VALUE IF NOT EXISTS 'FAILED';
+
+-- The melt quote a proof was spent to pay, so that it can be returned if the payment fails
+ALTER TABLE proof ADD COLUMN melt_quote_id UUID REFERENCES melt_quote(id);
+CREATE INDEX IF NOT EXISTS proof_melt_quote_id ON proof(melt_quote_id);
+
+-- Failed attempts to pay an order, which is retried with an exponential backoff
+ALTER TABLE withdraw_order ADD COLUMN attempts INT4 NOT NULL DEFAULT 0;
+ALTER TABLE withdraw_order ADD COLUMN next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
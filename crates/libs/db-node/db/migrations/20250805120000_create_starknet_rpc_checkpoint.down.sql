@@ -0,0 +1 @@
+DROP TABLE IF EXISTS starknet_rpc_checkpoint;
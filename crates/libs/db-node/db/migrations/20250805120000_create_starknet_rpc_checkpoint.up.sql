@@ -0,0 +1,5 @@
+-- Blocks indexed by the rpc indexer, used to detect the reorgs by comparing their hash
+CREATE TABLE IF NOT EXISTS starknet_rpc_checkpoint (
+    number BIGINT PRIMARY KEY,
+    hash TEXT NOT NULL
+);
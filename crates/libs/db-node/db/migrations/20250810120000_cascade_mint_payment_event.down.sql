@@ -0,0 +1,6 @@
+ALTER TABLE late_mint_payment DROP CONSTRAINT late_mint_payment_tx_hash_event_index_fkey;
+ALTER TABLE late_mint_payment
+    ADD CONSTRAINT late_mint_payment_tx_hash_event_index_fkey
+    FOREIGN KEY (tx_hash, event_index) REFERENCES mint_payment_event(tx_hash, event_index);
+
+ALTER TABLE mint_payment_event DROP CONSTRAINT mint_payment_event_block_id_fkey;
@@ -0,0 +1,10 @@
+-- Like the melt ones, the mint payment events are deleted along with their block on reorgs.
+-- Events indexed before the block table existed have no block, they are not checked.
+ALTER TABLE mint_payment_event
+    ADD CONSTRAINT mint_payment_event_block_id_fkey
+    FOREIGN KEY (block_id) REFERENCES substreams_starknet_block(id) ON DELETE CASCADE NOT VALID;
+
+ALTER TABLE late_mint_payment DROP CONSTRAINT late_mint_payment_tx_hash_event_index_fkey;
+ALTER TABLE late_mint_payment
+    ADD CONSTRAINT late_mint_payment_tx_hash_event_index_fkey
+    FOREIGN KEY (tx_hash, event_index) REFERENCES mint_payment_event(tx_hash, event_index) ON DELETE CASCADE;
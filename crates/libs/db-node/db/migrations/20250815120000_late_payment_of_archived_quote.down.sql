@@ -0,0 +1,7 @@
+DELETE FROM mint_payment_event e WHERE NOT EXISTS (SELECT 1 FROM mint_quote WHERE invoice_id = e.invoice_id);
+ALTER TABLE mint_payment_event
+    ADD CONSTRAINT payment_event_invoice_id_fkey
+    FOREIGN KEY (invoice_id) REFERENCES mint_quote(invoice_id);
+ALTER TABLE late_mint_payment
+    ADD CONSTRAINT late_mint_payment_quote_id_fkey
+    FOREIGN KEY (quote_id) REFERENCES mint_quote(id);
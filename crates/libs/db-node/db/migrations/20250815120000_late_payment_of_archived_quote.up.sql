@@ -0,0 +1,3 @@
+-- Payments may arrive after their quote was archived, they are recorded and flagged all the same
+ALTER TABLE mint_payment_event DROP CONSTRAINT payment_event_invoice_id_fkey;
+ALTER TABLE late_mint_payment DROP CONSTRAINT late_mint_payment_quote_id_fkey;
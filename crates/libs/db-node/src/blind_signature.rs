@@ -1,5 +1,11 @@
 use futures_util::StreamExt;
-use nuts::{Amount, nut01::PublicKey, nut02::KeysetId, traits::Unit};
+use nuts::{
+    Amount,
+    nut01::{PublicKey, SecretKey},
+    nut02::KeysetId,
+    nut12::BlindSignatureDleq,
+    traits::Unit,
+};
 use sqlx::{PgConnection, Row};
 
 use crate::Error;
@@ -49,6 +55,7 @@ pub struct RestoreFromDbResponse {
     pub keyset_id: KeysetId,
     pub blinded_secret: PublicKey,
     pub blind_signature: PublicKey,
+    pub dleq: Option<BlindSignatureDleq>,
 }
 
 pub async fn get_by_blind_secrets(
@@ -75,7 +82,7 @@ pub async fn get_by_blind_secrets(
             {}
           ) AS t(y, position)
         )
-        SELECT amount, keyset_id, c, blind_signature.y FROM blind_signature
+        SELECT amount, keyset_id, c, blind_signature.y, dleq_e, dleq_s FROM blind_signature
         JOIN lookup ON blind_signature.y = lookup.y
         ORDER BY lookup.position;"#,
         placeholders
@@ -96,6 +103,15 @@ pub async fn get_by_blind_secrets(
         let keyset_id = row.try_get::<i64, _>(1)?;
         let c = row.try_get::<&[u8], _>(2)?;
         let y = row.try_get::<&[u8], _>(3)?;
+        let dleq_e = row.try_get::<Option<&[u8]>, _>(4)?;
+        let dleq_s = row.try_get::<Option<&[u8]>, _>(5)?;
+        let dleq = match (dleq_e, dleq_s) {
+            (Some(e), Some(s)) => Some(BlindSignatureDleq {
+                e: SecretKey::from_slice(e).map_err(|_| Error::DbToRuntimeConversion)?,
+                s: SecretKey::from_slice(s).map_err(|_| Error::DbToRuntimeConversion)?,
+            }),
+            _ => None,
+        };
 
         ret.push(RestoreFromDbResponse {
             amount: Amount::from_i64_repr(amount),
@@ -104,8 +120,24 @@ pub async fn get_by_blind_secrets(
                 .map_err(|_| Error::DbToRuntimeConversion)?,
             blinded_secret: PublicKey::from_slice(y).map_err(|_| Error::DbToRuntimeConversion)?,
             blind_signature: PublicKey::from_slice(c).map_err(|_| Error::DbToRuntimeConversion)?,
+            dleq,
         });
     }
 
     Ok(ret)
 }
+
+/// Sum of the amounts signed with this keyset
+pub async fn sum_amount_signed_with_keyset(
+    conn: &mut PgConnection,
+    keyset_id: KeysetId,
+) -> Result<Amount, Error> {
+    let record = sqlx::query!(
+        r#"SELECT COALESCE(SUM(amount), 0)::INT8 AS "sum!" FROM blind_signature WHERE keyset_id = $1"#,
+        keyset_id.as_i64()
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(Amount::from_i64_repr(record.sum))
+}
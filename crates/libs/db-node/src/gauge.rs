@@ -20,7 +20,10 @@ pub async fn get_all_gauge_metrics_by_units<U: Unit>(
                 (SELECT COALESCE(SUM(amount), 0) FROM mint_quote WHERE unit = mq.unit AND state = 'ISSUED') AS "issued_deposits!",
                 (SELECT COALESCE(SUM(amount), 0) FROM melt_quote WHERE unit = mq.unit AND state = 'UNPAID') AS "unpaid_withdrawals!",
                 (SELECT COALESCE(SUM(amount), 0) FROM melt_quote WHERE unit = mq.unit AND state = 'PENDING') AS "pending_withdrawals!",
-                (SELECT COALESCE(SUM(amount), 0) FROM melt_quote WHERE unit = mq.unit AND state = 'PAID') AS "paid_withdrawals!"
+                (SELECT COALESCE(SUM(amount), 0) FROM melt_quote WHERE unit = mq.unit AND state = 'PAID') AS "paid_withdrawals!",
+                (SELECT COALESCE(SUM(amount), 0) FROM melt_quote WHERE unit = mq.unit AND state = 'FAILED') AS "failed_withdrawals!",
+                (SELECT COALESCE(SUM(fee), 0) FROM melt_quote WHERE unit = mq.unit AND state = 'PAID') AS "collected_withdrawal_fees!",
+                (SELECT COALESCE(SUM(amount), 0) FROM melt_transfer_fee WHERE unit = mq.unit) AS "spent_withdrawal_fees!"
             FROM (SELECT DISTINCT unit FROM unnest($1::text[]) AS unit) mq
         "#,
         &unit_strs
@@ -39,6 +42,11 @@ pub async fn get_all_gauge_metrics_by_units<U: Unit>(
                 unpaid_withdrawals: Amount::from(record.unpaid_withdrawals.to_u64().unwrap()),
                 pending_withdrawals: Amount::from(record.pending_withdrawals.to_u64().unwrap()),
                 paid_withdrawals: Amount::from(record.paid_withdrawals.to_u64().unwrap()),
+                failed_withdrawals: Amount::from(record.failed_withdrawals.to_u64().unwrap()),
+                collected_withdrawal_fees: Amount::from(
+                    record.collected_withdrawal_fees.to_u64().unwrap(),
+                ),
+                spent_withdrawal_fees: Amount::from(record.spent_withdrawal_fees.to_u64().unwrap()),
             },
         ));
     }
@@ -55,4 +63,62 @@ pub struct GaugeMetrics {
     pub unpaid_withdrawals: Amount,
     pub pending_withdrawals: Amount,
     pub paid_withdrawals: Amount,
+    /// Quotes whose on-chain payment failed for good
+    pub failed_withdrawals: Amount,
+    /// Fees charged to the users on paid melt quotes
+    pub collected_withdrawal_fees: Amount,
+    /// Fees paid on-chain by the node to send the melt transfers
+    pub spent_withdrawal_fees: Amount,
+}
+
+pub async fn get_quote_counts_by_units<U: Unit>(
+    conn: &mut PgConnection,
+    units: &[U],
+) -> Result<Vec<(String, QuoteCounts)>, Error> {
+    let unit_strs: Vec<String> = units.iter().map(|u| u.to_string()).collect();
+
+    let records = sqlx::query!(
+        r#"
+            SELECT
+                unit AS "unit!",
+                (SELECT COUNT(*) FROM mint_quote WHERE unit = u.unit AND state = 'UNPAID' AND expired_at IS NOT NULL) AS "expired_deposits!",
+                (SELECT COUNT(*) FROM mint_quote_archive WHERE unit = u.unit) AS "archived_deposits!",
+                (SELECT COUNT(*) FROM late_mint_payment l JOIN (SELECT id, unit FROM mint_quote UNION ALL SELECT id, unit FROM mint_quote_archive) mq ON mq.id = l.quote_id WHERE mq.unit = u.unit) AS "late_deposit_payments!",
+                (SELECT COUNT(*) FROM melt_quote WHERE unit = u.unit AND state = 'UNPAID' AND expired_at IS NOT NULL) AS "expired_withdrawals!",
+                (SELECT COUNT(*) FROM melt_quote_archive WHERE unit = u.unit) AS "archived_withdrawals!"
+            FROM (SELECT DISTINCT unit FROM unnest($1::text[]) AS unit) u
+        "#,
+        &unit_strs
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .map(|record| {
+            (
+                record.unit,
+                QuoteCounts {
+                    expired_deposits: record.expired_deposits.to_u64().unwrap(),
+                    archived_deposits: record.archived_deposits.to_u64().unwrap(),
+                    late_deposit_payments: record.late_deposit_payments.to_u64().unwrap(),
+                    expired_withdrawals: record.expired_withdrawals.to_u64().unwrap(),
+                    archived_withdrawals: record.archived_withdrawals.to_u64().unwrap(),
+                },
+            )
+        })
+        .collect())
+}
+
+/// Number of quotes handled by the quote sweeper, for a specific unit
+#[derive(Debug, Clone)]
+pub struct QuoteCounts {
+    /// Unpaid quotes marked expired, not archived yet
+    pub expired_deposits: u64,
+    pub archived_deposits: u64,
+    /// Payments received after the expiry of their quote
+    pub late_deposit_payments: u64,
+    /// Unpaid quotes marked expired, not archived yet
+    pub expired_withdrawals: u64,
+    pub archived_withdrawals: u64,
 }
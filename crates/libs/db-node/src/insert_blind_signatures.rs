@@ -11,7 +11,7 @@ impl InsertBlindSignaturesQueryBuilder<'_> {
     pub fn new() -> Self {
         Self {
             builder: QueryBuilder::new(
-                r#"INSERT INTO blind_signature (y, amount, keyset_id, c) VALUES "#,
+                r#"INSERT INTO blind_signature (y, amount, keyset_id, c, dleq_e, dleq_s) VALUES "#,
             ),
             first: true,
         }
@@ -22,6 +22,13 @@ impl InsertBlindSignaturesQueryBuilder<'_> {
         let amount = blind_signature.amount.into_i64_repr();
         let keyset_id = blind_signature.keyset_id.as_i64();
         let c = blind_signature.c.to_bytes();
+        let (dleq_e, dleq_s) = match &blind_signature.dleq {
+            Some(dleq) => (
+                Some(dleq.e.to_secret_bytes()),
+                Some(dleq.s.to_secret_bytes()),
+            ),
+            None => (None, None),
+        };
 
         if self.first {
             self.first = false;
@@ -38,6 +45,10 @@ impl InsertBlindSignaturesQueryBuilder<'_> {
             .push_bind(keyset_id)
             .push(", ")
             .push_bind(c)
+            .push(", ")
+            .push_bind(dleq_e)
+            .push(", ")
+            .push_bind(dleq_s)
             .push(')');
     }
 
@@ -71,6 +82,7 @@ mod query_builder {
                 "02194603ffa36356f4a56b7df9371fc3192472351453ec7398b8da8117e7c3e104",
             )
             .unwrap(),
+            dleq: None,
         };
 
         let y = PublicKey::from_hex(
@@ -83,7 +95,7 @@ mod query_builder {
         let query = builder.builder.sql();
         assert_eq!(
             query,
-            "INSERT INTO blind_signature (y, amount, keyset_id, c) VALUES ($1, $2, $3, $4), ($5, $6, $7, $8)"
+            "INSERT INTO blind_signature (y, amount, keyset_id, c, dleq_e, dleq_s) VALUES ($1, $2, $3, $4, $5, $6), ($7, $8, $9, $10, $11, $12)"
         );
     }
 }
@@ -16,17 +16,25 @@ impl InsertKeysetsQueryBuilder<'_> {
     pub fn new() -> Self {
         Self {
             builder: QueryBuilder::new(
-                r#"INSERT INTO keyset (id, unit, active, max_order, derivation_path_index) VALUES "#,
+                r#"INSERT INTO keyset (id, unit, active, max_order, derivation_path_index, input_fee_ppk) VALUES "#,
             ),
             first: true,
         }
     }
 
-    pub fn add_row<U: ToString>(&mut self, id: KeysetId, unit: U, max_order: u32, index: u32) {
+    pub fn add_row<U: ToString>(
+        &mut self,
+        id: KeysetId,
+        unit: U,
+        max_order: u32,
+        index: u32,
+        input_fee_ppk: u64,
+    ) {
         let id = id.as_i64();
         let unit = unit.to_string();
         let max_order = i32::from_be_bytes(max_order.to_be_bytes());
         let index = i32::from_be_bytes(index.to_be_bytes());
+        let input_fee_ppk = i64::from_be_bytes(input_fee_ppk.to_be_bytes());
 
         if self.first {
             self.first = false;
@@ -43,6 +51,8 @@ impl InsertKeysetsQueryBuilder<'_> {
             .push_bind(max_order)
             .push(", ")
             .push_bind(index)
+            .push(", ")
+            .push_bind(input_fee_ppk)
             .push(')');
     }
 
@@ -1,16 +1,22 @@
 use std::str::FromStr;
 
 use nuts::nut02::KeysetId;
-use sqlx::PgConnection;
+use sqlx::{PgConnection, types::time::OffsetDateTime};
 
 use crate::Error;
 
+/// Arbitrary key of the advisory lock taken by keyset rotations, see [`try_lock_rotation`]
+const KEYSET_ROTATION_LOCK_KEY: i64 = 0x6b65_7973_6574;
+
 #[derive(Debug, Clone)]
 pub struct KeysetInfo<U> {
     unit: U,
     active: bool,
     max_order: u8,
     derivation_path_index: u32,
+    input_fee_ppk: u64,
+    activated_at: u64,
+    final_expiry: Option<u64>,
 }
 
 impl<U> KeysetInfo<U> {
@@ -23,6 +29,17 @@ impl<U> KeysetInfo<U> {
     pub fn derivation_path_index(&self) -> u32 {
         self.derivation_path_index
     }
+    pub fn input_fee_ppk(&self) -> u64 {
+        self.input_fee_ppk
+    }
+    /// Unix timestamp of the keyset creation
+    pub fn activated_at(&self) -> u64 {
+        self.activated_at
+    }
+    /// Unix timestamp past which the keyset proofs are not accepted anymore
+    pub fn final_expiry(&self) -> Option<u64> {
+        self.final_expiry
+    }
 }
 
 impl<U: Clone> KeysetInfo<U> {
@@ -31,16 +48,33 @@ impl<U: Clone> KeysetInfo<U> {
     }
 }
 
+fn to_unix_timestamp(date: OffsetDateTime) -> Result<u64, Error> {
+    date.unix_timestamp()
+        .try_into()
+        .map_err(|_| Error::DbToRuntimeConversion)
+}
+
 pub async fn get_keysets(
     conn: &mut PgConnection,
-) -> Result<impl Iterator<Item = ([u8; 8], String, bool)>, sqlx::Error> {
-    let record = sqlx::query!("SELECT id, unit, active FROM keyset")
+) -> Result<impl Iterator<Item = ([u8; 8], String, bool, u64, Option<u64>)>, Error> {
+    let record = sqlx::query!("SELECT id, unit, active, input_fee_ppk, final_expiry FROM keyset")
         .fetch_all(conn)
         .await?;
 
-    Ok(record
+    let keysets = record
         .into_iter()
-        .map(|r| (r.id.to_be_bytes(), r.unit, r.active)))
+        .map(|r| -> Result<_, Error> {
+            Ok((
+                r.id.to_be_bytes(),
+                r.unit,
+                r.active,
+                u64::from_be_bytes(r.input_fee_ppk.to_be_bytes()),
+                r.final_expiry.map(to_unix_timestamp).transpose()?,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(keysets.into_iter())
 }
 
 pub async fn get_keyset<U: FromStr>(
@@ -48,7 +82,7 @@ pub async fn get_keyset<U: FromStr>(
     keyset_id: &KeysetId,
 ) -> Result<KeysetInfo<U>, Error> {
     let record = sqlx::query!(
-        r#"SELECT unit, active, max_order, derivation_path_index
+        r#"SELECT unit, active, max_order, derivation_path_index, input_fee_ppk, activated_at, final_expiry
         FROM keyset
         WHERE id = $1"#,
         keyset_id.as_i64()
@@ -61,6 +95,9 @@ pub async fn get_keyset<U: FromStr>(
         active: record.active,
         max_order: u8::try_from(record.max_order).map_err(|_| Error::DbToRuntimeConversion)?,
         derivation_path_index: u32::from_be_bytes(record.derivation_path_index.to_be_bytes()),
+        input_fee_ppk: u64::from_be_bytes(record.input_fee_ppk.to_be_bytes()),
+        activated_at: to_unix_timestamp(record.activated_at)?,
+        final_expiry: record.final_expiry.map(to_unix_timestamp).transpose()?,
     };
 
     Ok(info)
@@ -86,7 +123,7 @@ pub async fn get_active_keysets<U: FromStr>(
     conn: &mut PgConnection,
 ) -> Result<Vec<(KeysetId, KeysetInfo<U>)>, Error> {
     let records = sqlx::query!(
-        r#"SELECT id, unit, active, max_order, derivation_path_index
+        r#"SELECT id, unit, active, max_order, derivation_path_index, input_fee_ppk, activated_at, final_expiry
         FROM keyset
         WHERE active = TRUE"#,
     )
@@ -107,6 +144,9 @@ pub async fn get_active_keysets<U: FromStr>(
                     derivation_path_index: u32::from_be_bytes(
                         record.derivation_path_index.to_be_bytes(),
                     ),
+                    input_fee_ppk: u64::from_be_bytes(record.input_fee_ppk.to_be_bytes()),
+                    activated_at: to_unix_timestamp(record.activated_at)?,
+                    final_expiry: record.final_expiry.map(to_unix_timestamp).transpose()?,
                 },
             ))
         })
@@ -115,13 +155,47 @@ pub async fn get_active_keysets<U: FromStr>(
     Ok(keysets_info)
 }
 
-pub async fn deactivate_keysets(conn: &mut PgConnection, keyset_ids: &[i64]) -> Result<(), Error> {
+/// Stop signing outputs with these keysets
+///
+/// Their proofs are still accepted as inputs until `final_expiry`, or forever if `None`.
+pub async fn deactivate_keysets(
+    conn: &mut PgConnection,
+    keyset_ids: &[i64],
+    final_expiry: Option<u64>,
+) -> Result<(), Error> {
+    let final_expiry = final_expiry
+        .map(|expiry| {
+            i64::try_from(expiry)
+                .ok()
+                .and_then(|expiry| OffsetDateTime::from_unix_timestamp(expiry).ok())
+                .ok_or(Error::RuntimeToDbConversion)
+        })
+        .transpose()?;
+
     sqlx::query!(
-        "UPDATE keyset SET active = false WHERE id = ANY($1)",
-        keyset_ids
+        "UPDATE keyset SET active = false, final_expiry = $2 WHERE id = ANY($1)",
+        keyset_ids,
+        final_expiry
     )
-    .execute(conn)
+    .execute(&mut *conn)
     .await?;
 
+    crate::notify::keyset_activity(conn, keyset_ids).await?;
+
     Ok(())
 }
+
+/// Take the lock serializing the keyset rotations of all the node instances
+///
+/// Held until the end of the current transaction.
+/// Returns `false` if another transaction is already holding it.
+pub async fn try_lock_rotation(conn: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT pg_try_advisory_xact_lock($1) AS "locked!""#,
+        KEYSET_ROTATION_LOCK_KEY
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(record.locked)
+}
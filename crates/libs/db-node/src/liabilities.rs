@@ -0,0 +1,61 @@
+//! Ecash owed by the node to its users
+//!
+//! Every blind signature issued is a claim on the node, until the proof built from it is spent.
+
+use nuts::Amount;
+use sqlx::PgConnection;
+
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Liabilities {
+    /// Sum of the blind signatures issued
+    pub issued: Amount,
+    /// Sum of the proofs spent, or pending in a melt
+    pub redeemed: Amount,
+}
+
+impl Liabilities {
+    /// Ecash in circulation
+    pub fn outstanding(&self) -> Amount {
+        Amount::from(u64::from(self.issued).saturating_sub(u64::from(self.redeemed)))
+    }
+}
+
+/// The liabilities of every unit the node ever issued ecash for
+pub async fn get_by_unit(conn: &mut PgConnection) -> Result<Vec<(String, Liabilities)>, Error> {
+    let records = sqlx::query!(
+        r#"
+            WITH issued AS (
+                SELECT k.unit, SUM(bs.amount) AS amount
+                FROM blind_signature bs JOIN keyset k ON k.id = bs.keyset_id
+                GROUP BY k.unit
+            ), redeemed AS (
+                SELECT k.unit, SUM(p.amount) AS amount
+                FROM proof p JOIN keyset k ON k.id = p.keyset_id
+                GROUP BY k.unit
+            )
+            SELECT
+                COALESCE(i.unit, r.unit) AS "unit!",
+                COALESCE(i.amount, 0)::INT8 AS "issued!",
+                COALESCE(r.amount, 0)::INT8 AS "redeemed!"
+            FROM issued i FULL OUTER JOIN redeemed r ON i.unit = r.unit
+            ORDER BY 1
+        "#
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .map(|r| {
+            (
+                r.unit,
+                Liabilities {
+                    issued: Amount::from_i64_repr(r.issued),
+                    redeemed: Amount::from_i64_repr(r.redeemed),
+                },
+            )
+        })
+        .collect())
+}
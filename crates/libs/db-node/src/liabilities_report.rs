@@ -0,0 +1,335 @@
+//! Proof of liabilities reports
+//!
+//! Every blind signature and spent proof gets tagged with the first report covering it.
+//! The leaves of the trees of report `n` are thus the rows tagged with a report id `<= n`,
+//! which lets the inclusion proofs of past reports be rebuilt.
+
+use nuts::{Amount, nut01::PublicKey, nut02::KeysetId, nut07::ProofState};
+use sqlx::PgConnection;
+
+use crate::Error;
+
+/// Arbitrary key of the advisory lock taken while creating a report, see [`try_lock`]
+const LIABILITIES_REPORT_LOCK_KEY: i64 = 0x6c69_6162_696c;
+
+/// The commitments of a report for one keyset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeysetCommitments {
+    pub keyset_id: KeysetId,
+    /// Sum of the amounts signed
+    pub issued: Amount,
+    pub issued_count: u64,
+    pub issued_root: [u8; 32],
+    /// Sum of the amounts of the proofs spent
+    pub burned: Amount,
+    pub burned_count: u64,
+    pub burned_root: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub id: i64,
+    /// Unix timestamp
+    pub created_at: u64,
+    pub keysets: Vec<KeysetCommitments>,
+}
+
+/// A leaf of a report, found by its point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportedLeaf {
+    pub point: PublicKey,
+    pub keyset_id: KeysetId,
+    pub amount: Amount,
+}
+
+/// Prevent other node instances from creating a report until the end of the transaction
+///
+/// Returns false if another one holds the lock.
+pub async fn try_lock(conn: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT pg_try_advisory_xact_lock($1) AS "locked!""#,
+        LIABILITIES_REPORT_LOCK_KEY
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(record.locked)
+}
+
+/// Return true if some rows are not covered by any report yet
+pub async fn has_unreported_rows(conn: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT
+            EXISTS (SELECT * FROM blind_signature WHERE liabilities_report_id IS NULL)
+            OR EXISTS (SELECT * FROM proof WHERE liabilities_report_id IS NULL AND state = $1)
+        AS "exists!";"#,
+        ProofState::Spent as i16
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(record.exists)
+}
+
+/// Create a new report and tag the rows not covered by any report yet with it
+///
+/// Returns the report id.
+pub async fn create(conn: &mut PgConnection) -> Result<i64, sqlx::Error> {
+    let report_id = sqlx::query!("INSERT INTO liabilities_report DEFAULT VALUES RETURNING id")
+        .fetch_one(&mut *conn)
+        .await?
+        .id;
+
+    sqlx::query!(
+        "UPDATE blind_signature SET liabilities_report_id = $1 WHERE liabilities_report_id IS NULL",
+        report_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    // Pending proofs are covered once spent
+    sqlx::query!(
+        "UPDATE proof SET liabilities_report_id = $1 WHERE liabilities_report_id IS NULL AND state = $2",
+        report_id,
+        ProofState::Spent as i16
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(report_id)
+}
+
+pub async fn get_latest_id(conn: &mut PgConnection) -> Result<Option<i64>, sqlx::Error> {
+    let record = sqlx::query!(r#"SELECT MAX(id) AS "id" FROM liabilities_report"#)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(record.id)
+}
+
+/// The keysets covered by the report, those with at least one leaf
+pub async fn get_reported_keysets(
+    conn: &mut PgConnection,
+    report_id: i64,
+) -> Result<Vec<KeysetId>, Error> {
+    let records = sqlx::query!(
+        r#"
+            SELECT keyset_id FROM blind_signature WHERE liabilities_report_id <= $1
+            UNION
+            SELECT keyset_id FROM proof WHERE liabilities_report_id <= $1
+            ORDER BY 1
+        "#,
+        report_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            r.keyset_id
+                .ok_or(Error::DbToRuntimeConversion)?
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)
+        })
+        .collect()
+}
+
+/// The blinded messages signed with this keyset and covered by the report, ordered
+pub async fn get_issued_leaves(
+    conn: &mut PgConnection,
+    report_id: i64,
+    keyset_id: KeysetId,
+) -> Result<Vec<(PublicKey, Amount)>, Error> {
+    let records = sqlx::query!(
+        r#"SELECT y, amount FROM blind_signature
+        WHERE keyset_id = $1 AND liabilities_report_id <= $2
+        ORDER BY y"#,
+        keyset_id.as_i64(),
+        report_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            Ok((
+                PublicKey::from_slice(&r.y).map_err(|_| Error::DbToRuntimeConversion)?,
+                Amount::from_i64_repr(r.amount),
+            ))
+        })
+        .collect()
+}
+
+/// The proofs of this keyset spent and covered by the report, ordered
+pub async fn get_burned_leaves(
+    conn: &mut PgConnection,
+    report_id: i64,
+    keyset_id: KeysetId,
+) -> Result<Vec<(PublicKey, Amount)>, Error> {
+    let records = sqlx::query!(
+        r#"SELECT y, amount FROM proof
+        WHERE keyset_id = $1 AND liabilities_report_id <= $2
+        ORDER BY y"#,
+        keyset_id.as_i64(),
+        report_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            Ok((
+                PublicKey::from_slice(&r.y).map_err(|_| Error::DbToRuntimeConversion)?,
+                Amount::from_i64_repr(r.amount),
+            ))
+        })
+        .collect()
+}
+
+pub async fn insert_keyset_commitments(
+    conn: &mut PgConnection,
+    report_id: i64,
+    commitments: &KeysetCommitments,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO liabilities_report_keyset
+        (report_id, keyset_id, issued, issued_count, issued_root, burned, burned_count, burned_root)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"#,
+        report_id,
+        commitments.keyset_id.as_i64(),
+        commitments.issued.into_i64_repr(),
+        commitments.issued_count as i64,
+        &commitments.issued_root,
+        commitments.burned.into_i64_repr(),
+        commitments.burned_count as i64,
+        &commitments.burned_root,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get(conn: &mut PgConnection, report_id: i64) -> Result<Option<Report>, Error> {
+    let Some(report) = sqlx::query!(
+        "SELECT id, created_at FROM liabilities_report WHERE id = $1",
+        report_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let keysets = sqlx::query!(
+        r#"SELECT keyset_id, issued, issued_count, issued_root, burned, burned_count, burned_root
+        FROM liabilities_report_keyset WHERE report_id = $1
+        ORDER BY keyset_id"#,
+        report_id
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|r| -> Result<_, Error> {
+        Ok(KeysetCommitments {
+            keyset_id: r
+                .keyset_id
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)?,
+            issued: Amount::from_i64_repr(r.issued),
+            issued_count: u64::try_from(r.issued_count)
+                .map_err(|_| Error::DbToRuntimeConversion)?,
+            issued_root: r
+                .issued_root
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)?,
+            burned: Amount::from_i64_repr(r.burned),
+            burned_count: u64::try_from(r.burned_count)
+                .map_err(|_| Error::DbToRuntimeConversion)?,
+            burned_root: r
+                .burned_root
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)?,
+        })
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Some(Report {
+        id: report.id,
+        created_at: report
+            .created_at
+            .unix_timestamp()
+            .try_into()
+            .map_err(|_| Error::DbToRuntimeConversion)?,
+        keysets,
+    }))
+}
+
+/// The blinded messages among `blinded_messages` covered by the report
+pub async fn find_issued(
+    conn: &mut PgConnection,
+    report_id: i64,
+    blinded_messages: &[PublicKey],
+) -> Result<Vec<ReportedLeaf>, Error> {
+    let points: Vec<_> = blinded_messages
+        .iter()
+        .map(|pk| pk.to_bytes().to_vec())
+        .collect();
+
+    let records = sqlx::query!(
+        r#"SELECT y, keyset_id, amount FROM blind_signature
+        WHERE y = ANY($1) AND liabilities_report_id <= $2"#,
+        &points,
+        report_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            Ok(ReportedLeaf {
+                point: PublicKey::from_slice(&r.y).map_err(|_| Error::DbToRuntimeConversion)?,
+                keyset_id: r
+                    .keyset_id
+                    .try_into()
+                    .map_err(|_| Error::DbToRuntimeConversion)?,
+                amount: Amount::from_i64_repr(r.amount),
+            })
+        })
+        .collect()
+}
+
+/// The proofs among `ys` burned and covered by the report
+pub async fn find_burned(
+    conn: &mut PgConnection,
+    report_id: i64,
+    ys: &[PublicKey],
+) -> Result<Vec<ReportedLeaf>, Error> {
+    let points: Vec<_> = ys.iter().map(|pk| pk.to_bytes().to_vec()).collect();
+
+    let records = sqlx::query!(
+        r#"SELECT y, keyset_id, amount FROM proof
+        WHERE y = ANY($1) AND liabilities_report_id <= $2"#,
+        &points,
+        report_id
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            Ok(ReportedLeaf {
+                point: PublicKey::from_slice(&r.y).map_err(|_| Error::DbToRuntimeConversion)?,
+                keyset_id: r
+                    .keyset_id
+                    .try_into()
+                    .map_err(|_| Error::DbToRuntimeConversion)?,
+                amount: Amount::from_i64_repr(r.amount),
+            })
+        })
+        .collect()
+}
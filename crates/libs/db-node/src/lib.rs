@@ -9,11 +9,21 @@ mod insert_keysets;
 pub use insert_keysets::InsertKeysetsQueryBuilder;
 pub mod blind_signature;
 pub mod keyset;
+pub mod liabilities;
+pub mod liabilities_report;
 pub mod melt_payment_event;
 pub mod melt_quote;
+pub mod melt_transfer_fee;
 pub mod mint_payment_event;
 pub mod mint_quote;
+pub mod node_info;
+pub mod notify;
+pub mod nuts_settings;
 pub mod proof;
+pub mod reserves_attestation;
+pub mod response_cache;
+pub mod starknet_rpc_checkpoint;
+pub mod withdraw_order;
 pub use proof::InsertSpentProofsQueryBuilder;
 
 #[derive(Debug, Error)]
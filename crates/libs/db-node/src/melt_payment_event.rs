@@ -1,6 +1,6 @@
 use sqlx::PgConnection;
 
-use crate::PaymentEvent;
+use crate::{Error, PaymentEvent};
 
 pub async fn insert_new_payment_event(
     db_conn: &mut PgConnection,
@@ -28,15 +28,22 @@ pub async fn insert_new_payment_event(
     Ok(())
 }
 
+/// The amounts of the payments for this invoice included in a block up to `confirmed_height`
 pub async fn get_current_paid(
     db_conn: &mut PgConnection,
     invoice_id: &[u8; 32],
-) -> Result<impl Iterator<Item = (String, String)>, sqlx::Error> {
+    confirmed_height: u64,
+) -> Result<impl Iterator<Item = (String, String)>, Error> {
+    let confirmed_height =
+        i64::try_from(confirmed_height).map_err(|_| Error::RuntimeToDbConversion)?;
+
     let record = sqlx::query!(
-        r#"SELECT amount_low, amount_high
-        FROM melt_payment_event
-        WHERE invoice_id = $1"#,
-        invoice_id
+        r#"SELECT e.amount_low, e.amount_high
+        FROM melt_payment_event e
+            JOIN substreams_starknet_block b ON b.id = e.block_id
+        WHERE e.invoice_id = $1 AND b.number <= $2"#,
+        invoice_id,
+        confirmed_height
     )
     .fetch_all(&mut *db_conn)
     .await?;
@@ -45,3 +52,33 @@ pub async fn get_current_paid(
 
     Ok(amounts_iterator)
 }
+
+/// The invoices paid by the events of the blocks from `from_block` to `to_block` included
+pub async fn get_invoice_ids_in_blocks(
+    db_conn: &mut PgConnection,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<[u8; 32]>, Error> {
+    let from_block = i64::try_from(from_block).map_err(|_| Error::RuntimeToDbConversion)?;
+    let to_block = i64::try_from(to_block).unwrap_or(i64::MAX);
+
+    let records = sqlx::query!(
+        r#"SELECT DISTINCT e.invoice_id
+        FROM melt_payment_event e
+            JOIN substreams_starknet_block b ON b.id = e.block_id
+        WHERE b.number BETWEEN $1 AND $2"#,
+        from_block,
+        to_block
+    )
+    .fetch_all(db_conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            r.invoice_id
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)
+        })
+        .collect()
+}
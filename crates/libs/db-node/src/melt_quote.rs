@@ -145,9 +145,11 @@ pub async fn set_state(
         quote_id,
         state as MeltQuoteState,
     )
-    .execute(conn)
+    .execute(&mut *conn)
     .await?;
 
+    crate::notify::melt_quote_state(conn, quote_id).await?;
+
     Ok(())
 }
 
@@ -194,3 +196,120 @@ pub async fn get_state_and_transfer_ids(
 
     Ok((record.state, record.tx_hashes))
 }
+
+/// List the quotes matching the optional filters, most recently expiring first
+///
+/// Returns the id, unit, amount, fee, state, expiry and request of each quote.
+pub async fn list(
+    conn: &mut PgConnection,
+    state: Option<MeltQuoteState>,
+    unit: Option<&str>,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<(Uuid, String, Amount, Amount, MeltQuoteState, u64, String)>, Error> {
+    let records = sqlx::query!(
+        r#"SELECT id, unit, amount, fee, state AS "state: MeltQuoteState", expiry, request
+        FROM melt_quote
+        WHERE ($1::melt_quote_state IS NULL OR state = $1) AND ($2::TEXT IS NULL OR unit = $2)
+        ORDER BY expiry DESC
+        LIMIT $3 OFFSET $4"#,
+        state as Option<MeltQuoteState>,
+        unit,
+        i64::from(limit),
+        i64::from(offset),
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            let expiry = r
+                .expiry
+                .unix_timestamp()
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)?;
+
+            Ok((
+                r.id,
+                r.unit,
+                Amount::from_i64_repr(r.amount),
+                Amount::from_i64_repr(r.fee),
+                r.state,
+                expiry,
+                r.request,
+            ))
+        })
+        .collect()
+}
+
+/// Make an unpaid quote expire now
+///
+/// Returns false if there is no unpaid and unexpired quote with this id.
+pub async fn expire(conn: &mut PgConnection, quote_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE melt_quote
+            SET expiry = NOW()
+            WHERE id = $1 AND state = 'UNPAID' AND expiry > NOW()
+        "#,
+        quote_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    crate::notify::melt_quote_state(conn, quote_id).await?;
+
+    Ok(true)
+}
+
+/// Mark the unpaid quotes past their expiry as expired
+///
+/// Returns the number of quotes marked.
+pub async fn mark_expired(conn: &mut PgConnection) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE melt_quote
+            SET expired_at = NOW()
+            WHERE state = 'UNPAID' AND expiry <= NOW() AND expired_at IS NULL
+        "#
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Move the quotes marked expired before `expired_before` to the archive
+///
+/// Quotes that sent any payment are kept, whatever their state.
+/// Returns the number of quotes archived.
+pub async fn archive_expired(conn: &mut PgConnection, expired_before: u64) -> Result<u64, Error> {
+    let expired_before = i64::try_from(expired_before)
+        .ok()
+        .and_then(|t| OffsetDateTime::from_unix_timestamp(t).ok())
+        .ok_or(Error::RuntimeToDbConversion)?;
+
+    let result = sqlx::query!(
+        r#"
+            WITH archived AS (
+                DELETE FROM melt_quote mq
+                WHERE state = 'UNPAID' AND expired_at <= $1
+                    AND NOT EXISTS (SELECT 1 FROM melt_payment_event WHERE invoice_id = mq.invoice_id)
+                RETURNING id, invoice_id, unit, amount, fee, request, expiry, state, expired_at
+            )
+            INSERT INTO melt_quote_archive
+                (id, invoice_id, unit, amount, fee, request, expiry, state, expired_at, archived_at)
+            SELECT id, invoice_id, unit, amount, fee, request, expiry, state, expired_at, NOW()
+            FROM archived
+        "#,
+        expired_before
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,23 @@
+use nuts::{Amount, traits::Unit};
+use sqlx::PgConnection;
+
+/// Record the fee paid on-chain to send a melt transfer
+pub async fn insert<U: Unit>(
+    conn: &mut PgConnection,
+    tx_hash: &str,
+    unit: U,
+    amount: Amount,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO melt_transfer_fee (tx_hash, unit, amount)
+        VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING"#,
+        tx_hash,
+        &unit.to_string(),
+        amount.into_i64_repr(),
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use sqlx::PgConnection;
+use uuid::Uuid;
 
-use crate::PaymentEvent;
+use crate::{Error, PaymentEvent};
 
 pub async fn insert_new_payment_event(
     db_conn: &mut PgConnection,
@@ -28,15 +29,22 @@ pub async fn insert_new_payment_event(
     Ok(())
 }
 
+/// The amounts of the payments for this invoice included in a block up to `confirmed_height`
 pub async fn get_current_paid(
     db_conn: &mut PgConnection,
     invoice_id: &[u8; 32],
-) -> Result<impl Iterator<Item = (String, String)>, sqlx::Error> {
+    confirmed_height: u64,
+) -> Result<impl Iterator<Item = (String, String)>, Error> {
+    let confirmed_height =
+        i64::try_from(confirmed_height).map_err(|_| Error::RuntimeToDbConversion)?;
+
     let record = sqlx::query!(
-        r#"SELECT amount_low, amount_high
-        FROM mint_payment_event
-        WHERE invoice_id = $1"#,
-        invoice_id
+        r#"SELECT e.amount_low, e.amount_high
+        FROM mint_payment_event e
+            JOIN substreams_starknet_block b ON b.id = e.block_id
+        WHERE e.invoice_id = $1 AND b.number <= $2"#,
+        invoice_id,
+        confirmed_height
     )
     .fetch_all(&mut *db_conn)
     .await?;
@@ -45,3 +53,96 @@ pub async fn get_current_paid(
 
     Ok(amounts_iterator)
 }
+
+/// The invoices paid by the events of the blocks from `from_block` to `to_block` included
+pub async fn get_invoice_ids_in_blocks(
+    db_conn: &mut PgConnection,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<[u8; 32]>, Error> {
+    let from_block = i64::try_from(from_block).map_err(|_| Error::RuntimeToDbConversion)?;
+    let to_block = i64::try_from(to_block).unwrap_or(i64::MAX);
+
+    let records = sqlx::query!(
+        r#"SELECT DISTINCT e.invoice_id
+        FROM mint_payment_event e
+            JOIN substreams_starknet_block b ON b.id = e.block_id
+        WHERE b.number BETWEEN $1 AND $2"#,
+        from_block,
+        to_block
+    )
+    .fetch_all(db_conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            r.invoice_id
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)
+        })
+        .collect()
+}
+
+/// A payment included in a block more recent than the expiry of its quote
+#[derive(Debug, Clone)]
+pub struct LatePayment {
+    pub quote_id: Uuid,
+    pub tx_hash: String,
+    pub event_index: i64,
+    pub payer: String,
+    pub asset: String,
+    pub amount_low: String,
+    pub amount_high: String,
+}
+
+/// Flag the payments received after the expiry of their quote, archived or not
+///
+/// Returns the payments that were not flagged yet.
+pub async fn flag_late_payments(
+    db_conn: &mut PgConnection,
+) -> Result<Vec<LatePayment>, sqlx::Error> {
+    let records = sqlx::query!(
+        r#"
+            WITH flagged AS (
+                INSERT INTO late_mint_payment (tx_hash, event_index, quote_id, detected_at)
+                SELECT e.tx_hash, e.event_index, mq.id, NOW()
+                FROM mint_payment_event e
+                    JOIN (
+                        SELECT id, invoice_id, expiry FROM mint_quote
+                        UNION ALL
+                        SELECT id, invoice_id, expiry FROM mint_quote_archive
+                    ) mq ON mq.invoice_id = e.invoice_id
+                    JOIN substreams_starknet_block b ON b.id = e.block_id
+                WHERE b.timestamp > mq.expiry
+                ON CONFLICT DO NOTHING
+                RETURNING tx_hash, event_index, quote_id
+            )
+            SELECT
+                f.quote_id AS "quote_id!",
+                f.tx_hash AS "tx_hash!",
+                f.event_index AS "event_index!",
+                e.payer,
+                e.asset,
+                e.amount_low,
+                e.amount_high
+            FROM flagged f
+                JOIN mint_payment_event e ON e.tx_hash = f.tx_hash AND e.event_index = f.event_index
+        "#
+    )
+    .fetch_all(db_conn)
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .map(|r| LatePayment {
+            quote_id: r.quote_id,
+            tx_hash: r.tx_hash,
+            event_index: r.event_index,
+            payer: r.payer,
+            asset: r.asset,
+            amount_low: r.amount_low,
+            amount_high: r.amount_high,
+        })
+        .collect())
+}
@@ -40,7 +40,7 @@ pub async fn build_response_from_db(
     quote_id: Uuid,
 ) -> Result<Option<MintQuoteResponse<Uuid>>, Error> {
     let record = match sqlx::query!(
-        r#"SELECT request, state AS "state: MintQuoteState", expiry FROM mint_quote where id = $1"#,
+        r#"SELECT request, state AS "state: MintQuoteState", expiry, amount_paid FROM mint_quote where id = $1"#,
         quote_id
     )
     .fetch_optional(conn)
@@ -61,6 +61,7 @@ pub async fn build_response_from_db(
         request: record.request,
         state: record.state,
         expiry,
+        amount_paid: Amount::from_i64_repr(record.amount_paid),
     }))
 }
 
@@ -94,9 +95,39 @@ pub async fn set_state(
         quote_id,
         state as MintQuoteState
     )
-    .execute(conn)
+    .execute(&mut *conn)
+    .await?;
+
+    crate::notify::mint_quote_state(conn, quote_id).await?;
+
+    Ok(())
+}
+
+/// Record the sum of the payments received for this quote
+///
+/// `excess` is what was received on top of the quoted amount, in the asset blockchain-native
+/// representation.
+pub async fn set_amount_paid(
+    conn: &mut PgConnection,
+    quote_id: Uuid,
+    amount_paid: Amount,
+    excess: Option<[u8; 32]>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+            UPDATE mint_quote
+            SET amount_paid = $2, excess = $3
+            WHERE id = $1
+        "#,
+        quote_id,
+        amount_paid.into_i64_repr(),
+        excess.as_ref().map(|e| e.as_slice())
+    )
+    .execute(&mut *conn)
     .await?;
 
+    crate::notify::mint_quote_state(conn, quote_id).await?;
+
     Ok(())
 }
 
@@ -124,3 +155,164 @@ pub async fn get_quote_infos_by_invoice_id<U: Unit>(
 
     Ok(ret)
 }
+
+/// Same as [`get_quote_infos_by_invoice_id`], for the quotes moved to the archive
+pub async fn get_archived_quote_infos_by_invoice_id<U: Unit>(
+    conn: &mut PgConnection,
+    invoice_id: &[u8; 32],
+) -> Result<Option<(Uuid, Amount, U)>, Error> {
+    let record = sqlx::query!(
+        r#"
+            SELECT id, amount, unit from mint_quote_archive WHERE invoice_id = $1 LIMIT 1
+        "#,
+        invoice_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    let ret = if let Some(record) = record {
+        let quote_id = record.id;
+        let amount = Amount::from_i64_repr(record.amount);
+        let unit = U::from_str(&record.unit).map_err(|_| Error::DbToRuntimeConversion)?;
+        Some((quote_id, amount, unit))
+    } else {
+        None
+    };
+
+    Ok(ret)
+}
+
+/// A quote, as listed by [`list`]
+#[derive(Debug, Clone)]
+pub struct ListedMintQuote {
+    pub id: Uuid,
+    pub unit: String,
+    pub amount: Amount,
+    pub state: MintQuoteState,
+    pub expiry: u64,
+    pub request: String,
+    pub amount_paid: Amount,
+    /// See [`set_amount_paid`]
+    pub excess: Option<[u8; 32]>,
+}
+
+/// List the quotes matching the optional filters, most recently expiring first
+pub async fn list(
+    conn: &mut PgConnection,
+    state: Option<MintQuoteState>,
+    unit: Option<&str>,
+    limit: u32,
+    offset: u32,
+) -> Result<Vec<ListedMintQuote>, Error> {
+    let records = sqlx::query!(
+        r#"SELECT id, unit, amount, state AS "state: MintQuoteState", expiry, request, amount_paid, excess
+        FROM mint_quote
+        WHERE ($1::mint_quote_state IS NULL OR state = $1) AND ($2::TEXT IS NULL OR unit = $2)
+        ORDER BY expiry DESC
+        LIMIT $3 OFFSET $4"#,
+        state as Option<MintQuoteState>,
+        unit,
+        i64::from(limit),
+        i64::from(offset),
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            let expiry = r
+                .expiry
+                .unix_timestamp()
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)?;
+
+            let excess = r
+                .excess
+                .map(|e| e.try_into().map_err(|_| Error::DbToRuntimeConversion))
+                .transpose()?;
+
+            Ok(ListedMintQuote {
+                id: r.id,
+                unit: r.unit,
+                amount: Amount::from_i64_repr(r.amount),
+                state: r.state,
+                expiry,
+                request: r.request,
+                amount_paid: Amount::from_i64_repr(r.amount_paid),
+                excess,
+            })
+        })
+        .collect()
+}
+
+/// Make an unpaid quote expire now
+///
+/// Returns false if there is no unpaid and unexpired quote with this id.
+pub async fn expire(conn: &mut PgConnection, quote_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE mint_quote
+            SET expiry = NOW()
+            WHERE id = $1 AND state = 'UNPAID' AND expiry > NOW()
+        "#,
+        quote_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    crate::notify::mint_quote_state(conn, quote_id).await?;
+
+    Ok(true)
+}
+
+/// Mark the unpaid quotes past their expiry as expired
+///
+/// Returns the number of quotes marked.
+pub async fn mark_expired(conn: &mut PgConnection) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE mint_quote
+            SET expired_at = NOW()
+            WHERE state = 'UNPAID' AND expiry <= NOW() AND expired_at IS NULL
+        "#
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Move the quotes marked expired before `expired_before` to the archive
+///
+/// Quotes that received any payment are kept, whatever their state.
+/// Returns the number of quotes archived.
+pub async fn archive_expired(conn: &mut PgConnection, expired_before: u64) -> Result<u64, Error> {
+    let expired_before = i64::try_from(expired_before)
+        .ok()
+        .and_then(|t| OffsetDateTime::from_unix_timestamp(t).ok())
+        .ok_or(Error::RuntimeToDbConversion)?;
+
+    let result = sqlx::query!(
+        r#"
+            WITH archived AS (
+                DELETE FROM mint_quote mq
+                WHERE state = 'UNPAID' AND expired_at <= $1
+                    AND NOT EXISTS (SELECT 1 FROM mint_payment_event WHERE invoice_id = mq.invoice_id)
+                RETURNING id, invoice_id, unit, amount, request, expiry, state, expired_at
+            )
+            INSERT INTO mint_quote_archive
+                (id, invoice_id, unit, amount, request, expiry, state, expired_at, archived_at)
+            SELECT id, invoice_id, unit, amount, request, expiry, state, expired_at, NOW()
+            FROM archived
+        "#,
+        expired_before
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,28 @@
+//! Node info fields overriding the node built-in ones
+//!
+//! Stored as JSON text so that this crate doesn't need to know their concrete type.
+
+use sqlx::PgConnection;
+
+pub async fn get(conn: &mut PgConnection) -> Result<Option<String>, sqlx::Error> {
+    let record = sqlx::query!(r#"SELECT info::TEXT AS "info!" FROM node_info"#)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(record.map(|r| r.info))
+}
+
+/// Store new info and tell the other node instances to reload it
+pub async fn upsert(conn: &mut PgConnection, info: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO node_info (info) VALUES ($1::TEXT::JSONB)
+        ON CONFLICT (id) DO UPDATE SET info = EXCLUDED.info, updated_at = NOW()"#,
+        info
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    crate::notify::node_info(conn).await?;
+
+    Ok(())
+}
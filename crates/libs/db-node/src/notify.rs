@@ -0,0 +1,107 @@
+//! Postgres notifications emitted when the node shared state changes
+//!
+//! They tell the node instances about quote and proof state updates, for subscriptions,
+//! and about the changes that invalidate their in-process caches.
+//! They are sent from within the caller's transaction, so listeners only
+//! see them once the new state has been committed.
+
+use nuts::nut01::PublicKey;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+/// Payload is the quote id
+pub const MINT_QUOTE_STATE_CHANNEL: &str = "mint_quote_state";
+/// Payload is the quote id
+pub const MELT_QUOTE_STATE_CHANNEL: &str = "melt_quote_state";
+/// Payload is the hex encoded `Y` of the proof
+pub const PROOF_STATE_CHANNEL: &str = "proof_state";
+/// Payload is the keyset id, in its db representation
+pub const KEYSET_ACTIVITY_CHANNEL: &str = "keyset_activity";
+/// Empty payload, the settings are to be read from the db
+pub const NUTS_SETTINGS_CHANNEL: &str = "nuts_settings";
+/// Payload is `<route>:<request hash>` of the removed entry
+pub const RESPONSE_CACHE_CHANNEL: &str = "response_cache";
+/// Empty payload, the info is to be read from the db
+pub const NODE_INFO_CHANNEL: &str = "node_info";
+
+/// Empty payload, the orders are to be read from the db
+///
+/// Only listened to by the instance consuming the withdraw orders, see [`crate::withdraw_order`].
+pub const WITHDRAW_ORDER_CHANNEL: &str = "withdraw_order";
+
+/// The channels of the node shared state
+pub const ALL_CHANNELS: [&str; 7] = [
+    MINT_QUOTE_STATE_CHANNEL,
+    MELT_QUOTE_STATE_CHANNEL,
+    PROOF_STATE_CHANNEL,
+    KEYSET_ACTIVITY_CHANNEL,
+    NUTS_SETTINGS_CHANNEL,
+    RESPONSE_CACHE_CHANNEL,
+    NODE_INFO_CHANNEL,
+];
+
+pub async fn mint_quote_state(conn: &mut PgConnection, quote_id: Uuid) -> Result<(), sqlx::Error> {
+    notify(conn, MINT_QUOTE_STATE_CHANNEL, &[quote_id.to_string()]).await
+}
+
+pub async fn melt_quote_state(conn: &mut PgConnection, quote_id: Uuid) -> Result<(), sqlx::Error> {
+    notify(conn, MELT_QUOTE_STATE_CHANNEL, &[quote_id.to_string()]).await
+}
+
+pub async fn proof_state(conn: &mut PgConnection, ys: &[PublicKey]) -> Result<(), sqlx::Error> {
+    let payloads: Vec<String> = ys.iter().map(PublicKey::to_hex).collect();
+
+    notify(conn, PROOF_STATE_CHANNEL, &payloads).await
+}
+
+pub async fn keyset_activity(
+    conn: &mut PgConnection,
+    keyset_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    let payloads: Vec<String> = keyset_ids.iter().map(i64::to_string).collect();
+
+    notify(conn, KEYSET_ACTIVITY_CHANNEL, &payloads).await
+}
+
+pub async fn nuts_settings(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    notify(conn, NUTS_SETTINGS_CHANNEL, &[String::new()]).await
+}
+
+pub async fn node_info(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    notify(conn, NODE_INFO_CHANNEL, &[String::new()]).await
+}
+
+pub async fn response_cache_entry(
+    conn: &mut PgConnection,
+    route: &str,
+    request_hash: u64,
+) -> Result<(), sqlx::Error> {
+    notify(
+        conn,
+        RESPONSE_CACHE_CHANNEL,
+        &[format!("{route}:{request_hash}")],
+    )
+    .await
+}
+
+pub async fn withdraw_order(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    notify(conn, WITHDRAW_ORDER_CHANNEL, &[String::new()]).await
+}
+
+async fn notify(
+    conn: &mut PgConnection,
+    channel: &str,
+    payloads: &[String],
+) -> Result<(), sqlx::Error> {
+    if payloads.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query("SELECT pg_notify($1, payload) FROM UNNEST($2::TEXT[]) AS payload")
+        .bind(channel)
+        .bind(payloads)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+//! Nuts settings overriding the node built-in ones
+//!
+//! Stored as JSON text so that this crate doesn't need to know their concrete type.
+
+use sqlx::PgConnection;
+
+pub async fn get(conn: &mut PgConnection) -> Result<Option<String>, sqlx::Error> {
+    let record = sqlx::query!(r#"SELECT settings::TEXT AS "settings!" FROM nuts_settings"#)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(record.map(|r| r.settings))
+}
+
+/// Store new settings and tell the other node instances to reload them
+pub async fn upsert(conn: &mut PgConnection, settings: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"INSERT INTO nuts_settings (settings) VALUES ($1::TEXT::JSONB)
+        ON CONFLICT (id) DO UPDATE SET settings = EXCLUDED.settings, updated_at = NOW()"#,
+        settings
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    crate::notify::nuts_settings(conn).await?;
+
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use nuts::{
+    Amount,
     nut00::{Proof, secret::Secret},
     nut01::PublicKey,
     nut02::KeysetId,
@@ -6,6 +7,9 @@ use nuts::{
 };
 
 use sqlx::{PgConnection, Postgres, QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::Error;
 
 /// Return true if one of the provided secret
 /// is already in db with state = SPENT
@@ -153,6 +157,76 @@ pub async fn get_proofs_by_ids(
     Ok(ret)
 }
 
+/// Number of spent and pending proofs of each unit
+pub async fn count_by_unit(
+    conn: &mut PgConnection,
+) -> Result<Vec<(String, u64, u64)>, sqlx::Error> {
+    let records = sqlx::query!(
+        r#"SELECT
+            k.unit,
+            COUNT(*) FILTER (WHERE p.state = $1) AS "spent!",
+            COUNT(*) FILTER (WHERE p.state = $2) AS "pending!"
+        FROM proof p JOIN keyset k ON k.id = p.keyset_id
+        GROUP BY k.unit
+        ORDER BY k.unit"#,
+        ProofState::Spent as i16,
+        ProofState::Pending as i16,
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(records
+        .into_iter()
+        .map(|r| (r.unit, r.spent.unsigned_abs(), r.pending.unsigned_abs()))
+        .collect())
+}
+
+/// The proofs spent to pay a melt quote that could be returned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReturnedMeltInputs {
+    /// Deleted, which makes them unspent again
+    pub returned: Vec<PublicKey>,
+    /// Sum of the proofs that could not be returned, because they are committed to by a
+    /// liabilities report, and must be refunded by other means
+    pub kept_amount: Amount,
+}
+
+/// Return the proofs spent to pay a melt quote whose payment failed
+///
+/// Proofs already committed to by a liabilities report are kept, so that the report stays
+/// verifiable.
+pub async fn return_melt_inputs(
+    conn: &mut PgConnection,
+    quote_id: Uuid,
+) -> Result<ReturnedMeltInputs, Error> {
+    let returned = sqlx::query!(
+        r#"DELETE FROM proof
+        WHERE melt_quote_id = $1 AND liabilities_report_id IS NULL
+        RETURNING y"#,
+        quote_id
+    )
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|r| PublicKey::from_slice(&r.y).map_err(|_| Error::DbToRuntimeConversion))
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let kept_amount = sqlx::query!(
+        r#"SELECT COALESCE(SUM(amount), 0)::INT8 AS "amount!" FROM proof WHERE melt_quote_id = $1"#,
+        quote_id
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .amount;
+
+    crate::notify::proof_state(conn, &returned).await?;
+
+    Ok(ReturnedMeltInputs {
+        returned,
+        kept_amount: Amount::from_i64_repr(kept_amount),
+    })
+}
+
 /// Generate a query following this model:
 /// INSERT INTO proof (y, amount, keyset_id, secret, c, state)
 /// VALUES  ($1, $2, $3, $4, $5, 1), ($6, $7, $8, $9, $10, 1)
@@ -163,7 +237,8 @@ pub async fn get_proofs_by_ids(
 /// or or update previously existing UNSPENT proofs to SPENT.
 pub struct InsertSpentProofsQueryBuilder<'args> {
     builder: QueryBuilder<'args, Postgres>,
-    first: bool,
+    ys: Vec<PublicKey>,
+    melt_quote_id: Option<Uuid>,
 }
 
 impl<'args> InsertSpentProofsQueryBuilder<'args> {
@@ -172,11 +247,22 @@ impl<'args> InsertSpentProofsQueryBuilder<'args> {
             builder: QueryBuilder::new(
                 r#"INSERT INTO proof (y, amount, keyset_id, secret, c, state) VALUES "#,
             ),
-            first: true,
+            ys: Vec::new(),
+            melt_quote_id: None,
         }
     }
 
+    /// Link the proofs to the melt quote they pay, see [`return_melt_inputs`]
+    pub fn set_melt_quote_id(&mut self, quote_id: Uuid) {
+        self.melt_quote_id = Some(quote_id);
+    }
+
     pub fn add_row(&mut self, y: &PublicKey, proof: &'args Proof) {
+        if !self.ys.is_empty() {
+            self.builder.push(", ");
+        }
+        self.ys.push(*y);
+
         let y = y.to_bytes();
         let amount = proof.amount.into_i64_repr();
         let keyset_id = proof.keyset_id.as_i64();
@@ -184,12 +270,6 @@ impl<'args> InsertSpentProofsQueryBuilder<'args> {
         let c = proof.c.to_bytes();
         let state = ProofState::Spent as i16;
 
-        if self.first {
-            self.first = false;
-        } else {
-            self.builder.push(", ");
-        }
-
         self.builder
             .push('(')
             .push_bind(y)
@@ -217,9 +297,22 @@ impl<'args> InsertSpentProofsQueryBuilder<'args> {
                 ProofState::Spent as i16
             ))
             .build()
-            .execute(conn)
+            .execute(&mut *conn)
             .await?;
 
+        if let Some(quote_id) = self.melt_quote_id {
+            let ys: Vec<_> = self.ys.iter().map(|y| y.to_bytes().to_vec()).collect();
+            sqlx::query!(
+                "UPDATE proof SET melt_quote_id = $1 WHERE y = ANY($2)",
+                quote_id,
+                &ys
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        crate::notify::proof_state(conn, &self.ys).await?;
+
         Ok(())
     }
 }
@@ -253,6 +346,8 @@ mod query_builder {
                 "02194603ffa36356f4a56b7df9371fc3192472351453ec7398b8da8117e7c3e104",
             )
             .unwrap(),
+            witness: None,
+            dleq: None,
         };
         let y = proof.y().unwrap();
 
@@ -0,0 +1,124 @@
+//! Proof of reserves attestations
+//!
+//! The amounts are stored as 32 bytes big-endian integers, in the asset blockchain-native
+//! representation, which may not fit in any postgres integer type.
+
+use sqlx::{PgConnection, types::time::OffsetDateTime};
+
+use crate::Error;
+
+/// The amounts of an attestation for one asset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetAmounts {
+    pub asset: String,
+    pub liabilities: [u8; 32],
+    pub reserves: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attestation {
+    pub id: i64,
+    /// Unix timestamp
+    pub created_at: u64,
+    pub reserves_address: String,
+    pub assets: Vec<AssetAmounts>,
+    pub hash: [u8; 32],
+    pub signature: Vec<u8>,
+}
+
+/// Returns the attestation id
+pub async fn insert(
+    conn: &mut PgConnection,
+    created_at: u64,
+    reserves_address: &str,
+    assets: &[AssetAmounts],
+    hash: &[u8; 32],
+    signature: &[u8],
+) -> Result<i64, Error> {
+    let created_at = i64::try_from(created_at)
+        .ok()
+        .and_then(|t| OffsetDateTime::from_unix_timestamp(t).ok())
+        .ok_or(Error::RuntimeToDbConversion)?;
+
+    let attestation_id = sqlx::query!(
+        r#"INSERT INTO reserves_attestation (created_at, reserves_address, hash, signature)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id"#,
+        created_at,
+        reserves_address,
+        hash,
+        signature
+    )
+    .fetch_one(&mut *conn)
+    .await?
+    .id;
+
+    for asset in assets {
+        sqlx::query!(
+            r#"INSERT INTO reserves_attestation_asset (attestation_id, asset, liabilities, reserves)
+            VALUES ($1, $2, $3, $4)"#,
+            attestation_id,
+            asset.asset,
+            &asset.liabilities,
+            &asset.reserves
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(attestation_id)
+}
+
+pub async fn get_latest(conn: &mut PgConnection) -> Result<Option<Attestation>, Error> {
+    let Some(record) = sqlx::query!(
+        r#"SELECT id, created_at, reserves_address, hash, signature
+        FROM reserves_attestation
+        ORDER BY id DESC
+        LIMIT 1"#
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let assets = sqlx::query!(
+        r#"SELECT asset, liabilities, reserves FROM reserves_attestation_asset
+        WHERE attestation_id = $1
+        ORDER BY asset"#,
+        record.id
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|r| -> Result<_, Error> {
+        Ok(AssetAmounts {
+            asset: r.asset,
+            liabilities: r
+                .liabilities
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)?,
+            reserves: r
+                .reserves
+                .try_into()
+                .map_err(|_| Error::DbToRuntimeConversion)?,
+        })
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Some(Attestation {
+        id: record.id,
+        created_at: record
+            .created_at
+            .unix_timestamp()
+            .try_into()
+            .map_err(|_| Error::DbToRuntimeConversion)?,
+        reserves_address: record.reserves_address,
+        assets,
+        hash: record
+            .hash
+            .try_into()
+            .map_err(|_| Error::DbToRuntimeConversion)?,
+        signature: record.signature,
+    }))
+}
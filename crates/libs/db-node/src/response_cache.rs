@@ -0,0 +1,78 @@
+//! Storage of the NUT-19 cached responses
+//!
+//! `request_hash` is stored as the bit-identical `INT8`, and entries with a `NULL`
+//! `expires_at` never expire.
+
+use std::time::Duration;
+
+use sqlx::PgConnection;
+
+/// Get a response, unless it has expired
+pub async fn get(
+    conn: &mut PgConnection,
+    route: &str,
+    request_hash: u64,
+) -> Result<Option<Vec<u8>>, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT response FROM response_cache
+        WHERE route = $1 AND request_hash = $2 AND (expires_at IS NULL OR expires_at > NOW())"#,
+        route,
+        request_hash as i64,
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(record.map(|r| r.response))
+}
+
+/// Store a response, replacing the previous one for the same request
+pub async fn insert(
+    conn: &mut PgConnection,
+    route: &str,
+    request_hash: u64,
+    response: &[u8],
+    ttl: Option<Duration>,
+) -> Result<(), sqlx::Error> {
+    let ttl_secs = ttl.map(|ttl| ttl.as_secs_f64());
+
+    sqlx::query!(
+        r#"INSERT INTO response_cache (route, request_hash, response, expires_at)
+        VALUES ($1, $2, $3, NOW() + $4 * INTERVAL '1 second')
+        ON CONFLICT (route, request_hash)
+        DO UPDATE SET response = EXCLUDED.response, expires_at = EXCLUDED.expires_at"#,
+        route,
+        request_hash as i64,
+        response,
+        ttl_secs,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Returns true if there was a response to remove
+pub async fn remove(
+    conn: &mut PgConnection,
+    route: &str,
+    request_hash: u64,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM response_cache WHERE route = $1 AND request_hash = $2",
+        route,
+        request_hash as i64,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() != 0)
+}
+
+/// Returns the number of responses removed
+pub async fn delete_expired(conn: &mut PgConnection) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM response_cache WHERE expires_at <= NOW()")
+        .execute(conn)
+        .await?;
+
+    Ok(result.rows_affected())
+}
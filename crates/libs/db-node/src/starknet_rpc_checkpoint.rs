@@ -0,0 +1,89 @@
+//! Blocks indexed by the starknet rpc indexer
+//!
+//! The last block of each indexed range is recorded with its hash. When the chain no longer
+//! has the same hash at one of those heights a reorg happened, and everything indexed after
+//! the latest checkpoint still on the chain has to be indexed again.
+
+use sqlx::PgConnection;
+
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub number: u64,
+    pub hash: String,
+}
+
+pub async fn insert(conn: &mut PgConnection, number: u64, hash: &str) -> Result<(), Error> {
+    let number = i64::try_from(number).map_err(|_| Error::RuntimeToDbConversion)?;
+
+    sqlx::query!(
+        r#"
+            INSERT INTO starknet_rpc_checkpoint (number, hash) VALUES ($1, $2)
+            ON CONFLICT (number) DO UPDATE SET hash = excluded.hash
+        "#,
+        number,
+        hash
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// All the checkpoints, the latest first
+pub async fn get_all(conn: &mut PgConnection) -> Result<Vec<Checkpoint>, Error> {
+    let records = sqlx::query!(
+        r#"
+            SELECT number, hash FROM starknet_rpc_checkpoint ORDER BY number DESC
+        "#
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            Ok(Checkpoint {
+                number: r
+                    .number
+                    .try_into()
+                    .map_err(|_| Error::DbToRuntimeConversion)?,
+                hash: r.hash,
+            })
+        })
+        .collect()
+}
+
+/// Delete the checkpoints above `number`, which are no longer on the chain
+pub async fn delete_after(conn: &mut PgConnection, number: u64) -> Result<(), Error> {
+    let number = i64::try_from(number).map_err(|_| Error::RuntimeToDbConversion)?;
+
+    sqlx::query!(
+        r#"
+            DELETE FROM starknet_rpc_checkpoint WHERE number > $1
+        "#,
+        number
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Only keep the `kept` latest checkpoints
+pub async fn prune(conn: &mut PgConnection, kept: u32) -> Result<(), Error> {
+    sqlx::query!(
+        r#"
+            DELETE FROM starknet_rpc_checkpoint
+            WHERE number NOT IN (
+                SELECT number FROM starknet_rpc_checkpoint ORDER BY number DESC LIMIT $1
+            )
+        "#,
+        i64::from(kept)
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
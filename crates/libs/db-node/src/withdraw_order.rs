@@ -0,0 +1,293 @@
+//! Outbox of the on-chain payments of the melt quotes
+//!
+//! Orders are registered in the transaction spending the melt inputs, and consumed by the liquidity
+//! source sending the payments, so that none is lost if the node stops before they are sent.
+//! Registering one notifies [`crate::notify::WITHDRAW_ORDER_CHANNEL`] once committed.
+//! They are keyed on the quote invoice id, registering one twice is a no-op.
+//! Orders whose payment failed are retried with an exponential backoff, up to a maximum number of
+//! attempts.
+
+use std::time::Duration;
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::Error;
+
+/// Arbitrary key of the advisory lock held by the consumer of the orders, see [`try_lock`]
+const WITHDRAW_ORDER_LOCK_KEY: i64 = 0x7769_7468_6472;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "withdraw_order_state", rename_all = "UPPERCASE")]
+pub enum WithdrawOrderState {
+    /// Not sent yet
+    Pending,
+    /// Sent, waiting for the transaction to be included in a block
+    Sent,
+    Succeeded,
+    /// Every attempt to pay it failed
+    Failed,
+}
+
+/// An order that has not been sent yet
+#[derive(Debug, Clone)]
+pub struct PendingWithdrawOrder {
+    pub invoice_id: [u8; 32],
+    pub quote_id: Uuid,
+    pub payload: String,
+    /// Number of failed attempts to pay it
+    pub attempts: u32,
+    /// Unix timestamp
+    pub created_at: u64,
+    /// Unix timestamp of the quote expiry, after which the payment will be rejected
+    pub expiry: u64,
+}
+
+/// Register a new order
+///
+/// Returns false if there was already one for this invoice.
+pub async fn insert(
+    conn: &mut PgConnection,
+    invoice_id: &[u8; 32],
+    quote_id: Uuid,
+    payload: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            INSERT INTO withdraw_order (invoice_id, quote_id, payload, state, created_at, updated_at)
+            VALUES ($1, $2, $3, 'PENDING', NOW(), NOW())
+            ON CONFLICT DO NOTHING
+        "#,
+        invoice_id,
+        quote_id,
+        payload
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() != 0)
+}
+
+/// Prevent other node instances from consuming the orders, until this connection is closed
+///
+/// Returns false if another one holds the lock.
+pub async fn try_lock(conn: &mut PgConnection) -> Result<bool, sqlx::Error> {
+    let record = sqlx::query!(
+        r#"SELECT pg_try_advisory_lock($1) AS "locked!""#,
+        WITHDRAW_ORDER_LOCK_KEY
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(record.locked)
+}
+
+/// Mark as succeeded the pending orders whose payment was already indexed
+///
+/// It happens when the node stopped after sending the transaction, before registering it.
+/// Returns the number of orders marked.
+pub async fn mark_indexed_as_succeeded(conn: &mut PgConnection) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE withdraw_order wo
+            SET state = 'SUCCEEDED', updated_at = NOW()
+            WHERE state = 'PENDING'
+                AND EXISTS (SELECT 1 FROM melt_payment_event WHERE invoice_id = wo.invoice_id)
+        "#
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// The orders not sent yet whose retry delay is over, the ones expiring first first
+pub async fn get_pendings(
+    conn: &mut PgConnection,
+    limit: u32,
+) -> Result<Vec<PendingWithdrawOrder>, Error> {
+    let records = sqlx::query!(
+        r#"
+            SELECT wo.invoice_id, wo.quote_id, wo.payload, wo.attempts, wo.created_at, mq.expiry
+            FROM withdraw_order wo
+            JOIN melt_quote mq ON mq.id = wo.quote_id
+            WHERE wo.state = 'PENDING' AND wo.next_attempt_at <= NOW()
+            ORDER BY mq.expiry, wo.created_at
+            LIMIT $1
+        "#,
+        i64::from(limit)
+    )
+    .fetch_all(conn)
+    .await?;
+
+    records
+        .into_iter()
+        .map(|r| {
+            Ok(PendingWithdrawOrder {
+                invoice_id: r
+                    .invoice_id
+                    .try_into()
+                    .map_err(|_| Error::DbToRuntimeConversion)?,
+                quote_id: r.quote_id,
+                payload: r.payload,
+                attempts: r
+                    .attempts
+                    .try_into()
+                    .map_err(|_| Error::DbToRuntimeConversion)?,
+                created_at: r
+                    .created_at
+                    .unix_timestamp()
+                    .try_into()
+                    .map_err(|_| Error::DbToRuntimeConversion)?,
+                expiry: r
+                    .expiry
+                    .unix_timestamp()
+                    .try_into()
+                    .map_err(|_| Error::DbToRuntimeConversion)?,
+            })
+        })
+        .collect()
+}
+
+/// The hashes of the transactions sent, not included in a block yet
+pub async fn get_sent_tx_hashes(conn: &mut PgConnection) -> Result<Vec<String>, sqlx::Error> {
+    let records = sqlx::query!(
+        r#"
+            SELECT DISTINCT tx_hash AS "tx_hash!"
+            FROM withdraw_order
+            WHERE state = 'SENT'
+        "#
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(records.into_iter().map(|r| r.tx_hash).collect())
+}
+
+/// Register the transaction the orders were sent in
+pub async fn set_sent(
+    conn: &mut PgConnection,
+    invoice_ids: &[[u8; 32]],
+    tx_hash: &str,
+) -> Result<(), sqlx::Error> {
+    let invoice_ids: Vec<_> = invoice_ids.iter().map(|id| id.to_vec()).collect();
+
+    sqlx::query!(
+        r#"
+            UPDATE withdraw_order
+            SET state = 'SENT', tx_hash = $2, updated_at = NOW()
+            WHERE invoice_id = ANY($1)
+        "#,
+        &invoice_ids,
+        tx_hash
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Set the state of the orders sent in this transaction
+///
+/// Setting it back to [`WithdrawOrderState::Pending`] will have them sent again.
+pub async fn set_state_by_tx_hash(
+    conn: &mut PgConnection,
+    tx_hash: &str,
+    state: WithdrawOrderState,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+            UPDATE withdraw_order
+            SET state = $2, updated_at = NOW()
+            WHERE tx_hash = $1 AND state = 'SENT'
+        "#,
+        tx_hash,
+        state as WithdrawOrderState
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Register a failed attempt to pay the orders not sent yet, and schedule them to be sent again
+///
+/// The retry delay starts at `base_delay` and doubles with each attempt, up to `max_delay`.
+pub async fn retry_later(
+    conn: &mut PgConnection,
+    invoice_ids: &[[u8; 32]],
+    base_delay: Duration,
+    max_delay: Duration,
+) -> Result<(), sqlx::Error> {
+    let invoice_ids: Vec<_> = invoice_ids.iter().map(|id| id.to_vec()).collect();
+
+    sqlx::query!(
+        r#"
+            UPDATE withdraw_order
+            SET attempts = attempts + 1,
+                next_attempt_at = NOW() + make_interval(secs => LEAST($2 * POWER(2, attempts), $3)),
+                updated_at = NOW()
+            WHERE invoice_id = ANY($1) AND state = 'PENDING'
+        "#,
+        &invoice_ids,
+        base_delay.as_secs_f64(),
+        max_delay.as_secs_f64()
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Register a failed attempt to pay the orders sent in this transaction,
+/// and schedule them to be sent again
+///
+/// See [`retry_later`] for the retry delay.
+pub async fn retry_later_by_tx_hash(
+    conn: &mut PgConnection,
+    tx_hash: &str,
+    base_delay: Duration,
+    max_delay: Duration,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+            UPDATE withdraw_order
+            SET state = 'PENDING',
+                attempts = attempts + 1,
+                next_attempt_at = NOW() + make_interval(secs => LEAST($2 * POWER(2, attempts), $3)),
+                updated_at = NOW()
+            WHERE tx_hash = $1 AND state = 'SENT'
+        "#,
+        tx_hash,
+        base_delay.as_secs_f64(),
+        max_delay.as_secs_f64()
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark as failed the orders not sent yet that reached `max_attempts` failed attempts
+///
+/// Returns the ids of their quotes.
+pub async fn fail_exhausted(
+    conn: &mut PgConnection,
+    max_attempts: u32,
+) -> Result<Vec<Uuid>, Error> {
+    let max_attempts = i32::try_from(max_attempts).map_err(|_| Error::RuntimeToDbConversion)?;
+
+    let records = sqlx::query!(
+        r#"
+            UPDATE withdraw_order
+            SET state = 'FAILED', updated_at = NOW()
+            WHERE state = 'PENDING' AND attempts >= $1
+            RETURNING quote_id
+        "#,
+        max_attempts
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(records.into_iter().map(|r| r.quote_id).collect())
+}
@@ -2,6 +2,8 @@ mod deposit;
 use std::fmt::{LowerHex, UpperHex};
 
 pub use deposit::DepositInterface;
+mod reserves;
+pub use reserves::{AssetReserves, ReservesInterface, attestation_hash};
 mod withdraw;
 use nuts::traits::Unit;
 use uuid::Uuid;
@@ -12,8 +14,10 @@ pub trait LiquiditySource {
     type Unit: Unit;
     type Depositer: DepositInterface<InvoiceId = Self::InvoiceId>;
     type Withdrawer: WithdrawInterface<InvoiceId = Self::InvoiceId, Unit = Self::Unit>;
+    type Reserves: ReservesInterface<Unit = Self::Unit>;
 
     fn depositer(&self) -> Self::Depositer;
     fn withdrawer(&self) -> Self::Withdrawer;
+    fn reserves(&self) -> Self::Reserves;
     fn compute_invoice_id(&self, quote_id: Uuid, expiry: u64) -> Self::InvoiceId;
 }
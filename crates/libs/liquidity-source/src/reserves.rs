@@ -0,0 +1,54 @@
+use bitcoin_hashes::{HashEngine, Sha256};
+use nuts::traits::Unit;
+use primitive_types::U256;
+
+#[async_trait::async_trait]
+pub trait ReservesInterface: Send + Sync {
+    type Error: std::error::Error + Send + Sync + 'static;
+    type Unit: Unit;
+
+    /// Returns the address of the on-chain account holding the deposited assets
+    fn reserves_address(&self) -> String;
+
+    /// Returns the balance of the reserves account, in the asset blockchain-native representation
+    async fn get_balance(&self, asset: <Self::Unit as Unit>::Asset) -> Result<U256, Self::Error>;
+
+    /// Signs an [`attestation_hash`] with the key controlling the reserves account
+    fn sign_attestation(&self, hash: [u8; 32]) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// The reserves of one asset and the liabilities they back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetReserves {
+    pub asset: String,
+    /// Ecash in circulation, in the asset blockchain-native representation
+    pub liabilities: U256,
+    /// Balance of the reserves account, in the asset blockchain-native representation
+    pub reserves: U256,
+}
+
+/// The hash signed by the node to attest its reserves at `created_at`
+///
+/// Its 6 most significant bits are cleared, so that it fits in the 251 bits field
+/// some chains sign over, as Starknet does.
+pub fn attestation_hash(
+    created_at: u64,
+    reserves_address: &str,
+    assets: &[AssetReserves],
+) -> [u8; 32] {
+    let mut engine = Sha256::engine();
+    engine.input(&created_at.to_be_bytes());
+    engine.input(&(reserves_address.len() as u64).to_be_bytes());
+    engine.input(reserves_address.as_bytes());
+    for asset in assets {
+        engine.input(&(asset.asset.len() as u64).to_be_bytes());
+        engine.input(asset.asset.as_bytes());
+        engine.input(&asset.liabilities.to_big_endian());
+        engine.input(&asset.reserves.to_big_endian());
+    }
+
+    let mut hash = Sha256::from_engine(engine).to_byte_array();
+    hash[0] &= 0b0000_0011;
+
+    hash
+}
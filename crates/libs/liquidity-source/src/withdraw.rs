@@ -1,4 +1,5 @@
 use nuts::{Amount, nut05::MeltQuoteState, traits::Unit};
+use sqlx::PgConnection;
 use uuid::Uuid;
 
 #[async_trait::async_trait]
@@ -8,11 +9,20 @@ pub trait WithdrawInterface: Send {
     type Unit: Unit;
     type InvoiceId: Into<[u8; 32]> + Send + Sync + 'static;
 
-    fn compute_total_amount_expected(
+    /// Returns the amount of unit the node has to receive to pay this request, fees excluded
+    fn compute_amount_expected(
         &self,
-        request: Self::Request,
+        request: &Self::Request,
+        unit: Self::Unit,
+    ) -> Result<Amount, Self::Error>;
+
+    /// Returns an estimation, in unit, of the on-chain fee the node will pay to process this request
+    async fn estimate_fee(
+        &self,
+        quote_id: Uuid,
+        request: &Self::Request,
+        expiry: u64,
         unit: Self::Unit,
-        fee: Amount,
     ) -> Result<Amount, Self::Error>;
 
     fn deserialize_payment_request(
@@ -20,8 +30,13 @@ pub trait WithdrawInterface: Send {
         raw_json_string: &str,
     ) -> Result<Self::Request, Self::Error>;
 
+    /// Register the payment of this request, within the transaction that spends the melt inputs
+    ///
+    /// Nothing may be sent before `conn` commits, so that a payment is never lost,
+    /// nor made for inputs that were not spent.
     async fn proceed_to_payment(
         &mut self,
+        conn: &mut PgConnection,
         quote_id: Uuid,
         request: Self::Request,
         expiry: u64,
@@ -1,5 +1,5 @@
 use nuts::{nut04, nut05};
-pub use proto::bdhke::{BlindSignature, BlindedMessage, Proof};
+pub use proto::bdhke::{BlindSignature, BlindSignatureDleq, BlindedMessage, Proof};
 #[cfg(feature = "keyset-rotation")]
 pub use proto::keyset_rotation::keyset_rotation_service_client::KeysetRotationServiceClient;
 #[cfg(feature = "keyset-rotation")]
@@ -7,7 +7,17 @@ pub use proto::keyset_rotation::*;
 pub use proto::node::node_client::NodeClient;
 pub use proto::node::*;
 
+#[cfg(feature = "admin")]
+pub mod admin {
+    pub use super::proto::admin::admin_client::AdminClient;
+    pub use super::proto::admin::*;
+}
+
 mod proto {
+    #[cfg(feature = "admin")]
+    pub mod admin {
+        tonic::include_proto!("admin");
+    }
     pub mod bdhke {
         tonic::include_proto!("bdhke");
     }
@@ -33,6 +43,7 @@ impl TryFrom<MeltQuoteState> for nut05::MeltQuoteState {
             MeltQuoteState::MlqsUnpaid => Ok(nut05::MeltQuoteState::Unpaid),
             MeltQuoteState::MlqsPending => Ok(nut05::MeltQuoteState::Pending),
             MeltQuoteState::MlqsPaid => Ok(nut05::MeltQuoteState::Paid),
+            MeltQuoteState::MlqsFailed => Ok(nut05::MeltQuoteState::Failed),
         }
     }
 }
@@ -43,6 +54,7 @@ impl From<nut05::MeltQuoteState> for MeltQuoteState {
             nut05::MeltQuoteState::Unpaid => MeltQuoteState::MlqsUnpaid,
             nut05::MeltQuoteState::Pending => MeltQuoteState::MlqsPending,
             nut05::MeltQuoteState::Paid => MeltQuoteState::MlqsPaid,
+            nut05::MeltQuoteState::Failed => MeltQuoteState::MlqsFailed,
         }
     }
 }
@@ -15,6 +15,7 @@ use crate::nut00::{BlindSignature, Proof, Proofs};
 use crate::nut01::PublicKey;
 use crate::nut01::SecretKey;
 use crate::nut01::SetPubKeys;
+use crate::nut12::ProofDleq;
 
 const DOMAIN_SEPARATOR: &[u8; 28] = b"Secp256k1_HashToCurve_Cashu_";
 
@@ -143,6 +144,10 @@ pub fn construct_proofs(
             keyset_id: blind_signature.keyset_id,
             secret,
             c: unblind_signature,
+            witness: None,
+            dleq: blind_signature
+                .dleq
+                .map(|dleq| ProofDleq::new(dleq.e, dleq.s, r)),
         };
 
         proofs.push(proof);
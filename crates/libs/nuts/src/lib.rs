@@ -15,8 +15,16 @@ pub mod nut04;
 pub mod nut05;
 pub mod nut06;
 pub mod nut07;
+#[cfg(feature = "nut9")]
+pub mod nut09;
+pub mod nut10;
+pub mod nut11;
+pub mod nut12;
 #[cfg(feature = "nut13")]
 pub mod nut13;
+pub mod nut14;
+#[cfg(feature = "nut17")]
+pub mod nut17;
 #[cfg(feature = "nut19")]
 pub mod nut19;
 
@@ -208,3 +216,11 @@ pub static SECP256K1: Lazy<Secp256k1<All>> = Lazy::new(|| {
     ctx.randomize(&mut rng);
     ctx
 });
+
+/// Seconds since unix epoch
+pub fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
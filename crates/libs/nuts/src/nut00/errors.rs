@@ -36,12 +36,18 @@ pub enum Error {
     /// DHKE error
     #[error(transparent)]
     Dhke(#[from] crate::dhke::Error),
-    // /// NUT10 error
-    // #[error(transparent)]
-    // NUT10(#[from] crate::nuts::nut10::Error),
-    // /// NUT11 error
-    // #[error(transparent)]
-    // NUT11(#[from] crate::nuts::nut11::Error),
+    /// NUT10 error
+    #[error(transparent)]
+    NUT10(#[from] crate::nut10::Error),
+    /// NUT11 error
+    #[error(transparent)]
+    NUT11(#[from] crate::nut11::Error),
+    /// NUT12 error
+    #[error(transparent)]
+    NUT12(#[from] crate::nut12::Error),
+    /// NUT14 error
+    #[error(transparent)]
+    NUT14(#[from] crate::nut14::Error),
     /// Overflow
     #[error("Overflow")]
     Overflow,
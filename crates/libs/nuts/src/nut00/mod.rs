@@ -4,18 +4,47 @@ pub use errors::Error;
 use num_traits::CheckedAdd;
 use secret::Secret;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
-use crate::{Amount, dhke::hash_to_curve, nut01::PublicKey, nut02::KeysetId};
+use crate::{
+    Amount,
+    dhke::hash_to_curve,
+    nut01::PublicKey,
+    nut02::KeysetId,
+    nut11::P2PKWitness,
+    nut12::{BlindSignatureDleq, ProofDleq},
+    nut14::HTLCWitness,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CashuError {
     code: u16,
     detail: String,
+    /// Not part of NUT-00, lets wallets tell which proofs or outputs were rejected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fields: Vec<InvalidField>,
+}
+
+/// A part of the request the node rejected, such as `proofs[2]` or `outputs[0].amount`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidField {
+    pub field: String,
+    pub description: String,
 }
 
 impl CashuError {
     pub fn new(code: u16, detail: String) -> Self {
-        Self { code, detail }
+        Self {
+            code,
+            detail,
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn with_fields(mut self, fields: Vec<InvalidField>) -> Self {
+        self.fields = fields;
+        self
     }
 
     pub fn code(&self) -> u16 {
@@ -24,6 +53,29 @@ impl CashuError {
     pub fn detail(&self) -> &String {
         &self.detail
     }
+    pub fn fields(&self) -> &[InvalidField] {
+        &self.fields
+    }
+}
+
+/// Error codes defined by the NUTs, carried by [`CashuError`]
+pub mod error_code {
+    /// Any error without a code of its own, the detail says what went wrong
+    pub const UNKNOWN: u16 = 0;
+    /// Some outputs were already signed
+    pub const OUTPUT_ALREADY_SIGNED: u16 = 10002;
+    /// Some proofs failed cryptographic verification
+    pub const PROOF_VERIFICATION_FAILED: u16 = 10003;
+    /// Some proofs were already spent
+    pub const PROOF_ALREADY_SPENT: u16 = 11001;
+    /// The amount of an output is outside of the keyset range
+    pub const AMOUNT_OUTSIDE_LIMIT: u16 = 11006;
+    /// The same output appears several times
+    pub const DUPLICATE_OUTPUTS: u16 = 11008;
+    /// The keyset of an output is not known by the node
+    pub const KEYSET_UNKNOWN: u16 = 12001;
+    /// The keyset is inactive and cannot sign new outputs
+    pub const KEYSET_INACTIVE: u16 = 12002;
 }
 
 /// List of [Proof]
@@ -65,6 +117,12 @@ pub struct Proof {
     /// Unblind signature
     #[serde(rename = "C")]
     pub c: PublicKey,
+    /// Witness unlocking the spending conditions of the secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<Witness>,
+    /// DLEQ Proof
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dleq: Option<ProofDleq>,
 }
 
 impl Proof {
@@ -76,6 +134,89 @@ impl Proof {
     }
 }
 
+/// Witness
+///
+/// Serialized as a JSON string, as specified by NUT-10
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Witness {
+    /// P2PK Witness
+    P2PKWitness(P2PKWitness),
+    /// HTLC Witness
+    HTLCWitness(HTLCWitness),
+}
+
+impl Witness {
+    /// Add signatures to [`Witness`]
+    pub fn add_signatures(&mut self, signatures: Vec<String>) {
+        match self {
+            Self::P2PKWitness(p2pk_witness) => p2pk_witness.signatures.extend(signatures),
+            Self::HTLCWitness(htlc_witness) => match &mut htlc_witness.signatures {
+                Some(sigs) => sigs.extend(signatures),
+                None => htlc_witness.signatures = Some(signatures),
+            },
+        }
+    }
+
+    /// Get signatures on [`Witness`]
+    pub fn signatures(&self) -> Option<Vec<String>> {
+        match self {
+            Self::P2PKWitness(witness) => Some(witness.signatures.clone()),
+            Self::HTLCWitness(witness) => witness.signatures.clone(),
+        }
+    }
+
+    /// Get preimage from [`Witness`]
+    pub fn preimage(&self) -> Option<String> {
+        match self {
+            Self::P2PKWitness(_witness) => None,
+            Self::HTLCWitness(witness) => Some(witness.preimage.clone()),
+        }
+    }
+}
+
+impl FromStr for Witness {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // HTLC witnesses are the only ones carrying a preimage, so they must be tried first
+        match serde_json::from_str::<HTLCWitness>(s) {
+            Ok(htlc_witness) => Ok(Self::HTLCWitness(htlc_witness)),
+            Err(_) => Ok(Self::P2PKWitness(serde_json::from_str(s)?)),
+        }
+    }
+}
+
+impl fmt::Display for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = match self {
+            Self::P2PKWitness(witness) => serde_json::to_string(witness),
+            Self::HTLCWitness(witness) => serde_json::to_string(witness),
+        }
+        .map_err(|_| fmt::Error)?;
+
+        write!(f, "{}", json)
+    }
+}
+
+impl Serialize for Witness {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Witness {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Witness::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
 /// Blind Signature (also called `promise`)
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BlindSignature {
@@ -93,6 +234,11 @@ pub struct BlindSignature {
     /// The blind signature on the secret message `B_` of [BlindMessage].
     #[serde(rename = "C_")]
     pub c: PublicKey,
+    /// DLEQ Proof
+    ///
+    /// Allows the wallet to check that `c` was produced with the key of this keyset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dleq: Option<BlindSignatureDleq>,
 }
 
 /// Blind Message (also called `output`)
@@ -45,8 +45,8 @@ impl Default for Secret {
 impl Secret {
     /// Create new [`Secret`]
     ///
-    /// The secret must be a valid 64-character hex string representing
-    /// a 32-byte value
+    /// The secret must either be a valid 64-character hex string representing
+    /// a 32-byte value, or a NUT-10 well-known secret
     #[inline]
     pub fn new<S>(secret: S) -> Result<Self, Error>
     where
@@ -62,7 +62,7 @@ impl Secret {
     /// # Safety
     ///
     /// This function should only be used in contexts where the input
-    /// is guaranteed to be a valid 64-character hex string.
+    /// is guaranteed to be a valid 64-character hex string or well-known secret.
     #[inline]
     pub(crate) fn new_unchecked<S>(secret: S) -> Self
     where
@@ -73,6 +73,12 @@ impl Secret {
 
     /// Validate that a string is a proper Secret
     fn validate(s: &str) -> Result<(), Error> {
+        // NUT-10 well-known secrets are serialized as a JSON array
+        if s.starts_with('[') {
+            serde_json::from_str::<crate::nut10::Secret>(s)?;
+            return Ok(());
+        }
+
         // Check the length
         if s.len() != 64 {
             return Err(Error::InvalidLength(s.len() as u64));
@@ -206,5 +212,14 @@ mod tests {
         let invalid_chars = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdeg";
         assert!(Secret::new(invalid_chars).is_err());
         assert!(Secret::from_str(invalid_chars).is_err());
+
+        // Well-known secret
+        let well_known = r#"["P2PK",{"nonce":"5d11913ee0f92fefdc82a6764fd2457a","data":"026562efcfadc8e86d44da6a8adf80633d974302e62c850774db1fb36ff4cc7198"}]"#;
+        assert!(Secret::new(well_known).is_ok());
+        assert!(Secret::from_str(well_known).is_ok());
+
+        // Malformed well-known secret
+        let malformed = r#"["P2PK",{"nonce":"5d11913ee0f92fefdc82a6764fd2457a"}]"#;
+        assert!(Secret::new(malformed).is_err());
     }
 }
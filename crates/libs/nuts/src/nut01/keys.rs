@@ -53,6 +53,16 @@ impl SetPubKeys {
     }
 }
 
+impl FromIterator<(Amount, PublicKey)> for SetPubKeys {
+    fn from_iter<T: IntoIterator<Item = (Amount, PublicKey)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(amount, public_key)| (AmountStr::from(amount), public_key))
+                .collect(),
+        )
+    }
+}
+
 impl From<&SetPubKeys> for KeysetId {
     fn from(keys: &SetPubKeys) -> Self {
         let iter = keys.iter().map(|(_, pubkey)| *pubkey);
@@ -11,6 +11,11 @@ use bitcoin::secp256k1;
 use bitcoin::secp256k1::rand::rngs::OsRng;
 use bitcoin::secp256k1::schnorr::Signature;
 use bitcoin::secp256k1::{Keypair, Message, Scalar};
+#[cfg(feature = "rusqlite")]
+use rusqlite::{
+    Result,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+};
 use serde::{Deserialize, Deserializer, Serialize};
 
 /// SecretKey
@@ -131,6 +136,22 @@ impl<'de> Deserialize<'de> for SecretKey {
     }
 }
 
+#[cfg(feature = "rusqlite")]
+impl ToSql for SecretKey {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
+        Ok(ToSqlOutput::from(self.to_secret_bytes().to_vec()))
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl FromSql for SecretKey {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value
+            .as_blob()
+            .and_then(|b| Self::from_slice(b).map_err(|e| FromSqlError::Other(Box::new(e))))
+    }
+}
+
 impl Drop for SecretKey {
     fn drop(&mut self) {
         self.inner.non_secure_erase();
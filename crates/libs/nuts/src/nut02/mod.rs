@@ -81,12 +81,22 @@ pub struct KeySetInfo<U: Unit> {
     /// Input Fee PPK
     #[serde(default = "default_input_fee_ppk")]
     pub input_fee_ppk: u64,
+    /// Unix timestamp past which the mint stops accepting the proofs of this keyset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_expiry: Option<u64>,
 }
 
 fn default_input_fee_ppk() -> u64 {
     0
 }
 
+/// Fee owed for spending a set of inputs
+///
+/// Takes the sum of the `input_fee_ppk` of each input's keyset and rounds it up to the next unit
+pub fn calculate_input_fee(sum_fee_ppk: u64) -> Amount {
+    Amount::from(sum_fee_ppk.div_ceil(1000))
+}
+
 /// MintKeyset
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MintKeySet<U: Unit> {
@@ -187,11 +197,12 @@ mod test {
     use rand::RngCore;
 
     use crate::{
+        Amount,
         nut02::{KeysResponse, KeysetId},
         traits::test_types::TestUnit,
     };
 
-    use super::{KeySetInfo, KeysetResponse, SetPubKeys};
+    use super::{KeySetInfo, KeysetResponse, SetPubKeys, calculate_input_fee};
 
     const SHORT_KEYSET_ID: &str = "00456a94ab4e1c46";
     const SHORT_KEYSET: &str = r#"
@@ -290,6 +301,15 @@ mod test {
         assert_eq!(id, KeysetId::from_str(KEYSET_ID).unwrap());
     }
 
+    #[test]
+    fn test_calculate_input_fee() {
+        assert_eq!(calculate_input_fee(0), Amount::ZERO);
+        assert_eq!(calculate_input_fee(1), Amount::ONE);
+        assert_eq!(calculate_input_fee(1000), Amount::ONE);
+        assert_eq!(calculate_input_fee(1001), Amount::from(2u64));
+        assert_eq!(calculate_input_fee(3 * 100), Amount::ONE);
+    }
+
     #[test]
     fn test_deserialization_keyset_info() {
         let h = r#"{"id":"009a1f293253e41e","unit":"sat","active":true}"#;
@@ -297,6 +317,19 @@ mod test {
         let _keyset_response: KeySetInfo<TestUnit> = serde_json::from_str(h).unwrap();
     }
 
+    #[test]
+    fn test_keyset_info_final_expiry() {
+        let h = r#"{"id":"009a1f293253e41e","unit":"sat","active":false,"input_fee_ppk":0,"final_expiry":1754296607}"#;
+        let keyset_info: KeySetInfo<TestUnit> = serde_json::from_str(h).unwrap();
+        assert_eq!(keyset_info.final_expiry, Some(1754296607));
+        assert_eq!(serde_json::to_string(&keyset_info).unwrap(), h);
+
+        let h = r#"{"id":"009a1f293253e41e","unit":"sat","active":true,"input_fee_ppk":0}"#;
+        let keyset_info: KeySetInfo<TestUnit> = serde_json::from_str(h).unwrap();
+        assert_eq!(keyset_info.final_expiry, None);
+        assert_eq!(serde_json::to_string(&keyset_info).unwrap(), h);
+    }
+
     #[test]
     fn test_deserialization_of_keyset_response() {
         let h = r#"{"keysets":[{"id":"009a1f293253e41e","unit":"sat","active":true, "input_fee_ppk": 100},{"id":"003dfdf4e5e35487","unit":"sat","active":true},{"id":"0066ad1a4b6fc57c","unit":"sat","active":true},{"id":"00f7ca24d44c3e5e","unit":"sat","active":true},{"id":"001fcea2931f2d85","unit":"sat","active":true},{"id":"00d095959d940edb","unit":"sat","active":true},{"id":"000d7f730d657125","unit":"sat","active":true},{"id":"0007208d861d7295","unit":"sat","active":true},{"id":"00bfdf8889b719dd","unit":"sat","active":true},{"id":"00ca9b17da045f21","unit":"sat","active":true}]}"#;
@@ -51,6 +51,19 @@ impl core::fmt::Display for MintQuoteState {
     }
 }
 
+impl core::str::FromStr for MintQuoteState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "UNPAID" => Ok(MintQuoteState::Unpaid),
+            "PAID" => Ok(MintQuoteState::Paid),
+            "ISSUED" => Ok(MintQuoteState::Issued),
+            _ => Err(Error::UnknownState),
+        }
+    }
+}
+
 #[cfg(feature = "rusqlite")]
 impl ToSql for MintQuoteState {
     fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
@@ -81,12 +94,16 @@ pub struct MintQuoteRequest<U: Unit> {
     pub description: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct MintQuoteResponse<Q> {
     pub quote: Q,
     pub request: String,
     pub state: MintQuoteState,
     pub expiry: u64,
+    /// Sum of the payments received so far, it may be lower than the quoted amount while the
+    /// quote is unpaid, or higher if it was overpaid
+    #[serde(default)]
+    pub amount_paid: Amount,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
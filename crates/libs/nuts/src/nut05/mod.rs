@@ -37,6 +37,8 @@ pub enum MeltQuoteState {
     Pending,
     /// Payment has been done on chain
     Paid,
+    /// On-chain payment failed for good, the inputs were returned
+    Failed,
 }
 
 impl From<MeltQuoteState> for i32 {
@@ -45,6 +47,7 @@ impl From<MeltQuoteState> for i32 {
             MeltQuoteState::Unpaid => 1,
             MeltQuoteState::Pending => 2,
             MeltQuoteState::Paid => 3,
+            MeltQuoteState::Failed => 4,
         }
     }
 }
@@ -58,11 +61,26 @@ impl core::fmt::Display for MeltQuoteState {
                 MeltQuoteState::Unpaid => "UNPAID",
                 MeltQuoteState::Pending => "PENDING",
                 MeltQuoteState::Paid => "PAID",
+                MeltQuoteState::Failed => "FAILED",
             }
         )
     }
 }
 
+impl core::str::FromStr for MeltQuoteState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "UNPAID" => Ok(MeltQuoteState::Unpaid),
+            "PENDING" => Ok(MeltQuoteState::Pending),
+            "PAID" => Ok(MeltQuoteState::Paid),
+            "FAILED" => Ok(MeltQuoteState::Failed),
+            _ => Err(Error::UnknownState),
+        }
+    }
+}
+
 #[cfg(feature = "rusqlite")]
 impl ToSql for MeltQuoteState {
     fn to_sql(&self) -> Result<ToSqlOutput<'_>, rusqlite::Error> {
@@ -70,6 +88,7 @@ impl ToSql for MeltQuoteState {
             MeltQuoteState::Unpaid => 1,
             MeltQuoteState::Pending => 2,
             MeltQuoteState::Paid => 3,
+            MeltQuoteState::Failed => 4,
         }))
     }
 }
@@ -81,6 +100,7 @@ impl FromSql for MeltQuoteState {
             1 => Ok(MeltQuoteState::Unpaid),
             2 => Ok(MeltQuoteState::Pending),
             3 => Ok(MeltQuoteState::Paid),
+            4 => Ok(MeltQuoteState::Failed),
             _ => Err(FromSqlError::Other(Box::new(Error::UnknownState))),
         })
     }
@@ -120,7 +140,7 @@ pub struct MeltRequest<Q> {
     pub inputs: Proofs,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MeltResponse {
     pub state: MeltQuoteState,
     pub transfer_ids: Option<Vec<String>>,
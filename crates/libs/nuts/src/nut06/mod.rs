@@ -5,6 +5,8 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
+#[cfg(feature = "nut17")]
+use crate::nut17;
 #[cfg(feature = "nut19")]
 use crate::nut19;
 use crate::traits::Method;
@@ -202,6 +204,22 @@ pub struct NutsSettings<M: Method, U, O> {
     #[cfg(feature = "nut9")]
     #[serde(rename = "9")]
     pub nut09: SupportedSettings,
+    /// NUT10 Settings
+    #[serde(rename = "10", default)]
+    pub nut10: SupportedSettings,
+    /// NUT11 Settings
+    #[serde(rename = "11", default)]
+    pub nut11: SupportedSettings,
+    /// NUT12 Settings
+    #[serde(rename = "12", default)]
+    pub nut12: SupportedSettings,
+    /// NUT14 Settings
+    #[serde(rename = "14", default)]
+    pub nut14: SupportedSettings,
+    /// NUT17 Settings
+    #[cfg(feature = "nut17")]
+    #[serde(rename = "17", default = "nut17::Settings::default")]
+    pub nut17: nut17::Settings<M, U>,
     #[cfg(feature = "nut19")]
     #[serde(rename = "19")]
     pub nut19: nut19::Settings,
@@ -213,6 +231,12 @@ pub struct NutsSettingsBuilder<M: Method, U, O> {
     nut05: Option<nut05::Settings<M, U>>,
     #[cfg(feature = "nut9")]
     nut09: Option<SupportedSettings>,
+    nut10: SupportedSettings,
+    nut11: SupportedSettings,
+    nut12: SupportedSettings,
+    nut14: SupportedSettings,
+    #[cfg(feature = "nut17")]
+    nut17: nut17::Settings<M, U>,
     #[cfg(feature = "nut19")]
     nut19: Option<nut19::Settings>,
 }
@@ -224,6 +248,12 @@ impl<M: Method, U, O> Default for NutsSettingsBuilder<M, U, O> {
             nut05: None,
             #[cfg(feature = "nut9")]
             nut09: None,
+            nut10: SupportedSettings::default(),
+            nut11: SupportedSettings::default(),
+            nut12: SupportedSettings::default(),
+            nut14: SupportedSettings::default(),
+            #[cfg(feature = "nut17")]
+            nut17: nut17::Settings::default(),
             #[cfg(feature = "nut19")]
             nut19: None,
         }
@@ -250,6 +280,29 @@ impl<M: traits::Method, U, O> NutsSettingsBuilder<M, U, O> {
         self
     }
 
+    pub fn nut_10(mut self, nut10_settings: SupportedSettings) -> Self {
+        self.nut10 = nut10_settings;
+        self
+    }
+    pub fn nut_11(mut self, nut11_settings: SupportedSettings) -> Self {
+        self.nut11 = nut11_settings;
+        self
+    }
+    pub fn nut_12(mut self, nut12_settings: SupportedSettings) -> Self {
+        self.nut12 = nut12_settings;
+        self
+    }
+    pub fn nut_14(mut self, nut14_settings: SupportedSettings) -> Self {
+        self.nut14 = nut14_settings;
+        self
+    }
+
+    #[cfg(feature = "nut17")]
+    pub fn nut_17(mut self, nut17_settings: nut17::Settings<M, U>) -> Self {
+        self.nut17 = nut17_settings;
+        self
+    }
+
     pub fn build(self) -> Result<NutsSettings<M, U, O>, NutsBuilderError> {
         let nut04 = self.nut04.ok_or(NutsBuilderError::MissingConfig(4))?;
         let nut05 = self.nut05.ok_or(NutsBuilderError::MissingConfig(5))?;
@@ -263,6 +316,12 @@ impl<M: traits::Method, U, O> NutsSettingsBuilder<M, U, O> {
             nut05,
             #[cfg(feature = "nut9")]
             nut09,
+            nut10: self.nut10,
+            nut11: self.nut11,
+            nut12: self.nut12,
+            nut14: self.nut14,
+            #[cfg(feature = "nut17")]
+            nut17: self.nut17,
             #[cfg(feature = "nut19")]
             nut19,
         })
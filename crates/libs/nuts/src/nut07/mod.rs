@@ -1,7 +1,10 @@
 //! NUT-07: Token state check
 
+use serde::{Deserialize, Serialize};
+
 use crate::nut01::PublicKey;
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum ProofState {
     Unspecified,
     Unspent,
@@ -38,11 +41,21 @@ impl From<ProofState> for i32 {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckStateRequest {
+    #[serde(rename = "Ys")]
+    pub ys: Vec<PublicKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProofCheckState {
+    #[serde(rename = "Y")]
     pub y: PublicKey,
     pub state: ProofState,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CheckStateResponse {
+    #[serde(rename = "states")]
     pub proof_check_states: Vec<ProofCheckState>,
 }
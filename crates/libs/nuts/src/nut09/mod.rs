@@ -0,0 +1,23 @@
+//! NUT-09: Restore signatures
+//!
+//! <https://github.com/cashubtc/nuts/blob/main/09.md>
+
+use serde::{Deserialize, Serialize};
+
+use crate::nut00::{BlindSignature, BlindedMessage};
+
+/// Restore Request [NUT-09]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestoreRequest {
+    /// Outputs
+    pub outputs: Vec<BlindedMessage>,
+}
+
+/// Restore Response [NUT-09]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RestoreResponse {
+    /// Outputs that have already been signed by the node
+    pub outputs: Vec<BlindedMessage>,
+    /// Signatures, in the same order as the outputs
+    pub signatures: Vec<BlindSignature>,
+}
@@ -0,0 +1,803 @@
+//! NUT-11: Pay to Public Key (P2PK)
+//!
+//! <https://github.com/cashubtc/nuts/blob/main/11.md>
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::secp256k1::schnorr::Signature;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::nut00::Witness;
+use crate::nut00::{Proof, secret::Secret};
+use crate::nut01::{PublicKey, SecretKey};
+use crate::nut10::{self, Kind};
+use crate::unix_time;
+
+/// Nut11 Error
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Incorrect secret kind
+    #[error("Secret is not a p2pk secret")]
+    IncorrectSecretKind,
+    /// P2PK locktime has already passed
+    #[error("Locktime in past")]
+    LocktimeInPast,
+    /// Witness signature is not valid
+    #[error("Invalid signature")]
+    InvalidSignature,
+    /// Unknown tag in P2PK secret
+    #[error("Unknown tag P2PK secret")]
+    UnknownTag,
+    /// Unknown Sigflag
+    #[error("Unknown sigflag: `{0}`")]
+    UnknownSigFlag(String),
+    /// Sig all flag is not supported
+    #[error("SIG_ALL spending conditions are not supported")]
+    SigAllUnsupported,
+    /// P2PK Spend conditions not meet
+    #[error("P2PK spend conditions are not met")]
+    SpendConditionsNotMet,
+    /// Witness signatures not provided
+    #[error("Witness signatures not provided")]
+    SignaturesNotProvided,
+    /// Parse int error
+    #[error(transparent)]
+    ParseInt(#[from] std::num::ParseIntError),
+    /// From hex error
+    #[error(transparent)]
+    Secp256k1(#[from] bitcoin::secp256k1::Error),
+    /// NUT01 Error
+    #[error(transparent)]
+    NUT01(#[from] crate::nut01::Error),
+    /// NUT10 Error
+    #[error(transparent)]
+    NUT10(#[from] crate::nut10::Error),
+    /// Serde Json error
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+    /// Hex error
+    #[error(transparent)]
+    Hex(#[from] hex::FromHexError),
+    /// Hash lock parse error
+    #[error(transparent)]
+    HexToArray(#[from] bitcoin::hashes::hex::HexToArrayError),
+}
+
+/// P2PK Witness
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct P2PKWitness {
+    /// Signatures
+    pub signatures: Vec<String>,
+}
+
+impl P2PKWitness {
+    #[inline]
+    /// Check id Witness is empty
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+}
+
+impl Proof {
+    /// Sign [Proof]
+    ///
+    /// The signature is appended to the existing witness, if any
+    pub fn sign_p2pk(&mut self, secret_key: SecretKey) -> Result<(), Error> {
+        let msg: Vec<u8> = self.secret.to_bytes();
+        let signature: Signature = secret_key.sign(&msg)?;
+
+        let signatures = vec![signature.to_string()];
+
+        match self.witness.as_mut() {
+            Some(witness) => {
+                witness.add_signatures(signatures);
+            }
+            None => {
+                let mut p2pk_witness = Witness::P2PKWitness(P2PKWitness::default());
+                p2pk_witness.add_signatures(signatures);
+                self.witness = Some(p2pk_witness);
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Verify P2PK signature on [Proof]
+    ///
+    /// Only `SIG_INPUTS` is supported, our outputs don't carry any witness
+    pub fn verify_p2pk(&self) -> Result<(), Error> {
+        let secret: nut10::Secret = (&self.secret).try_into()?;
+        let spending_conditions: Conditions = secret
+            .secret_data
+            .tags
+            .clone()
+            .unwrap_or_default()
+            .try_into()?;
+
+        if secret.kind != Kind::P2PK {
+            return Err(Error::IncorrectSecretKind);
+        }
+
+        if spending_conditions.sig_flag == SigFlag::SigAll {
+            return Err(Error::SigAllUnsupported);
+        }
+
+        let msg: &[u8] = self.secret.as_bytes();
+
+        let signatures = match self.witness.as_ref().and_then(|w| w.signatures()) {
+            Some(signatures) => signatures
+                .iter()
+                .map(|s| Signature::from_str(s))
+                .collect::<Result<Vec<Signature>, _>>()?,
+            None => vec![],
+        };
+
+        let mut pubkeys = spending_conditions.pubkeys.clone().unwrap_or_default();
+        pubkeys.push(PublicKey::from_str(&secret.secret_data.data)?);
+
+        let valid_sigs = valid_signatures(msg, &pubkeys, &signatures);
+        if valid_sigs >= spending_conditions.num_sigs.unwrap_or(1) {
+            return Ok(());
+        }
+
+        if let Some(locktime) = spending_conditions.locktime {
+            // If lock time has passed check if refund witness signature is valid
+            if locktime.lt(&unix_time()) {
+                match &spending_conditions.refund_keys {
+                    Some(refund_keys) => {
+                        let valid_refund_sigs = valid_signatures(msg, refund_keys, &signatures);
+                        if valid_refund_sigs >= spending_conditions.num_sigs_refund.unwrap_or(1) {
+                            return Ok(());
+                        }
+                    }
+                    // Anyone can spend once the locktime is expired and no refund key was set
+                    None => return Ok(()),
+                }
+            }
+        }
+
+        if signatures.is_empty() {
+            return Err(Error::SignaturesNotProvided);
+        }
+
+        Err(Error::SpendConditionsNotMet)
+    }
+}
+
+/// Returns the number of distinct pubkeys that produced one of the signatures
+pub fn valid_signatures(msg: &[u8], pubkeys: &[PublicKey], signatures: &[Signature]) -> u64 {
+    let mut verified_pubkeys = HashSet::new();
+
+    for pubkey in pubkeys {
+        for signature in signatures {
+            if pubkey.verify(msg, signature).is_ok() {
+                verified_pubkeys.insert(*pubkey);
+            }
+        }
+    }
+
+    verified_pubkeys.len() as u64
+}
+
+/// Spending Conditions
+///
+/// Defined in [NUT10](https://github.com/cashubtc/nuts/blob/main/10.md)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpendingConditions {
+    /// NUT11 Spending conditions
+    ///
+    /// Defined in [NUT11](https://github.com/cashubtc/nuts/blob/main/11.md)
+    P2PKConditions {
+        /// The public key of the recipient of the locked ecash
+        data: PublicKey,
+        /// Additional Optional Spending [`Conditions`]
+        conditions: Option<Conditions>,
+    },
+    /// NUT14 Spending conditions
+    ///
+    /// Defined in [NUT14](https://github.com/cashubtc/nuts/blob/main/14.md)
+    HTLCConditions {
+        /// Hash Lock of ecash
+        data: Sha256Hash,
+        /// Additional Optional Spending [`Conditions`]
+        conditions: Option<Conditions>,
+    },
+}
+
+impl SpendingConditions {
+    /// New P2PK [SpendingConditions]
+    pub fn new_p2pk(pubkey: PublicKey, conditions: Option<Conditions>) -> Self {
+        Self::P2PKConditions {
+            data: pubkey,
+            conditions,
+        }
+    }
+
+    /// New HTLC [SpendingConditions] locked to the hash of a hex encoded `preimage`
+    pub fn new_htlc(preimage: &str, conditions: Option<Conditions>) -> Result<Self, Error> {
+        let preimage = hex::decode(preimage)?;
+        let htlc = Sha256Hash::hash(&preimage);
+
+        Ok(Self::HTLCConditions {
+            data: htlc,
+            conditions,
+        })
+    }
+
+    /// New HTLC [SpendingConditions] from a hex encoded hash lock
+    pub fn new_htlc_hash(hash: &str, conditions: Option<Conditions>) -> Result<Self, Error> {
+        let hash = Sha256Hash::from_str(hash)?;
+
+        Ok(Self::HTLCConditions {
+            data: hash,
+            conditions,
+        })
+    }
+
+    /// Kind of [SpendingConditions]
+    pub fn kind(&self) -> Kind {
+        match self {
+            Self::P2PKConditions { .. } => Kind::P2PK,
+            Self::HTLCConditions { .. } => Kind::HTLC,
+        }
+    }
+
+    /// Number if signatures required to unlock
+    pub fn num_sigs(&self) -> Option<u64> {
+        match self {
+            Self::P2PKConditions { conditions, .. } | Self::HTLCConditions { conditions, .. } => {
+                conditions.as_ref().and_then(|c| c.num_sigs)
+            }
+        }
+    }
+
+    /// Public keys of locked [`Proof`]
+    pub fn pubkeys(&self) -> Option<Vec<PublicKey>> {
+        match self {
+            Self::P2PKConditions { data, conditions } => {
+                let mut pubkeys = vec![*data];
+                if let Some(conditions) = conditions {
+                    pubkeys.extend(conditions.pubkeys.clone().unwrap_or_default());
+                }
+
+                Some(pubkeys)
+            }
+            Self::HTLCConditions { conditions, .. } => {
+                conditions.as_ref().and_then(|c| c.pubkeys.clone())
+            }
+        }
+    }
+
+    /// Locktime of Spending Conditions
+    pub fn locktime(&self) -> Option<u64> {
+        match self {
+            Self::P2PKConditions { conditions, .. } | Self::HTLCConditions { conditions, .. } => {
+                conditions.as_ref().and_then(|c| c.locktime)
+            }
+        }
+    }
+
+    /// Refund keys
+    pub fn refund_keys(&self) -> Option<Vec<PublicKey>> {
+        match self {
+            Self::P2PKConditions { conditions, .. } | Self::HTLCConditions { conditions, .. } => {
+                conditions.as_ref().and_then(|c| c.refund_keys.clone())
+            }
+        }
+    }
+}
+
+impl TryFrom<&Secret> for SpendingConditions {
+    type Error = Error;
+    fn try_from(secret: &Secret) -> Result<SpendingConditions, Error> {
+        let nut10_secret: nut10::Secret = secret.try_into()?;
+
+        nut10_secret.try_into()
+    }
+}
+
+impl TryFrom<nut10::Secret> for SpendingConditions {
+    type Error = Error;
+    fn try_from(secret: nut10::Secret) -> Result<SpendingConditions, Error> {
+        match secret.kind {
+            Kind::P2PK => Ok(SpendingConditions::P2PKConditions {
+                data: PublicKey::from_str(&secret.secret_data.data)?,
+                conditions: secret.secret_data.tags.map(|t| t.try_into()).transpose()?,
+            }),
+            Kind::HTLC => Ok(Self::HTLCConditions {
+                data: Sha256Hash::from_str(&secret.secret_data.data)?,
+                conditions: secret.secret_data.tags.map(|t| t.try_into()).transpose()?,
+            }),
+        }
+    }
+}
+
+impl From<SpendingConditions> for nut10::Secret {
+    fn from(conditions: SpendingConditions) -> nut10::Secret {
+        match conditions {
+            SpendingConditions::P2PKConditions { data, conditions } => nut10::Secret::new(
+                Kind::P2PK,
+                data.to_hex(),
+                conditions.map(Vec::<Vec<String>>::from),
+            ),
+            SpendingConditions::HTLCConditions { data, conditions } => nut10::Secret::new(
+                Kind::HTLC,
+                data.to_string(),
+                conditions.map(Vec::<Vec<String>>::from),
+            ),
+        }
+    }
+}
+
+impl TryFrom<SpendingConditions> for Secret {
+    type Error = Error;
+
+    fn try_from(conditions: SpendingConditions) -> Result<Secret, Error> {
+        Ok(nut10::Secret::from(conditions).try_into()?)
+    }
+}
+
+/// P2PK and HTLC spending conditions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Conditions {
+    /// Unix locktime after which refund keys can be used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locktime: Option<u64>,
+    /// Additional Public keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pubkeys: Option<Vec<PublicKey>>,
+    /// Refund keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_keys: Option<Vec<PublicKey>>,
+    /// Number of signatures required
+    ///
+    /// Default is 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_sigs: Option<u64>,
+    /// Signature flag
+    ///
+    /// Default [`SigFlag::SigInputs`]
+    pub sig_flag: SigFlag,
+    /// Number of refund signatures required
+    ///
+    /// Default is 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_sigs_refund: Option<u64>,
+}
+
+impl Conditions {
+    /// Create new Spending [`Conditions`]
+    ///
+    /// Fails if the locktime is already in the past
+    pub fn new(
+        locktime: Option<u64>,
+        pubkeys: Option<Vec<PublicKey>>,
+        refund_keys: Option<Vec<PublicKey>>,
+        num_sigs: Option<u64>,
+        sig_flag: Option<SigFlag>,
+        num_sigs_refund: Option<u64>,
+    ) -> Result<Self, Error> {
+        if let Some(locktime) = locktime {
+            if locktime.lt(&unix_time()) {
+                return Err(Error::LocktimeInPast);
+            }
+        }
+
+        Ok(Self {
+            locktime,
+            pubkeys,
+            refund_keys,
+            num_sigs,
+            sig_flag: sig_flag.unwrap_or_default(),
+            num_sigs_refund,
+        })
+    }
+}
+
+impl From<Conditions> for Vec<Vec<String>> {
+    fn from(conditions: Conditions) -> Vec<Vec<String>> {
+        let Conditions {
+            locktime,
+            pubkeys,
+            refund_keys,
+            num_sigs,
+            sig_flag,
+            num_sigs_refund,
+        } = conditions;
+
+        let mut tags = Vec::new();
+
+        if let Some(pubkeys) = pubkeys {
+            tags.push(Tag::PubKeys(pubkeys.into_iter().collect()).as_vec());
+        }
+
+        if let Some(locktime) = locktime {
+            tags.push(Tag::LockTime(locktime).as_vec());
+        }
+
+        if let Some(num_sigs) = num_sigs {
+            tags.push(Tag::NSigs(num_sigs).as_vec());
+        }
+
+        if let Some(refund_keys) = refund_keys {
+            tags.push(Tag::Refund(refund_keys).as_vec())
+        }
+
+        if let Some(num_sigs_refund) = num_sigs_refund {
+            tags.push(Tag::NSigsRefund(num_sigs_refund).as_vec())
+        }
+
+        tags.push(Tag::SigFlag(sig_flag).as_vec());
+        tags
+    }
+}
+
+impl TryFrom<Vec<Vec<String>>> for Conditions {
+    type Error = Error;
+    fn try_from(tags: Vec<Vec<String>>) -> Result<Conditions, Self::Error> {
+        let tags = tags
+            .into_iter()
+            .map(Tag::try_from)
+            .collect::<Result<Vec<Tag>, _>>()?;
+
+        let mut conditions = Conditions::default();
+
+        for tag in tags {
+            match tag {
+                Tag::SigFlag(sig_flag) => conditions.sig_flag = sig_flag,
+                Tag::NSigs(num_sigs) => conditions.num_sigs = Some(num_sigs),
+                Tag::LockTime(locktime) => conditions.locktime = Some(locktime),
+                Tag::Refund(refund_keys) => conditions.refund_keys = Some(refund_keys),
+                Tag::PubKeys(pubkeys) => conditions.pubkeys = Some(pubkeys),
+                Tag::NSigsRefund(num_sigs_refund) => {
+                    conditions.num_sigs_refund = Some(num_sigs_refund)
+                }
+            }
+        }
+
+        Ok(conditions)
+    }
+}
+
+/// P2PK and HTLC Spending condition tags
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagKind {
+    /// Signature flag
+    SigFlag,
+    /// Number signatures required
+    #[serde(rename = "n_sigs")]
+    NSigs,
+    /// Locktime
+    Locktime,
+    /// Refund
+    Refund,
+    /// Pubkey
+    Pubkeys,
+    /// Number signatures required for refund
+    #[serde(rename = "n_sigs_refund")]
+    NSigsRefund,
+}
+
+impl fmt::Display for TagKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SigFlag => write!(f, "sigflag"),
+            Self::NSigs => write!(f, "n_sigs"),
+            Self::Locktime => write!(f, "locktime"),
+            Self::Refund => write!(f, "refund"),
+            Self::Pubkeys => write!(f, "pubkeys"),
+            Self::NSigsRefund => write!(f, "n_sigs_refund"),
+        }
+    }
+}
+
+impl FromStr for TagKind {
+    type Err = Error;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        match tag {
+            "sigflag" => Ok(Self::SigFlag),
+            "n_sigs" => Ok(Self::NSigs),
+            "locktime" => Ok(Self::Locktime),
+            "refund" => Ok(Self::Refund),
+            "pubkeys" => Ok(Self::Pubkeys),
+            "n_sigs_refund" => Ok(Self::NSigsRefund),
+            _ => Err(Error::UnknownTag),
+        }
+    }
+}
+
+/// Signature flag
+///
+/// Defined in [NUT11](https://github.com/cashubtc/nuts/blob/main/11.md)
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub enum SigFlag {
+    #[default]
+    /// Requires valid signatures on all inputs.
+    /// It is the default signature flag and will be applied even if the `sigflag` tag is absent.
+    SigInputs,
+    /// Requires valid signatures on all inputs and on all outputs.
+    SigAll,
+}
+
+impl fmt::Display for SigFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SigAll => write!(f, "SIG_ALL"),
+            Self::SigInputs => write!(f, "SIG_INPUTS"),
+        }
+    }
+}
+
+impl FromStr for SigFlag {
+    type Err = Error;
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        match tag {
+            "SIG_ALL" => Ok(Self::SigAll),
+            "SIG_INPUTS" => Ok(Self::SigInputs),
+            _ => Err(Error::UnknownSigFlag(tag.to_string())),
+        }
+    }
+}
+
+/// Tag
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Tag {
+    /// Sigflag [`Tag`]
+    SigFlag(SigFlag),
+    /// Number of Sigs [`Tag`]
+    NSigs(u64),
+    /// Locktime [`Tag`]
+    LockTime(u64),
+    /// Refund [`Tag`]
+    Refund(Vec<PublicKey>),
+    /// Pubkeys [`Tag`]
+    PubKeys(Vec<PublicKey>),
+    /// Number of Sigs refund [`Tag`]
+    NSigsRefund(u64),
+}
+
+impl Tag {
+    /// Get [`Tag`] Kind
+    pub fn kind(&self) -> TagKind {
+        match self {
+            Self::SigFlag(_) => TagKind::SigFlag,
+            Self::NSigs(_) => TagKind::NSigs,
+            Self::LockTime(_) => TagKind::Locktime,
+            Self::Refund(_) => TagKind::Refund,
+            Self::PubKeys(_) => TagKind::Pubkeys,
+            Self::NSigsRefund(_) => TagKind::NSigsRefund,
+        }
+    }
+
+    /// Get [`Tag`] as string vector
+    pub fn as_vec(&self) -> Vec<String> {
+        let mut tag = vec![self.kind().to_string()];
+
+        match self {
+            Self::SigFlag(sig_flag) => tag.push(sig_flag.to_string()),
+            Self::NSigs(num_sig) | Self::NSigsRefund(num_sig) => tag.push(num_sig.to_string()),
+            Self::LockTime(locktime) => tag.push(locktime.to_string()),
+            Self::PubKeys(pubkeys) | Self::Refund(pubkeys) => {
+                tag.extend(pubkeys.iter().map(|p| p.to_hex()))
+            }
+        }
+
+        tag
+    }
+}
+
+impl<S> TryFrom<Vec<S>> for Tag
+where
+    S: AsRef<str>,
+{
+    type Error = Error;
+
+    fn try_from(tag: Vec<S>) -> Result<Self, Self::Error> {
+        let tag_kind = tag
+            .first()
+            .map(|t| TagKind::from_str(t.as_ref()))
+            .ok_or(Error::UnknownTag)??;
+
+        let first_value = || tag.get(1).map(|v| v.as_ref()).ok_or(Error::UnknownTag);
+        let pubkeys = || {
+            tag.iter()
+                .skip(1)
+                .map(|p| PublicKey::from_str(p.as_ref()))
+                .collect::<Result<Vec<PublicKey>, _>>()
+        };
+
+        match tag_kind {
+            TagKind::SigFlag => Ok(Tag::SigFlag(SigFlag::from_str(first_value()?)?)),
+            TagKind::NSigs => Ok(Tag::NSigs(first_value()?.parse()?)),
+            TagKind::Locktime => Ok(Tag::LockTime(first_value()?.parse()?)),
+            TagKind::NSigsRefund => Ok(Tag::NSigsRefund(first_value()?.parse()?)),
+            TagKind::Refund => Ok(Self::Refund(pubkeys()?)),
+            TagKind::Pubkeys => Ok(Self::PubKeys(pubkeys()?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Amount, nut02::KeysetId};
+
+    fn locked_proof(conditions: SpendingConditions) -> Proof {
+        Proof {
+            amount: Amount::ONE,
+            keyset_id: KeysetId::from_str("009a1f293253e41e").unwrap(),
+            secret: conditions.try_into().unwrap(),
+            c: PublicKey::from_str(
+                "02698c4e2b5f9534cd0687d87513c759790cf829aa5739184a3e3735471fbda904",
+            )
+            .unwrap(),
+            witness: None,
+            dleq: None,
+        }
+    }
+
+    #[test]
+    fn test_conditions_tags_roundtrip() {
+        let pubkey = SecretKey::generate().public_key();
+        let refund = SecretKey::generate().public_key();
+        let conditions = Conditions {
+            locktime: Some(21_000_000),
+            pubkeys: Some(vec![pubkey]),
+            refund_keys: Some(vec![refund]),
+            num_sigs: Some(2),
+            sig_flag: SigFlag::SigInputs,
+            num_sigs_refund: Some(1),
+        };
+
+        let tags: Vec<Vec<String>> = conditions.clone().into();
+        assert_eq!(Conditions::try_from(tags).unwrap(), conditions);
+    }
+
+    #[test]
+    fn test_unknown_tag_is_rejected() {
+        let tags = vec![vec!["not_a_tag".to_string(), "1".to_string()]];
+        assert!(matches!(Conditions::try_from(tags), Err(Error::UnknownTag)));
+    }
+
+    #[test]
+    fn test_secret_roundtrip() {
+        let pubkey = SecretKey::generate().public_key();
+        let spending_conditions = SpendingConditions::new_p2pk(pubkey, None);
+
+        let secret: Secret = spending_conditions.clone().try_into().unwrap();
+        // Well-known secrets are valid nut00 secrets
+        assert!(Secret::from_str(secret.as_ref()).is_ok());
+        assert_eq!(
+            SpendingConditions::try_from(&secret).unwrap(),
+            spending_conditions
+        );
+    }
+
+    #[test]
+    fn test_verify_p2pk() {
+        let secret_key = SecretKey::generate();
+        let mut proof = locked_proof(SpendingConditions::new_p2pk(secret_key.public_key(), None));
+
+        assert!(matches!(
+            proof.verify_p2pk(),
+            Err(Error::SignaturesNotProvided)
+        ));
+
+        proof.sign_p2pk(secret_key).unwrap();
+        assert!(proof.verify_p2pk().is_ok());
+    }
+
+    #[test]
+    fn test_verify_p2pk_wrong_key() {
+        let mut proof = locked_proof(SpendingConditions::new_p2pk(
+            SecretKey::generate().public_key(),
+            None,
+        ));
+
+        proof.sign_p2pk(SecretKey::generate()).unwrap();
+        assert!(matches!(
+            proof.verify_p2pk(),
+            Err(Error::SpendConditionsNotMet)
+        ));
+    }
+
+    #[test]
+    fn test_verify_p2pk_multisig() {
+        let first_key = SecretKey::generate();
+        let second_key = SecretKey::generate();
+        let conditions = Conditions {
+            pubkeys: Some(vec![second_key.public_key()]),
+            num_sigs: Some(2),
+            ..Default::default()
+        };
+        let mut proof = locked_proof(SpendingConditions::new_p2pk(
+            first_key.public_key(),
+            Some(conditions),
+        ));
+
+        proof.sign_p2pk(first_key.clone()).unwrap();
+        // Signing twice with the same key doesn't count twice
+        proof.sign_p2pk(first_key).unwrap();
+        assert!(proof.verify_p2pk().is_err());
+
+        proof.sign_p2pk(second_key).unwrap();
+        assert!(proof.verify_p2pk().is_ok());
+    }
+
+    #[test]
+    fn test_verify_p2pk_refund_after_locktime() {
+        let refund_key = SecretKey::generate();
+        let conditions = Conditions {
+            locktime: Some(1),
+            refund_keys: Some(vec![refund_key.public_key()]),
+            ..Default::default()
+        };
+        let mut proof = locked_proof(SpendingConditions::new_p2pk(
+            SecretKey::generate().public_key(),
+            Some(conditions),
+        ));
+
+        assert!(proof.verify_p2pk().is_err());
+        proof.sign_p2pk(refund_key).unwrap();
+        assert!(proof.verify_p2pk().is_ok());
+    }
+
+    #[test]
+    fn test_refund_key_not_usable_before_locktime() {
+        let refund_key = SecretKey::generate();
+        let conditions = Conditions {
+            locktime: Some(unix_time() + 3600),
+            refund_keys: Some(vec![refund_key.public_key()]),
+            ..Default::default()
+        };
+        let mut proof = locked_proof(SpendingConditions::new_p2pk(
+            SecretKey::generate().public_key(),
+            Some(conditions),
+        ));
+
+        proof.sign_p2pk(refund_key).unwrap();
+        assert!(matches!(
+            proof.verify_p2pk(),
+            Err(Error::SpendConditionsNotMet)
+        ));
+    }
+
+    #[test]
+    fn test_anyone_can_spend_after_locktime_without_refund_keys() {
+        let conditions = Conditions {
+            locktime: Some(1),
+            ..Default::default()
+        };
+        let proof = locked_proof(SpendingConditions::new_p2pk(
+            SecretKey::generate().public_key(),
+            Some(conditions),
+        ));
+
+        assert!(proof.verify_p2pk().is_ok());
+    }
+
+    #[test]
+    fn test_sig_all_is_rejected() {
+        let secret_key = SecretKey::generate();
+        let conditions = Conditions {
+            sig_flag: SigFlag::SigAll,
+            ..Default::default()
+        };
+        let mut proof = locked_proof(SpendingConditions::new_p2pk(
+            secret_key.public_key(),
+            Some(conditions),
+        ));
+
+        proof.sign_p2pk(secret_key).unwrap();
+        assert!(matches!(proof.verify_p2pk(), Err(Error::SigAllUnsupported)));
+    }
+}
@@ -0,0 +1,263 @@
+//! NUT-12: Offline ecash signature validation
+//!
+//! <https://github.com/cashubtc/nuts/blob/main/12.md>
+
+use bitcoin::secp256k1::Scalar;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::SECP256K1;
+use crate::dhke::{hash_e, hash_to_curve};
+use crate::nut00::{BlindSignature, Proof};
+use crate::nut01::{PublicKey, SecretKey};
+
+/// NUT12 Error
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Missing Dleq Proof
+    #[error("No Dleq Proof provided")]
+    MissingDleqProof,
+    /// Invalid Dleq Proof
+    #[error("Invalid Dleq Proof")]
+    InvalidDleqProof,
+    /// DHKE error
+    #[error(transparent)]
+    DHKE(#[from] crate::dhke::Error),
+    /// NUT01 Error
+    #[error(transparent)]
+    NUT01(#[from] crate::nut01::Error),
+    /// SECP256k1 Error
+    #[error(transparent)]
+    Secp256k1(#[from] bitcoin::secp256k1::Error),
+}
+
+/// Blinded Signature Dleq
+///
+/// Attached by the node to each [`BlindSignature`] it returns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlindSignatureDleq {
+    /// e
+    pub e: SecretKey,
+    /// s
+    pub s: SecretKey,
+}
+
+/// Proof Dleq
+///
+/// The [`BlindSignatureDleq`] along with the blinding factor `r`,
+/// required by the receiver of a [`Proof`] to verify it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofDleq {
+    /// e
+    pub e: SecretKey,
+    /// s
+    pub s: SecretKey,
+    /// Blinding factor
+    pub r: SecretKey,
+}
+
+impl ProofDleq {
+    /// Create new [`ProofDleq`]
+    pub fn new(e: SecretKey, s: SecretKey, r: SecretKey) -> Self {
+        Self { e, s, r }
+    }
+}
+
+/// Verify DLEQ
+///
+/// `R1 = s*G - e*A`, `R2 = s*B_ - e*C_` and `e == hash(R1, R2, A, C_)`
+pub fn verify_dleq(
+    blinded_message: PublicKey,   // B'
+    blinded_signature: PublicKey, // C'
+    e: &SecretKey,
+    s: &SecretKey,
+    mint_pubkey: PublicKey, // A
+) -> Result<(), Error> {
+    let e_bytes: [u8; 32] = e.to_secret_bytes();
+    let e: Scalar = e.as_scalar();
+
+    // a = e*A
+    let a: PublicKey = mint_pubkey.mul_tweak(&SECP256K1, &e)?.into();
+
+    // R1 = s*G - a
+    let a: PublicKey = a.negate(&SECP256K1).into();
+    let r1: PublicKey = s.public_key().combine(&a)?.into(); // s*G + (-a)
+
+    // b = s*B'
+    let s: Scalar = s.as_scalar();
+    let b: PublicKey = blinded_message.mul_tweak(&SECP256K1, &s)?.into();
+
+    // c = e*C'
+    let c: PublicKey = blinded_signature.mul_tweak(&SECP256K1, &e)?.into();
+
+    // R2 = b - c
+    let c: PublicKey = c.negate(&SECP256K1).into();
+    let r2: PublicKey = b.combine(&c)?.into();
+
+    // hash(R1,R2,A,C')
+    let hash_e: [u8; 32] = hash_e([r1, r2, mint_pubkey, blinded_signature]);
+
+    if e_bytes != hash_e {
+        tracing::warn!("DLEQ on signature failed");
+        tracing::debug!("e_bytes: {:?}, hash_e: {:?}", e_bytes, hash_e);
+        return Err(Error::InvalidDleqProof);
+    }
+
+    Ok(())
+}
+
+/// Calculate DLEQ
+///
+/// `r1 = p*G`, `r2 = p*B_`, `e = hash(r1, r2, A, C_)` and `s = p + e*a`,
+/// where `p` is a random nonce and `a` the node secret key for this amount
+pub fn calculate_dleq(
+    blinded_signature: PublicKey, // C'
+    blinded_message: &PublicKey,  // B'
+    mint_secret_key: &SecretKey,  // a
+) -> Result<BlindSignatureDleq, Error> {
+    // Random nonce
+    let p: SecretKey = SecretKey::generate();
+
+    // r1 = p*G
+    let r1 = p.public_key();
+
+    // r2 = p*B'
+    let r2: PublicKey = blinded_message
+        .mul_tweak(&SECP256K1, &p.as_scalar())?
+        .into();
+
+    // e = hash(r1,r2,A,C')
+    let e: [u8; 32] = hash_e([r1, r2, mint_secret_key.public_key(), blinded_signature]);
+    let e_sk: SecretKey = SecretKey::from_slice(&e)?;
+
+    // s1 = e*a
+    let s1: SecretKey = e_sk.mul_tweak(&mint_secret_key.as_scalar())?.into();
+
+    // s = p + s1
+    let s: SecretKey = p.add_tweak(&s1.as_scalar())?.into();
+
+    Ok(BlindSignatureDleq { e: e_sk, s })
+}
+
+impl Proof {
+    /// Verify proof Dleq
+    ///
+    /// Rebuilds `B_ = Y + r*G` and `C_ = C + r*A` from the unblinded proof
+    pub fn verify_dleq(&self, mint_pubkey: PublicKey) -> Result<(), Error> {
+        let dleq = self.dleq.as_ref().ok_or(Error::MissingDleqProof)?;
+
+        let y = hash_to_curve(self.secret.as_bytes())?;
+
+        let r: Scalar = dleq.r.as_scalar();
+        let bs1: PublicKey = mint_pubkey.mul_tweak(&SECP256K1, &r)?.into();
+
+        let blinded_signature: PublicKey = self.c.combine(&bs1)?.into();
+        let blinded_message: PublicKey = y.combine(&dleq.r.public_key())?.into();
+
+        verify_dleq(
+            blinded_message,
+            blinded_signature,
+            &dleq.e,
+            &dleq.s,
+            mint_pubkey,
+        )
+    }
+}
+
+impl BlindSignature {
+    /// Verify blind signature dleq
+    ///
+    /// Checks that the signature was produced by the node key `mint_pubkey`
+    pub fn verify_dleq(
+        &self,
+        mint_pubkey: PublicKey,
+        blinded_message: PublicKey,
+    ) -> Result<(), Error> {
+        match &self.dleq {
+            Some(dleq) => verify_dleq(blinded_message, self.c, &dleq.e, &dleq.s, mint_pubkey),
+            None => Err(Error::MissingDleqProof),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{
+        Amount,
+        dhke::{blind_message, sign_message, unblind_message},
+        nut00::secret::Secret,
+        nut02::KeysetId,
+    };
+
+    #[test]
+    fn test_blind_signature_dleq() {
+        let blinded_sig = r#"{"amount":8,"id":"00882760bfa2eb41","C_":"02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2","dleq":{"e":"9818e061ee51d5c8edc3342369a554998ff7b4381c8652d724cdf46429be73d9","s":"9818e061ee51d5c8edc3342369a554998ff7b4381c8652d724cdf46429be73da"}}"#;
+
+        let blinded: BlindSignature = serde_json::from_str(blinded_sig).unwrap();
+
+        let secret_key =
+            SecretKey::from_hex("0000000000000000000000000000000000000000000000000000000000000001")
+                .unwrap();
+        let mint_key = secret_key.public_key();
+
+        let blinded_secret = PublicKey::from_str(
+            "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2",
+        )
+        .unwrap();
+
+        blinded.verify_dleq(mint_key, blinded_secret).unwrap()
+    }
+
+    #[test]
+    fn test_dleq_roundtrip() {
+        let mint_secret_key = SecretKey::generate();
+        let mint_pubkey = mint_secret_key.public_key();
+        let secret = Secret::generate();
+
+        let (blinded_message, r) = blind_message(secret.as_bytes(), None).unwrap();
+        let c_ = sign_message(&mint_secret_key, &blinded_message).unwrap();
+        let dleq = calculate_dleq(c_, &blinded_message, &mint_secret_key).unwrap();
+
+        let keyset_id = KeysetId::from_str("009a1f293253e41e").unwrap();
+        let blind_signature = BlindSignature {
+            amount: Amount::ONE,
+            keyset_id,
+            c: c_,
+            dleq: Some(dleq.clone()),
+        };
+        assert!(
+            blind_signature
+                .verify_dleq(mint_pubkey, blinded_message)
+                .is_ok()
+        );
+        assert!(matches!(
+            blind_signature.verify_dleq(SecretKey::generate().public_key(), blinded_message),
+            Err(Error::InvalidDleqProof)
+        ));
+
+        let mut proof = Proof {
+            amount: Amount::ONE,
+            keyset_id,
+            secret,
+            c: unblind_message(&c_, &r, &mint_pubkey).unwrap(),
+            witness: None,
+            dleq: Some(ProofDleq::new(dleq.e, dleq.s, r)),
+        };
+        assert!(proof.verify_dleq(mint_pubkey).is_ok());
+
+        proof.secret = Secret::generate();
+        assert!(matches!(
+            proof.verify_dleq(mint_pubkey),
+            Err(Error::InvalidDleqProof)
+        ));
+
+        proof.dleq = None;
+        assert!(matches!(
+            proof.verify_dleq(mint_pubkey),
+            Err(Error::MissingDleqProof)
+        ));
+    }
+}
@@ -0,0 +1,294 @@
+//! NUT-14: Hashed Time Lock Contacts (HTLC)
+//!
+//! <https://github.com/cashubtc/nuts/blob/main/14.md>
+
+use std::str::FromStr;
+
+use bitcoin::hashes::Hash;
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::secp256k1::schnorr::Signature;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::nut00::{Proof, Witness};
+use crate::nut10::{self, Kind};
+use crate::nut11::{Conditions, SigFlag, valid_signatures};
+use crate::unix_time;
+
+/// NUT14 Errors
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Incorrect secret kind
+    #[error("Secret is not a HTLC secret")]
+    IncorrectSecretKind,
+    /// HTLC locktime has already passed
+    #[error("Locktime in past")]
+    LocktimeInPast,
+    /// Hash Required
+    #[error("Hash required")]
+    HashRequired,
+    /// Preimage does not match the hash lock
+    #[error("Preimage does not match")]
+    Preimage,
+    /// Witness signatures not provided
+    #[error("Witness did not provide signatures")]
+    SignaturesNotProvided,
+    /// Not enough valid signatures
+    #[error("HTLC spend conditions are not met")]
+    SpendConditionsNotMet,
+    /// Sig all flag is not supported
+    #[error("SIG_ALL spending conditions are not supported")]
+    SigAllUnsupported,
+    /// Hex error
+    #[error(transparent)]
+    Hex(#[from] hex::FromHexError),
+    /// Hash lock parse error
+    #[error(transparent)]
+    HexToArray(#[from] bitcoin::hashes::hex::HexToArrayError),
+    /// Secp256k1 error
+    #[error(transparent)]
+    Secp256k1(#[from] bitcoin::secp256k1::Error),
+    /// NUT10 Error
+    #[error(transparent)]
+    NUT10(#[from] crate::nut10::Error),
+    /// NUT11 Error
+    #[error(transparent)]
+    NUT11(#[from] crate::nut11::Error),
+}
+
+/// HTLC Witness
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HTLCWitness {
+    /// Hex encoded preimage of the hash lock
+    pub preimage: String,
+    /// Signatures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signatures: Option<Vec<String>>,
+}
+
+impl Proof {
+    /// Verify HTLC
+    ///
+    /// Once the locktime has passed, a signature from one of the refund keys is enough to unlock it,
+    /// or nothing at all if there is no refund key, the same way as for P2PK.
+    /// Only `SIG_INPUTS` is supported, our outputs don't carry any witness
+    pub fn verify_htlc(&self) -> Result<(), Error> {
+        let secret: nut10::Secret = (&self.secret).try_into()?;
+        if secret.kind != Kind::HTLC {
+            return Err(Error::IncorrectSecretKind);
+        }
+
+        let conditions: Conditions = secret
+            .secret_data
+            .tags
+            .clone()
+            .unwrap_or_default()
+            .try_into()?;
+
+        if conditions.sig_flag == SigFlag::SigAll {
+            return Err(Error::SigAllUnsupported);
+        }
+
+        let msg: &[u8] = self.secret.as_bytes();
+        let signatures = self
+            .witness
+            .as_ref()
+            .and_then(|w| w.signatures())
+            .unwrap_or_default()
+            .iter()
+            .map(|s| Signature::from_str(s))
+            .collect::<Result<Vec<Signature>, _>>()?;
+
+        if let Some(locktime) = conditions.locktime {
+            // If lock time has passed check if refund witness signature is valid
+            if locktime.lt(&unix_time()) {
+                match &conditions.refund_keys {
+                    Some(refund_keys) => {
+                        let valid_refund_sigs = valid_signatures(msg, refund_keys, &signatures);
+                        if valid_refund_sigs >= conditions.num_sigs_refund.unwrap_or(1) {
+                            return Ok(());
+                        }
+                    }
+                    // Anyone can spend once the locktime is expired and no refund key was set
+                    None => return Ok(()),
+                }
+            }
+        }
+
+        let preimage = self
+            .witness
+            .as_ref()
+            .and_then(|w| w.preimage())
+            .ok_or(Error::Preimage)?;
+        let hash_lock = Sha256Hash::from_str(&secret.secret_data.data)?;
+        let preimage_hash = Sha256Hash::hash(&hex::decode(preimage)?);
+
+        if hash_lock != preimage_hash {
+            return Err(Error::Preimage);
+        }
+
+        if let Some(pubkeys) = conditions.pubkeys {
+            if signatures.is_empty() {
+                return Err(Error::SignaturesNotProvided);
+            }
+
+            let valid_sigs = valid_signatures(msg, &pubkeys, &signatures);
+            if valid_sigs < conditions.num_sigs.unwrap_or(1) {
+                return Err(Error::SpendConditionsNotMet);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add Preimage
+    ///
+    /// Signatures already present in a P2PK witness are preserved
+    #[inline]
+    pub fn add_preimage(&mut self, preimage: String) {
+        let signatures = self.witness.as_ref().and_then(|w| w.signatures());
+
+        self.witness = Some(Witness::HTLCWitness(HTLCWitness {
+            preimage,
+            signatures,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Amount,
+        nut00::secret::Secret,
+        nut01::{PublicKey, SecretKey},
+        nut02::KeysetId,
+        nut11::SpendingConditions,
+    };
+
+    const PREIMAGE: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+    fn locked_proof(conditions: SpendingConditions) -> Proof {
+        Proof {
+            amount: Amount::ONE,
+            keyset_id: KeysetId::from_str("009a1f293253e41e").unwrap(),
+            secret: conditions.try_into().unwrap(),
+            c: PublicKey::from_str(
+                "02698c4e2b5f9534cd0687d87513c759790cf829aa5739184a3e3735471fbda904",
+            )
+            .unwrap(),
+            witness: None,
+            dleq: None,
+        }
+    }
+
+    #[test]
+    fn test_htlc_secret_roundtrip() {
+        let conditions = SpendingConditions::new_htlc(PREIMAGE, None).unwrap();
+        let secret: Secret = conditions.clone().try_into().unwrap();
+
+        assert!(Secret::from_str(secret.as_ref()).is_ok());
+        assert_eq!(SpendingConditions::try_from(&secret).unwrap(), conditions);
+        assert_eq!(
+            SpendingConditions::new_htlc_hash(
+                "ec4916dd28fc4c10d78e287ca5d9cc51ee1ae73cbfde08c6b37324cbfaac8bc5",
+                None
+            )
+            .unwrap(),
+            conditions
+        );
+    }
+
+    #[test]
+    fn test_witness_roundtrip() {
+        let witness = Witness::HTLCWitness(HTLCWitness {
+            preimage: PREIMAGE.to_string(),
+            signatures: None,
+        });
+
+        assert_eq!(Witness::from_str(&witness.to_string()).unwrap(), witness);
+    }
+
+    #[test]
+    fn test_verify_htlc() {
+        let mut proof = locked_proof(SpendingConditions::new_htlc(PREIMAGE, None).unwrap());
+
+        assert!(matches!(proof.verify_htlc(), Err(Error::Preimage)));
+
+        proof.add_preimage(
+            "0000000000000000000000000000000000000000000000000000000000000002".to_string(),
+        );
+        assert!(matches!(proof.verify_htlc(), Err(Error::Preimage)));
+
+        proof.add_preimage(PREIMAGE.to_string());
+        assert!(proof.verify_htlc().is_ok());
+    }
+
+    #[test]
+    fn test_verify_htlc_with_pubkeys() {
+        let secret_key = SecretKey::generate();
+        let conditions = Conditions {
+            pubkeys: Some(vec![secret_key.public_key()]),
+            ..Default::default()
+        };
+        let mut proof =
+            locked_proof(SpendingConditions::new_htlc(PREIMAGE, Some(conditions)).unwrap());
+
+        proof.add_preimage(PREIMAGE.to_string());
+        assert!(matches!(
+            proof.verify_htlc(),
+            Err(Error::SignaturesNotProvided)
+        ));
+
+        proof.sign_p2pk(SecretKey::generate()).unwrap();
+        assert!(matches!(
+            proof.verify_htlc(),
+            Err(Error::SpendConditionsNotMet)
+        ));
+
+        proof.sign_p2pk(secret_key).unwrap();
+        assert!(proof.verify_htlc().is_ok());
+    }
+
+    #[test]
+    fn test_verify_htlc_refund() {
+        let refund_key = SecretKey::generate();
+        let conditions = Conditions {
+            locktime: Some(1),
+            refund_keys: Some(vec![refund_key.public_key()]),
+            ..Default::default()
+        };
+        let mut proof =
+            locked_proof(SpendingConditions::new_htlc(PREIMAGE, Some(conditions)).unwrap());
+
+        assert!(proof.verify_htlc().is_err());
+
+        proof.sign_p2pk(refund_key).unwrap();
+        assert!(proof.verify_htlc().is_ok());
+    }
+
+    #[test]
+    fn test_verify_htlc_expired_without_refund_keys() {
+        let conditions = Conditions {
+            locktime: Some(1),
+            pubkeys: Some(vec![SecretKey::generate().public_key()]),
+            ..Default::default()
+        };
+        let proof = locked_proof(SpendingConditions::new_htlc(PREIMAGE, Some(conditions)).unwrap());
+
+        assert!(proof.verify_htlc().is_ok());
+    }
+
+    #[test]
+    fn test_p2pk_secret_is_not_htlc() {
+        let proof = locked_proof(SpendingConditions::new_p2pk(
+            SecretKey::generate().public_key(),
+            None,
+        ));
+
+        assert!(matches!(
+            proof.verify_htlc(),
+            Err(Error::IncorrectSecretKind)
+        ));
+    }
+}
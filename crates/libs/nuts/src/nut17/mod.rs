@@ -0,0 +1,299 @@
+//! NUT-17: WebSocket subscriptions
+//!
+//! <https://github.com/cashubtc/nuts/blob/main/17.md>
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    nut04::MintQuoteResponse, nut05::MeltQuoteResponse, nut07::ProofCheckState, traits::Unit,
+};
+
+pub const JSON_RPC_VERSION: &str = "2.0";
+
+/// JSON-RPC error codes
+pub mod error_code {
+    pub const PARSE_ERROR: i32 = -32700;
+    pub const INVALID_REQUEST: i32 = -32600;
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const INTERNAL_ERROR: i32 = -32603;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("unknown subscription kind `{0}`")]
+    UnknownKind(String),
+    #[error("invalid method in subscription kind `{0}`")]
+    InvalidMethod(String),
+}
+
+/// Subscription kind
+///
+/// Quote kinds are prefixed by their payment method, eg. `starknet_mint_quote`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind<M> {
+    MintQuote(M),
+    MeltQuote(M),
+    ProofState,
+}
+
+const MINT_QUOTE_SUFFIX: &str = "_mint_quote";
+const MELT_QUOTE_SUFFIX: &str = "_melt_quote";
+const PROOF_STATE: &str = "proof_state";
+
+impl<M: Display> Display for Kind<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::MintQuote(method) => write!(f, "{}{}", method, MINT_QUOTE_SUFFIX),
+            Kind::MeltQuote(method) => write!(f, "{}{}", method, MELT_QUOTE_SUFFIX),
+            Kind::ProofState => f.write_str(PROOF_STATE),
+        }
+    }
+}
+
+impl<M: FromStr> FromStr for Kind<M> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_method =
+            |method: &str| M::from_str(method).map_err(|_| Error::InvalidMethod(s.to_string()));
+
+        if s == PROOF_STATE {
+            Ok(Kind::ProofState)
+        } else if let Some(method) = s.strip_suffix(MINT_QUOTE_SUFFIX) {
+            Ok(Kind::MintQuote(parse_method(method)?))
+        } else if let Some(method) = s.strip_suffix(MELT_QUOTE_SUFFIX) {
+            Ok(Kind::MeltQuote(parse_method(method)?))
+        } else {
+            Err(Error::UnknownKind(s.to_string()))
+        }
+    }
+}
+
+impl<M: Display> Serialize for Kind<M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, M: FromStr> Deserialize<'de> for Kind<M> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Kind::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Mint settings
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Settings<M, U> {
+    pub supported: Vec<SupportedMethod<M, U>>,
+}
+
+impl<M, U> Default for Settings<M, U> {
+    fn default() -> Self {
+        Self {
+            supported: Vec::new(),
+        }
+    }
+}
+
+/// A method/unit pair and the subscription kinds available for it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SupportedMethod<M, U> {
+    pub method: M,
+    pub unit: U,
+    pub commands: Vec<String>,
+}
+
+/// Subscribe params
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "M: Display", deserialize = "M: FromStr"))]
+pub struct Params<M> {
+    pub kind: Kind<M>,
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+    /// Quote ids for quote kinds, hex encoded `Y`s for [`Kind::ProofState`]
+    pub filters: Vec<String>,
+}
+
+/// Unsubscribe params
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnsubscribeParams {
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "method",
+    content = "params",
+    rename_all = "lowercase",
+    bound(serialize = "M: Display", deserialize = "M: FromStr")
+)]
+pub enum WsMethodRequest<M> {
+    Subscribe(Params<M>),
+    Unsubscribe(UnsubscribeParams),
+}
+
+/// JSON-RPC request sent by the wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "M: Display", deserialize = "M: FromStr"))]
+pub struct WsRequest<M> {
+    pub jsonrpc: String,
+    #[serde(flatten)]
+    pub method: WsMethodRequest<M>,
+    pub id: u64,
+}
+
+/// JSON-RPC response to a [`WsRequest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WsResponse {
+    pub jsonrpc: String,
+    pub result: WsResponseResult,
+    pub id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WsResponseResult {
+    pub status: String,
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+}
+
+impl WsResponse {
+    pub fn ok(id: u64, sub_id: String) -> Self {
+        Self {
+            jsonrpc: JSON_RPC_VERSION.to_string(),
+            result: WsResponseResult {
+                status: "OK".to_string(),
+                sub_id,
+            },
+            id,
+        }
+    }
+}
+
+/// JSON-RPC error answered to a [`WsRequest`]
+///
+/// `id` is `None` when the request could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WsErrorResponse {
+    pub jsonrpc: String,
+    pub error: WsErrorBody,
+    pub id: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WsErrorBody {
+    pub code: i32,
+    pub message: String,
+}
+
+impl WsErrorResponse {
+    pub fn new(id: Option<u64>, code: i32, message: String) -> Self {
+        Self {
+            jsonrpc: JSON_RPC_VERSION.to_string(),
+            error: WsErrorBody { code, message },
+            id,
+        }
+    }
+}
+
+/// Notification pushed by the node for an active subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WsNotification<T> {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: NotificationParams<T>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationParams<T> {
+    #[serde(rename = "subId")]
+    pub sub_id: String,
+    pub payload: T,
+}
+
+impl<T> WsNotification<T> {
+    pub fn new(sub_id: String, payload: T) -> Self {
+        Self {
+            jsonrpc: JSON_RPC_VERSION.to_string(),
+            method: "subscribe".to_string(),
+            params: NotificationParams { sub_id, payload },
+        }
+    }
+}
+
+/// Payload of a [`WsNotification`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum NotificationPayload<Q, U: Unit> {
+    MintQuote(MintQuoteResponse<Q>),
+    MeltQuote(MeltQuoteResponse<Q, U>),
+    ProofState(ProofCheckState),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::test_types::TestMethod;
+
+    #[test]
+    fn kind_roundtrip() {
+        let cases = [
+            ("bolt11_mint_quote", Kind::MintQuote(TestMethod::Bolt11)),
+            ("bolt11_melt_quote", Kind::MeltQuote(TestMethod::Bolt11)),
+            ("proof_state", Kind::ProofState),
+        ];
+
+        for (s, kind) in cases {
+            assert_eq!(Kind::<TestMethod>::from_str(s).unwrap(), kind);
+            assert_eq!(kind.to_string(), s);
+        }
+
+        assert!(matches!(
+            Kind::<TestMethod>::from_str("onchain_mint_quote"),
+            Err(Error::InvalidMethod(_))
+        ));
+        assert!(matches!(
+            Kind::<TestMethod>::from_str("bolt11_swap"),
+            Err(Error::UnknownKind(_))
+        ));
+    }
+
+    #[test]
+    fn parse_requests() {
+        let subscribe = r#"{"jsonrpc":"2.0","id":0,"method":"subscribe","params":{"kind":"bolt11_mint_quote","subId":"9d1c0eab","filters":["3b2b0a68-0bf8-4b1d-9a67-2dc5d1a6f34a"]}}"#;
+        let request: WsRequest<TestMethod> = serde_json::from_str(subscribe).unwrap();
+        assert_eq!(request.id, 0);
+        assert_eq!(
+            request.method,
+            WsMethodRequest::Subscribe(Params {
+                kind: Kind::MintQuote(TestMethod::Bolt11),
+                sub_id: "9d1c0eab".to_string(),
+                filters: vec!["3b2b0a68-0bf8-4b1d-9a67-2dc5d1a6f34a".to_string()],
+            })
+        );
+        assert_eq!(
+            serde_json::from_str::<WsRequest<TestMethod>>(
+                &serde_json::to_string(&request).unwrap()
+            )
+            .unwrap(),
+            request
+        );
+
+        let unsubscribe =
+            r#"{"jsonrpc":"2.0","id":1,"method":"unsubscribe","params":{"subId":"9d1c0eab"}}"#;
+        let request: WsRequest<TestMethod> = serde_json::from_str(unsubscribe).unwrap();
+        assert_eq!(
+            request.method,
+            WsMethodRequest::Unsubscribe(UnsubscribeParams {
+                sub_id: "9d1c0eab".to_string()
+            })
+        );
+    }
+}
@@ -45,6 +45,17 @@ impl Display for Route {
 
 pub type CacheResponseKey = (Route, u64);
 
+/// Acknowledge request
+///
+/// Tells the node the client stored the response to this request, so that it can stop caching it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcknowledgeRequest {
+    /// Route of the request, as displayed by [`Route`]
+    pub path: String,
+    /// Hash of the request
+    pub request_hash: u64,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum PathFromStrError {
     #[error("Invalid version {0}")]
@@ -0,0 +1,299 @@
+//! Proof of liabilities
+//!
+//! For each of its keysets, the node commits to the blinded messages it signed and to the proofs
+//! it burned, as two Merkle sum trees. Each node of those trees carries the sum of the amounts
+//! below it, so that the roots commit to the totals issued and burned.
+//!
+//! A user knowing one of the leaves can check it is included in the tree, and so counted in its
+//! total. A node hiding some of its issuance would have to leave some users' blinded messages out.
+
+use bitcoin_hashes::{HashEngine, sha256};
+use nuts::{Amount, nut01::PublicKey};
+
+const LEAF_TAG: u8 = 0;
+const BRANCH_TAG: u8 = 1;
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("the sum of the amounts overflows")]
+    AmountOverflow,
+    #[error("leaf index {0} is out of a tree of {1} leaves")]
+    LeafIndexOutOfRange(u64, u64),
+    #[error("the proof has too few siblings")]
+    MissingSibling,
+    #[error("the proof has too many siblings")]
+    UnexpectedSibling,
+    #[error("the proof doesn't lead to the expected root")]
+    RootMismatch,
+}
+
+/// A node of a Merkle sum tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Node {
+    pub hash: sha256::Hash,
+    /// Sum of the amounts of the leaves below
+    pub amount: Amount,
+}
+
+impl Node {
+    /// Root of a tree without leaves
+    pub const EMPTY: Node = Node {
+        hash: sha256::Hash::from_byte_array([0; 32]),
+        amount: Amount::ZERO,
+    };
+
+    pub fn new(hash: [u8; 32], amount: Amount) -> Self {
+        Self {
+            hash: sha256::Hash::from_byte_array(hash),
+            amount,
+        }
+    }
+
+    /// Leaf committing to a point and its amount
+    ///
+    /// The point is the blinded message `B_` in the issuance tree, and the proof `Y` in the burn one.
+    pub fn leaf(point: &PublicKey, amount: Amount) -> Self {
+        let mut engine = sha256::Hash::engine();
+        engine.input(&[LEAF_TAG]);
+        engine.input(&point.to_bytes());
+        engine.input(&u64::from(amount).to_be_bytes());
+
+        Self {
+            hash: sha256::Hash::from_engine(engine),
+            amount,
+        }
+    }
+
+    fn branch(left: &Node, right: &Node) -> Result<Self, Error> {
+        let amount = u64::from(left.amount)
+            .checked_add(u64::from(right.amount))
+            .ok_or(Error::AmountOverflow)?;
+
+        let mut engine = sha256::Hash::engine();
+        engine.input(&[BRANCH_TAG]);
+        engine.input(left.hash.as_byte_array());
+        engine.input(&u64::from(left.amount).to_be_bytes());
+        engine.input(right.hash.as_byte_array());
+        engine.input(&u64::from(right.amount).to_be_bytes());
+
+        Ok(Self {
+            hash: sha256::Hash::from_engine(engine),
+            amount: Amount::from(amount),
+        })
+    }
+}
+
+/// Merkle sum tree over an ordered list of leaves
+///
+/// The last node of a level with an odd length is moved up as is, rather than paired with itself.
+#[derive(Debug, Clone)]
+pub struct MerkleSumTree {
+    levels: Vec<Vec<Node>>,
+}
+
+impl MerkleSumTree {
+    pub fn new(leaves: Vec<Node>) -> Result<Self, Error> {
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last().filter(|l| l.len() > 1) {
+            let next_level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => Node::branch(left, right),
+                    [single] => Ok(*single),
+                    _ => unreachable!("chunks of 2"),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            levels.push(next_level);
+        }
+
+        Ok(Self { levels })
+    }
+
+    pub fn leaf_count(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    pub fn root(&self) -> Node {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or(Node::EMPTY)
+    }
+
+    /// Proof that the leaf at `index` is part of the tree
+    pub fn prove(&self, index: usize) -> Option<InclusionProof> {
+        if index >= self.levels[0].len() {
+            return None;
+        }
+
+        let mut siblings = Vec::with_capacity(self.levels.len() - 1);
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+
+        Some(InclusionProof {
+            leaf_index: index as u64,
+            leaf_count: self.leaf_count(),
+            siblings,
+        })
+    }
+}
+
+/// Path from a leaf up to the root of a [`MerkleSumTree`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub leaf_count: u64,
+    /// From the leaf level up, levels where the node has no sibling are skipped
+    pub siblings: Vec<Node>,
+}
+
+impl InclusionProof {
+    /// The root of the tree `leaf` belongs to, according to this proof
+    pub fn compute_root(&self, leaf: Node) -> Result<Node, Error> {
+        if self.leaf_index >= self.leaf_count {
+            return Err(Error::LeafIndexOutOfRange(self.leaf_index, self.leaf_count));
+        }
+
+        let mut siblings = self.siblings.iter();
+        let mut node = leaf;
+        let mut position = self.leaf_index;
+        let mut level_len = self.leaf_count;
+        while level_len > 1 {
+            let sibling_position = position ^ 1;
+            if sibling_position < level_len {
+                let sibling = siblings.next().ok_or(Error::MissingSibling)?;
+                node = if position % 2 == 0 {
+                    Node::branch(&node, sibling)?
+                } else {
+                    Node::branch(sibling, &node)?
+                };
+            }
+            position /= 2;
+            level_len = level_len.div_ceil(2);
+        }
+        if siblings.next().is_some() {
+            return Err(Error::UnexpectedSibling);
+        }
+
+        Ok(node)
+    }
+
+    /// Check that `leaf` is part of the tree committed to by `root`
+    pub fn verify(&self, leaf: Node, root: &Node) -> Result<(), Error> {
+        if self.compute_root(leaf)? != *root {
+            return Err(Error::RootMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nuts::nut01::SecretKey;
+
+    use super::*;
+
+    fn leaves(n: u64) -> Vec<Node> {
+        (1..=n)
+            .map(|i| Node::leaf(&SecretKey::generate().public_key(), Amount::from(i)))
+            .collect()
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree = MerkleSumTree::new(vec![]).unwrap();
+
+        assert_eq!(tree.root(), Node::EMPTY);
+        assert!(tree.prove(0).is_none());
+    }
+
+    #[test]
+    fn root_amount_is_the_sum_of_the_leaves() {
+        for n in 1..=9 {
+            let tree = MerkleSumTree::new(leaves(n)).unwrap();
+
+            assert_eq!(tree.root().amount, Amount::from(n * (n + 1) / 2));
+        }
+    }
+
+    #[test]
+    fn every_leaf_is_proven() {
+        for n in 1..=17 {
+            let leaves = leaves(n);
+            let tree = MerkleSumTree::new(leaves.clone()).unwrap();
+            let root = tree.root();
+
+            for (index, leaf) in leaves.into_iter().enumerate() {
+                let proof = tree.prove(index).unwrap();
+                assert_eq!(proof.verify(leaf, &root), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_leaf_is_rejected() {
+        let leaves = leaves(5);
+        let tree = MerkleSumTree::new(leaves.clone()).unwrap();
+        let root = tree.root();
+        let proof = tree.prove(2).unwrap();
+
+        let understated = Node {
+            amount: Amount::ONE,
+            ..leaves[2]
+        };
+        assert_eq!(proof.verify(understated, &root), Err(Error::RootMismatch));
+        assert_eq!(proof.verify(leaves[3], &root), Err(Error::RootMismatch));
+    }
+
+    #[test]
+    fn malformed_proof_is_rejected() {
+        let leaves = leaves(6);
+        let tree = MerkleSumTree::new(leaves.clone()).unwrap();
+        let root = tree.root();
+        let proof = tree.prove(4).unwrap();
+
+        let mut missing_sibling = proof.clone();
+        missing_sibling.siblings.pop();
+        assert_eq!(
+            missing_sibling.verify(leaves[4], &root),
+            Err(Error::MissingSibling)
+        );
+
+        let mut extra_sibling = proof.clone();
+        extra_sibling.siblings.push(leaves[0]);
+        assert_eq!(
+            extra_sibling.verify(leaves[4], &root),
+            Err(Error::UnexpectedSibling)
+        );
+
+        let out_of_range = InclusionProof {
+            leaf_index: 6,
+            ..proof
+        };
+        assert_eq!(
+            out_of_range.verify(leaves[4], &root),
+            Err(Error::LeafIndexOutOfRange(6, 6))
+        );
+    }
+
+    #[test]
+    fn amount_overflow_is_rejected() {
+        let point = SecretKey::generate().public_key();
+        let leaves = vec![
+            Node::leaf(&point, Amount::from(u64::MAX)),
+            Node::leaf(&point, Amount::ONE),
+        ];
+
+        assert_eq!(
+            MerkleSumTree::new(leaves).unwrap_err(),
+            Error::AmountOverflow
+        );
+    }
+}
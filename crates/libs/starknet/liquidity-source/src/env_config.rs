@@ -1,4 +1,4 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::{env::VarError, num::ParseIntError, str::FromStr};
 
 use http::{Uri, uri};
 use starknet_types::CairoShortStringToFeltError;
@@ -21,6 +21,12 @@ pub enum ReadStarknetConfigError {
     Uri(#[from] uri::InvalidUri),
     #[error("Invalid value for env var `{STARKNET_INDEXER_START_BLOCK_ENV_VAR}`: {0}")]
     StartBlock(#[from] ParseIntError),
+    #[error("Invalid value for env var `{0}`: {1}")]
+    Integer(&'static str, #[source] ParseIntError),
+    #[error(
+        "Invalid value for env var `{STARKNET_INDEXER_ENV_VAR}`: `{0}`, expected `substreams` or `rpc`"
+    )]
+    Indexer(String),
 }
 
 const STARKNET_CASHIER_PRIVATE_KEY_ENV_VAR: &str = "STARKNET_CASHIER_PRIVATE_KEY";
@@ -28,7 +34,26 @@ const STARKNET_CHAIN_ID_ENV_VAR: &str = "STARKNET_CHAIN_ID";
 const STARKNET_INDEXER_START_BLOCK_ENV_VAR: &str = "STARKNET_INDEXER_START_BLOCK";
 const STARKNET_CASHIER_ACCOUNT_ADDRESS_ENV_VAR: &str = "STARKNET_CASHIER_ACCOUNT_ADDRESS";
 const STARKNET_SUBSTREAMS_URL_ENV_VAR: &str = "STARKNET_SUBSTREAMS_URL";
+const STARKNET_INDEXER_ENV_VAR: &str = "STARKNET_INDEXER";
+const STARKNET_INDEXER_CONFIRMATIONS_ENV_VAR: &str = "STARKNET_INDEXER_CONFIRMATIONS";
 const STARKNET_RPC_NODE_URL_ENV_VAR: &str = "STARKNET_RPC_NODE_URL";
+const STARKNET_WITHDRAW_BATCH_MAX_WAIT_ENV_VAR: &str = "STARKNET_WITHDRAW_BATCH_MAX_WAIT";
+const STARKNET_WITHDRAW_BATCH_MAX_ORDERS_ENV_VAR: &str = "STARKNET_WITHDRAW_BATCH_MAX_ORDERS";
+const STARKNET_WITHDRAW_BATCH_MAX_L2_GAS_ENV_VAR: &str = "STARKNET_WITHDRAW_BATCH_MAX_L2_GAS";
+
+/// Read an optional integer env variable
+fn read_optional_env_variable<T: FromStr<Err = ParseIntError>>(
+    name: &'static str,
+) -> Result<Option<T>, ReadStarknetConfigError> {
+    match std::env::var(name) {
+        Ok(v) => Ok(Some(
+            v.parse()
+                .map_err(|e| ReadStarknetConfigError::Integer(name, e))?,
+        )),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(e) => Err(ReadStarknetConfigError::Env(name, e)),
+    }
+}
 
 pub(crate) fn read_env_variables() -> Result<StarknetCliConfig, ReadStarknetConfigError> {
     let chain_id = std::env::var(STARKNET_CHAIN_ID_ENV_VAR)
@@ -41,8 +66,13 @@ pub(crate) fn read_env_variables() -> Result<StarknetCliConfig, ReadStarknetConf
         .map_err(|e| ReadStarknetConfigError::Env(STARKNET_CASHIER_PRIVATE_KEY_ENV_VAR, e))?;
     let rpc_node_url = std::env::var(STARKNET_RPC_NODE_URL_ENV_VAR)
         .map_err(|e| ReadStarknetConfigError::Env(STARKNET_RPC_NODE_URL_ENV_VAR, e))?;
-    let substreams_url = std::env::var(STARKNET_SUBSTREAMS_URL_ENV_VAR)
-        .map_err(|e| ReadStarknetConfigError::Env(STARKNET_SUBSTREAMS_URL_ENV_VAR, e))?;
+    let indexer = match std::env::var(STARKNET_INDEXER_ENV_VAR) {
+        Ok(v) if v == "rpc" => IndexerBackend::Rpc,
+        Ok(v) if v == "substreams" => read_substreams_backend()?,
+        Ok(v) => return Err(ReadStarknetConfigError::Indexer(v)),
+        Err(VarError::NotPresent) => read_substreams_backend()?,
+        Err(e) => return Err(ReadStarknetConfigError::Env(STARKNET_INDEXER_ENV_VAR, e)),
+    };
 
     let config = StarknetCliConfig {
         chain_id: starknet_types::ChainId::from_str(&chain_id)?,
@@ -52,12 +82,31 @@ pub(crate) fn read_env_variables() -> Result<StarknetCliConfig, ReadStarknetConf
         cashier_private_key: Felt::from_str(&cashier_private_key)
             .map_err(ReadStarknetConfigError::CashierPrivateKey)?,
         rpc_node_url: Url::from_str(&rpc_node_url)?,
-        substreams_url: Uri::from_str(&substreams_url)?,
+        indexer,
+        indexer_confirmations: read_optional_env_variable(STARKNET_INDEXER_CONFIRMATIONS_ENV_VAR)?,
+        withdraw_batch_max_wait: read_optional_env_variable(
+            STARKNET_WITHDRAW_BATCH_MAX_WAIT_ENV_VAR,
+        )?,
+        withdraw_batch_max_orders: read_optional_env_variable(
+            STARKNET_WITHDRAW_BATCH_MAX_ORDERS_ENV_VAR,
+        )?,
+        withdraw_batch_max_l2_gas: read_optional_env_variable(
+            STARKNET_WITHDRAW_BATCH_MAX_L2_GAS_ENV_VAR,
+        )?,
     };
 
     Ok(config)
 }
 
+fn read_substreams_backend() -> Result<IndexerBackend, ReadStarknetConfigError> {
+    let substreams_url = std::env::var(STARKNET_SUBSTREAMS_URL_ENV_VAR)
+        .map_err(|e| ReadStarknetConfigError::Env(STARKNET_SUBSTREAMS_URL_ENV_VAR, e))?;
+
+    Ok(IndexerBackend::Substreams {
+        url: Uri::from_str(&substreams_url)?,
+    })
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct StarknetCliConfig {
     /// The chain we are using as backend
@@ -68,8 +117,29 @@ pub struct StarknetCliConfig {
     pub cashier_private_key: starknet_types_core::felt::Felt,
     /// The url of the starknet rpc node we want to use
     pub rpc_node_url: Url,
-    #[serde(with = "uri_serde")]
-    pub substreams_url: Uri,
+    /// How the payments to and from the cashier account are indexed
+    pub indexer: IndexerBackend,
+    /// How many blocks, counting the one including it, confirm a payment
+    pub indexer_confirmations: Option<u64>,
+    /// In seconds, how long a withdraw order may wait for others to be paid in the same transaction
+    pub withdraw_batch_max_wait: Option<u64>,
+    /// Maximum number of withdraw orders paid in a single transaction
+    pub withdraw_batch_max_orders: Option<u32>,
+    /// Maximum amount of L2 gas a single withdraw transaction may consume
+    pub withdraw_batch_max_l2_gas: Option<u64>,
+}
+
+/// The source of the invoice contract events
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum IndexerBackend {
+    /// Stream them from a substreams endpoint
+    Substreams {
+        #[serde(with = "uri_serde")]
+        url: Uri,
+    },
+    /// Poll them from the starknet rpc node
+    Rpc,
 }
 
 mod uri_serde {
@@ -1,27 +1,54 @@
 use futures::{FutureExt, select};
-use http::Uri;
 use sqlx::PgPool;
+use starknet::providers::{JsonRpcClient, jsonrpc::HttpTransport};
 use starknet_types::ChainId;
 use starknet_types_core::felt::Felt;
 use tracing::error;
 
+use crate::{env_config::IndexerBackend, rpc_indexer};
+
+/// Payments are settled as soon as they are included in a block
+pub const DEFAULT_CONFIRMATIONS: u64 = 1;
+
 pub async fn init_indexer_task(
     pg_pool: PgPool,
-    substreams_endpoint: Uri,
+    backend: IndexerBackend,
+    provider: JsonRpcClient<HttpTransport>,
     chain_id: ChainId,
     start_block: i64,
     cashier_account_address: Felt,
+    confirmations: u64,
 ) {
     tokio::spawn(async move {
+        let indexer = async move {
+            match backend {
+                IndexerBackend::Substreams { url } => {
+                    substreams_sink::launch(
+                        pg_pool,
+                        url,
+                        chain_id,
+                        start_block,
+                        cashier_account_address,
+                        confirmations,
+                    )
+                    .await
+                }
+                IndexerBackend::Rpc => {
+                    rpc_indexer::launch(
+                        pg_pool,
+                        provider,
+                        chain_id,
+                        start_block,
+                        cashier_account_address,
+                        confirmations,
+                    )
+                    .await
+                }
+            }
+        };
+
         select! {
-          indexer_res =
-          substreams_sink::launch(
-              pg_pool,
-              substreams_endpoint,
-              chain_id,
-              start_block,
-              cashier_account_address,
-          ).fuse() => match indexer_res {
+          indexer_res = indexer.fuse() => match indexer_res {
                 Ok(()) => {
                     error!(name: "indexer-task-error", name = "indexer-task-error", error = "returned");
                 },
@@ -1,12 +1,13 @@
 #[cfg(feature = "mock")]
 mod mock_impl {
-    use crate::{Depositer, StarknetLiquiditySource, Withdrawer};
+    use crate::{Depositer, Reserves, StarknetLiquiditySource, Withdrawer};
 
     impl StarknetLiquiditySource {
         pub fn new() -> Self {
             StarknetLiquiditySource {
                 depositer: Depositer,
                 withdrawer: Withdrawer,
+                reserves: Reserves,
             }
         }
     }
@@ -20,7 +21,7 @@ mod mock_impl {
 
 #[cfg(not(feature = "mock"))]
 mod not_mock_impl {
-    use std::sync::Arc;
+    use std::{sync::Arc, time::Duration};
 
     use sqlx::PgPool;
     use starknet::{
@@ -31,8 +32,8 @@ mod not_mock_impl {
     use starknet_types::constants::ON_CHAIN_CONSTANTS;
 
     use crate::{
-        Depositer, Error, StarknetLiquiditySource, Withdrawer, env_config::read_env_variables,
-        indexer,
+        BatchPolicy, Depositer, Error, Reserves, StarknetLiquiditySource, Withdrawer,
+        env_config::read_env_variables, indexer,
     };
 
     impl StarknetLiquiditySource {
@@ -43,8 +44,8 @@ mod not_mock_impl {
             let provider = JsonRpcClient::new(HttpTransport::new(config.rpc_node_url));
 
             // Create signer
-            let signer =
-                LocalWallet::from(SigningKey::from_secret_scalar(config.cashier_private_key));
+            let signing_key = SigningKey::from_secret_scalar(config.cashier_private_key);
+            let signer = LocalWallet::from(signing_key.clone());
 
             let account = Arc::new(SingleOwnerAccount::new(
                 provider.clone(),
@@ -57,25 +58,50 @@ mod not_mock_impl {
             let cloned_chain_id = config.chain_id.clone();
             let cloned_cashier_account_address = config.cashier_account_address;
             let cloned_pg_pool = pg_pool.clone();
+            let cloned_provider = provider.clone();
             let _handle = tokio::spawn(async move {
                 indexer::init_indexer_task(
                     cloned_pg_pool,
-                    config.substreams_url,
+                    config.indexer,
+                    cloned_provider,
                     cloned_chain_id,
                     config.indexer_start_block,
                     cloned_cashier_account_address,
+                    config
+                        .indexer_confirmations
+                        .unwrap_or(indexer::DEFAULT_CONFIRMATIONS),
                 )
                 .await
             });
 
             let on_chain_constants = ON_CHAIN_CONSTANTS.get(config.chain_id.as_str()).unwrap();
 
+            let default_batch_policy = BatchPolicy::default();
+            let batch_policy = BatchPolicy {
+                max_wait: config
+                    .withdraw_batch_max_wait
+                    .map(Duration::from_secs)
+                    .unwrap_or(default_batch_policy.max_wait),
+                max_orders: config
+                    .withdraw_batch_max_orders
+                    .unwrap_or(default_batch_policy.max_orders),
+                max_l2_gas: config.withdraw_batch_max_l2_gas,
+            };
+
             Ok(StarknetLiquiditySource {
                 depositer: Depositer::new(config.chain_id.clone(), config.cashier_account_address),
+                reserves: Reserves::new(
+                    config.chain_id.clone(),
+                    provider,
+                    config.cashier_account_address,
+                    signing_key,
+                ),
                 withdrawer: Withdrawer::new(
                     config.chain_id,
                     account,
                     on_chain_constants.invoice_payment_contract_address,
+                    pg_pool,
+                    batch_policy,
                 ),
             })
         }
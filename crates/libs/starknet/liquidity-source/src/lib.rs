@@ -4,13 +4,19 @@ mod env_config;
 #[cfg(not(feature = "mock"))]
 mod indexer;
 mod init;
+mod reserves;
+#[cfg(not(feature = "mock"))]
+mod rpc_indexer;
 mod withdraw;
 
 use std::fmt::{LowerHex, UpperHex};
 
 pub use deposit::{Depositer, Error as DepositError};
+pub use reserves::{Error as ReservesError, Reserves};
 use starknet_types::{CairoShortStringToFeltError, Unit};
 use starknet_types_core::{felt::Felt, hash::Poseidon};
+#[cfg(not(feature = "mock"))]
+pub use withdraw::BatchPolicy;
 pub use withdraw::{
     Error as WithdrawalError, MeltPaymentRequest, NewMeltPaymentRequestError, Withdrawer,
 };
@@ -48,11 +54,13 @@ impl UpperHex for StarknetInvoiceId {
 pub struct StarknetLiquiditySource {
     pub depositer: Depositer,
     pub withdrawer: Withdrawer,
+    pub reserves: Reserves,
 }
 
 impl liquidity_source::LiquiditySource for StarknetLiquiditySource {
     type Depositer = Depositer;
     type Withdrawer = Withdrawer;
+    type Reserves = Reserves;
     type InvoiceId = StarknetInvoiceId;
     type Unit = Unit;
 
@@ -64,6 +72,10 @@ impl liquidity_source::LiquiditySource for StarknetLiquiditySource {
         self.withdrawer.clone()
     }
 
+    fn reserves(&self) -> Reserves {
+        self.reserves.clone()
+    }
+
     fn compute_invoice_id(&self, quote_id: uuid::Uuid, expiry: u64) -> Self::InvoiceId {
         let quote_id_hash =
             Felt::from_bytes_be(bitcoin_hashes::Sha256::hash(quote_id.as_bytes()).as_byte_array());
@@ -0,0 +1,30 @@
+use liquidity_source::ReservesInterface;
+use primitive_types::U256;
+use starknet_types::{Asset, Unit};
+
+#[derive(Debug, thiserror::Error)]
+#[error("mock liquidity source error")]
+pub struct Error;
+
+#[derive(Debug, Clone)]
+pub struct Reserves;
+
+#[async_trait::async_trait]
+impl ReservesInterface for Reserves {
+    type Error = Error;
+    type Unit = Unit;
+
+    fn reserves_address(&self) -> String {
+        "0x0".to_string()
+    }
+
+    async fn get_balance(&self, _asset: Asset) -> Result<U256, Error> {
+        // Nothing is held on-chain
+        Ok(U256::zero())
+    }
+
+    fn sign_attestation(&self, _hash: [u8; 32]) -> Result<Vec<u8>, Error> {
+        // There is no account to sign for
+        Ok(vec![])
+    }
+}
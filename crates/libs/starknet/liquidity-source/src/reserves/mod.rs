@@ -0,0 +1,112 @@
+#[cfg(feature = "mock")]
+mod mock;
+
+#[cfg(feature = "mock")]
+pub use mock::*;
+#[cfg(not(feature = "mock"))]
+pub use not_mock::*;
+
+#[cfg(not(feature = "mock"))]
+mod not_mock {
+    use liquidity_source::ReservesInterface;
+    use primitive_types::U256;
+    use starknet::{
+        core::{
+            crypto::EcdsaSignError,
+            types::{BlockId, BlockTag, FunctionCall},
+        },
+        providers::{JsonRpcClient, Provider, ProviderError, jsonrpc::HttpTransport},
+        signers::SigningKey,
+    };
+    use starknet_types::{Asset, ChainId, StarknetU256, Unit, constants::ON_CHAIN_CONSTANTS};
+    use starknet_types_core::felt::Felt;
+
+    const BALANCE_OF_SELECTOR: Felt = Felt::from_hex_unchecked(
+        "0x035a73cd311a05d46deda634c5ee045db92f811b4e74bca4437fcb5302b7af33",
+    );
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum Error {
+        #[error("asset {0} not found in on-chain constants")]
+        AssetNotFound(Asset),
+        #[error("failed to call the {0} contract: {1}")]
+        BalanceOf(Asset, #[source] ProviderError),
+        #[error("unexpected balance_of return value for {0}: {1:?}")]
+        InvalidBalance(Asset, Vec<Felt>),
+        #[error("failed to sign the attestation: {0}")]
+        Sign(#[from] EcdsaSignError),
+    }
+
+    /// Reads the balances of the cashier account, and signs on its behalf
+    #[derive(Debug, Clone)]
+    pub struct Reserves {
+        chain_id: ChainId,
+        provider: JsonRpcClient<HttpTransport>,
+        cashier_account_address: Felt,
+        cashier_signing_key: SigningKey,
+    }
+
+    impl Reserves {
+        pub fn new(
+            chain_id: ChainId,
+            provider: JsonRpcClient<HttpTransport>,
+            cashier_account_address: Felt,
+            cashier_signing_key: SigningKey,
+        ) -> Self {
+            Self {
+                chain_id,
+                provider,
+                cashier_account_address,
+                cashier_signing_key,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ReservesInterface for Reserves {
+        type Error = Error;
+        type Unit = Unit;
+
+        fn reserves_address(&self) -> String {
+            self.cashier_account_address.to_hex_string()
+        }
+
+        async fn get_balance(&self, asset: Asset) -> Result<U256, Error> {
+            let on_chain_constants = ON_CHAIN_CONSTANTS.get(self.chain_id.as_str()).unwrap();
+            let token_contract_address = on_chain_constants
+                .assets_contract_address
+                .get_contract_address_for_asset(asset)
+                .ok_or(Error::AssetNotFound(asset))?;
+
+            let balance = self
+                .provider
+                .call(
+                    FunctionCall {
+                        contract_address: token_contract_address,
+                        entry_point_selector: BALANCE_OF_SELECTOR,
+                        calldata: vec![self.cashier_account_address],
+                    },
+                    BlockId::Tag(BlockTag::Latest),
+                )
+                .await
+                .map_err(|e| Error::BalanceOf(asset, e))?;
+
+            match balance.as_slice() {
+                [low, high] => Ok(U256::from(&StarknetU256 {
+                    low: *low,
+                    high: *high,
+                })),
+                _ => Err(Error::InvalidBalance(asset, balance)),
+            }
+        }
+
+        /// The signature is `r` then `s`, each as 32 big-endian bytes
+        ///
+        /// It can be checked against the cashier account, through its `is_valid_signature` entrypoint.
+        fn sign_attestation(&self, hash: [u8; 32]) -> Result<Vec<u8>, Error> {
+            let signature = self.cashier_signing_key.sign(&Felt::from_bytes_be(&hash))?;
+
+            Ok([signature.r.to_bytes_be(), signature.s.to_bytes_be()].concat())
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! Indexer of the invoice contract payments polling a starknet json-rpc node
+//!
+//! A lighter alternative to the substreams indexer, for small operators and local testing.
+//! The `Remittance` events emitted since the last block indexed are fetched with
+//! `starknet_getEvents`, and written by [`substreams_sink::index_block_events`], so that both
+//! indexers produce the same payment events.
+//!
+//! The last block of each indexed range is checkpointed with its hash. When the chain no longer
+//! has this hash at this height, a reorg happened: the blocks indexed after the latest checkpoint
+//! still on the chain are deleted, along with their payment events, and indexed again.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use db_node::starknet_rpc_checkpoint::Checkpoint;
+use sqlx::PgPool;
+use starknet::{
+    core::types::{
+        BlockId, BlockWithTxHashes, EventFilter, MaybePendingBlockWithTxHashes, StarknetError,
+    },
+    providers::{JsonRpcClient, Provider, ProviderError, jsonrpc::HttpTransport},
+};
+use starknet_types::{ChainId, constants::ON_CHAIN_CONSTANTS};
+use starknet_types_core::felt::Felt;
+use substreams_sink::RemittanceEvent;
+use tracing::{error, warn};
+
+// starkli selector Remittance
+const REMITTANCE_EVENT_SELECTOR: Felt =
+    Felt::from_hex_unchecked("0x027a12f554d018764f982295090da45b4ff0734785be0982b62c329b9ac38033");
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Maximum number of blocks indexed at once, when catching up with the chain
+const MAX_BLOCK_RANGE: u64 = 1000;
+const EVENTS_CHUNK_SIZE: u64 = 1000;
+/// How many checkpoints are kept to find where a reorg started
+const KEPT_CHECKPOINTS: u32 = 128;
+
+pub async fn launch(
+    pg_pool: PgPool,
+    provider: JsonRpcClient<HttpTransport>,
+    chain_id: ChainId,
+    initial_block: i64,
+    cashier_account_address: Felt,
+    confirmations: u64,
+) -> Result<()> {
+    let invoice_contract_address = ON_CHAIN_CONSTANTS
+        .get(chain_id.as_str())
+        .ok_or(anyhow!("unsuported chain id"))?
+        .invoice_payment_contract_address;
+
+    let indexer = RpcIndexer {
+        pg_pool,
+        provider,
+        chain_id,
+        initial_block: u64::try_from(initial_block)?,
+        cashier_account_address,
+        invoice_contract_address,
+        confirmations,
+    };
+
+    loop {
+        match indexer.index_next_blocks().await {
+            // Keep going until we reach the tip of the chain
+            Ok(false) => {}
+            Ok(true) => tokio::time::sleep(POLL_INTERVAL).await,
+            Err(err) => {
+                error!(name: "rpc-indexer-error", name = "rpc-indexer-error", error = ?err);
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+struct RpcIndexer {
+    pg_pool: PgPool,
+    provider: JsonRpcClient<HttpTransport>,
+    chain_id: ChainId,
+    initial_block: u64,
+    cashier_account_address: Felt,
+    invoice_contract_address: Felt,
+    confirmations: u64,
+}
+
+/// A block containing payments
+struct BlockToIndex {
+    number: u64,
+    hash: Felt,
+    timestamp: u64,
+    events: Vec<RemittanceEvent>,
+}
+
+impl RpcIndexer {
+    /// Index the blocks following the last one indexed
+    ///
+    /// Returns true once the latest block has been indexed.
+    async fn index_next_blocks(&self) -> Result<bool> {
+        let from_block = match self.undo_reorged_blocks().await? {
+            Some(last_indexed_block) => last_indexed_block + 1,
+            None => self.initial_block,
+        };
+        let latest_block = self.provider.block_number().await?;
+        if from_block > latest_block {
+            return Ok(true);
+        }
+        let to_block = latest_block.min(from_block + MAX_BLOCK_RANGE - 1);
+        let to_block_hash = self.get_block_hash(to_block).await?;
+
+        let blocks = self.get_blocks_with_payments(from_block, to_block).await?;
+
+        // The events must belong to the chain ending with the block we checkpoint
+        if self.get_block_hash(to_block).await? != to_block_hash {
+            warn!(
+                name: "starknet-reorg",
+                name = "starknet-reorg",
+                from_block,
+                to_block,
+                "reorg while indexing"
+            );
+            return Ok(false);
+        }
+
+        let mut tx = self.pg_pool.begin().await?;
+        for block in blocks {
+            substreams_sink::index_block_events(
+                &mut tx,
+                &block.hash.to_hex_string(),
+                block.number,
+                i64::try_from(block.timestamp)?,
+                block.events,
+                &self.chain_id,
+                self.cashier_account_address,
+            )
+            .await?;
+        }
+        substreams_sink::confirm_payments(&mut tx, from_block..=to_block, self.confirmations)
+            .await?;
+        db_node::starknet_rpc_checkpoint::insert(&mut tx, to_block, &to_block_hash.to_hex_string())
+            .await?;
+        db_node::starknet_rpc_checkpoint::prune(&mut tx, KEPT_CHECKPOINTS).await?;
+        tx.commit().await?;
+
+        Ok(to_block == latest_block)
+    }
+
+    /// Delete what was indexed after the latest checkpoint still on the chain
+    ///
+    /// Returns the last block indexed that is still valid, if any.
+    async fn undo_reorged_blocks(&self) -> Result<Option<u64>> {
+        let checkpoints = {
+            let mut conn = self.pg_pool.acquire().await?;
+            db_node::starknet_rpc_checkpoint::get_all(&mut conn).await?
+        };
+        let Some(last_checkpoint) = checkpoints.first() else {
+            return Ok(None);
+        };
+        let Some(last_valid_block) = find_reorg(&checkpoints, |number| async move {
+            Ok(self
+                .get_block(BlockId::Number(number))
+                .await?
+                .map(|block| block.block_hash))
+        })
+        .await?
+        else {
+            return Ok(Some(last_checkpoint.number));
+        };
+
+        warn!(
+            name: "starknet-reorg",
+            name = "starknet-reorg",
+            last_valid_block,
+            last_indexed_block = last_checkpoint.number,
+        );
+        let mut tx = self.pg_pool.begin().await?;
+        substreams_sink::undo_blocks(&mut tx, last_valid_block, self.confirmations).await?;
+        db_node::starknet_rpc_checkpoint::delete_after(&mut tx, last_valid_block).await?;
+        tx.commit().await?;
+
+        Ok(Some(last_valid_block))
+    }
+
+    /// The blocks of this range containing payments, in ascending order
+    async fn get_blocks_with_payments(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<BlockToIndex>> {
+        let filter = EventFilter {
+            from_block: Some(BlockId::Number(from_block)),
+            to_block: Some(BlockId::Number(to_block)),
+            address: Some(self.invoice_contract_address),
+            keys: Some(vec![vec![REMITTANCE_EVENT_SELECTOR]]),
+        };
+
+        // The events are returned in the order they were emitted
+        let mut transactions: Vec<(u64, Felt, Felt)> = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let page = self
+                .provider
+                .get_events(filter.clone(), continuation_token, EVENTS_CHUNK_SIZE)
+                .await?;
+            for event in page.events {
+                let (Some(block_number), Some(block_hash)) = (event.block_number, event.block_hash)
+                else {
+                    return Err(anyhow!(
+                        "event of tx {:#x} is pending",
+                        event.transaction_hash
+                    ));
+                };
+                if transactions.last().map(|(_, _, tx_hash)| *tx_hash)
+                    != Some(event.transaction_hash)
+                {
+                    transactions.push((block_number, block_hash, event.transaction_hash));
+                }
+            }
+            continuation_token = page.continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        let mut blocks: Vec<BlockToIndex> = Vec::new();
+        for (block_number, block_hash, tx_hash) in transactions {
+            let events = self.get_remittance_events(tx_hash).await?;
+            match blocks.last_mut() {
+                Some(block) if block.number == block_number => block.events.extend(events),
+                _ => {
+                    let timestamp = self
+                        .get_block(BlockId::Hash(block_hash))
+                        .await?
+                        .ok_or(anyhow!("block {:#x} not found", block_hash))?
+                        .timestamp;
+                    blocks.push(BlockToIndex {
+                        number: block_number,
+                        hash: block_hash,
+                        timestamp,
+                        events,
+                    });
+                }
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    /// The `Remittance` events emitted by the invoice contract in this transaction
+    ///
+    /// Their index is the one of the event among all those of the transaction,
+    /// as computed by the substreams module.
+    async fn get_remittance_events(&self, tx_hash: Felt) -> Result<Vec<RemittanceEvent>> {
+        let receipt = self
+            .provider
+            .get_transaction_receipt(tx_hash)
+            .await?
+            .receipt;
+
+        receipt
+            .events()
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| {
+                event.from_address == self.invoice_contract_address
+                    && event.keys.first() == Some(&REMITTANCE_EVENT_SELECTOR)
+            })
+            .map(|(index, event)| {
+                let [_, asset, payer, payee] = event.keys[..] else {
+                    return Err(anyhow!("unexpected Remittance keys in tx {:#x}", tx_hash));
+                };
+                let [invoice_id, amount_low, amount_high] = event.data[..] else {
+                    return Err(anyhow!("unexpected Remittance data in tx {:#x}", tx_hash));
+                };
+
+                Ok(RemittanceEvent {
+                    tx_hash: tx_hash.to_bytes_be().to_vec(),
+                    event_index: u64::try_from(index)?,
+                    asset: asset.to_bytes_be().to_vec(),
+                    payer: payer.to_bytes_be().to_vec(),
+                    payee: payee.to_bytes_be().to_vec(),
+                    invoice_id: invoice_id.to_bytes_be().to_vec(),
+                    amount_low: amount_low.to_bytes_be().to_vec(),
+                    amount_high: amount_high.to_bytes_be().to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    async fn get_block_hash(&self, block_number: u64) -> Result<Felt> {
+        Ok(self
+            .get_block(BlockId::Number(block_number))
+            .await?
+            .ok_or(anyhow!("block {} not found", block_number))?
+            .block_hash)
+    }
+
+    /// Returns None if the chain has no such block, or only a pending one
+    async fn get_block(&self, block_id: BlockId) -> Result<Option<BlockWithTxHashes>> {
+        match self.provider.get_block_with_tx_hashes(block_id).await {
+            Ok(MaybePendingBlockWithTxHashes::Block(block)) => Ok(Some(block)),
+            Ok(MaybePendingBlockWithTxHashes::PendingBlock(_)) => Ok(None),
+            Err(ProviderError::StarknetError(StarknetError::BlockNotFound)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Compare the checkpoints, the latest first, with the hashes the chain has at their heights
+///
+/// Returns the latest block checkpointed still on the chain, if the blocks indexed after it
+/// were reorged. `get_block_hash` returns None when the chain has no such block.
+async fn find_reorg<F, Fut>(
+    checkpoints: &[Checkpoint],
+    mut get_block_hash: F,
+) -> Result<Option<u64>>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<Option<Felt>>>,
+{
+    for (i, checkpoint) in checkpoints.iter().enumerate() {
+        let on_chain_hash = get_block_hash(checkpoint.number)
+            .await?
+            .map(|hash| hash.to_hex_string());
+        if on_chain_hash.as_ref() == Some(&checkpoint.hash) {
+            return Ok((i != 0).then_some(checkpoint.number));
+        }
+    }
+
+    Err(anyhow!(
+        "none of the {} blocks checkpointed is still on the chain",
+        checkpoints.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use futures::executor::block_on;
+
+    use super::*;
+
+    fn checkpoints(numbers: &[u64]) -> Vec<Checkpoint> {
+        numbers
+            .iter()
+            .map(|number| Checkpoint {
+                number: *number,
+                hash: Felt::from(*number).to_hex_string(),
+            })
+            .collect()
+    }
+
+    /// Compare the checkpoints with a chain having these hashes, and the heights looked up
+    fn find_reorg_on(
+        checkpoints: &[Checkpoint],
+        chain: HashMap<u64, Felt>,
+    ) -> (Result<Option<u64>>, Vec<u64>) {
+        let mut looked_up = Vec::new();
+        let reorg = block_on(find_reorg(checkpoints, |number| {
+            looked_up.push(number);
+            let hash = chain.get(&number).copied();
+            async move { Ok(hash) }
+        }));
+
+        (reorg, looked_up)
+    }
+
+    #[test]
+    fn latest_checkpoint_still_on_the_chain_is_not_undone() {
+        let chain = HashMap::from([
+            (10, Felt::from(10)),
+            (20, Felt::from(20)),
+            (30, Felt::from(30)),
+        ]);
+
+        let (reorg, looked_up) = find_reorg_on(&checkpoints(&[30, 20, 10]), chain);
+
+        assert_eq!(reorg.unwrap(), None);
+        assert_eq!(looked_up, [30]);
+    }
+
+    #[test]
+    fn checkpoint_hash_mismatch_triggers_an_undo() {
+        let chain = HashMap::from([
+            (10, Felt::from(10)),
+            (20, Felt::from(20)),
+            (30, Felt::from(31)),
+        ]);
+
+        let (reorg, looked_up) = find_reorg_on(&checkpoints(&[30, 20, 10]), chain);
+
+        assert_eq!(reorg.unwrap(), Some(20));
+        assert_eq!(looked_up, [30, 20]);
+    }
+
+    #[test]
+    fn checkpoints_above_the_chain_tip_are_undone() {
+        let chain = HashMap::from([(10, Felt::from(10))]);
+
+        let (reorg, _) = find_reorg_on(&checkpoints(&[30, 20, 10]), chain);
+
+        assert_eq!(reorg.unwrap(), Some(10));
+    }
+
+    #[test]
+    fn reorg_deeper_than_the_checkpoints_is_an_error() {
+        let chain = HashMap::from([(10, Felt::from(11)), (20, Felt::from(21))]);
+
+        let (reorg, looked_up) = find_reorg_on(&checkpoints(&[20, 10]), chain);
+
+        assert!(reorg.is_err());
+        assert_eq!(looked_up, [20, 10]);
+    }
+}
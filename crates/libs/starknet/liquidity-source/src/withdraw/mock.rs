@@ -2,6 +2,7 @@ use liquidity_source::WithdrawInterface;
 use num_traits::CheckedAdd;
 use nuts::traits::Unit as UnitT;
 use nuts::{Amount, nut05::MeltQuoteState};
+use sqlx::PgConnection;
 use starknet_types::{Asset, AssetToUnitConversionError, Unit, is_valid_starknet_address};
 use starknet_types_core::felt::Felt;
 use uuid::Uuid;
@@ -45,11 +46,10 @@ impl WithdrawInterface for Withdrawer {
         Ok(pr)
     }
 
-    fn compute_total_amount_expected(
+    fn compute_amount_expected(
         &self,
-        request: Self::Request,
+        request: &Self::Request,
         unit: Unit,
-        fee: Amount,
     ) -> Result<nuts::Amount, Self::Error> {
         if !unit.is_asset_supported(request.asset) {
             return Err(Error::InvalidAssetForUnit(request.asset, unit));
@@ -59,19 +59,27 @@ impl WithdrawInterface for Withdrawer {
             .asset
             .convert_to_amount_of_unit(request.amount.clone().into(), unit)?;
 
-        if fee == Amount::ZERO {
-            if rem.is_zero() {
-                Ok(amount)
-            } else {
-                amount.checked_add(&Amount::ONE).ok_or(Error::Overflow)
-            }
+        if rem.is_zero() {
+            Ok(amount)
         } else {
-            amount.checked_add(&fee).ok_or(Error::Overflow)
+            amount.checked_add(&Amount::ONE).ok_or(Error::Overflow)
         }
     }
 
+    async fn estimate_fee(
+        &self,
+        _quote_id: Uuid,
+        _request: &Self::Request,
+        _expiry: u64,
+        _unit: Unit,
+    ) -> Result<Amount, Error> {
+        // Nothing is sent on-chain
+        Ok(Amount::ZERO)
+    }
+
     async fn proceed_to_payment(
         &mut self,
+        _conn: &mut PgConnection,
         _quote_id: Uuid,
         _melt_payment_request: MeltPaymentRequest,
         _expiry: u64,
@@ -49,29 +49,36 @@ impl MeltPaymentRequest {
 mod not_mock {
     use num_traits::CheckedAdd;
     use nuts::traits::Unit as UnitT;
-    use nuts::{Amount, nut05::MeltQuoteState};
+    use nuts::{Amount, nut05::MeltQuoteState, unix_time};
     use starknet_types::{
-        Asset, AssetToUnitConversionError, ChainId, PayInvoiceCallData, Unit,
+        Asset, AssetToUnitConversionError, ChainId, PayInvoiceCallData, Unit, compute_invoice_id,
         constants::ON_CHAIN_CONSTANTS,
     };
 
+    use db_node::withdraw_order::{PendingWithdrawOrder, WithdrawOrderState};
     use liquidity_source::WithdrawInterface;
     use starknet_types::is_valid_starknet_address;
     use uuid::Uuid;
 
     use std::{sync::Arc, time::Duration};
 
+    use primitive_types::U256;
+    use sqlx::{PgConnection, PgPool, postgres::PgListener};
     use starknet::{
         accounts::{Account, ConnectedAccount, SingleOwnerAccount},
-        core::types::{ExecutionResult, Felt, TransactionStatus},
+        core::types::{
+            BlockId, BlockTag, ExecutionResult, FeeEstimate, FeePayment, Felt, PriceUnit,
+            StarknetError, TransactionReceipt, TransactionStatus,
+        },
         providers::{JsonRpcClient, Provider, ProviderError, jsonrpc::HttpTransport},
         signers::LocalWallet,
     };
     use starknet_types::transactions::{
-        sign_and_send_payment_transactions, sign_and_send_single_payment_transactions,
+        estimate_payment_transactions_fee, generate_single_payment_transaction_calls,
+        sign_and_send_payment_transactions,
     };
-    use tokio::{sync::mpsc, time::sleep};
-    use tracing::{error, info};
+    use tokio::time::sleep;
+    use tracing::{error, info, warn};
 
     use crate::StarknetInvoiceId;
 
@@ -79,6 +86,17 @@ mod not_mock {
 
     type OurAccount = SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>;
 
+    /// Time between two checks for the orders due for a retry, or for the lock on the orders to be
+    /// released
+    const WITHDRAW_ORDERS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+    /// Number of times a transaction unknown to the node is looked up before giving up on it
+    const TX_NOT_FOUND_RETRIES: u32 = 20;
+    /// Number of failed attempts after which an order is given up, and its quote failed
+    const MAX_WITHDRAW_ATTEMPTS: u32 = 8;
+    /// Delay before the first retry of a failed order, doubled with each attempt
+    const WITHDRAW_RETRY_BASE_DELAY: Duration = Duration::from_secs(15);
+    const WITHDRAW_RETRY_MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+
     #[derive(Debug, thiserror::Error)]
     pub enum Error {
         #[error("invalid payment request json string: {0}")]
@@ -87,16 +105,28 @@ mod not_mock {
         InvalidStarknetAddress(Felt),
         #[error("failed to send transaction: {0}")]
         Transaction(#[from] starknet_types::transactions::Error<OurAccount>),
-        #[error("withdraw order channel has been closed")]
-        ChannelClosed,
         #[error("failed to emit confirmation for tx {0}")]
         TransactionConfirmation(Felt),
         #[error("failed to get transaction status from node: {0}")]
         GetTransactionStatus(ProviderError),
         #[error("failed to get nonce from node: {0}")]
         GetNonce(ProviderError),
-        #[error("failed to send withdraw order through channel: {0}")]
-        SendWithdrawOrder(#[from] mpsc::error::SendError<PayInvoiceCallData>),
+        #[error("failed to serialize withdraw order: {0}")]
+        SerializeWithdrawOrder(#[source] serde_json::Error),
+        #[error("invalid withdraw order for invoice {0:#x}: {1}")]
+        InvalidWithdrawOrder(Felt, #[source] serde_json::Error),
+        #[error("failed to register withdraw order: {0}")]
+        RegisterWithdrawOrder(#[source] sqlx::Error),
+        #[error("failed to read withdraw orders: {0}")]
+        GetWithdrawOrders(#[source] db_node::Error),
+        #[error("failed to update withdraw orders: {0}")]
+        UpdateWithdrawOrders(#[source] sqlx::Error),
+        #[error("failed to fail the quote {0}: {1}")]
+        FailMeltQuote(Uuid, #[source] db_node::Error),
+        #[error("invalid transaction hash `{0}`")]
+        InvalidTxHash(String),
+        #[error("transaction {0} is unknown to the node")]
+        TransactionNotFound(Felt),
         #[error("asset {0} not found in on-chain constants")]
         AssetNotFound(Asset),
         #[error("failed to acquire a conneciton from the pool: {0}")]
@@ -109,12 +139,85 @@ mod not_mock {
         Overflow,
         #[error("unsupported asset `{0}` for unit `{1}`")]
         InvalidAssetForUnit(Asset, Unit),
+        #[error("failed to estimate transaction fee: {0}")]
+        EstimateFee(#[source] starknet_types::transactions::Error<OurAccount>),
+        #[error("failed to register the fee paid for tx {0}: {1}")]
+        RegisterTransferFee(Felt, #[source] sqlx::Error),
+    }
+
+    /// How the orders are grouped into transactions
+    ///
+    /// Waiting for more orders lowers the fee paid per order, at the cost of the payout latency.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct BatchPolicy {
+        /// How long an order may wait for others to be paid along with it
+        pub max_wait: Duration,
+        /// Maximum number of orders paid in a single transaction, a full batch is sent right away
+        pub max_orders: u32,
+        /// Maximum amount of L2 gas a single transaction may consume
+        pub max_l2_gas: Option<u64>,
+    }
+
+    impl Default for BatchPolicy {
+        fn default() -> Self {
+            Self {
+                max_wait: Duration::ZERO,
+                max_orders: 10,
+                max_l2_gas: None,
+            }
+        }
+    }
+
+    impl BatchPolicy {
+        /// Select the orders to pay in the next transaction, among the pending ones, ordered by
+        /// expiry
+        ///
+        /// An order that failed before is paid alone, so that it cannot make the others fail again.
+        /// The others are paid together once there are enough of them, once the oldest one waited
+        /// long enough, or as soon as one of them could expire before the end of the wait.
+        /// Returns an empty list if it is worth waiting for more orders.
+        fn select_orders(
+            &self,
+            mut pendings: Vec<PendingWithdrawOrder>,
+            now: u64,
+        ) -> Vec<PendingWithdrawOrder> {
+            match pendings.first() {
+                None => return pendings,
+                Some(order) if order.attempts != 0 => {
+                    pendings.truncate(1);
+                    return pendings;
+                }
+                Some(_) => {}
+            }
+            pendings.retain(|order| order.attempts == 0);
+            pendings.truncate(self.max_orders as usize);
+
+            let max_wait = self.max_wait.as_secs();
+            let is_full = pendings.len() >= self.max_orders as usize;
+            let waited_enough = pendings
+                .iter()
+                .any(|order| order.created_at.saturating_add(max_wait) <= now);
+            // The orders are only checked once per poll interval
+            let expires_soon = pendings.iter().any(|order| {
+                order.expiry
+                    <= now
+                        .saturating_add(max_wait)
+                        .saturating_add(WITHDRAW_ORDERS_POLL_INTERVAL.as_secs())
+            });
+
+            if is_full || waited_enough || expires_soon {
+                pendings
+            } else {
+                Vec::new()
+            }
+        }
     }
 
     #[derive(Debug, Clone)]
     pub struct Withdrawer {
         chain_id: ChainId,
-        withdraw_order_sender: mpsc::UnboundedSender<PayInvoiceCallData>,
+        account: Arc<OurAccount>,
+        invoice_payment_contract_address: Felt,
     }
 
     impl Withdrawer {
@@ -122,12 +225,18 @@ mod not_mock {
             chain_id: ChainId,
             account: Arc<OurAccount>,
             invoice_payment_contract_address: Felt,
+            pg_pool: PgPool,
+            batch_policy: BatchPolicy,
         ) -> Self {
-            let (tx, rx) = mpsc::unbounded_channel();
-
+            let cloned_account = account.clone();
             let _join_handle = tokio::spawn(async move {
-                let res =
-                    process_withdraw_requests(account, rx, invoice_payment_contract_address).await;
+                let res = process_withdraw_requests(
+                    cloned_account,
+                    invoice_payment_contract_address,
+                    pg_pool,
+                    batch_policy,
+                )
+                .await;
 
                 match res {
                     Ok(_) => error!(name: "cashier-worker", error = "returned"),
@@ -137,9 +246,46 @@ mod not_mock {
 
             Self {
                 chain_id,
-                withdraw_order_sender: tx,
+                account,
+                invoice_payment_contract_address,
             }
         }
+
+        fn asset_contract_address(&self, asset: Asset) -> Result<Felt, Error> {
+            let on_chain_constants = ON_CHAIN_CONSTANTS.get(self.chain_id.as_str()).unwrap();
+            on_chain_constants
+                .assets_contract_address
+                .get_contract_address_for_asset(asset)
+                .ok_or(Error::AssetNotFound(asset))
+        }
+    }
+
+    fn compute_quote_id_hash(quote_id: Uuid) -> Felt {
+        Felt::from_bytes_be(bitcoin_hashes::Sha256::hash(quote_id.as_bytes()).as_byte_array())
+    }
+
+    /// Convert an amount of gas, paid in `price_unit`, into an amount of `unit`
+    ///
+    /// Rounded up, so that we never underestimate what we spent.
+    fn convert_gas_to_amount_of_unit(
+        gas_amount: U256,
+        price_unit: PriceUnit,
+        unit: Unit,
+    ) -> Result<Amount, Error> {
+        let gas_asset = match price_unit {
+            PriceUnit::Fri => Asset::Strk,
+            PriceUnit::Wei => Asset::Eth,
+        };
+        if !unit.is_asset_supported(gas_asset) {
+            return Err(Error::InvalidAssetForUnit(gas_asset, unit));
+        }
+
+        let (amount, rem) = gas_asset.convert_to_amount_of_unit(gas_amount, unit)?;
+        if rem.is_zero() {
+            Ok(amount)
+        } else {
+            amount.checked_add(&Amount::ONE).ok_or(Error::Overflow)
+        }
     }
 
     #[async_trait::async_trait]
@@ -163,11 +309,10 @@ mod not_mock {
             Ok(pr)
         }
 
-        fn compute_total_amount_expected(
+        fn compute_amount_expected(
             &self,
-            request: Self::Request,
+            request: &Self::Request,
             unit: Unit,
-            fee: Amount,
         ) -> Result<nuts::Amount, Self::Error> {
             if !unit.is_asset_supported(request.asset) {
                 return Err(Error::InvalidAssetForUnit(request.asset, unit));
@@ -177,145 +322,751 @@ mod not_mock {
                 .asset
                 .convert_to_amount_of_unit(request.amount.clone().into(), unit)?;
 
-            if fee == Amount::ZERO {
-                if rem.is_zero() {
-                    Ok(amount)
-                } else {
-                    amount.checked_add(&Amount::ONE).ok_or(Error::Overflow)
-                }
+            if rem.is_zero() {
+                Ok(amount)
             } else {
-                amount.checked_add(&fee).ok_or(Error::Overflow)
+                amount.checked_add(&Amount::ONE).ok_or(Error::Overflow)
             }
         }
 
+        async fn estimate_fee(
+            &self,
+            quote_id: Uuid,
+            melt_payment_request: &MeltPaymentRequest,
+            expiry: u64,
+            unit: Unit,
+        ) -> Result<Amount, Error> {
+            let calls = generate_single_payment_transaction_calls(
+                self.invoice_payment_contract_address,
+                compute_quote_id_hash(quote_id),
+                expiry.into(),
+                self.asset_contract_address(melt_payment_request.asset)?,
+                &melt_payment_request.amount,
+                melt_payment_request.payee,
+            );
+
+            let fee_estimate = self
+                .account
+                .execute_v3(calls.to_vec())
+                .estimate_fee()
+                .await
+                .map_err(|e| Error::EstimateFee(e.into()))?;
+
+            convert_gas_to_amount_of_unit(
+                U256::from(fee_estimate.overall_fee),
+                fee_estimate.unit,
+                unit,
+            )
+        }
+
         async fn proceed_to_payment(
             &mut self,
+            conn: &mut PgConnection,
             quote_id: Uuid,
             melt_payment_request: MeltPaymentRequest,
             expiry: u64,
         ) -> Result<MeltQuoteState, Error> {
-            let quote_id_hash = Felt::from_bytes_be(
-                bitcoin_hashes::Sha256::hash(quote_id.as_bytes()).as_byte_array(),
-            );
-
-            let on_chain_constants = ON_CHAIN_CONSTANTS.get(self.chain_id.as_str()).unwrap();
-            let asset_contract_address = on_chain_constants
-                .assets_contract_address
-                .get_contract_address_for_asset(melt_payment_request.asset)
-                .ok_or(Error::AssetNotFound(melt_payment_request.asset))?;
+            let quote_id_hash = compute_quote_id_hash(quote_id);
+            let asset_contract_address = self.asset_contract_address(melt_payment_request.asset)?;
 
-            self.withdraw_order_sender.send(PayInvoiceCallData::new(
+            let order = PayInvoiceCallData::new(
                 quote_id_hash,
                 expiry.into(),
                 melt_payment_request.amount,
                 asset_contract_address,
                 melt_payment_request.payee,
-            ))?;
+            );
+            let payload = serde_json::to_string(&order).map_err(Error::SerializeWithdrawOrder)?;
+            let invoice_id = compute_invoice_id(quote_id_hash, expiry);
+
+            let is_new = db_node::withdraw_order::insert(
+                conn,
+                &invoice_id.to_bytes_be(),
+                quote_id,
+                &payload,
+            )
+            .await
+            .map_err(Error::RegisterWithdrawOrder)?;
+            if is_new {
+                // Delivered once the melt commits
+                db_node::notify::withdraw_order(conn)
+                    .await
+                    .map_err(Error::RegisterWithdrawOrder)?;
+            }
 
             Ok(MeltQuoteState::Pending)
         }
     }
 
+    /// How a transaction ended
+    enum TxOutcome {
+        Succeeded(FeePayment),
+        Reverted(FeePayment),
+        Rejected,
+    }
+
+    /// Wait for the transaction to be included in a block
     async fn wait_for_tx_completion<A: Account + ConnectedAccount + Sync>(
         account: Arc<A>,
         tx_hash: Felt,
-    ) -> Result<(), Error> {
-        loop {
-            match account
-                .provider()
-                .get_transaction_status(tx_hash)
-                .await
-                .map_err(Error::GetTransactionStatus)?
-            {
-                TransactionStatus::Received => {
+    ) -> Result<TxOutcome, Error> {
+        let mut not_found_retries = 0;
+        let succeeded = loop {
+            match account.provider().get_transaction_status(tx_hash).await {
+                Ok(TransactionStatus::Received) => {
                     sleep(Duration::from_millis(500)).await;
                     continue;
                 }
-                TransactionStatus::AcceptedOnL2(ExecutionResult::Succeeded) => {
+                Ok(TransactionStatus::AcceptedOnL2(ExecutionResult::Succeeded)) => {
                     info!(name: "withdraw-tx-result", name =  "withdraw-tx-result", tx_hash = tx_hash.to_hex_string(), status = "succeeded");
-                    break;
+                    break true;
                 }
-                TransactionStatus::AcceptedOnL2(ExecutionResult::Reverted { reason }) => {
+                Ok(TransactionStatus::AcceptedOnL2(ExecutionResult::Reverted { reason })) => {
                     error!(name: "withdraw-tx-result", name =  "withdraw-tx-result", tx_hash = tx_hash.to_hex_string(), status = "reverted", reason = reason);
-                    break;
+                    break false;
                 }
-                TransactionStatus::Rejected => {
+                Ok(TransactionStatus::Rejected) => {
                     error!(name: "withdraw-tx-result", name = "withdraw-tx-result", tx_hash = tx_hash.to_hex_string(), status = "rejected");
-                    break;
+                    return Ok(TxOutcome::Rejected);
+                }
+                Ok(TransactionStatus::AcceptedOnL1(_)) => unreachable!(),
+                // The node may not know about a transaction it was just sent yet
+                Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound))
+                    if not_found_retries < TX_NOT_FOUND_RETRIES =>
+                {
+                    not_found_retries += 1;
+                    sleep(Duration::from_millis(500)).await;
+                    continue;
+                }
+                Err(ProviderError::StarknetError(StarknetError::TransactionHashNotFound)) => {
+                    return Err(Error::TransactionNotFound(tx_hash));
                 }
-                TransactionStatus::AcceptedOnL1(_) => unreachable!(),
+                Err(e) => return Err(Error::GetTransactionStatus(e)),
             }
-        }
+        };
         loop {
-            if let starknet::core::types::ReceiptBlock::Block {
-                block_hash: _,
-                block_number: _,
-            } = account
+            let receipt = account
                 .provider()
                 .get_transaction_receipt(tx_hash)
                 .await
-                .map_err(Error::GetTransactionStatus)?
-                .block
+                .map_err(Error::GetTransactionStatus)?;
+            if let starknet::core::types::ReceiptBlock::Block {
+                block_hash: _,
+                block_number: _,
+            } = receipt.block
             {
-                break;
+                let actual_fee = match receipt.receipt {
+                    TransactionReceipt::Invoke(r) => r.actual_fee,
+                    TransactionReceipt::L1Handler(r) => r.actual_fee,
+                    TransactionReceipt::Declare(r) => r.actual_fee,
+                    TransactionReceipt::Deploy(r) => r.actual_fee,
+                    TransactionReceipt::DeployAccount(r) => r.actual_fee,
+                };
+                break Ok(if succeeded {
+                    TxOutcome::Succeeded(actual_fee)
+                } else {
+                    TxOutcome::Reverted(actual_fee)
+                });
             } else {
                 sleep(Duration::from_secs(1)).await;
                 continue;
             }
         }
+    }
+
+    /// Store the fee a transaction cost us
+    async fn register_fee(
+        tx_hash: Felt,
+        actual_fee: FeePayment,
+        pg_pool: &PgPool,
+    ) -> Result<(), Error> {
+        let gas_amount = U256::from_big_endian(&actual_fee.amount.to_bytes_be());
+        let unit = match actual_fee.unit {
+            PriceUnit::Fri => Asset::Strk,
+            PriceUnit::Wei => Asset::Eth,
+        }
+        .find_best_unit();
+        let amount = convert_gas_to_amount_of_unit(gas_amount, actual_fee.unit, unit)?;
+
+        let mut conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+        db_node::melt_transfer_fee::insert(&mut conn, &tx_hash.to_hex_string(), unit, amount)
+            .await
+            .map_err(|e| Error::RegisterTransferFee(tx_hash, e))?;
+
+        Ok(())
+    }
+
+    /// Wait for the transaction completion, store the fee it cost us,
+    /// and update the state of the orders it pays
+    ///
+    /// The orders of a reverted or rejected transaction are retried later, one by one.
+    /// Those of a transaction unknown to the node are sent again right away.
+    /// Returns whether the transaction consumed its nonce.
+    async fn complete_withdraw_orders<A: Account + ConnectedAccount + Sync>(
+        account: Arc<A>,
+        tx_hash: Felt,
+        pg_pool: &PgPool,
+    ) -> Result<bool, Error> {
+        let outcome = match wait_for_tx_completion(account, tx_hash).await {
+            Ok(outcome) => Some(outcome),
+            Err(Error::TransactionNotFound(_)) => {
+                warn!(name: "withdraw-tx-not-found", name = "withdraw-tx-not-found", tx_hash = tx_hash.to_hex_string());
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        let nonce_consumed = match &outcome {
+            Some(TxOutcome::Succeeded(actual_fee)) | Some(TxOutcome::Reverted(actual_fee)) => {
+                register_fee(tx_hash, actual_fee.clone(), pg_pool).await?;
+                true
+            }
+            Some(TxOutcome::Rejected) | None => false,
+        };
+
+        let tx_hash = tx_hash.to_hex_string();
+        let mut conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+        match outcome {
+            Some(TxOutcome::Succeeded(_)) => {
+                db_node::withdraw_order::set_state_by_tx_hash(
+                    &mut conn,
+                    &tx_hash,
+                    WithdrawOrderState::Succeeded,
+                )
+                .await
+            }
+            Some(TxOutcome::Reverted(_)) | Some(TxOutcome::Rejected) => {
+                db_node::withdraw_order::retry_later_by_tx_hash(
+                    &mut conn,
+                    &tx_hash,
+                    WITHDRAW_RETRY_BASE_DELAY,
+                    WITHDRAW_RETRY_MAX_DELAY,
+                )
+                .await
+            }
+            None => {
+                db_node::withdraw_order::set_state_by_tx_hash(
+                    &mut conn,
+                    &tx_hash,
+                    WithdrawOrderState::Pending,
+                )
+                .await
+            }
+        }
+        .map_err(Error::UpdateWithdrawOrders)?;
+
+        Ok(nonce_consumed)
+    }
+
+    /// Fail the orders that reached the maximum number of attempts, along with their quote
+    ///
+    /// The proofs spent to pay the quotes are returned to their owners, except those already
+    /// committed to by a liabilities report, which must be refunded by an operator.
+    async fn fail_exhausted_orders(pg_pool: &PgPool) -> Result<(), Error> {
+        let mut tx = pg_pool.begin().await.map_err(Error::PgPool)?;
+        let quote_ids = db_node::withdraw_order::fail_exhausted(&mut tx, MAX_WITHDRAW_ATTEMPTS)
+            .await
+            .map_err(Error::GetWithdrawOrders)?;
+
+        for quote_id in quote_ids {
+            db_node::melt_quote::set_state(&mut tx, quote_id, MeltQuoteState::Failed)
+                .await
+                .map_err(|e| Error::FailMeltQuote(quote_id, e.into()))?;
+            let returned_inputs = db_node::proof::return_melt_inputs(&mut tx, quote_id)
+                .await
+                .map_err(|e| Error::FailMeltQuote(quote_id, e))?;
+
+            error!(
+                name: "withdraw-order-failed",
+                name = "withdraw-order-failed",
+                %quote_id,
+                attempts = MAX_WITHDRAW_ATTEMPTS,
+                returned_proofs = returned_inputs.returned.len(),
+            );
+            if returned_inputs.kept_amount != Amount::ZERO {
+                error!(
+                    name: "withdraw-refund-required",
+                    name = "withdraw-refund-required",
+                    %quote_id,
+                    amount = u64::from(returned_inputs.kept_amount),
+                );
+            }
+        }
+
+        tx.commit().await.map_err(Error::PgPool)?;
 
         Ok(())
     }
 
+    /// Whether the failure to send a transaction is caused by the orders it pays,
+    /// rather than by the node we send it to
+    fn is_caused_by_orders(error: &starknet_types::transactions::Error<OurAccount>) -> bool {
+        use starknet::accounts::AccountError;
+        use starknet_types::transactions::Error as TxError;
+
+        match error {
+            TxError::Account(AccountError::Provider(ProviderError::StarknetError(e)))
+            | TxError::Provider(ProviderError::StarknetError(e)) => {
+                !matches!(e, StarknetError::InvalidTransactionNonce)
+            }
+            TxError::Account(AccountError::FeeOutOfRange) => true,
+            _ => false,
+        }
+    }
+
+    /// Halve the number of orders paid in a single transaction until its fee estimate fits in
+    /// `max_l2_gas`, keeping the first ones
+    ///
+    /// `estimate_fee` estimates the transaction paying the given number of orders.
+    /// Returns the number of orders kept, along with the estimate of the last attempt.
+    async fn fit_in_gas_cap<E, Fut>(
+        mut n_orders: usize,
+        max_l2_gas: Option<u64>,
+        mut estimate_fee: impl FnMut(usize) -> Fut,
+    ) -> (usize, Result<FeeEstimate, E>)
+    where
+        Fut: Future<Output = Result<FeeEstimate, E>>,
+    {
+        loop {
+            match estimate_fee(n_orders).await {
+                Ok(fee_estimate)
+                    if n_orders > 1
+                        && max_l2_gas.is_some_and(|max| fee_estimate.l2_gas_consumed > max) =>
+                {
+                    n_orders /= 2;
+                }
+                result => return (n_orders, result),
+            }
+        }
+    }
+
+    /// Pay the orders not sent yet selected by the batch policy, in a single transaction,
+    /// and wait for its completion
+    ///
+    /// The transactions sent previously, whose completion was not registered, because the node
+    /// stopped or the wait failed, are waited for first.
+    /// The orders expiring last are left for a later transaction, until the fee estimate of the
+    /// transaction fits in the gas cap.
+    /// `nonce` is the one of our next transaction, fetched again from the node when `None`.
+    /// Returns false if there was no order to send yet.
+    async fn send_pending_orders(
+        account: Arc<OurAccount>,
+        invoice_payment_contract_address: Felt,
+        pg_pool: &PgPool,
+        batch_policy: &BatchPolicy,
+        nonce: &mut Option<Felt>,
+    ) -> Result<bool, Error> {
+        let sent_tx_hashes = {
+            let mut conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+            db_node::withdraw_order::get_sent_tx_hashes(&mut conn)
+                .await
+                .map_err(Error::PgPool)?
+        };
+        for tx_hash in sent_tx_hashes {
+            let tx_hash = Felt::from_hex(&tx_hash).map_err(|_| Error::InvalidTxHash(tx_hash))?;
+            *nonce = None;
+            complete_withdraw_orders(account.clone(), tx_hash, pg_pool).await?;
+        }
+
+        {
+            let mut conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+            db_node::withdraw_order::mark_indexed_as_succeeded(&mut conn)
+                .await
+                .map_err(Error::UpdateWithdrawOrders)?;
+        }
+        fail_exhausted_orders(pg_pool).await?;
+
+        let pendings = {
+            let mut conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+            db_node::withdraw_order::get_pendings(&mut conn, batch_policy.max_orders)
+                .await
+                .map_err(Error::GetWithdrawOrders)?
+        };
+        let pendings = batch_policy.select_orders(pendings, unix_time());
+        if pendings.is_empty() {
+            return Ok(false);
+        }
+
+        let mut orders = pendings
+            .iter()
+            .map(|order| {
+                serde_json::from_str::<PayInvoiceCallData>(&order.payload).map_err(|e| {
+                    Error::InvalidWithdrawOrder(Felt::from_bytes_be(&order.invoice_id), e)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut invoice_ids: Vec<_> = pendings.iter().map(|order| order.invoice_id).collect();
+
+        let tx_nonce = match *nonce {
+            Some(nonce) => nonce,
+            None => account
+                .provider()
+                .get_nonce(BlockId::Tag(BlockTag::Pending), account.address())
+                .await
+                .map_err(Error::GetNonce)?,
+        };
+        let (n_orders, fee_estimate) =
+            fit_in_gas_cap(orders.len(), batch_policy.max_l2_gas, |n_orders| {
+                estimate_payment_transactions_fee(
+                    account.clone(),
+                    invoice_payment_contract_address,
+                    orders[..n_orders].iter(),
+                    tx_nonce,
+                )
+            })
+            .await;
+        orders.truncate(n_orders);
+        invoice_ids.truncate(n_orders);
+        let send_result = match fee_estimate {
+            Ok(fee_estimate) => {
+                info!(
+                    name: "withdraw-batch",
+                    name = "withdraw-batch",
+                    orders = orders.len(),
+                    l2_gas = fee_estimate.l2_gas_consumed,
+                    overall_fee = %fee_estimate.overall_fee,
+                );
+                sign_and_send_payment_transactions(
+                    account.clone(),
+                    invoice_payment_contract_address,
+                    orders.iter(),
+                    tx_nonce,
+                    &fee_estimate,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+        let tx_hash = match send_result {
+            Ok(tx_hash) => tx_hash,
+            Err(err) => {
+                *nonce = None;
+                if is_caused_by_orders(&err) {
+                    let mut conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+                    db_node::withdraw_order::retry_later(
+                        &mut conn,
+                        &invoice_ids,
+                        WITHDRAW_RETRY_BASE_DELAY,
+                        WITHDRAW_RETRY_MAX_DELAY,
+                    )
+                    .await
+                    .map_err(Error::UpdateWithdrawOrders)?;
+                }
+                return Err(Error::Transaction(err));
+            }
+        };
+        *nonce = Some(tx_nonce + Felt::ONE);
+
+        {
+            let mut conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+            db_node::withdraw_order::set_sent(&mut conn, &invoice_ids, &tx_hash.to_hex_string())
+                .await
+                .map_err(Error::UpdateWithdrawOrders)?;
+        }
+
+        match complete_withdraw_orders(account, tx_hash, pg_pool).await {
+            Ok(true) => {}
+            Ok(false) => *nonce = None,
+            Err(err) => {
+                *nonce = None;
+                return Err(err);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Consume the withdraw orders outbox
+    ///
+    /// A single node instance consumes it at a time, another one takes over if it stops.
     pub async fn process_withdraw_requests(
         account: Arc<SingleOwnerAccount<JsonRpcClient<HttpTransport>, LocalWallet>>,
-        mut withdraw_queue: mpsc::UnboundedReceiver<PayInvoiceCallData>,
         invoice_payment_contract_address: Felt,
+        pg_pool: PgPool,
+        batch_policy: BatchPolicy,
     ) -> Result<(), Error> {
-        let mut orders = Vec::new();
-        let mut tx_handle: Option<tokio::task::JoinHandle<Result<(), Error>>> = None;
+        // Held for as long as this instance consumes the orders
+        let mut lock_conn = pg_pool.acquire().await.map_err(Error::PgPool)?;
+        while !db_node::withdraw_order::try_lock(&mut lock_conn)
+            .await
+            .map_err(Error::PgPool)?
+        {
+            sleep(WITHDRAW_ORDERS_POLL_INTERVAL).await;
+        }
+        let mut new_orders = PgListener::connect_with(&pg_pool)
+            .await
+            .map_err(Error::PgPool)?;
+        new_orders
+            .listen(db_node::notify::WITHDRAW_ORDER_CHANNEL)
+            .await
+            .map_err(Error::PgPool)?;
 
-        // TODO: retry logic
+        let mut nonce = None;
         loop {
-            match tx_handle.as_ref() {
-                None if orders.is_empty() => {
-                    withdraw_queue.recv_many(&mut orders, 10).await;
+            match send_pending_orders(
+                account.clone(),
+                invoice_payment_contract_address,
+                &pg_pool,
+                &batch_policy,
+                &mut nonce,
+            )
+            .await
+            {
+                Ok(true) => continue,
+                Ok(false) => {}
+                // The orders are handled again on the next iteration, unless they are waiting
+                // for a retry
+                Err(err) => {
+                    error!(name: "withdraw-orders-error", name = "withdraw-orders-error", error = %err)
                 }
-                Some(txh) => {
-                    while !txh.is_finished() {
-                        let _ = tokio::time::timeout(
-                            Duration::from_secs(1),
-                            withdraw_queue.recv_many(&mut orders, 10),
-                        )
-                        .await;
-                    }
+            }
 
-                    tx_handle = None;
-                }
-                _ => {
-                    let tx_hash = if orders.len() == 1 {
-                        sign_and_send_single_payment_transactions(
-                            account.clone(),
-                            invoice_payment_contract_address,
-                            &orders[0],
-                        )
-                        .await?
-                    } else {
-                        sign_and_send_payment_transactions(
-                            account.clone(),
-                            invoice_payment_contract_address,
-                            orders.iter(),
-                        )
-                        .await?
-                    };
-
-                    orders.clear();
-
-                    tx_handle = Some(tokio::spawn(wait_for_tx_completion(
-                        account.clone(),
-                        tx_hash,
-                    )));
+            tokio::select! {
+                notification = new_orders.recv() => {
+                    // The listener reconnects on the next call, the orders are polled meanwhile
+                    if let Err(err) = notification {
+                        error!(name: "withdraw-orders-listener-error", name = "withdraw-orders-listener-error", error = %err);
+                        sleep(WITHDRAW_ORDERS_POLL_INTERVAL).await;
+                    }
                 }
+                _ = sleep(WITHDRAW_ORDERS_POLL_INTERVAL) => {}
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const NOW: u64 = 1_000_000;
+
+        fn order(id: u8, attempts: u32, created_at: u64, expiry: u64) -> PendingWithdrawOrder {
+            PendingWithdrawOrder {
+                invoice_id: [id; 32],
+                quote_id: Uuid::nil(),
+                payload: String::new(),
+                attempts,
+                created_at,
+                expiry,
+            }
+        }
+
+        fn selected_ids(policy: &BatchPolicy, pendings: Vec<PendingWithdrawOrder>) -> Vec<u8> {
+            policy
+                .select_orders(pendings, NOW)
+                .into_iter()
+                .map(|order| order.invoice_id[0])
+                .collect()
+        }
+
+        fn waiting_policy() -> BatchPolicy {
+            BatchPolicy {
+                max_wait: Duration::from_secs(60),
+                max_orders: 3,
+                max_l2_gas: None,
+            }
+        }
+
+        #[test]
+        fn default_policy_sends_orders_right_away() {
+            let policy = BatchPolicy::default();
+
+            assert!(selected_ids(&policy, Vec::new()).is_empty());
+            assert_eq!(
+                selected_ids(&policy, vec![order(1, 0, NOW, NOW + 3600)]),
+                vec![1]
+            );
+        }
+
+        #[test]
+        fn full_batch_is_sent_right_away() {
+            let policy = waiting_policy();
+            let pendings = (1..=4).map(|id| order(id, 0, NOW, NOW + 3600)).collect();
+
+            assert_eq!(selected_ids(&policy, pendings), vec![1, 2, 3]);
+        }
+
+        #[test]
+        fn orders_wait_for_others_until_the_max_wait() {
+            let policy = waiting_policy();
+
+            assert!(
+                selected_ids(
+                    &policy,
+                    vec![
+                        order(1, 0, NOW - 59, NOW + 3600),
+                        order(2, 0, NOW, NOW + 3600)
+                    ]
+                )
+                .is_empty()
+            );
+            assert_eq!(
+                selected_ids(
+                    &policy,
+                    vec![
+                        order(1, 0, NOW - 60, NOW + 3600),
+                        order(2, 0, NOW, NOW + 3600)
+                    ]
+                ),
+                vec![1, 2]
+            );
+        }
+
+        #[test]
+        fn orders_expiring_before_the_end_of_the_wait_are_sent_right_away() {
+            let policy = waiting_policy();
+            let end_of_wait = NOW + 60 + WITHDRAW_ORDERS_POLL_INTERVAL.as_secs();
+
+            assert!(
+                selected_ids(
+                    &policy,
+                    vec![
+                        order(1, 0, NOW, end_of_wait + 1),
+                        order(2, 0, NOW, NOW + 3600)
+                    ]
+                )
+                .is_empty()
+            );
+            assert_eq!(
+                selected_ids(
+                    &policy,
+                    vec![order(1, 0, NOW, end_of_wait), order(2, 0, NOW, NOW + 3600)]
+                ),
+                vec![1, 2]
+            );
+        }
+
+        #[test]
+        fn retried_order_is_sent_alone() {
+            let policy = waiting_policy();
+            let pendings = vec![
+                order(1, 2, NOW, NOW + 3600),
+                order(2, 0, NOW - 60, NOW + 3600),
+                order(3, 0, NOW - 60, NOW + 3600),
+            ];
+            assert_eq!(selected_ids(&policy, pendings), vec![1]);
+
+            // Only sent alone once it comes first
+            let pendings = vec![
+                order(1, 0, NOW - 60, NOW + 3600),
+                order(2, 1, NOW, NOW + 3600),
+                order(3, 0, NOW, NOW + 3600),
+            ];
+            assert_eq!(selected_ids(&policy, pendings), vec![1, 3]);
+        }
+
+        fn fee_estimate(l2_gas_consumed: u64) -> FeeEstimate {
+            FeeEstimate {
+                l1_gas_consumed: 0,
+                l1_gas_price: 0,
+                l2_gas_consumed,
+                l2_gas_price: 1,
+                l1_data_gas_consumed: 0,
+                l1_data_gas_price: 0,
+                overall_fee: l2_gas_consumed.into(),
+                unit: PriceUnit::Fri,
+            }
+        }
+
+        /// Run `fit_in_gas_cap` with orders costing 100 gas each
+        ///
+        /// Returns the number of orders of each estimate, along with the result.
+        fn fit_orders_in_gas_cap(
+            n_orders: usize,
+            max_l2_gas: Option<u64>,
+        ) -> (Vec<usize>, usize, Result<FeeEstimate, ()>) {
+            let mut estimated = Vec::new();
+            let (n_orders, result) =
+                futures::executor::block_on(fit_in_gas_cap(n_orders, max_l2_gas, |n_orders| {
+                    estimated.push(n_orders);
+                    std::future::ready(Ok(fee_estimate(n_orders as u64 * 100)))
+                }));
+
+            (estimated, n_orders, result)
+        }
+
+        #[test]
+        fn orders_are_halved_until_they_fit_in_the_gas_cap() {
+            assert_eq!(
+                fit_orders_in_gas_cap(8, Some(350)),
+                (vec![8, 4, 2], 2, Ok(fee_estimate(200)))
+            );
+            assert_eq!(
+                fit_orders_in_gas_cap(8, Some(800)),
+                (vec![8], 8, Ok(fee_estimate(800)))
+            );
+            assert_eq!(
+                fit_orders_in_gas_cap(8, None),
+                (vec![8], 8, Ok(fee_estimate(800)))
+            );
+            // A single order is sent even above the cap
+            assert_eq!(
+                fit_orders_in_gas_cap(3, Some(50)),
+                (vec![3, 1], 1, Ok(fee_estimate(100)))
+            );
+        }
+
+        #[test]
+        fn failed_estimate_stops_the_halving() {
+            let (n_orders, result) =
+                futures::executor::block_on(fit_in_gas_cap(8, Some(350), |_| {
+                    std::future::ready(Err::<FeeEstimate, _>("estimate failed"))
+                }));
+
+            assert_eq!((n_orders, result), (8, Err("estimate failed")));
+        }
+
+        #[test]
+        fn gas_paid_in_fri_converts_into_milli_strk_rounded_up() {
+            let one_milli_strk = U256::from(10u64.pow(15));
+
+            assert_eq!(
+                convert_gas_to_amount_of_unit(one_milli_strk * 2, PriceUnit::Fri, Unit::MilliStrk)
+                    .unwrap(),
+                Amount::from(2u64)
+            );
+            assert_eq!(
+                convert_gas_to_amount_of_unit(
+                    one_milli_strk * 2 + 1,
+                    PriceUnit::Fri,
+                    Unit::MilliStrk
+                )
+                .unwrap(),
+                Amount::from(3u64)
+            );
+        }
+
+        #[test]
+        fn invalid_nonce_is_not_blamed_on_the_orders() {
+            use starknet::accounts::AccountError;
+            use starknet_types::transactions::Error as TxError;
+
+            let invalid_nonce =
+                || ProviderError::StarknetError(StarknetError::InvalidTransactionNonce);
+            // The nonce is fetched again, the orders are sent again without counting an attempt
+            assert!(!is_caused_by_orders(&TxError::Provider(invalid_nonce())));
+            assert!(!is_caused_by_orders(&TxError::Account(
+                AccountError::Provider(invalid_nonce())
+            )));
+            assert!(!is_caused_by_orders(&TxError::Provider(
+                ProviderError::RateLimited
+            )));
+
+            // The orders are retried later
+            assert!(is_caused_by_orders(&TxError::Provider(
+                ProviderError::StarknetError(StarknetError::InsufficientAccountBalance)
+            )));
+            assert!(is_caused_by_orders(&TxError::Account(
+                AccountError::FeeOutOfRange
+            )));
+        }
+
+        #[test]
+        fn gas_cannot_be_converted_into_another_asset() {
+            for unit in [Unit::Gwei, Unit::Satoshi, Unit::CentiUsdT, Unit::CentiUsdC] {
+                assert!(matches!(
+                    convert_gas_to_amount_of_unit(U256::from(1u64), PriceUnit::Fri, unit),
+                    Err(Error::InvalidAssetForUnit(Asset::Strk, u)) if u == unit
+                ));
             }
         }
     }
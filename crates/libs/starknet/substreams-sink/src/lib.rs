@@ -1,5 +1,6 @@
 use std::{
     env::{self, VarError},
+    ops::RangeInclusive,
     str::FromStr,
     sync::Arc,
 };
@@ -10,18 +11,12 @@ use db_node::PaymentEvent;
 use futures::StreamExt;
 use http::Uri;
 use nuts::traits::Unit as UnitT;
-use nuts::{Amount, nut04::MintQuoteState, nut05::MeltQuoteState};
-use pb::{
-    invoice_contract::v1::RemittanceEvent,
-    sf::substreams::v1::module::input::{Input, Params},
-};
+use nuts::{nut04::MintQuoteState, nut05::MeltQuoteState};
+use pb::sf::substreams::v1::module::input::{Input, Params};
 use prost::Message;
 use sqlx::{
-    PgConnection, PgPool,
-    types::{
-        Uuid,
-        chrono::{DateTime, Utc},
-    },
+    Connection, PgConnection, PgPool,
+    types::chrono::{DateTime, Utc},
 };
 use starknet::core::types::Felt;
 use starknet_types::{ChainId, StarknetU256, Unit, constants::ON_CHAIN_CONSTANTS};
@@ -35,12 +30,15 @@ mod pb;
 mod substreams;
 mod substreams_stream;
 
+pub use pb::invoice_contract::v1::RemittanceEvent;
+
 pub async fn launch(
     pg_pool: PgPool,
     endpoint_url: Uri,
     chain_id: ChainId,
     initial_block: i64,
     cashier_account_address: Felt,
+    confirmations: u64,
 ) -> Result<()> {
     const OUTPUT_MODULE_NAME: &str = "map_invoice_contract_events";
     const STARKNET_FILTERED_TRANSACTIONS_MODULE_NAME: &str = "starknet:filtered_transactions";
@@ -102,14 +100,28 @@ pub async fn launch(
                 break;
             }
             Some(Ok(BlockResponse::New(data))) => {
-                process_block_scoped_data(&mut db_conn, &data, &chain_id, cashier_account_address)
-                    .await?;
-                persist_cursor(&mut db_conn, data.cursor).await?;
+                let mut tx = db_conn.begin().await?;
+                process_block_scoped_data(
+                    &mut tx,
+                    &data,
+                    &chain_id,
+                    cashier_account_address,
+                    confirmations,
+                )
+                .await?;
+                persist_cursor(&mut tx, data.cursor).await?;
+                tx.commit().await?;
             }
             Some(Ok(BlockResponse::Undo(undo_signal))) => {
-                delete_invalid_blocks(&mut db_conn, undo_signal.last_valid_block.unwrap().number)
-                    .await?;
-                persist_cursor(&mut db_conn, undo_signal.last_valid_cursor).await?;
+                let mut tx = db_conn.begin().await?;
+                undo_blocks(
+                    &mut tx,
+                    undo_signal.last_valid_block.unwrap().number,
+                    confirmations,
+                )
+                .await?;
+                persist_cursor(&mut tx, undo_signal.last_valid_cursor).await?;
+                tx.commit().await?;
             }
             Some(Err(err)) => {
                 return Err(err);
@@ -125,6 +137,7 @@ async fn process_block_scoped_data(
     data: &BlockScopedData,
     chain_id: &ChainId,
     cashier_account_address: Felt,
+    confirmations: u64,
 ) -> Result<(), Error> {
     let output = data.output.as_ref().unwrap().map_output.as_ref().unwrap();
 
@@ -143,23 +156,121 @@ async fn process_block_scoped_data(
         -date.signed_duration_since(Utc::now()).num_seconds()
     );
 
-    if !events.events.is_empty() {
-        sqlx::query(r#"
-            INSERT INTO substreams_starknet_block (id, number, timestamp) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING;
-        "#)
-        .bind(&clock.id)
-            .bind(i64::try_from(clock.number).unwrap())
-                .bind(date)
-        .execute(&mut *conn).await?;
-
-        process_payment_event(
-            events.events,
-            conn,
-            chain_id,
-            cashier_account_address,
-            clock.id.clone(),
-        )
-        .await?;
+    index_block_events(
+        conn,
+        &clock.id,
+        clock.number,
+        timestamp.seconds,
+        events.events,
+        chain_id,
+        cashier_account_address,
+    )
+    .await?;
+    confirm_payments(conn, clock.number..=clock.number, confirmations).await?;
+
+    Ok(())
+}
+
+/// Register the block and handle the payments it contains
+///
+/// Shared by the indexers, so that all of them write the same events.
+/// Blocks without any event are not registered. `block_timestamp` is a unix timestamp.
+pub async fn index_block_events(
+    conn: &mut PgConnection,
+    block_id: &str,
+    block_number: u64,
+    block_timestamp: i64,
+    events: Vec<RemittanceEvent>,
+    chain_id: &ChainId,
+    cashier_account_address: Felt,
+) -> Result<(), Error> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let timestamp = DateTime::from_timestamp(block_timestamp, 0)
+        .ok_or(anyhow!("invalid block timestamp {}", block_timestamp))?;
+
+    sqlx::query(r#"
+        INSERT INTO substreams_starknet_block (id, number, timestamp) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING;
+    "#)
+    .bind(block_id)
+        .bind(i64::try_from(block_number)?)
+            .bind(timestamp)
+    .execute(&mut *conn).await?;
+
+    process_payment_event(
+        events,
+        conn,
+        chain_id,
+        cashier_account_address,
+        block_id.to_string(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Settle the quotes paid in the blocks confirmed while the head of the chain moved through `heads`
+///
+/// A block is confirmed once the head is `confirmations - 1` blocks above it, so with a single
+/// confirmation the payments are settled as soon as they are included.
+/// Only the confirmed payments count toward the amount paid of a quote.
+pub async fn confirm_payments(
+    conn: &mut PgConnection,
+    heads: RangeInclusive<u64>,
+    confirmations: u64,
+) -> Result<(), Error> {
+    let Some(confirmed_height) = (heads.end() + 1).checked_sub(confirmations) else {
+        return Ok(());
+    };
+    let from_block = (heads.start() + 1).saturating_sub(confirmations);
+
+    for invoice_id in
+        db_node::mint_payment_event::get_invoice_ids_in_blocks(conn, from_block, confirmed_height)
+            .await?
+    {
+        refresh_mint_quote(conn, &invoice_id, Some(confirmed_height)).await?;
+    }
+    for invoice_id in
+        db_node::melt_payment_event::get_invoice_ids_in_blocks(conn, from_block, confirmed_height)
+            .await?
+    {
+        refresh_melt_quote(conn, &invoice_id, Some(confirmed_height)).await?;
+    }
+
+    Ok(())
+}
+
+/// Delete the blocks above `last_valid_block_number`, along with the payment events they contained
+///
+/// The quotes those payments were settling go back to their previous state if the remaining
+/// confirmed payments no longer cover them.
+pub async fn undo_blocks(
+    conn: &mut PgConnection,
+    last_valid_block_number: u64,
+    confirmations: u64,
+) -> Result<(), Error> {
+    let mint_invoice_ids = db_node::mint_payment_event::get_invoice_ids_in_blocks(
+        conn,
+        last_valid_block_number + 1,
+        u64::MAX,
+    )
+    .await?;
+    let melt_invoice_ids = db_node::melt_payment_event::get_invoice_ids_in_blocks(
+        conn,
+        last_valid_block_number + 1,
+        u64::MAX,
+    )
+    .await?;
+
+    delete_invalid_blocks(conn, last_valid_block_number).await?;
+
+    let confirmed_height = (last_valid_block_number + 1).checked_sub(confirmations);
+    for invoice_id in mint_invoice_ids {
+        refresh_mint_quote(conn, &invoice_id, confirmed_height).await?;
+    }
+    for invoice_id in melt_invoice_ids {
+        refresh_melt_quote(conn, &invoice_id, confirmed_height).await?;
     }
 
     Ok(())
@@ -218,22 +329,31 @@ async fn process_payment_event(
 ) -> Result<(), Error> {
     for payment_event in remittance_events {
         let invoice_id = Felt::from_bytes_be_slice(&payment_event.invoice_id);
-        let (is_mint, quote_id, quote_amount, unit) = if let Some((quote_id, amount, unit)) =
+        let (is_mint, quote_id, unit) = if let Some((quote_id, _, unit)) =
             db_node::mint_quote::get_quote_infos_by_invoice_id::<Unit>(
                 conn,
                 &invoice_id.to_bytes_be(),
             )
             .await?
         {
-            (true, quote_id, amount, unit)
-        } else if let Some((quote_id, amount, unit)) =
+            (true, quote_id, unit)
+        } else if let Some((quote_id, _, unit)) =
             db_node::melt_quote::get_quote_infos_by_invoice_id::<Unit>(
                 conn,
                 &invoice_id.to_bytes_be(),
             )
             .await?
         {
-            (false, quote_id, amount, unit)
+            (false, quote_id, unit)
+        } else if let Some((quote_id, _, unit)) =
+            db_node::mint_quote::get_archived_quote_infos_by_invoice_id::<Unit>(
+                conn,
+                &invoice_id.to_bytes_be(),
+            )
+            .await?
+        {
+            // Paid after it expired and was archived, still recorded for the sweeper to flag it
+            (true, quote_id, unit)
         } else {
             error!("no quote for invoice_id {:#x}", invoice_id);
             continue;
@@ -286,7 +406,7 @@ async fn process_payment_event(
                     amount_high: Felt::from_bytes_be_slice(&payment_event.amount_high)
                         .to_hex_string(),
                 };
-                handle_mint_payment(conn, quote_id, db_event, unit, quote_amount).await?;
+                db_node::mint_payment_event::insert_new_payment_event(conn, &db_event).await?;
             }
         } else {
             let payer = Felt::from_bytes_be_slice(&payment_event.payer);
@@ -304,7 +424,7 @@ async fn process_payment_event(
                     amount_high: Felt::from_bytes_be_slice(&payment_event.amount_high)
                         .to_hex_string(),
                 };
-                handle_melt_payment(conn, quote_id, db_event, unit, quote_amount).await?;
+                db_node::melt_payment_event::insert_new_payment_event(conn, &db_event).await?;
             }
         }
     }
@@ -312,88 +432,170 @@ async fn process_payment_event(
     Ok(())
 }
 
+/// Sum the amounts of payment events
+fn sum_amounts(
+    amounts: impl Iterator<Item = (String, String)>,
+) -> Result<primitive_types::U256, Error> {
+    amounts
+        .map(|(low, high)| -> Result<primitive_types::U256, Error> {
+            let amount_as_strk_256 = StarknetU256 {
+                low: Felt::from_str(&low)?,
+                high: Felt::from_str(&high)?,
+            };
+
+            Ok(primitive_types::U256::from(amount_as_strk_256))
+        })
+        .try_fold(primitive_types::U256::zero(), |acc, a| match a {
+            Ok(v) => v.checked_add(acc).ok_or(anyhow!(
+                "u256 value overflowed during the computation of the total amount paid for invoice"
+            )),
+            Err(e) => Err(e),
+        })
+}
+
 // Yeah I know it's basically the same code copied and pasted.
 // For now it's fine, better this than adding trait and struct and so on.
-async fn handle_mint_payment(
+
+/// Update the amount paid and the state of a mint quote from its confirmed payments
+///
+/// Nothing is confirmed when `confirmed_height` is None.
+async fn refresh_mint_quote(
     db_conn: &mut PgConnection,
-    quote_id: Uuid,
-    payment_event: PaymentEvent,
-    unit: Unit,
-    quote_amount: Amount,
+    invoice_id: &[u8; 32],
+    confirmed_height: Option<u64>,
 ) -> Result<(), Error> {
-    db_node::mint_payment_event::insert_new_payment_event(db_conn, &payment_event).await?;
-
-    let current_paid =
-        db_node::mint_payment_event::get_current_paid(db_conn, &payment_event.invoice_id)
-            .await?
-            .map(|(low, high)| -> Result<primitive_types::U256, Error> {
-                let amount_as_strk_256 = StarknetU256 {
-                    low: Felt::from_str(&low)?,
-                    high: Felt::from_str(&high)?,
-                };
-
-                Ok(primitive_types::U256::from(amount_as_strk_256))
-            })
-            .try_fold(primitive_types::U256::zero(), |acc, a| {
-                match a {
-        Ok(v) => v.checked_add(acc).ok_or(anyhow!(
-            "u256 value overflowed during the computation of the total amount paid for invoice"
-        )),
-        Err(e) => Err(e),
-    }
-            })?;
+    let Some((quote_id, quote_amount, unit)) =
+        db_node::mint_quote::get_quote_infos_by_invoice_id::<Unit>(db_conn, invoice_id).await?
+    else {
+        return Ok(());
+    };
+    let current_paid = match confirmed_height {
+        Some(confirmed_height) => sum_amounts(
+            db_node::mint_payment_event::get_current_paid(db_conn, invoice_id, confirmed_height)
+                .await?,
+        )?,
+        None => primitive_types::U256::zero(),
+    };
 
+    // The quote becomes paid once the payments cover the full amount,
+    // anything received on top of it is recorded as excess
     let to_pay = unit.convert_amount_into_u256(quote_amount);
-    if current_paid >= to_pay {
-        db_node::mint_quote::set_state(db_conn, quote_id, MintQuoteState::Paid).await?;
+    let excess = current_paid
+        .checked_sub(to_pay)
+        .filter(|excess| !excess.is_zero());
+    db_node::mint_quote::set_amount_paid(
+        db_conn,
+        quote_id,
+        unit.convert_u256_into_amount(current_paid),
+        excess.map(|excess| excess.to_big_endian()),
+    )
+    .await?;
+
+    let (_, state) = db_node::mint_quote::get_amount_and_state(db_conn, quote_id).await?;
+    if let Some(excess) = excess {
         event!(
-            name: "mint-quote-paid",
-            Level::INFO,
+            name: "mint-quote-overpaid",
+            Level::WARN,
             %quote_id,
-            "Mint quote paid"
+            %excess,
+            "Mint quote overpaid"
         );
     }
+    let is_paid = current_paid >= to_pay;
+    match state {
+        MintQuoteState::Unpaid if is_paid => {
+            db_node::mint_quote::set_state(db_conn, quote_id, MintQuoteState::Paid).await?;
+            event!(
+                name: "mint-quote-paid",
+                Level::INFO,
+                %quote_id,
+                "Mint quote paid"
+            );
+        }
+        MintQuoteState::Unpaid if !current_paid.is_zero() => {
+            event!(
+                name: "mint-quote-partially-paid",
+                Level::INFO,
+                %quote_id,
+                %current_paid,
+                %to_pay,
+                "Mint quote partially paid"
+            );
+        }
+        // The payments it was relying on were reorged out
+        MintQuoteState::Paid if !is_paid => {
+            db_node::mint_quote::set_state(db_conn, quote_id, MintQuoteState::Unpaid).await?;
+            event!(
+                name: "mint-quote-payment-reverted",
+                Level::WARN,
+                %quote_id,
+                %current_paid,
+                %to_pay,
+                "Mint quote payment reverted"
+            );
+        }
+        // Too late, the ecash was already issued
+        MintQuoteState::Issued if !is_paid => {
+            event!(
+                name: "issued-mint-quote-payment-reverted",
+                Level::ERROR,
+                %quote_id,
+                %current_paid,
+                %to_pay,
+                "Payment of an issued mint quote reverted"
+            );
+        }
+        _ => {}
+    }
 
     Ok(())
 }
 
-async fn handle_melt_payment(
+/// Update the state of a melt quote from its confirmed payments
+///
+/// Nothing is confirmed when `confirmed_height` is None.
+async fn refresh_melt_quote(
     db_conn: &mut PgConnection,
-    quote_id: Uuid,
-    payment_event: PaymentEvent,
-    unit: Unit,
-    quote_amount: Amount,
+    invoice_id: &[u8; 32],
+    confirmed_height: Option<u64>,
 ) -> Result<(), Error> {
-    db_node::melt_payment_event::insert_new_payment_event(db_conn, &payment_event).await?;
-    let current_paid =
-        db_node::melt_payment_event::get_current_paid(db_conn, &payment_event.invoice_id)
-            .await?
-            .map(|(low, high)| -> Result<primitive_types::U256, Error> {
-                let amount_as_strk_256 = StarknetU256 {
-                    low: Felt::from_str(&low)?,
-                    high: Felt::from_str(&high)?,
-                };
-
-                Ok(primitive_types::U256::from(amount_as_strk_256))
-            })
-            .try_fold(primitive_types::U256::zero(), |acc, a| {
-                match a {
-        Ok(v) => v.checked_add(acc).ok_or(anyhow!(
-            "u256 value overflowed during the computation of the total amount paid for invoice"
-        )),
-                Err(e) => Err(e),
-            }
-            })?;
+    let Some((quote_id, quote_amount, unit)) =
+        db_node::melt_quote::get_quote_infos_by_invoice_id::<Unit>(db_conn, invoice_id).await?
+    else {
+        return Ok(());
+    };
+    let current_paid = match confirmed_height {
+        Some(confirmed_height) => sum_amounts(
+            db_node::melt_payment_event::get_current_paid(db_conn, invoice_id, confirmed_height)
+                .await?,
+        )?,
+        None => primitive_types::U256::zero(),
+    };
 
     let to_pay = unit.convert_amount_into_u256(quote_amount);
-    if current_paid >= to_pay {
-        db_node::melt_quote::set_state(db_conn, quote_id, MeltQuoteState::Paid).await?;
-        event!(
-            name: "melt-quote-paid",
-            Level::INFO,
-            %quote_id,
-            "Melt quote paid"
-        );
+    let is_paid = current_paid >= to_pay;
+    let state = db_node::melt_quote::get_state(db_conn, quote_id).await?;
+    match state {
+        MeltQuoteState::Paid if !is_paid => {
+            // The payment transaction is expected to be included again
+            db_node::melt_quote::set_state(db_conn, quote_id, MeltQuoteState::Pending).await?;
+            event!(
+                name: "melt-quote-payment-reverted",
+                Level::WARN,
+                %quote_id,
+                "Melt quote payment reverted"
+            );
+        }
+        _ if is_paid => {
+            db_node::melt_quote::set_state(db_conn, quote_id, MeltQuoteState::Paid).await?;
+            event!(
+                name: "melt-quote-paid",
+                Level::INFO,
+                %quote_id,
+                "Melt quote paid"
+            );
+        }
+        _ => {}
     }
 
     Ok(())
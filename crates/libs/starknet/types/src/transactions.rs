@@ -3,8 +3,8 @@ use std::sync::Arc;
 use primitive_types::U256;
 use starknet::{
     accounts::{Account, AccountError, ConnectedAccount},
-    core::types::{BlockId, BlockTag, Call},
-    providers::{Provider, ProviderError},
+    core::types::{Call, FeeEstimate},
+    providers::ProviderError,
 };
 use starknet_types_core::felt::Felt;
 use tracing::{Instrument, info_span};
@@ -94,51 +94,72 @@ pub fn generate_single_payment_transaction_calls(
     [approve_call, transfer_call]
 }
 
-pub async fn sign_and_send_payment_transactions<
+/// Margin applied to the gas amounts and prices of a fee estimate, when sending the transaction
+const FEE_ESTIMATE_MULTIPLIER: f64 = 1.5;
+
+/// Estimate the fee of paying the orders in a single transaction
+pub async fn estimate_payment_transactions_fee<
     A: Account + ConnectedAccount + Sync + std::fmt::Debug,
 >(
     account: Arc<A>,
     invoice_payment_contract_address: Felt,
     withdrawal_orders: impl ExactSizeIterator<Item = &PayInvoiceCallData> + Clone,
-) -> Result<Felt, Error<A>> {
+    nonce: Felt,
+) -> Result<FeeEstimate, Error<A>> {
     let calls =
         generate_payment_transaction_calls(invoice_payment_contract_address, withdrawal_orders);
 
-    send_transation(account, calls).await
+    let fee_estimate = account
+        .execute_v3(calls)
+        .nonce(nonce)
+        .estimate_fee()
+        .await?;
+
+    Ok(fee_estimate)
 }
 
-pub async fn sign_and_send_single_payment_transactions<
+/// Pay the orders in a single transaction, within the bounds of `fee_estimate`
+///
+/// The estimate must be the one of those same orders, see [`estimate_payment_transactions_fee`].
+pub async fn sign_and_send_payment_transactions<
     A: Account + ConnectedAccount + Sync + std::fmt::Debug,
 >(
     account: Arc<A>,
     invoice_payment_contract_address: Felt,
-    withdrawal_order: &PayInvoiceCallData,
+    withdrawal_orders: impl ExactSizeIterator<Item = &PayInvoiceCallData> + Clone,
+    nonce: Felt,
+    fee_estimate: &FeeEstimate,
 ) -> Result<Felt, Error<A>> {
-    let calls = generate_single_payment_transaction_calls(
-        invoice_payment_contract_address,
-        withdrawal_order.quote_id_hash,
-        withdrawal_order.expiry,
-        withdrawal_order.asset_contract_address,
-        &withdrawal_order.amount,
-        withdrawal_order.payee,
-    );
-
-    send_transation(account, calls.to_vec()).await
+    let calls =
+        generate_payment_transaction_calls(invoice_payment_contract_address, withdrawal_orders);
+
+    send_transation(account, calls, nonce, fee_estimate).await
 }
 
+/// Send the calls with an explicit nonce and fee bounds
+///
+/// The nonce of the pending block may lag behind the transactions we just sent,
+/// so the caller is in charge of keeping track of it.
 async fn send_transation<A: Account + ConnectedAccount + Sync + std::fmt::Debug>(
     account: Arc<A>,
     calls: Vec<Call>,
+    nonce: Felt,
+    fee_estimate: &FeeEstimate,
 ) -> Result<Felt, Error<A>> {
     let calls_debug_string = format!("{:?}", calls);
-    let nonce = account
-        .provider()
-        .get_nonce(BlockId::Tag(BlockTag::Pending), account.address())
-        .await?;
+    let gas = |amount: u64| (amount as f64 * FEE_ESTIMATE_MULTIPLIER) as u64;
+    let gas_price = |price: u128| (price as f64 * FEE_ESTIMATE_MULTIPLIER) as u128;
+
     // Execute the transaction
     let tx_result = account
         .execute_v3(calls)
         .nonce(nonce)
+        .l1_gas(gas(fee_estimate.l1_gas_consumed))
+        .l1_gas_price(gas_price(fee_estimate.l1_gas_price))
+        .l2_gas(gas(fee_estimate.l2_gas_consumed))
+        .l2_gas_price(gas_price(fee_estimate.l2_gas_price))
+        .l1_data_gas(gas(fee_estimate.l1_data_gas_consumed))
+        .l1_data_gas_price(gas_price(fee_estimate.l1_data_gas_price))
         .send()
         .instrument(info_span!("send-withdraw-transaction"))
         .await
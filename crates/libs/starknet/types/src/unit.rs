@@ -175,4 +175,42 @@ impl Unit {
     pub fn convert_amount_into_u256(&self, amount: Amount) -> U256 {
         U256::from(u64::from(amount)) * U256::from(self.scale_factor())
     }
+
+    /// Converts a blockchain-native value to an amount of unit
+    ///
+    /// The value is rounded down, and saturates at the maximum amount.
+    pub fn convert_u256_into_amount(&self, value: U256) -> Amount {
+        let amount = value / U256::from(self.scale_factor());
+
+        Amount::from(u64::try_from(amount).unwrap_or(u64::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_u256_into_amount_rounds_down() {
+        let unit = Unit::MilliStrk;
+        let one_milli_strk = U256::from(unit.scale_factor());
+
+        assert_eq!(unit.convert_u256_into_amount(U256::zero()), Amount::ZERO);
+        assert_eq!(
+            unit.convert_u256_into_amount(one_milli_strk - 1),
+            Amount::ZERO
+        );
+        assert_eq!(
+            unit.convert_u256_into_amount(one_milli_strk * 3 + 1),
+            Amount::from(3u64)
+        );
+        assert_eq!(
+            unit.convert_u256_into_amount(unit.convert_amount_into_u256(Amount::from(42u64))),
+            Amount::from(42u64)
+        );
+        assert_eq!(
+            unit.convert_u256_into_amount(U256::MAX),
+            Amount::from(u64::MAX)
+        );
+    }
 }
@@ -15,6 +15,15 @@ pub const CREATE_TABLE_KEYSET: &str = r#"
         CREATE INDEX keyset_active ON keyset(active);
     "#;
 
+pub const ADD_COLUMN_INPUT_FEE_PPK: &str = r#"
+        ALTER TABLE keyset ADD COLUMN input_fee_ppk INTEGER NOT NULL DEFAULT 0;
+    "#;
+
+/// Unix timestamp after which the node stops accepting the keyset proofs, if any
+pub const ADD_COLUMN_FINAL_EXPIRY: &str = r#"
+        ALTER TABLE keyset ADD COLUMN final_expiry INTEGER;
+    "#;
+
 pub fn upsert_many_for_node(
     conn: &Connection,
     node_id: u32,
@@ -28,11 +37,12 @@ pub fn upsert_many_for_node(
     )?;
 
     const UPSERT_NODE_KEYSET: &str = r#"
-            INSERT INTO keyset (id, node_id, unit, active)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT INTO keyset (id, node_id, unit, active, input_fee_ppk, final_expiry)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
             ON CONFLICT(id) DO UPDATE
-                SET active=excluded.active
-                WHERE active != excluded.active;
+                SET active=excluded.active, input_fee_ppk=excluded.input_fee_ppk, final_expiry=excluded.final_expiry
+                WHERE active != excluded.active OR input_fee_ppk != excluded.input_fee_ppk
+                    OR final_expiry IS NOT excluded.final_expiry;
     "#;
 
     for keyset in keysets {
@@ -45,7 +55,14 @@ pub fn upsert_many_for_node(
         })?;
         conn.execute(
             UPSERT_NODE_KEYSET,
-            params![id, node_id, keyset.unit, keyset.active],
+            params![
+                id,
+                node_id,
+                keyset.unit,
+                keyset.active,
+                keyset.input_fee_ppk,
+                keyset.final_expiry
+            ],
         )?;
     }
 
@@ -92,6 +109,22 @@ pub fn get_unit_by_id(conn: &Connection, keyset_id: KeysetId) -> Result<Option<S
     Ok(opt_unit)
 }
 
+/// Returns the highest `input_fee_ppk` among this node keysets of unit
+///
+/// This is the most a single input of this unit can cost us when spent at this node
+pub fn get_max_input_fee_ppk_for_node_and_unit(
+    conn: &Connection,
+    node_id: u32,
+    unit: &str,
+) -> Result<u64> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(MAX(input_fee_ppk), 0) FROM keyset WHERE node_id = ?1 AND unit = ?2",
+    )?;
+    let max_input_fee_ppk = stmt.query_row(params![node_id, unit], |r| r.get::<_, u64>(0))?;
+
+    Ok(max_input_fee_ppk)
+}
+
 pub fn get_counter(conn: &Connection, keyset_id: KeysetId) -> Result<u32> {
     let mut stmt = conn.prepare("SELECT counter FROM keyset WHERE id = ?1 LIMIT 1")?;
 
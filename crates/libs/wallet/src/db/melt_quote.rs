@@ -14,6 +14,32 @@ pub const CREATE_TABLE_MELT_QUOTE: &str = r#"
             transfer_ids TEXT
         );"#;
 
+/// Allow the `FAILED` state
+///
+/// SQLite can't alter a CHECK constraint, the table is rebuilt instead.
+pub const ALLOW_STATE_FAILED: &str = r#"
+        CREATE TABLE melt_quote_new (
+            id BLOB(16) PRIMARY KEY,
+            node_id INTEGER NOT NULL REFERENCES node(id) ON DELETE CASCADE,
+            method TEXT NOT NULL,
+            amount INTEGER NOT NULL,
+            unit TEXT NOT NULL,
+            request TEXT NOT NULL,
+            state INTEGER NOT NULL CHECK (state IN (1, 2, 3, 4)),
+            expiry INTEGER NOT NULL,
+            transfer_ids TEXT
+        );
+        INSERT INTO melt_quote_new (id, node_id, method, amount, unit, request, state, expiry, transfer_ids)
+            SELECT id, node_id, method, amount, unit, request, state, expiry, transfer_ids FROM melt_quote;
+        DROP TABLE melt_quote;
+        ALTER TABLE melt_quote_new RENAME TO melt_quote;
+    "#;
+
+/// The inputs spent to pay the quote, see [`register_inputs`]
+pub const ADD_COLUMN_INPUTS: &str = r#"
+        ALTER TABLE melt_quote ADD COLUMN inputs TEXT;
+    "#;
+
 #[derive(Debug)]
 pub struct MeltQuote {
     pub id: String,
@@ -67,6 +93,34 @@ pub fn register_transfer_ids(conn: &Connection, quote_id: &str, transfer_ids: &s
     Ok(())
 }
 
+/// Register the `Y`s of the proofs spent to pay the quote, as a json array
+///
+/// They are checked again if the payment fails, the node returning them to us.
+pub fn register_inputs(conn: &Connection, quote_id: &str, inputs: &str) -> Result<()> {
+    const REGISTER_INPUTS: &str = r#"
+        UPDATE melt_quote SET inputs = ?2 WHERE id = ?1;
+    "#;
+
+    conn.execute(REGISTER_INPUTS, [quote_id, inputs])?;
+
+    Ok(())
+}
+
+/// Returns the state of the quote, and its inputs if they were registered
+pub fn get_state_and_inputs(
+    conn: &Connection,
+    quote_id: &str,
+) -> Result<Option<(MeltQuoteState, Option<String>)>> {
+    const GET_STATE_AND_INPUTS: &str = r#"
+        SELECT state, inputs FROM melt_quote WHERE id = ?1 LIMIT 1;
+    "#;
+
+    conn.query_row(GET_STATE_AND_INPUTS, [quote_id], |r| {
+        Ok((r.get(0)?, r.get(1)?))
+    })
+    .optional()
+}
+
 #[derive(Debug, Clone)]
 pub struct PendingMeltQuote {
     pub id: String,
@@ -13,6 +13,11 @@ pub const CREATE_TABLE_MINT_QUOTE: &str = r#"
             expiry INTEGER NOT NULL
         );"#;
 
+/// Sum of the payments the node received for the quote, see [`set_amount_paid`]
+pub const ADD_COLUMN_AMOUNT_PAID: &str = r#"
+        ALTER TABLE mint_quote ADD COLUMN amount_paid INTEGER NOT NULL DEFAULT 0;
+    "#;
+
 #[derive(Debug)]
 pub struct MintQuote {
     pub id: String,
@@ -23,6 +28,7 @@ pub struct MintQuote {
     pub request: String,
     pub state: MintQuoteState,
     pub expiry: u64,
+    pub amount_paid: Amount,
 }
 
 pub fn store(
@@ -35,9 +41,9 @@ pub fn store(
 ) -> Result<()> {
     const INSERT_NEW_MINT_QUOTE: &str = r#"
         INSERT INTO mint_quote
-            (id, node_id, method, amount, unit, request, state, expiry)
+            (id, node_id, method, amount, unit, request, state, expiry, amount_paid)
         VALUES
-            (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);
+            (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);
     "#;
 
     conn.execute(
@@ -51,6 +57,7 @@ pub fn store(
             &response.request,
             response.state,
             response.expiry,
+            response.amount_paid,
         ),
     )?;
 
@@ -68,6 +75,21 @@ pub fn set_state(conn: &Connection, quote_id: &str, state: MintQuoteState) -> Re
     Ok(())
 }
 
+/// Record the sum of the payments received, as reported by the node
+///
+/// An unpaid quote with some amount paid is partially paid.
+pub fn set_amount_paid(conn: &Connection, quote_id: &str, amount_paid: Amount) -> Result<()> {
+    const SET_MINT_QUOTE_AMOUNT_PAID: &str = r#"
+        UPDATE mint_quote
+        SET amount_paid = ?2
+        WHERE id = ?1;
+    "#;
+
+    conn.execute(SET_MINT_QUOTE_AMOUNT_PAID, (quote_id, amount_paid))?;
+
+    Ok(())
+}
+
 pub fn delete(conn: &Connection, quote_id: &str) -> Result<()> {
     const DELETE_MINT_QUOTE_STATE: &str = r#"
         DELETE FROM mint_quote
@@ -96,6 +118,7 @@ pub fn get(conn: &Connection, node_id: u32, quote_id: &str) -> Result<Option<Min
                 request: r.get::<_, _>(5)?,
                 state: r.get::<_, _>(6)?,
                 expiry: r.get::<_, _>(7)?,
+                amount_paid: r.get::<_, _>(8)?,
             })
         })
         .optional()?;
@@ -112,6 +135,7 @@ pub struct PendingMintQuote {
     pub request: String,
     pub state: MintQuoteState,
     pub expiry: u64,
+    pub amount_paid: Amount,
 }
 
 pub fn get_pendings(conn: &Connection) -> Result<Vec<(u32, Vec<PendingMintQuote>)>> {
@@ -136,6 +160,7 @@ pub fn get_pendings(conn: &Connection) -> Result<Vec<(u32, Vec<PendingMintQuote>
             request: r.get::<_, _>(5)?,
             state: r.get::<_, _>(6)?,
             expiry: r.get::<_, _>(7)?,
+            amount_paid: r.get::<_, _>(8)?,
         };
 
         match quote_per_node.iter().position(|v| v.0 == node_id) {
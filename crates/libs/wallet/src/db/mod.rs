@@ -1,5 +1,7 @@
-use nuts::nut02::KeysetId;
-use rusqlite::{Connection, Result, params};
+use std::str::FromStr;
+
+use nuts::{Amount, nut01::PublicKey, nut02::KeysetId};
+use rusqlite::{Connection, OptionalExtension, Result, params};
 
 pub mod balance;
 pub mod keyset;
@@ -18,6 +20,17 @@ pub const CREATE_TABLE_KEY: &str = r#"
         );
     "#;
 
+/// Changes to the tables of the databases created by a previous version, in order
+///
+/// The database `user_version` is the number of them already applied.
+pub const MIGRATIONS: &[&str] = &[
+    keyset::ADD_COLUMN_INPUT_FEE_PPK,
+    keyset::ADD_COLUMN_FINAL_EXPIRY,
+    mint_quote::ADD_COLUMN_AMOUNT_PAID,
+    melt_quote::ALLOW_STATE_FAILED,
+    melt_quote::ADD_COLUMN_INPUTS,
+];
+
 pub fn create_tables(conn: &mut Connection) -> Result<()> {
     let tx = conn.transaction()?;
 
@@ -27,9 +40,16 @@ pub fn create_tables(conn: &mut Connection) -> Result<()> {
     tx.execute(mint_quote::CREATE_TABLE_MINT_QUOTE, ())?;
     tx.execute(melt_quote::CREATE_TABLE_MELT_QUOTE, ())?;
     tx.execute(proof::CREATE_TABLE_PROOF, ())?;
+    tx.execute(proof::CREATE_TABLE_PROOF_DLEQ, ())?;
     tx.execute(wad::CREATE_TABLE_WAD, ())?;
     tx.execute(wad::CREATE_TABLE_WAD_PROOF, ())?;
 
+    let applied_migrations: usize = tx.query_row("PRAGMA user_version", (), |r| r.get(0))?;
+    for migration in MIGRATIONS.iter().skip(applied_migrations) {
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+
     tx.commit()?;
 
     Ok(())
@@ -51,3 +71,99 @@ pub fn insert_keyset_keys<'a>(
 
     Ok(())
 }
+
+/// Returns the node public key used to sign `amount` in this keyset
+pub fn get_keyset_key(
+    conn: &Connection,
+    keyset_id: KeysetId,
+    amount: Amount,
+) -> Result<Option<PublicKey>> {
+    let mut stmt =
+        conn.prepare_cached("SELECT pubkey FROM key WHERE keyset_id = ?1 and amount = ?2 LIMIT 1")?;
+
+    let opt_pubkey = stmt
+        .query_row(params![keyset_id, amount], |r| r.get::<_, String>(0))
+        .optional()?
+        .map(|pk| {
+            PublicKey::from_str(&pk).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    0,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })
+        })
+        .transpose()?;
+
+    Ok(opt_pubkey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_tables_migrates_existing_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // A database created before the migrations
+        conn.execute(node::CREATE_TABLE_NODE, ()).unwrap();
+        conn.execute(keyset::CREATE_TABLE_KEYSET, ()).unwrap();
+        conn.execute(mint_quote::CREATE_TABLE_MINT_QUOTE, ())
+            .unwrap();
+        conn.execute(melt_quote::CREATE_TABLE_MELT_QUOTE, ())
+            .unwrap();
+        conn.execute("INSERT INTO node (id, url) VALUES (1, 'http://node')", ())
+            .unwrap();
+        conn.execute(
+            "INSERT INTO keyset (id, node_id, unit, active) VALUES (x'0001020304050607', 1, 'strk', TRUE)",
+            (),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO mint_quote (id, node_id, method, amount, unit, request, state, expiry) VALUES ('quote', 1, 'starknet', 10, 'strk', 'request', 1, 0)",
+            (),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO melt_quote (id, node_id, method, amount, unit, request, state, expiry, transfer_ids) VALUES ('quote', 1, 'starknet', 10, 'strk', 'request', 3, 0, '[\"0x1\"]')",
+            (),
+        )
+        .unwrap();
+        assert!(conn.execute("UPDATE melt_quote SET state = 4", ()).is_err());
+
+        create_tables(&mut conn).unwrap();
+        // Running it again is a no-op
+        create_tables(&mut conn).unwrap();
+
+        let user_version: usize = conn
+            .query_row("PRAGMA user_version", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(user_version, MIGRATIONS.len());
+        let input_fee_ppk: u64 = conn
+            .query_row("SELECT input_fee_ppk FROM keyset", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(input_fee_ppk, 0);
+        let final_expiry: Option<u64> = conn
+            .query_row("SELECT final_expiry FROM keyset", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(final_expiry, None);
+        let amount_paid: u64 = conn
+            .query_row("SELECT amount_paid FROM mint_quote", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(amount_paid, 0);
+        // The melt quotes are kept by the table rebuild
+        let transfer_ids: String = conn
+            .query_row("SELECT transfer_ids FROM melt_quote", (), |r| r.get(0))
+            .unwrap();
+        assert_eq!(transfer_ids, r#"["0x1"]"#);
+        conn.execute(
+            "UPDATE melt_quote SET state = 4, inputs = '[]' WHERE id = 'quote'",
+            (),
+        )
+        .unwrap();
+        assert_eq!(
+            melt_quote::get_state_and_inputs(&conn, "quote").unwrap(),
+            Some((nuts::nut05::MeltQuoteState::Failed, Some("[]".to_string())))
+        );
+    }
+}
@@ -1,7 +1,13 @@
 use rusqlite::{Connection, OptionalExtension, Result, params};
 
 use crate::types::ProofState;
-use nuts::{Amount, nut00::secret::Secret, nut01::PublicKey, nut02::KeysetId};
+use nuts::{
+    Amount,
+    nut00::{Proof, secret::Secret},
+    nut01::{PublicKey, SecretKey},
+    nut02::{KeysetId, calculate_input_fee},
+    nut12::ProofDleq,
+};
 
 pub const CREATE_TABLE_PROOF: &str = r#"
         CREATE TABLE IF NOT EXISTS proof (
@@ -19,6 +25,26 @@ pub const CREATE_TABLE_PROOF: &str = r#"
         CREATE INDEX proof_state ON proof(state);
     "#;
 
+/// NUT-12 DLEQ proof of the proofs signed by a node that supports it
+pub const CREATE_TABLE_PROOF_DLEQ: &str = r#"
+        CREATE TABLE IF NOT EXISTS proof_dleq (
+            y BLOB(33) PRIMARY KEY REFERENCES proof(y) ON DELETE CASCADE,
+            e BLOB(32) NOT NULL,
+            s BLOB(32) NOT NULL,
+            r BLOB(32) NOT NULL
+        );
+    "#;
+
+pub fn insert_proof_dleq(conn: &Connection, y: PublicKey, dleq: &ProofDleq) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO proof_dleq (y, e, s, r) VALUES (?1, ?2, ?3, ?4)
+           ON CONFLICT DO UPDATE SET e = excluded.e, s = excluded.s, r = excluded.r;"#,
+        params![y, dleq.e, dleq.s, dleq.r],
+    )?;
+
+    Ok(())
+}
+
 /// Fetch the proof info and set it to pending
 ///
 /// Will return None if the proof is already Pending.
@@ -104,18 +130,22 @@ pub struct GetProofsByIdsError(#[from] rusqlite::Error);
 ///
 /// Will error if any of those ids doesn't exist
 /// The order of the returned proofs is not guaranteed to match the input `proof_ids`.
+/// The DLEQ is only present for proofs signed by a node that supports NUT-12.
 #[allow(clippy::type_complexity)]
 pub fn get_proofs_by_ids(
     conn: &Connection,
     ys: &[PublicKey],
-) -> Result<Vec<(Amount, KeysetId, PublicKey, Secret)>, GetProofsByIdsError> {
+) -> Result<Vec<(Amount, KeysetId, PublicKey, Secret, Option<ProofDleq>)>, GetProofsByIdsError> {
     if ys.is_empty() {
         return Ok(Vec::new());
     }
 
     let placeholders = build_ys_placeholder_string_for_in_statement(ys.len());
     let sql = format!(
-        "SELECT amount, keyset_id, unblind_signature, secret FROM proof WHERE y IN ({})",
+        r#"SELECT amount, keyset_id, unblind_signature, secret, d.e, d.s, d.r
+           FROM proof p
+           LEFT JOIN proof_dleq d ON d.y = p.y
+           WHERE p.y IN ({})"#,
         placeholders
     );
 
@@ -127,16 +157,24 @@ pub fn get_proofs_by_ids(
 
     let proofs = stmt
         .raw_query()
-        .mapped(|r| -> Result<(Amount, KeysetId, PublicKey, Secret)> {
-            {
+        .mapped(
+            |r| -> Result<(Amount, KeysetId, PublicKey, Secret, Option<ProofDleq>)> {
+                let dleq = match (r.get(4)?, r.get(5)?, r.get(6)?) {
+                    (Some(e), Some(s), Some(blinding_factor)) => {
+                        Some(ProofDleq::new(e, s, blinding_factor))
+                    }
+                    _ => None,
+                };
+
                 Ok((
                     r.get::<_, Amount>(0)?,
                     r.get::<_, KeysetId>(1)?,
                     r.get::<_, PublicKey>(2)?,
                     r.get::<_, Secret>(3)?,
+                    dleq,
                 ))
-            }
-        })
+            },
+        )
         .collect::<Result<Vec<_>>>()?;
 
     Ok(proofs)
@@ -168,6 +206,100 @@ pub fn get_proofs_state_by_ids(conn: &Connection, ys: &[PublicKey]) -> Result<Ve
     Ok(proofs)
 }
 
+/// Returns the fee the node will charge for spending those proofs
+///
+/// Sum the `input_fee_ppk` of each proof keyset, rounded up to the next unit
+pub fn get_inputs_fee(conn: &Connection, ys: &[PublicKey]) -> Result<Amount> {
+    if ys.is_empty() {
+        return Ok(Amount::ZERO);
+    }
+
+    let placeholders = build_ys_placeholder_string_for_in_statement(ys.len());
+    let sql = format!(
+        r#"SELECT COALESCE(SUM(k.input_fee_ppk), 0)
+           FROM proof p
+           JOIN keyset k ON p.keyset_id = k.id
+           WHERE p.y IN ({})"#,
+        placeholders
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    for (i, y) in ys.iter().enumerate() {
+        stmt.raw_bind_parameter(i + 1, y)?;
+    }
+
+    let sum_fee_ppk = stmt
+        .raw_query()
+        .next()?
+        .map(|r| r.get::<_, u64>(0))
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(calculate_input_fee(sum_fee_ppk))
+}
+
+/// Returns the unspent proofs of this node whose keyset expires before `deadline`, with their unit
+///
+/// `deadline` is a unix timestamp. Keysets without a final expiry are never returned.
+pub fn get_unspent_proofs_expiring_before(
+    conn: &Connection,
+    node_id: u32,
+    deadline: u64,
+) -> Result<Vec<(String, PublicKey)>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT k.unit, p.y
+           FROM proof p
+           JOIN keyset k ON p.keyset_id = k.id
+           WHERE p.node_id = ?1 AND p.state = ?2 AND k.final_expiry < ?3"#,
+    )?;
+    let proofs = stmt
+        .query_map(params![node_id, ProofState::Unspent, deadline], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, PublicKey>(1)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(proofs)
+}
+
+/// Returns the proofs of this node with their state
+///
+/// The blinding factor is only known for the proofs signed by a node that supports NUT-12.
+#[allow(clippy::type_complexity)]
+pub fn get_node_proofs_with_blinding_factor(
+    conn: &Connection,
+    node_id: u32,
+) -> Result<
+    Vec<(
+        PublicKey,
+        KeysetId,
+        Amount,
+        Secret,
+        ProofState,
+        Option<SecretKey>,
+    )>,
+> {
+    let mut stmt = conn.prepare(
+        r#"SELECT p.y, p.keyset_id, p.amount, p.secret, p.state, d.r
+           FROM proof p
+           LEFT JOIN proof_dleq d ON d.y = p.y
+           WHERE p.node_id = ?1"#,
+    )?;
+    let proofs = stmt
+        .query_map(params![node_id], |r| {
+            Ok((
+                r.get::<_, PublicKey>(0)?,
+                r.get::<_, KeysetId>(1)?,
+                r.get::<_, Amount>(2)?,
+                r.get::<_, Secret>(3)?,
+                r.get::<_, ProofState>(4)?,
+                r.get::<_, Option<SecretKey>>(5)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(proofs)
+}
+
 /// Returns the maximum allowed amount (max_order) for a given keyset_id from the key table.
 pub fn get_max_order_for_keyset(
     conn: &rusqlite::Connection,
@@ -179,16 +311,46 @@ pub fn get_max_order_for_keyset(
     Ok(max_order)
 }
 
+/// Store a proof, along with its DLEQ proof if any
+pub fn insert_proof(
+    conn: &Connection,
+    y: PublicKey,
+    node_id: u32,
+    proof: &Proof,
+    state: ProofState,
+) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO proof (y, node_id, keyset_id, amount, secret, unblind_signature, state)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);"#,
+        params![
+            y,
+            node_id,
+            proof.keyset_id,
+            proof.amount,
+            proof.secret,
+            proof.c,
+            state
+        ],
+    )?;
+    if let Some(dleq) = &proof.dleq {
+        insert_proof_dleq(conn, y, dleq)?;
+    }
+
+    Ok(())
+}
+
 pub fn delete_proofs(conn: &Connection, ys: &[PublicKey]) -> Result<()> {
     let placeholders = build_ys_placeholder_string_for_in_statement(ys.len());
-    let sql = format!("DELETE FROM proof WHERE y IN ({})", placeholders);
-    let mut stmt = conn.prepare(&sql)?;
-    for (i, y) in ys.iter().enumerate() {
-        stmt.raw_bind_parameter(i + 1, y)?;
+    for table in ["proof_dleq", "proof"] {
+        let sql = format!("DELETE FROM {} WHERE y IN ({})", table, placeholders);
+        let mut stmt = conn.prepare(&sql)?;
+        for (i, y) in ys.iter().enumerate() {
+            stmt.raw_bind_parameter(i + 1, y)?;
+        }
+
+        stmt.raw_execute()?;
     }
 
-    stmt.raw_execute()?;
-
     Ok(())
 }
 
@@ -68,6 +68,8 @@ pub enum Error {
     Nut13(#[from] nuts::nut13::Error),
     #[error("bdhke error: {0}")]
     Dhke(#[from] nuts::dhke::Error),
+    #[error("the node signature for proof {0} does not match its DLEQ proof: {1}")]
+    InvalidDleq(PublicKey, #[source] nuts::nut12::Error),
     #[error("conversion error: {0}")]
     Conversion(String),
     #[error("nuts error: {0}")]
@@ -108,6 +110,7 @@ impl From<StoreNewProofsError> for Error {
             StoreNewProofsError::Rusqlite(error) => Error::Database(error),
             StoreNewProofsError::Nut01(error) => Error::Nut01(error),
             StoreNewProofsError::Dhke(error) => Error::Dhke(error),
+            StoreNewProofsError::InvalidDleq(y, error) => Error::InvalidDleq(y, error),
         }
     }
 }
@@ -0,0 +1,282 @@
+//! Hash time locked proofs (NUT-14)
+//!
+//! Used to exchange value atomically between two nodes:
+//! each party locks proofs on its node to the same hash,
+//! revealing the preimage to redeem one side unlocks the other.
+
+use cashu_client::{CashuClient, CashuClientError};
+use num_traits::{CheckedSub, Zero};
+use nuts::{
+    Amount, SplitTarget,
+    dhke::{self, blind_message, hash_to_curve, unblind_message},
+    nut00::{BlindedMessage, Proof, ProofsMethods, secret::Secret},
+    nut01::{PublicKey, SecretKey},
+    nut02::KeysetId,
+    nut11::{self, SpendingConditions},
+    nut12::ProofDleq,
+    nut19::{self, Route},
+};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+use crate::{
+    ReceiveWadError, acknowledge, db,
+    errors::{CommonError, handle_already_spent_proofs, handle_crypto_invalid_proofs},
+    get_active_keyset_for_unit, receive_wad,
+    types::{NodeUrl, ProofState, compact_wad::CompactKeysetProofs},
+    unprotected_load_tokens_from_db,
+    wallet::SeedPhraseManager,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CreateHtlcProofsError {
+    #[error(transparent)]
+    Common(#[from] CommonError),
+    #[error(transparent)]
+    UnprotectedLoadTokensFormDb(#[from] crate::UnprotectedLoadTokensFormDbError),
+    #[error("failed to get the active keyset for unit: {0}")]
+    ActiveKeyset(#[source] crate::Error),
+    #[error("failed to compute the inputs total amount: {0}")]
+    TotalAmount(#[from] nuts::nut00::Error),
+    #[error("failed to split the inputs total amount: {0}")]
+    SplitAmount(#[from] nuts::Error),
+    #[error("failed to build the locked secret: {0}")]
+    Nut11(#[from] nut11::Error),
+    #[error(transparent)]
+    Dhke(#[from] dhke::Error),
+    #[error("failed to interact with the database: {0}")]
+    Rusqlite(#[from] rusqlite::Error),
+    #[error("no key for amount {1} in keyset {0}")]
+    MissingKey(KeysetId, Amount),
+    #[error("the inputs amount {0} does not cover the node fee {1}")]
+    AmountLowerThanFee(Amount, Amount),
+    #[error("failed to swap proofs with node: {0}")]
+    SwapWithNode(#[source] CashuClientError),
+    #[error(transparent)]
+    SetProofsToState(#[from] db::proof::SetProofsToStateError),
+}
+
+/// Swap the wallet proofs `proofs_ids` for new proofs locked by `spending_conditions`
+///
+/// The node input fee is taken out of the inputs amount, what remains gets locked.
+/// The returned proofs are stored as reserved, as the wallet cannot spend them until they are unlocked.
+/// They are meant to be sent to the counterparty, using [`crate::wad::create_from_parts`].
+pub async fn create_htlc_proofs(
+    pool: Pool<SqliteConnectionManager>,
+    node_client: &mut impl CashuClient,
+    node_id: u32,
+    unit: &str,
+    proofs_ids: &[PublicKey],
+    spending_conditions: &SpendingConditions,
+) -> Result<Vec<Proof>, CreateHtlcProofsError> {
+    let (inputs, keyset_id, fee) = {
+        let mut db_conn = pool.get().map_err(CommonError::GetDbConnection)?;
+        let tx = db_conn
+            .transaction()
+            .map_err(CommonError::CreateDbTransaction)?;
+        let inputs = unprotected_load_tokens_from_db(&tx, proofs_ids)?;
+        let (keyset_id, _) = get_active_keyset_for_unit(&tx, node_id, unit)
+            .map_err(CreateHtlcProofsError::ActiveKeyset)?;
+        let fee = db::proof::get_inputs_fee(&tx, proofs_ids)?;
+        tx.commit().map_err(CommonError::CommitDbTransaction)?;
+
+        (inputs, keyset_id, fee)
+    };
+
+    // The node keeps its input fee, only what remains gets locked
+    let total_amount = inputs.total_amount()?;
+    let locked_amount = total_amount
+        .checked_sub(&fee)
+        .filter(|a| !a.is_zero())
+        .ok_or(CreateHtlcProofsError::AmountLowerThanFee(total_amount, fee))?;
+    let mut secrets = Vec::new();
+    let mut outputs = Vec::new();
+    for amount in locked_amount.split_targeted(&SplitTarget::None)? {
+        // Each secret gets its own random nonce
+        let secret: Secret = spending_conditions.clone().try_into()?;
+        let (blinded_secret, r) = blind_message(secret.as_bytes(), None)?;
+        outputs.push(BlindedMessage {
+            amount,
+            keyset_id,
+            blinded_secret,
+        });
+        secrets.push((secret, r));
+    }
+
+    let swap_request = nuts::nut03::SwapRequest { inputs, outputs };
+    let swap_request_hash = nut19::hash_swap_request(&swap_request);
+    let swap_result = node_client.swap(swap_request.clone()).await;
+
+    let mut db_conn = pool.get().map_err(CommonError::GetDbConnection)?;
+    let swap_response = match swap_result {
+        Ok(r) => r,
+        Err(e) => {
+            db::proof::set_proofs_to_state(&db_conn, proofs_ids, ProofState::Unspent)?;
+            if let CashuClientError::Proof(errors) = &e {
+                if !errors[0].indexes.is_empty() {
+                    handle_already_spent_proofs(errors[0].indexes.clone(), proofs_ids, &db_conn)
+                        .map_err(CommonError::HandleProofVerificationErrors)?;
+                }
+                if !errors[1].indexes.is_empty() {
+                    handle_crypto_invalid_proofs(errors[1].indexes.clone(), proofs_ids, &db_conn)
+                        .map_err(CommonError::HandleProofVerificationErrors)?;
+                }
+            }
+
+            return Err(CreateHtlcProofsError::SwapWithNode(e));
+        }
+    };
+
+    // The locked proofs are kept, so that they can still be refunded if we crash before sending them
+    let tx = db_conn
+        .transaction()
+        .map_err(CommonError::CreateDbTransaction)?;
+    db::proof::set_proofs_to_state(&tx, proofs_ids, ProofState::Spent)?;
+    let mut locked_proofs = Vec::with_capacity(secrets.len());
+    for ((blind_signature, output), (secret, r)) in swap_response
+        .signatures
+        .into_iter()
+        .zip(swap_request.outputs)
+        .zip(secrets)
+    {
+        let node_key_pubkey = db::get_keyset_key(&tx, keyset_id, output.amount)?
+            .ok_or(CreateHtlcProofsError::MissingKey(keyset_id, output.amount))?;
+        let c = unblind_message(&blind_signature.c, &r, &node_key_pubkey)?;
+        let y = hash_to_curve(secret.as_ref())?;
+
+        let proof = Proof {
+            amount: output.amount,
+            keyset_id,
+            secret,
+            c,
+            witness: None,
+            dleq: blind_signature
+                .dleq
+                .map(|dleq| ProofDleq::new(dleq.e, dleq.s, r)),
+        };
+        db::proof::insert_proof(&tx, y, node_id, &proof, ProofState::Reserved)?;
+        locked_proofs.push(proof);
+    }
+    tx.commit().map_err(CommonError::CommitDbTransaction)?;
+
+    acknowledge(node_client, Route::Swap, swap_request_hash)
+        .await
+        .map_err(|e| CommonError::AcknowledgeNodeResponse(nut19::SWAP, e))?;
+
+    Ok(locked_proofs)
+}
+
+/// Add the witness required to spend HTLC locked proofs
+///
+/// `preimage` unlocks the hash lock, `signing_key` is required when the conditions
+/// contain pubkeys, or alone to get refunded once the locktime has passed.
+pub fn unlock_htlc_proofs(
+    compact_keyset_proofs: &mut [CompactKeysetProofs],
+    preimage: Option<&str>,
+    signing_key: Option<&SecretKey>,
+) -> Result<(), nut11::Error> {
+    for compact_keyset_proof in compact_keyset_proofs.iter_mut() {
+        for compact_proof in compact_keyset_proof.proofs.iter_mut() {
+            let mut proof = compact_proof.proof(&compact_keyset_proof.keyset_id);
+            if let Some(preimage) = preimage {
+                proof.add_preimage(preimage.to_string());
+            }
+            if let Some(signing_key) = signing_key {
+                proof.sign_p2pk(signing_key.clone())?;
+            }
+            compact_proof.witness = proof.witness;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RedeemHtlcWadError {
+    #[error("failed to unlock the htlc proofs: {0}")]
+    Unlock(#[from] nut11::Error),
+    #[error(transparent)]
+    ReceiveWad(#[from] ReceiveWadError),
+}
+
+/// Unlock the HTLC proofs of a wad and swap them for new proofs of our own
+#[allow(clippy::too_many_arguments)]
+pub async fn redeem_htlc_wad(
+    seed_phrase_manager: impl SeedPhraseManager,
+    pool: Pool<SqliteConnectionManager>,
+    node_client: &mut impl CashuClient,
+    node_id: u32,
+    node_url: &NodeUrl,
+    unit: &str,
+    mut compact_keyset_proofs: Vec<CompactKeysetProofs>,
+    memo: &Option<String>,
+    preimage: Option<&str>,
+    signing_key: Option<&SecretKey>,
+) -> Result<Amount, RedeemHtlcWadError> {
+    unlock_htlc_proofs(&mut compact_keyset_proofs, preimage, signing_key)?;
+
+    let amount = receive_wad(
+        seed_phrase_manager,
+        pool,
+        node_client,
+        node_id,
+        node_url,
+        unit,
+        compact_keyset_proofs,
+        memo,
+    )
+    .await?;
+
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use nuts::nut11::Conditions;
+
+    use super::*;
+    use crate::types::compact_wad::CompactProof;
+
+    const PREIMAGE: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+    #[test]
+    fn unlocked_proofs_satisfy_htlc_conditions() {
+        let signing_key = SecretKey::generate();
+        let conditions = Conditions {
+            pubkeys: Some(vec![signing_key.public_key()]),
+            ..Default::default()
+        };
+        let spending_conditions = SpendingConditions::new_htlc(PREIMAGE, Some(conditions)).unwrap();
+        let keyset_id = KeysetId::from_bytes(&[0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+        let c = PublicKey::from_str(
+            "02698c4e2b5f9534cd0687d87513c759790cf829aa5739184a3e3735471fbda904",
+        )
+        .unwrap();
+
+        let mut compact_keyset_proofs = vec![CompactKeysetProofs {
+            keyset_id,
+            proofs: vec![CompactProof {
+                amount: Amount::ONE,
+                secret: spending_conditions.try_into().unwrap(),
+                c,
+                witness: None,
+                dleq: None,
+            }],
+        }];
+
+        unlock_htlc_proofs(&mut compact_keyset_proofs, Some(PREIMAGE), None).unwrap();
+        let proof = compact_keyset_proofs[0].proofs[0].proof(&keyset_id);
+        assert!(proof.verify_htlc().is_err());
+
+        unlock_htlc_proofs(
+            &mut compact_keyset_proofs,
+            Some(PREIMAGE),
+            Some(&signing_key),
+        )
+        .unwrap();
+        let proof = compact_keyset_proofs[0].proofs[0].proof(&keyset_id);
+        assert!(proof.verify_htlc().is_ok());
+    }
+}
@@ -0,0 +1,260 @@
+//! Check the node proof of liabilities against the wallet history
+//!
+//! The blinded messages of the wallet proofs must be part of the issuance trees of the report,
+//! and its spent proofs of the burn ones. A leaf proven with another amount or keyset than the
+//! wallet one means the node misreports its liabilities.
+//!
+//! Only the proofs whose blinding factor was kept, those signed with a NUT-12 DLEQ,
+//! can have their blinded message recomputed and be checked against the issuance trees.
+
+use std::collections::{HashMap, HashSet};
+
+use cashu_client::GrpcClient;
+use node_client::{
+    GetLiabilitiesInclusionProofsRequest, GetLiabilitiesReportRequest, KeysetLiabilities,
+    LiabilitiesInclusionProof, MerkleSumNode,
+};
+use nuts::{Amount, dhke, nut01::PublicKey, nut02::KeysetId};
+use proof_of_liabilities::{InclusionProof, Node};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+
+use crate::{db, types::ProofState};
+
+/// Maximum number of points the node proves per request
+const MAX_POINTS_PER_REQUEST: usize = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CheckLiabilitiesError {
+    #[error("failed to get a connection from the pool: {0}")]
+    R2d2(#[from] r2d2::Error),
+    #[error("fail to interact with the database: {0}")]
+    Rusqlite(#[from] rusqlite::Error),
+    #[error("failed to get the liabilities report: {0}")]
+    Grpc(#[from] tonic::Status),
+    #[error("failed to recompute the blinded message: {0}")]
+    Dhke(#[from] dhke::Error),
+    #[error("invalid public key in node response: {0}")]
+    Nut01(#[from] nuts::nut01::Error),
+    #[error("invalid keyset id in node response: {0}")]
+    Nut02(#[from] nuts::nut02::Error),
+    #[error("malformed Merkle sum tree node in node response")]
+    MalformedNode,
+    #[error("the node proved {0}, which was not asked for")]
+    UnexpectedPoint(PublicKey),
+    #[error("the node reported {0} under keyset {1} instead of {2}")]
+    KeysetMismatch(PublicKey, KeysetId, KeysetId),
+    #[error("the node reported {0} with amount {1} instead of {2}")]
+    AmountMismatch(PublicKey, Amount, Amount),
+    #[error("the report has no commitments for keyset {0}")]
+    UnreportedKeyset(KeysetId),
+    #[error("invalid inclusion proof for {0}: {1}")]
+    InvalidProof(PublicKey, #[source] proof_of_liabilities::Error),
+}
+
+/// Outcome of [`check_liabilities`]
+///
+/// Points are identified by the `Y` of the wallet proof they relate to.
+/// A missing point is either issued or spent after the report was created, or left out by the node.
+#[derive(Debug, Clone)]
+pub struct LiabilitiesCheck {
+    pub report_id: u64,
+    /// Unix timestamp
+    pub report_created_at: u64,
+    pub issued_included: Vec<PublicKey>,
+    pub issued_missing: Vec<PublicKey>,
+    pub burned_included: Vec<PublicKey>,
+    pub burned_missing: Vec<PublicKey>,
+}
+
+/// What the wallet knows about a leaf
+struct ExpectedLeaf {
+    y: PublicKey,
+    keyset_id: KeysetId,
+    amount: Amount,
+}
+
+fn parse_node(node: Option<&MerkleSumNode>) -> Result<Node, CheckLiabilitiesError> {
+    let node = node.ok_or(CheckLiabilitiesError::MalformedNode)?;
+    let hash = node
+        .hash
+        .as_slice()
+        .try_into()
+        .map_err(|_| CheckLiabilitiesError::MalformedNode)?;
+
+    Ok(Node::new(hash, Amount::from(node.amount)))
+}
+
+/// Verify a proof returned by the node, returning the `Y` of the wallet proof it relates to
+fn verify_inclusion_proof(
+    expected_leaves: &HashMap<PublicKey, ExpectedLeaf>,
+    keysets: &HashMap<KeysetId, &KeysetLiabilities>,
+    burned: bool,
+    proof: &LiabilitiesInclusionProof,
+) -> Result<PublicKey, CheckLiabilitiesError> {
+    let point = PublicKey::from_slice(&proof.point)?;
+    let expected = expected_leaves
+        .get(&point)
+        .ok_or(CheckLiabilitiesError::UnexpectedPoint(point))?;
+    let keyset_id = KeysetId::from_bytes(&proof.keyset_id)?;
+    if keyset_id != expected.keyset_id {
+        return Err(CheckLiabilitiesError::KeysetMismatch(
+            point,
+            keyset_id,
+            expected.keyset_id,
+        ));
+    }
+    let amount = Amount::from(proof.amount);
+    if amount != expected.amount {
+        return Err(CheckLiabilitiesError::AmountMismatch(
+            point,
+            amount,
+            expected.amount,
+        ));
+    }
+
+    let commitments = keysets
+        .get(&keyset_id)
+        .ok_or(CheckLiabilitiesError::UnreportedKeyset(keyset_id))?;
+    let (root, leaf_count) = if burned {
+        (
+            parse_node(commitments.burned_root.as_ref())?,
+            commitments.burned_count,
+        )
+    } else {
+        (
+            parse_node(commitments.issued_root.as_ref())?,
+            commitments.issued_count,
+        )
+    };
+    let siblings = proof
+        .siblings
+        .iter()
+        .map(|n| parse_node(Some(n)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // The tree size is taken from the report, not from the node response
+    InclusionProof {
+        leaf_index: proof.leaf_index,
+        leaf_count,
+        siblings,
+    }
+    .verify(Node::leaf(&point, amount), &root)
+    .map_err(|e| CheckLiabilitiesError::InvalidProof(point, e))?;
+
+    Ok(expected.y)
+}
+
+/// Check that the node liabilities report accounts for this wallet proofs
+///
+/// Uses the latest report if `report_id` is `None`.
+/// Errors if the node proves a leaf that doesn't match the wallet history.
+pub async fn check_liabilities(
+    pool: Pool<SqliteConnectionManager>,
+    node_client: &mut GrpcClient,
+    node_id: u32,
+    report_id: Option<u64>,
+) -> Result<LiabilitiesCheck, CheckLiabilitiesError> {
+    let report = node_client
+        .node
+        .get_liabilities_report(GetLiabilitiesReportRequest { report_id })
+        .await?
+        .into_inner();
+    let keysets = report
+        .keysets
+        .iter()
+        .map(|k| Ok((KeysetId::from_bytes(&k.keyset_id)?, k)))
+        .collect::<Result<HashMap<_, _>, CheckLiabilitiesError>>()?;
+
+    // Blinded messages and spent `Y`s, mapped to what the wallet knows about them
+    let mut issued = HashMap::new();
+    let mut burned = HashMap::new();
+    {
+        let db_conn = pool.get()?;
+        for (y, keyset_id, amount, secret, state, blinding_factor) in
+            db::proof::get_node_proofs_with_blinding_factor(&db_conn, node_id)?
+        {
+            if let Some(r) = blinding_factor {
+                let (blinded_message, _) = dhke::blind_message(secret.as_bytes(), Some(r))?;
+                issued.insert(
+                    blinded_message,
+                    ExpectedLeaf {
+                        y,
+                        keyset_id,
+                        amount,
+                    },
+                );
+            }
+            if state == ProofState::Spent {
+                burned.insert(
+                    y,
+                    ExpectedLeaf {
+                        y,
+                        keyset_id,
+                        amount,
+                    },
+                );
+            }
+        }
+    }
+
+    let mut issued_included = Vec::new();
+    let issued_points: Vec<_> = issued.keys().copied().collect();
+    for chunk in issued_points.chunks(MAX_POINTS_PER_REQUEST) {
+        let response = node_client
+            .node
+            .get_liabilities_inclusion_proofs(GetLiabilitiesInclusionProofsRequest {
+                report_id: report.id,
+                blinded_messages: chunk.iter().map(|p| p.to_bytes().to_vec()).collect(),
+                ys: vec![],
+            })
+            .await?
+            .into_inner();
+        for proof in &response.issued {
+            issued_included.push(verify_inclusion_proof(&issued, &keysets, false, proof)?);
+        }
+    }
+
+    let mut burned_included = Vec::new();
+    let burned_points: Vec<_> = burned.keys().copied().collect();
+    for chunk in burned_points.chunks(MAX_POINTS_PER_REQUEST) {
+        let response = node_client
+            .node
+            .get_liabilities_inclusion_proofs(GetLiabilitiesInclusionProofsRequest {
+                report_id: report.id,
+                blinded_messages: vec![],
+                ys: chunk.iter().map(|p| p.to_bytes().to_vec()).collect(),
+            })
+            .await?
+            .into_inner();
+        for proof in &response.burned {
+            burned_included.push(verify_inclusion_proof(&burned, &keysets, true, proof)?);
+        }
+    }
+
+    let issued_missing = {
+        let included: HashSet<_> = issued_included.iter().collect();
+        issued
+            .values()
+            .map(|l| l.y)
+            .filter(|y| !included.contains(y))
+            .collect()
+    };
+    let burned_missing = {
+        let included: HashSet<_> = burned_included.iter().collect();
+        burned
+            .keys()
+            .copied()
+            .filter(|y| !included.contains(y))
+            .collect()
+    };
+
+    Ok(LiabilitiesCheck {
+        report_id: report.id,
+        report_created_at: report.created_at,
+        issued_included,
+        issued_missing,
+        burned_included,
+        burned_missing,
+    })
+}
@@ -1,5 +1,7 @@
 pub mod db;
 pub mod errors;
+pub mod htlc;
+pub mod liabilities;
 pub mod melt;
 pub mod mint;
 pub mod node;
@@ -14,18 +16,18 @@ pub mod wallet;
 use cashu_client::{CashuClient, GrpcClient};
 use errors::{CommonError, Error};
 use node_client::NodeClient;
-use num_traits::{CheckedAdd, Zero};
+use num_traits::{CheckedAdd, CheckedSub, Zero};
 use nuts::dhke::{self, hash_to_curve, unblind_message};
 use nuts::nut00::secret::Secret;
 use nuts::nut00::{self, BlindedMessage, Proof};
 use nuts::nut01::{self, PublicKey, SecretKey};
-use nuts::nut02::KeysetId;
+use nuts::nut02::{KeysetId, calculate_input_fee};
+use nuts::nut12::{self, BlindSignatureDleq, ProofDleq};
 use nuts::nut19::{Route, hash_swap_request};
 use nuts::{Amount, SplitTarget};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{Connection, Transaction, params};
-use std::str::FromStr;
 use types::compact_wad::CompactKeysetProofs;
 use types::{BlindingData, NodeUrl, PreMints, ProofState};
 use wallet::SeedPhraseManager;
@@ -40,6 +42,7 @@ pub fn convert_inputs(inputs: &[Proof]) -> Vec<node_client::Proof> {
             keyset_id: p.keyset_id.to_bytes().to_vec(),
             secret: p.secret.to_string(),
             unblind_signature: p.c.to_bytes().to_vec(),
+            witness: p.witness.as_ref().map(|w| w.to_string()),
         })
         .collect()
 }
@@ -79,8 +82,14 @@ pub async fn read_or_import_node_keyset(
 
     let db_conn = pool.get()?;
     db_conn.execute(
-        "INSERT INTO keyset (id, node_id, unit, active) VALUES (?1, ?2, ?3, ?4)",
-        params![keyset_id_as_bytes, node_id, &keyset.unit, keyset.active],
+        "INSERT INTO keyset (id, node_id, unit, active, input_fee_ppk) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            keyset_id_as_bytes,
+            node_id,
+            &keyset.unit,
+            keyset.active,
+            keyset.input_fee_ppk
+        ],
     )?;
 
     let pairs: Vec<(u64, String)> = keyset
@@ -117,19 +126,31 @@ pub enum StoreNewProofsError {
     Nut01(#[from] nut01::Error),
     #[error(transparent)]
     Dhke(#[from] dhke::Error),
+    #[error("the node signature for proof {0} does not match its DLEQ proof: {1}")]
+    InvalidDleq(PublicKey, #[source] nut12::Error),
 }
 
+/// Unblind and store the signatures returned by the node
+///
+/// When a signature comes with a DLEQ proof, it is verified against the keyset key
+/// and stored alongside the proof, so that it can be shared with the proof later on.
 pub fn store_new_proofs_from_blind_signatures(
     tx: &Transaction,
     node_id: u32,
     keyset_id: KeysetId,
     signatures_iterator: impl IntoIterator<
-        Item = Result<(PublicKey, Secret, SecretKey, Amount), nut01::Error>,
+        Item = Result<
+            (
+                PublicKey,
+                Secret,
+                SecretKey,
+                Amount,
+                Option<BlindSignatureDleq>,
+            ),
+            nut01::Error,
+        >,
     >,
 ) -> Result<Vec<(PublicKey, Amount)>, StoreNewProofsError> {
-    const GET_PUBKEY: &str = r#"
-        SELECT pubkey FROM key WHERE keyset_id = ?1 and amount = ?2 LIMIT 1;
-    "#;
     const INSERT_PROOF: &str = r#"
         INSERT INTO proof
             (y, node_id, keyset_id, amount, secret, unblind_signature, state)
@@ -145,21 +166,37 @@ pub fn store_new_proofs_from_blind_signatures(
     "#;
     let mut new_tokens = Vec::new();
 
-    let mut get_pubkey_stmt = tx.prepare(GET_PUBKEY)?;
     let mut insert_proof_stmt = tx.prepare(INSERT_PROOF)?;
 
     for res in signatures_iterator {
-        let (blinded_message, secret, r, amount) = res?;
+        let (blinded_message, secret, r, amount, dleq) = res?;
 
-        let node_key_pubkey = PublicKey::from_str(
-            &get_pubkey_stmt
-                .query_row(params![keyset_id, amount], |row| row.get::<_, String>(0))?,
-        )?;
+        let node_key_pubkey = db::get_keyset_key(tx, keyset_id, amount)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
         let unblinded_signature: PublicKey =
             unblind_message(&blinded_message, &r, &node_key_pubkey)?;
 
         let y = hash_to_curve(secret.as_ref())?;
 
+        let proof_dleq = match dleq {
+            Some(dleq) => {
+                let proof = Proof {
+                    amount,
+                    keyset_id,
+                    secret: secret.clone(),
+                    c: unblinded_signature,
+                    witness: None,
+                    dleq: Some(ProofDleq::new(dleq.e, dleq.s, r)),
+                };
+                proof
+                    .verify_dleq(node_key_pubkey)
+                    .map_err(|e| StoreNewProofsError::InvalidDleq(y, e))?;
+
+                proof.dleq
+            }
+            None => None,
+        };
+
         insert_proof_stmt.execute(params![
             &y,
             node_id,
@@ -169,6 +206,9 @@ pub fn store_new_proofs_from_blind_signatures(
             &unblinded_signature,
             ProofState::Unspent,
         ])?;
+        if let Some(proof_dleq) = proof_dleq {
+            db::proof::insert_proof_dleq(tx, y, &proof_dleq)?;
+        }
 
         new_tokens.push((y, amount));
     }
@@ -198,21 +238,27 @@ pub async fn fetch_inputs_ids_from_db_or_node(
         }
 
         let mut stmt = db_conn.prepare(
-            "SELECT p.y, p.amount 
+            "SELECT p.y, p.amount, k.input_fee_ppk 
                     FROM proof p 
                     JOIN keyset k ON p.keyset_id = k.id 
                     WHERE p.node_id = ?1 AND p.state = ?2 AND k.unit = ?3 
                   ORDER BY p.amount DESC;",
         )?;
-        let proofs_res_iterator = stmt
-            .query_map(params![node_id, ProofState::Unspent, unit], |r| {
-                Ok((r.get::<_, PublicKey>(0)?, r.get::<_, Amount>(1)?))
+        let proofs_res_iterator =
+            stmt.query_map(params![node_id, ProofState::Unspent, unit], |r| {
+                Ok((
+                    r.get::<_, PublicKey>(0)?,
+                    r.get::<_, Amount>(1)?,
+                    r.get::<_, u64>(2)?,
+                ))
             })?;
 
         for proof_res in proofs_res_iterator {
-            let (y, proof_amount) = proof_res?;
+            let (y, proof_amount, input_fee_ppk) = proof_res?;
             match remaining_amount.cmp(&proof_amount) {
-                std::cmp::Ordering::Less => proofs_not_used.push((y, proof_amount)),
+                std::cmp::Ordering::Less => {
+                    proofs_not_used.push((y, proof_amount, calculate_input_fee(input_fee_ppk)))
+                }
                 std::cmp::Ordering::Equal => {
                     proofs_ids.push(y);
                     remaining_amount -= proof_amount;
@@ -227,14 +273,17 @@ pub async fn fetch_inputs_ids_from_db_or_node(
     }
 
     if !remaining_amount.is_zero() {
-        let proof_to_swap = proofs_not_used
+        // We know that total_amount_available was >= target_amount
+        // We know it cannot be equal to remaining amount otherwise we would have subtracted it
+        // So there must be one greater stored in proofs_not_used,
+        // but it also has to cover the fee the node will charge us to swap it
+        let Some(&(y, proof_amount, _)) = proofs_not_used
             .iter()
             .rev()
-            .find(|(_, a)| a > &remaining_amount)
-            // We know that total_amount_available was >= target_amount
-            // We know it cannot be equal to remaining amount otherwise we would have subtracted it
-            // So there must be one greater stored in proofs_not_used
-            .unwrap();
+            .find(|(_, a, fee)| a.checked_sub(fee).is_some_and(|a| a >= remaining_amount))
+        else {
+            return Ok(None);
+        };
 
         let new_tokens = swap_to_have_target_amount(
             seed_phrase_manager,
@@ -243,7 +292,7 @@ pub async fn fetch_inputs_ids_from_db_or_node(
             node_id,
             unit,
             remaining_amount,
-            proof_to_swap,
+            &(y, proof_amount),
         )
         .await?;
 
@@ -285,11 +334,13 @@ pub fn unprotected_load_tokens_from_db(
     let proofs = db::proof::get_proofs_by_ids(db_conn, proofs_ids)?
         .into_iter()
         .map(
-            |(amount, keyset_id, unblinded_signature, secret)| nut00::Proof {
+            |(amount, keyset_id, unblinded_signature, secret, dleq)| nut00::Proof {
                 amount,
                 keyset_id,
                 secret,
                 c: unblinded_signature,
+                witness: None,
+                dleq,
             },
         )
         .collect();
@@ -299,6 +350,10 @@ pub fn unprotected_load_tokens_from_db(
     Ok(proofs)
 }
 
+/// Swap a single proof into new ones, some of them adding up to `target_amount`
+///
+/// The node input fee is taken out of the swapped proof amount,
+/// which must then be at least `target_amount` plus the fee.
 pub async fn swap_to_have_target_amount(
     seed_phrase_manager: impl SeedPhraseManager,
     pool: Pool<SqliteConnectionManager>,
@@ -308,32 +363,80 @@ pub async fn swap_to_have_target_amount(
     target_amount: Amount,
     proof_to_swap: &(PublicKey, Amount),
 ) -> Result<Vec<(PublicKey, Amount)>, Error> {
-    let (blinding_data, input_unblind_signature) = {
-        let db_conn = pool.get()?;
+    swap_proofs(
+        seed_phrase_manager,
+        pool,
+        node_client,
+        node_id,
+        unit,
+        &[proof_to_swap.0],
+        SplitTarget::Value(target_amount),
+    )
+    .await
+}
+
+/// Swap the unspent proofs `ys`, all of `unit`, into new ones split following `split_target`
+///
+/// The node input fee is taken out of the swapped proofs amount, what remains must not be zero,
+/// nor lower than the `split_target` value.
+pub async fn swap_proofs(
+    seed_phrase_manager: impl SeedPhraseManager,
+    pool: Pool<SqliteConnectionManager>,
+    node_client: &mut impl CashuClient,
+    node_id: u32,
+    unit: &str,
+    ys: &[PublicKey],
+    split_target: SplitTarget,
+) -> Result<Vec<(PublicKey, Amount)>, Error> {
+    let (blinding_data, inputs, amount) = {
+        let mut db_conn = pool.get()?;
+        let tx = db_conn.transaction()?;
 
-        let blinding_data =
-            BlindingData::load_from_db(seed_phrase_manager, &db_conn, node_id, unit)?;
+        let blinding_data = BlindingData::load_from_db(seed_phrase_manager, &tx, node_id, unit)?;
 
-        let input_unblind_signature =
-            db::proof::get_proof_and_set_state_pending(&db_conn, proof_to_swap.0)?
-                .ok_or(Error::ProofNotAvailable)?;
+        let proofs = db::proof::get_proofs_by_ids(&tx, ys)?;
+        let total_amount = proofs
+            .iter()
+            .try_fold(Amount::ZERO, |acc, (amount, ..)| acc.checked_add(amount))
+            .ok_or(Error::AmountOverflow)?;
+        let fee = db::proof::get_inputs_fee(&tx, ys)?;
+        let amount = total_amount
+            .checked_sub(&fee)
+            .filter(|a| {
+                !a.is_zero()
+                    && match &split_target {
+                        SplitTarget::Value(target_amount) => a >= target_amount,
+                        _ => true,
+                    }
+            })
+            .ok_or(Error::NotEnoughFunds)?;
 
-        (blinding_data, input_unblind_signature)
-    };
+        for y in ys {
+            db::proof::get_proof_and_set_state_pending(&tx, *y)?.ok_or(Error::ProofNotAvailable)?;
+        }
+        tx.commit()?;
 
-    let pre_mints = PreMints::generate_for_amount(
-        proof_to_swap.1,
-        &SplitTarget::Value(target_amount),
-        blinding_data,
-    )?;
+        let inputs: Vec<_> = proofs
+            .into_iter()
+            .map(|(amount, keyset_id, c, secret, _)| nut00::Proof {
+                amount,
+                keyset_id,
+                secret,
+                c,
+                witness: None,
+                dleq: None,
+            })
+            .collect();
 
-    let inputs = vec![nuts::nut00::Proof {
-        amount: proof_to_swap.1,
-        keyset_id: input_unblind_signature.0,
-        secret: input_unblind_signature.2,
-        c: input_unblind_signature.1,
-    }];
+        (blinding_data, inputs, amount)
+    };
+    // Same order as the inputs, which the node errors refer to
+    let ys = inputs
+        .iter()
+        .map(|proof| hash_to_curve(proof.secret.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
 
+    let pre_mints = PreMints::generate_for_amount(amount, &split_target, blinding_data)?;
     let outputs = pre_mints.build_nuts_outputs();
 
     let swap_request = nuts::nut03::SwapRequest { inputs, outputs };
@@ -343,10 +446,7 @@ pub async fn swap_to_have_target_amount(
     let new_tokens = {
         let mut db_conn = pool.get()?;
         let swap_response = match swap_result {
-            Ok(r) => {
-                db::proof::set_proof_to_state(&db_conn, proof_to_swap.0, ProofState::Spent)?;
-                r
-            }
+            Ok(r) => r,
             Err(e) => {
                 // TODO: add retry once we are sync
                 match &e {
@@ -354,14 +454,14 @@ pub async fn swap_to_have_target_amount(
                         if !proof_errors[0].indexes.is_empty() {
                             handle_already_spent_proofs(
                                 proof_errors[0].indexes.clone(),
-                                &[proof_to_swap.0],
+                                &ys,
                                 &db_conn,
                             )?;
                         }
                         if !proof_errors[1].indexes.is_empty() {
                             handle_crypto_invalid_proofs(
                                 proof_errors[1].indexes.clone(),
-                                &[proof_to_swap.0],
+                                &ys,
                                 &db_conn,
                             )?;
                         }
@@ -376,6 +476,7 @@ pub async fn swap_to_have_target_amount(
         };
 
         let tx = db_conn.transaction()?;
+        db::proof::set_proofs_to_state(&tx, &ys, ProofState::Spent)?;
         let new_tokens = pre_mints.store_new_tokens(&tx, node_id, swap_response.signatures)?;
         tx.commit()?;
 
@@ -409,6 +510,16 @@ pub enum ReceiveWadError {
     SetProofsToState(#[from] db::proof::SetProofsToStateError),
     #[error(transparent)]
     UpdateWadStatus(#[from] db::wad::UpdateWadStatusError),
+    #[error("failed to get the keyset key: {0}")]
+    GetKeysetKey(#[source] rusqlite::Error),
+    #[error("no key for amount {1} in keyset {0}")]
+    MissingKey(KeysetId, Amount),
+    #[error("failed to compute the node input fee: {0}")]
+    GetInputsFee(#[source] rusqlite::Error),
+    #[error("wad amount {0} does not cover the node input fee {1}")]
+    AmountLowerThanFee(Amount, Amount),
+    #[error("the node signature for proof {0} does not match its DLEQ proof: {1}")]
+    InvalidDleq(PublicKey, #[source] nut12::Error),
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -472,12 +583,20 @@ pub async fn receive_wad(
                 .checked_add(&compact_proof.amount)
                 .ok_or(ReceiveWadError::TotalWadAmountOverflow)?;
 
-            inputs.push(Proof {
-                amount: amount.into(),
-                keyset_id: compact_keyset_proof.keyset_id,
-                secret: compact_proof.secret.clone(),
-                c: compact_proof.c,
-            });
+            // Make sure the node really signed this proof before going any further
+            let proof = compact_proof.proof(&compact_keyset_proof.keyset_id);
+            if proof.dleq.is_some() {
+                let node_key_pubkey = {
+                    let db_conn = pool.get().map_err(CommonError::GetDbConnection)?;
+                    db::get_keyset_key(&db_conn, proof.keyset_id, proof.amount)
+                        .map_err(ReceiveWadError::GetKeysetKey)?
+                        .ok_or(ReceiveWadError::MissingKey(proof.keyset_id, proof.amount))?
+                };
+                proof
+                    .verify_dleq(node_key_pubkey)
+                    .map_err(|e| ReceiveWadError::InvalidDleq(y, e))?;
+            }
+            inputs.push(proof);
             stmt_params.push((
                 y,
                 node_id,
@@ -490,7 +609,7 @@ pub async fn receive_wad(
         }
     }
 
-    let (wad_id, blinding_data) = {
+    let (wad_id, blinding_data, fee) = {
         let mut db_conn = pool.get().map_err(CommonError::GetDbConnection)?;
         let tx = db_conn
             .transaction()
@@ -510,14 +629,21 @@ pub async fn receive_wad(
             .map_err(ReceiveWadError::RegisterWad)?;
         let binding_data = BlindingData::load_from_db(seed_phrase_manager, &tx, node_id, unit)
             .map_err(CommonError::LoadBlindingData)?;
+        let fee = db::proof::get_inputs_fee(&tx, &ys).map_err(ReceiveWadError::GetInputsFee)?;
 
         tx.commit().map_err(CommonError::CommitDbTransaction)?;
 
-        (wad_id, binding_data)
+        (wad_id, binding_data, fee)
     };
 
-    let pre_mints = PreMints::generate_for_amount(total_amount, &SplitTarget::None, blinding_data)
-        .map_err(|e| CommonError::GeneratePremintsForAmount(total_amount, e))?;
+    // The node keeps its input fee, we only get back what remains
+    let received_amount = total_amount
+        .checked_sub(&fee)
+        .filter(|a| !a.is_zero())
+        .ok_or(ReceiveWadError::AmountLowerThanFee(total_amount, fee))?;
+    let pre_mints =
+        PreMints::generate_for_amount(received_amount, &SplitTarget::None, blinding_data)
+            .map_err(|e| CommonError::GeneratePremintsForAmount(received_amount, e))?;
     let outputs = pre_mints.build_nuts_outputs();
 
     let swap_request = nuts::nut03::SwapRequest {
@@ -562,7 +688,7 @@ pub async fn receive_wad(
         .await
         .map_err(|e| CommonError::AcknowledgeNodeResponse(nuts::nut19::SWAP, e))?;
 
-    Ok(total_amount)
+    Ok(received_amount)
 }
 
 #[derive(Debug, thiserror::Error)]
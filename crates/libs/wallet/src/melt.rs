@@ -1,4 +1,7 @@
-use cashu_client::{CashuClient, ClientMeltQuoteRequest, ClientMeltQuoteResponse};
+use cashu_client::{
+    CashuClient, ClientMeltQuoteRequest, ClientMeltQuoteResponse, ClientSubscriptionKind,
+};
+use num_traits::CheckedAdd;
 use nuts::{Amount, nut19::MELT};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
@@ -42,12 +45,18 @@ pub enum PayMeltQuoteError {
     FetchInputsIds(#[source] Error),
     #[error("not enough funds")]
     NotEnoughFunds,
+    #[error("failed to compute the node input fee: {0}")]
+    GetInputsFee(#[source] rusqlite::Error),
+    #[error("could not find a set of proofs covering both the quote amount and their input fee")]
+    InputsFeeNotSettled,
     #[error(transparent)]
     SetProofsState(#[from] db::proof::SetProofsToStateError),
     #[error("failed update quote state: {0}")]
     UpdateQuoteState(#[source] rusqlite::Error),
     #[error("failed to register transfers ids : {0}")]
     RegisterTransfersIds(#[source] rusqlite::Error),
+    #[error("failed to register the quote inputs: {0}")]
+    RegisterInputs(#[source] rusqlite::Error),
     #[error("failed to handle the error occured during proof verification: {0}")]
     HandleProofsVerficationErrors(#[source] Error),
     #[error("melt operation failed: {0}")]
@@ -58,8 +67,15 @@ pub enum PayMeltQuoteError {
     UnprotectedLoadTokensFormDb(#[from] crate::UnprotectedLoadTokensFormDbError),
     #[error(transparent)]
     SyncMeltQuote(#[from] sync::SyncMeltQuoteError),
+    #[error(
+        "the payment of melt quote {0} failed, the inputs returned by the node are spendable again"
+    )]
+    QuoteFailed(String),
 }
 
+/// How many times we try to select proofs matching their own input fee before giving up
+const MAX_INPUTS_FEE_ATTEMPTS: u8 = 4;
+
 #[allow(clippy::too_many_arguments)]
 pub async fn pay_quote(
     seed_phrase_manager: impl SeedPhraseManager,
@@ -72,19 +88,47 @@ pub async fn pay_quote(
     unit: &str,
 ) -> Result<nuts::nut05::MeltResponse, PayMeltQuoteError> {
     // Gather the proofs
-    let proofs_ids = fetch_inputs_ids_from_db_or_node(
-        seed_phrase_manager,
-        pool.clone(),
-        node_client,
-        node_id,
-        amount,
-        unit,
-    )
-    .await
-    .map_err(PayMeltQuoteError::FetchInputsIds)?
-    .ok_or(PayMeltQuoteError::NotEnoughFunds)?;
+    //
+    // The node expects the inputs to cover both the quote amount and their own input fee.
+    // As this fee depends on the proofs selected, we iterate until it settles.
+    let mut fee = Amount::ZERO;
+    let mut attempts_left = MAX_INPUTS_FEE_ATTEMPTS;
+    let proofs_ids = loop {
+        let target_amount = amount
+            .checked_add(&fee)
+            .ok_or(PayMeltQuoteError::NotEnoughFunds)?;
+        let proofs_ids = fetch_inputs_ids_from_db_or_node(
+            seed_phrase_manager.clone(),
+            pool.clone(),
+            node_client,
+            node_id,
+            target_amount,
+            unit,
+        )
+        .await
+        .map_err(PayMeltQuoteError::FetchInputsIds)?
+        .ok_or(PayMeltQuoteError::NotEnoughFunds)?;
+
+        let inputs_fee = {
+            let db_conn = pool.get().map_err(CommonError::GetDbConnection)?;
+            db::proof::get_inputs_fee(&db_conn, &proofs_ids)
+                .map_err(PayMeltQuoteError::GetInputsFee)?
+        };
+        if inputs_fee == fee {
+            break proofs_ids;
+        }
+
+        attempts_left -= 1;
+        if attempts_left == 0 {
+            return Err(PayMeltQuoteError::InputsFeeNotSettled);
+        }
+        fee = inputs_fee;
+    };
     let inputs = {
         let db_conn = pool.get().map_err(CommonError::GetDbConnection)?;
+        // Checked again if the payment fails, see `sync::melt_quote`
+        db::melt_quote::register_inputs(&db_conn, &quote_id, &serde_json::to_string(&proofs_ids)?)
+            .map_err(PayMeltQuoteError::RegisterInputs)?;
         unprotected_load_tokens_from_db(&db_conn, &proofs_ids)?
     };
 
@@ -150,14 +194,25 @@ pub async fn wait_for_payment(
     method: String,
     quote_id: String,
 ) -> Result<Option<Vec<String>>, PayMeltQuoteError> {
+    let mut updates = sync::QuoteUpdates::subscribe(
+        node_client,
+        ClientSubscriptionKind::MeltQuote,
+        method.clone(),
+        quote_id.clone(),
+    )
+    .await;
+
     loop {
         let quote_state =
             sync::melt_quote(pool.clone(), node_client, method.clone(), quote_id.clone()).await?;
 
         match quote_state {
             Some((nuts::nut05::MeltQuoteState::Paid, tx_ids)) => return Ok(Some(tx_ids)),
+            Some((nuts::nut05::MeltQuoteState::Failed, _)) => {
+                return Err(PayMeltQuoteError::QuoteFailed(quote_id));
+            }
             None => return Ok(None),
-            _ => tokio::time::sleep(std::time::Duration::from_secs(1)).await,
+            _ => updates.next().await,
         }
     }
 }
@@ -1,4 +1,4 @@
-use cashu_client::{CashuClient, ClientMintQuoteRequest};
+use cashu_client::{CashuClient, ClientMintQuoteRequest, ClientSubscriptionKind};
 use nuts::{
     Amount, SplitTarget,
     nut04::{MintQuoteResponse, MintQuoteState},
@@ -45,18 +45,32 @@ pub enum QuotePaymentIssue {
     Expired,
 }
 
+/// Wait for the quote to be paid, or to expire
+///
+/// `on_partial_payment` is called with the sum of the payments received each time it grows
+/// while the quote is still unpaid.
 pub async fn wait_for_quote_payment(
     pool: Pool<SqliteConnectionManager>,
     node_client: &mut impl CashuClient,
     method: String,
     quote_id: String,
+    mut on_partial_payment: impl FnMut(Amount),
 ) -> Result<QuotePaymentIssue, SyncMintQuoteError> {
+    let mut updates = sync::QuoteUpdates::subscribe(
+        node_client,
+        ClientSubscriptionKind::MintQuote,
+        method.clone(),
+        quote_id.clone(),
+    )
+    .await;
+
+    let mut last_amount_paid = Amount::ZERO;
     loop {
-        let state =
+        let (state, amount_paid) =
             match sync::mint_quote(pool.clone(), node_client, method.clone(), quote_id.clone())
                 .await?
             {
-                Some(new_state) => new_state,
+                Some(update) => update,
                 None => {
                     return Ok(QuotePaymentIssue::Expired);
                 }
@@ -65,9 +79,12 @@ pub async fn wait_for_quote_payment(
         if state == MintQuoteState::Paid {
             return Ok(QuotePaymentIssue::Paid);
         }
+        if amount_paid > last_amount_paid {
+            on_partial_payment(amount_paid);
+            last_amount_paid = amount_paid;
+        }
 
-        // Wait a bit
-        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        updates.next().await;
     }
 }
 
@@ -1,7 +1,14 @@
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use bitcoin::bip32::Xpriv;
 use cashu_client::CashuClient;
 use futures::{StreamExt, future::join_all};
+use num_traits::CheckedAdd;
 use nuts::{
+    Amount, SplitTarget,
     dhke::{self, hash_to_curve},
     nut01::{self, PublicKey},
     nut02::KeysetId,
@@ -13,6 +20,7 @@ use tracing::error;
 use crate::{
     ConnectToNodeError, StoreNewProofsError,
     db::{self, keyset},
+    errors::Error,
     seed_phrase, store_new_proofs_from_blind_signatures,
     types::NodeUrl,
     wallet::SeedPhraseManager,
@@ -177,7 +185,7 @@ async fn restore_keyset(
                     } else {
                         let (secret, r) = secrets[&bm.blinded_secret].clone();
 
-                        Some(Ok((bs.c, secret, r, bs.amount)))
+                        Some(Ok((bs.c, secret, r, bs.amount, bs.dleq)))
                     }
                 });
 
@@ -251,3 +259,59 @@ pub async fn refresh_keysets(
 
     Ok(())
 }
+
+/// Swap the proofs of the keysets expiring in less than `margin` seconds into the active ones
+///
+/// The node keysets are refreshed first, so that newly retired keysets are taken into account.
+/// Proofs not worth the node input fee are left untouched.
+/// Returns the amount received for each unit.
+pub async fn swap_expiring_proofs(
+    seed_phrase_manager: impl SeedPhraseManager,
+    pool: Pool<SqliteConnectionManager>,
+    node_client: &mut impl CashuClient,
+    node_id: u32,
+    margin: u64,
+) -> Result<Vec<(String, Amount)>, Error> {
+    refresh_keysets(pool.clone(), node_client, node_id).await?;
+
+    let deadline = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        + margin;
+    let mut ys_per_unit: BTreeMap<String, Vec<PublicKey>> = BTreeMap::new();
+    {
+        let db_conn = pool.get()?;
+        for (unit, y) in db::proof::get_unspent_proofs_expiring_before(&db_conn, node_id, deadline)?
+        {
+            ys_per_unit.entry(unit).or_default().push(y);
+        }
+    }
+
+    let mut received = Vec::with_capacity(ys_per_unit.len());
+    for (unit, ys) in ys_per_unit {
+        let new_tokens = match crate::swap_proofs(
+            seed_phrase_manager.clone(),
+            pool.clone(),
+            node_client,
+            node_id,
+            &unit,
+            &ys,
+            SplitTarget::None,
+        )
+        .await
+        {
+            Ok(new_tokens) => new_tokens,
+            // Not worth more than the fee, nothing to save
+            Err(Error::NotEnoughFunds) => continue,
+            Err(e) => return Err(e),
+        };
+        let received_amount = new_tokens
+            .iter()
+            .try_fold(Amount::ZERO, |acc, (_, amount)| acc.checked_add(amount))
+            .ok_or(Error::AmountOverflow)?;
+        received.push((unit, received_amount));
+    }
+
+    Ok(received)
+}
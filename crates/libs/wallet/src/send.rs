@@ -1,6 +1,6 @@
 use cashu_client::CashuClient;
-use num_traits::Zero;
-use nuts::{Amount, nut01::PublicKey, traits::Unit};
+use num_traits::{CheckedSub, Zero};
+use nuts::{Amount, nut01::PublicKey, nut02::calculate_input_fee, traits::Unit};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::Connection;
@@ -25,6 +25,10 @@ pub enum PlanSpendingError {
     DuplicatePreferedNodeId(u32),
 }
 
+/// Returns how much of `amount_to_send` should be taken from each node
+///
+/// When we only use part of a node funds, a swap may be required to get the exact amount,
+/// so we keep enough on that node to pay for the input fee of a single proof.
 pub fn plan_spending<U: Unit>(
     db_conn: &Connection,
     amount_to_send: Amount,
@@ -46,12 +50,14 @@ pub fn plan_spending<U: Unit>(
     for node_id in prefered_node_ids {
         let total_amount_available =
             db::proof::get_node_total_available_amount_of_unit(db_conn, *node_id, unit.as_ref())?;
-        if total_amount_available < amount_left_to_send {
-            amount_left_to_send -= total_amount_available;
-            amount_per_node_id.push((*node_id, total_amount_available));
-        } else {
-            amount_per_node_id.push((*node_id, amount_left_to_send));
-            amount_left_to_send = Amount::ZERO;
+        if take_from_node(
+            db_conn,
+            *node_id,
+            unit.as_ref(),
+            total_amount_available,
+            &mut amount_left_to_send,
+            &mut amount_per_node_id,
+        )? {
             break;
         }
     }
@@ -67,12 +73,14 @@ pub fn plan_spending<U: Unit>(
     )?;
 
     for (node_id, total_amount_available) in ordered_nodes_and_amount {
-        if total_amount_available < amount_left_to_send {
-            amount_left_to_send -= total_amount_available;
-            amount_per_node_id.push((node_id, total_amount_available));
-        } else {
-            amount_per_node_id.push((node_id, amount_left_to_send));
-            amount_left_to_send = Amount::ZERO;
+        if take_from_node(
+            db_conn,
+            node_id,
+            unit.as_ref(),
+            total_amount_available,
+            &mut amount_left_to_send,
+            &mut amount_per_node_id,
+        )? {
             break;
         }
     }
@@ -88,6 +96,46 @@ pub fn plan_spending<U: Unit>(
     Ok(amount_per_node_id)
 }
 
+/// Plan to spend as much as possible of `amount_left_to_send` from this node
+///
+/// Returns true once there is nothing left to send.
+fn take_from_node(
+    db_conn: &Connection,
+    node_id: u32,
+    unit: &str,
+    total_amount_available: Amount,
+    amount_left_to_send: &mut Amount,
+    amount_per_node_id: &mut Vec<(u32, Amount)>,
+) -> Result<bool, rusqlite::Error> {
+    // Using all the node proofs, no swap needed
+    if total_amount_available <= *amount_left_to_send {
+        if !total_amount_available.is_zero() {
+            *amount_left_to_send -= total_amount_available;
+            amount_per_node_id.push((node_id, total_amount_available));
+        }
+        return Ok(amount_left_to_send.is_zero());
+    }
+
+    let swap_fee = calculate_input_fee(db::keyset::get_max_input_fee_ppk_for_node_and_unit(
+        db_conn, node_id, unit,
+    )?);
+    let usable_amount = total_amount_available
+        .checked_sub(&swap_fee)
+        .unwrap_or(Amount::ZERO);
+    if usable_amount >= *amount_left_to_send {
+        amount_per_node_id.push((node_id, *amount_left_to_send));
+        *amount_left_to_send = Amount::ZERO;
+        return Ok(true);
+    }
+
+    if !usable_amount.is_zero() {
+        *amount_left_to_send -= usable_amount;
+        amount_per_node_id.push((node_id, usable_amount));
+    }
+
+    Ok(false)
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum GatherProofIdsFromNodeError {
     #[error("failed to get a connection from the pool: {0}")]
@@ -1,7 +1,7 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use node_client::UnspecifiedEnum;
-use nuts::nut05::MeltQuoteState;
+use nuts::{nut01::PublicKey, nut05::MeltQuoteState};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use tracing::{Level, debug, event};
@@ -9,6 +9,7 @@ use tracing::{Level, debug, event};
 use crate::{
     db::{self, melt_quote::PendingMeltQuote},
     melt::format_melt_transfers_id_into_term_message,
+    types::ProofState,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -57,6 +58,11 @@ pub async fn melt_quotes(
                 tx_ids=format_melt_transfers_id_into_term_message(tx_ids),
                 "Melt quote paid"
             );
+        } else if MeltQuoteState::Failed == new_state && pending_melt_quote.state != new_state {
+            event!(name: "melt-quote-failed", Level::WARN,
+                quote_id=pending_melt_quote.id,
+                "Melt quote payment failed, the inputs returned by the node are spendable again"
+            );
         }
 
         if new_state == pending_melt_quote.state {
@@ -93,13 +99,59 @@ pub enum SyncMeltQuoteError {
     CommitDbTransaction(#[source] rusqlite::Error),
     #[error("failed to serialize transfer ids: {0}")]
     SerializeTransferIds(#[from] serde_json::Error),
+    #[error("failed to read the quote inputs: {0}")]
+    GetInputs(#[source] rusqlite::Error),
+    #[error("invalid quote inputs: {0}")]
+    InvalidInputs(#[source] serde_json::Error),
+    #[error(transparent)]
+    SetProofsState(#[from] db::proof::SetProofsToStateError),
+}
+
+/// The inputs of a quote whose payment just failed, that the node made spendable again
+///
+/// The node keeps spent those already committed to by a liabilities report, until it refunds them.
+/// Returns nothing if the failure was already handled, or if the inputs are unknown.
+async fn get_returned_inputs(
+    pool: &Pool<SqliteConnectionManager>,
+    node_client: &mut impl cashu_client::CashuClient,
+    quote_id: &str,
+) -> Result<Vec<PublicKey>, SyncMeltQuoteError> {
+    let inputs = {
+        let db_conn = pool.get()?;
+        match db::melt_quote::get_state_and_inputs(&db_conn, quote_id)
+            .map_err(SyncMeltQuoteError::GetInputs)?
+        {
+            Some((state, Some(inputs))) if state != MeltQuoteState::Failed => {
+                serde_json::from_str::<Vec<PublicKey>>(&inputs)
+                    .map_err(SyncMeltQuoteError::InvalidInputs)?
+            }
+            _ => return Ok(Vec::new()),
+        }
+    };
+    if inputs.is_empty() {
+        return Ok(inputs);
+    }
+
+    let response = node_client
+        .check_state(cashu_client::CheckStateRequest {
+            ys: inputs.iter().map(|y| y.to_bytes().to_vec()).collect(),
+        })
+        .await?;
+
+    Ok(inputs
+        .into_iter()
+        .zip(response.proof_check_states)
+        .filter(|(_, state)| state.state == nuts::nut07::ProofState::Unspent)
+        .map(|(y, _)| y)
+        .collect())
 }
 
 /// Sync the state of this quote from the node.
 ///
 /// 1. query the node for the state
 /// 2. delete expired quote
-/// 3. update state in database
+/// 3. mark as unspent the inputs of a failed quote that the node returned
+/// 4. update state in database
 ///
 /// Returns node if the melt quote has been deleted,
 /// otherwise returns its current state.
@@ -110,6 +162,12 @@ pub async fn melt_quote(
     quote_id: String,
 ) -> Result<Option<(MeltQuoteState, Vec<String>)>, SyncMeltQuoteError> {
     let response = node_client.melt_quote_state(method, quote_id.clone()).await;
+    let returned_inputs = match &response {
+        Ok(response) if response.state == MeltQuoteState::Failed => {
+            get_returned_inputs(&pool, node_client, &quote_id).await?
+        }
+        _ => Vec::new(),
+    };
 
     let mut db_conn = pool.get()?;
     match response {
@@ -133,6 +191,11 @@ pub async fn melt_quote(
                     }
                 }
                 MeltQuoteState::Pending => {}
+                MeltQuoteState::Failed => {
+                    if !returned_inputs.is_empty() {
+                        db::proof::set_proofs_to_state(&tx, &returned_inputs, ProofState::Unspent)?;
+                    }
+                }
                 MeltQuoteState::Paid => {
                     if response.transfer_ids.is_some() {
                         let transfer_ids_to_store = serde_json::to_string(&response.transfer_ids)?;
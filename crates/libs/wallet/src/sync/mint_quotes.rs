@@ -2,7 +2,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use cashu_client::CashuClient;
 use node_client::UnspecifiedEnum;
-use nuts::nut04::MintQuoteState;
+use nuts::{Amount, nut04::MintQuoteState};
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use tracing::{Level, error, event};
@@ -36,7 +36,7 @@ pub async fn mint_quotes(
 ) -> Result<MintQuotesStateUpdate, SyncMintQuotesError> {
     let mut states_updates = MintQuotesStateUpdate::default();
     for pending_mint_quote in pending_mint_quotes {
-        let new_state = {
+        let (new_state, amount_paid) = {
             match mint_quote(
                 pool.clone(),
                 node_client,
@@ -46,7 +46,7 @@ pub async fn mint_quotes(
             .await
             {
                 Ok(opt) => match opt {
-                    Some(new_state) => new_state,
+                    Some(update) => update,
                     None => {
                         states_updates.deleted.push(pending_mint_quote.id);
                         continue;
@@ -80,10 +80,11 @@ pub async fn mint_quotes(
             new_state
         };
 
+        let mut pending_mint_quote = pending_mint_quote;
+        pending_mint_quote.amount_paid = amount_paid;
         if new_state == pending_mint_quote.state {
             states_updates.unchanged.push(pending_mint_quote);
         } else {
-            let mut pending_mint_quote = pending_mint_quote;
             pending_mint_quote.state = new_state;
             states_updates.changed.push(pending_mint_quote);
         }
@@ -104,6 +105,8 @@ pub enum SyncMintQuoteError {
     Delete(rusqlite::Error),
     #[error("failed to set quote state: {0}")]
     SetState(rusqlite::Error),
+    #[error("failed to set quote amount paid: {0}")]
+    SetAmountPaid(rusqlite::Error),
     #[error("failed to interact with the node: {0}")]
     CashuClient(#[from] cashu_client::CashuClientError),
 }
@@ -112,16 +115,17 @@ pub enum SyncMintQuoteError {
 ///
 /// 1. query the node for the state
 /// 2. delete expired quote
-/// 3. update state in database
+/// 3. update state and amount paid in database
 ///
 /// Returns node if the mint quote has been deleted,
-/// otherwise returns its current state.
+/// otherwise returns its current state and the sum of the payments received so far.
+/// An unpaid quote with some amount paid is partially paid.
 pub async fn mint_quote(
     pool: Pool<SqliteConnectionManager>,
     node_client: &mut impl CashuClient,
     method: String,
     quote_id: String,
-) -> Result<Option<MintQuoteState>, SyncMintQuoteError> {
+) -> Result<Option<(MintQuoteState, Amount)>, SyncMintQuoteError> {
     let response = node_client.mint_quote_state(method, quote_id.clone()).await;
 
     let db_conn = pool.get()?;
@@ -144,8 +148,10 @@ pub async fn mint_quote(
 
             db::mint_quote::set_state(&db_conn, &response.quote, state)
                 .map_err(SyncMintQuoteError::SetState)?;
+            db::mint_quote::set_amount_paid(&db_conn, &response.quote, response.amount_paid)
+                .map_err(SyncMintQuoteError::SetAmountPaid)?;
 
-            Ok(Some(state))
+            Ok(Some((state, response.amount_paid)))
         }
         Err(cashu_client::CashuClientError::QuoteNotFound) => {
             db::mint_quote::delete(&db_conn, &quote_id).map_err(SyncMintQuoteError::Delete)?;
@@ -1,7 +1,9 @@
 mod melt_quotes;
 mod mint_quotes;
+mod updates;
 mod wads;
 
 pub use melt_quotes::*;
 pub use mint_quotes::*;
+pub(crate) use updates::QuoteUpdates;
 pub use wads::*;
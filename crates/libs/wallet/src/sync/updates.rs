@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use cashu_client::{
+    CashuClient, ClientNotificationStream, ClientSubscribeRequest, ClientSubscriptionKind,
+};
+use futures::StreamExt;
+
+/// Delay between two syncs when the node cannot push updates
+const POLLING_INTERVAL: Duration = Duration::from_secs(1);
+/// Even when subscribed, sync once in a while so that expiry is noticed
+const RESYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Updates of a single quote
+///
+/// Relies on a node subscription (NUT-17) when available, otherwise on polling.
+pub(crate) struct QuoteUpdates {
+    subscription: Option<ClientNotificationStream>,
+}
+
+impl QuoteUpdates {
+    pub(crate) async fn subscribe(
+        node_client: &mut impl CashuClient,
+        kind: ClientSubscriptionKind,
+        method: String,
+        quote_id: String,
+    ) -> Self {
+        let subscription = node_client
+            .subscribe(ClientSubscribeRequest {
+                kind,
+                method,
+                filters: vec![quote_id],
+            })
+            .await
+            .inspect_err(|e| {
+                tracing::debug!("quote subscription unavailable, falling back to polling: {e}")
+            })
+            .ok();
+
+        Self { subscription }
+    }
+
+    /// Wait until the quote may have changed and should be synced again
+    pub(crate) async fn next(&mut self) {
+        let Some(subscription) = self.subscription.as_mut() else {
+            tokio::time::sleep(POLLING_INTERVAL).await;
+            return;
+        };
+
+        match tokio::time::timeout(RESYNC_INTERVAL, subscription.next()).await {
+            Ok(Some(Ok(_))) | Err(_) => {}
+            Ok(Some(Err(_))) | Ok(None) => {
+                self.subscription = None;
+                tokio::time::sleep(POLLING_INTERVAL).await;
+            }
+        }
+    }
+}
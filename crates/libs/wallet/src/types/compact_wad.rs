@@ -5,9 +5,10 @@ use num_traits::CheckedAdd;
 
 use nuts::Amount;
 use nuts::nut00::secret::Secret;
-use nuts::nut00::{Proof, Proofs};
-use nuts::nut01::PublicKey;
+use nuts::nut00::{Proof, Proofs, Witness};
+use nuts::nut01::{PublicKey, SecretKey};
 use nuts::nut02::KeysetId;
+use nuts::nut12::ProofDleq;
 
 use serde::{Deserialize, Serialize};
 
@@ -199,6 +200,12 @@ pub struct CompactProof {
         deserialize_with = "deserialize_pubkey_from_bytes"
     )]
     pub c: PublicKey,
+    /// Witness
+    #[serde(rename = "w", default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<Witness>,
+    /// DLEQ proof
+    #[serde(rename = "d", default, skip_serializing_if = "Option::is_none")]
+    pub dleq: Option<CompactProofDleq>,
 }
 
 impl CompactProof {
@@ -209,10 +216,68 @@ impl CompactProof {
             keyset_id: *keyset_id,
             secret: self.secret.clone(),
             c: self.c,
+            witness: self.witness.clone(),
+            dleq: self.dleq.clone().map(Into::into),
         }
     }
 }
 
+/// Proof DLEQ V4
+///
+/// Allows the receiver to check offline that the proof was signed by the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactProofDleq {
+    /// e
+    #[serde(
+        serialize_with = "serialize_secret_key_as_bytes",
+        deserialize_with = "deserialize_secret_key_from_bytes"
+    )]
+    pub e: SecretKey,
+    /// s
+    #[serde(
+        serialize_with = "serialize_secret_key_as_bytes",
+        deserialize_with = "deserialize_secret_key_from_bytes"
+    )]
+    pub s: SecretKey,
+    /// Blinding factor
+    #[serde(
+        serialize_with = "serialize_secret_key_as_bytes",
+        deserialize_with = "deserialize_secret_key_from_bytes"
+    )]
+    pub r: SecretKey,
+}
+
+impl From<ProofDleq> for CompactProofDleq {
+    fn from(dleq: ProofDleq) -> Self {
+        Self {
+            e: dleq.e,
+            s: dleq.s,
+            r: dleq.r,
+        }
+    }
+}
+
+impl From<CompactProofDleq> for ProofDleq {
+    fn from(dleq: CompactProofDleq) -> Self {
+        ProofDleq::new(dleq.e, dleq.s, dleq.r)
+    }
+}
+
+fn serialize_secret_key_as_bytes<S>(key: &SecretKey, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_bytes(key.as_secret_bytes())
+}
+
+fn deserialize_secret_key_from_bytes<'de, D>(deserializer: D) -> Result<SecretKey, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let bytes = Vec::<u8>::deserialize(deserializer)?;
+    SecretKey::from_slice(&bytes).map_err(serde::de::Error::custom)
+}
+
 fn serialize_pubkey_as_bytes<S>(key: &PublicKey, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
@@ -260,6 +325,8 @@ mod tests {
                     amount: Amount::from(amount),
                     secret,
                     c: pubkey,
+                    witness: None,
+                    dleq: None,
                 }],
             }],
         }
@@ -282,6 +349,8 @@ mod tests {
                 amount: Amount::from(amount),
                 secret,
                 c: pubkey,
+                witness: None,
+                dleq: None,
             });
         }
 
@@ -365,6 +434,23 @@ mod tests {
         assert_eq!(wads, deserialized);
     }
 
+    #[test]
+    fn test_proof_with_dleq_token_roundtrip() {
+        let mut original_token = create_test_compact_wad_single_proof("mint.example.com", 100);
+        original_token.proofs[0].proofs[0].dleq = Some(CompactProofDleq {
+            e: SecretKey::generate(),
+            s: SecretKey::generate(),
+            r: SecretKey::generate(),
+        });
+        let wads = CompactWads::new(vec![original_token.clone()]);
+
+        let serialized = wads.to_string();
+        let deserialized: CompactWads = CompactWads::from_str(&serialized).unwrap();
+
+        assert_eq!(wads, deserialized);
+        assert!(deserialized.0[0].proofs()[0].dleq.is_some());
+    }
+
     #[test]
     fn test_multiple_proofs_token_roundtrip() {
         // Same thing with a token with multiple proofs
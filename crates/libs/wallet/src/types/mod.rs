@@ -126,7 +126,9 @@ impl PreMints {
             self.initial_keyset_counter + self.pre_mints.len() as u32,
         )?;
         let signatures_iterator = self.pre_mints.into_iter().zip(signatures).map(
-            |(pm, bs)| -> Result<_, nuts::nut01::Error> { Ok((bs.c, pm.secret, pm.r, pm.amount)) },
+            |(pm, bs)| -> Result<_, nuts::nut01::Error> {
+                Ok((bs.c, pm.secret, pm.r, pm.amount, bs.dleq))
+            },
         );
 
         let new_tokens = store_new_proofs_from_blind_signatures(
@@ -23,6 +23,8 @@ pub fn create_from_parts(
                     amount: p.amount,
                     secret: p.secret,
                     c: p.c,
+                    witness: p.witness,
+                    dleq: p.dleq.map(Into::into),
                 })
                 .collect(),
         })
@@ -2,7 +2,9 @@ use std::str::FromStr;
 
 use anyhow::Result;
 use e2e_tests::{db_connection, read_env_variables};
-use test_utils::e2e::starknet::wallet_ops::{WalletOps, recieve_already_spent_wad};
+use test_utils::e2e::starknet::wallet_ops::{
+    WalletOps, check_reserves_attestation, recieve_already_spent_wad,
+};
 use wallet::types::NodeUrl;
 
 #[tokio::test]
@@ -19,8 +21,10 @@ pub async fn run_e2e() -> Result<()> {
     let seed_phrase = wallet_ops.init()?;
     // Mint
     wallet_ops
-        .mint(10.into(), starknet_types::Asset::Strk, env)
+        .mint(10.into(), starknet_types::Asset::Strk, env.clone())
         .await?;
+    // Reserves
+    check_reserves_attestation(&mut wallet_ops, env, starknet_types::Asset::Strk).await?;
     // Send
     let wad = wallet_ops
         .send(
@@ -0,0 +1,261 @@
+use anyhow::Result;
+use cashu_client::{CashuClient, ClientMeltQuoteRequest, ClientMintQuoteRequest};
+use node_client::admin::{
+    ExpireQuoteRequest, GetLiabilitiesRequest, GetSpentProofCountsRequest, ListPendingMeltsRequest,
+    ListQuotesRequest, MethodSettings, QuoteKind, UpdateNodeInfoRequest, UpdateNutsSettingsRequest,
+};
+use node_tests::{init_admin_client, init_node_client, init_unauthenticated_admin_client};
+use starknet_liquidity_source::MeltPaymentRequest;
+use starknet_types::{StarknetU256, Unit};
+use starknet_types_core::felt::Felt;
+use tonic::Code;
+
+#[tokio::test]
+async fn requires_token() -> Result<()> {
+    let mut admin_client = init_unauthenticated_admin_client().await?;
+
+    let status = admin_client
+        .get_liabilities(GetLiabilitiesRequest {})
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_node_info() -> Result<()> {
+    let mut admin_client = init_admin_client().await?;
+    let mut node_client = init_node_client().await?;
+
+    let motd = "Maintenance planned tonight".to_string();
+    admin_client
+        .update_node_info(UpdateNodeInfoRequest {
+            motd: Some(motd.clone()),
+            ..Default::default()
+        })
+        .await?;
+
+    let info: serde_json::Value = serde_json::from_str(&node_client.info().await?.info)?;
+    assert_eq!(info["motd"], motd);
+
+    // An empty value clears the field
+    admin_client
+        .update_node_info(UpdateNodeInfoRequest {
+            motd: Some(String::new()),
+            ..Default::default()
+        })
+        .await?;
+    let info: serde_json::Value = serde_json::from_str(&node_client.info().await?.info)?;
+    assert!(info.get("motd").is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn disable_and_enable_unit() -> Result<()> {
+    let mut admin_client = init_admin_client().await?;
+    let mut node_client = init_node_client().await?;
+    let mint_quote_request = ClientMintQuoteRequest {
+        method: "starknet".to_string(),
+        amount: 10,
+        unit: Unit::Gwei.to_string(),
+        description: None,
+    };
+    let gwei_mint_settings = |enabled, min_amount| MethodSettings {
+        kind: QuoteKind::QkMint.into(),
+        method: "starknet".to_string(),
+        unit: Unit::Gwei.to_string(),
+        enabled,
+        min_amount,
+        max_amount: None,
+    };
+
+    admin_client
+        .update_nuts_settings(UpdateNutsSettingsRequest {
+            methods: vec![gwei_mint_settings(false, None)],
+            ..Default::default()
+        })
+        .await?;
+    assert!(
+        node_client
+            .mint_quote(mint_quote_request.clone())
+            .await
+            .is_err()
+    );
+
+    // Min amount above the requested one
+    admin_client
+        .update_nuts_settings(UpdateNutsSettingsRequest {
+            methods: vec![gwei_mint_settings(true, Some(11))],
+            ..Default::default()
+        })
+        .await?;
+    assert!(
+        node_client
+            .mint_quote(mint_quote_request.clone())
+            .await
+            .is_err()
+    );
+
+    // Back to the built-in settings
+    admin_client
+        .update_nuts_settings(UpdateNutsSettingsRequest {
+            methods: vec![gwei_mint_settings(true, Some(1))],
+            ..Default::default()
+        })
+        .await?;
+    node_client.mint_quote(mint_quote_request).await?;
+
+    let status = admin_client
+        .update_nuts_settings(UpdateNutsSettingsRequest {
+            methods: vec![MethodSettings {
+                min_amount: Some(10),
+                max_amount: Some(1),
+                ..gwei_mint_settings(true, None)
+            }],
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_and_expire_quote() -> Result<()> {
+    let mut admin_client = init_admin_client().await?;
+    let mut node_client = init_node_client().await?;
+
+    // Melt quotes stay unpaid until melted, whatever the liquidity source
+    let quote = node_client
+        .melt_quote(ClientMeltQuoteRequest {
+            method: "starknet".to_string(),
+            unit: Unit::MilliStrk.to_string(),
+            request: serde_json::to_string(&MeltPaymentRequest {
+                payee: Felt::from_hex_unchecked(
+                    "0x064b48806902a367c8598f4f95c305e8c1a1acba5f082d294a43793113115691",
+                ),
+                asset: starknet_types::Asset::Strk,
+                amount: StarknetU256 {
+                    low: Felt::from_dec_str("32000000000000000").unwrap(),
+                    high: Felt::from(0),
+                },
+            })?,
+        })
+        .await?;
+
+    let list_unpaid = ListQuotesRequest {
+        kind: QuoteKind::QkMelt.into(),
+        state: Some("UNPAID".to_string()),
+        unit: Some(Unit::MilliStrk.to_string()),
+        limit: Some(1000),
+        offset: 0,
+    };
+    let quotes = admin_client
+        .list_quotes(list_unpaid.clone())
+        .await?
+        .into_inner()
+        .quotes;
+    let listed = quotes
+        .iter()
+        .find(|q| q.quote == quote.quote)
+        .expect("new quote should be listed");
+    assert_eq!(listed.amount, 32);
+    assert_eq!(listed.expiry, quote.expiry);
+
+    let expire_request = ExpireQuoteRequest {
+        kind: QuoteKind::QkMelt.into(),
+        quote: quote.quote.clone(),
+    };
+    admin_client.expire_quote(expire_request.clone()).await?;
+    let quotes = admin_client
+        .list_quotes(list_unpaid)
+        .await?
+        .into_inner()
+        .quotes;
+    let listed = quotes.iter().find(|q| q.quote == quote.quote).unwrap();
+    assert!(listed.expiry < quote.expiry);
+
+    // Already expired
+    let status = admin_client.expire_quote(expire_request).await.unwrap_err();
+    assert_eq!(status.code(), Code::FailedPrecondition);
+
+    let status = admin_client
+        .list_quotes(ListQuotesRequest {
+            kind: QuoteKind::QkMint.into(),
+            state: Some("PENDING".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_mint_quote_amount_paid() -> Result<()> {
+    let mut admin_client = init_admin_client().await?;
+    let mut node_client = init_node_client().await?;
+
+    let quote = node_client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: 16,
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+
+    let quotes = admin_client
+        .list_quotes(ListQuotesRequest {
+            kind: QuoteKind::QkMint.into(),
+            unit: Some(Unit::MilliStrk.to_string()),
+            limit: Some(1000),
+            ..Default::default()
+        })
+        .await?
+        .into_inner()
+        .quotes;
+    let listed = quotes
+        .iter()
+        .find(|q| q.quote == quote.quote)
+        .expect("new quote should be listed");
+    // Paid right away by the mock backend, not at all by the others
+    assert_eq!(listed.amount_paid, u64::from(quote.amount_paid));
+    assert_eq!(listed.excess, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_only_views() -> Result<()> {
+    let mut admin_client = init_admin_client().await?;
+
+    let liabilities = admin_client
+        .get_liabilities(GetLiabilitiesRequest {})
+        .await?
+        .into_inner()
+        .liabilities;
+    for unit_liabilities in liabilities {
+        assert_eq!(
+            unit_liabilities.outstanding,
+            unit_liabilities.issued - unit_liabilities.redeemed
+        );
+    }
+
+    admin_client
+        .get_spent_proof_counts(GetSpentProofCountsRequest {})
+        .await?;
+
+    let pending_melts = admin_client
+        .list_pending_melts(ListPendingMeltsRequest {})
+        .await?
+        .into_inner()
+        .quotes;
+    assert!(pending_melts.iter().all(|q| q.state == "PENDING"));
+
+    Ok(())
+}
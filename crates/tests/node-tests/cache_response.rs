@@ -101,6 +101,8 @@ async fn works() -> Result<()> {
         keyset_id: KeysetId::from_bytes(&active_keyset.id.clone())?,
         secret,
         c: unblinded_signature,
+        witness: None,
+        dleq: None,
     };
 
     let secret = Secret::generate();
@@ -132,6 +134,8 @@ async fn works() -> Result<()> {
         keyset_id: KeysetId::from_bytes(&active_keyset.id.clone())?,
         secret,
         c: unblinded_signature,
+        witness: None,
+        dleq: None,
     };
 
     let melt_quote_request = ClientMeltQuoteRequest {
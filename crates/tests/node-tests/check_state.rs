@@ -109,6 +109,8 @@ async fn test_multiple_tokens() -> Result<()> {
             keyset_id: KeysetId::from_bytes(&active_keyset.id.clone())?,
             secret: secrets[i].clone(),
             c: unblinded_signature,
+            witness: None,
+            dleq: None,
         });
     }
 
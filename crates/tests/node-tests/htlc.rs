@@ -0,0 +1,168 @@
+use anyhow::Result;
+use cashu_client::{CashuClient, ClientMintQuoteRequest, GrpcClient};
+use node_tests::init_node_client;
+use nuts::Amount;
+use nuts::dhke::{blind_message, unblind_message};
+use nuts::nut00::secret::Secret;
+use nuts::nut01::SecretKey;
+use nuts::nut02::KeysetId;
+use nuts::nut11::{Conditions, SpendingConditions};
+use starknet_types::Unit;
+
+const PREIMAGE: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+
+/// Mint a proof of `amount` whose secret is locked by `spending_conditions`
+async fn mint_locked_proof(
+    client: &mut GrpcClient,
+    amount: Amount,
+    spending_conditions: SpendingConditions,
+) -> Result<nuts::nut00::Proof> {
+    let mint_quote_response = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: amount.into(),
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+
+    let keysets = client.keysets().await?.keysets;
+    let active_keyset = keysets
+        .iter()
+        .find(|ks| ks.active && ks.unit == Unit::MilliStrk.as_str())
+        .unwrap();
+    let keyset_id = KeysetId::from_bytes(&active_keyset.id.clone())?;
+    let node_pubkey_for_amount = client
+        .keys(Some(keyset_id))
+        .await?
+        .keysets
+        .first()
+        .unwrap()
+        .keys
+        .iter()
+        .find(|key| key.amount == amount)
+        .unwrap()
+        .publickey;
+
+    let secret: Secret = spending_conditions.try_into()?;
+    let (blinded_secret, r) = blind_message(secret.as_bytes(), None)?;
+    let mint_response = client
+        .mint(
+            nuts::nut04::MintRequest {
+                quote: mint_quote_response.quote,
+                outputs: vec![nuts::nut00::BlindedMessage {
+                    amount,
+                    keyset_id,
+                    blinded_secret,
+                }],
+            },
+            "starknet".to_string(),
+        )
+        .await?;
+    let blind_signature = mint_response.signatures.first().unwrap().c;
+
+    Ok(nuts::nut00::Proof {
+        amount,
+        keyset_id,
+        secret,
+        c: unblind_message(&blind_signature, &r, &node_pubkey_for_amount)?,
+        witness: None,
+        dleq: None,
+    })
+}
+
+fn swap_request(proof: nuts::nut00::Proof) -> Result<nuts::nut03::SwapRequest> {
+    let (blinded_secret, _r) = blind_message(Secret::generate().as_bytes(), None)?;
+    Ok(nuts::nut03::SwapRequest {
+        outputs: vec![nuts::nut00::BlindedMessage {
+            amount: proof.amount,
+            keyset_id: proof.keyset_id,
+            blinded_secret,
+        }],
+        inputs: vec![proof],
+    })
+}
+
+// This test checks that the node enforces HTLC spending conditions on swap inputs.
+//
+// - mint a proof whose secret is locked to a hash and a public key
+// - swap it without witness and check it is rejected
+// - swap it with the wrong preimage and check it is rejected
+// - swap it with the preimage but no signature and check it is rejected
+// - swap it with the preimage signed by the locking key and check it succeeds
+#[tokio::test]
+async fn swap_enforces_htlc_spending_conditions() -> Result<()> {
+    let mut client = init_node_client().await?;
+    let locking_key = SecretKey::generate();
+    let conditions = Conditions {
+        pubkeys: Some(vec![locking_key.public_key()]),
+        ..Default::default()
+    };
+    let mut proof = mint_locked_proof(
+        &mut client,
+        Amount::from_i64_repr(32),
+        SpendingConditions::new_htlc(PREIMAGE, Some(conditions))?,
+    )
+    .await?;
+
+    // No witness
+    assert!(client.swap(swap_request(proof.clone())?).await.is_err());
+
+    // Wrong preimage
+    let mut wrong_preimage_proof = proof.clone();
+    wrong_preimage_proof.add_preimage(
+        "0000000000000000000000000000000000000000000000000000000000000002".to_string(),
+    );
+    wrong_preimage_proof.sign_p2pk(locking_key.clone())?;
+    assert!(
+        client
+            .swap(swap_request(wrong_preimage_proof)?)
+            .await
+            .is_err()
+    );
+
+    // Preimage without signature
+    proof.add_preimage(PREIMAGE.to_string());
+    assert!(client.swap(swap_request(proof.clone())?).await.is_err());
+
+    // Preimage and signature
+    proof.sign_p2pk(locking_key)?;
+    client.swap(swap_request(proof)?).await?;
+
+    Ok(())
+}
+
+// This test checks that once the locktime has passed, the refund key alone unlocks the proof.
+#[tokio::test]
+async fn swap_accepts_htlc_refund_after_locktime() -> Result<()> {
+    let mut client = init_node_client().await?;
+    let refund_key = SecretKey::generate();
+    let conditions = Conditions {
+        locktime: Some(1),
+        pubkeys: Some(vec![SecretKey::generate().public_key()]),
+        refund_keys: Some(vec![refund_key.public_key()]),
+        ..Default::default()
+    };
+    let mut proof = mint_locked_proof(
+        &mut client,
+        Amount::from_i64_repr(16),
+        SpendingConditions::new_htlc(PREIMAGE, Some(conditions))?,
+    )
+    .await?;
+
+    // Signed by another key
+    let mut wrongly_signed_proof = proof.clone();
+    wrongly_signed_proof.sign_p2pk(SecretKey::generate())?;
+    assert!(
+        client
+            .swap(swap_request(wrongly_signed_proof)?)
+            .await
+            .is_err()
+    );
+
+    // Signed by the refund key, without preimage
+    proof.sign_p2pk(refund_key)?;
+    client.swap(swap_request(proof)?).await?;
+
+    Ok(())
+}
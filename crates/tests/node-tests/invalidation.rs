@@ -0,0 +1,165 @@
+//! Check that the node drops its cached state when another instance, sharing the database
+//! found at `PG_URL`, changes it
+//!
+//! The other instance is played by the test, writing to the database through `db_node`
+//! the same way the node does.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Result, anyhow};
+use cashu_client::{CashuClient, CashuClientError, ClientMintQuoteRequest, GrpcClient};
+use node_tests::init_node_client;
+use nuts::Amount;
+use nuts::dhke::blind_message;
+use nuts::nut00::BlindedMessage;
+use nuts::nut00::secret::Secret;
+use nuts::nut02::KeysetId;
+use sqlx::PgPool;
+use starknet_types::Unit;
+
+const INVALIDATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Poll `check` until it returns true, the notifications being handled asynchronously
+async fn eventually<F, Fut>(mut check: F) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let timeout = Instant::now() + INVALIDATION_TIMEOUT;
+    while !check().await? {
+        if Instant::now() > timeout {
+            return Err(anyhow!("the node did not pick up the change"));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    Ok(())
+}
+
+async fn motd(client: &mut GrpcClient) -> Result<Option<String>> {
+    let info: serde_json::Value = serde_json::from_str(&client.info().await?.info)?;
+
+    Ok(info["motd"].as_str().map(str::to_string))
+}
+
+#[tokio::test]
+async fn node_info_updated_by_another_instance() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut conn = pg_pool.acquire().await?;
+    let mut client = init_node_client().await?;
+
+    // The fields of the operator info, as served by the node
+    let info: serde_json::Value = serde_json::from_str(&client.info().await?.info)?;
+    let original: serde_json::Map<String, serde_json::Value> = [
+        "name",
+        "description",
+        "description_long",
+        "contact",
+        "icon_url",
+        "urls",
+        "motd",
+    ]
+    .into_iter()
+    .filter_map(|field| Some((field.to_string(), info.get(field)?.clone())))
+    .collect();
+
+    let motd_from_other_instance = format!("Set by another instance {}", uuid::Uuid::new_v4());
+    let mut updated = original.clone();
+    updated.insert(
+        "motd".to_string(),
+        serde_json::Value::String(motd_from_other_instance.clone()),
+    );
+    db_node::node_info::upsert(&mut conn, &serde_json::to_string(&updated)?).await?;
+    eventually(|| {
+        let mut client = client.clone();
+        let expected = motd_from_other_instance.clone();
+        async move { Ok(motd(&mut client).await? == Some(expected)) }
+    })
+    .await?;
+
+    let original_motd = original
+        .get("motd")
+        .and_then(|motd| motd.as_str())
+        .map(str::to_string);
+    db_node::node_info::upsert(&mut conn, &serde_json::to_string(&original)?).await?;
+    eventually(|| {
+        let mut client = client.clone();
+        let expected = original_motd.clone();
+        async move { Ok(motd(&mut client).await? == expected) }
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Try to mint with an output of `keyset_id`
+async fn mint(client: &mut GrpcClient, keyset_id: KeysetId) -> Result<(), CashuClientError> {
+    let amount = Amount::from(8u64);
+    let quote = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: amount.into(),
+            unit: Unit::Gwei.to_string(),
+            description: None,
+        })
+        .await?;
+    let (blinded_secret, _r) = blind_message(Secret::generate().as_bytes(), None).unwrap();
+    client
+        .mint(
+            nuts::nut04::MintRequest {
+                quote: quote.quote,
+                outputs: vec![BlindedMessage {
+                    amount,
+                    keyset_id,
+                    blinded_secret,
+                }],
+            },
+            "starknet".to_string(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+// - deactivate the active gwei keyset in the database and check the node stops signing with it
+// - reactivate it and check the node signs with it again
+#[tokio::test]
+async fn keyset_deactivated_by_another_instance() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut conn = pg_pool.acquire().await?;
+    let mut client = init_node_client().await?;
+
+    let keysets = client.keysets().await?.keysets;
+    let keyset_id = KeysetId::from_bytes(
+        &keysets
+            .iter()
+            .find(|ks| ks.active && ks.unit == Unit::Gwei.as_str())
+            .unwrap()
+            .id,
+    )?;
+    // Have the node cache the keyset as active
+    mint(&mut client, keyset_id).await?;
+
+    db_node::keyset::deactivate_keysets(&mut conn, &[keyset_id.as_i64()], None).await?;
+    let deactivated = eventually(|| {
+        let mut client = client.clone();
+        async move { Ok(mint(&mut client, keyset_id).await.is_err()) }
+    })
+    .await;
+
+    // Restore the keyset before checking, for the other tests to keep using it
+    sqlx::query("UPDATE keyset SET active = true, final_expiry = NULL WHERE id = $1")
+        .bind(keyset_id.as_i64())
+        .execute(&mut *conn)
+        .await?;
+    db_node::notify::keyset_activity(&mut conn, &[keyset_id.as_i64()]).await?;
+    deactivated?;
+
+    eventually(|| {
+        let mut client = client.clone();
+        async move { Ok(mint(&mut client, keyset_id).await.is_ok()) }
+    })
+    .await?;
+
+    Ok(())
+}
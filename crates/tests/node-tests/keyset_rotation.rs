@@ -58,6 +58,11 @@ async fn ok() -> Result<()> {
     for keyset in &curr_keysets_response.keysets {
         if !old_keysets.contains_key(&keyset.id) {
             assert!(keyset.active, "New keyset {:?} is not active!", keyset.id);
+            assert!(
+                keyset.final_expiry.is_none(),
+                "New keyset {:?} has an expiry!",
+                keyset.id
+            );
         } else {
             assert!(!keyset.active, "Old keyset {:?} is active!", keyset.id);
         }
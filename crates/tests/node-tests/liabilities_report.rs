@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use cashu_client::{CashuClient, ClientMintQuoteRequest};
+use node_client::{
+    GetLiabilitiesInclusionProofsRequest, GetLiabilitiesInclusionProofsResponse,
+    GetLiabilitiesReportRequest, LiabilitiesInclusionProof, LiabilitiesReport, MerkleSumNode,
+};
+use node_tests::init_node_client;
+use nuts::dhke::{blind_message, hash_to_curve, unblind_message};
+use nuts::nut00::secret::Secret;
+use nuts::nut02::KeysetId;
+use nuts::{Amount, nut01::PublicKey};
+use proof_of_liabilities::{InclusionProof, Node};
+use starknet_types::Unit;
+use tonic::Code;
+
+fn to_node(node: &MerkleSumNode) -> Node {
+    Node::new(
+        node.hash.clone().try_into().unwrap(),
+        Amount::from(node.amount),
+    )
+}
+
+fn verify(report: &LiabilitiesReport, proof: &LiabilitiesInclusionProof, burned: bool) {
+    let keyset = report
+        .keysets
+        .iter()
+        .find(|k| k.keyset_id == proof.keyset_id)
+        .expect("proven leaf keyset should be reported");
+    let (root, leaf_count) = if burned {
+        (keyset.burned_root.as_ref().unwrap(), keyset.burned_count)
+    } else {
+        (keyset.issued_root.as_ref().unwrap(), keyset.issued_count)
+    };
+    let point = PublicKey::from_slice(&proof.point).unwrap();
+
+    InclusionProof {
+        leaf_index: proof.leaf_index,
+        leaf_count,
+        siblings: proof.siblings.iter().map(to_node).collect(),
+    }
+    .verify(
+        Node::leaf(&point, Amount::from(proof.amount)),
+        &to_node(root),
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn issuance_and_spends_are_reported() -> Result<()> {
+    let mut client = init_node_client().await?;
+
+    let amounts = [Amount::from(8u64), Amount::from(16u64)];
+    let mint_quote = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: 24,
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+    let keysets = client.keysets().await?.keysets;
+    let active_keyset = keysets
+        .iter()
+        .find(|ks| ks.active && ks.unit == Unit::MilliStrk.as_str())
+        .unwrap();
+    let keyset_id = KeysetId::from_bytes(&active_keyset.id)?;
+
+    let mut secrets = Vec::new();
+    let mut rs = Vec::new();
+    let mut outputs = Vec::new();
+    for amount in amounts {
+        let secret = Secret::generate();
+        let (blinded_secret, r) = blind_message(secret.as_bytes(), None)?;
+        secrets.push(secret);
+        rs.push(r);
+        outputs.push(nuts::nut00::BlindedMessage {
+            amount,
+            keyset_id,
+            blinded_secret,
+        });
+    }
+    let blinded_messages: Vec<_> = outputs
+        .iter()
+        .map(|o| o.blinded_secret.to_bytes().to_vec())
+        .collect();
+    let mint_response = client
+        .mint(
+            nuts::nut04::MintRequest {
+                quote: mint_quote.quote,
+                outputs,
+            },
+            "starknet".to_string(),
+        )
+        .await?;
+
+    // Spend the first token
+    let node_keys = client.keys(Some(keyset_id)).await?.keysets;
+    let node_pubkey = node_keys[0]
+        .keys
+        .iter()
+        .find(|k| k.amount == amounts[0])
+        .unwrap()
+        .publickey;
+    let proof = nuts::nut00::Proof {
+        amount: amounts[0],
+        keyset_id,
+        secret: secrets[0].clone(),
+        c: unblind_message(&mint_response.signatures[0].c, &rs[0], &node_pubkey)?,
+        witness: None,
+        dleq: None,
+    };
+    let (blinded_secret, _) = blind_message(Secret::generate().as_bytes(), None)?;
+    client
+        .swap(nuts::nut03::SwapRequest {
+            inputs: vec![proof],
+            outputs: vec![nuts::nut00::BlindedMessage {
+                amount: amounts[0],
+                keyset_id,
+                blinded_secret,
+            }],
+        })
+        .await?;
+    let spent_y = hash_to_curve(secrets[0].as_bytes())?.to_bytes().to_vec();
+
+    // Wait for a report covering them
+    let mut covered = None;
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let report = match client
+            .node
+            .get_liabilities_report(GetLiabilitiesReportRequest { report_id: None })
+            .await
+        {
+            Ok(report) => report.into_inner(),
+            Err(status) if status.code() == Code::NotFound => continue,
+            Err(status) => return Err(status.into()),
+        };
+        let proofs = client
+            .node
+            .get_liabilities_inclusion_proofs(GetLiabilitiesInclusionProofsRequest {
+                report_id: report.id,
+                blinded_messages: blinded_messages.clone(),
+                ys: vec![spent_y.clone()],
+            })
+            .await?
+            .into_inner();
+        if proofs.issued.len() == blinded_messages.len() && proofs.burned.len() == 1 {
+            covered = Some((report, proofs));
+            break;
+        }
+    }
+    let (report, GetLiabilitiesInclusionProofsResponse { issued, burned }) =
+        covered.ok_or_else(|| anyhow!("no report covered the minted tokens"))?;
+
+    for proof in &issued {
+        let index = blinded_messages
+            .iter()
+            .position(|b| *b == proof.point)
+            .unwrap();
+        assert_eq!(Amount::from(proof.amount), amounts[index]);
+        assert_eq!(proof.keyset_id, active_keyset.id);
+        verify(&report, proof, false);
+    }
+    assert_eq!(burned[0].point, spent_y);
+    assert_eq!(Amount::from(burned[0].amount), amounts[0]);
+    verify(&report, &burned[0], true);
+
+    // Past reports stay available, unknown ones are not found
+    let same_report = client
+        .node
+        .get_liabilities_report(GetLiabilitiesReportRequest {
+            report_id: Some(report.id),
+        })
+        .await?
+        .into_inner();
+    assert_eq!(same_report, report);
+    let status = client
+        .node
+        .get_liabilities_report(GetLiabilitiesReportRequest {
+            report_id: Some(i64::MAX as u64),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use cashu_client::{CashuClient, ClientMintQuoteRequest};
+use node_tests::init_node_client;
+use nuts::Amount;
+use nuts::dhke::{blind_message, unblind_message};
+use nuts::nut00::secret::Secret;
+use nuts::nut01::SecretKey;
+use nuts::nut02::KeysetId;
+use nuts::nut11::SpendingConditions;
+use starknet_types::Unit;
+
+// This test checks that the node enforces P2PK spending conditions on swap inputs.
+//
+// - mint a proof whose secret is locked to a public key
+// - swap it without witness and check it is rejected
+// - swap it signed by another key and check it is rejected
+// - swap it signed by the locking key and check it succeeds
+#[tokio::test]
+async fn swap_enforces_p2pk_spending_conditions() -> Result<()> {
+    let mut client = init_node_client().await?;
+    let amount = Amount::from_i64_repr(32);
+
+    let mint_quote_response = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: amount.into(),
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+
+    let keysets = client.keysets().await?.keysets;
+    let active_keyset = keysets
+        .iter()
+        .find(|ks| ks.active && ks.unit == Unit::MilliStrk.as_str())
+        .unwrap();
+    let keyset_id = KeysetId::from_bytes(&active_keyset.id.clone())?;
+    let node_pubkey_for_amount = client
+        .keys(Some(keyset_id))
+        .await?
+        .keysets
+        .first()
+        .unwrap()
+        .keys
+        .iter()
+        .find(|key| key.amount == amount)
+        .unwrap()
+        .publickey;
+
+    // Mint a locked proof
+    let locking_key = SecretKey::generate();
+    let secret: Secret = SpendingConditions::new_p2pk(locking_key.public_key(), None).try_into()?;
+    let (blinded_secret, r) = blind_message(secret.as_bytes(), None)?;
+    let mint_response = client
+        .mint(
+            nuts::nut04::MintRequest {
+                quote: mint_quote_response.quote,
+                outputs: vec![nuts::nut00::BlindedMessage {
+                    amount,
+                    keyset_id,
+                    blinded_secret,
+                }],
+            },
+            "starknet".to_string(),
+        )
+        .await?;
+    let blind_signature = mint_response.signatures.first().unwrap().c;
+    let mut proof = nuts::nut00::Proof {
+        amount,
+        keyset_id,
+        secret,
+        c: unblind_message(&blind_signature, &r, &node_pubkey_for_amount)?,
+        witness: None,
+        dleq: None,
+    };
+
+    let swap_request = |proof: nuts::nut00::Proof| -> Result<nuts::nut03::SwapRequest> {
+        let (blinded_secret, _r) = blind_message(Secret::generate().as_bytes(), None)?;
+        Ok(nuts::nut03::SwapRequest {
+            inputs: vec![proof],
+            outputs: vec![nuts::nut00::BlindedMessage {
+                amount,
+                keyset_id,
+                blinded_secret,
+            }],
+        })
+    };
+
+    // No witness
+    assert!(client.swap(swap_request(proof.clone())?).await.is_err());
+
+    // Wrong key
+    let mut wrongly_signed_proof = proof.clone();
+    wrongly_signed_proof.sign_p2pk(SecretKey::generate())?;
+    assert!(
+        client
+            .swap(swap_request(wrongly_signed_proof)?)
+            .await
+            .is_err()
+    );
+
+    // Right key
+    proof.sign_p2pk(locking_key)?;
+    client.swap(swap_request(proof)?).await?;
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+//! Sweep expired quotes the way the node quote sweeper does, against the node database
+//!
+//! Each scenario runs in a transaction that is rolled back at the end,
+//! so that they neither interfere with each other nor leave anything behind.
+
+use anyhow::{Result, anyhow};
+use node_tests::unix_time;
+use nuts::Amount;
+use sqlx::{PgPool, Postgres, Transaction};
+use starknet_types::{Asset, ChainId, StarknetU256, Unit, constants::ON_CHAIN_CONSTANTS};
+use starknet_types_core::felt::Felt;
+use substreams_sink::RemittanceEvent;
+use uuid::Uuid;
+
+/// Far above any block a test database may have indexed for real
+const FIRST_BLOCK: u64 = 1 << 41;
+const QUOTE_AMOUNT: u64 = 10;
+
+struct Sweep {
+    tx: Transaction<'static, Postgres>,
+    quote_id: Uuid,
+    invoice_id: [u8; 32],
+    cashier: Felt,
+    blocks: u64,
+}
+
+impl Sweep {
+    /// Start with a mint quote that expired unpaid
+    async fn new() -> Result<Self> {
+        let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+        let mut tx = pg_pool.begin().await?;
+        let quote_id = Uuid::new_v4();
+        let mut invoice_id = [0u8; 32];
+        invoice_id[16..].copy_from_slice(quote_id.as_bytes());
+        db_node::mint_quote::insert_new(
+            &mut tx,
+            quote_id,
+            invoice_id,
+            Unit::MilliStrk,
+            Amount::from(QUOTE_AMOUNT),
+            "request",
+            // Before the start of the transaction, which is what `NOW()` returns
+            unix_time() - 60,
+        )
+        .await?;
+
+        Ok(Self {
+            tx,
+            quote_id,
+            invoice_id,
+            cashier: Felt::from(0xca5e1e4u64),
+            blocks: 0,
+        })
+    }
+
+    /// Run the steps of the node sweeper, archiving the quotes expired before `expired_before`
+    ///
+    /// Returns whether the quote was archived, and whether one of its payments was flagged.
+    async fn sweep(&mut self, expired_before: u64) -> Result<(bool, bool)> {
+        db_node::mint_quote::mark_expired(&mut self.tx).await?;
+        let late_payments = db_node::mint_payment_event::flag_late_payments(&mut self.tx).await?;
+        db_node::mint_quote::archive_expired(&mut self.tx, expired_before).await?;
+
+        let archived = db_node::mint_quote::get_archived_quote_infos_by_invoice_id::<Unit>(
+            &mut self.tx,
+            &self.invoice_id,
+        )
+        .await?
+        .is_some();
+        let flagged = late_payments.iter().any(|p| p.quote_id == self.quote_id);
+
+        Ok((archived, flagged))
+    }
+
+    async fn is_marked_expired(&mut self) -> Result<bool> {
+        let is_marked_expired =
+            sqlx::query_scalar("SELECT expired_at IS NOT NULL FROM mint_quote WHERE id = $1")
+                .bind(self.quote_id)
+                .fetch_one(&mut *self.tx)
+                .await?;
+
+        Ok(is_marked_expired)
+    }
+
+    /// Index a block, mined now, with a payment of `amount` for the quote
+    async fn pay(&mut self, amount: u64) -> Result<()> {
+        let strk_address = ON_CHAIN_CONSTANTS
+            .get(ChainId::Devnet.as_str())
+            .and_then(|c| {
+                c.assets_contract_address
+                    .get_contract_address_for_asset(Asset::Strk)
+            })
+            .ok_or(anyhow!("no strk address on devnet"))?;
+        let amount = Unit::MilliStrk.convert_amount_into_u256(Amount::from(amount));
+        let amount = StarknetU256::from_parts(amount.low_u128(), 0u128);
+        let mut tx_hash = [0u8; 32];
+        tx_hash[16..].copy_from_slice(Uuid::new_v4().as_bytes());
+        let event = RemittanceEvent {
+            tx_hash: tx_hash.to_vec(),
+            event_index: 0,
+            asset: strk_address.to_bytes_be().to_vec(),
+            payer: Felt::from(0xa11ceu64).to_bytes_be().to_vec(),
+            payee: self.cashier.to_bytes_be().to_vec(),
+            invoice_id: self.invoice_id.to_vec(),
+            amount_low: amount.low.to_bytes_be().to_vec(),
+            amount_high: amount.high.to_bytes_be().to_vec(),
+        };
+
+        self.blocks += 1;
+        let number = FIRST_BLOCK + self.blocks;
+        substreams_sink::index_block_events(
+            &mut self.tx,
+            &format!("{}-{}", self.quote_id, number),
+            number,
+            unix_time().try_into()?,
+            vec![event],
+            &ChainId::Devnet,
+            self.cashier,
+        )
+        .await?;
+        substreams_sink::confirm_payments(&mut self.tx, number..=number, 1).await?;
+
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn expired_quote_is_archived_after_the_delay() -> Result<()> {
+    let mut sweep = Sweep::new().await?;
+
+    // Expired by this sweep, too recently to be archived
+    assert_eq!(sweep.sweep(unix_time() - 3600).await?, (false, false));
+    assert!(sweep.is_marked_expired().await?);
+
+    assert_eq!(sweep.sweep(unix_time() + 1).await?, (true, false));
+    assert!(
+        db_node::mint_quote::build_response_from_db(&mut sweep.tx, sweep.quote_id)
+            .await?
+            .is_none()
+    );
+
+    sweep.tx.rollback().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn late_payment_is_flagged_and_keeps_the_quote() -> Result<()> {
+    let mut sweep = Sweep::new().await?;
+
+    sweep.pay(QUOTE_AMOUNT / 2).await?;
+    assert_eq!(sweep.sweep(unix_time() + 1).await?, (false, true));
+    assert!(sweep.is_marked_expired().await?);
+    // Only flagged once
+    assert_eq!(sweep.sweep(unix_time() + 1).await?, (false, false));
+
+    sweep.tx.rollback().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn late_payment_of_an_archived_quote_is_flagged() -> Result<()> {
+    let mut sweep = Sweep::new().await?;
+
+    assert_eq!(sweep.sweep(unix_time() + 1).await?, (true, false));
+
+    sweep.pay(QUOTE_AMOUNT).await?;
+    assert_eq!(sweep.sweep(unix_time() + 1).await?, (true, true));
+
+    sweep.tx.rollback().await?;
+    Ok(())
+}
@@ -0,0 +1,333 @@
+//! Replay recorded block streams, with partial payments and forks, through the substreams sink,
+//! against the node database
+//!
+//! Each scenario runs in a transaction that is rolled back at the end,
+//! so that they neither interfere with each other nor leave anything behind.
+
+use anyhow::{Result, anyhow};
+use node_tests::unix_time;
+use nuts::{Amount, nut04::MintQuoteState, nut05::MeltQuoteState};
+use sqlx::{PgPool, Postgres, Transaction};
+use starknet_types::{Asset, ChainId, StarknetU256, Unit, constants::ON_CHAIN_CONSTANTS};
+use starknet_types_core::felt::Felt;
+use substreams_sink::RemittanceEvent;
+use uuid::Uuid;
+
+/// Far above any block a test database may have indexed for real
+const FIRST_BLOCK: u64 = 1 << 40;
+const QUOTE_AMOUNT: u64 = 10;
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    /// A new block, with payments of these amounts for the quote
+    Block(u64, &'static [u64]),
+    /// The chain reorganized, every block above this one is gone
+    Undo(u64),
+}
+
+struct Replay {
+    tx: Transaction<'static, Postgres>,
+    run_id: Uuid,
+    cashier: Felt,
+    strk_address: Felt,
+    invoice_id: [u8; 32],
+    confirmations: u64,
+    steps: usize,
+}
+
+impl Replay {
+    async fn new(confirmations: u64) -> Result<Self> {
+        let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+        let strk_address = ON_CHAIN_CONSTANTS
+            .get(ChainId::Devnet.as_str())
+            .and_then(|c| {
+                c.assets_contract_address
+                    .get_contract_address_for_asset(Asset::Strk)
+            })
+            .ok_or(anyhow!("no strk address on devnet"))?;
+        let run_id = Uuid::new_v4();
+        let mut invoice_id = [0u8; 32];
+        invoice_id[16..].copy_from_slice(run_id.as_bytes());
+
+        Ok(Self {
+            tx: pg_pool.begin().await?,
+            run_id,
+            cashier: Felt::from(0xca5e1e4u64),
+            strk_address,
+            invoice_id,
+            confirmations,
+            steps: 0,
+        })
+    }
+
+    async fn create_mint_quote(&mut self) -> Result<Uuid> {
+        let quote_id = Uuid::new_v4();
+        db_node::mint_quote::insert_new(
+            &mut self.tx,
+            quote_id,
+            self.invoice_id,
+            Unit::MilliStrk,
+            Amount::from(QUOTE_AMOUNT),
+            "request",
+            unix_time() + 3600,
+        )
+        .await?;
+
+        Ok(quote_id)
+    }
+
+    async fn create_pending_melt_quote(&mut self) -> Result<Uuid> {
+        let quote_id = Uuid::new_v4();
+        db_node::melt_quote::insert_new(
+            &mut self.tx,
+            quote_id,
+            &self.invoice_id,
+            Unit::MilliStrk,
+            Amount::from(QUOTE_AMOUNT),
+            Amount::ZERO,
+            "request",
+            unix_time() + 3600,
+        )
+        .await?;
+        db_node::melt_quote::set_state(&mut self.tx, quote_id, MeltQuoteState::Pending).await?;
+
+        Ok(quote_id)
+    }
+
+    /// Process the step the way the sink does, `is_mint` telling the direction of the payments
+    async fn apply(&mut self, step: Step, is_mint: bool) -> Result<()> {
+        self.steps += 1;
+        match step {
+            Step::Block(number, amounts) => {
+                let number = FIRST_BLOCK + number;
+                let events = amounts
+                    .iter()
+                    .enumerate()
+                    .map(|(index, amount)| self.payment_event(index, *amount, is_mint))
+                    .collect();
+                substreams_sink::index_block_events(
+                    &mut self.tx,
+                    // Blocks of different forks have different ids
+                    &format!("{}-{}-{}", self.run_id, number, self.steps),
+                    number,
+                    unix_time().try_into()?,
+                    events,
+                    &ChainId::Devnet,
+                    self.cashier,
+                )
+                .await?;
+                substreams_sink::confirm_payments(
+                    &mut self.tx,
+                    number..=number,
+                    self.confirmations,
+                )
+                .await?;
+            }
+            Step::Undo(last_valid) => {
+                substreams_sink::undo_blocks(
+                    &mut self.tx,
+                    FIRST_BLOCK + last_valid,
+                    self.confirmations,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn payment_event(&self, index: usize, amount: u64, is_mint: bool) -> RemittanceEvent {
+        let amount = Unit::MilliStrk.convert_amount_into_u256(Amount::from(amount));
+        let amount = StarknetU256::from_parts(amount.low_u128(), 0u128);
+        let other_party = Felt::from(0xa11ceu64);
+        let (payer, payee) = if is_mint {
+            (other_party, self.cashier)
+        } else {
+            (self.cashier, other_party)
+        };
+        let mut tx_hash = [0u8; 32];
+        tx_hash[16..].copy_from_slice(Uuid::new_v4().as_bytes());
+
+        RemittanceEvent {
+            tx_hash: tx_hash.to_vec(),
+            event_index: index as u64,
+            asset: self.strk_address.to_bytes_be().to_vec(),
+            payer: payer.to_bytes_be().to_vec(),
+            payee: payee.to_bytes_be().to_vec(),
+            invoice_id: self.invoice_id.to_vec(),
+            amount_low: amount.low.to_bytes_be().to_vec(),
+            amount_high: amount.high.to_bytes_be().to_vec(),
+        }
+    }
+}
+
+/// Replay the stream, checking the state and the amount paid of the mint quote after each step
+async fn replay_mint(confirmations: u64, stream: &[(Step, MintQuoteState, u64)]) -> Result<()> {
+    let mut replay = Replay::new(confirmations).await?;
+    let quote_id = replay.create_mint_quote().await?;
+
+    for (step, expected_state, expected_paid) in stream {
+        replay.apply(*step, true).await?;
+        let quote = db_node::mint_quote::build_response_from_db(&mut replay.tx, quote_id)
+            .await?
+            .ok_or(anyhow!("quote not found"))?;
+        assert_eq!(quote.state, *expected_state, "after {:?}", step);
+        assert_eq!(
+            quote.amount_paid,
+            Amount::from(*expected_paid),
+            "after {:?}",
+            step
+        );
+    }
+
+    replay.tx.rollback().await?;
+    Ok(())
+}
+
+/// Replay the stream, checking the state of the melt quote after each step
+async fn replay_melt(confirmations: u64, stream: &[(Step, MeltQuoteState)]) -> Result<()> {
+    let mut replay = Replay::new(confirmations).await?;
+    let quote_id = replay.create_pending_melt_quote().await?;
+
+    for (step, expected_state) in stream {
+        replay.apply(*step, false).await?;
+        let state = db_node::melt_quote::get_state(&mut replay.tx, quote_id).await?;
+        assert_eq!(state, *expected_state, "after {:?}", step);
+    }
+
+    replay.tx.rollback().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn mint_quote_is_paid_once_confirmed() -> Result<()> {
+    replay_mint(
+        3,
+        &[
+            (Step::Block(1, &[]), MintQuoteState::Unpaid, 0),
+            (Step::Block(2, &[QUOTE_AMOUNT]), MintQuoteState::Unpaid, 0),
+            (Step::Block(3, &[]), MintQuoteState::Unpaid, 0),
+            (Step::Block(4, &[]), MintQuoteState::Paid, QUOTE_AMOUNT),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn reorged_mint_payment_reverts_the_quote() -> Result<()> {
+    replay_mint(
+        1,
+        &[
+            (
+                Step::Block(1, &[QUOTE_AMOUNT]),
+                MintQuoteState::Paid,
+                QUOTE_AMOUNT,
+            ),
+            (Step::Block(2, &[]), MintQuoteState::Paid, QUOTE_AMOUNT),
+            (Step::Undo(0), MintQuoteState::Unpaid, 0),
+            (Step::Block(1, &[]), MintQuoteState::Unpaid, 0),
+            (Step::Block(2, &[]), MintQuoteState::Unpaid, 0),
+            // The payment made it into the new fork
+            (
+                Step::Block(3, &[QUOTE_AMOUNT]),
+                MintQuoteState::Paid,
+                QUOTE_AMOUNT,
+            ),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn mint_payment_reorged_before_confirmation_is_ignored() -> Result<()> {
+    replay_mint(
+        2,
+        &[
+            (Step::Block(1, &[QUOTE_AMOUNT]), MintQuoteState::Unpaid, 0),
+            (Step::Undo(0), MintQuoteState::Unpaid, 0),
+            (Step::Block(1, &[]), MintQuoteState::Unpaid, 0),
+            (Step::Block(2, &[]), MintQuoteState::Unpaid, 0),
+            (Step::Block(3, &[]), MintQuoteState::Unpaid, 0),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn reorged_partial_payment_is_deducted() -> Result<()> {
+    replay_mint(
+        1,
+        &[
+            (Step::Block(1, &[4]), MintQuoteState::Unpaid, 4),
+            (Step::Block(2, &[6]), MintQuoteState::Paid, QUOTE_AMOUNT),
+            (Step::Undo(1), MintQuoteState::Unpaid, 4),
+            (Step::Block(2, &[]), MintQuoteState::Unpaid, 4),
+            (Step::Block(3, &[3, 3]), MintQuoteState::Paid, QUOTE_AMOUNT),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn reorged_melt_payment_reverts_the_quote() -> Result<()> {
+    replay_melt(
+        1,
+        &[
+            (Step::Block(1, &[QUOTE_AMOUNT]), MeltQuoteState::Paid),
+            (Step::Undo(0), MeltQuoteState::Pending),
+            (Step::Block(1, &[]), MeltQuoteState::Pending),
+            // The payment transaction made it into the new fork
+            (Step::Block(2, &[QUOTE_AMOUNT]), MeltQuoteState::Paid),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn underpaid_mint_quote_stays_unpaid() -> Result<()> {
+    replay_mint(
+        1,
+        &[
+            (Step::Block(1, &[4]), MintQuoteState::Unpaid, 4),
+            (Step::Block(2, &[]), MintQuoteState::Unpaid, 4),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn mint_quote_paid_in_several_transfers() -> Result<()> {
+    replay_mint(
+        1,
+        &[
+            (Step::Block(1, &[3, 3]), MintQuoteState::Unpaid, 6),
+            (Step::Block(2, &[1]), MintQuoteState::Unpaid, 7),
+            (Step::Block(3, &[3]), MintQuoteState::Paid, QUOTE_AMOUNT),
+        ],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn overpaid_mint_quote_records_the_excess() -> Result<()> {
+    let mut replay = Replay::new(1).await?;
+    let quote_id = replay.create_mint_quote().await?;
+
+    replay.apply(Step::Block(1, &[7]), true).await?;
+    replay.apply(Step::Block(2, &[5]), true).await?;
+
+    let quote = db_node::mint_quote::build_response_from_db(&mut replay.tx, quote_id)
+        .await?
+        .ok_or(anyhow!("quote not found"))?;
+    assert_eq!(quote.state, MintQuoteState::Paid);
+    assert_eq!(quote.amount_paid, Amount::from(12u64));
+    let excess: Option<Vec<u8>> = sqlx::query_scalar("SELECT excess FROM mint_quote WHERE id = $1")
+        .bind(quote_id)
+        .fetch_one(&mut *replay.tx)
+        .await?;
+    let expected = Unit::MilliStrk.convert_amount_into_u256(Amount::from(2u64));
+    assert_eq!(excess, Some(expected.to_big_endian().to_vec()));
+
+    replay.tx.rollback().await?;
+    Ok(())
+}
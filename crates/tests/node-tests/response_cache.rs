@@ -0,0 +1,141 @@
+//! Check the responses cached in the node database, found at `PG_URL`
+
+use std::time::Duration;
+
+use anyhow::Result;
+use cashu_client::{CashuClient, ClientMintQuoteRequest};
+use node_tests::init_node_client;
+use nuts::Amount;
+use nuts::dhke::blind_message;
+use nuts::nut00::secret::Secret;
+use nuts::nut02::KeysetId;
+use nuts::nut19::hash_mint_request;
+use sqlx::PgPool;
+use starknet_types::Unit;
+use uuid::Uuid;
+
+const ROUTE: &str = "mint";
+
+/// A request hash no other test will use
+fn request_hash() -> u64 {
+    Uuid::new_v4().as_u64_pair().0
+}
+
+// The whole scenario runs in a transaction that is rolled back,
+// during which `NOW()` doesn't move: a zero TTL means already expired.
+#[tokio::test]
+async fn entries_expire_after_their_ttl() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut tx = pg_pool.begin().await?;
+    let (expired, alive, eternal) = (request_hash(), request_hash(), request_hash());
+
+    db_node::response_cache::insert(&mut tx, ROUTE, expired, b"expired", Some(Duration::ZERO))
+        .await?;
+    db_node::response_cache::insert(
+        &mut tx,
+        ROUTE,
+        alive,
+        b"alive",
+        Some(Duration::from_secs(3600)),
+    )
+    .await?;
+    db_node::response_cache::insert(&mut tx, ROUTE, eternal, b"eternal", None).await?;
+
+    assert_eq!(
+        db_node::response_cache::get(&mut tx, ROUTE, expired).await?,
+        None
+    );
+    assert_eq!(
+        db_node::response_cache::get(&mut tx, ROUTE, alive).await?,
+        Some(b"alive".to_vec())
+    );
+    assert_eq!(
+        db_node::response_cache::get(&mut tx, ROUTE, eternal).await?,
+        Some(b"eternal".to_vec())
+    );
+
+    // Other expired entries may exist in the database
+    assert!(db_node::response_cache::delete_expired(&mut tx).await? >= 1);
+    assert!(!db_node::response_cache::remove(&mut tx, ROUTE, expired).await?);
+    assert!(db_node::response_cache::remove(&mut tx, ROUTE, alive).await?);
+    assert!(db_node::response_cache::remove(&mut tx, ROUTE, eternal).await?);
+
+    tx.rollback().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn insert_replaces_the_previous_response() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut tx = pg_pool.begin().await?;
+    let hash = request_hash();
+
+    db_node::response_cache::insert(&mut tx, ROUTE, hash, b"first", Some(Duration::ZERO)).await?;
+    db_node::response_cache::insert(&mut tx, ROUTE, hash, b"second", None).await?;
+    assert_eq!(
+        db_node::response_cache::get(&mut tx, ROUTE, hash).await?,
+        Some(b"second".to_vec())
+    );
+    // Same hash, other route
+    assert_eq!(
+        db_node::response_cache::get(&mut tx, "swap", hash).await?,
+        None
+    );
+
+    tx.rollback().await?;
+    Ok(())
+}
+
+// This test needs the node to cache its responses in postgres, which is the default.
+//
+// - mint and check the response is stored in the database
+// - acknowledge it and check it is removed
+#[tokio::test]
+async fn node_stores_responses_in_the_database() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut client = init_node_client().await?;
+    let amount = Amount::from_i64_repr(8);
+
+    let quote = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: amount.into(),
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+    let keysets = client.keysets().await?.keysets;
+    let active_keyset = keysets
+        .iter()
+        .find(|ks| ks.active && ks.unit == Unit::MilliStrk.as_str())
+        .unwrap();
+    let (blinded_secret, _r) = blind_message(Secret::generate().as_bytes(), None)?;
+    let mint_request = nuts::nut04::MintRequest {
+        quote: quote.quote,
+        outputs: vec![nuts::nut00::BlindedMessage {
+            amount,
+            keyset_id: KeysetId::from_bytes(&active_keyset.id)?,
+            blinded_secret,
+        }],
+    };
+    client
+        .mint(mint_request.clone(), "starknet".to_string())
+        .await?;
+
+    let request_hash = hash_mint_request(&mint_request);
+    let mut conn = pg_pool.acquire().await?;
+    assert!(
+        db_node::response_cache::get(&mut conn, ROUTE, request_hash)
+            .await?
+            .is_some()
+    );
+
+    client.acknowledge(ROUTE.to_string(), request_hash).await?;
+    assert!(
+        db_node::response_cache::get(&mut conn, ROUTE, request_hash)
+            .await?
+            .is_none()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,367 @@
+//! Round-trips through each route of the REST API, listening on `REST_PORT`
+
+use anyhow::Result;
+use cashu_client::{
+    CashuClient, CashuClientError, CheckStateRequest, ClientMeltQuoteRequest,
+    ClientMintQuoteRequest, HttpClient, ProofErrorKind,
+};
+use node_tests::init_http_client;
+use nuts::Amount;
+use nuts::dhke::{blind_message, hash_to_curve, unblind_message};
+use nuts::nut00::secret::Secret;
+use nuts::nut00::{BlindedMessage, CashuError, Proof, error_code};
+use nuts::nut01::SecretKey;
+use nuts::nut02::KeysetId;
+use nuts::nut04::MintQuoteState;
+use nuts::nut05::MeltQuoteState;
+use nuts::nut07::ProofState;
+use nuts::nut19::hash_mint_request;
+use starknet_liquidity_source::MeltPaymentRequest;
+use starknet_types::{StarknetU256, Unit};
+use starknet_types_core::felt::Felt;
+
+const AMOUNT: u64 = 32;
+
+fn rest_url(path: &str) -> Result<String> {
+    Ok(format!(
+        "http://[::1]:{}/v1/{}",
+        std::env::var("REST_PORT")?,
+        path
+    ))
+}
+
+async fn active_keyset_id(client: &mut HttpClient) -> Result<KeysetId> {
+    let keysets = client.keysets().await?.keysets;
+    let active_keyset = keysets
+        .iter()
+        .find(|ks| ks.active && ks.unit == Unit::MilliStrk.as_str())
+        .unwrap();
+
+    Ok(KeysetId::from_bytes(&active_keyset.id)?)
+}
+
+/// Blind a new secret, returning the secret and its blinding factor along with the output
+fn new_output(keyset_id: KeysetId) -> Result<(Secret, SecretKey, BlindedMessage)> {
+    let secret = Secret::generate();
+    let (blinded_secret, r) = blind_message(secret.as_bytes(), None)?;
+
+    Ok((
+        secret,
+        r,
+        BlindedMessage {
+            amount: Amount::from(AMOUNT),
+            keyset_id,
+            blinded_secret,
+        },
+    ))
+}
+
+/// Mint a proof of `AMOUNT` through the REST API
+async fn mint_proof(client: &mut HttpClient) -> Result<Proof> {
+    let keyset_id = active_keyset_id(client).await?;
+    let quote = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: AMOUNT,
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+    let (secret, r, output) = new_output(keyset_id)?;
+    let mint_response = client
+        .mint(
+            nuts::nut04::MintRequest {
+                quote: quote.quote,
+                outputs: vec![output],
+            },
+            "starknet".to_string(),
+        )
+        .await?;
+
+    unblind(
+        client,
+        keyset_id,
+        secret,
+        &r,
+        &mint_response.signatures[0].c,
+    )
+    .await
+}
+
+async fn unblind(
+    client: &mut HttpClient,
+    keyset_id: KeysetId,
+    secret: Secret,
+    r: &SecretKey,
+    blind_signature: &nuts::nut01::PublicKey,
+) -> Result<Proof> {
+    let node_pubkey_for_amount = client
+        .keys(Some(keyset_id))
+        .await?
+        .keysets
+        .first()
+        .unwrap()
+        .keys
+        .iter()
+        .find(|key| key.amount == Amount::from(AMOUNT))
+        .unwrap()
+        .publickey;
+
+    Ok(Proof {
+        amount: Amount::from(AMOUNT),
+        keyset_id,
+        secret,
+        c: unblind_message(blind_signature, r, &node_pubkey_for_amount)?,
+        witness: None,
+        dleq: None,
+    })
+}
+
+#[tokio::test]
+async fn keys_and_keysets() -> Result<()> {
+    let mut client = init_http_client().await?;
+
+    let keyset_id = active_keyset_id(&mut client).await?;
+    let all_keys = client.keys(None).await?.keysets;
+    assert!(all_keys.iter().any(|ks| ks.id == keyset_id.to_bytes()));
+    let keys = client.keys(Some(keyset_id)).await?.keysets;
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].id, keyset_id.to_bytes());
+    assert!(!keys[0].keys.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn info() -> Result<()> {
+    let mut client = init_http_client().await?;
+
+    let info: serde_json::Value = serde_json::from_str(&client.info().await?.info)?;
+    assert!(info.get("nuts").is_some());
+
+    Ok(())
+}
+
+// This test goes through the mint routes, then spends the proof with swap,
+// and checks its state and the restore of its outputs.
+#[tokio::test]
+async fn mint_swap_check_state_and_restore() -> Result<()> {
+    let mut client = init_http_client().await?;
+    let keyset_id = active_keyset_id(&mut client).await?;
+
+    let quote = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: AMOUNT,
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+    let quote_state = client
+        .mint_quote_state("starknet".to_string(), quote.quote.clone())
+        .await?;
+    assert_eq!(quote_state.quote, quote.quote);
+
+    let (secret, r, output) = new_output(keyset_id)?;
+    let mint_response = client
+        .mint(
+            nuts::nut04::MintRequest {
+                quote: quote.quote.clone(),
+                outputs: vec![output],
+            },
+            "starknet".to_string(),
+        )
+        .await?;
+    let quote_state = client
+        .mint_quote_state("starknet".to_string(), quote.quote)
+        .await?;
+    assert_eq!(quote_state.state, MintQuoteState::Issued);
+    let proof = unblind(
+        &mut client,
+        keyset_id,
+        secret,
+        &r,
+        &mint_response.signatures[0].c,
+    )
+    .await?;
+    let y = hash_to_curve(proof.secret.as_bytes())?;
+
+    let state = client
+        .check_state(CheckStateRequest {
+            ys: vec![y.to_bytes().to_vec()],
+        })
+        .await?;
+    assert_eq!(state.proof_check_states[0].state, ProofState::Unspent);
+
+    let (_, _, output) = new_output(keyset_id)?;
+    let swap_response = client
+        .swap(nuts::nut03::SwapRequest {
+            inputs: vec![proof],
+            outputs: vec![output.clone()],
+        })
+        .await?;
+    let state = client
+        .check_state(CheckStateRequest {
+            ys: vec![y.to_bytes().to_vec()],
+        })
+        .await?;
+    assert_eq!(state.proof_check_states[0].state, ProofState::Spent);
+
+    let restored = client.restore(vec![output.clone()]).await?;
+    assert_eq!(restored.outputs, vec![output]);
+    assert_eq!(restored.signatures, swap_response.signatures);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn melt() -> Result<()> {
+    let mut client = init_http_client().await?;
+    let proof = mint_proof(&mut client).await?;
+
+    let melt_quote = client
+        .melt_quote(ClientMeltQuoteRequest {
+            method: "starknet".to_string(),
+            unit: Unit::MilliStrk.to_string(),
+            request: serde_json::to_string(&MeltPaymentRequest {
+                payee: Felt::from_hex_unchecked(
+                    "0x064b48806902a367c8598f4f95c305e8c1a1acba5f082d294a43793113115691",
+                ),
+                asset: starknet_types::Asset::Strk,
+                amount: StarknetU256 {
+                    low: Felt::from_dec_str("32000000000000000").unwrap(),
+                    high: Felt::from(0),
+                },
+            })?,
+        })
+        .await?;
+    let quote_state = client
+        .melt_quote_state("starknet".to_string(), melt_quote.quote.clone())
+        .await?;
+    assert_eq!(quote_state.state, MeltQuoteState::Unpaid);
+
+    client
+        .melt(
+            "starknet".to_string(),
+            nuts::nut05::MeltRequest {
+                quote: melt_quote.quote.clone(),
+                inputs: vec![proof],
+            },
+        )
+        .await?;
+    let quote_state = client
+        .melt_quote_state("starknet".to_string(), melt_quote.quote)
+        .await?;
+    assert_ne!(quote_state.state, MeltQuoteState::Unpaid);
+
+    Ok(())
+}
+
+// This test checks that acknowledging a response removes it from the cache.
+//
+// - mint and check that the same request gets the cached response
+// - acknowledge it
+// - check that the same request now fails, the quote being already issued
+#[tokio::test]
+async fn acknowledge() -> Result<()> {
+    let mut client = init_http_client().await?;
+    let keyset_id = active_keyset_id(&mut client).await?;
+    let quote = client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: AMOUNT,
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?;
+    let (_, _, output) = new_output(keyset_id)?;
+    let mint_request = nuts::nut04::MintRequest {
+        quote: quote.quote,
+        outputs: vec![output],
+    };
+    let original_mint_response = client
+        .mint(mint_request.clone(), "starknet".to_string())
+        .await?;
+    let cached_mint_response = client
+        .mint(mint_request.clone(), "starknet".to_string())
+        .await?;
+    assert_eq!(original_mint_response, cached_mint_response);
+
+    client
+        .acknowledge("mint".to_string(), hash_mint_request(&mint_request))
+        .await?;
+
+    assert!(
+        client
+            .mint(mint_request, "starknet".to_string())
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn errors_use_nut_codes() -> Result<()> {
+    let http = reqwest::Client::new();
+
+    // Not a NUT code, even though gRPC gives it one
+    let response = http
+        .get(rest_url("mint/quote/starknet/not-a-uuid")?)
+        .send()
+        .await?;
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let error: CashuError = response.json().await?;
+    assert_eq!(error.code(), error_code::UNKNOWN);
+
+    // Spending a proof twice
+    let mut client = init_http_client().await?;
+    let proof = mint_proof(&mut client).await?;
+    let keyset_id = proof.keyset_id;
+    let (_, _, signed_output) = new_output(keyset_id)?;
+    client
+        .swap(nuts::nut03::SwapRequest {
+            inputs: vec![proof.clone()],
+            outputs: vec![signed_output.clone()],
+        })
+        .await?;
+    let (_, _, output) = new_output(keyset_id)?;
+    let double_spend = nuts::nut03::SwapRequest {
+        inputs: vec![proof],
+        outputs: vec![output],
+    };
+    let response = http
+        .post(rest_url("swap")?)
+        .json(&double_spend)
+        .send()
+        .await?;
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let error: CashuError = response.json().await?;
+    assert_eq!(error.code(), error_code::PROOF_ALREADY_SPENT);
+    assert_eq!(error.fields()[0].field, "proofs[0]");
+    // The client tells which proof to discard
+    match client.swap(double_spend).await {
+        Err(CashuClientError::Proof(errors)) => {
+            assert!(matches!(errors[0].kind, ProofErrorKind::AlreadySpent));
+            assert_eq!(errors[0].indexes, vec![0]);
+        }
+        other => panic!("unexpected response: {:?}", other),
+    }
+
+    // Getting an output signed twice
+    let proof = mint_proof(&mut client).await?;
+    let response = http
+        .post(rest_url("swap")?)
+        .json(&nuts::nut03::SwapRequest {
+            inputs: vec![proof],
+            outputs: vec![signed_output],
+        })
+        .send()
+        .await?;
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let error: CashuError = response.json().await?;
+    assert_eq!(error.code(), error_code::OUTPUT_ALREADY_SIGNED);
+    assert_eq!(error.fields()[0].field, "outputs");
+
+    Ok(())
+}
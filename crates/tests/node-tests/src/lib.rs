@@ -1,15 +1,27 @@
 use anyhow::{Result, anyhow};
-use cashu_client::GrpcClient;
+use cashu_client::{GrpcClient, HttpClient};
 use std::time::{Duration, Instant};
 use tonic_health::pb::health_client::HealthClient;
 
+use node_client::admin::AdminClient;
 use node_client::keyset_rotation_service_client::KeysetRotationServiceClient;
 use node_client::node_client::NodeClient;
 
-use tonic::transport::Channel;
+pub use nuts::unix_time;
+
+use tonic::{
+    Request, Status,
+    metadata::{Ascii, MetadataValue},
+    service::{Interceptor, interceptor::InterceptedService},
+    transport::Channel,
+};
 
 async fn get_grpc_channel() -> Result<Channel> {
-    let grpc_port = std::env::var("GRPC_PORT")?;
+    connect_to_port_from_env("GRPC_PORT").await
+}
+
+async fn connect_to_port_from_env(port_env_var: &str) -> Result<Channel> {
+    let grpc_port = std::env::var(port_env_var)?;
     let endpoint = format!("http://[::0]:{}", grpc_port);
 
     let timeout = Instant::now() + Duration::from_secs(10);
@@ -43,6 +55,21 @@ pub async fn init_node_client() -> Result<GrpcClient> {
     Ok(client)
 }
 
+/// A client of the REST API, listening on `REST_PORT`
+pub async fn init_http_client() -> Result<HttpClient> {
+    let rest_port = std::env::var("REST_PORT")?;
+    let base_url = url::Url::parse(&format!("http://[::1]:{}", rest_port))?;
+
+    let timeout = Instant::now() + Duration::from_secs(10);
+    while reqwest::get(base_url.join("v1/info")?).await.is_err() {
+        if Instant::now() > timeout {
+            return Err(anyhow!("timeout waiting for node"));
+        }
+    }
+
+    Ok(HttpClient::new(base_url)?)
+}
+
 pub async fn init_keyset_client() -> Result<KeysetRotationServiceClient<tonic::transport::Channel>>
 {
     let channel = get_grpc_channel().await?;
@@ -50,3 +77,34 @@ pub async fn init_keyset_client() -> Result<KeysetRotationServiceClient<tonic::t
 
     Ok(client)
 }
+
+/// Adds the `ADMIN_TOKEN` to the admin service requests
+#[derive(Debug, Clone)]
+pub struct AdminToken(MetadataValue<Ascii>);
+
+impl Interceptor for AdminToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        request
+            .metadata_mut()
+            .insert("authorization", self.0.clone());
+
+        Ok(request)
+    }
+}
+
+pub type AuthenticatedAdminClient = AdminClient<InterceptedService<Channel, AdminToken>>;
+
+pub async fn init_admin_client() -> Result<AuthenticatedAdminClient> {
+    let channel = connect_to_port_from_env("ADMIN_GRPC_PORT").await?;
+    let token = std::env::var("ADMIN_TOKEN")?;
+    let client =
+        AdminClient::with_interceptor(channel, AdminToken(format!("Bearer {token}").parse()?));
+
+    Ok(client)
+}
+
+pub async fn init_unauthenticated_admin_client() -> Result<AdminClient<Channel>> {
+    let channel = connect_to_port_from_env("ADMIN_GRPC_PORT").await?;
+
+    Ok(AdminClient::new(channel))
+}
@@ -0,0 +1,273 @@
+//! Check that the subscriptions of the gRPC `Subscribe` stream and of the `/v1/ws` websocket
+//! notify the changes of quote and proof states
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use cashu_client::{
+    CashuClient, ClientMeltQuoteRequest, ClientMintQuoteRequest, ClientNotification,
+    ClientNotificationStream, ClientSubscribeRequest, ClientSubscriptionKind,
+};
+use futures::StreamExt;
+use node_tests::{init_http_client, init_node_client};
+use nuts::Amount;
+use nuts::dhke::{blind_message, hash_to_curve, unblind_message};
+use nuts::nut00::secret::Secret;
+use nuts::nut00::{BlindedMessage, Proof};
+use nuts::nut02::KeysetId;
+use nuts::nut04::MintQuoteState;
+use nuts::nut05::MeltQuoteState;
+use nuts::nut07::ProofState;
+use starknet_liquidity_source::MeltPaymentRequest;
+use starknet_types::{StarknetU256, Unit};
+use starknet_types_core::felt::Felt;
+
+const AMOUNT: u64 = 32;
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn next_notification(stream: &mut ClientNotificationStream) -> Result<ClientNotification> {
+    tokio::time::timeout(NOTIFICATION_TIMEOUT, stream.next())
+        .await
+        .map_err(|_| anyhow!("no notification received"))?
+        .ok_or(anyhow!("subscription closed"))?
+        .map_err(Into::into)
+}
+
+async fn active_keyset_id<C: CashuClient>(client: &mut C) -> Result<KeysetId> {
+    let keysets = client.keysets().await?.keysets;
+    let active_keyset = keysets
+        .iter()
+        .find(|ks| ks.active && ks.unit == Unit::MilliStrk.as_str())
+        .unwrap();
+
+    Ok(KeysetId::from_bytes(&active_keyset.id)?)
+}
+
+fn new_output(keyset_id: KeysetId) -> Result<(Secret, nuts::nut01::SecretKey, BlindedMessage)> {
+    let secret = Secret::generate();
+    let (blinded_secret, r) = blind_message(secret.as_bytes(), None)?;
+
+    Ok((
+        secret,
+        r,
+        BlindedMessage {
+            amount: Amount::from(AMOUNT),
+            keyset_id,
+            blinded_secret,
+        },
+    ))
+}
+
+async fn mint_quote<C: CashuClient>(client: &mut C) -> Result<String> {
+    Ok(client
+        .mint_quote(ClientMintQuoteRequest {
+            method: "starknet".to_string(),
+            amount: AMOUNT,
+            unit: Unit::MilliStrk.to_string(),
+            description: None,
+        })
+        .await?
+        .quote)
+}
+
+async fn mint_proof<C: CashuClient>(client: &mut C, quote: String) -> Result<Proof> {
+    let keyset_id = active_keyset_id(client).await?;
+    let (secret, r, output) = new_output(keyset_id)?;
+    let mint_response = client
+        .mint(
+            nuts::nut04::MintRequest {
+                quote,
+                outputs: vec![output],
+            },
+            "starknet".to_string(),
+        )
+        .await?;
+    let node_pubkey_for_amount = client
+        .keys(Some(keyset_id))
+        .await?
+        .keysets
+        .first()
+        .unwrap()
+        .keys
+        .iter()
+        .find(|key| key.amount == Amount::from(AMOUNT))
+        .unwrap()
+        .publickey;
+
+    Ok(Proof {
+        amount: Amount::from(AMOUNT),
+        keyset_id,
+        secret,
+        c: unblind_message(&mint_response.signatures[0].c, &r, &node_pubkey_for_amount)?,
+        witness: None,
+        dleq: None,
+    })
+}
+
+// - subscribe to a new mint quote and check its current state is notified
+// - mint it and check the issued state is notified
+async fn mint_quote_notifications<C: CashuClient>(mut client: C) -> Result<()> {
+    let quote = mint_quote(&mut client).await?;
+    let mut stream = client
+        .subscribe(ClientSubscribeRequest {
+            kind: ClientSubscriptionKind::MintQuote,
+            method: "starknet".to_string(),
+            filters: vec![quote.clone()],
+        })
+        .await?;
+
+    match next_notification(&mut stream).await? {
+        ClientNotification::MintQuote(response) => {
+            assert_eq!(response.quote, quote);
+            assert_ne!(response.state, MintQuoteState::Issued);
+        }
+        n => panic!("unexpected notification: {:?}", n),
+    }
+
+    mint_proof(&mut client, quote.clone()).await?;
+    loop {
+        match next_notification(&mut stream).await? {
+            ClientNotification::MintQuote(response) => {
+                assert_eq!(response.quote, quote);
+                if response.state == MintQuoteState::Issued {
+                    break;
+                }
+            }
+            n => panic!("unexpected notification: {:?}", n),
+        }
+    }
+
+    Ok(())
+}
+
+// - subscribe to the state of an unspent proof and check it is notified
+// - swap it and check the spent state is notified
+async fn proof_state_notifications<C: CashuClient>(mut client: C) -> Result<()> {
+    let quote = mint_quote(&mut client).await?;
+    let proof = mint_proof(&mut client, quote).await?;
+    let y = hash_to_curve(proof.secret.as_bytes())?;
+    let mut stream = client
+        .subscribe(ClientSubscribeRequest {
+            kind: ClientSubscriptionKind::ProofState,
+            method: String::new(),
+            filters: vec![y.to_hex()],
+        })
+        .await?;
+
+    match next_notification(&mut stream).await? {
+        ClientNotification::ProofState(state) => {
+            assert_eq!(state.y, y);
+            assert_eq!(state.state, ProofState::Unspent);
+        }
+        n => panic!("unexpected notification: {:?}", n),
+    }
+
+    let (_, _, output) = new_output(proof.keyset_id)?;
+    client
+        .swap(nuts::nut03::SwapRequest {
+            inputs: vec![proof],
+            outputs: vec![output],
+        })
+        .await?;
+    loop {
+        match next_notification(&mut stream).await? {
+            ClientNotification::ProofState(state) => {
+                assert_eq!(state.y, y);
+                if state.state == ProofState::Spent {
+                    break;
+                }
+            }
+            n => panic!("unexpected notification: {:?}", n),
+        }
+    }
+
+    Ok(())
+}
+
+// - subscribe to a new melt quote and check it is notified unpaid
+// - melt it and check its new state is notified
+async fn melt_quote_notifications<C: CashuClient>(mut client: C) -> Result<()> {
+    let quote = mint_quote(&mut client).await?;
+    let proof = mint_proof(&mut client, quote).await?;
+    let melt_quote = client
+        .melt_quote(ClientMeltQuoteRequest {
+            method: "starknet".to_string(),
+            unit: Unit::MilliStrk.to_string(),
+            request: serde_json::to_string(&MeltPaymentRequest {
+                payee: Felt::from_hex_unchecked(
+                    "0x064b48806902a367c8598f4f95c305e8c1a1acba5f082d294a43793113115691",
+                ),
+                asset: starknet_types::Asset::Strk,
+                amount: StarknetU256 {
+                    low: Felt::from_dec_str("32000000000000000").unwrap(),
+                    high: Felt::from(0),
+                },
+            })?,
+        })
+        .await?
+        .quote;
+    let mut stream = client
+        .subscribe(ClientSubscribeRequest {
+            kind: ClientSubscriptionKind::MeltQuote,
+            method: "starknet".to_string(),
+            filters: vec![melt_quote.clone()],
+        })
+        .await?;
+
+    match next_notification(&mut stream).await? {
+        ClientNotification::MeltQuote(response) => {
+            assert_eq!(response.quote, melt_quote);
+            assert_eq!(response.state, MeltQuoteState::Unpaid);
+        }
+        n => panic!("unexpected notification: {:?}", n),
+    }
+
+    client
+        .melt(
+            "starknet".to_string(),
+            nuts::nut05::MeltRequest {
+                quote: melt_quote.clone(),
+                inputs: vec![proof],
+            },
+        )
+        .await?;
+    match next_notification(&mut stream).await? {
+        ClientNotification::MeltQuote(response) => {
+            assert_eq!(response.quote, melt_quote);
+            assert_ne!(response.state, MeltQuoteState::Unpaid);
+        }
+        n => panic!("unexpected notification: {:?}", n),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn grpc_mint_quote_notifications() -> Result<()> {
+    mint_quote_notifications(init_node_client().await?).await
+}
+
+#[tokio::test]
+async fn grpc_proof_state_notifications() -> Result<()> {
+    proof_state_notifications(init_node_client().await?).await
+}
+
+#[tokio::test]
+async fn grpc_melt_quote_notifications() -> Result<()> {
+    melt_quote_notifications(init_node_client().await?).await
+}
+
+#[tokio::test]
+async fn ws_mint_quote_notifications() -> Result<()> {
+    mint_quote_notifications(init_http_client().await?).await
+}
+
+#[tokio::test]
+async fn ws_proof_state_notifications() -> Result<()> {
+    proof_state_notifications(init_http_client().await?).await
+}
+
+#[tokio::test]
+async fn ws_melt_quote_notifications() -> Result<()> {
+    melt_quote_notifications(init_http_client().await?).await
+}
@@ -92,6 +92,7 @@ async fn test_melt_with_valid_address() -> Result<()> {
         keyset_id: active_keyset.id.clone(),
         secret: secret.to_string(),
         unblind_signature: unblinded_signature.to_bytes().to_vec(),
+        witness: None,
     };
 
     let payment_request = MeltPaymentRequest {
@@ -219,6 +220,7 @@ async fn test_melt_with_invalid_addresses() -> Result<()> {
         keyset_id: active_keyset.id.clone(),
         secret: secret.to_string(),
         unblind_signature: unblinded_signature.to_bytes().to_vec(),
+        witness: None,
     };
 
     for invalid_address in invalid_addresses {
@@ -0,0 +1,397 @@
+//! Drive the withdraw orders outbox the way the liquidity source does, against the node database
+//!
+//! Each scenario runs in a transaction that is rolled back at the end,
+//! so that they neither interfere with each other nor leave anything behind.
+//! The lock one expects no node instance to consume the orders, which the mock node doesn't.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use db_node::withdraw_order::{self, WithdrawOrderState};
+use node_tests::unix_time;
+use nuts::{
+    Amount, dhke::hash_to_curve, nut00::Proof, nut00::secret::Secret, nut02::KeysetId,
+    nut05::MeltQuoteState,
+};
+use sqlx::{Connection, PgConnection, PgPool, Postgres, Transaction, postgres::PgListener};
+use starknet_types::{Asset, ChainId, Unit, constants::ON_CHAIN_CONSTANTS};
+use starknet_types_core::felt::Felt;
+use substreams_sink::RemittanceEvent;
+use uuid::Uuid;
+
+/// Far above any block a test database may have indexed for real
+const BLOCK: u64 = (1 << 41) + (1 << 20);
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(15);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Outbox {
+    tx: Transaction<'static, Postgres>,
+    quote_id: Uuid,
+    invoice_id: [u8; 32],
+}
+
+impl Outbox {
+    /// Start with a melt quote whose payment order was just registered
+    async fn new(pg_pool: &PgPool) -> Result<Self> {
+        let mut tx = pg_pool.begin().await?;
+        let (quote_id, invoice_id) = register_melt(&mut tx).await?;
+
+        Ok(Self {
+            tx,
+            quote_id,
+            invoice_id,
+        })
+    }
+
+    /// The number of failed attempts of the order, if it is to be sent now
+    async fn pending_attempts(&mut self) -> Result<Option<u32>> {
+        let pendings = withdraw_order::get_pendings(&mut self.tx, u32::MAX).await?;
+
+        Ok(pendings
+            .into_iter()
+            .find(|order| order.invoice_id == self.invoice_id)
+            .map(|order| order.attempts))
+    }
+
+    async fn state(&mut self) -> Result<WithdrawOrderState> {
+        let state = sqlx::query_scalar("SELECT state FROM withdraw_order WHERE invoice_id = $1")
+            .bind(self.invoice_id.to_vec())
+            .fetch_one(&mut *self.tx)
+            .await?;
+
+        Ok(state)
+    }
+
+    /// Seconds until the next attempt to send the order
+    async fn retry_delay(&mut self) -> Result<f64> {
+        let delay = sqlx::query_scalar(
+            "SELECT EXTRACT(EPOCH FROM next_attempt_at - NOW())::FLOAT8 FROM withdraw_order WHERE invoice_id = $1",
+        )
+        .bind(self.invoice_id.to_vec())
+        .fetch_one(&mut *self.tx)
+        .await?;
+
+        Ok(delay)
+    }
+
+    /// Index a block with the payment of the order, made by the cashier
+    async fn index_payment(&mut self) -> Result<()> {
+        let strk_address = ON_CHAIN_CONSTANTS
+            .get(ChainId::Devnet.as_str())
+            .and_then(|c| {
+                c.assets_contract_address
+                    .get_contract_address_for_asset(Asset::Strk)
+            })
+            .ok_or(anyhow!("no strk address on devnet"))?;
+        let cashier = Felt::from(0xca5e1e4u64);
+        let mut tx_hash = [0u8; 32];
+        tx_hash[16..].copy_from_slice(Uuid::new_v4().as_bytes());
+        let event = RemittanceEvent {
+            tx_hash: tx_hash.to_vec(),
+            event_index: 0,
+            asset: strk_address.to_bytes_be().to_vec(),
+            payer: cashier.to_bytes_be().to_vec(),
+            payee: Felt::from(0xa11ceu64).to_bytes_be().to_vec(),
+            invoice_id: self.invoice_id.to_vec(),
+            amount_low: Felt::from(10u64).to_bytes_be().to_vec(),
+            amount_high: Felt::ZERO.to_bytes_be().to_vec(),
+        };
+
+        substreams_sink::index_block_events(
+            &mut self.tx,
+            &format!("{}-{}", self.quote_id, BLOCK),
+            BLOCK,
+            unix_time().try_into()?,
+            vec![event],
+            &ChainId::Devnet,
+            cashier,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Insert a melt quote and register its payment order, as the melt does in its transaction
+async fn register_melt(conn: &mut PgConnection) -> Result<(Uuid, [u8; 32])> {
+    let quote_id = Uuid::new_v4();
+    let mut invoice_id = [0u8; 32];
+    invoice_id[16..].copy_from_slice(quote_id.as_bytes());
+    db_node::melt_quote::insert_new(
+        conn,
+        quote_id,
+        &invoice_id,
+        Unit::MilliStrk,
+        Amount::from(10u64),
+        Amount::ZERO,
+        "request",
+        unix_time() + 3600,
+    )
+    .await?;
+    assert!(withdraw_order::insert(conn, &invoice_id, quote_id, "payload").await?);
+    db_node::notify::withdraw_order(conn).await?;
+
+    Ok((quote_id, invoice_id))
+}
+
+fn tx_hash() -> String {
+    format!("{:#x}", Uuid::new_v4().as_u128())
+}
+
+#[tokio::test]
+async fn order_is_registered_once() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut outbox = Outbox::new(&pg_pool).await?;
+
+    assert!(
+        !withdraw_order::insert(
+            &mut outbox.tx,
+            &outbox.invoice_id,
+            outbox.quote_id,
+            "other payload"
+        )
+        .await?
+    );
+    assert_eq!(outbox.pending_attempts().await?, Some(0));
+
+    outbox.tx.rollback().await?;
+    Ok(())
+}
+
+// - register an order in a transaction and check the consumer neither sees nor hears of it
+// - commit and check it is notified and sees it
+#[tokio::test]
+async fn order_is_only_consumed_once_the_melt_commits() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut consumer = PgListener::connect_with(&pg_pool).await?;
+    consumer
+        .listen(db_node::notify::WITHDRAW_ORDER_CHANNEL)
+        .await?;
+    let order_count = |invoice_id: [u8; 32]| {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM withdraw_order WHERE invoice_id = $1")
+            .bind(invoice_id.to_vec())
+            .fetch_one(&pg_pool)
+    };
+
+    let mut tx = pg_pool.begin().await?;
+    let (quote_id, invoice_id) = register_melt(&mut tx).await?;
+    let notified_before_commit = tokio::time::timeout(NOTIFICATION_TIMEOUT, consumer.recv()).await;
+    let count_before_commit = order_count(invoice_id).await?;
+    tx.commit().await?;
+    let notified_after_commit = tokio::time::timeout(NOTIFICATION_TIMEOUT, consumer.recv()).await;
+    let count_after_commit = order_count(invoice_id).await?;
+
+    // Remove them before checking, not to leave them to a real consumer
+    sqlx::query("DELETE FROM withdraw_order WHERE invoice_id = $1")
+        .bind(invoice_id.to_vec())
+        .execute(&pg_pool)
+        .await?;
+    sqlx::query("DELETE FROM melt_quote WHERE id = $1")
+        .bind(quote_id)
+        .execute(&pg_pool)
+        .await?;
+
+    assert!(notified_before_commit.is_err());
+    assert_eq!(count_before_commit, 0);
+    assert!(notified_after_commit?.is_ok());
+    assert_eq!(count_after_commit, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn order_of_an_unknown_transaction_is_sent_again() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut outbox = Outbox::new(&pg_pool).await?;
+    let tx_hash = tx_hash();
+
+    withdraw_order::set_sent(&mut outbox.tx, &[outbox.invoice_id], &tx_hash).await?;
+    assert_eq!(outbox.pending_attempts().await?, None);
+    assert!(
+        withdraw_order::get_sent_tx_hashes(&mut outbox.tx)
+            .await?
+            .contains(&tx_hash)
+    );
+
+    // The transaction was not found, it is replayed without counting as an attempt
+    withdraw_order::set_state_by_tx_hash(&mut outbox.tx, &tx_hash, WithdrawOrderState::Pending)
+        .await?;
+    assert_eq!(outbox.pending_attempts().await?, Some(0));
+    assert!(
+        !withdraw_order::get_sent_tx_hashes(&mut outbox.tx)
+            .await?
+            .contains(&tx_hash)
+    );
+
+    outbox.tx.rollback().await?;
+    Ok(())
+}
+
+// - revert the transaction of the order and check it is retried after the base delay
+// - fail it again and check the delay doubled
+// - check it is given up once it reached the maximum number of attempts
+#[tokio::test]
+async fn failed_order_is_retried_with_backoff() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut outbox = Outbox::new(&pg_pool).await?;
+    let tx_hash = tx_hash();
+
+    withdraw_order::set_sent(&mut outbox.tx, &[outbox.invoice_id], &tx_hash).await?;
+    withdraw_order::retry_later_by_tx_hash(
+        &mut outbox.tx,
+        &tx_hash,
+        RETRY_BASE_DELAY,
+        RETRY_MAX_DELAY,
+    )
+    .await?;
+    assert_eq!(outbox.state().await?, WithdrawOrderState::Pending);
+    assert_eq!(outbox.pending_attempts().await?, None);
+    assert_eq!(outbox.retry_delay().await?, 15.0);
+
+    withdraw_order::retry_later(
+        &mut outbox.tx,
+        &[outbox.invoice_id],
+        RETRY_BASE_DELAY,
+        RETRY_MAX_DELAY,
+    )
+    .await?;
+    assert_eq!(outbox.retry_delay().await?, 30.0);
+    assert!(
+        !withdraw_order::fail_exhausted(&mut outbox.tx, 3)
+            .await?
+            .contains(&outbox.quote_id)
+    );
+
+    // Without delay it is sent right away
+    withdraw_order::retry_later(
+        &mut outbox.tx,
+        &[outbox.invoice_id],
+        Duration::ZERO,
+        Duration::ZERO,
+    )
+    .await?;
+    assert_eq!(outbox.pending_attempts().await?, Some(3));
+    assert!(
+        withdraw_order::fail_exhausted(&mut outbox.tx, 3)
+            .await?
+            .contains(&outbox.quote_id)
+    );
+    assert_eq!(outbox.state().await?, WithdrawOrderState::Failed);
+    assert_eq!(outbox.pending_attempts().await?, None);
+
+    outbox.tx.rollback().await?;
+    Ok(())
+}
+
+// - spend a proof to pay the quote, as the melt does
+// - fail its order until it reaches the maximum number of attempts
+// - give it up the way the liquidity source does, and check the proof is spendable again
+#[tokio::test]
+async fn exhausted_order_fails_its_quote_and_returns_its_inputs() -> Result<()> {
+    const MAX_ATTEMPTS: u32 = 3;
+
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut outbox = Outbox::new(&pg_pool).await?;
+
+    let keyset_id: i64 = sqlx::query_scalar("SELECT id FROM keyset LIMIT 1")
+        .fetch_one(&mut *outbox.tx)
+        .await?;
+    let secret = Secret::generate();
+    let y = hash_to_curve(secret.as_bytes())?;
+    let input = Proof {
+        amount: Amount::from(10u64),
+        keyset_id: KeysetId::try_from(keyset_id)?,
+        secret,
+        c: y,
+        witness: None,
+        dleq: None,
+    };
+    let mut insert_spent_proofs = db_node::proof::InsertSpentProofsQueryBuilder::new();
+    insert_spent_proofs.add_row(&y, &input);
+    insert_spent_proofs.set_melt_quote_id(outbox.quote_id);
+    insert_spent_proofs.execute(&mut outbox.tx).await?;
+
+    for _ in 0..MAX_ATTEMPTS {
+        assert!(
+            !withdraw_order::fail_exhausted(&mut outbox.tx, MAX_ATTEMPTS)
+                .await?
+                .contains(&outbox.quote_id)
+        );
+        withdraw_order::retry_later(
+            &mut outbox.tx,
+            &[outbox.invoice_id],
+            Duration::ZERO,
+            Duration::ZERO,
+        )
+        .await?;
+    }
+    assert_eq!(
+        withdraw_order::fail_exhausted(&mut outbox.tx, MAX_ATTEMPTS).await?,
+        vec![outbox.quote_id]
+    );
+    db_node::melt_quote::set_state(&mut outbox.tx, outbox.quote_id, MeltQuoteState::Failed).await?;
+    let returned_inputs =
+        db_node::proof::return_melt_inputs(&mut outbox.tx, outbox.quote_id).await?;
+
+    assert_eq!(returned_inputs.returned, vec![y]);
+    assert_eq!(returned_inputs.kept_amount, Amount::ZERO);
+    let is_spent: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM proof WHERE y = $1)")
+        .bind(y.to_bytes().to_vec())
+        .fetch_one(&mut *outbox.tx)
+        .await?;
+    assert!(!is_spent);
+    assert_eq!(
+        db_node::melt_quote::get_state(&mut outbox.tx, outbox.quote_id).await?,
+        MeltQuoteState::Failed
+    );
+
+    outbox.tx.rollback().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn order_paid_before_the_node_stopped_is_not_sent_again() -> Result<()> {
+    let pg_pool = PgPool::connect(&std::env::var("PG_URL")?).await?;
+    let mut outbox = Outbox::new(&pg_pool).await?;
+
+    assert_eq!(
+        withdraw_order::mark_indexed_as_succeeded(&mut outbox.tx).await?,
+        0
+    );
+    outbox.index_payment().await?;
+    assert_eq!(
+        withdraw_order::mark_indexed_as_succeeded(&mut outbox.tx).await?,
+        1
+    );
+    assert_eq!(outbox.state().await?, WithdrawOrderState::Succeeded);
+    assert_eq!(outbox.pending_attempts().await?, None);
+
+    outbox.tx.rollback().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn consumer_lock_is_taken_over_once_released() -> Result<()> {
+    let pg_url = std::env::var("PG_URL")?;
+    let mut first = PgConnection::connect(&pg_url).await?;
+    let mut second = PgConnection::connect(&pg_url).await?;
+
+    assert!(withdraw_order::try_lock(&mut first).await?);
+    assert!(!withdraw_order::try_lock(&mut second).await?);
+
+    // The lock is released as the server ends the session of the first consumer
+    first.close().await?;
+    let mut taken_over = false;
+    for _ in 0..20 {
+        if withdraw_order::try_lock(&mut second).await? {
+            taken_over = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(taken_over);
+
+    second.close().await?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use signer::{DeclareKeysetRequest, ListKeysetsRequest};
+use signer_tests::init_signer_client;
+use starknet_types::Unit;
+
+#[tokio::test]
+async fn declared_keyset_is_listed() -> Result<()> {
+    let mut client = init_signer_client().await?;
+    let declared = client
+        .declare_keyset(DeclareKeysetRequest {
+            unit: Unit::MilliStrk.to_string(),
+            index: 2,
+            max_order: 16,
+        })
+        .await?
+        .into_inner();
+
+    let keysets = client
+        .list_keysets(ListKeysetsRequest {})
+        .await?
+        .into_inner()
+        .keysets;
+
+    let listed = keysets
+        .iter()
+        .find(|k| k.keyset_id == declared.keyset_id)
+        .expect("declared keyset should be listed");
+    assert_eq!(listed.unit, Unit::MilliStrk.to_string());
+    assert_eq!(listed.index, 2);
+    assert_eq!(listed.max_order, 16);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn declaring_twice_lists_once() -> Result<()> {
+    let mut client = init_signer_client().await?;
+    let request = DeclareKeysetRequest {
+        unit: Unit::MilliStrk.to_string(),
+        index: 3,
+        max_order: 8,
+    };
+    let declared = client.declare_keyset(request.clone()).await?.into_inner();
+    client.declare_keyset(request).await?;
+
+    let keysets = client
+        .list_keysets(ListKeysetsRequest {})
+        .await?
+        .into_inner()
+        .keysets;
+
+    assert_eq!(
+        keysets
+            .iter()
+            .filter(|k| k.keyset_id == declared.keyset_id)
+            .count(),
+        1
+    );
+
+    Ok(())
+}
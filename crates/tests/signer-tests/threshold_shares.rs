@@ -0,0 +1,137 @@
+use assert_matches::assert_matches;
+use nuts::nut01::SecretKey;
+use signer::shares::{
+    Error, KeyPath, ShareFile, combine_points, combine_scalars, deal, lagrange_coefficients,
+};
+
+const PATH: KeyPath = KeyPath::Amount {
+    unit: 0,
+    index: 1,
+    amount: 8,
+};
+
+fn interpolate(share_files: &[&ShareFile], path: KeyPath) -> SecretKey {
+    let indexes: Vec<u32> = share_files.iter().map(|s| s.index).collect();
+    let shares: Vec<SecretKey> = share_files
+        .iter()
+        .map(|s| s.secret_share(path).unwrap())
+        .collect();
+
+    combine_scalars(&lagrange_coefficients(&indexes).unwrap(), &shares).unwrap()
+}
+
+#[test]
+fn every_threshold_subset_recovers_the_same_key() {
+    let share_files = deal(3, 5).unwrap();
+
+    let mut keys = Vec::new();
+    for a in 0..5 {
+        for b in a + 1..5 {
+            for c in b + 1..5 {
+                keys.push(interpolate(
+                    &[&share_files[a], &share_files[b], &share_files[c]],
+                    PATH,
+                ));
+            }
+        }
+    }
+
+    assert_eq!(keys.len(), 10);
+    assert!(keys.iter().all(|key| *key == keys[0]));
+}
+
+#[test]
+fn public_shares_combine_into_the_public_key() {
+    let share_files = deal(2, 3).unwrap();
+    let subset = [&share_files[0], &share_files[2]];
+
+    let public_shares: Vec<_> = subset
+        .iter()
+        .map(|s| s.secret_share(PATH).unwrap().public_key())
+        .collect();
+    let public_key =
+        combine_points(&lagrange_coefficients(&[1, 3]).unwrap(), &public_shares).unwrap();
+
+    assert_eq!(public_key, interpolate(&subset, PATH).public_key());
+}
+
+#[test]
+fn fewer_shares_than_the_threshold_dont_recover_the_key() {
+    let share_files = deal(3, 4).unwrap();
+
+    let key = interpolate(&[&share_files[0], &share_files[1], &share_files[2]], PATH);
+    let guess = interpolate(&[&share_files[0], &share_files[1]], PATH);
+
+    assert_ne!(key, guess);
+}
+
+#[test]
+fn keys_are_independent_across_paths() {
+    let share_files = deal(2, 3).unwrap();
+    let subset = [&share_files[0], &share_files[1]];
+
+    let other_amount = KeyPath::Amount {
+        unit: 0,
+        index: 1,
+        amount: 16,
+    };
+    let other_index = KeyPath::Amount {
+        unit: 0,
+        index: 2,
+        amount: 8,
+    };
+
+    let key = interpolate(&subset, PATH);
+    assert_ne!(key, interpolate(&subset, other_amount));
+    assert_ne!(key, interpolate(&subset, other_index));
+    assert_ne!(key, interpolate(&subset, KeyPath::Root));
+}
+
+#[test]
+fn groups_are_independent() {
+    let first = deal(2, 3).unwrap();
+    let second = deal(2, 3).unwrap();
+
+    assert_ne!(
+        interpolate(&[&first[0], &first[1]], PATH),
+        interpolate(&[&second[0], &second[1]], PATH)
+    );
+}
+
+#[test]
+fn invalid_parameters() {
+    assert_matches!(
+        deal(1, 3),
+        Err(Error::InvalidParameters {
+            threshold: 1,
+            total: 3
+        })
+    );
+    assert_matches!(deal(4, 3), Err(Error::InvalidParameters { .. }));
+    assert_matches!(deal(2, 11), Err(Error::InvalidParameters { .. }));
+    assert_matches!(lagrange_coefficients(&[1, 1]), Err(Error::InvalidIndexes));
+    assert_matches!(lagrange_coefficients(&[0, 1]), Err(Error::InvalidIndexes));
+}
+
+#[test]
+fn dealt_share_files_are_valid() {
+    for share_file in deal(3, 5).unwrap() {
+        share_file.validate().unwrap();
+    }
+}
+
+#[test]
+fn share_file_missing_a_seed_is_invalid() {
+    let mut share_file = deal(2, 3).unwrap().remove(0);
+    share_file.seeds.pop();
+
+    assert_matches!(share_file.validate(), Err(Error::InvalidShareFile(_)));
+}
+
+#[test]
+fn share_file_with_out_of_range_index_is_invalid() {
+    let mut share_file = deal(2, 3).unwrap().remove(0);
+    share_file.index = 4;
+
+    assert_matches!(share_file.validate(), Err(Error::InvalidShareFile(_)));
+}
@@ -62,6 +62,7 @@ async fn create_valid_proof(amount: Amount) -> Result<Proof> {
         keyset_id: declare_keyset_response.keyset_id,
         secret: secret.to_string(),
         unblind_signature: unblinded_signature.to_bytes().to_vec(),
+        witness: None,
     };
 
     Ok(proof)
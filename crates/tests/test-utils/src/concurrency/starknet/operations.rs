@@ -209,6 +209,8 @@ pub async fn swap_same_output(mut node_client: impl CashuClient, env: EnvVariabl
                 keyset_id: KeysetId::from_bytes(&active_keyset.id.clone())?,
                 secret: secrets[i].clone(),
                 c: unblind_message(&s.c, &rs[i], &node_pubkey_for_amount)?,
+                witness: None,
+                dleq: None,
             })
         })
         .collect::<Result<Vec<nuts::nut00::Proof>>>()?;
@@ -287,6 +289,8 @@ pub async fn swap_same_input(mut node_client: impl CashuClient, env: EnvVariable
         keyset_id: KeysetId::from_bytes(&active_keyset.id.clone())?,
         secret,
         c: unblinded_signature,
+        witness: None,
+        dleq: None,
     };
 
     let mut multi_swap = Vec::new();
@@ -377,6 +381,8 @@ pub async fn melt_same_input(mut node_client: impl CashuClient, env: EnvVariable
         keyset_id: KeysetId::from_bytes(&active_keyset.id.clone())?,
         secret,
         c: unblinded_signature,
+        witness: None,
+        dleq: None,
     };
 
     let mut melt_quote_ids: Vec<String> = Vec::new();
@@ -507,6 +513,8 @@ pub async fn melt_same_quote(mut node_client: impl CashuClient, env: EnvVariable
                 keyset_id: KeysetId::from_bytes(&active_keyset.id.clone())?,
                 secret: secrets[i].clone(),
                 c: unblind_message(&s.c, &rs[i], &node_pubkey_for_amount)?,
+                witness: None,
+                dleq: None,
             })
         })
         .collect::<Result<Vec<nuts::nut00::Proof>>>()?;
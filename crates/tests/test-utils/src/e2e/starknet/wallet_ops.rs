@@ -1,12 +1,28 @@
+use std::{
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
 use anyhow::{Result, anyhow};
 use bip39::Mnemonic;
-use cashu_client::GrpcClient;
+use cashu_client::{CashuClient, GrpcClient};
 use itertools::Itertools;
+use liquidity_source::{AssetReserves, attestation_hash};
+use node_client::GetReservesAttestationRequest;
 use nuts::nut01::PublicKey;
 use primitive_types::U256;
 use r2d2_sqlite::SqliteConnectionManager;
-use starknet_types::{Asset, DepositPayload, STARKNET_STR, constants::ON_CHAIN_CONSTANTS};
+use starknet::{
+    core::{
+        types::{BlockId, BlockTag, FunctionCall},
+        utils::{cairo_short_string_to_felt, get_selector_from_name},
+    },
+    providers::{JsonRpcClient, Provider, jsonrpc::HttpTransport},
+};
+use starknet_types::{Asset, DepositPayload, STARKNET_STR, Unit, constants::ON_CHAIN_CONSTANTS};
 use starknet_types_core::felt::Felt;
+use tonic::Code;
+use url::Url;
 use wallet::{
     self,
     db::{balance::Balance, wad::delete_wad},
@@ -99,6 +115,7 @@ impl WalletOps {
             &mut self.node_client,
             STARKNET_STR.to_string(),
             quote.quote.clone(),
+            |_| {},
         )
         .await?
         {
@@ -109,6 +126,19 @@ impl WalletOps {
             wallet::mint::QuotePaymentIssue::Paid => {}
         }
 
+        let paid_quote = self
+            .node_client
+            .mint_quote_state(STARKNET_STR.to_string(), quote.quote.clone())
+            .await?;
+        if paid_quote.amount_paid != amount {
+            return Err(anyhow!(
+                "quote {} is paid {} instead of {}",
+                quote.quote,
+                paid_quote.amount_paid,
+                amount
+            ));
+        }
+
         let seed_phrase_manager =
             wallet::wallet::sqlite::SeedPhraseManager::new(self.db_pool.clone())?;
         wallet::mint::redeem_quote(
@@ -165,6 +195,8 @@ impl WalletOps {
                         amount: p.amount,
                         secret: p.secret,
                         c: p.c,
+                        witness: p.witness,
+                        dleq: p.dleq.map(Into::into),
                     })
                     .collect(),
             })
@@ -292,3 +324,85 @@ pub async fn recieve_already_spent_wad(wallet_ops: &mut WalletOps, wad: &Compact
     assert_eq!(proof_ids.len(), proofs_state.len());
     Ok(())
 }
+
+/// Wait for a reserves attestation made after now, and check it against the wallet and the chain
+pub async fn check_reserves_attestation(
+    wallet_ops: &mut WalletOps,
+    env: EnvVariables,
+    asset: Asset,
+) -> Result<()> {
+    let since = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut attestation = None;
+    for _ in 0..30 {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        match wallet_ops
+            .node_client
+            .node
+            .get_reserves_attestation(GetReservesAttestationRequest {})
+            .await
+        {
+            Ok(response) if response.get_ref().created_at > since => {
+                attestation = Some(response.into_inner());
+                break;
+            }
+            Ok(_) => {}
+            Err(status) if status.code() == Code::NotFound => {}
+            Err(status) => return Err(status.into()),
+        }
+    }
+    let attestation = attestation.ok_or(anyhow!("no reserves attestation was made"))?;
+
+    let assets: Vec<_> = attestation
+        .assets
+        .iter()
+        .map(|a| AssetReserves {
+            asset: a.asset.clone(),
+            liabilities: U256::from_big_endian(&a.liabilities),
+            reserves: U256::from_big_endian(&a.reserves),
+        })
+        .collect();
+    let hash = attestation_hash(
+        attestation.created_at,
+        &attestation.reserves_address,
+        &assets,
+    );
+    assert_eq!(attestation.hash, hash);
+
+    // The node owes at least what this wallet holds, and has enough to pay it back
+    let attested = assets
+        .iter()
+        .find(|a| a.asset == asset.as_str())
+        .ok_or(anyhow!("asset {} is not attested", asset))?;
+    let mut wallet_holdings = U256::zero();
+    for balance in wallet_ops.balance()? {
+        let unit = Unit::from_str(&balance.unit)?;
+        if unit.asset() == asset {
+            wallet_holdings += unit.convert_amount_into_u256(balance.amount);
+        }
+    }
+    assert!(attested.liabilities >= wallet_holdings);
+    assert!(attested.reserves >= attested.liabilities);
+
+    // The signature is the one of the account holding the reserves
+    let provider = JsonRpcClient::new(HttpTransport::new(Url::parse(&env.rpc_url)?));
+    assert_eq!(attestation.signature.len(), 64);
+    let (r, s) = attestation.signature.split_at(32);
+    let is_valid = provider
+        .call(
+            FunctionCall {
+                contract_address: Felt::from_hex(&attestation.reserves_address)?,
+                entry_point_selector: get_selector_from_name("is_valid_signature")?,
+                calldata: vec![
+                    Felt::from_bytes_be(&hash),
+                    Felt::TWO,
+                    Felt::from_bytes_be_slice(r),
+                    Felt::from_bytes_be_slice(s),
+                ],
+            },
+            BlockId::Tag(BlockTag::Latest),
+        )
+        .await?;
+    assert_eq!(is_valid, vec![cairo_short_string_to_felt("VALID")?]);
+
+    Ok(())
+}
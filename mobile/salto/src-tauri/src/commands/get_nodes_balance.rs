@@ -236,8 +236,8 @@ async fn sync_node_melt_quotes(
                 unit: melt_quote.unit,
                 amount: melt_quote.amount.into(),
             }),
-            MeltQuoteState::Paid => {
-                unreachable!("Paid quote wouldn't have been selected as pending")
+            MeltQuoteState::Paid | MeltQuoteState::Failed => {
+                unreachable!("Paid or failed quote wouldn't have been selected as pending")
             }
         }
     }
@@ -247,7 +247,7 @@ async fn sync_node_melt_quotes(
                 unreachable!("the state cannot have changed and now be unpaid")
             }
             MeltQuoteState::Pending => any_deposited = true,
-            MeltQuoteState::Paid => {}
+            MeltQuoteState::Paid | MeltQuoteState::Failed => {}
         }
     }
 
@@ -50,5 +50,83 @@ pub fn migrations() -> Vec<Migration> {
             sql: wallet::db::wad::CREATE_TABLE_WAD_PROOF,
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 9,
+            description: "add_column_keyset_input_fee_ppk",
+            sql: wallet::db::keyset::ADD_COLUMN_INPUT_FEE_PPK,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "add_column_keyset_final_expiry",
+            sql: wallet::db::keyset::ADD_COLUMN_FINAL_EXPIRY,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "add_column_mint_quote_amount_paid",
+            sql: wallet::db::mint_quote::ADD_COLUMN_AMOUNT_PAID,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "allow_melt_quote_state_failed",
+            sql: wallet::db::melt_quote::ALLOW_STATE_FAILED,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "add_column_melt_quote_inputs",
+            sql: wallet::db::melt_quote::ADD_COLUMN_INPUTS,
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "create_table_proof_dleq",
+            sql: wallet::db::proof::CREATE_TABLE_PROOF_DLEQ,
+            kind: MigrationKind::Up,
+        },
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use super::*;
+
+    /// The columns of each table, by table name
+    fn schema(conn: &Connection) -> Vec<(String, Vec<String>)> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT m.name, p.name FROM sqlite_master m, pragma_table_info(m.name) p \
+                 WHERE m.type = 'table' ORDER BY m.name, p.cid",
+            )
+            .unwrap();
+        let mut schema: Vec<(String, Vec<String>)> = Vec::new();
+        for row in stmt
+            .query_map((), |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+            .unwrap()
+        {
+            let (table, column) = row.unwrap();
+            match schema.last_mut() {
+                Some((t, columns)) if *t == table => columns.push(column),
+                _ => schema.push((table, vec![column])),
+            }
+        }
+
+        schema
+    }
+
+    #[test]
+    fn migrations_build_the_wallet_schema() {
+        let migrated = Connection::open_in_memory().unwrap();
+        for migration in migrations() {
+            migrated.execute_batch(migration.sql).unwrap();
+        }
+        let mut created = Connection::open_in_memory().unwrap();
+        wallet::db::create_tables(&mut created).unwrap();
+
+        assert_eq!(schema(&migrated), schema(&created));
+    }
+}
@@ -300,7 +300,8 @@ pub async fn start_syncing_quotes(
                         node_id,
                         quote_id: pending_melt_quote.id,
                     },
-                    MeltQuoteState::Paid => continue, // Skip paid quotes as they're complete
+                    // Skip paid and failed quotes as they're complete
+                    MeltQuoteState::Paid | MeltQuoteState::Failed => continue,
                 };
                 events.push(QuoteHandlerEvent::Melt(melt_event));
             }
@@ -433,8 +434,8 @@ pub async fn sync_mint_quote_until_is_paid(
         .await;
 
         match res {
-            Ok(Some(MintQuoteState::Unpaid)) => {}
-            Ok(Some(MintQuoteState::Paid)) => {
+            Ok(Some((MintQuoteState::Unpaid, _))) => {}
+            Ok(Some((MintQuoteState::Paid, _))) => {
                 event!(name: "mint-quote-paid", Level::INFO, quote_id = %quote_id, "Mint quote paid");
 
                 state
@@ -448,7 +449,7 @@ pub async fn sync_mint_quote_until_is_paid(
 
                 break;
             }
-            Ok(Some(MintQuoteState::Issued)) => {
+            Ok(Some((MintQuoteState::Issued, _))) => {
                 event!(name: "mint-quote-issued", Level::INFO, quote_id = %quote_id, "Mint quote issued");
                 error!(
                     "mint quote {} has been issued before it was synced as paid",